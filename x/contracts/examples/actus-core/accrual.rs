@@ -0,0 +1,189 @@
+//! Year fractions of the scheduled accrual periods, computed once when a contract is
+//! set up so events on scheduled dates skip the day-count arithmetic.
+//!
+//! Each [`AccrualFactor`] carries a check value over its period, the convention and
+//! maturity it was computed under and the fraction itself. [`AccrualFactor::verified`]
+//! recomputes only that check, so a corrupted or stale entry is ignored and the caller
+//! computes the fraction live, as it does for unscheduled events:
+//!
+//! ```ignore
+//! let factor = cached.and_then(|factor| factor.verified(&terms, state.status_date, now));
+//! TransitionEngine::process_event_with_factor(event, now, &mut state, &terms, factor)?;
+//! ```
+
+use crate::{
+    core::{ContractTerms, Result, Timestamp},
+    lifecycle,
+    scenario::SplitMix64,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The year fraction of one accrual period, in basis points of a year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct AccrualFactor {
+    pub from: Timestamp,
+    pub to: Timestamp,
+    pub year_fraction: u64,
+    check: u64,
+}
+
+impl AccrualFactor {
+    /// The factor from `from` to `to` under the day-count convention of `terms`, unset
+    /// when they have none as nothing accrues
+    pub fn new(terms: &ContractTerms, from: Timestamp, to: Timestamp) -> Option<Self> {
+        let dcc = terms.day_count_convention?;
+        let granularity = terms.granularity();
+        let year_fraction = granularity.year_fraction(dcc as u8, from, to, terms.maturity_date);
+
+        Some(Self {
+            from,
+            to,
+            year_fraction,
+            check: check(terms, from, to, year_fraction)?,
+        })
+    }
+
+    /// The year fraction from `from` to `to`, unless the entry is for another period,
+    /// was computed under another convention or maturity, or fails its check
+    pub fn verified(&self, terms: &ContractTerms, from: Timestamp, to: Timestamp) -> Option<u64> {
+        let valid = self.from == from
+            && self.to == to
+            && check(terms, from, to, self.year_fraction) == Some(self.check);
+
+        valid.then_some(self.year_fraction)
+    }
+}
+
+/// The factors of the periods between consecutive dates of the [`lifecycle::schedule`]
+/// of `terms`, the first `limit` of them
+pub fn scheduled_factors(terms: &ContractTerms, limit: usize) -> Result<Vec<AccrualFactor>> {
+    let mut dates: Vec<Timestamp> = lifecycle::schedule(terms)?
+        .into_iter()
+        .map(|(_, date)| date)
+        .collect();
+    dates.dedup();
+
+    Ok(dates
+        .windows(2)
+        .take(limit)
+        .filter_map(|period| AccrualFactor::new(terms, period[0], period[1]))
+        .collect())
+}
+
+fn check(terms: &ContractTerms, from: Timestamp, to: Timestamp, year_fraction: u64) -> Option<u64> {
+    let inputs = [
+        from,
+        to,
+        terms.day_count_convention? as u64,
+        terms.maturity_date.unwrap_or_default(),
+        year_fraction,
+    ];
+
+    Some(
+        inputs
+            .into_iter()
+            .fold(0, |check, input| SplitMix64::new(check ^ input).next_u64()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            ContractState, ContractType, Cycle, DayCountConvention, EventType, Period, Stub,
+            TransitionEngine,
+        },
+        math,
+    };
+
+    fn quarterly_pam() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2025, 1, 1);
+
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 24),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360ISDA),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 3),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn factors_cover_every_scheduled_period() {
+        let terms = quarterly_pam();
+        let factors = scheduled_factors(&terms, usize::MAX).unwrap();
+
+        // the periods between the IED, seven coupon dates and maturity
+        assert_eq!(factors.len(), 8);
+        for (factor, next) in factors.iter().zip(&factors[1..]) {
+            assert_eq!(factor.to, next.from);
+        }
+        for factor in &factors {
+            let live = math::year_fraction(
+                DayCountConvention::DCC_E30_360ISDA as u8,
+                factor.from,
+                factor.to,
+                terms.maturity_date,
+            );
+            assert_eq!(factor.verified(&terms, factor.from, factor.to), Some(live));
+        }
+
+        assert_eq!(scheduled_factors(&terms, 3).unwrap(), factors[..3]);
+    }
+
+    #[test]
+    fn corrupted_or_stale_entries_are_rejected() {
+        let terms = quarterly_pam();
+        let factor = scheduled_factors(&terms, 1).unwrap()[0];
+        let (from, to) = (factor.from, factor.to);
+
+        let corrupted = AccrualFactor {
+            year_fraction: factor.year_fraction + 1,
+            ..factor
+        };
+        assert_eq!(corrupted.verified(&terms, from, to), None);
+
+        // a lookup of another period, or after a roll-over moved the maturity
+        assert_eq!(factor.verified(&terms, from + 1, to), None);
+        let rolled = ContractTerms {
+            maturity_date: math::add_months(to, 36),
+            ..terms.clone()
+        };
+        assert_eq!(factor.verified(&rolled, from, to), None);
+    }
+
+    #[test]
+    fn cached_factors_accrue_like_live_ones() {
+        let terms = quarterly_pam();
+        let factor = scheduled_factors(&terms, 1).unwrap()[0];
+
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, factor.from, &mut state, &terms).unwrap();
+        let (mut cached, mut live) = (state.clone(), state);
+
+        let payoff = TransitionEngine::process_event_with_factor(
+            EventType::IP,
+            factor.to,
+            &mut cached,
+            &terms,
+            factor.verified(&terms, factor.from, factor.to),
+        );
+
+        assert_eq!(
+            payoff,
+            TransitionEngine::process_event(EventType::IP, factor.to, &mut live, &terms)
+        );
+        assert_eq!(cached, live);
+    }
+}
@@ -0,0 +1,348 @@
+//! The amortization table of a contract: per payment date, the balance before and
+//! after it and how the payment splits into interest and principal.
+//!
+//! Rows come from [`lifecycle::run`], so they are what the transitions pay rather than
+//! a separate evaluation of the formulas. The events of a date make up one row: the
+//! principal is the fall in the notional, the payment what the debtor paid that date
+//! net of fees and penalties, and the interest the rest of it. A tranche disbursed
+//! after the IED shows as a negative payment and principal, and a PAM repays everything
+//! in its last row.
+//!
+//! ```ignore
+//! let rows = amortization::amortization_table(&terms, &())?;
+//! amortization::write_table(&rows, std::io::stdout())?;
+//! ```
+
+use crate::{
+    core::{ContractState, ContractTerms, Error, EventType, SignedUnits, Timestamp, Units},
+    lifecycle::{self, RunError},
+    risk_factors::RiskFactors,
+};
+
+/// One payment date of an amortization table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmortizationRow {
+    pub date: Timestamp,
+    pub opening_balance: Units,
+    pub interest: SignedUnits,
+    pub principal: SignedUnits,
+    /// `interest + principal`
+    pub payment: SignedUnits,
+    pub closing_balance: Units,
+}
+
+fn signed(amount: Units) -> Result<SignedUnits, Error> {
+    SignedUnits::try_from(amount).map_err(|_| Error::MathError("Amortization overflow".into()))
+}
+
+/// The row of the events of one date processed on `before`, unset when they neither
+/// paid nor moved the balance
+fn row(
+    before: &ContractState,
+    events: &[lifecycle::CashflowEvent],
+) -> Result<Option<AmortizationRow>, Error> {
+    let Some(last) = events.last() else {
+        return Ok(None);
+    };
+    let after = &last.state;
+
+    let payoffs: SignedUnits = events
+        .iter()
+        .filter_map(|event| {
+            let payoff = event.payoff?;
+            Some(if event.event_type.paid_by_creditor() {
+                -payoff
+            } else {
+                payoff
+            })
+        })
+        .sum();
+    let charges = (after.total_fees_paid - before.total_fees_paid)
+        + (after.total_penalties_paid - before.total_penalties_paid);
+    let payment = payoffs - signed(charges)?;
+    let principal = signed(before.notional_principal)? - signed(after.notional_principal)?;
+    if payment == 0 && principal == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(AmortizationRow {
+        date: last.time,
+        opening_balance: before.notional_principal,
+        interest: payment - principal,
+        principal,
+        payment,
+        closing_balance: after.notional_principal,
+    }))
+}
+
+/// A row per date after the IED on which the contract pays interest or principal, in
+/// order, with the market data in `risk_factors`
+pub fn amortization_table<R>(
+    terms: &ContractTerms,
+    risk_factors: &R,
+) -> Result<Vec<AmortizationRow>, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    let events = lifecycle::run(terms, risk_factors)?;
+    let mut rows = Vec::new();
+    let mut before: Option<&ContractState> = None;
+
+    for date in events.chunk_by(|a, b| a.time == b.time) {
+        let exchanges = date.iter().any(|event| event.event_type == EventType::IED);
+        if let Some(before) = before.filter(|_| !exchanges) {
+            rows.extend(row(before, date)?);
+        }
+        before = date.last().map(|event| &event.state);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(feature = "csv")]
+pub use table::{write_table, TABLE_HEADER};
+
+#[cfg(feature = "csv")]
+mod table {
+    use super::AmortizationRow;
+    use crate::{
+        csv::{format_amount, format_signed},
+        math,
+    };
+    use std::io::{self, Write};
+
+    pub const TABLE_HEADER: [&str; 6] = [
+        "date",
+        "opening_balance",
+        "interest",
+        "principal",
+        "payment",
+        "closing_balance",
+    ];
+
+    /// Writes `rows` as a table with a header, dated with their ISO 8601 calendar
+    /// dates and amounts in whole units right-aligned under it
+    pub fn write_table<W: Write>(rows: &[AmortizationRow], mut writer: W) -> io::Result<()> {
+        let cells: Vec<[String; 6]> = rows
+            .iter()
+            .map(|row| {
+                let (year, month, day) = math::civil_date(row.date);
+                [
+                    format!("{year:04}-{month:02}-{day:02}"),
+                    format_amount(row.opening_balance),
+                    format_signed(row.interest),
+                    format_signed(row.principal),
+                    format_signed(row.payment),
+                    format_amount(row.closing_balance),
+                ]
+            })
+            .collect();
+
+        let widths: [usize; 6] = std::array::from_fn(|column| {
+            cells
+                .iter()
+                .map(|row| row[column].len())
+                .chain([TABLE_HEADER[column].len()])
+                .max()
+                .unwrap_or_default()
+        });
+        let mut line = |cells: [&str; 6]| {
+            let [date, amounts @ ..] = cells;
+            write!(writer, "{date:<width$}", width = widths[0])?;
+            for (cell, width) in amounts.iter().zip(&widths[1..]) {
+                write!(writer, "  {cell:>width$}")?;
+            }
+            writeln!(writer)
+        };
+
+        line(TABLE_HEADER)?;
+        for row in &cells {
+            line(row.each_ref().map(String::as_str))?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractType, Cycle, DayCountConvention, PayoffDirection, Period, Stub},
+        lifecycle::CashflowEvent,
+        math,
+    };
+
+    fn monthly() -> Option<Cycle> {
+        Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        })
+    }
+
+    fn ied() -> Timestamp {
+        math::timestamp_from_civil(2025, 1, 1)
+    }
+
+    fn loan(contract_type: ContractType) -> ContractTerms {
+        let ied = ied();
+        ContractTerms {
+            contract_type,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 12),
+            notional_principal: Some(1_200_000),
+            nominal_interest_rate: Some(600),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 3),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 3,
+                ..monthly().unwrap()
+            }),
+            cycle_anchor_date_of_principal_redemption: math::add_months(ied, 1),
+            cycle_of_principal_redemption: monthly(),
+            next_principal_redemption_payment: Some(100_000),
+            ..Default::default()
+        }
+    }
+
+    /// Checks each row against the events the engine processes on its date
+    fn cross_check(terms: &ContractTerms) -> Vec<AmortizationRow> {
+        let rows = amortization_table(terms, &()).unwrap();
+        let events = lifecycle::run(terms, &()).unwrap();
+        let ied = terms.initial_exchange_date.unwrap();
+
+        let mut opening = events[0].state.notional_principal;
+        for row in &rows {
+            let date: Vec<&CashflowEvent> = events
+                .iter()
+                .filter(|event| event.time == row.date)
+                .collect();
+            let after = &date.last().unwrap().state;
+            let paid: SignedUnits = date
+                .iter()
+                .filter_map(|event| {
+                    let payoff = event.payoff?;
+                    match PayoffDirection::of(event.event_type, payoff) {
+                        PayoffDirection::DebtorToCreditor => Some(payoff.abs()),
+                        PayoffDirection::CreditorToDebtor => Some(-payoff.abs()),
+                    }
+                })
+                .sum();
+
+            assert!(row.date > ied);
+            assert_eq!(row.opening_balance, opening, "{row:?}");
+            assert_eq!(row.closing_balance, after.notional_principal, "{row:?}");
+            assert_eq!(row.payment, paid, "{row:?}");
+            assert_eq!(row.interest + row.principal, row.payment, "{row:?}");
+            assert_eq!(
+                row.opening_balance as SignedUnits - row.principal,
+                row.closing_balance as SignedUnits
+            );
+            opening = row.closing_balance;
+        }
+
+        // the dates without a row neither pay nor repay anything
+        let dated: Vec<_> = rows.iter().map(|row| row.date).collect();
+        for event in events.iter().filter(|event| !dated.contains(&event.time)) {
+            assert!(
+                event.time == ied || event.payoff.unwrap_or(0) == 0,
+                "{event:?}"
+            );
+        }
+        assert_eq!(opening, 0);
+        rows
+    }
+
+    #[test]
+    fn lam_rows_match_the_run() {
+        let terms = loan(ContractType::LAM);
+        let rows = cross_check(&terms);
+        assert_eq!(rows.len(), 12);
+
+        // a month of principal and, every third month, the interest since the last IP
+        // over year fractions in whole basis points
+        assert_eq!(
+            rows[..3]
+                .iter()
+                .map(|row| (row.interest, row.principal))
+                .collect::<Vec<_>>(),
+            [(0, 100_000), (0, 100_000), (16_492, 100_000)]
+        );
+
+        // the interest portions are what the transitions count as interest paid
+        let totals = lifecycle::run(&terms, &()).unwrap();
+        let paid = totals.last().unwrap().state.total_interest_paid;
+        assert_eq!(
+            rows.iter().map(|row| row.interest).sum::<SignedUnits>(),
+            paid
+        );
+    }
+
+    #[test]
+    fn annuity_rows_pay_equal_installments() {
+        let terms = ContractTerms {
+            cycle_anchor_date_of_interest_payment: math::add_months(ied(), 1),
+            cycle_of_interest_payment: monthly(),
+            cycle_anchor_date_of_principal_redemption: None,
+            cycle_of_principal_redemption: None,
+            next_principal_redemption_payment: None,
+            ..loan(ContractType::ANN)
+        };
+        let rows = cross_check(&terms);
+        assert_eq!(rows.len(), 12);
+
+        let (last, regular) = rows.split_last().unwrap();
+        assert!(regular.iter().all(|row| row.payment == rows[0].payment));
+        assert!(regular
+            .windows(2)
+            .all(|pair| pair[0].interest > pair[1].interest));
+        assert!(last.payment.abs_diff(rows[0].payment) < 12);
+    }
+
+    #[test]
+    fn nam_rows_match_the_run() {
+        let terms = loan(ContractType::NAM);
+        let rows = cross_check(&terms);
+        assert_eq!(rows.last().unwrap().date, terms.maturity_date.unwrap());
+    }
+
+    #[test]
+    fn tranches_show_as_negative_principal() {
+        let tranche = math::add_months(ied(), 1).unwrap() - 10 * math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            disbursements: Some(vec![(ied(), 600_000), (tranche, 600_000)]),
+            ..loan(ContractType::LAM)
+        };
+        let rows = cross_check(&terms);
+
+        assert_eq!(rows[0].date, tranche);
+        assert_eq!(
+            (rows[0].opening_balance, rows[0].closing_balance),
+            (600_000, 1_200_000)
+        );
+        assert_eq!((rows[0].principal, rows[0].payment), (-600_000, -600_000));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn table_prints_aligned_columns() {
+        let terms = ContractTerms {
+            maturity_date: math::add_months(ied(), 2),
+            ..loan(ContractType::LAM)
+        };
+        let mut table = Vec::new();
+        write_table(&amortization_table(&terms, &()).unwrap(), &mut table).unwrap();
+
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "\
+date        opening_balance     interest    principal      payment  closing_balance
+2025-02-01      0.001200000  0.000000000  0.000100000  0.000100000      0.001100000
+2025-03-01      0.001100000  0.000011494  0.001100000  0.001111494      0.000000000
+"
+        );
+    }
+}
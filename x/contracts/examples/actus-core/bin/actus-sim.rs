@@ -0,0 +1,145 @@
+// x/contracts/examples/actus-core/bin/actus-sim.rs
+
+//! Runs a contract's lifecycle off-chain from a terms file and prints its events:
+//!
+//! ```sh
+//! actus-sim --terms terms.json --until 2030-01-01 [--risk-factors rf.json] [--csv out.csv]
+//! ```
+//!
+//! Terms are the serde form of `ContractTerms`. Risk factors map each market object
+//! code to its `[timestamp, value]` observations in time order.
+
+use actus_core::{
+    core::Timestamp,
+    csv::{self, format_amount, format_signed},
+    lifecycle::{self, CashflowEvent},
+    math,
+    risk_factors::RiskFactorSeries,
+};
+use std::{env, fs, fs::File, io::BufWriter, process::ExitCode};
+
+const USAGE: &str =
+    "usage: actus-sim --terms <terms.json> --until <YYYY-MM-DD> [--risk-factors <rf.json>] [--csv <out.csv>]";
+
+struct Args {
+    terms: String,
+    until: Timestamp,
+    risk_factors: Option<String>,
+    csv: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match simulate(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let (mut terms, mut until, mut risk_factors, mut csv) = (None, None, None, None);
+
+    while let Some(flag) = args.next() {
+        let slot = match flag.as_str() {
+            "--terms" => &mut terms,
+            "--until" => &mut until,
+            "--risk-factors" => &mut risk_factors,
+            "--csv" => &mut csv,
+            _ => return Err(format!("unknown argument `{flag}`")),
+        };
+        *slot = Some(
+            args.next()
+                .ok_or_else(|| format!("`{flag}` needs a value"))?,
+        );
+    }
+
+    let until = until.ok_or("`--until` is required")?;
+
+    Ok(Args {
+        terms: terms.ok_or("`--terms` is required")?,
+        until: parse_date(&until).ok_or_else(|| format!("`{until}` is not a YYYY-MM-DD date"))?,
+        risk_factors,
+        csv,
+    })
+}
+
+/// The last second of the day, so events dated on `until` are included
+fn parse_date(date: &str) -> Option<Timestamp> {
+    let mut parts = date.splitn(3, '-').map(str::parse::<u64>);
+    let (year, month, day) = (
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+
+    if year < 1970 || !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    if day > math::days_in_civil_month(year, month) {
+        return None;
+    }
+
+    Some(math::timestamp_from_civil(year, month, day) + math::SECONDS_PER_DAY - 1)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let bytes = fs::read(path).map_err(|err| format!("cannot read {path}: {err}"))?;
+    serde_json::from_slice(&bytes).map_err(|err| format!("invalid {path}: {err}"))
+}
+
+fn simulate(args: &Args) -> Result<(), String> {
+    let terms = read_json(&args.terms)?;
+    let risk_factors: RiskFactorSeries = match &args.risk_factors {
+        Some(path) => read_json(path)?,
+        None => RiskFactorSeries::new(),
+    };
+
+    let events =
+        lifecycle::run_until(&terms, &risk_factors, args.until).map_err(|err| match err.event {
+            Some((event_type, time)) => format!("{event_type:?} on {}: {}", date(time), err.error),
+            None => err.error.to_string(),
+        })?;
+
+    print_table(&events);
+
+    if let Some(path) = &args.csv {
+        let file = File::create(path).map_err(|err| format!("cannot create {path}: {err}"))?;
+        csv::export_csv(&events, BufWriter::new(file))
+            .map_err(|err| format!("cannot write {path}: {err}"))?;
+    }
+
+    Ok(())
+}
+
+fn date(time: Timestamp) -> String {
+    let (year, month, day) = math::civil_date(time);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn print_table(events: &[CashflowEvent]) {
+    println!(
+        "{:<5} {:<10} {:>24} {:>24} {:>24}",
+        "event", "date", "payoff", "notional", "accrued"
+    );
+
+    for event in events {
+        println!(
+            "{:<5} {:<10} {:>24} {:>24} {:>24}",
+            format!("{:?}", event.event_type),
+            date(event.time),
+            event.payoff.map(format_signed).unwrap_or_default(),
+            format_amount(event.state.notional_principal),
+            format_signed(event.state.accrued_interest),
+        );
+    }
+}
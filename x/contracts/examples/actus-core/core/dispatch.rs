@@ -0,0 +1,1559 @@
+// src/core/dispatch.rs
+
+//! The events each contract type handles, as a table of payoff and state transition
+//! functions (the ACTUS POF and STF).
+//!
+//! [`TransitionEngine`](super::TransitionEngine) accrues interest up to the event and
+//! advances the status date around the table lookup, so the functions here only read
+//! the payoff off the accrued state and then apply the event. Events missing from a
+//! table pay nothing and leave the state unchanged. Adding a contract type is a matter
+//! of writing its table and listing it in [`ContractLogic::of`].
+
+// the shared handlers go partly unused when no lending contract type is compiled in
+#![cfg_attr(not(any(feature = "pam", feature = "lam", feature = "ann")), allow(dead_code))]
+
+use super::types::*;
+use super::Error;
+use super::Result;
+use super::TransitionEngine;
+#[cfg(any(feature = "pam", feature = "lam", feature = "ann"))]
+use crate::math;
+
+/// Bounds the payment schedule an annuity installment is computed over; a thousand
+/// years of monthly payments
+#[cfg(feature = "ann")]
+const MAX_ANNUITY_PERIODS: usize = 12_000;
+
+type Payoff = fn(&ContractState, &ContractTerms, Timestamp) -> Result<Option<SignedUnits>>;
+type Transition = fn(&mut ContractState, &ContractTerms, Timestamp) -> Result<()>;
+
+/// What an event pays and how it moves the state. The transition only runs once the
+/// payoff succeeded, so it skips the checks the payoff made.
+#[derive(Clone, Copy)]
+pub(crate) struct Handler {
+    payoff: Payoff,
+    transition: Transition,
+}
+
+/// The handlers of the events a contract type defines
+pub(crate) struct ContractLogic {
+    handlers: &'static [(EventType, Handler)],
+}
+
+impl ContractLogic {
+    /// The table of `contract_type`, unset when it is not implemented or not compiled in
+    pub fn of(contract_type: ContractType) -> Option<&'static Self> {
+        match contract_type {
+            ContractType::STK => Some(&STK),
+            ContractType::COM => Some(&COM),
+            ContractType::CSH => Some(&CSH),
+            #[cfg(feature = "pam")]
+            ContractType::PAM => Some(&PAM),
+            #[cfg(feature = "pam")]
+            ContractType::UMP => Some(&UMP),
+            #[cfg(feature = "pam")]
+            ContractType::CLM => Some(&CLM),
+            #[cfg(feature = "lam")]
+            ContractType::LAM => Some(&LAM),
+            #[cfg(feature = "lam")]
+            ContractType::NAM => Some(&NAM),
+            #[cfg(feature = "ann")]
+            ContractType::ANN => Some(&ANN),
+            #[cfg(feature = "derivatives")]
+            ContractType::OPTNS => Some(&OPTNS),
+            #[cfg(feature = "derivatives")]
+            ContractType::SWPPV => Some(&SWPPV),
+            #[cfg(feature = "derivatives")]
+            ContractType::CAPFL => Some(&CAPFL),
+            #[cfg(feature = "credit-enhancements")]
+            ContractType::CEG => Some(&CEG),
+            _ => None,
+        }
+    }
+
+    /// The payoff of `event` on the state accrued up to `timestamp`
+    pub fn payoff(
+        &self,
+        event: EventType,
+        state: &ContractState,
+        terms: &ContractTerms,
+        timestamp: Timestamp,
+    ) -> Result<Option<SignedUnits>> {
+        self.handler(event)
+            .map_or(Ok(None), |handler| (handler.payoff)(state, terms, timestamp))
+    }
+
+    /// Applies `event` to the state accrued up to `timestamp`
+    pub fn state_transition(
+        &self,
+        event: EventType,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: Timestamp,
+    ) -> Result<()> {
+        self.handler(event)
+            .map_or(Ok(()), |handler| (handler.transition)(state, terms, timestamp))
+    }
+
+    fn handler(&self, event: EventType) -> Option<&Handler> {
+        self.handlers
+            .iter()
+            .find(|(handled, _)| *handled == event)
+            .map(|(_, handler)| handler)
+    }
+}
+
+// =======================
+//        Tables
+// =======================
+
+/// A position of `quantity` shares, one if unset, bought at the PRD and sold at the TD,
+/// which earns the dividend on each share at every DV
+static STK: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::PRD, PURCHASE),
+        (EventType::DV, DIVIDEND),
+        (EventType::TD, TERMINATION),
+    ],
+};
+
+/// A position of `quantity` units of a commodity, one if unset, bought at the PRD and
+/// sold at the TD
+static COM: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::PRD, PURCHASE),
+        (EventType::TD, TERMINATION),
+    ],
+};
+
+/// A cash balance of the notional, which pays nothing and only takes AD events
+static CSH: ContractLogic = ContractLogic { handlers: &[] };
+
+#[cfg(feature = "pam")]
+static PAM: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, INITIAL_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, INTEREST_PAYMENT),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+        (EventType::PP, PREPAYMENT),
+        (EventType::PI, DISBURSEMENT),
+    ],
+};
+
+/// A deposit without a fixed maturity. Its balance changes with each deposit and
+/// withdrawal, which only [`TransitionEngine::deposit`] and
+/// [`TransitionEngine::withdraw`] process.
+#[cfg(feature = "pam")]
+static UMP: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, INITIAL_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, INTEREST_PAYMENT),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+/// Call money, without a maturity until [`TransitionEngine::call`] sets one the notice
+/// after the call
+#[cfg(feature = "pam")]
+static CLM: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, INITIAL_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, INTEREST_PAYMENT),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+#[cfg(feature = "lam")]
+static LAM: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, AMORTIZING_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, INTEREST_PAYMENT),
+        (EventType::PR, FIXED_REDEMPTION),
+        (EventType::PI, DISBURSEMENT),
+        (EventType::IPCB, INTEREST_BASE_FIXING),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+/// Interest is capitalized rather than paid, or paid out of a fixed installment that
+/// may not cover it
+#[cfg(feature = "lam")]
+static NAM: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, AMORTIZING_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::PR, NEGATIVE_AMORTIZATION),
+        (EventType::IPCI, CAPITALIZATION),
+        (EventType::PI, DISBURSEMENT),
+        (EventType::IPCB, INTEREST_BASE_FIXING),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+/// Each interest payment is a constant installment of interest and principal
+#[cfg(feature = "ann")]
+static ANN: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, ANNUITY_EXCHANGE),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, INSTALLMENT),
+        (EventType::PRF, INSTALLMENT_FIXING),
+        (EventType::MD, MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+/// An option on the notional in units of an underlying, bought at the IED for the
+/// premium. An exercise fixes what it pays, which the STD settles, or else the MD.
+#[cfg(feature = "derivatives")]
+static OPTNS: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, OPTION_PURCHASE),
+        (EventType::XD, EXERCISE),
+        (EventType::STD, SETTLEMENT),
+        (EventType::MD, EXPIRY),
+    ],
+};
+
+/// A plain vanilla swap: the debtor pays a fixed rate and the creditor a floating one,
+/// both on a notional that is never exchanged. Each IP settles the legs net.
+#[cfg(feature = "derivatives")]
+static SWPPV: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, SWAP_START),
+        (EventType::FP, FEE_PAYMENT),
+        (EventType::IP, NET_INTEREST),
+        (EventType::MD, SWAP_MATURITY),
+        (EventType::PY, PENALTY),
+    ],
+};
+
+/// A cap or floor on a floating rate, bought at the IED for the premium. The notional is
+/// never exchanged; each IP pays what the rate passed the strikes by over the period.
+#[cfg(feature = "derivatives")]
+static CAPFL: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, OPTION_PURCHASE),
+        (EventType::IP, CAPLET),
+        (EventType::MD, CAP_EXPIRY),
+    ],
+};
+
+/// A guarantee of the contract it covers, bought at the IED for the premium. Once that
+/// contract defaults the holder exercises it, fixing what the guarantor pays at the STD.
+#[cfg(feature = "credit-enhancements")]
+static CEG: ContractLogic = ContractLogic {
+    handlers: &[
+        (EventType::IED, OPTION_PURCHASE),
+        (EventType::XD, EXERCISE),
+        (EventType::STD, SETTLEMENT),
+        (EventType::MD, EXPIRY),
+    ],
+};
+
+// =======================
+//       Handlers
+// =======================
+
+const PURCHASE: Handler = Handler {
+    payoff: purchase_price,
+    transition: buy_shares,
+};
+
+const DIVIDEND: Handler = Handler {
+    payoff: dividend,
+    transition: keep_shares,
+};
+
+const TERMINATION: Handler = Handler {
+    payoff: termination_price,
+    transition: sell_shares,
+};
+
+#[cfg(any(feature = "pam", feature = "lam"))]
+const INITIAL_EXCHANGE: Handler = Handler {
+    payoff: net_initial_exchange,
+    transition: exchange_and_prepay,
+};
+
+#[cfg(any(feature = "pam", feature = "lam"))]
+const INTEREST_PAYMENT: Handler = Handler {
+    payoff: accrued_interest,
+    transition: clear_accrued_interest,
+};
+
+#[cfg(any(feature = "pam", feature = "lam"))]
+const DISBURSEMENT: Handler = Handler {
+    payoff: disbursement,
+    transition: disburse,
+};
+
+const FEE_PAYMENT: Handler = Handler {
+    payoff: fee,
+    transition: clear_fee_accrued,
+};
+
+const MATURITY: Handler = Handler {
+    payoff: maturity_payment,
+    transition: redeem,
+};
+
+const PENALTY: Handler = Handler {
+    payoff: penalty,
+    transition: assess_penalty,
+};
+
+#[cfg(feature = "pam")]
+const PREPAYMENT: Handler = Handler {
+    payoff: prepayment,
+    transition: prepay,
+};
+
+#[cfg(feature = "lam")]
+const AMORTIZING_EXCHANGE: Handler = Handler {
+    payoff: net_initial_exchange,
+    transition: exchange_and_fix_base,
+};
+
+#[cfg(feature = "lam")]
+const INTEREST_BASE_FIXING: Handler = Handler {
+    payoff: no_payoff,
+    transition: fix_interest_base,
+};
+
+#[cfg(feature = "lam")]
+const FIXED_REDEMPTION: Handler = Handler {
+    payoff: fixed_redemption,
+    transition: redeem_fixed,
+};
+
+#[cfg(feature = "lam")]
+const NEGATIVE_AMORTIZATION: Handler = Handler {
+    payoff: fixed_installment,
+    transition: pay_fixed_installment,
+};
+
+#[cfg(feature = "lam")]
+const CAPITALIZATION: Handler = Handler {
+    payoff: accrued_interest_capitalized,
+    transition: capitalize,
+};
+
+#[cfg(feature = "ann")]
+const ANNUITY_EXCHANGE: Handler = Handler {
+    payoff: initial_exchange,
+    transition: exchange_annuity,
+};
+
+#[cfg(feature = "ann")]
+const INSTALLMENT: Handler = Handler {
+    payoff: installment,
+    transition: pay_installment,
+};
+
+#[cfg(feature = "ann")]
+const INSTALLMENT_FIXING: Handler = Handler {
+    payoff: no_payoff,
+    transition: refix_installment,
+};
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+const OPTION_PURCHASE: Handler = Handler {
+    payoff: premium,
+    transition: exchange_notional,
+};
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+const EXERCISE: Handler = Handler {
+    payoff: no_payoff,
+    transition: fix_exercise,
+};
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+const SETTLEMENT: Handler = Handler {
+    payoff: exercise_amount,
+    transition: settle_exercise,
+};
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+const EXPIRY: Handler = Handler {
+    payoff: expiry_payment,
+    transition: expire,
+};
+
+#[cfg(feature = "derivatives")]
+const SWAP_START: Handler = Handler {
+    payoff: no_exchange,
+    transition: start_legs,
+};
+
+#[cfg(feature = "derivatives")]
+const NET_INTEREST: Handler = Handler {
+    payoff: net_interest,
+    transition: settle_legs,
+};
+
+#[cfg(feature = "derivatives")]
+const SWAP_MATURITY: Handler = Handler {
+    payoff: net_maturity_payment,
+    transition: redeem_legs,
+};
+
+#[cfg(feature = "derivatives")]
+const CAPLET: Handler = Handler {
+    payoff: caplet,
+    transition: clear_caplet,
+};
+
+#[cfg(feature = "derivatives")]
+const CAP_EXPIRY: Handler = Handler {
+    payoff: net_maturity_payment,
+    transition: redeem,
+};
+
+/// The notional, or its first tranche when disbursed in stages, on the IED only
+fn initial_exchange(
+    _state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let ied = terms
+        .initial_exchange_date
+        .ok_or(Error::MissingTerm { field: "initial_exchange_date" })?;
+    if timestamp != ied {
+        return Ok(None);
+    }
+
+    let notional = terms
+        .initial_disbursement()
+        .ok_or(Error::MissingTerm { field: "notional_principal" })?;
+    signed(notional).map(Some)
+}
+
+/// Sets up the initial principal and interest
+fn exchange_notional(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if terms.initial_exchange_date == Some(timestamp) {
+        state.notional_principal = terms.initial_disbursement().unwrap_or(0);
+        state.nominal_interest_rate = terms.nominal_interest_rate.unwrap_or(0);
+        state.accrued_interest = 0;
+    }
+
+    Ok(())
+}
+
+/// The notional on the IED, net of the first period's interest if paid in advance
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn net_initial_exchange(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let Some(notional) = initial_exchange(state, terms, timestamp)? else {
+        return Ok(None);
+    };
+
+    let (principal, rate) = (notional.unsigned_abs(), terms.nominal_interest_rate.unwrap_or(0));
+    notional
+        .checked_sub(interest_in_advance(terms, principal, rate, timestamp)?)
+        .ok_or(Error::MathError("Initial exchange overflow".into()))
+        .map(Some)
+}
+
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn exchange_and_prepay(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    exchange_notional(state, terms, timestamp)?;
+    if terms.initial_exchange_date == Some(timestamp) {
+        prepay_interest(state, terms, timestamp)?;
+    }
+
+    Ok(())
+}
+
+/// The tranche the terms disburse at `timestamp` after the IED, if any
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn disbursement(
+    _state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    terms.disbursement_at(timestamp).map(signed).transpose()
+}
+
+/// Adds the tranche to the notional, which accrues interest from then on
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn disburse(state: &mut ContractState, terms: &ContractTerms, timestamp: Timestamp) -> Result<()> {
+    if let Some(amount) = terms.disbursement_at(timestamp) {
+        state.notional_principal = state
+            .notional_principal
+            .checked_add(amount)
+            .ok_or(Error::MathError("Disbursement overflow".into()))?;
+    }
+
+    Ok(())
+}
+
+/// The interest accrued since the last payment, if any, plus that of the period ahead
+/// when paid in advance. Scaled when the scaling effect says so; the creditor pays it
+/// when the rate is negative.
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn accrued_interest(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let (notional, rate) = (state.notional_principal, state.nominal_interest_rate);
+    let due = state
+        .accrued_interest
+        .checked_add(interest_in_advance(terms, notional, rate, timestamp)?)
+        .ok_or(Error::MathError("Interest payment overflow".into()))?;
+
+    let scales = terms.scaling_effect.is_some_and(ScalingEffect::scales_interest);
+    let interest = scale(due, state.interest_scaling_multiplier, scales)?;
+    Ok((interest != 0).then_some(interest))
+}
+
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn clear_accrued_interest(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    prepay_interest(state, terms, timestamp)
+}
+
+/// Carries the interest of the period ahead, if paid in advance, as owed to the debtor
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn prepay_interest(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    let (notional, rate) = (state.notional_principal, state.nominal_interest_rate);
+    state.accrued_interest = -interest_in_advance(terms, notional, rate, timestamp)?;
+    Ok(())
+}
+
+/// The interest `notional` accrues at `rate` from `timestamp` to the next IP, or to
+/// maturity after the last, when the terms pay it in advance; nothing otherwise
+#[cfg(any(feature = "pam", feature = "lam"))]
+fn interest_in_advance(
+    terms: &ContractTerms,
+    notional: Units,
+    rate: Rate,
+    timestamp: Timestamp,
+) -> Result<SignedUnits> {
+    let (Some(InterestTiming::Advance), Some(dcc)) =
+        (terms.interest_timing, terms.day_count_convention)
+    else {
+        return Ok(0);
+    };
+
+    let (maturity, granularity) = (terms.maturity_date, terms.granularity());
+    let next_payment = match (
+        terms.cycle_anchor_date_of_interest_payment,
+        terms.cycle_of_interest_payment.as_ref(),
+    ) {
+        (Some(anchor), Some(cycle)) => cycle.next_after(anchor, timestamp, granularity),
+        (Some(anchor), None) => Some(anchor).filter(|date| *date > timestamp),
+        _ => None,
+    };
+    let end = next_payment
+        .filter(|date| maturity.is_none_or(|md| *date < md))
+        .or(maturity)
+        .unwrap_or(timestamp);
+    let time_fraction = granularity.year_fraction(dcc as u8, timestamp, end, maturity);
+
+    // as it accrues: principal * rate * time_fraction, both in basis points
+    signed(notional)?
+        .checked_mul(SignedUnits::from(rate))
+        .and_then(|v| v.checked_mul(SignedUnits::from(time_fraction)))
+        .map(|v| v / SignedUnits::from(math::BASIS_POINT_FACTOR).pow(2))
+        .ok_or(Error::MathError("Interest in advance overflow".into()))
+}
+
+/// The fee rate as an amount under `FEB_A`, the fee accrued since the last payment
+/// under `FEB_N`
+fn fee(
+    state: &ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    match terms.fee_basis {
+        Some(FeeBasis::FEB_A) => {
+            let amount = terms.fee_rate.ok_or(Error::MissingTerm { field: "fee_rate" })?;
+            signed(Units::from(amount)).map(Some)
+        }
+        Some(FeeBasis::FEB_N) => signed(state.fee_accrued).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn clear_fee_accrued(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.fee_accrued = 0;
+    Ok(())
+}
+
+/// The outstanding principal, interest and fees, on the maturity date only. Principal
+/// and interest are scaled when the scaling effect says so.
+fn maturity_payment(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let md = terms
+        .maturity_date
+        .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+    if timestamp != md {
+        return Ok(None);
+    }
+
+    let fee = signed(state.fee_accrued)?;
+    scaled_notional(state, terms)?
+        .checked_add(scaled_interest(state, terms)?)
+        .and_then(|v| v.checked_add(fee))
+        .ok_or(Error::MathError("Maturity payment overflow".into()))
+        .map(Some)
+}
+
+/// Zeroes out principal, interest, the redemption amount and the interest calculation
+/// base at maturity, whatever the schedule left of them
+fn redeem(state: &mut ContractState, terms: &ContractTerms, timestamp: Timestamp) -> Result<()> {
+    if terms.maturity_date == Some(timestamp) {
+        state.notional_principal = 0;
+        state.accrued_interest = 0;
+        state.fee_accrued = 0;
+        state.nominal_interest_rate = 0;
+        state.next_principal_redemption_payment = 0;
+        state.interest_calculation_base = 0;
+    }
+
+    Ok(())
+}
+
+/// The penalty of the overdue payments once their grace period lapsed; settling
+/// them later no longer charges it
+fn penalty(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    if matches!(terms.penalty_type, None | Some(PenaltyType::PYTP_O)) {
+        return Err(Error::ValidationError("PY events need a penalty type".into()));
+    }
+
+    match TransitionEngine::penalty_due(EventType::PY, timestamp, state, terms)? {
+        0 => Err(Error::TransitionError("No penalty is due".into())),
+        penalty => signed(penalty).map(Some),
+    }
+}
+
+fn assess_penalty(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.penalty_assessed = true;
+    Ok(())
+}
+
+/// The next scheduled redemption under `PPEF_A`, the whole principal under `PPEF_M`
+#[cfg(feature = "pam")]
+fn prepayment(
+    state: &ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let amount = match terms.prepayment_effect {
+        Some(PrepaymentEffect::PPEF_A) => (state.next_principal_redemption_payment > 0)
+            .then_some(state.next_principal_redemption_payment),
+        Some(PrepaymentEffect::PPEF_M) => Some(state.notional_principal),
+        Some(PrepaymentEffect::PPEF_N) | None => None,
+    };
+    amount.map(signed).transpose()
+}
+
+#[cfg(feature = "pam")]
+fn prepay(state: &mut ContractState, terms: &ContractTerms, _timestamp: Timestamp) -> Result<()> {
+    match terms.prepayment_effect {
+        Some(PrepaymentEffect::PPEF_A) => state.next_principal_redemption_payment = 0,
+        Some(PrepaymentEffect::PPEF_M) => state.notional_principal = 0,
+        Some(PrepaymentEffect::PPEF_N) | None => {}
+    }
+
+    Ok(())
+}
+
+/// Also sets the redemption amount and the interest calculation base at the IED
+#[cfg(feature = "lam")]
+fn exchange_and_fix_base(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    exchange_and_prepay(state, terms, timestamp)?;
+    if terms.initial_exchange_date == Some(timestamp) {
+        state.next_principal_redemption_payment =
+            terms.next_principal_redemption_payment.unwrap_or(0);
+        state.interest_calculation_base = match terms.interest_calculation_base {
+            Some(IPCB::IPCB_NTL) => terms
+                .interest_calculation_base_amount
+                .unwrap_or(state.notional_principal),
+            _ => state.notional_principal,
+        };
+    }
+
+    Ok(())
+}
+
+/// Nothing changes hands
+#[cfg(any(
+    feature = "lam",
+    feature = "ann",
+    feature = "derivatives",
+    feature = "credit-enhancements"
+))]
+fn no_payoff(
+    _state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    Ok(None)
+}
+
+/// Fixes the interest calculation base at the notional under `IPCB_NTL`; the other
+/// bases ignore the fixing
+#[cfg(feature = "lam")]
+fn fix_interest_base(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    if terms.interest_calculation_base == Some(IPCB::IPCB_NTL) {
+        state.interest_calculation_base = state.notional_principal;
+    }
+
+    Ok(())
+}
+
+/// The redemption amount fixed at the IED, capped at the outstanding principal
+#[cfg(feature = "lam")]
+fn fixed_redemption(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let amount = state.next_principal_redemption_payment;
+    signed(amount.min(state.notional_principal)).map(Some)
+}
+
+/// Under `IPCB_NT`, the default, the interest calculation base follows the notional down
+#[cfg(feature = "lam")]
+fn redeem_fixed(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    let redemption = state.next_principal_redemption_payment;
+    state.notional_principal = state.notional_principal.saturating_sub(redemption);
+    if terms.interest_calculation_base.is_none_or(|base| base == IPCB::IPCB_NT) {
+        state.interest_calculation_base = state.notional_principal;
+    }
+    Ok(())
+}
+
+/// The installment fixed at the IED, capped at what is outstanding. It pays the interest
+/// accrued before any principal. Without a redemption cycle there is no installment and
+/// the interest is only capitalized.
+#[cfg(feature = "lam")]
+fn fixed_installment(
+    state: &ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    if terms.cycle_of_principal_redemption.is_none() {
+        return Ok(None);
+    }
+
+    let outstanding = signed(state.notional_principal)?
+        .checked_add(state.accrued_interest)
+        .ok_or(Error::MathError("Installment overflow".into()))?;
+    let installment = signed(state.next_principal_redemption_payment)?;
+    Ok(Some(installment.min(outstanding)))
+}
+
+/// Repays the notional by what the installment leaves of the interest, or adds the
+/// interest it does not cover to the notional
+#[cfg(feature = "lam")]
+fn pay_fixed_installment(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    let overflow = || Error::MathError("Negative amortization overflow".into());
+    let Some(installment) = fixed_installment(state, terms, timestamp)? else {
+        return Ok(());
+    };
+    let unpaid_interest = state.accrued_interest.checked_sub(installment).ok_or_else(overflow)?;
+    state.notional_principal = state
+        .notional_principal
+        .checked_add_signed(unpaid_interest)
+        .ok_or_else(overflow)?;
+    state.accrued_interest = 0;
+    Ok(())
+}
+
+/// The capitalized interest, reported as a flow
+#[cfg(feature = "lam")]
+fn accrued_interest_capitalized(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    Ok(Some(state.accrued_interest))
+}
+
+#[cfg(feature = "lam")]
+fn capitalize(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.notional_principal = state
+        .notional_principal
+        .checked_add_signed(state.accrued_interest)
+        .ok_or(Error::MathError("Interest capitalization overflow".into()))?;
+    state.accrued_interest = 0;
+    Ok(())
+}
+
+/// Also fixes the installment at the IED unless the terms set it
+#[cfg(feature = "ann")]
+fn exchange_annuity(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    exchange_notional(state, terms, timestamp)?;
+
+    if terms.initial_exchange_date == Some(timestamp)
+        && terms.next_principal_redemption_payment.is_none()
+    {
+        let rate = state.nominal_interest_rate;
+        state.next_principal_redemption_payment =
+            compute_annuity_payment(terms, state.notional_principal, rate, timestamp)?;
+    }
+
+    Ok(())
+}
+
+/// Refixes the installment so it repays the notional left over the remaining periods
+/// at the current rate, or at the reset rate pending under `CP_B`. Nothing is refixed
+/// before the IED.
+#[cfg(feature = "ann")]
+fn refix_installment(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if state.notional_principal == 0 {
+        return Ok(());
+    }
+
+    let rate = state.reset_rate.unwrap_or(state.nominal_interest_rate);
+    state.next_principal_redemption_payment =
+        compute_annuity_payment(terms, state.notional_principal, rate, timestamp)?;
+    Ok(())
+}
+
+/// The installment repays what it does not spend on interest. Interest owed to the
+/// debtor after a reset below zero is paid on top rather than repaying more principal.
+#[cfg(feature = "ann")]
+fn principal_portion(state: &ContractState) -> Units {
+    state
+        .next_principal_redemption_payment
+        .saturating_sub(Units::try_from(state.accrued_interest).unwrap_or(0))
+        .min(state.notional_principal)
+}
+
+#[cfg(feature = "ann")]
+fn installment(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    signed(principal_portion(state))?
+        .checked_add(state.accrued_interest)
+        .ok_or(Error::MathError("Installment overflow".into()))
+        .map(Some)
+}
+
+#[cfg(feature = "ann")]
+fn pay_installment(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.notional_principal -= principal_portion(state);
+    state.accrued_interest = 0;
+    Ok(())
+}
+
+/// Computes the constant installment repaying `notional` over the interest payment
+/// periods between `from` and maturity, with interest accrued at `rate`
+#[cfg(feature = "ann")]
+fn compute_annuity_payment(
+    terms: &ContractTerms,
+    notional: Units,
+    rate: Rate,
+    from: Timestamp,
+) -> Result<Units> {
+    let maturity = terms
+        .maturity_date
+        .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+    let granularity = terms.granularity();
+
+    let mut dates = match (
+        terms.cycle_anchor_date_of_interest_payment,
+        terms.cycle_of_interest_payment.as_ref(),
+    ) {
+        (Some(anchor), Some(cycle)) => {
+            std::iter::successors(cycle.next_after(anchor, from, granularity), |date| {
+                cycle.next_after(anchor, *date, granularity)
+            })
+            .take_while(|date| *date < maturity)
+            .take(MAX_ANNUITY_PERIODS)
+            .collect()
+        },
+        (Some(anchor), None) => {
+            Some(anchor).into_iter().filter(|t| *t > from && *t < maturity).collect()
+        },
+        _ => Vec::new(),
+    };
+
+    if dates.len() == MAX_ANNUITY_PERIODS {
+        return Err(Error::ValidationError("Too many annuity payment periods".into()));
+    }
+    dates.push(maturity);
+
+    // without a day count convention no interest accrues
+    let year_fractions: Vec<u64> = std::iter::once(from)
+        .chain(dates.iter().copied())
+        .zip(&dates)
+        .map(|(start, &end)| {
+            terms.day_count_convention.map_or(0, |dcc| {
+                granularity.year_fraction(dcc as u8, start, end, Some(maturity))
+            })
+        })
+        .collect();
+
+    // the annuity formula has no closed form below a zero rate
+    let rate = u64::try_from(rate).map_err(|_| {
+        Error::ValidationError("Annuity installments need a non-negative rate".into())
+    })?;
+
+    math::annuity_payment(notional, rate, &year_fractions)
+        .ok_or(Error::MathError("Annuity payment overflow".into()))
+}
+
+/// The premium the holder pays on the IED
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn premium(
+    _state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let ied = terms
+        .initial_exchange_date
+        .ok_or(Error::MissingTerm { field: "initial_exchange_date" })?;
+    if timestamp != ied {
+        return Ok(None);
+    }
+
+    signed(terms.premium_discount_at_ied.unwrap_or(0)).map(Some)
+}
+
+/// Records the exercise [`TransitionEngine::exercise`] or
+/// [`TransitionEngine::exercise_guarantee`] fixed the amount of, which only they process
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn fix_exercise(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if state.exercise_amount.is_none() {
+        return Err(Error::ValidationError("XD events are only processed by an exercise".into()));
+    }
+
+    state.exercise_date = Some(timestamp);
+    Ok(())
+}
+
+/// What the exercise fixed, once the contract is exercised
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn exercise_amount(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let amount = state
+        .exercise_amount
+        .ok_or(Error::TransitionError("The contract was not exercised".into()))?;
+    signed(amount).map(Some)
+}
+
+/// What an exercise left unsettled, on the maturity date only. An option never exercised
+/// expires worthless.
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn expiry_payment(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let md = terms
+        .maturity_date
+        .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+    if timestamp != md {
+        return Ok(None);
+    }
+
+    signed(state.exercise_amount.unwrap_or(0)).map(Some)
+}
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn settle_exercise(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.notional_principal = 0;
+    if state.exercise_amount.is_some() {
+        state.exercise_amount = Some(0);
+    }
+    Ok(())
+}
+
+#[cfg(any(feature = "derivatives", feature = "credit-enhancements"))]
+fn expire(state: &mut ContractState, terms: &ContractTerms, timestamp: Timestamp) -> Result<()> {
+    if terms.maturity_date == Some(timestamp) {
+        settle_exercise(state, terms, timestamp)?;
+    }
+
+    Ok(())
+}
+
+/// Nothing is exchanged at the IED of a swap, though the IED still takes place
+#[cfg(feature = "derivatives")]
+fn no_exchange(
+    _state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let ied = terms
+        .initial_exchange_date
+        .ok_or(Error::MissingTerm { field: "initial_exchange_date" })?;
+    Ok((timestamp == ied).then_some(0))
+}
+
+/// Sets up the notional both legs accrue on, the floating leg starting at the second
+/// rate of the terms
+#[cfg(feature = "derivatives")]
+fn start_legs(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if terms.initial_exchange_date == Some(timestamp) {
+        state.notional_principal = terms
+            .notional_principal
+            .ok_or(Error::MissingTerm { field: "notional_principal" })?;
+        state.nominal_interest_rate = terms.nominal_interest_rate2.unwrap_or(0);
+        clear_legs(state);
+    }
+
+    Ok(())
+}
+
+/// The fixed leg net of the floating one; the creditor pays when the floating leg
+/// accrued more
+#[cfg(feature = "derivatives")]
+fn net_interest(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    Ok((state.accrued_interest != 0).then_some(state.accrued_interest))
+}
+
+#[cfg(feature = "derivatives")]
+fn settle_legs(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    clear_legs(state);
+    Ok(())
+}
+
+/// The legs of a swap or the last caplet, and the fees outstanding, on the maturity date
+/// only. The notional stays put.
+#[cfg(feature = "derivatives")]
+fn net_maturity_payment(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let md = terms
+        .maturity_date
+        .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+    if timestamp != md {
+        return Ok(None);
+    }
+
+    scaled_interest(state, terms)?
+        .checked_add(signed(state.fee_accrued)?)
+        .ok_or(Error::MathError("Maturity payment overflow".into()))
+        .map(Some)
+}
+
+#[cfg(feature = "derivatives")]
+fn redeem_legs(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    redeem(state, terms, timestamp)?;
+    if terms.maturity_date == Some(timestamp) {
+        clear_legs(state);
+    }
+
+    Ok(())
+}
+
+/// What the cap or floor accrued over the period, which its writer pays
+#[cfg(feature = "derivatives")]
+fn caplet(
+    state: &ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    Ok(Some(state.accrued_interest))
+}
+
+#[cfg(feature = "derivatives")]
+fn clear_caplet(
+    state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    state.accrued_interest = 0;
+    Ok(())
+}
+
+#[cfg(feature = "derivatives")]
+fn clear_legs(state: &mut ContractState) {
+    state.accrued_interest_first_leg = Some(0);
+    state.accrued_interest_second_leg = Some(0);
+    state.accrued_interest = 0;
+}
+
+/// What the holder pays for the shares, on the purchase date only
+fn purchase_price(
+    _state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    if terms.purchase_date != Some(timestamp) {
+        return Ok(None);
+    }
+
+    let price = terms.price_at_purchase_date.unwrap_or(0);
+    shares_at(shares(terms), price).map(Some)
+}
+
+fn buy_shares(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if terms.purchase_date == Some(timestamp) {
+        state.notional_principal = shares(terms);
+    }
+
+    Ok(())
+}
+
+/// The dividend of the terms on each share held
+fn dividend(
+    state: &ContractState,
+    terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    let amount = terms
+        .next_dividend_payment_amount
+        .ok_or(Error::ValidationError("DV events need a dividend amount".into()))?;
+    shares_at(state.notional_principal, amount).map(Some)
+}
+
+fn keep_shares(
+    _state: &mut ContractState,
+    _terms: &ContractTerms,
+    _timestamp: Timestamp,
+) -> Result<()> {
+    Ok(())
+}
+
+/// What the shares held sell for, on the termination date only
+fn termination_price(
+    state: &ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<Option<SignedUnits>> {
+    if terms.termination_date != Some(timestamp) {
+        return Ok(None);
+    }
+
+    let price = terms
+        .price_at_termination_date
+        .ok_or(Error::ValidationError("TD events need a termination price".into()))?;
+    shares_at(state.notional_principal, price).map(Some)
+}
+
+fn sell_shares(
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    timestamp: Timestamp,
+) -> Result<()> {
+    if terms.termination_date == Some(timestamp) {
+        state.notional_principal = 0;
+    }
+
+    Ok(())
+}
+
+/// The shares or units a stock or commodity position holds
+fn shares(terms: &ContractTerms) -> Units {
+    Units::from(terms.quantity.unwrap_or(1))
+}
+
+/// `shares` at `price` each, as a payoff
+fn shares_at(shares: Units, price: Units) -> Result<SignedUnits> {
+    shares
+        .checked_mul(price)
+        .ok_or(Error::MathError("Share payoff overflow".into()))
+        .and_then(signed)
+}
+
+/// `amount` as a payoff
+fn signed(amount: Units) -> Result<SignedUnits> {
+    SignedUnits::try_from(amount).map_err(|_| Error::MathError("Payoff overflow".into()))
+}
+
+/// The accrued interest, times the interest multiplier if the terms scale interest
+pub(crate) fn scaled_interest(state: &ContractState, terms: &ContractTerms) -> Result<SignedUnits> {
+    let scales = terms.scaling_effect.is_some_and(ScalingEffect::scales_interest);
+    scale(state.accrued_interest, state.interest_scaling_multiplier, scales)
+}
+
+/// The notional, times the notional multiplier if the terms scale the notional
+fn scaled_notional(state: &ContractState, terms: &ContractTerms) -> Result<SignedUnits> {
+    let scales = terms.scaling_effect.is_some_and(ScalingEffect::scales_notional);
+    scale(signed(state.notional_principal)?, state.notional_scaling_multiplier, scales)
+}
+
+/// `amount` times a multiplier in basis points, truncated toward zero
+fn scale(amount: SignedUnits, multiplier: u64, scales: bool) -> Result<SignedUnits> {
+    if !scales {
+        return Ok(amount);
+    }
+
+    amount
+        .checked_mul(SignedUnits::from(multiplier))
+        .map(|v| v / SignedUnits::from(SCALING_FACTOR_ONE))
+        .ok_or(Error::MathError("Scaled payoff overflow".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+
+    #[test]
+    fn tables_list_each_event_once() {
+        let tables = [
+            ContractType::STK,
+            ContractType::COM,
+            ContractType::CSH,
+            ContractType::PAM,
+            ContractType::UMP,
+            ContractType::CLM,
+            ContractType::LAM,
+            ContractType::NAM,
+            ContractType::ANN,
+            ContractType::OPTNS,
+            ContractType::SWPPV,
+            ContractType::CAPFL,
+            ContractType::CEG,
+        ]
+        .map(|contract_type| ContractLogic::of(contract_type).unwrap());
+
+        for logic in tables {
+            for (i, (event, _)) in logic.handlers.iter().enumerate() {
+                assert!(logic.handlers[i + 1..].iter().all(|(other, _)| other != event));
+            }
+        }
+
+        assert!(ContractLogic::of(ContractType::FUTUR).is_none());
+    }
+
+    #[test]
+    fn annuities_reject_negative_rates() {
+        let terms = ContractTerms {
+            contract_type: ContractType::ANN,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(365 * crate::math::SECONDS_PER_DAY),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(-50),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+
+        assert!(matches!(
+            exchange_annuity(&mut state, &terms, 0),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn annuities_refix_their_installment_after_a_reset() {
+        let month = |k| math::add_months(0, k).unwrap();
+        let terms = ContractTerms {
+            contract_type: ContractType::ANN,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(month(12)),
+            notional_principal: Some(1_200),
+            nominal_interest_rate: Some(0),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: Some(month(1)),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            cycle_anchor_date_of_rate_reset: Some(month(6)),
+            ..Default::default()
+        };
+
+        // the installment is refixed right after the reset
+        let schedule = crate::core::unadjusted_schedule(&terms).unwrap();
+        let at_reset: Vec<_> = schedule.iter().filter(|(_, date)| *date == month(6)).collect();
+        assert_eq!(
+            at_reset,
+            [&(EventType::IP, month(6)), &(EventType::RR, month(6)), &(EventType::PRF, month(6))]
+        );
+
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        for k in 1..=6 {
+            TransitionEngine::process_event(EventType::IP, month(k), &mut state, &terms).unwrap();
+        }
+        assert_eq!(state.next_principal_redemption_payment, 100);
+        assert_eq!(state.notional_principal, 600);
+
+        // the reset rate applies to the six periods left
+        state.nominal_interest_rate = 1_200;
+        let payoff = TransitionEngine::process_event(EventType::PRF, month(6), &mut state, &terms);
+        assert_eq!(payoff, Ok(None));
+        let installment = math::annuity_payment(600, 1_200, &[833; 6]).unwrap();
+        assert!(installment > 100);
+        assert_eq!(state.next_principal_redemption_payment, installment);
+
+        // only an annuity refixes, a NAM keeps the installment of its terms
+        let terms = ContractTerms {
+            contract_type: ContractType::NAM,
+            next_principal_redemption_payment: Some(100),
+            ..terms
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        state.nominal_interest_rate = 1_200;
+        let payoff = TransitionEngine::process_event(EventType::PRF, month(6), &mut state, &terms);
+        assert_eq!(payoff, Ok(None));
+        assert_eq!(state.next_principal_redemption_payment, 100);
+    }
+
+    #[test]
+    fn swaps_settle_their_legs_net() {
+        let month = |k| math::add_months(0, k).unwrap();
+        let terms = ContractTerms {
+            contract_type: ContractType::SWPPV,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(month(3)),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            nominal_interest_rate2: Some(300),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: Some(month(1)),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+
+        // no notional changes hands
+        let mut state = ContractState::new(&terms);
+        let payoff = TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(0)));
+        assert_eq!(state.nominal_interest_rate, 300);
+
+        // the fixed leg accrued more: 4_165 against 2_499
+        let payoff = TransitionEngine::process_event(EventType::IP, month(1), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(1_666)));
+        assert_eq!(state.accrued_interest_first_leg, Some(0));
+
+        // the floating leg reset above the fixed one, so the creditor pays
+        state.nominal_interest_rate = 700;
+        let payoff = TransitionEngine::process_event(EventType::IP, month(2), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(-1_666)));
+
+        // the last period settles net at maturity, leaving the notional unpaid
+        let payoff = TransitionEngine::process_event(EventType::MD, month(3), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(-1_666)));
+        assert_eq!(state.notional_principal, 0);
+        assert_eq!(state.phase, ContractPhase::Matured);
+
+        // the floating leg cannot accrue below zero
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        state.nominal_interest_rate = -100;
+        assert!(matches!(
+            TransitionEngine::process_event(EventType::IP, month(1), &mut state, &terms),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn caps_and_floors_pay_past_their_strikes() {
+        let month = |k| math::add_months(0, k).unwrap();
+        let terms = ContractTerms {
+            contract_type: ContractType::CAPFL,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(month(3)),
+            notional_principal: Some(1_000_000),
+            premium_discount_at_ied: Some(2_000),
+            nominal_interest_rate: Some(300),
+            life_cap: Some(400),
+            life_floor: Some(200),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: Some(month(1)),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+
+        // the holder pays the premium, and nothing is due between the strikes
+        let mut state = ContractState::new(&terms);
+        let payoff = TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(2_000)));
+        let payoff = TransitionEngine::process_event(EventType::IP, month(1), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(0)));
+
+        // 300 above the cap for a month
+        state.nominal_interest_rate = 700;
+        let payoff = TransitionEngine::process_event(EventType::IP, month(2), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(2_499)));
+
+        // 100 below the floor, paid at maturity with the notional left unpaid
+        state.nominal_interest_rate = 100;
+        let payoff = TransitionEngine::process_event(EventType::MD, month(3), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(833)));
+        assert_eq!(state.notional_principal, 0);
+        assert_eq!(state.phase, ContractPhase::Matured);
+
+        // without a strike there is nothing to pay past
+        let terms = ContractTerms { life_cap: None, life_floor: None, ..terms };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        assert!(matches!(
+            TransitionEngine::process_event(EventType::IP, month(1), &mut state, &terms),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn stocks_pay_dividends_on_the_shares_held() {
+        let month = |k| math::add_months(0, k).unwrap();
+        let terms = ContractTerms {
+            contract_type: ContractType::STK,
+            purchase_date: Some(0),
+            price_at_purchase_date: Some(50),
+            quantity: Some(100),
+            next_dividend_payment_amount: Some(2),
+            cycle_anchor_date_of_dividend_payment: Some(month(3)),
+            cycle_of_dividend_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            termination_date: Some(month(6)),
+            price_at_termination_date: Some(60),
+            ..Default::default()
+        };
+
+        // no dividend before the shares are bought
+        let mut state = ContractState::new(&terms);
+        assert!(matches!(
+            TransitionEngine::process_event(EventType::DV, month(3), &mut state, &terms),
+            Err(Error::EventNotAllowed { .. })
+        ));
+
+        let payoff = TransitionEngine::process_event(EventType::PRD, 0, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(5_000)));
+        assert_eq!(state.phase, ContractPhase::Active);
+        let payoff = TransitionEngine::process_event(EventType::DV, month(3), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(200)));
+
+        let payoff = TransitionEngine::process_event(EventType::TD, month(6), &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(6_000)));
+        assert_eq!(state.notional_principal, 0);
+        assert_eq!(state.phase, ContractPhase::Terminated);
+
+        // a dividend has to be set to be paid
+        let terms = ContractTerms { next_dividend_payment_amount: None, ..terms };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::PRD, 0, &mut state, &terms).unwrap();
+        assert!(matches!(
+            TransitionEngine::process_event(EventType::DV, month(3), &mut state, &terms),
+            Err(Error::ValidationError(_))
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+//! JSON form of the contract state for monitoring APIs.
+//!
+//! Fields carry the camelCase ACTUS state variable names, and integers are written as
+//! decimal strings since JavaScript numbers lose precision above 2^53. Indexers decode
+//! the Borsh state returned by `get_state` and re-emit it with
+//! [`ContractState::to_json`].
+
+use super::{ContractAddress, ContractId, ContractState};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "json")]
+impl ContractState {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("contract state always serializes")
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// The displayed id, so short ids stay readable in terms files
+impl Serialize for ContractId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|id| ContractId::from(id.as_str()))
+    }
+}
+
+/// The `0x` hex form of the address
+impl Serialize for ContractAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
+
+/// An integer as a decimal string
+pub(crate) mod string {
+    use super::*;
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Display,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr<Err: Display>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(D::Error::custom)
+    }
+}
+
+/// An optional integer as a decimal string or `null`
+pub(crate) mod option_string {
+    use super::*;
+
+    pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Display,
+    {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr<Err: Display>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| value.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use crate::core::{
+        ContractPerformance, ContractState, ContractTerms, EventType, TransitionEngine,
+    };
+
+    /// The fields dashboards read; renaming any of them has to update the fixture
+    const GOLDEN: &str = include_str!("../tests/fixtures/state.json");
+
+    fn state_after_ied() -> ContractState {
+        let terms = ContractTerms {
+            status_date: 1_735_689_600,
+            initial_exchange_date: Some(1_735_689_600),
+            maturity_date: Some(1_798_761_600),
+            notional_principal: Some(u64::MAX.into()),
+            nominal_interest_rate: Some(500),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 1_735_689_600, &mut state, &terms).unwrap();
+        state
+    }
+
+    #[test]
+    fn state_matches_golden_json() {
+        let golden: serde_json::Value = serde_json::from_str(GOLDEN).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&state_after_ied().to_json()).unwrap();
+
+        assert_eq!(json, golden);
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let mut state = state_after_ied();
+        assert_eq!(ContractState::from_json(&state.to_json()).unwrap(), state);
+
+        state.exercise_amount = Some(1);
+        state.contract_performance = ContractPerformance::DF;
+        assert_eq!(ContractState::from_json(&state.to_json()).unwrap(), state);
+    }
+
+    #[test]
+    fn borsh_state_reemits_as_json() {
+        let state = state_after_ied();
+        let bytes = borsh::to_vec(&state).unwrap();
+
+        assert_eq!(borsh::from_slice::<ContractState>(&bytes).unwrap().to_json(), state.to_json());
+    }
+
+    #[test]
+    fn integers_must_be_strings() {
+        let json = GOLDEN.replace("\"500\"", "500");
+        assert!(ContractState::from_json(&json).is_err());
+    }
+}
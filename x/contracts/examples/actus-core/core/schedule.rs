@@ -0,0 +1,383 @@
+// src/core/schedule.rs
+
+//! The event schedule of a contract, derived from its terms. The engine processes
+//! events at their unadjusted dates, which [`unadjusted_schedule`] lists;
+//! [`GenerateSchedule`] and [`event_schedule`] give the days the [`ScheduleConfig`] of
+//! the terms moves them to, for display and settlement off-chain.
+
+use super::types::*;
+use super::{GenerateSchedule, Result, TransitionEngine};
+use crate::math;
+
+/// The days of one event type, in order, as [`event_schedule`] lists them
+impl GenerateSchedule for EventType {
+    fn generate_schedule(&self, terms: &ContractTerms) -> Result<Vec<ShiftedDay>> {
+        check_terms(terms)?;
+
+        Ok(dates_of(*self, terms)
+            .into_iter()
+            .map(|date| shift(*self, date, terms))
+            .collect())
+    }
+}
+
+/// The events of `terms` in processing order: the IED, or the PRD of a stock, a PI for
+/// each disbursement after it, every date of the [`cycles`](TransitionEngine::cycles)
+/// strictly between it and maturity, or the [`schedule_end`](ContractTerms::schedule_end)
+/// of a contract without one, but for the payments on skipped dates, and the MD, or
+/// the TD of a stock, if any. A disbursement comes first on its date and the other
+/// events follow the order of the cycles, so a reset coinciding with an interest
+/// payment comes after it.
+pub fn unadjusted_schedule(terms: &ContractTerms) -> Result<Vec<(EventType, Timestamp)>> {
+    check_terms(terms)?;
+
+    // (date, position among the events on that date, event)
+    let mut events: Vec<_> = scheduled_events(terms)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(position, event)| {
+            dates_of(event, terms).into_iter().map(move |date| (date, position, event))
+        })
+        .collect();
+
+    events.sort();
+    Ok(events
+        .into_iter()
+        .map(|(date, _, event)| (event, date))
+        .collect())
+}
+
+/// The events of [`unadjusted_schedule`], each on the days the schedule config moves
+/// it to: a monthly cycle anchored at the end of a month stays at month ends under
+/// `EOMC_EOM`, and a day off the `CLDR_MF` calendar is paid, and under an `SC`
+/// convention also calculated, on the business day the convention picks
+pub fn event_schedule(terms: &ContractTerms) -> Result<Vec<(EventType, ShiftedDay)>> {
+    Ok(unadjusted_schedule(terms)?
+        .into_iter()
+        .map(|(event, date)| (event, shift(event, date, terms)))
+        .collect())
+}
+
+fn check_terms(terms: &ContractTerms) -> Result<()> {
+    terms.check_required()?;
+    terms.check_disbursements()?;
+    terms.check_dates()
+}
+
+/// Every scheduled event type, in the order events on the same date are processed
+fn scheduled_events(terms: &ContractTerms) -> Vec<EventType> {
+    let cyclic = TransitionEngine::cycles(terms).map(|(event, _, _)| event);
+
+    [terms.opening().0, EventType::PI]
+        .into_iter()
+        .chain(cyclic)
+        .chain([terms.closing().0])
+        .collect()
+}
+
+/// The unadjusted dates `event` is scheduled on, none for events that never are
+fn dates_of(event: EventType, terms: &ContractTerms) -> Vec<Timestamp> {
+    let (opening, opened) = terms.opening();
+    let (closing, closed) = terms.closing();
+    let ied = opened.unwrap_or_default();
+    let maturity = terms.schedule_end();
+    let granularity = terms.granularity();
+
+    match event {
+        _ if event == opening => return vec![ied],
+        _ if event == closing => return closed.into_iter().collect(),
+        EventType::PI => {
+            let tranches = terms.disbursements.as_deref().unwrap_or_default();
+            return tranches.iter().skip(1).map(|(date, _)| *date).collect();
+        }
+        _ => {}
+    }
+
+    let dates = match cycle_of(event, terms) {
+        Some((Some(anchor), Some(cycle))) => {
+            std::iter::successors(cycle.next_after(anchor, ied, granularity), |date| {
+                cycle.next_after(anchor, *date, granularity)
+            })
+            .take_while(|date| *date < maturity)
+            .collect()
+        }
+        Some((Some(anchor), None)) => vec![anchor],
+        _ => vec![],
+    };
+
+    dates
+        .into_iter()
+        .filter(|date| ied < *date && *date < maturity && !terms.skips(event, *date))
+        .collect()
+}
+
+/// The anchor and cycle scheduling `event`, if it is cyclic
+fn cycle_of(
+    event: EventType,
+    terms: &ContractTerms,
+) -> Option<(Option<Timestamp>, Option<&Cycle>)> {
+    TransitionEngine::cycles(terms)
+        .into_iter()
+        .find(|(cyclic, _, _)| *cyclic == event)
+        .map(|(_, anchor, cycle)| (anchor, cycle))
+}
+
+/// The days `date` of `event` is paid and calculated on under the schedule config
+fn shift(event: EventType, date: Timestamp, terms: &ContractTerms) -> ShiftedDay {
+    let config = &terms.schedule_config;
+    let granularity = terms.granularity();
+
+    let end_of_month = config.end_of_month_convention == Some(EndOfMonthConvention::EOMC_EOM)
+        && matches!(
+            cycle_of(event, terms),
+            Some((Some(anchor), Some(cycle)))
+                if !matches!(cycle.p, Period::P_D | Period::P_W)
+                    && is_month_end(anchor, granularity)
+        );
+    let date = if end_of_month { month_end(date, granularity) } else { date };
+
+    if config.calendar != Some(Calendar::CLDR_MF) {
+        return ShiftedDay::from_single(date);
+    }
+
+    use BusinessDayConvention::*;
+    let convention = config.business_day_convention.unwrap_or(BDC_NULL);
+    let payment_day = match convention {
+        BDC_NULL => date,
+        BDC_SCF | BDC_CSF => following(date, granularity),
+        BDC_SCP | BDC_CSP => preceding(date, granularity),
+        // modified conventions turn back rather than leave the month
+        BDC_SCMF | BDC_CSMF => Some(following(date, granularity))
+            .filter(|day| same_month(date, *day, granularity))
+            .unwrap_or_else(|| preceding(date, granularity)),
+        BDC_SCMP | BDC_CSMP => Some(preceding(date, granularity))
+            .filter(|day| same_month(date, *day, granularity))
+            .unwrap_or_else(|| following(date, granularity)),
+    };
+
+    match convention {
+        BDC_CSF | BDC_CSMF | BDC_CSP | BDC_CSMP => ShiftedDay::new(payment_day, date),
+        _ => ShiftedDay::from_single(payment_day),
+    }
+}
+
+fn same_month(date: Timestamp, other: Timestamp, granularity: TimeGranularity) -> bool {
+    let (year, month, _) = granularity.civil_date(date);
+    let (other_year, other_month, _) = granularity.civil_date(other);
+    (year, month) == (other_year, other_month)
+}
+
+/// Monday to Friday
+fn is_business_day(date: Timestamp, granularity: TimeGranularity) -> bool {
+    // the epoch was a Thursday, so this counts from Sunday
+    let weekday = (granularity.to_seconds(date) / math::SECONDS_PER_DAY + 4) % 7;
+    !matches!(weekday, 0 | 6)
+}
+
+/// The first business day from `date` on
+fn following(date: Timestamp, granularity: TimeGranularity) -> Timestamp {
+    let mut day = date;
+    while !is_business_day(day, granularity) {
+        day = day.saturating_add(granularity.day());
+    }
+    day
+}
+
+/// The last business day up to `date`, or the epoch
+fn preceding(date: Timestamp, granularity: TimeGranularity) -> Timestamp {
+    let mut day = date;
+    while !is_business_day(day, granularity) && day > 0 {
+        day = day.saturating_sub(granularity.day());
+    }
+    day
+}
+
+fn is_month_end(date: Timestamp, granularity: TimeGranularity) -> bool {
+    let (year, month, day) = granularity.civil_date(date);
+    day == math::days_in_civil_month(year, month)
+}
+
+/// The last day of the month of `date`, at the same time of day
+fn month_end(date: Timestamp, granularity: TimeGranularity) -> Timestamp {
+    let (year, month, day) = granularity.civil_date(date);
+    let days_left = math::days_in_civil_month(year, month) - day;
+    date.saturating_add(days_left * granularity.day())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Error;
+
+    /// A PAM from Friday 2024-05-31 paying interest at each month end to 2024-12-31
+    fn month_end_terms(config: ScheduleConfig) -> ContractTerms {
+        let ied = math::timestamp_from_civil(2024, 5, 31);
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(ied),
+            maturity_date: Some(math::timestamp_from_civil(2024, 12, 31)),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            status_date: ied,
+            cycle_anchor_date_of_interest_payment: Some(ied),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            schedule_config: config,
+            ..Default::default()
+        }
+    }
+
+    fn days(schedule: &[ShiftedDay]) -> Vec<(u64, u64)> {
+        schedule
+            .iter()
+            .map(|day| {
+                let (_, month, payment) = math::civil_date(day.payment_day);
+                let (_, _, calculation) = math::civil_date(day.calculation_day);
+                assert_eq!(month, math::civil_date(day.calculation_day).1);
+                (payment, calculation)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn schedules_without_a_config_keep_their_dates() {
+        let terms = month_end_terms(ScheduleConfig::default());
+        let unadjusted = unadjusted_schedule(&terms).unwrap();
+
+        let events: Vec<_> = unadjusted.iter().map(|(event, _)| *event).collect();
+        assert_eq!(events[0], EventType::IED);
+        assert_eq!(events[1..7], [EventType::IP; 6]);
+        assert_eq!(events[7..], [EventType::MD]);
+
+        // without a config every event falls on its unadjusted date
+        let shifted = event_schedule(&terms).unwrap();
+        assert!(shifted
+            .iter()
+            .zip(&unadjusted)
+            .all(|((_, day), (_, date))| *day == ShiftedDay::from_single(*date)));
+    }
+
+    #[test]
+    fn month_ends_follow_the_end_of_month_convention() {
+        let same_day = month_end_terms(ScheduleConfig {
+            end_of_month_convention: Some(EndOfMonthConvention::EOMC_SD),
+            ..Default::default()
+        });
+        // shorter months end the cycle on their last day either way
+        let ip = EventType::IP.generate_schedule(&same_day).unwrap();
+        assert_eq!(days(&ip), [(30, 30), (31, 31), (31, 31), (30, 30), (31, 31), (30, 30)]);
+
+        let end_of_month = ContractTerms {
+            schedule_config: ScheduleConfig {
+                end_of_month_convention: Some(EndOfMonthConvention::EOMC_EOM),
+                ..Default::default()
+            },
+            ..same_day.clone()
+        };
+        let ip = EventType::IP.generate_schedule(&end_of_month).unwrap();
+        assert_eq!(days(&ip), [(30, 30), (31, 31), (31, 31), (30, 30), (31, 31), (30, 30)]);
+
+        // anchored at a month end that is not the 31st, only EOM keeps to month ends
+        let anchor = math::timestamp_from_civil(2024, 6, 30);
+        let from_june = |terms: &ContractTerms| ContractTerms {
+            cycle_anchor_date_of_interest_payment: Some(anchor),
+            ..terms.clone()
+        };
+        let ip = EventType::IP.generate_schedule(&from_june(&same_day)).unwrap();
+        assert_eq!(days(&ip)[1], (30, 30));
+        let ip = EventType::IP.generate_schedule(&from_june(&end_of_month)).unwrap();
+        assert_eq!(days(&ip)[1], (31, 31));
+    }
+
+    #[test]
+    fn weekends_move_to_a_business_day() {
+        let config = |convention| ScheduleConfig {
+            calendar: Some(Calendar::CLDR_MF),
+            end_of_month_convention: None,
+            business_day_convention: Some(convention),
+        };
+        // 2024-06-30 is a Sunday and 2024-08-31 a Saturday
+        let days_of = |convention| {
+            let terms = month_end_terms(config(convention));
+            days(&EventType::IP.generate_schedule(&terms).unwrap())[..3].to_vec()
+        };
+
+        assert_eq!(days_of(BusinessDayConvention::BDC_NULL), [(30, 30), (31, 31), (31, 31)]);
+        // following moves June's payment into July, unless modified
+        let first_of_july = math::timestamp_from_civil(2024, 7, 1);
+        let terms = month_end_terms(config(BusinessDayConvention::BDC_SCF));
+        let ip = EventType::IP.generate_schedule(&terms).unwrap();
+        assert_eq!(ip[0], ShiftedDay::from_single(first_of_july));
+        let terms = month_end_terms(config(BusinessDayConvention::BDC_CSF));
+        let ip = EventType::IP.generate_schedule(&terms).unwrap();
+        assert_eq!(ip[0], ShiftedDay::new(first_of_july, first_of_july - math::SECONDS_PER_DAY));
+
+        assert_eq!(days_of(BusinessDayConvention::BDC_SCMF), [(28, 28), (31, 31), (30, 30)]);
+        assert_eq!(days_of(BusinessDayConvention::BDC_CSMF), [(28, 30), (31, 31), (30, 31)]);
+        assert_eq!(days_of(BusinessDayConvention::BDC_SCP), [(28, 28), (31, 31), (30, 30)]);
+        assert_eq!(days_of(BusinessDayConvention::BDC_CSMP), [(28, 30), (31, 31), (30, 31)]);
+
+        // the calendar without business days leaves them alone
+        let terms = month_end_terms(ScheduleConfig {
+            calendar: Some(Calendar::CLDR_NC),
+            ..config(BusinessDayConvention::BDC_SCF)
+        });
+        let ip = EventType::IP.generate_schedule(&terms).unwrap();
+        assert_eq!(days(&ip)[..3], [(30, 30), (31, 31), (31, 31)]);
+    }
+
+    #[test]
+    fn amortizing_schedules_redeem_on_their_cycle() {
+        let terms = ContractTerms {
+            contract_type: ContractType::LAM,
+            cycle_anchor_date_of_principal_redemption: Some(math::timestamp_from_civil(
+                2024, 8, 31,
+            )),
+            cycle_of_principal_redemption: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            next_principal_redemption_payment: Some(250_000),
+            ..month_end_terms(ScheduleConfig::default())
+        };
+
+        let pr = EventType::PR.generate_schedule(&terms).unwrap();
+        assert_eq!(days(&pr), [(31, 31), (30, 30)]);
+        assert_eq!(EventType::FP.generate_schedule(&terms), Ok(vec![]));
+
+        // a PAM never redeems on a cycle
+        let pam = ContractTerms { contract_type: ContractType::PAM, ..terms };
+        assert_eq!(EventType::PR.generate_schedule(&pam), Ok(vec![]));
+    }
+
+    #[test]
+    fn deposits_without_maturity_are_scheduled_over_the_horizon() {
+        let terms = ContractTerms {
+            contract_type: ContractType::UMP,
+            maturity_date: None,
+            ..month_end_terms(ScheduleConfig::default())
+        };
+        let ied = terms.initial_exchange_date.unwrap();
+
+        // the monthly payments of a century, with no MD
+        let schedule = unadjusted_schedule(&terms).unwrap();
+        assert_eq!(schedule.first(), Some(&(EventType::IED, ied)));
+        assert!(schedule[1..].iter().all(|(event, _)| *event == EventType::IP));
+        let (_, last) = schedule.last().unwrap();
+        let end = terms.schedule_end();
+        assert!(*last < end && end - last <= 31 * math::SECONDS_PER_DAY);
+
+        // other types still need a maturity
+        let pam = ContractTerms { contract_type: ContractType::PAM, ..terms };
+        assert_eq!(
+            unadjusted_schedule(&pam),
+            Err(Error::MissingTerm { field: "maturity_date" })
+        );
+    }
+}
@@ -0,0 +1,246 @@
+// src/core/state.rs
+
+//! [`ContractState`] split by how often its fields change, so a contract rewrites the
+//! few that every event moves and persists the rest only when one of them changes.
+
+use super::types::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The fields every event moves: the status date, the accruals and the notional and
+/// rate they accrue on, and the totals of the payments, which most events are
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct HotState {
+    pub status_date: Timestamp,
+    pub notional_principal: Units,
+    pub nominal_interest_rate: Rate,
+    pub accrued_interest: SignedUnits,
+    pub fee_accrued: Units,
+    pub total_interest_paid: SignedUnits,
+    pub total_principal_repaid: Units,
+    pub total_fees_paid: Units,
+    pub total_penalties_paid: Units,
+}
+
+/// The fields only some events change: maturity, scaling, performance, redemption
+/// amounts, exercise, missed payments, what they pay for and their penalty, the
+/// reference and pending reset rates, the per-leg accruals of swaps, the phase and the
+/// payoff residual
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ColdState {
+    pub time_of_maturity: Option<Timestamp>,
+    pub accrued_interest_first_leg: Option<Units>,
+    pub accrued_interest_second_leg: Option<Units>,
+    pub last_interest_period: Option<u64>,
+    pub notional_scaling_multiplier: u64,
+    pub interest_scaling_multiplier: u64,
+    pub contract_performance: ContractPerformance,
+    pub next_principal_redemption_payment: Units,
+    pub interest_calculation_base: Units,
+    pub exercise_date: Option<Timestamp>,
+    pub exercise_amount: Option<Units>,
+    pub overdue_amount: Units,
+    pub overdue_since: Option<Timestamp>,
+    pub overdue_interest: Units,
+    pub overdue_fees: Units,
+    pub overdue_penalties: Units,
+    pub penalty_assessed: bool,
+    pub reference_rate: Option<Rate>,
+    pub reset_rate: Option<Rate>,
+    pub phase: ContractPhase,
+    pub payoff_residual: SignedUnits,
+}
+
+// Both conversions destructure every field, so a new state variable fails to compile
+// until it is assigned to one of the records
+
+impl From<ContractState> for (HotState, ColdState) {
+    fn from(state: ContractState) -> Self {
+        let ContractState {
+            time_of_maturity,
+            notional_principal,
+            nominal_interest_rate,
+            accrued_interest,
+            accrued_interest_first_leg,
+            accrued_interest_second_leg,
+            last_interest_period,
+            fee_accrued,
+            notional_scaling_multiplier,
+            interest_scaling_multiplier,
+            contract_performance,
+            status_date,
+            next_principal_redemption_payment,
+            interest_calculation_base,
+            exercise_date,
+            exercise_amount,
+            overdue_amount,
+            overdue_since,
+            penalty_assessed,
+            reference_rate,
+            reset_rate,
+            phase,
+            payoff_residual,
+            total_interest_paid,
+            total_principal_repaid,
+            total_fees_paid,
+            total_penalties_paid,
+            overdue_interest,
+            overdue_fees,
+            overdue_penalties,
+        } = state;
+
+        (
+            HotState {
+                status_date,
+                notional_principal,
+                nominal_interest_rate,
+                accrued_interest,
+                fee_accrued,
+                total_interest_paid,
+                total_principal_repaid,
+                total_fees_paid,
+                total_penalties_paid,
+            },
+            ColdState {
+                time_of_maturity,
+                accrued_interest_first_leg,
+                accrued_interest_second_leg,
+                last_interest_period,
+                notional_scaling_multiplier,
+                interest_scaling_multiplier,
+                contract_performance,
+                next_principal_redemption_payment,
+                interest_calculation_base,
+                exercise_date,
+                exercise_amount,
+                overdue_amount,
+                overdue_since,
+                overdue_interest,
+                overdue_fees,
+                overdue_penalties,
+                penalty_assessed,
+                reference_rate,
+                reset_rate,
+                phase,
+                payoff_residual,
+            },
+        )
+    }
+}
+
+impl From<(HotState, ColdState)> for ContractState {
+    fn from((hot, cold): (HotState, ColdState)) -> Self {
+        let HotState {
+            status_date,
+            notional_principal,
+            nominal_interest_rate,
+            accrued_interest,
+            fee_accrued,
+            total_interest_paid,
+            total_principal_repaid,
+            total_fees_paid,
+            total_penalties_paid,
+        } = hot;
+        let ColdState {
+            time_of_maturity,
+            accrued_interest_first_leg,
+            accrued_interest_second_leg,
+            last_interest_period,
+            notional_scaling_multiplier,
+            interest_scaling_multiplier,
+            contract_performance,
+            next_principal_redemption_payment,
+            interest_calculation_base,
+            exercise_date,
+            exercise_amount,
+            overdue_amount,
+            overdue_since,
+            overdue_interest,
+            overdue_fees,
+            overdue_penalties,
+            penalty_assessed,
+            reference_rate,
+            reset_rate,
+            phase,
+            payoff_residual,
+        } = cold;
+
+        ContractState {
+            time_of_maturity,
+            notional_principal,
+            nominal_interest_rate,
+            accrued_interest,
+            accrued_interest_first_leg,
+            accrued_interest_second_leg,
+            last_interest_period,
+            fee_accrued,
+            notional_scaling_multiplier,
+            interest_scaling_multiplier,
+            contract_performance,
+            status_date,
+            next_principal_redemption_payment,
+            interest_calculation_base,
+            exercise_date,
+            exercise_amount,
+            overdue_amount,
+            overdue_since,
+            penalty_assessed,
+            reference_rate,
+            reset_rate,
+            phase,
+            payoff_residual,
+            total_interest_paid,
+            total_principal_repaid,
+            total_fees_paid,
+            total_penalties_paid,
+            overdue_interest,
+            overdue_fees,
+            overdue_penalties,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::TransitionEngine;
+
+    /// A year-long PAM and its state after the IED
+    fn exchanged_pam() -> (ContractTerms, ContractState) {
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(365 * 86_400),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        (terms, state)
+    }
+
+    #[test]
+    fn records_reassemble_the_state() {
+        let (_, mut state) = exchanged_pam();
+        state.exercise_amount = Some(7);
+
+        let (hot, cold): (HotState, ColdState) = state.clone().into();
+        assert_eq!(hot.notional_principal, 1_000_000);
+        assert_eq!(cold.exercise_amount, Some(7));
+        assert_eq!(ContractState::from((hot, cold)), state);
+    }
+
+    #[test]
+    fn accruing_leaves_the_cold_record_unchanged() {
+        let (terms, mut state) = exchanged_pam();
+        let (before_hot, before_cold): (HotState, ColdState) = state.clone().into();
+
+        TransitionEngine::process_event(EventType::AD, 90 * 86_400, &mut state, &terms).unwrap();
+        let (hot, cold): (HotState, ColdState) = state.into();
+
+        assert_ne!(hot, before_hot);
+        assert_eq!(cold, before_cold);
+    }
+}
@@ -0,0 +1,645 @@
+// src/core/terms.rs
+
+//! [`ContractTerms`] split into groups by concern, so a contract can persist each
+//! group under its own key and a transition deserializes only the groups it reads.
+
+use super::types::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::io;
+
+/// A group of terms persisted together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TermGroup {
+    Principal   = 0,
+    Rate        = 1,
+    Schedule    = 2,
+    Optionality = 3,
+}
+
+impl TermGroup {
+    /// All groups, indexed by their `u8` discriminant
+    pub const ALL: [TermGroup; 4] =
+        [TermGroup::Principal, TermGroup::Rate, TermGroup::Schedule, TermGroup::Optionality];
+
+    /// The groups a transition of `event` reads. Every transition accrues interest up
+    /// to the event, so it takes the rate terms along with the principal; only an IED or
+    /// a PRF, which fix an annuity over the interest cycle, need the schedule, and only a
+    /// prepayment, an exercise or the purchase or sale of a stock the optionality terms.
+    pub fn read_by(event: EventType) -> &'static [TermGroup] {
+        match event {
+            EventType::IED | EventType::PRF => {
+                &[TermGroup::Principal, TermGroup::Rate, TermGroup::Schedule]
+            }
+            EventType::PP | EventType::XD | EventType::PRD | EventType::TD => {
+                &[TermGroup::Principal, TermGroup::Rate, TermGroup::Optionality]
+            }
+            _ => &[TermGroup::Principal, TermGroup::Rate],
+        }
+    }
+}
+
+/// Identification, parties' roles, key dates and amounts of the principal, how it is
+/// settled and the dividends a stock pays
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct PrincipalTerms {
+    pub contract_id: ContractId,
+    pub contract_label: Option<String>,
+    pub contract_type: ContractType,
+    pub contract_role: ContractRole,
+    pub settlement_currency: Option<String>,
+    pub status_date: Timestamp,
+    pub market_object_code: Option<String>,
+    pub contract_performance: Option<ContractPerformance>,
+    pub initial_exchange_date: Option<Timestamp>,
+    pub maturity_date: Option<Timestamp>,
+    pub amortization_date: Option<Timestamp>,
+    pub notional_principal: Option<Units>,
+    pub premium_discount_at_ied: Option<Units>,
+    pub next_principal_redemption_payment: Option<Units>,
+    pub credit_limit: Option<Units>,
+    pub disbursements: Option<Vec<(Timestamp, Units)>>,
+    pub quantity: Option<u64>,
+    pub currency: Option<String>,
+    pub currency2: Option<String>,
+    pub settlement_policy: Option<SettlementPolicy>,
+    pub next_dividend_payment_amount: Option<Units>,
+    pub market_object_code_of_dividends: Option<String>,
+    pub market_object_provider: Option<ContractAddress>,
+}
+
+/// Day count, interest, fee, scaling, rate reset, rate limit, penalty and delinquency terms
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct RateTerms {
+    pub day_count_convention: Option<DayCountConvention>,
+    pub accrued_interest: Option<SignedUnits>,
+    pub capitalization_end_date: Option<Timestamp>,
+    pub interest_calculation_base: Option<IPCB>,
+    pub interest_calculation_base_amount: Option<Units>,
+    pub nominal_interest_rate: Option<Rate>,
+    pub nominal_interest_rate2: Option<Rate>,
+    pub interest_scaling_multiplier: Option<u64>,
+    pub fee_accrued: Option<Units>,
+    pub fee_basis: Option<FeeBasis>,
+    pub fee_rate: Option<u64>,
+    pub scaling_effect: Option<ScalingEffect>,
+    pub scaling_index_at_status_date: Option<u64>,
+    pub scaling_index_at_contract_deal_date: Option<u64>,
+    pub market_object_code_of_scaling_index: Option<String>,
+    pub notional_scaling_multiplier: Option<u64>,
+    pub rate_spread: Option<Rate>,
+    pub market_object_code_of_rate_reset: Option<String>,
+    pub penalty_rate: Option<u64>,
+    pub penalty_type: Option<PenaltyType>,
+    pub grace_period: Option<u64>,
+    pub delinquency_rate: Option<u64>,
+    pub cycle_point_of_rate_reset: Option<CyclePoint>,
+    pub fixing_period: Option<u64>,
+    pub interest_timing: Option<InterestTiming>,
+    pub time_granularity: Option<TimeGranularity>,
+    pub life_cap: Option<Rate>,
+    pub life_floor: Option<Rate>,
+    pub rate_multiplier: Option<u64>,
+    pub period_cap: Option<Rate>,
+    pub period_floor: Option<Rate>,
+}
+
+/// Cycles and their anchors
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ScheduleTerms {
+    pub schedule_config: ScheduleConfig,
+    pub cycle_of_fee: Option<Cycle>,
+    pub cycle_anchor_date_of_fee: Option<Timestamp>,
+    pub cycle_anchor_date_of_interest_payment: Option<Timestamp>,
+    pub cycle_of_interest_payment: Option<Cycle>,
+    pub cycle_anchor_date_of_interest_calculation_base: Option<Timestamp>,
+    pub cycle_of_interest_calculation_base: Option<Cycle>,
+    pub cycle_anchor_date_of_principal_redemption: Option<Timestamp>,
+    pub cycle_of_principal_redemption: Option<Cycle>,
+    pub cycle_anchor_date_of_scaling_index: Option<Timestamp>,
+    pub cycle_of_scaling_index: Option<Cycle>,
+    pub cycle_anchor_date_of_rate_reset: Option<Timestamp>,
+    pub cycle_of_rate_reset: Option<Cycle>,
+    pub skipped_dates: Option<Vec<Timestamp>>,
+    pub cycle_anchor_date_of_dividend_payment: Option<Timestamp>,
+    pub cycle_of_dividend_payment: Option<Cycle>,
+}
+
+/// Exercise, purchase, termination and prepayment terms, what an option pays, what a
+/// guarantee covers, the notice a call gives and the legs of a swap
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct OptionalityTerms {
+    pub exercise_date: Option<Timestamp>,
+    pub purchase_date: Option<Timestamp>,
+    pub price_at_purchase_date: Option<Units>,
+    pub termination_date: Option<Timestamp>,
+    pub price_at_termination_date: Option<Units>,
+    pub prepayment_effect: Option<PrepaymentEffect>,
+    pub option_exercise_type: Option<OptionExerciseType>,
+    pub option_exercise_start_date: Option<Timestamp>,
+    pub option_exercise_end_date: Option<Timestamp>,
+    pub option_exercise_dates: Option<Vec<Timestamp>>,
+    pub option_type: Option<OptionType>,
+    pub option_strike1: Option<Units>,
+    pub market_object_code_of_underlying: Option<String>,
+    pub covered_contract: Option<ContractAddress>,
+    pub coverage_of_credit_enhancement: Option<u64>,
+    pub guaranteed_exposure: Option<GuaranteedExposure>,
+    pub x_day_notice: Option<u64>,
+    pub first_leg: Option<ContractAddress>,
+    pub second_leg: Option<ContractAddress>,
+}
+
+/// Every field of [`ContractTerms`], by group. Converting back leaves the terms of a
+/// group that was never read at their defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TermGroups {
+    pub principal: PrincipalTerms,
+    pub rate: RateTerms,
+    pub schedule: ScheduleTerms,
+    pub optionality: OptionalityTerms,
+}
+
+impl TermGroups {
+    /// The borsh encoding of `group`
+    pub fn to_bytes(&self, group: TermGroup) -> io::Result<Vec<u8>> {
+        match group {
+            TermGroup::Principal => borsh::to_vec(&self.principal),
+            TermGroup::Rate => borsh::to_vec(&self.rate),
+            TermGroup::Schedule => borsh::to_vec(&self.schedule),
+            TermGroup::Optionality => borsh::to_vec(&self.optionality),
+        }
+    }
+
+    /// Replaces `group` with the one encoded in `bytes`
+    pub fn read(&mut self, group: TermGroup, bytes: &[u8]) -> io::Result<()> {
+        match group {
+            TermGroup::Principal => self.principal = borsh::from_slice(bytes)?,
+            TermGroup::Rate => self.rate = borsh::from_slice(bytes)?,
+            TermGroup::Schedule => self.schedule = borsh::from_slice(bytes)?,
+            TermGroup::Optionality => self.optionality = borsh::from_slice(bytes)?,
+        }
+        Ok(())
+    }
+}
+
+// Both conversions destructure every field, so a new term fails to compile until it
+// is given a group
+
+impl From<ContractTerms> for TermGroups {
+    fn from(terms: ContractTerms) -> Self {
+        let ContractTerms {
+            contract_id,
+            contract_type,
+            contract_role,
+            settlement_currency,
+            initial_exchange_date,
+            day_count_convention,
+            schedule_config,
+            status_date,
+            market_object_code,
+            contract_performance,
+            cycle_of_fee,
+            cycle_anchor_date_of_fee,
+            fee_accrued,
+            fee_basis,
+            fee_rate,
+            cycle_anchor_date_of_interest_payment,
+            cycle_of_interest_payment,
+            accrued_interest,
+            capitalization_end_date,
+            cycle_anchor_date_of_interest_calculation_base,
+            cycle_of_interest_calculation_base,
+            interest_calculation_base,
+            interest_calculation_base_amount,
+            nominal_interest_rate,
+            nominal_interest_rate2,
+            interest_scaling_multiplier,
+            maturity_date,
+            amortization_date,
+            exercise_date,
+            notional_principal,
+            premium_discount_at_ied,
+            cycle_anchor_date_of_principal_redemption,
+            cycle_of_principal_redemption,
+            next_principal_redemption_payment,
+            credit_limit,
+            disbursements,
+            purchase_date,
+            price_at_purchase_date,
+            termination_date,
+            price_at_termination_date,
+            quantity,
+            currency,
+            currency2,
+            scaling_effect,
+            scaling_index_at_status_date,
+            cycle_anchor_date_of_scaling_index,
+            cycle_of_scaling_index,
+            scaling_index_at_contract_deal_date,
+            market_object_code_of_scaling_index,
+            notional_scaling_multiplier,
+            cycle_anchor_date_of_rate_reset,
+            cycle_of_rate_reset,
+            rate_spread,
+            market_object_code_of_rate_reset,
+            penalty_rate,
+            penalty_type,
+            prepayment_effect,
+            option_exercise_type,
+            option_exercise_start_date,
+            option_exercise_end_date,
+            option_exercise_dates,
+            contract_label,
+            grace_period,
+            delinquency_rate,
+            cycle_point_of_rate_reset,
+            fixing_period,
+            interest_timing,
+            skipped_dates,
+            time_granularity,
+            settlement_policy,
+            option_type,
+            option_strike1,
+            market_object_code_of_underlying,
+            covered_contract,
+            coverage_of_credit_enhancement,
+            guaranteed_exposure,
+            x_day_notice,
+            life_cap,
+            life_floor,
+            cycle_anchor_date_of_dividend_payment,
+            cycle_of_dividend_payment,
+            next_dividend_payment_amount,
+            market_object_code_of_dividends,
+            first_leg,
+            second_leg,
+            rate_multiplier,
+            period_cap,
+            period_floor,
+            market_object_provider,
+        } = terms;
+
+        TermGroups {
+            principal: PrincipalTerms {
+                contract_id,
+                contract_label,
+                contract_type,
+                contract_role,
+                settlement_currency,
+                status_date,
+                market_object_code,
+                contract_performance,
+                initial_exchange_date,
+                maturity_date,
+                amortization_date,
+                notional_principal,
+                premium_discount_at_ied,
+                next_principal_redemption_payment,
+                credit_limit,
+                disbursements,
+                quantity,
+                currency,
+                currency2,
+                settlement_policy,
+                next_dividend_payment_amount,
+                market_object_code_of_dividends,
+                market_object_provider,
+            },
+            rate: RateTerms {
+                day_count_convention,
+                accrued_interest,
+                capitalization_end_date,
+                interest_calculation_base,
+                interest_calculation_base_amount,
+                nominal_interest_rate,
+                nominal_interest_rate2,
+                interest_scaling_multiplier,
+                fee_accrued,
+                fee_basis,
+                fee_rate,
+                scaling_effect,
+                scaling_index_at_status_date,
+                scaling_index_at_contract_deal_date,
+                market_object_code_of_scaling_index,
+                notional_scaling_multiplier,
+                rate_spread,
+                market_object_code_of_rate_reset,
+                penalty_rate,
+                penalty_type,
+                grace_period,
+                delinquency_rate,
+                cycle_point_of_rate_reset,
+                fixing_period,
+                interest_timing,
+                time_granularity,
+                life_cap,
+                life_floor,
+                rate_multiplier,
+                period_cap,
+                period_floor,
+            },
+            schedule: ScheduleTerms {
+                schedule_config,
+                cycle_of_fee,
+                cycle_anchor_date_of_fee,
+                cycle_anchor_date_of_interest_payment,
+                cycle_of_interest_payment,
+                cycle_anchor_date_of_interest_calculation_base,
+                cycle_of_interest_calculation_base,
+                cycle_anchor_date_of_principal_redemption,
+                cycle_of_principal_redemption,
+                cycle_anchor_date_of_scaling_index,
+                cycle_of_scaling_index,
+                cycle_anchor_date_of_rate_reset,
+                cycle_of_rate_reset,
+                skipped_dates,
+                cycle_anchor_date_of_dividend_payment,
+                cycle_of_dividend_payment,
+            },
+            optionality: OptionalityTerms {
+                exercise_date,
+                purchase_date,
+                price_at_purchase_date,
+                termination_date,
+                price_at_termination_date,
+                prepayment_effect,
+                option_exercise_type,
+                option_exercise_start_date,
+                option_exercise_end_date,
+                option_exercise_dates,
+                option_type,
+                option_strike1,
+                market_object_code_of_underlying,
+                covered_contract,
+                coverage_of_credit_enhancement,
+                guaranteed_exposure,
+                x_day_notice,
+                first_leg,
+                second_leg,
+            },
+        }
+    }
+}
+
+impl From<TermGroups> for ContractTerms {
+    fn from(groups: TermGroups) -> Self {
+        let TermGroups {
+            principal:
+                PrincipalTerms {
+                    contract_id,
+                    contract_label,
+                    contract_type,
+                    contract_role,
+                    settlement_currency,
+                    status_date,
+                    market_object_code,
+                    contract_performance,
+                    initial_exchange_date,
+                    maturity_date,
+                    amortization_date,
+                    notional_principal,
+                    premium_discount_at_ied,
+                    next_principal_redemption_payment,
+                    credit_limit,
+                    disbursements,
+                    quantity,
+                    currency,
+                    currency2,
+                    settlement_policy,
+                    next_dividend_payment_amount,
+                    market_object_code_of_dividends,
+                    market_object_provider,
+                },
+            rate:
+                RateTerms {
+                    day_count_convention,
+                    accrued_interest,
+                    capitalization_end_date,
+                    interest_calculation_base,
+                    interest_calculation_base_amount,
+                    nominal_interest_rate,
+                    nominal_interest_rate2,
+                    interest_scaling_multiplier,
+                    fee_accrued,
+                    fee_basis,
+                    fee_rate,
+                    scaling_effect,
+                    scaling_index_at_status_date,
+                    scaling_index_at_contract_deal_date,
+                    market_object_code_of_scaling_index,
+                    notional_scaling_multiplier,
+                    rate_spread,
+                    market_object_code_of_rate_reset,
+                    penalty_rate,
+                    penalty_type,
+                    grace_period,
+                    delinquency_rate,
+                    cycle_point_of_rate_reset,
+                    fixing_period,
+                    interest_timing,
+                    time_granularity,
+                    life_cap,
+                    life_floor,
+                    rate_multiplier,
+                    period_cap,
+                    period_floor,
+                },
+            schedule:
+                ScheduleTerms {
+                    schedule_config,
+                    cycle_of_fee,
+                    cycle_anchor_date_of_fee,
+                    cycle_anchor_date_of_interest_payment,
+                    cycle_of_interest_payment,
+                    cycle_anchor_date_of_interest_calculation_base,
+                    cycle_of_interest_calculation_base,
+                    cycle_anchor_date_of_principal_redemption,
+                    cycle_of_principal_redemption,
+                    cycle_anchor_date_of_scaling_index,
+                    cycle_of_scaling_index,
+                    cycle_anchor_date_of_rate_reset,
+                    cycle_of_rate_reset,
+                    skipped_dates,
+                    cycle_anchor_date_of_dividend_payment,
+                    cycle_of_dividend_payment,
+                },
+            optionality:
+                OptionalityTerms {
+                    exercise_date,
+                    purchase_date,
+                    price_at_purchase_date,
+                    termination_date,
+                    price_at_termination_date,
+                    prepayment_effect,
+                    option_exercise_type,
+                    option_exercise_start_date,
+                    option_exercise_end_date,
+                    option_exercise_dates,
+                    option_type,
+                    option_strike1,
+                    market_object_code_of_underlying,
+                    covered_contract,
+                    coverage_of_credit_enhancement,
+                    guaranteed_exposure,
+                    x_day_notice,
+                    first_leg,
+                    second_leg,
+                },
+        } = groups;
+
+        ContractTerms {
+            contract_id,
+            contract_type,
+            contract_role,
+            settlement_currency,
+            initial_exchange_date,
+            day_count_convention,
+            schedule_config,
+            status_date,
+            market_object_code,
+            contract_performance,
+            cycle_of_fee,
+            cycle_anchor_date_of_fee,
+            fee_accrued,
+            fee_basis,
+            fee_rate,
+            cycle_anchor_date_of_interest_payment,
+            cycle_of_interest_payment,
+            accrued_interest,
+            capitalization_end_date,
+            cycle_anchor_date_of_interest_calculation_base,
+            cycle_of_interest_calculation_base,
+            interest_calculation_base,
+            interest_calculation_base_amount,
+            nominal_interest_rate,
+            nominal_interest_rate2,
+            interest_scaling_multiplier,
+            maturity_date,
+            amortization_date,
+            exercise_date,
+            notional_principal,
+            premium_discount_at_ied,
+            cycle_anchor_date_of_principal_redemption,
+            cycle_of_principal_redemption,
+            next_principal_redemption_payment,
+            credit_limit,
+            disbursements,
+            purchase_date,
+            price_at_purchase_date,
+            termination_date,
+            price_at_termination_date,
+            quantity,
+            currency,
+            currency2,
+            scaling_effect,
+            scaling_index_at_status_date,
+            cycle_anchor_date_of_scaling_index,
+            cycle_of_scaling_index,
+            scaling_index_at_contract_deal_date,
+            market_object_code_of_scaling_index,
+            notional_scaling_multiplier,
+            cycle_anchor_date_of_rate_reset,
+            cycle_of_rate_reset,
+            rate_spread,
+            market_object_code_of_rate_reset,
+            penalty_rate,
+            penalty_type,
+            prepayment_effect,
+            option_exercise_type,
+            option_exercise_start_date,
+            option_exercise_end_date,
+            option_exercise_dates,
+            contract_label,
+            grace_period,
+            delinquency_rate,
+            cycle_point_of_rate_reset,
+            fixing_period,
+            interest_timing,
+            skipped_dates,
+            time_granularity,
+            settlement_policy,
+            option_type,
+            option_strike1,
+            market_object_code_of_underlying,
+            covered_contract,
+            coverage_of_credit_enhancement,
+            guaranteed_exposure,
+            x_day_notice,
+            life_cap,
+            life_floor,
+            cycle_anchor_date_of_dividend_payment,
+            cycle_of_dividend_payment,
+            next_dividend_payment_amount,
+            market_object_code_of_dividends,
+            first_leg,
+            second_leg,
+            rate_multiplier,
+            period_cap,
+            period_floor,
+            market_object_provider,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(n: i64) -> Cycle {
+        Cycle { n, p: Period::P_M, stub: Stub::ShortStub, include_end_day: false }
+    }
+
+    #[test]
+    fn groups_reassemble_the_terms() {
+        let terms = ContractTerms {
+            contract_id: ContractId::from("grouped"),
+            contract_label: Some("Grouped LAM".into()),
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(100),
+            maturity_date: Some(1_000),
+            notional_principal: Some(5_000),
+            next_principal_redemption_payment: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_360),
+            nominal_interest_rate: Some(250),
+            rate_spread: Some(15),
+            cycle_anchor_date_of_interest_payment: Some(200),
+            cycle_of_interest_payment: Some(cycle(3)),
+            cycle_of_rate_reset: Some(cycle(6)),
+            prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+            termination_date: Some(900),
+            ..Default::default()
+        };
+
+        let groups = TermGroups::from(terms.clone());
+        let mut read = TermGroups::default();
+        for group in TermGroup::ALL {
+            read.read(group, &groups.to_bytes(group).unwrap()).unwrap();
+        }
+
+        assert_eq!(ContractTerms::from(read), terms);
+    }
+
+    #[test]
+    fn unread_groups_stay_default() {
+        let terms = ContractTerms {
+            notional_principal: Some(5_000),
+            nominal_interest_rate: Some(250),
+            cycle_of_interest_payment: Some(cycle(3)),
+            prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+            ..Default::default()
+        };
+        let groups = TermGroups::from(terms);
+
+        let mut read = TermGroups::default();
+        for &group in TermGroup::read_by(EventType::IP) {
+            read.read(group, &groups.to_bytes(group).unwrap()).unwrap();
+        }
+        let partial = ContractTerms::from(read);
+
+        assert_eq!(partial.notional_principal, Some(5_000));
+        assert_eq!(partial.nominal_interest_rate, Some(250));
+        assert_eq!(partial.cycle_of_interest_payment, None);
+        assert_eq!(partial.prepayment_effect, None);
+    }
+}
@@ -0,0 +1,2842 @@
+use super::types::*;
+use super::Error;
+use super::Result;
+use super::dispatch::{self, ContractLogic};
+use crate::math;
+
+pub struct TransitionEngine;
+
+/// What a payoff paid for, added to the totals of the state once it is settled
+#[derive(Debug, Default)]
+struct Paid {
+    interest: SignedUnits,
+    principal: Units,
+    fees: Units,
+    penalties: Units,
+}
+
+impl TransitionEngine {
+    /// Main entry point for processing an ACTUS event.
+    /// `state` is only updated when the event succeeds.
+    /// A negative payoff flows against the usual direction of the event.
+    pub fn process_event(
+        event: EventType,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms
+    ) -> Result<Option<SignedUnits>> {
+        Self::process_event_with_factor(event, timestamp, state, terms, None)
+    }
+
+    /// Like [`process_event`](Self::process_event), accruing interest from the status
+    /// date to `timestamp` over `year_fraction` when given instead of computing it
+    pub fn process_event_with_factor(
+        event: EventType,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        year_fraction: Option<u64>,
+    ) -> Result<Option<SignedUnits>> {
+        let penalty = Self::penalty_due(event, timestamp, state, terms)?;
+        let mut next = state.clone();
+        let (payoff, mut paid) =
+            Self::apply_event(event, timestamp, &mut next, terms, year_fraction)?;
+        let payoff =
+            Self::settle_overdue(event, timestamp, &mut next, terms, payoff, penalty, &mut paid)?;
+        Self::add_to_totals(&mut next, &paid)?;
+        *state = next;
+        Ok(payoff)
+    }
+
+    /// Processes a scheduled payment of the debtor that was not made. The state moves
+    /// on as if it had been paid and its payoff is added to the overdue amount, which
+    /// the debtor's next payment settles along with the delinquency charge. Returns the
+    /// amount missed.
+    pub fn miss_payment(
+        event: EventType,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) -> Result<Units> {
+        if event.paid_by_creditor() {
+            return Err(Error::TransitionError("Only payments of the debtor can be missed".into()));
+        }
+
+        let mut next = state.clone();
+        let (payoff, paid) = Self::apply_event(event, timestamp, &mut next, terms, None)?;
+        let missed = payoff
+            .and_then(|payoff| Units::try_from(payoff).ok())
+            .filter(|payoff| *payoff > 0)
+            .ok_or(Error::TransitionError("Nothing is due from the debtor".into()))?;
+
+        let overflow = || Error::MathError("Overdue amount overflow".into());
+        next.overdue_amount = next.overdue_amount.checked_add(missed).ok_or_else(overflow)?;
+        next.overdue_interest = Units::try_from(paid.interest)
+            .ok()
+            .and_then(|interest| next.overdue_interest.checked_add(interest))
+            .ok_or_else(overflow)?;
+        next.overdue_fees = next.overdue_fees.checked_add(paid.fees).ok_or_else(overflow)?;
+        next.overdue_penalties =
+            next.overdue_penalties.checked_add(paid.penalties).ok_or_else(overflow)?;
+        next.overdue_since.get_or_insert(timestamp);
+        Self::update_performance(&mut next, terms, timestamp);
+
+        *state = next;
+        Ok(missed)
+    }
+
+    /// The charge on the overdue amount if it were settled at `timestamp`: nothing
+    /// within the grace period, else the delinquency rate from the due date of the
+    /// earliest missed payment
+    pub fn delinquency_charge(
+        state: &ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+    ) -> Result<Units> {
+        match state.overdue_since {
+            Some(since) => Self::late_charge(state.overdue_amount, since, terms, timestamp),
+            None => Ok(0),
+        }
+    }
+
+    /// The delinquency charge on `amount` due at `due_date` if it were settled at
+    /// `timestamp`, in the unit of `amount`
+    pub fn late_charge(
+        amount: Units,
+        due_date: u64,
+        terms: &ContractTerms,
+        timestamp: u64,
+    ) -> Result<Units> {
+        let Some(rate) = terms.delinquency_rate else {
+            return Ok(0);
+        };
+        if timestamp <= grace_end(due_date, terms) {
+            return Ok(0);
+        }
+
+        let dcc = terms.day_count_convention.unwrap_or(DayCountConvention::DCC_A_365);
+        let time_fraction =
+            terms.granularity().year_fraction(dcc as u8, due_date, timestamp, terms.maturity_date);
+
+        amount
+            .checked_mul(rate as Units)
+            .and_then(|v| v.checked_mul(time_fraction as Units))
+            .map(|v| v / (math::BASIS_POINT_FACTOR as Units).pow(2))
+            .ok_or(Error::MathError("Delinquency charge overflow".into()))
+    }
+
+    /// Keeps a contract whose payment due at `due_date` was committed but left unpaid
+    /// overdue from then, as of `timestamp`: delayed within the grace period, then
+    /// delinquent. The contract tracks the amount itself, so the overdue amount is not
+    /// touched and the debtor's next payment does not settle it.
+    pub fn record_shortfall(
+        due_date: u64,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) {
+        let since = state.overdue_since.map_or(due_date, |since| since.min(due_date));
+        state.overdue_since = Some(since);
+        Self::update_performance(state, terms, timestamp);
+    }
+
+    /// Cures a contract whose shortfalls were settled at `timestamp`, unless a missed
+    /// payment is still overdue. `unpaid_since` is the due date of the earliest shortfall
+    /// left, which keeps it overdue from then.
+    pub fn settle_shortfall(
+        unpaid_since: Option<u64>,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) {
+        if state.overdue_amount > 0 {
+            return;
+        }
+
+        state.overdue_since = unpaid_since;
+        if unpaid_since.is_some() {
+            Self::update_performance(state, terms, timestamp);
+            return;
+        }
+
+        state.penalty_assessed = false;
+        if state.contract_performance != ContractPerformance::DF {
+            state.contract_performance = ContractPerformance::PF;
+        }
+    }
+
+    /// The late-payment penalty `event` at `timestamp` would include: that of the PY,
+    /// or of a payment of the debtor settling the overdue amount, past the grace period
+    /// of the earliest missed payment. It is charged once, by whichever comes first.
+    pub fn penalty_due(
+        event: EventType,
+        timestamp: u64,
+        state: &ContractState,
+        terms: &ContractTerms,
+    ) -> Result<Units> {
+        let Some(since) = state.overdue_since else {
+            return Ok(0);
+        };
+        let within_grace = timestamp <= grace_end(since, terms);
+        if event.paid_by_creditor() || state.penalty_assessed || within_grace {
+            return Ok(0);
+        }
+
+        let penalty_rate = || {
+            terms
+                .penalty_rate
+                .ok_or(Error::MissingTerm { field: "penalty_rate" })
+        };
+        // a rate on the notional, from the due date of the earliest missed payment
+        let on_notional = |rate: u64| {
+            let dcc = terms.day_count_convention.unwrap_or(DayCountConvention::DCC_A_365);
+            let time_fraction = terms.granularity().year_fraction(
+                dcc as u8,
+                since,
+                timestamp,
+                terms.maturity_date,
+            );
+
+            state
+                .notional_principal
+                .checked_mul(Units::from(rate))
+                .and_then(|v| v.checked_mul(time_fraction as Units))
+                .map(|v| v / (math::BASIS_POINT_FACTOR as Units).pow(2))
+                .ok_or(Error::MathError("Penalty overflow".into()))
+        };
+
+        match terms.penalty_type {
+            None | Some(PenaltyType::PYTP_O) => Ok(0),
+            Some(PenaltyType::PYTP_A) => penalty_rate().map(Units::from),
+            Some(PenaltyType::PYTP_N) => on_notional(penalty_rate()?),
+            // as in ACTUS, the interest the nominal rate earns above the reference rate
+            // last observed, and nothing when it is below
+            Some(PenaltyType::PYTP_I) => {
+                let reference = state.reference_rate.ok_or(Error::ValidationError(
+                    "No reference rate observed for the penalty".into(),
+                ))?;
+                let differential = state.nominal_interest_rate.saturating_sub(reference);
+                on_notional(u64::try_from(differential).unwrap_or(0))
+            }
+        }
+    }
+
+    /// Fails unless the option of `terms` can be exercised at `timestamp`, by its
+    /// [`OptionExerciseType`]
+    pub fn check_exercise(terms: &ContractTerms, timestamp: u64) -> Result<()> {
+        let exercise_type = terms
+            .option_exercise_type
+            .ok_or(Error::ValidationError("XD events need an option exercise type".into()))?;
+        let tolerance = terms.granularity().from_seconds(EXERCISE_TOLERANCE);
+        let near = |date: Timestamp| date.abs_diff(timestamp) <= tolerance;
+
+        if terms.option_exercise_end_date.is_some_and(|expiry| timestamp > expiry) {
+            return Err(Error::ValidationError("The option has expired".into()));
+        }
+
+        let exercisable = match exercise_type {
+            OptionExerciseType::XT_E => {
+                let expiry = terms
+                    .option_exercise_end_date
+                    .ok_or(Error::ValidationError("European options need an expiry".into()))?;
+                near(expiry)
+            }
+            OptionExerciseType::XT_A => {
+                let expiry = terms
+                    .option_exercise_end_date
+                    .ok_or(Error::ValidationError("American options need an expiry".into()))?;
+                let start = terms
+                    .option_exercise_start_date
+                    .or(terms.initial_exchange_date)
+                    .unwrap_or(terms.status_date);
+                (start..=expiry).contains(&timestamp)
+            }
+            OptionExerciseType::XT_B => {
+                let dates = terms.option_exercise_dates.as_deref().unwrap_or_default();
+                if dates.is_empty() {
+                    return Err(Error::ValidationError(
+                        "Bermudan options need exercise dates".into(),
+                    ));
+                }
+                dates.iter().any(|date| near(*date))
+            }
+        };
+
+        if !exercisable {
+            return Err(Error::ValidationError(format!(
+                "The option cannot be exercised at {timestamp}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Exercises an option at `timestamp` at `price`, that of a unit of its underlying,
+    /// fixing what the STD settles: the notional times what the price exceeds the strike
+    /// by for a call, or falls short of it by for a put. An XD is only processed here.
+    pub fn exercise(
+        timestamp: u64,
+        price: Units,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) -> Result<()> {
+        Self::check_unexercised(ContractType::OPTNS, state, terms)?;
+
+        let strike = terms.option_strike1.ok_or(Error::MissingTerm { field: "option_strike1" })?;
+        let gain = match terms.option_type.ok_or(Error::MissingTerm { field: "option_type" })? {
+            OptionType::C => price.saturating_sub(strike),
+            OptionType::P => strike.saturating_sub(price),
+        };
+        let amount = state
+            .notional_principal
+            .checked_mul(gain)
+            .ok_or(Error::MathError("Exercise amount overflow".into()))?;
+
+        Self::process_exercise(amount, timestamp, state, terms)
+    }
+
+    /// Exercises a guarantee at `timestamp` once `covered`, the state of the contract it
+    /// covers, is in default, fixing what the STD settles: the covered notional, and the
+    /// interest accrued on it under [`GuaranteedExposure::CEGE_NI`], times the coverage,
+    /// at most the notional of the guarantee. An XD is only processed here.
+    pub fn exercise_guarantee(
+        timestamp: u64,
+        covered: &ContractState,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) -> Result<()> {
+        Self::check_unexercised(ContractType::CEG, state, terms)?;
+        if covered.contract_performance != ContractPerformance::DF {
+            return Err(Error::ValidationError("The covered contract is not in default".into()));
+        }
+
+        let interest = match terms.guaranteed_exposure {
+            Some(GuaranteedExposure::CEGE_NI) => {
+                Units::try_from(covered.accrued_interest).unwrap_or(0)
+            }
+            _ => 0,
+        };
+        let coverage = terms.coverage_of_credit_enhancement.unwrap_or(math::BASIS_POINT_FACTOR);
+        let amount = covered
+            .notional_principal
+            .checked_add(interest)
+            .and_then(|exposure| exposure.checked_mul(Units::from(coverage)))
+            .map(|v| v / Units::from(math::BASIS_POINT_FACTOR))
+            .ok_or(Error::MathError("Exercise amount overflow".into()))?;
+
+        Self::process_exercise(amount.min(state.notional_principal), timestamp, state, terms)
+    }
+
+    /// Fails unless `terms` are of a `contract_type` that is exercised, in its active phase
+    /// and not exercised yet
+    fn check_unexercised(
+        contract_type: ContractType,
+        state: &ContractState,
+        terms: &ContractTerms,
+    ) -> Result<()> {
+        if terms.contract_type != contract_type {
+            return Err(Error::EventNotApplicable {
+                event: EventType::XD,
+                contract_type: terms.contract_type,
+            });
+        }
+        if state.phase != ContractPhase::Active {
+            return Err(Error::EventNotAllowed { event: EventType::XD, phase: state.phase });
+        }
+        if state.exercise_amount.is_some() {
+            return Err(Error::TransitionError("The contract was already exercised".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Processes the XD at `timestamp` that fixed `amount` for the STD to settle
+    fn process_exercise(
+        amount: Units,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) -> Result<()> {
+        let mut next = state.clone();
+        next.exercise_amount = Some(amount);
+        Self::process_event(EventType::XD, timestamp, &mut next, terms)?;
+        *state = next;
+        Ok(())
+    }
+
+    /// The principal the MD at `timestamp` would settle beyond the regular redemption
+    /// of an amortizing contract: what rounding or a missed PR left of the notional.
+    /// The MD repays it along with the rest, so nothing is left outstanding.
+    pub fn residual_principal(
+        event: EventType,
+        timestamp: u64,
+        state: &ContractState,
+        terms: &ContractTerms,
+        year_fraction: Option<u64>,
+    ) -> Result<Units> {
+        if event != EventType::MD || terms.maturity_date != Some(timestamp) {
+            return Ok(0);
+        }
+
+        let redemption = match terms.contract_type {
+            ContractType::LAM => state.next_principal_redemption_payment,
+            // the installment repays what it does not spend on the interest accrued
+            ContractType::ANN => {
+                let mut accrued = state.clone();
+                if timestamp > state.status_date {
+                    Self::update_accrued_interest(&mut accrued, terms, timestamp, year_fraction)?;
+                }
+                let interest = Units::try_from(accrued.accrued_interest).unwrap_or(0);
+                state.next_principal_redemption_payment.saturating_sub(interest)
+            }
+            _ => return Ok(0),
+        };
+
+        Ok(state.notional_principal.saturating_sub(redemption))
+    }
+
+    fn apply_event(
+        event: EventType,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        year_fraction: Option<u64>,
+    ) -> Result<(Option<SignedUnits>, Paid)> {
+        // Nothing is paid before the IED, and only AD events are taken once it is over
+        if !state.phase.allows(event) {
+            return Err(Error::EventNotAllowed { event, phase: state.phase });
+        }
+
+        // Write-offs and amendments only go through their own entrypoints
+        if event.is_recorded_only() {
+            return Err(Error::EventNotApplicable { event, contract_type: terms.contract_type });
+        }
+
+        // Index fixings need a scaling effect, if only to track the index
+        if event == EventType::SC && terms.scaling_effect.is_none() {
+            return Err(Error::ValidationError("SC events need a scaling effect".into()));
+        }
+
+        // Validate event timing
+        if timestamp < state.status_date {
+            return Err(Error::EventBeforeStatusDate {
+                event_time: timestamp,
+                status_date: state.status_date,
+            });
+        }
+
+        // a guarantee is exercised whenever the contract it covers defaults
+        if event == EventType::XD && terms.contract_type != ContractType::CEG {
+            Self::check_exercise(terms, timestamp)?;
+        }
+
+        // Update accrued interest if time has passed
+        if timestamp > state.status_date {
+            Self::update_accrued_interest(state, terms, timestamp, year_fraction)?;
+            Self::update_fee_accrued(state, terms, timestamp, year_fraction)?;
+        }
+        Self::update_performance(state, terms, timestamp);
+
+        // A credit event puts the contract in default, freezing further accrual
+        if event == EventType::CE {
+            state.contract_performance = ContractPerformance::DF;
+            state.status_date = timestamp;
+            return Ok((None, Paid::default()));
+        }
+
+        // Look up the table of the contract type (PAM, LAM, NAM, ANN, etc.), then read
+        // the payoff off the accrued state before applying the event
+        let logic = ContractLogic::of(terms.contract_type)
+            .ok_or(Error::EventNotApplicable { event, contract_type: terms.contract_type })?;
+        let result = logic.payoff(event, state, terms, timestamp)?;
+        let accrued = state.clone();
+        logic.state_transition(event, state, terms, timestamp)?;
+        let paid = Self::paid_by(event, result, &accrued, state, terms)?;
+
+        // A new interest period starts with the rate of a reset pending under `CP_B`
+        if matches!(event, EventType::IP | EventType::IPCI) {
+            if let Some(rate) = state.reset_rate.take() {
+                state.nominal_interest_rate = rate;
+            }
+        }
+
+        match event {
+            EventType::IED | EventType::PRD if terms.opening() == (event, Some(timestamp)) => {
+                state.phase = ContractPhase::Active;
+            }
+            EventType::MD if terms.maturity_date == Some(timestamp) => {
+                state.phase = ContractPhase::Matured;
+            }
+            // a stock or commodity is over once sold
+            EventType::TD if terms.closing() == (event, Some(timestamp)) => {
+                state.phase = ContractPhase::Terminated;
+            }
+            // an exercised option or guarantee is over once settled
+            EventType::STD
+                if matches!(terms.contract_type, ContractType::OPTNS | ContractType::CEG) =>
+            {
+                state.phase = ContractPhase::Terminated;
+            }
+            // a prepayment leaving interest accrued still has the MD to settle it
+            EventType::PP
+                if state.notional_principal == 0
+                    && state.accrued_interest == 0
+                    && state.fee_accrued == 0 =>
+            {
+                state.phase = ContractPhase::Terminated;
+            }
+            _ => {}
+        }
+
+        // Update status date
+        state.status_date = timestamp;
+
+        Ok((result, paid))
+    }
+
+    /// What the payoff of `event` paid for, from the state it was read off and the state
+    /// the event left. An IP pays interest besides the principal an installment repays,
+    /// and the MD settles the interest and fees outstanding besides the principal.
+    fn paid_by(
+        event: EventType,
+        payoff: Option<SignedUnits>,
+        accrued: &ContractState,
+        after: &ContractState,
+        terms: &ContractTerms,
+    ) -> Result<Paid> {
+        let Some(payoff) = payoff else {
+            return Ok(Paid::default());
+        };
+        let overflow = || Error::MathError("Payment split overflow".into());
+        // the debtor pays these, so their payoffs are never negative
+        let units = |amount: SignedUnits| Units::try_from(amount).map_err(|_| overflow());
+
+        Ok(match event {
+            // the interest paid in advance nets against the notional paid out
+            EventType::IED => Paid { interest: -after.accrued_interest, ..Default::default() },
+            // an installment pays interest besides the principal it repays
+            EventType::IP | EventType::PR
+                if event == EventType::IP || terms.contract_type == ContractType::NAM =>
+            {
+                let principal =
+                    accrued.notional_principal.saturating_sub(after.notional_principal);
+                let interest = SignedUnits::try_from(principal)
+                    .ok()
+                    .and_then(|principal| payoff.checked_sub(principal))
+                    .ok_or_else(overflow)?;
+                Paid { interest, principal, ..Default::default() }
+            }
+            EventType::PR | EventType::PP => {
+                Paid { principal: units(payoff)?, ..Default::default() }
+            }
+            EventType::FP => Paid { fees: units(payoff)?, ..Default::default() },
+            EventType::PY => Paid { penalties: units(payoff)?, ..Default::default() },
+            EventType::MD => {
+                let interest = dispatch::scaled_interest(accrued, terms)?;
+                let fees = accrued.fee_accrued;
+                let principal = SignedUnits::try_from(fees)
+                    .ok()
+                    .and_then(|fees| payoff.checked_sub(fees))
+                    .and_then(|rest| rest.checked_sub(interest))
+                    .ok_or_else(overflow)?;
+                Paid { interest, principal: units(principal)?, fees, penalties: 0 }
+            }
+            _ => Paid::default(),
+        })
+    }
+
+    /// Adds what a payment paid for to the totals of `state`
+    fn add_to_totals(state: &mut ContractState, paid: &Paid) -> Result<()> {
+        let overflow = || Error::MathError("Payment total overflow".into());
+        state.total_interest_paid =
+            state.total_interest_paid.checked_add(paid.interest).ok_or_else(overflow)?;
+        state.total_principal_repaid =
+            state.total_principal_repaid.checked_add(paid.principal).ok_or_else(overflow)?;
+        state.total_fees_paid =
+            state.total_fees_paid.checked_add(paid.fees).ok_or_else(overflow)?;
+        state.total_penalties_paid =
+            state.total_penalties_paid.checked_add(paid.penalties).ok_or_else(overflow)?;
+        Ok(())
+    }
+
+    /// The payoff of `event` in a currency of `decimals` decimals, folding in the
+    /// [`payoff_residual`](ContractState::payoff_residual) left by earlier payoffs. The
+    /// sum is truncated to the currency's smallest unit and what that drops is carried
+    /// to the next payoff, except at the MD, which rounds away from zero to flush it.
+    pub fn settle_payoff(
+        event: EventType,
+        payoff: SignedUnits,
+        state: &mut ContractState,
+        decimals: u8,
+    ) -> Result<SignedUnits> {
+        let overflow = || Error::MathError("Payoff exceeds the currency's range".into());
+        let Some(shift) = math::AMOUNT_DECIMALS.checked_sub(decimals) else {
+            let magnitude = payoff.unsigned_abs();
+            let magnitude = math::rescale_decimals(magnitude, math::AMOUNT_DECIMALS, decimals)
+                .and_then(|magnitude| SignedUnits::try_from(magnitude).ok())
+                .ok_or_else(overflow)?;
+            return Ok(if payoff < 0 { -magnitude } else { magnitude });
+        };
+
+        // oriented as owed to the creditor side, so residuals of either direction net
+        let to_creditor = if event.paid_by_creditor() { -payoff } else { payoff };
+        let exact = to_creditor.checked_add(state.payoff_residual).ok_or_else(overflow)?;
+        let unit = SignedUnits::checked_pow(10, shift.into()).ok_or_else(overflow)?;
+
+        let mut settled = exact / unit;
+        if event == EventType::MD && exact % unit != 0 {
+            settled += exact.signum();
+        }
+        state.payoff_residual = if event == EventType::MD { 0 } else { exact % unit };
+
+        Ok(if event.paid_by_creditor() { -settled } else { settled })
+    }
+
+    /// Moves a swap of two contracts to `timestamp` as its legs settle: it must be in a
+    /// phase taking an STD, and goes from its pending phase to the active one, never out
+    /// of one that is over
+    pub fn settle_swap(state: &mut ContractState, timestamp: u64) -> Result<()> {
+        if !state.phase.allows(EventType::STD) {
+            return Err(Error::EventNotAllowed { event: EventType::STD, phase: state.phase });
+        }
+
+        if timestamp < state.status_date {
+            return Err(Error::EventBeforeStatusDate {
+                event_time: timestamp,
+                status_date: state.status_date,
+            });
+        }
+
+        state.status_date = timestamp;
+        if state.phase == ContractPhase::PreInitialExchange {
+            state.phase = ContractPhase::Active;
+        }
+
+        Ok(())
+    }
+
+    /// Writes off a defaulted contract, returning the loss: the remaining notional
+    /// plus the accrued interest frozen at default. The contract is closed afterwards.
+    pub fn write_off(state: &mut ContractState, timestamp: u64) -> Result<Units> {
+        if state.phase.is_over() {
+            return Err(Error::AlreadyTerminated);
+        }
+
+        if state.contract_performance != ContractPerformance::DF {
+            return Err(Error::TransitionError("Only defaulted contracts can be written off".into()));
+        }
+
+        if timestamp < state.status_date {
+            return Err(Error::EventBeforeStatusDate {
+                event_time: timestamp,
+                status_date: state.status_date,
+            });
+        }
+
+        let loss = state
+            .notional_principal
+            .checked_add_signed(state.accrued_interest)
+            .and_then(|v| v.checked_add(state.overdue_amount))
+            .ok_or(Error::MathError("Write-off amount overflow".into()))?;
+
+        state.notional_principal = 0;
+        state.overdue_amount = 0;
+        state.overdue_interest = 0;
+        state.overdue_fees = 0;
+        state.overdue_penalties = 0;
+        state.overdue_since = None;
+        state.penalty_assessed = false;
+        state.accrued_interest = 0;
+        state.nominal_interest_rate = 0;
+        state.fee_accrued = 0;
+        state.next_principal_redemption_payment = 0;
+        state.status_date = timestamp;
+        state.phase = ContractPhase::WrittenOff;
+
+        Ok(loss)
+    }
+
+    /// Extends the maturity of a contract that has not matured yet, optionally
+    /// resetting the interest rate from the status date onwards
+    pub fn roll_over(
+        state: &mut ContractState,
+        terms: &mut ContractTerms,
+        new_maturity: Timestamp,
+        new_rate: Option<Rate>,
+    ) -> Result<()> {
+        if state.phase.is_over() {
+            return Err(Error::AlreadyTerminated);
+        }
+
+        if state.contract_performance == ContractPerformance::DF {
+            return Err(Error::TransitionError("Defaulted contracts cannot roll over".into()));
+        }
+
+        let maturity = terms
+            .maturity_date
+            .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+
+        if state.status_date >= maturity {
+            return Err(Error::AlreadyTerminated);
+        }
+
+        if new_maturity <= maturity {
+            return Err(Error::ValidationError("New maturity must extend the current one".into()));
+        }
+
+        terms.maturity_date = Some(new_maturity);
+        state.time_of_maturity = Some(new_maturity);
+
+        if let Some(rate) = new_rate {
+            terms.nominal_interest_rate = Some(rate);
+            if state.notional_principal > 0 {
+                state.nominal_interest_rate = rate;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls a CLM at `timestamp`, setting its maturity the `x_day_notice` after it, or
+    /// then if the terms give no notice. Its interest is then paid at each IP and with the
+    /// principal at the MD, as a PAM's.
+    pub fn call(
+        state: &mut ContractState,
+        terms: &mut ContractTerms,
+        timestamp: Timestamp,
+    ) -> Result<Timestamp> {
+        if terms.contract_type != ContractType::CLM {
+            return Err(Error::ValidationError("Only CLM contracts can be called".into()));
+        }
+
+        if state.phase.is_over() {
+            return Err(Error::AlreadyTerminated);
+        }
+
+        if terms.maturity_date.is_some() {
+            return Err(Error::ValidationError("The contract was already called".into()));
+        }
+
+        if timestamp < state.status_date {
+            return Err(Error::ValidationError("A call cannot precede the status date".into()));
+        }
+
+        let maturity = timestamp
+            .checked_add(terms.x_day_notice.unwrap_or_default())
+            .ok_or(Error::MathError("Maturity overflow".into()))?;
+        terms.maturity_date = Some(maturity);
+        state.time_of_maturity = Some(maturity);
+
+        Ok(maturity)
+    }
+
+    /// Resets the rate after an RR that observed `reference` for the
+    /// `market_object_code_of_rate_reset`, returning the new rate: the reference times the
+    /// `rate_multiplier` in basis points, truncated toward zero, plus the `rate_spread`,
+    /// changed from the rate before by no more than the `period_cap` up or the
+    /// `period_floor` down and then kept within the `life_floor` and `life_cap`. A cap or
+    /// floor resets its underlying rate unclamped, its life limits being its strikes. The
+    /// reference is kept for penalties. Under `CP_B` a reset that does not start an
+    /// interest period is left pending until the next one.
+    pub fn reset_rate(
+        reference: Rate,
+        starts_period: bool,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+    ) -> Result<Rate> {
+        let one = i128::from(SCALING_FACTOR_ONE);
+        let multiplier = terms.rate_multiplier.map_or(one, i128::from);
+        let mut rate = Rate::try_from(i128::from(reference) * multiplier / one)
+            .ok()
+            .and_then(|rate| rate.checked_add(terms.rate_spread.unwrap_or(0)))
+            .ok_or(Error::MathError("Reset rate overflow".into()))?;
+
+        let previous = state.reset_rate.unwrap_or(state.nominal_interest_rate);
+        if let Some(floor) = terms.period_floor {
+            rate = rate.max(previous.saturating_sub(floor));
+        }
+        if let Some(cap) = terms.period_cap {
+            rate = rate.min(previous.saturating_add(cap));
+        }
+        if terms.contract_type != ContractType::CAPFL {
+            rate = terms.life_floor.map_or(rate, |floor| rate.max(floor));
+            rate = terms.life_cap.map_or(rate, |cap| rate.min(cap));
+        }
+
+        state.reference_rate = Some(reference);
+        match terms.cycle_point_of_rate_reset {
+            Some(CyclePoint::CP_B) if !starts_period => state.reset_rate = Some(rate),
+            _ => state.nominal_interest_rate = rate,
+        }
+
+        Ok(rate)
+    }
+
+    /// Applies `amendment` to a live contract at `timestamp`. Interest up to then accrues
+    /// under the old terms, as an AD accrues it, and the new rate, maturity and skipped
+    /// payment dates apply from then on. `state` and `terms` are only updated when the
+    /// amendment is valid.
+    pub fn amend(
+        state: &mut ContractState,
+        terms: &mut ContractTerms,
+        amendment: &Amendment,
+        timestamp: u64,
+    ) -> Result<()> {
+        if state.phase != ContractPhase::Active {
+            return Err(Error::StateError("Only live contracts can be amended".into()));
+        }
+
+        if state.contract_performance == ContractPerformance::DF {
+            return Err(Error::TransitionError("Defaulted contracts cannot be amended".into()));
+        }
+
+        let maturity = amendment
+            .maturity
+            .or(terms.maturity_date)
+            .ok_or(Error::MissingTerm { field: "maturity_date" })?;
+        if maturity <= timestamp {
+            return Err(Error::ValidationError("Maturity must follow the amendment".into()));
+        }
+
+        let skipped = &amendment.skipped_dates;
+        if skipped.iter().any(|date| *date <= timestamp || *date >= maturity) {
+            return Err(Error::ValidationError(
+                "Skipped dates must fall between the amendment and maturity".into(),
+            ));
+        }
+
+        let mut next = state.clone();
+        Self::process_event(EventType::AD, timestamp, &mut next, terms)?;
+
+        let mut amended = terms.clone();
+        if let Some(maturity) = amendment.maturity {
+            amended.maturity_date = Some(maturity);
+            next.time_of_maturity = Some(maturity);
+        }
+        if let Some(rate) = amendment.rate {
+            amended.nominal_interest_rate = Some(rate);
+            next.nominal_interest_rate = rate;
+        }
+        if !skipped.is_empty() {
+            let dates = amended.skipped_dates.get_or_insert_with(Vec::new);
+            dates.extend(skipped);
+            dates.sort_unstable();
+            dates.dedup();
+        }
+
+        *state = next;
+        *terms = amended;
+        Ok(())
+    }
+
+    /// Draws `amount` on a credit facility as a PI, raising the notional up to the
+    /// credit limit. Interest up to the drawdown accrues on the balance drawn before it.
+    pub fn draw_down(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        amount: Units,
+    ) -> Result<()> {
+        let limit = terms
+            .credit_limit
+            .ok_or(Error::ValidationError("Drawdowns need a credit limit".into()))?;
+        if state.contract_performance == ContractPerformance::DF {
+            return Err(Error::TransitionError("Defaulted facilities cannot be drawn".into()));
+        }
+
+        Self::change_notional(EventType::PI, state, terms, timestamp, |notional| {
+            notional
+                .checked_add(amount)
+                .filter(|drawn| *drawn <= limit)
+                .ok_or(Error::ValidationError(format!(
+                    "Drawdown exceeds the credit limit of {limit}"
+                )))
+        })
+    }
+
+    /// Repays `amount` of the balance drawn on a credit facility as a PP, lowering the
+    /// notional the interest accrues on from `timestamp`
+    pub fn repay(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        amount: Units,
+    ) -> Result<()> {
+        if terms.credit_limit.is_none() {
+            return Err(Error::ValidationError("Repayments need a credit limit".into()));
+        }
+
+        let repaid = state
+            .total_principal_repaid
+            .checked_add(amount)
+            .ok_or(Error::MathError("Payment total overflow".into()))?;
+        Self::change_notional(EventType::PP, state, terms, timestamp, |notional| {
+            notional
+                .checked_sub(amount)
+                .ok_or(Error::ValidationError("Repayment exceeds the drawn balance".into()))
+        })?;
+        state.total_principal_repaid = repaid;
+        Ok(())
+    }
+
+    /// Deposits `amount` in a UMP as a PI, raising the notional the interest accrues on
+    /// from `timestamp`, up to the credit limit if it has one
+    pub fn deposit(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        amount: Units,
+    ) -> Result<()> {
+        if terms.contract_type != ContractType::UMP {
+            return Err(Error::ValidationError("Deposits are made to UMP contracts".into()));
+        }
+
+        Self::change_notional(EventType::PI, state, terms, timestamp, |notional| {
+            notional
+                .checked_add(amount)
+                .filter(|balance| terms.credit_limit.is_none_or(|limit| *balance <= limit))
+                .ok_or(Error::ValidationError("Deposit exceeds the credit limit".into()))
+        })
+    }
+
+    /// Withdraws `amount` of the balance of a UMP as a PP, lowering the notional the
+    /// interest accrues on from `timestamp`. An emptied deposit stays open.
+    pub fn withdraw(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        amount: Units,
+    ) -> Result<()> {
+        if terms.contract_type != ContractType::UMP {
+            return Err(Error::ValidationError("Withdrawals are made from UMP contracts".into()));
+        }
+
+        let withdrawn = state
+            .total_principal_repaid
+            .checked_add(amount)
+            .ok_or(Error::MathError("Payment total overflow".into()))?;
+        Self::change_notional(EventType::PP, state, terms, timestamp, |notional| {
+            notional
+                .checked_sub(amount)
+                .ok_or(Error::ValidationError("Withdrawal exceeds the balance".into()))
+        })?;
+        state.total_principal_repaid = withdrawn;
+        Ok(())
+    }
+
+    /// Accrues up to `timestamp`, then sets the notional to what `change` makes of it
+    fn change_notional(
+        event: EventType,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        change: impl FnOnce(Units) -> Result<Units>,
+    ) -> Result<()> {
+        if !state.phase.allows(event) {
+            return Err(Error::EventNotAllowed { event, phase: state.phase });
+        }
+
+        if timestamp < state.status_date {
+            return Err(Error::EventBeforeStatusDate {
+                event_time: timestamp,
+                status_date: state.status_date,
+            });
+        }
+
+        let mut next = state.clone();
+        if timestamp > next.status_date {
+            Self::update_accrued_interest(&mut next, terms, timestamp, None)?;
+            Self::update_fee_accrued(&mut next, terms, timestamp, None)?;
+        }
+        next.notional_principal = change(next.notional_principal)?;
+        next.status_date = timestamp;
+
+        *state = next;
+        Ok(())
+    }
+
+    /// Returns the next event due after `state.status_date`, derived from the
+    /// [`opening`](ContractTerms::opening), the [`cycles`](Self::cycles) of the terms, and
+    /// the [`closing`](ContractTerms::closing)
+    pub fn next_event(state: &ContractState, terms: &ContractTerms) -> Option<(EventType, Timestamp)> {
+        if state.phase.is_over() {
+            return None;
+        }
+
+        if let (opening, Some(opened)) = terms.opening() {
+            if Self::is_empty(state, terms) && opened >= state.status_date {
+                return Some((opening, opened));
+            }
+        }
+
+        // nothing outstanding once the principal is repaid
+        if Self::is_empty(state, terms) {
+            return None;
+        }
+
+        let sd = state.status_date;
+        let granularity = terms.granularity();
+        let (closing, closed) = terms.closing();
+        let before_maturity = |t: &Timestamp| closed.is_none_or(|md| *t < md);
+
+        // a tranche disbursed on the date of a cyclic event comes first
+        let tranches = terms.disbursements.as_deref().unwrap_or_default();
+        let mut next: Option<(EventType, Timestamp)> = tranches
+            .iter()
+            .find(|(date, _)| *date > sd)
+            .map(|(date, _)| (EventType::PI, *date));
+
+        for (event, anchor, cycle) in Self::cycles(terms) {
+            let date = match (anchor, cycle) {
+                (Some(anchor), Some(cycle)) => {
+                    let mut date = cycle.next_after(anchor, sd, granularity);
+                    // a payment holiday moves on to the following date of the cycle
+                    while let Some(skipped) = date.filter(|date| terms.skips(event, *date)) {
+                        date = cycle.next_after(anchor, skipped, granularity);
+                    }
+                    date
+                }
+                (Some(anchor), None) => {
+                    Some(anchor).filter(|t| *t > sd && !terms.skips(event, *t))
+                }
+                _ => None,
+            };
+
+            if let Some(date) = date.filter(before_maturity) {
+                if next.is_none_or(|(_, t)| date < t) {
+                    next = Some((event, date));
+                }
+            }
+        }
+
+        if let Some(md) = closed.filter(|md| *md > sd) {
+            if next.is_none_or(|(_, t)| md < t) {
+                next = Some((closing, md));
+            }
+        }
+
+        next
+    }
+
+    /// When `event` is next scheduled and not yet processed: its first date after the
+    /// status date, or on it when it follows `last`, the last event processed, on that
+    /// date in the order [`next_event`](Self::next_event) schedules them. `None` for
+    /// events that are never scheduled, such as PP and CE, and once nothing is left.
+    pub fn next_occurrence(
+        event: EventType,
+        state: &ContractState,
+        terms: &ContractTerms,
+        last: Option<(EventType, Timestamp)>,
+    ) -> Option<Timestamp> {
+        if state.phase.is_over() {
+            return None;
+        }
+
+        if let (opening, Some(opened)) = terms.opening() {
+            if Self::is_empty(state, terms) && opened >= state.status_date {
+                return Some(opened).filter(|_| event == opening);
+            }
+        }
+
+        if Self::is_empty(state, terms) {
+            return None;
+        }
+
+        // the position of an event among those of its date: a tranche first, the cycles
+        // in their order, the MD or TD last
+        let cycles = Self::cycles(terms);
+        let (closing, closed) = terms.closing();
+        let position = |event: EventType| match event {
+            EventType::PI => Some(0),
+            _ if event == closing => Some(usize::MAX),
+            _ => cycles.iter().position(|(cyclic, ..)| *cyclic == event).map(|at| at + 1),
+        };
+
+        let sd = state.status_date;
+        let processed = last.filter(|(_, time)| *time == sd).and_then(|(last, _)| position(last));
+        let earliest = match (processed, position(event)) {
+            (Some(processed), Some(position)) if position > processed => sd,
+            _ => sd.checked_add(1)?,
+        };
+        let before_maturity = |t: &Timestamp| closed.is_none_or(|md| *t < md);
+        let granularity = terms.granularity();
+
+        match event {
+            EventType::PI => {
+                let tranches = terms.disbursements.as_deref().unwrap_or_default();
+                tranches.iter().map(|(date, _)| *date).find(|date| *date >= earliest)
+            }
+            _ if event == closing => closed.filter(|md| *md >= earliest),
+            _ => {
+                let (_, anchor, cycle) = cycles.into_iter().find(|(cyclic, ..)| *cyclic == event)?;
+                let date = match (anchor?, cycle) {
+                    (anchor, Some(cycle)) => {
+                        let mut date =
+                            cycle.next_after(anchor, earliest.saturating_sub(1), granularity);
+                        while let Some(skipped) = date.filter(|date| terms.skips(event, *date)) {
+                            date = cycle.next_after(anchor, skipped, granularity);
+                        }
+                        date
+                    }
+                    (anchor, None) => {
+                        Some(anchor).filter(|t| *t >= earliest && !terms.skips(event, *t))
+                    }
+                };
+                date.filter(before_maturity)
+            }
+        }
+    }
+
+    /// Whether nothing is outstanding: before the IED, and once the principal is repaid
+    /// but for a UMP, which stays open for deposits when emptied
+    fn is_empty(state: &ContractState, terms: &ContractTerms) -> bool {
+        let open = terms.contract_type == ContractType::UMP && state.phase == ContractPhase::Active;
+        state.notional_principal == 0 && !open
+    }
+
+    /// The cyclic events of `terms` with the anchor and cycle scheduling them, in the
+    /// ACTUS order of events falling on the same date
+    pub fn cycles(terms: &ContractTerms) -> [(EventType, Option<Timestamp>, Option<&Cycle>); 8] {
+        let amortizing = matches!(
+            terms.contract_type,
+            ContractType::LAM | ContractType::NAM | ContractType::ANN
+        );
+        // only a base fixed at the last fixing needs fixings
+        let fixed_base = matches!(terms.contract_type, ContractType::LAM | ContractType::NAM)
+            && terms.interest_calculation_base == Some(IPCB::IPCB_NTL);
+        // an annuity refixes its installment after each reset
+        let annuity = terms.contract_type == ContractType::ANN;
+        // fixings are scheduled whenever there is a scaling effect, as `SE_OOO` still
+        // tracks the index
+        let scaled = terms.scaling_effect.is_some();
+
+        [
+            (
+                EventType::FP,
+                terms.cycle_anchor_date_of_fee,
+                terms.cycle_of_fee.as_ref(),
+            ),
+            (
+                EventType::IP,
+                terms.cycle_anchor_date_of_interest_payment,
+                terms.cycle_of_interest_payment.as_ref(),
+            ),
+            (
+                EventType::PR,
+                terms.cycle_anchor_date_of_principal_redemption.filter(|_| amortizing),
+                terms.cycle_of_principal_redemption.as_ref(),
+            ),
+            (
+                EventType::RR,
+                terms.cycle_anchor_date_of_rate_reset,
+                terms.cycle_of_rate_reset.as_ref(),
+            ),
+            (
+                EventType::PRF,
+                terms.cycle_anchor_date_of_rate_reset.filter(|_| annuity),
+                terms.cycle_of_rate_reset.as_ref(),
+            ),
+            (
+                EventType::SC,
+                terms.cycle_anchor_date_of_scaling_index.filter(|_| scaled),
+                terms.cycle_of_scaling_index.as_ref(),
+            ),
+            (
+                EventType::IPCB,
+                terms.cycle_anchor_date_of_interest_calculation_base.filter(|_| fixed_base),
+                terms.cycle_of_interest_calculation_base.as_ref(),
+            ),
+            (
+                EventType::DV,
+                terms.cycle_anchor_date_of_dividend_payment,
+                terms.cycle_of_dividend_payment.as_ref(),
+            ),
+        ]
+    }
+
+    // =======================
+    //   Shared Logic
+    // =======================
+    /// Adds the overdue amount, its delinquency charge and the `penalty` due to a payment
+    /// of the debtor, which cures the contract unless it is in default, and what they pay
+    /// for to `paid`. The PY only pays the penalty.
+    fn settle_overdue(
+        event: EventType,
+        timestamp: u64,
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        payoff: Option<SignedUnits>,
+        penalty: Units,
+        paid: &mut Paid,
+    ) -> Result<Option<SignedUnits>> {
+        let Some(payoff) = payoff else {
+            return Ok(None);
+        };
+        if state.overdue_amount == 0 || event.paid_by_creditor() || event == EventType::PY {
+            return Ok(Some(payoff));
+        }
+
+        let overflow = || Error::MathError("Overdue payment overflow".into());
+        let charges = Self::delinquency_charge(state, terms, timestamp)?
+            .checked_add(penalty)
+            .ok_or_else(overflow)?;
+        let overdue = charges
+            .checked_add(state.overdue_amount)
+            .and_then(|v| SignedUnits::try_from(v).ok())
+            .and_then(|v| v.checked_add(payoff))
+            .ok_or_else(overflow)?;
+
+        // what the missed payments did not pay for is the principal they repay
+        let principal = state
+            .overdue_amount
+            .checked_sub(state.overdue_interest)
+            .and_then(|v| v.checked_sub(state.overdue_fees))
+            .and_then(|v| v.checked_sub(state.overdue_penalties))
+            .ok_or_else(overflow)?;
+        let interest = SignedUnits::try_from(state.overdue_interest).map_err(|_| overflow())?;
+        *paid = Paid {
+            interest: paid.interest.checked_add(interest).ok_or_else(overflow)?,
+            principal: paid.principal.checked_add(principal).ok_or_else(overflow)?,
+            fees: paid.fees.checked_add(state.overdue_fees).ok_or_else(overflow)?,
+            penalties: [charges, state.overdue_penalties]
+                .into_iter()
+                .try_fold(paid.penalties, Units::checked_add)
+                .ok_or_else(overflow)?,
+        };
+
+        state.overdue_amount = 0;
+        state.overdue_interest = 0;
+        state.overdue_fees = 0;
+        state.overdue_penalties = 0;
+        state.overdue_since = None;
+        state.penalty_assessed = false;
+        if state.contract_performance != ContractPerformance::DF {
+            state.contract_performance = ContractPerformance::PF;
+        }
+
+        Ok(Some(overdue))
+    }
+
+    /// A missed payment is delayed until its grace period lapses, then delinquent
+    fn update_performance(state: &mut ContractState, terms: &ContractTerms, timestamp: u64) {
+        let Some(since) = state.overdue_since else {
+            return;
+        };
+        if state.contract_performance == ContractPerformance::DF {
+            return;
+        }
+
+        state.contract_performance = if timestamp > grace_end(since, terms) {
+            ContractPerformance::DQ
+        } else {
+            ContractPerformance::DL
+        };
+    }
+
+    /// Recalculates accrued interest from `status_date` to `timestamp` using day-count,
+    /// or over `year_fraction` when the caller already has it
+    fn update_accrued_interest(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        year_fraction: Option<u64>,
+    ) -> Result<()> {
+        // Accrued interest is frozen once the contract is in default or exercised
+        let exercised = state.exercise_amount.is_some();
+        if state.contract_performance == ContractPerformance::DF || exercised {
+            return Ok(());
+        }
+
+        // If the contract defines a day count convention, compute interest
+        if let Some(dcc) = terms.day_count_convention {
+            let time_fraction = year_fraction.unwrap_or_else(|| {
+                let (start, md) = (state.status_date, terms.maturity_date);
+                terms.granularity().year_fraction(dcc as u8, start, timestamp, md)
+            });
+
+            match terms.contract_type {
+                ContractType::SWPPV => return Self::accrue_legs(state, terms, time_fraction),
+                ContractType::CAPFL => return Self::accrue_caplets(state, terms, time_fraction),
+                _ => {}
+            }
+
+            // interest = base * rate * time_fraction, where both the rate and the
+            // year fraction are expressed in basis points. A negative rate accrues
+            // interest owed to the debtor, truncated toward zero like a positive one.
+            let accrual = SignedUnits::try_from(Self::interest_base(state, terms))
+                .ok()
+                .and_then(|v| v.checked_mul(state.nominal_interest_rate as SignedUnits))
+                .and_then(|v| v.checked_mul(time_fraction as SignedUnits))
+                .map(|v| v / (math::BASIS_POINT_FACTOR as SignedUnits).pow(2))
+                .ok_or(Error::MathError("Interest calculation overflow".into()))?;
+
+            // Add to accrued_interest
+            state.accrued_interest = state
+                .accrued_interest
+                .checked_add(accrual)
+                .ok_or(Error::MathError("Interest accrual overflow".into()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Accrues the fixed leg of a swap at the rate of the terms and its floating leg at
+    /// the rate of the state, both on the notional, leaving their difference as the
+    /// accrued interest. Positive, it is owed by the debtor, who pays the fixed leg.
+    fn accrue_legs(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        time_fraction: u64,
+    ) -> Result<()> {
+        let overflow = || Error::MathError("Swap leg accrual overflow".into());
+        let leg = |accrued: Option<Units>, rate: Rate| {
+            let rate = Units::try_from(rate).map_err(|_| {
+                Error::ValidationError("Swap legs accrue at non-negative rates".into())
+            })?;
+            state
+                .notional_principal
+                .checked_mul(rate)
+                .and_then(|v| v.checked_mul(Units::from(time_fraction)))
+                .map(|v| v / Units::from(math::BASIS_POINT_FACTOR).pow(2))
+                .and_then(|v| v.checked_add(accrued.unwrap_or(0)))
+                .ok_or_else(overflow)
+        };
+
+        let fixed_rate = terms.nominal_interest_rate.unwrap_or(0);
+        let fixed = leg(state.accrued_interest_first_leg, fixed_rate)?;
+        let floating = leg(state.accrued_interest_second_leg, state.nominal_interest_rate)?;
+        let net = SignedUnits::try_from(fixed)
+            .ok()
+            .zip(SignedUnits::try_from(floating).ok())
+            .and_then(|(fixed, floating)| fixed.checked_sub(floating))
+            .ok_or_else(overflow)?;
+
+        state.accrued_interest_first_leg = Some(fixed);
+        state.accrued_interest_second_leg = Some(floating);
+        state.accrued_interest = net;
+        Ok(())
+    }
+
+    /// The rate a cap or floor pays at when the underlying rate is `rate`: how far it is
+    /// above the life cap plus how far it is below the life floor, or nothing
+    pub fn cap_floor_rate(rate: Rate, terms: &ContractTerms) -> Result<Rate> {
+        if terms.life_cap.is_none() && terms.life_floor.is_none() {
+            return Err(Error::ValidationError("Caps and floors need a life cap or floor".into()));
+        }
+
+        let above = terms.life_cap.map_or(0, |cap| rate.saturating_sub(cap).max(0));
+        let below = terms.life_floor.map_or(0, |floor| floor.saturating_sub(rate).max(0));
+        above
+            .checked_add(below)
+            .ok_or(Error::MathError("Cap and floor rate overflow".into()))
+    }
+
+    /// Accrues what a cap or floor pays over the period on the notional, at its
+    /// [`cap_floor_rate`](Self::cap_floor_rate) given the underlying rate of the state.
+    /// It is owed by the debtor, who wrote it.
+    fn accrue_caplets(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        time_fraction: u64,
+    ) -> Result<()> {
+        let rate = Self::cap_floor_rate(state.nominal_interest_rate, terms)?;
+        let accrual = SignedUnits::try_from(state.notional_principal)
+            .ok()
+            .and_then(|v| v.checked_mul(SignedUnits::from(rate)))
+            .and_then(|v| v.checked_mul(SignedUnits::from(time_fraction)))
+            .map(|v| v / (math::BASIS_POINT_FACTOR as SignedUnits).pow(2))
+            .and_then(|v| state.accrued_interest.checked_add(v))
+            .ok_or(Error::MathError("Caplet accrual overflow".into()))?;
+
+        state.accrued_interest = accrual;
+        Ok(())
+    }
+
+    /// What interest accrues on: the notional, unless the terms of a LAM or NAM fix an
+    /// interest calculation base that does not follow it
+    fn interest_base(state: &ContractState, terms: &ContractTerms) -> Units {
+        let fixed = matches!(terms.contract_type, ContractType::LAM | ContractType::NAM)
+            && matches!(terms.interest_calculation_base, Some(IPCB::IPCB_NTIED | IPCB::IPCB_NTL));
+
+        if fixed {
+            state.interest_calculation_base
+        } else {
+            state.notional_principal
+        }
+    }
+
+    /// Accrues the fee from `status_date` to `timestamp` under `FEB_N`, the fee rate on
+    /// the current notional, so a redemption between fee payments pro-rates the fee.
+    /// Under `FEB_A` each FP pays the fee rate as an amount and nothing accrues.
+    fn update_fee_accrued(
+        state: &mut ContractState,
+        terms: &ContractTerms,
+        timestamp: u64,
+        year_fraction: Option<u64>,
+    ) -> Result<()> {
+        // Fees stop accruing with the interest at default or exercise
+        let exercised = state.exercise_amount.is_some();
+        if state.contract_performance == ContractPerformance::DF || exercised {
+            return Ok(());
+        }
+
+        match terms.fee_basis {
+            None | Some(FeeBasis::FEB_A) => Ok(()),
+            Some(FeeBasis::FEB_N) => {
+                let rate = terms.fee_rate.ok_or(Error::MissingTerm { field: "fee_rate" })?;
+                let dcc = terms
+                    .day_count_convention
+                    .ok_or(Error::MissingTerm { field: "day_count_convention" })?;
+                let (start, md) = (state.status_date, terms.maturity_date);
+                let time_fraction = year_fraction.unwrap_or_else(|| {
+                    terms.granularity().year_fraction(dcc as u8, start, timestamp, md)
+                });
+
+                // fee = notional * rate * time_fraction, both in basis points
+                state.fee_accrued = state
+                    .notional_principal
+                    .checked_mul(Units::from(rate))
+                    .and_then(|v| v.checked_mul(time_fraction as Units))
+                    .map(|v| v / (math::BASIS_POINT_FACTOR as Units).pow(2))
+                    .and_then(|fee| state.fee_accrued.checked_add(fee))
+                    .ok_or(Error::MathError("Fee accrual overflow".into()))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The end of the grace period of a payment due at `due`
+fn grace_end(due: Timestamp, terms: &ContractTerms) -> Timestamp {
+    due.saturating_add(terms.grace_period.unwrap_or(0))
+}
+
+// =======================
+// Unit Tests
+// =======================
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pam_initial_exchange() {
+        let mut state = ContractState {
+            status_date: 1000,
+            notional_principal: 0,
+            nominal_interest_rate: 0,
+            accrued_interest: 0,
+            contract_performance: ContractPerformance::PF,
+            fee_accrued: 0,
+            notional_scaling_multiplier: 0,
+            interest_scaling_multiplier: 0,
+            next_principal_redemption_payment: 0,
+            interest_calculation_base: 0,
+            exercise_date: None,
+            exercise_amount: None,
+            // Additional fields from your new struct:
+            time_of_maturity: None,
+            accrued_interest_first_leg: None,
+            accrued_interest_second_leg: None,
+            last_interest_period: None,
+            overdue_amount: 0,
+            overdue_since: None,
+            penalty_assessed: false,
+            reference_rate: None,
+            reset_rate: None,
+            phase: ContractPhase::PreInitialExchange,
+            payoff_residual: 0,
+            total_interest_paid: 0,
+            total_principal_repaid: 0,
+            total_fees_paid: 0,
+            total_penalties_paid: 0,
+            overdue_interest: 0,
+            overdue_fees: 0,
+            overdue_penalties: 0,
+        };
+
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(1000),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500), // 5% in basis points
+            day_count_convention: None,
+            schedule_config: ScheduleConfig {
+                calendar: None,
+                end_of_month_convention: None,
+                business_day_convention: None,
+            },
+            status_date: 1000,
+            // Fill in any other fields as needed
+            ..Default::default()
+        };
+
+        let result = TransitionEngine::process_event(
+            EventType::IED,
+            1000,
+            &mut state,
+            &terms
+        ).unwrap();
+
+        assert_eq!(result, Some(1_000_000));
+        assert_eq!(state.notional_principal, 1_000_000);
+        assert_eq!(state.nominal_interest_rate, 500);
+        assert_eq!(state.accrued_interest, 0);
+    }
+
+    #[test]
+    fn test_failed_event_leaves_state_unchanged() {
+        let terms = ContractTerms {
+            contract_type: ContractType::FUTUR,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        state.notional_principal = 1_000;
+        state.phase = ContractPhase::Active;
+        let before = state.clone();
+
+        // interest accrues before the contract type turns out not to support IP
+        let result = TransitionEngine::process_event(EventType::IP, 365 * math::SECONDS_PER_DAY, &mut state, &terms);
+
+        assert!(matches!(result, Err(Error::EventNotApplicable { .. })));
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn test_next_event_follows_cycles() {
+        let month = Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        };
+        let ied = math::timestamp_from_civil(2024, 1, 15);
+
+        let terms = ContractTerms {
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(ied),
+            notional_principal: Some(1_200),
+            cycle_anchor_date_of_principal_redemption: Some(math::timestamp_from_civil(2024, 2, 15)),
+            cycle_of_principal_redemption: Some(month.clone()),
+            cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2024, 3, 31)),
+            cycle_of_interest_payment: Some(Cycle { n: 3, ..month }),
+            maturity_date: Some(math::timestamp_from_civil(2025, 1, 15)),
+            status_date: ied,
+            ..Default::default()
+        };
+
+        let mut state = ContractState::new(&terms);
+        assert_eq!(TransitionEngine::next_event(&state, &terms), Some((EventType::IED, ied)));
+
+        TransitionEngine::process_event(EventType::IED, ied, &mut state, &terms).unwrap();
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::PR, math::timestamp_from_civil(2024, 2, 15)))
+        );
+
+        state.status_date = math::timestamp_from_civil(2024, 3, 20);
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::IP, math::timestamp_from_civil(2024, 3, 31)))
+        );
+
+        state.status_date = math::timestamp_from_civil(2024, 12, 31);
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::MD, math::timestamp_from_civil(2025, 1, 15)))
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_waits_for_earlier_events_of_its_date() {
+        let month = Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        };
+        let ied = math::timestamp_from_civil(2024, 1, 15);
+        let [first, second] = [1, 2].map(|n| math::add_months(ied, n).unwrap());
+
+        let terms = ContractTerms {
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(ied),
+            notional_principal: Some(1_200),
+            next_principal_redemption_payment: Some(100),
+            nominal_interest_rate: Some(500),
+            cycle_anchor_date_of_principal_redemption: Some(first),
+            cycle_of_principal_redemption: Some(month.clone()),
+            cycle_anchor_date_of_interest_payment: Some(first),
+            cycle_of_interest_payment: Some(month),
+            maturity_date: math::add_months(ied, 12),
+            status_date: ied,
+            ..Default::default()
+        };
+        let next = |event, state: &ContractState, last| {
+            TransitionEngine::next_occurrence(event, state, &terms, last)
+        };
+
+        let mut state = ContractState::new(&terms);
+        assert_eq!(next(EventType::IED, &state, None), Some(ied));
+        assert_eq!(next(EventType::IP, &state, None), None);
+
+        TransitionEngine::process_event(EventType::IED, ied, &mut state, &terms).unwrap();
+        let last = Some((EventType::IED, ied));
+        assert_eq!(next(EventType::IED, &state, last), None);
+        assert_eq!(next(EventType::IP, &state, last), Some(first));
+        assert_eq!(next(EventType::PR, &state, last), Some(first));
+        assert_eq!(next(EventType::MD, &state, last), terms.maturity_date);
+        assert_eq!(next(EventType::PP, &state, last), None);
+        assert_eq!(next(EventType::CE, &state, last), None);
+
+        // the PR of the date still follows its IP
+        TransitionEngine::process_event(EventType::IP, first, &mut state, &terms).unwrap();
+        let last = Some((EventType::IP, first));
+        assert_eq!(next(EventType::IP, &state, last), Some(second));
+        assert_eq!(next(EventType::PR, &state, last), Some(first));
+
+        TransitionEngine::process_event(EventType::PR, first, &mut state, &terms).unwrap();
+        let last = Some((EventType::PR, first));
+        assert_eq!(next(EventType::IP, &state, last), Some(second));
+        assert_eq!(next(EventType::PR, &state, last), Some(second));
+    }
+
+    #[test]
+    fn test_write_off_after_credit_event() {
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(730 * math::SECONDS_PER_DAY),
+            ..Default::default()
+        };
+
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // a performant contract cannot be written off
+        assert!(TransitionEngine::write_off(&mut state.clone(), 0).is_err());
+
+        let default_date = 73 * math::SECONDS_PER_DAY;
+        TransitionEngine::process_event(EventType::CE, default_date, &mut state, &terms).unwrap();
+        assert_eq!(state.contract_performance, ContractPerformance::DF);
+        let frozen = state.accrued_interest;
+        assert!(frozen > 0);
+
+        // accrual stops once in default
+        let later = 2 * default_date;
+        TransitionEngine::process_event(EventType::AD, later, &mut state, &terms).unwrap();
+        assert_eq!(state.accrued_interest, frozen);
+
+        let loss = TransitionEngine::write_off(&mut state, later).unwrap();
+        assert_eq!(loss, 1_000_000 + frozen as Units);
+        assert_eq!(state.notional_principal, 0);
+        assert_eq!(state.accrued_interest, 0);
+        assert_eq!(state.phase, ContractPhase::WrittenOff);
+
+        assert!(TransitionEngine::process_event(EventType::IP, later, &mut state, &terms).is_err());
+        assert_eq!(TransitionEngine::next_event(&state, &terms), None);
+    }
+
+    #[test]
+    fn test_phases_guard_their_events() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+            ..Default::default()
+        };
+        let after = |events: &[(EventType, u64)]| {
+            let mut state = ContractState::new(&terms);
+            for &(event, days) in events {
+                TransitionEngine::process_event(event, days * day, &mut state, &terms).unwrap();
+            }
+            state
+        };
+
+        let mut written_off = after(&[(EventType::IED, 0), (EventType::CE, 30)]);
+        TransitionEngine::write_off(&mut written_off, 30 * day).unwrap();
+        let states = [
+            (ContractState::new(&terms), ContractPhase::PreInitialExchange),
+            (after(&[(EventType::IED, 0)]), ContractPhase::Active),
+            (after(&[(EventType::IED, 0), (EventType::MD, 365)]), ContractPhase::Matured),
+            (
+                after(&[(EventType::IED, 0), (EventType::IP, 30), (EventType::PP, 30)]),
+                ContractPhase::Terminated,
+            ),
+            (written_off, ContractPhase::WrittenOff),
+        ];
+
+        // whether IED, IP, MD and AD events are allowed in each phase
+        let allowed = [
+            [true, false, false, true],
+            [false, true, true, true],
+            [false, false, false, true],
+            [false, false, false, true],
+            [false, false, false, false],
+        ];
+        let events = [EventType::IED, EventType::IP, EventType::MD, EventType::AD];
+
+        for ((state, phase), allowed) in states.into_iter().zip(allowed) {
+            assert_eq!(state.phase, phase);
+
+            for (event, allowed) in events.into_iter().zip(allowed) {
+                let timestamp = if event == EventType::IED { 0 } else { 365 * day };
+                let mut next = state.clone();
+                let result = TransitionEngine::process_event(event, timestamp, &mut next, &terms);
+
+                let rejected = Err(Error::EventNotAllowed { event, phase });
+                assert_eq!(result == rejected, !allowed, "{event:?} in {phase:?}: {result:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_interest_accrues_on_the_drawn_balance() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(0),
+            credit_limit: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+
+        // the facility opens at the IED
+        assert_eq!(
+            TransitionEngine::draw_down(&mut state, &terms, 0, 400_000),
+            Err(Error::EventNotAllowed {
+                event: EventType::PI,
+                phase: ContractPhase::PreInitialExchange
+            })
+        );
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // draw 400_000, repay 100_000 and draw 500_000 more, 73 days (a fifth of a year)
+        // apart: 5% of a fifth of each segment's balance
+        TransitionEngine::draw_down(&mut state, &terms, 0, 400_000).unwrap();
+        TransitionEngine::repay(&mut state, &terms, 73 * day, 100_000).unwrap();
+        assert_eq!((state.notional_principal, state.accrued_interest), (300_000, 4_000));
+
+        TransitionEngine::draw_down(&mut state, &terms, 146 * day, 500_000).unwrap();
+        assert_eq!((state.notional_principal, state.accrued_interest), (800_000, 7_000));
+
+        TransitionEngine::process_event(EventType::AD, 219 * day, &mut state, &terms).unwrap();
+        assert_eq!(state.accrued_interest, 15_000);
+
+        // neither past the limit nor below zero
+        let before = state.clone();
+        assert_eq!(
+            TransitionEngine::draw_down(&mut state, &terms, 219 * day, 200_001),
+            Err(Error::ValidationError("Drawdown exceeds the credit limit of 1000000".into()))
+        );
+        assert_eq!(
+            TransitionEngine::repay(&mut state, &terms, 219 * day, 800_001),
+            Err(Error::ValidationError("Repayment exceeds the drawn balance".into()))
+        );
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn test_deposits_accrue_on_the_balance_between_changes() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::UMP,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: Some(365 * day),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_Y,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // 1_000_000 for 73 days, 1_500_000 for 146 and 500_000 for the other 146
+        TransitionEngine::deposit(&mut state, &terms, 73 * day, 500_000).unwrap();
+        TransitionEngine::withdraw(&mut state, &terms, 219 * day, 1_000_000).unwrap();
+        assert_eq!(state.notional_principal, 500_000);
+        assert_eq!(state.total_principal_repaid, 1_000_000);
+        let payoff = TransitionEngine::process_event(EventType::IP, 365 * day, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(50_000)));
+
+        assert_eq!(
+            TransitionEngine::withdraw(&mut state, &terms, 400 * day, 500_001),
+            Err(Error::ValidationError("Withdrawal exceeds the balance".into()))
+        );
+
+        // an emptied deposit stays open for the next deposit
+        TransitionEngine::withdraw(&mut state, &terms, 400 * day, 500_000).unwrap();
+        assert_eq!(state.phase, ContractPhase::Active);
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::IP, 730 * day))
+        );
+        TransitionEngine::deposit(&mut state, &terms, 500 * day, 100_000).unwrap();
+
+        let capped = ContractTerms { credit_limit: Some(150_000), ..terms.clone() };
+        assert_eq!(
+            TransitionEngine::deposit(&mut state, &capped, 500 * day, 50_001),
+            Err(Error::ValidationError("Deposit exceeds the credit limit".into()))
+        );
+        let loan = ContractTerms { contract_type: ContractType::PAM, ..terms };
+        assert!(TransitionEngine::deposit(&mut state, &loan, 500 * day, 1).is_err());
+        assert_eq!(state.notional_principal, 100_000);
+    }
+
+    #[test]
+    fn test_called_money_matures_after_the_notice() {
+        let day = math::SECONDS_PER_DAY;
+        let mut terms = ContractTerms {
+            contract_type: ContractType::CLM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(365),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            x_day_notice: Some(30 * day),
+            ..Default::default()
+        };
+        assert_eq!(terms.check_required(), Ok(()));
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        assert_eq!(TransitionEngine::call(&mut state, &mut terms, 70 * day), Ok(100 * day));
+        assert_eq!(terms.maturity_date, Some(100 * day));
+        assert_eq!(state.time_of_maturity, Some(100 * day));
+        assert_eq!(
+            TransitionEngine::call(&mut state, &mut terms, 80 * day),
+            Err(Error::ValidationError("The contract was already called".into()))
+        );
+
+        // the principal is repaid with 100 days of interest, accrued over a year fraction
+        // held in basis points, so the 0.27397 of a year is truncated to 0.2739
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::MD, 100 * day))
+        );
+        let payoff = TransitionEngine::process_event(EventType::MD, 100 * day, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(1_009_997)));
+
+        let mut loan = ContractTerms { contract_type: ContractType::PAM, ..terms };
+        loan.maturity_date = None;
+        assert!(TransitionEngine::call(&mut state, &mut loan, 80 * day).is_err());
+    }
+
+    #[test]
+    fn test_roll_over_extends_maturity() {
+        let day = math::SECONDS_PER_DAY;
+        let mut terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            maturity_date: Some(90 * day),
+            ..Default::default()
+        };
+
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // maturity must move forward
+        assert!(TransitionEngine::roll_over(&mut state, &mut terms, 90 * day, None).is_err());
+
+        TransitionEngine::roll_over(&mut state, &mut terms, 180 * day, Some(600)).unwrap();
+        assert_eq!(terms.maturity_date, Some(180 * day));
+        assert_eq!(state.time_of_maturity, Some(180 * day));
+        assert_eq!(state.nominal_interest_rate, 600);
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::MD, 180 * day))
+        );
+
+        TransitionEngine::process_event(EventType::MD, 180 * day, &mut state, &terms).unwrap();
+        assert!(TransitionEngine::roll_over(&mut state, &mut terms, 270 * day, None).is_err());
+    }
+
+    #[test]
+    fn test_amendment_accrues_the_old_rate_up_to_it() {
+        let day = math::SECONDS_PER_DAY;
+        let dcc = DayCountConvention::DCC_A_365;
+        let mut terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(dcc),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let cut = Amendment {
+            rate: Some(300),
+            ..Default::default()
+        };
+
+        let mut state = ContractState::new(&terms);
+        let result = TransitionEngine::amend(&mut state, &mut terms, &cut, 0);
+        assert!(matches!(result, Err(Error::StateError(_))));
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        let before = (state.clone(), terms.clone());
+        let late = Amendment {
+            skipped_dates: vec![30 * day],
+            ..cut.clone()
+        };
+        let result = TransitionEngine::amend(&mut state, &mut terms, &late, 45 * day);
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+        assert_eq!((state.clone(), terms.clone()), before);
+
+        TransitionEngine::amend(&mut state, &mut terms, &cut, 45 * day).unwrap();
+        assert_eq!(terms.nominal_interest_rate, Some(300));
+        assert_eq!(state.status_date, 45 * day);
+
+        let interest = |rate: SignedUnits, from: u64, to: u64| {
+            let fraction = math::year_fraction(dcc as u8, from * day, to * day, None);
+            1_000_000 * rate * fraction as SignedUnits / 100_000_000
+        };
+        let payoff = TransitionEngine::process_event(EventType::IP, 90 * day, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(interest(500, 0, 45) + interest(300, 45, 90))));
+    }
+
+    #[test]
+    fn test_skipped_dates_defer_their_payments() {
+        let day = math::SECONDS_PER_DAY;
+        let month = Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        };
+        let mut terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(math::add_months(0, 12).unwrap()),
+            cycle_anchor_date_of_interest_payment: math::add_months(0, 1),
+            cycle_of_interest_payment: Some(month),
+            ..Default::default()
+        };
+        let [first, second, third] = [1, 2, 3].map(|n| math::add_months(0, n).unwrap());
+
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        TransitionEngine::process_event(EventType::IP, first, &mut state, &terms).unwrap();
+
+        let holiday = Amendment {
+            skipped_dates: vec![second],
+            ..Default::default()
+        };
+        TransitionEngine::amend(&mut state, &mut terms, &holiday, first + day).unwrap();
+        assert_eq!(terms.skipped_dates, Some(vec![second]));
+        assert_eq!(TransitionEngine::next_event(&state, &terms), Some((EventType::IP, third)));
+
+        // the next payment pays the interest of both months
+        let accrued = state.accrued_interest;
+        let payoff = TransitionEngine::process_event(EventType::IP, third, &mut state, &terms);
+        let dcc = DayCountConvention::DCC_A_365 as u8;
+        let fraction = math::year_fraction(dcc, first + day, third, None);
+        let rest = 1_000_000 * 500 * fraction as SignedUnits / 100_000_000;
+        assert_eq!(payoff, Ok(Some(accrued + rest)));
+        assert_eq!(
+            TransitionEngine::next_event(&state, &terms),
+            Some((EventType::IP, math::add_months(0, 4).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_negative_rate_reverses_interest() {
+        let day = math::SECONDS_PER_DAY;
+        // a deposit at -0.5%, so the depositor pays interest on it
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(-50),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // truncated toward zero like a positive accrual
+        let quarter = math::year_fraction(DayCountConvention::DCC_A_365 as u8, 0, 91 * day, None);
+        let interest = -(1_000_000 * 50 * quarter as SignedUnits / 100_000_000);
+        assert!(interest < 0);
+
+        let payoff = TransitionEngine::process_event(EventType::IP, 91 * day, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(interest)));
+        assert_eq!(state.accrued_interest, 0);
+
+        // the interest owed by the depositor nets against their principal at maturity
+        let payoff = TransitionEngine::process_event(EventType::MD, 365 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+        assert!(payoff < 1_000_000 && payoff > 1_000_000 + 4 * interest);
+    }
+
+    /// A PAM at 5% whose first IP, 91 days in, is missed, with five days of grace, a 10%
+    /// delinquency rate and a penalty of 250
+    fn missed_interest_payment() -> (ContractTerms, ContractState, Units) {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            grace_period: Some(5 * day),
+            delinquency_rate: Some(1_000),
+            penalty_type: Some(PenaltyType::PYTP_A),
+            penalty_rate: Some(250),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        let missed =
+            TransitionEngine::miss_payment(EventType::IP, 91 * day, &mut state, &terms).unwrap();
+        (terms, state, missed)
+    }
+
+    #[test]
+    fn test_late_payment_pays_the_delinquency_charge() {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, mut state, missed) = missed_interest_payment();
+        assert_eq!(missed, 12_465);
+        assert_eq!((state.overdue_amount, state.overdue_since), (missed, Some(91 * day)));
+        assert_eq!(state.accrued_interest, 0);
+        assert_eq!(state.contract_performance, ContractPerformance::DL);
+
+        // delinquent once the grace period lapses
+        TransitionEngine::process_event(EventType::AD, 97 * day, &mut state, &terms).unwrap();
+        assert_eq!(state.contract_performance, ContractPerformance::DQ);
+
+        // paid ten days late: 10% on the overdue amount over the ten days from its due
+        // date and the penalty, on top of the interest accrued since
+        let charge = TransitionEngine::delinquency_charge(&state, &terms, 101 * day).unwrap();
+        assert_eq!(charge, 12_465 * 1_000 * 273 / 100_000_000);
+        assert!(charge > 0);
+        let penalty = TransitionEngine::penalty_due(EventType::IP, 101 * day, &state, &terms);
+        assert_eq!(penalty, Ok(250));
+
+        let payoff = TransitionEngine::process_event(EventType::IP, 101 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+        let interest = 1_000_000 * 500 * 273 / 100_000_000;
+        assert_eq!(payoff, interest + (missed + charge + 250) as SignedUnits);
+        assert_eq!((state.overdue_amount, state.overdue_since), (0, None));
+        assert_eq!(state.contract_performance, ContractPerformance::PF);
+    }
+
+    #[test]
+    fn test_shortfalls_stay_overdue_until_settled() {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, _, _) = missed_interest_payment();
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        let interest = TransitionEngine::process_event(EventType::IP, 91 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+
+        TransitionEngine::record_shortfall(91 * day, 91 * day, &mut state, &terms);
+        assert_eq!((state.overdue_amount, state.overdue_since), (0, Some(91 * day)));
+        assert_eq!(state.contract_performance, ContractPerformance::DL);
+
+        // the next payment leaves the shortfall to the contract
+        TransitionEngine::process_event(EventType::AD, 97 * day, &mut state, &terms).unwrap();
+        assert_eq!(state.contract_performance, ContractPerformance::DQ);
+        let penalty = TransitionEngine::penalty_due(EventType::PY, 97 * day, &state, &terms);
+        assert_eq!(penalty, Ok(250));
+        let charge = TransitionEngine::late_charge(interest as Units, 91 * day, &terms, 101 * day);
+        assert_eq!(charge, Ok(12_465 * 1_000 * 273 / 100_000_000));
+
+        TransitionEngine::settle_shortfall(Some(95 * day), 101 * day, &mut state, &terms);
+        assert_eq!(state.overdue_since, Some(95 * day));
+        TransitionEngine::settle_shortfall(None, 101 * day, &mut state, &terms);
+        assert_eq!(state.overdue_since, None);
+        assert_eq!(state.contract_performance, ContractPerformance::PF);
+    }
+
+    #[test]
+    fn test_missed_payments_count_towards_the_totals_once_settled() {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, mut state, missed) = missed_interest_payment();
+        assert_eq!((state.overdue_interest, state.total_interest_paid), (missed, 0));
+
+        let charge = TransitionEngine::delinquency_charge(&state, &terms, 101 * day).unwrap();
+        let payoff = TransitionEngine::process_event(EventType::IP, 101 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.total_penalties_paid, charge + 250);
+        assert_eq!(
+            state.total_interest_paid + state.total_penalties_paid as SignedUnits,
+            payoff
+        );
+        assert_eq!(state.overdue_interest, 0);
+
+        TransitionEngine::process_event(EventType::MD, 365 * day, &mut state, &terms).unwrap();
+        assert_eq!(state.total_principal_repaid, 1_000_000);
+        assert_eq!(state.total_fees_paid, 0);
+    }
+
+    #[test]
+    fn test_payment_within_grace_is_not_charged() {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, mut state, missed) = missed_interest_payment();
+
+        // cured exactly as the grace period ends
+        assert_eq!(TransitionEngine::penalty_due(EventType::IP, 96 * day, &state, &terms), Ok(0));
+        let payoff = TransitionEngine::process_event(EventType::IP, 96 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+        let interest = 1_000_000 * 500 * 136 / 100_000_000;
+        assert_eq!(payoff, interest + missed as SignedUnits);
+        assert_eq!(state.contract_performance, ContractPerformance::PF);
+
+        // disbursements are never missed
+        assert!(TransitionEngine::miss_payment(EventType::IED, 0, &mut state, &terms).is_err());
+    }
+
+    #[test]
+    fn test_penalty_paid_by_py_is_not_charged_again() {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, mut state, missed) = missed_interest_payment();
+
+        // no penalty within the grace period
+        let result = TransitionEngine::process_event(EventType::PY, 96 * day, &mut state, &terms);
+        assert!(result.is_err());
+
+        // the PY pays the penalty alone, and only once
+        let penalty = TransitionEngine::process_event(EventType::PY, 98 * day, &mut state, &terms);
+        assert_eq!(penalty, Ok(Some(250)));
+        assert_eq!(state.overdue_amount, missed);
+        assert!(state.penalty_assessed);
+        let result = TransitionEngine::process_event(EventType::PY, 99 * day, &mut state, &terms);
+        assert!(result.is_err());
+
+        // settling the overdue amount afterwards pays the delinquency charge only
+        let charge = TransitionEngine::delinquency_charge(&state, &terms, 101 * day).unwrap();
+        assert_eq!(TransitionEngine::penalty_due(EventType::IP, 101 * day, &state, &terms), Ok(0));
+        let payoff = TransitionEngine::process_event(EventType::IP, 101 * day, &mut state, &terms)
+            .unwrap()
+            .unwrap();
+        let interest = 1_000_000 * 500 * 273 / 100_000_000;
+        assert_eq!(payoff, interest + (missed + charge) as SignedUnits);
+        assert!(!state.penalty_assessed);
+    }
+
+    /// The PY ten days after the missed IP of [`missed_interest_payment`], under a
+    /// penalty of `penalty_type` at `penalty_rate`
+    fn penalty_ten_days_late(
+        penalty_type: Option<PenaltyType>,
+        penalty_rate: Option<u64>,
+        reference_rate: Option<Rate>,
+    ) -> Result<Option<SignedUnits>> {
+        let day = math::SECONDS_PER_DAY;
+        let (terms, mut state, _) = missed_interest_payment();
+        let terms = ContractTerms { penalty_type, penalty_rate, ..terms };
+        state.reference_rate = reference_rate;
+
+        TransitionEngine::process_event(EventType::PY, 101 * day, &mut state, &terms)
+    }
+
+    #[test]
+    fn test_py_needs_a_penalty_type() {
+        let rejected = Err(Error::ValidationError("PY events need a penalty type".into()));
+        assert_eq!(penalty_ten_days_late(None, Some(250), None), rejected);
+        assert_eq!(penalty_ten_days_late(Some(PenaltyType::PYTP_O), Some(250), None), rejected);
+    }
+
+    #[test]
+    fn test_absolute_penalty_pays_the_penalty_rate() {
+        let penalty = penalty_ten_days_late(Some(PenaltyType::PYTP_A), Some(250), None);
+        assert_eq!(penalty, Ok(Some(250)));
+    }
+
+    #[test]
+    fn test_penalty_on_notional_is_pro_rated() {
+        // 2% a year on the notional over the ten days since the due date
+        let penalty = penalty_ten_days_late(Some(PenaltyType::PYTP_N), Some(200), None);
+        assert_eq!(penalty, Ok(Some(1_000_000 * 200 * 273 / 100_000_000)));
+    }
+
+    #[test]
+    fn test_penalty_on_the_interest_differential() {
+        let differential =
+            |reference| penalty_ten_days_late(Some(PenaltyType::PYTP_I), None, reference);
+
+        // the 5% nominal rate is 2% above the reference rate
+        assert_eq!(differential(Some(300)), Ok(Some(1_000_000 * 200 * 273 / 100_000_000)));
+
+        // nothing is due below the reference rate, and it has to be observed
+        assert!(differential(Some(600)).is_err());
+        assert_eq!(
+            differential(None),
+            Err(Error::ValidationError("No reference rate observed for the penalty".into()))
+        );
+    }
+
+    #[test]
+    fn test_fee_on_notional_pro_rates_a_redemption() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            next_principal_redemption_payment: Some(250_000),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            fee_basis: Some(FeeBasis::FEB_N),
+            fee_rate: Some(100),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // a quarter is repaid halfway between the IED and the FP
+        TransitionEngine::process_event(EventType::PR, 90 * day, &mut state, &terms).unwrap();
+        let fee = TransitionEngine::process_event(EventType::FP, 180 * day, &mut state, &terms);
+
+        let quarter = math::year_fraction(
+            DayCountConvention::DCC_A_365 as u8,
+            0,
+            90 * day,
+            terms.maturity_date,
+        ) as SignedUnits;
+        let expected = (1_000_000 + 750_000) * 100 * quarter / 100_000_000;
+        assert_eq!(fee, Ok(Some(expected)));
+        assert_eq!(state.fee_accrued, 0);
+    }
+
+    #[test]
+    fn test_maturity_settles_the_residual_principal() {
+        let day = math::SECONDS_PER_DAY;
+        // three redemptions of 250_000 leave 250_003 for the MD instead of 250_000
+        let terms = ContractTerms {
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_003),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            next_principal_redemption_payment: Some(250_000),
+            maturity_date: Some(360 * day),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        for quarter in 1..=3 {
+            let date = quarter * 90 * day;
+            TransitionEngine::process_event(EventType::IP, date, &mut state, &terms).unwrap();
+            TransitionEngine::process_event(EventType::PR, date, &mut state, &terms).unwrap();
+        }
+
+        let maturity = 360 * day;
+        let residual =
+            TransitionEngine::residual_principal(EventType::MD, maturity, &state, &terms, None);
+        assert_eq!(residual, Ok(3));
+        assert_eq!(
+            TransitionEngine::residual_principal(EventType::IP, maturity, &state, &terms, None),
+            Ok(0)
+        );
+
+        // the MD pays the whole notional with the interest on it and leaves nothing behind
+        let interest = 250_003 * 500 * 2_465 / 100_000_000;
+        let payoff = TransitionEngine::process_event(EventType::MD, maturity, &mut state, &terms);
+        assert_eq!(payoff, Ok(Some(250_003 + interest)));
+        assert_eq!(state.notional_principal, 0);
+        assert_eq!(state.accrued_interest, 0);
+        assert_eq!(state.next_principal_redemption_payment, 0);
+    }
+
+    #[test]
+    fn test_interest_accrues_on_the_calculation_base() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = |base| ContractTerms {
+            contract_type: ContractType::LAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(1_000),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            next_principal_redemption_payment: Some(500_000),
+            maturity_date: Some(1095 * day),
+            interest_calculation_base: base,
+            interest_calculation_base_amount: Some(800_000),
+            cycle_anchor_date_of_interest_calculation_base: Some(365 * day),
+            cycle_of_interest_calculation_base: Some(Cycle {
+                n: 1,
+                p: Period::P_Y,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+        // a year at 10% on the base before a redemption of half the notional and
+        // another after it, with the base fixed between them if `fixing`
+        let accrued = |terms: &ContractTerms, fixing: bool| {
+            let mut state = ContractState::new(terms);
+            TransitionEngine::process_event(EventType::IED, 0, &mut state, terms).unwrap();
+            TransitionEngine::process_event(EventType::PR, 365 * day, &mut state, terms).unwrap();
+            if fixing {
+                TransitionEngine::process_event(EventType::IPCB, 365 * day, &mut state, terms)
+                    .unwrap();
+            }
+            TransitionEngine::process_event(EventType::AD, 730 * day, &mut state, terms).unwrap();
+            (state.accrued_interest, state.interest_calculation_base)
+        };
+
+        // the notional by default, which the base follows
+        assert_eq!(accrued(&terms(None), false), (100_000 + 50_000, 500_000));
+        assert_eq!(accrued(&terms(Some(IPCB::IPCB_NT)), true), (100_000 + 50_000, 500_000));
+        // the notional at the IED, which no fixing moves
+        assert_eq!(accrued(&terms(Some(IPCB::IPCB_NTIED)), true), (100_000 + 100_000, 1_000_000));
+        // the base amount of the terms until a fixing resets it to the notional
+        let last_fixing = terms(Some(IPCB::IPCB_NTL));
+        assert_eq!(accrued(&last_fixing, false), (80_000 + 80_000, 800_000));
+        assert_eq!(accrued(&last_fixing, true), (80_000 + 50_000, 500_000));
+
+        // only that base schedules its fixings
+        let after_ied = |terms: &ContractTerms| {
+            let mut state = ContractState::new(terms);
+            TransitionEngine::process_event(EventType::IED, 0, &mut state, terms).unwrap();
+            TransitionEngine::next_event(&state, terms)
+        };
+        assert_eq!(after_ied(&last_fixing), Some((EventType::IPCB, 365 * day)));
+        assert_eq!(after_ied(&terms(Some(IPCB::IPCB_NTIED))), Some((EventType::MD, 1095 * day)));
+    }
+
+    #[test]
+    fn test_settled_payoffs_carry_the_truncated_residual() {
+        // thirty years of monthly interest on an amount finer than the cents it settles in
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 360),
+            notional_principal: Some(1_000_000_123_456_789),
+            nominal_interest_rate: Some(537),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+        let unit = SignedUnits::pow(10, u32::from(math::AMOUNT_DECIMALS) - 2);
+        let oriented = |event: EventType, amount: SignedUnits| {
+            if event.paid_by_creditor() { -amount } else { amount }
+        };
+
+        let (mut exact, mut settled) = (0, 0);
+        let mut state = ContractState::new(&terms);
+        for event in crate::lifecycle::run(&terms, &()).unwrap() {
+            let payoff = event.payoff.unwrap();
+            let cents =
+                TransitionEngine::settle_payoff(event.event_type, payoff, &mut state, 2).unwrap();
+            assert!(state.payoff_residual.abs() < unit);
+
+            exact += oriented(event.event_type, payoff);
+            settled += oriented(event.event_type, cents) * unit;
+        }
+
+        assert_eq!(state.payoff_residual, 0);
+        assert!((exact - settled).abs() < unit, "{exact} settled as {settled}");
+    }
+
+    /// An option exercisable up to a year in, with Bermudan dates at each quarter
+    fn option_terms(exercise_type: OptionExerciseType) -> ContractTerms {
+        let day = math::SECONDS_PER_DAY;
+        ContractTerms {
+            contract_type: ContractType::OPTNS,
+            initial_exchange_date: Some(0),
+            option_exercise_type: Some(exercise_type),
+            option_exercise_end_date: Some(365 * day),
+            option_exercise_dates: Some(vec![91 * day, 182 * day, 273 * day, 365 * day]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_american_option_exercises_within_its_window() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = option_terms(OptionExerciseType::XT_A);
+
+        assert_eq!(TransitionEngine::check_exercise(&terms, 100 * day), Ok(()));
+        assert_eq!(TransitionEngine::check_exercise(&terms, 365 * day), Ok(()));
+
+        let late = ContractTerms { option_exercise_start_date: Some(200 * day), ..terms };
+        assert_eq!(
+            TransitionEngine::check_exercise(&late, 100 * day),
+            Err(Error::ValidationError(format!(
+                "The option cannot be exercised at {}",
+                100 * day
+            )))
+        );
+    }
+
+    #[test]
+    fn test_bermudan_option_exercises_on_listed_dates_only() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = option_terms(OptionExerciseType::XT_B);
+
+        assert_eq!(TransitionEngine::check_exercise(&terms, 182 * day), Ok(()));
+        assert_eq!(
+            TransitionEngine::check_exercise(&terms, 182 * day + EXERCISE_TOLERANCE),
+            Ok(())
+        );
+        assert_eq!(
+            TransitionEngine::check_exercise(&terms, 100 * day),
+            Err(Error::ValidationError(format!(
+                "The option cannot be exercised at {}",
+                100 * day
+            )))
+        );
+
+        // European options only at the expiry
+        let european = option_terms(OptionExerciseType::XT_E);
+        assert!(TransitionEngine::check_exercise(&european, 182 * day).is_err());
+        assert_eq!(TransitionEngine::check_exercise(&european, 365 * day), Ok(()));
+    }
+
+    #[test]
+    fn test_no_option_exercises_after_expiry() {
+        let after_expiry = 365 * math::SECONDS_PER_DAY + 1;
+        let exercise_types =
+            [OptionExerciseType::XT_E, OptionExerciseType::XT_A, OptionExerciseType::XT_B];
+
+        for exercise_type in exercise_types {
+            let terms = option_terms(exercise_type);
+            let mut state = ContractState::new(&terms);
+            let result =
+                TransitionEngine::process_event(EventType::XD, after_expiry, &mut state, &terms);
+
+            assert_eq!(
+                result,
+                Err(Error::ValidationError("The option has expired".into())),
+                "{exercise_type:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_guarantee_covers_its_share_of_a_defaulted_contract() {
+        let day = math::SECONDS_PER_DAY;
+        let covered_terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let mut covered = ContractState::new(&covered_terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut covered, &covered_terms).unwrap();
+        TransitionEngine::process_event(EventType::AD, 73 * day, &mut covered, &covered_terms)
+            .unwrap();
+        assert_eq!(covered.accrued_interest, 10_000);
+
+        let terms = ContractTerms {
+            contract_type: ContractType::CEG,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(900_000),
+            maturity_date: Some(365 * day),
+            coverage_of_credit_enhancement: Some(8_000),
+            guaranteed_exposure: Some(GuaranteedExposure::CEGE_NI),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        // nothing is covered before a default
+        assert_eq!(
+            TransitionEngine::exercise_guarantee(100 * day, &covered, &mut state, &terms),
+            Err(Error::ValidationError("The covered contract is not in default".into()))
+        );
+
+        // 80% of the notional and the interest accrued up to the credit event
+        TransitionEngine::process_event(EventType::CE, 73 * day, &mut covered, &covered_terms)
+            .unwrap();
+        let mut guarantee = state.clone();
+        TransitionEngine::exercise_guarantee(100 * day, &covered, &mut guarantee, &terms).unwrap();
+        assert_eq!(guarantee.exercise_amount, Some(808_000));
+        let payoff =
+            TransitionEngine::process_event(EventType::STD, 100 * day, &mut guarantee, &terms);
+        assert_eq!(payoff, Ok(Some(808_000)));
+        assert_eq!(guarantee.phase, ContractPhase::Terminated);
+
+        // never more than the guarantee's notional
+        let full = ContractTerms { coverage_of_credit_enhancement: None, ..terms };
+        TransitionEngine::exercise_guarantee(100 * day, &covered, &mut state, &full).unwrap();
+        assert_eq!(state.exercise_amount, Some(900_000));
+        assert_eq!(
+            TransitionEngine::exercise_guarantee(100 * day, &covered, &mut state, &full),
+            Err(Error::TransitionError("The contract was already exercised".into()))
+        );
+    }
+
+    #[test]
+    fn test_exercised_contract_stops_accruing() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+        state.exercise_date = Some(30 * day);
+        state.exercise_amount = Some(0);
+
+        TransitionEngine::process_event(EventType::AD, 90 * day, &mut state, &terms).unwrap();
+        assert_eq!(state.accrued_interest, 0);
+    }
+
+    #[test]
+    fn test_scaling_fixing_needs_a_scaling_effect() {
+        let day = math::SECONDS_PER_DAY;
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(0),
+            notional_principal: Some(1_000_000),
+            maturity_date: Some(365 * day),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut state, &terms).unwrap();
+
+        let result = TransitionEngine::process_event(EventType::SC, 90 * day, &mut state, &terms);
+        assert_eq!(
+            result,
+            Err(Error::ValidationError("SC events need a scaling effect".into()))
+        );
+
+        // a fixing that only tracks the index pays nothing
+        let tracked = ContractTerms { scaling_effect: Some(ScalingEffect::SE_OOO), ..terms };
+        let result = TransitionEngine::process_event(EventType::SC, 90 * day, &mut state, &tracked);
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn test_transition_error_variants() {
+        let terms = ContractTerms {
+            contract_type: ContractType::PAM,
+            initial_exchange_date: Some(1000),
+            notional_principal: Some(1_000),
+            maturity_date: Some(2000),
+            status_date: 1000,
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+
+        assert_eq!(
+            TransitionEngine::process_event(EventType::IED, 999, &mut state, &terms),
+            Err(Error::EventBeforeStatusDate { event_time: 999, status_date: 1000 })
+        );
+
+        assert_eq!(
+            TransitionEngine::process_event(EventType::WO, 1000, &mut state, &terms),
+            Err(Error::EventNotApplicable { event: EventType::WO, contract_type: ContractType::PAM })
+        );
+
+        let unsupported = ContractTerms { contract_type: ContractType::FUTUR, ..terms.clone() };
+        assert_eq!(
+            TransitionEngine::process_event(EventType::IED, 1000, &mut state, &unsupported),
+            Err(Error::EventNotApplicable {
+                event: EventType::IED,
+                contract_type: ContractType::FUTUR
+            })
+        );
+
+        let no_notional = ContractTerms { notional_principal: None, ..terms.clone() };
+        assert_eq!(
+            TransitionEngine::process_event(EventType::IED, 1000, &mut state, &no_notional),
+            Err(Error::MissingTerm { field: "notional_principal" })
+        );
+        assert_eq!(no_notional.check_required(), Err(Error::MissingTerm { field: "notional_principal" }));
+
+        TransitionEngine::process_event(EventType::IED, 1000, &mut state, &terms).unwrap();
+        TransitionEngine::process_event(EventType::MD, 2000, &mut state, &terms).unwrap();
+        assert_eq!(
+            TransitionEngine::process_event(EventType::IP, 2000, &mut state, &terms),
+            Err(Error::EventNotAllowed { event: EventType::IP, phase: ContractPhase::Matured })
+        );
+
+        let mut rolled = terms.clone();
+        assert_eq!(
+            TransitionEngine::roll_over(&mut state, &mut rolled, 3000, None),
+            Err(Error::AlreadyTerminated)
+        );
+    }
+
+    #[test]
+    fn swaps_only_settle_until_they_are_over() {
+        let swap = ContractTerms {
+            contract_type: ContractType::SWAPS,
+            status_date: 100,
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&swap);
+        assert_eq!(state.phase, ContractPhase::PreInitialExchange);
+
+        TransitionEngine::settle_swap(&mut state, 200).unwrap();
+        assert_eq!((state.status_date, state.phase), (200, ContractPhase::Active));
+        assert_eq!(
+            TransitionEngine::settle_swap(&mut state, 150),
+            Err(Error::EventBeforeStatusDate { event_time: 150, status_date: 200 })
+        );
+
+        for phase in [ContractPhase::Terminated, ContractPhase::WrittenOff] {
+            let mut over = ContractState { phase, ..state.clone() };
+            assert_eq!(
+                TransitionEngine::settle_swap(&mut over, 300),
+                Err(Error::EventNotAllowed { event: EventType::STD, phase })
+            );
+            assert_eq!((over.status_date, over.phase), (200, phase));
+        }
+    }
+
+    /// Terms that deserialize fine but make little financial sense
+    fn pathological_terms() -> Vec<ContractTerms> {
+        let day = math::SECONDS_PER_DAY;
+        let cycle = |n, p| Cycle { n, p, stub: Stub::ShortStub, include_end_day: false };
+        let dccs = [
+            DayCountConvention::DCC_A_AISDA,
+            DayCountConvention::DCC_A_360,
+            DayCountConvention::DCC_A_365,
+            DayCountConvention::DCC_E30_360ISDA,
+            DayCountConvention::DCC_E30_360,
+            DayCountConvention::DCC_B_252,
+        ];
+
+        let mut all = Vec::new();
+        for contract_type in [
+            ContractType::PAM,
+            ContractType::LAM,
+            ContractType::NAM,
+            ContractType::ANN,
+            ContractType::STK,
+        ] {
+            // None everywhere
+            all.push(ContractTerms { contract_type, ..Default::default() });
+
+            for dcc in dccs {
+                // zero notional with a nonzero rate
+                all.push(ContractTerms {
+                    contract_type,
+                    initial_exchange_date: Some(0),
+                    notional_principal: Some(0),
+                    nominal_interest_rate: Some(500),
+                    day_count_convention: Some(dcc),
+                    maturity_date: Some(365 * day),
+                    ..Default::default()
+                });
+
+                // maturity on the initial exchange date
+                all.push(ContractTerms {
+                    contract_type,
+                    initial_exchange_date: Some(40 * day),
+                    notional_principal: Some(1_000),
+                    nominal_interest_rate: Some(500),
+                    day_count_convention: Some(dcc),
+                    maturity_date: Some(40 * day),
+                    status_date: 40 * day,
+                    ..Default::default()
+                });
+
+                // values at the edge of their ranges
+                all.push(ContractTerms {
+                    contract_type,
+                    initial_exchange_date: Some(0),
+                    notional_principal: Some(Units::MAX),
+                    nominal_interest_rate: Some(Rate::MAX),
+                    next_principal_redemption_payment: Some(Units::MAX),
+                    accrued_interest: Some(SignedUnits::MAX),
+                    day_count_convention: Some(dcc),
+                    maturity_date: Some(u64::MAX),
+                    cycle_anchor_date_of_interest_payment: Some(0),
+                    cycle_of_interest_payment: Some(cycle(i64::MAX, Period::P_M)),
+                    cycle_anchor_date_of_principal_redemption: Some(u64::MAX - 1),
+                    cycle_of_principal_redemption: Some(cycle(1, Period::P_D)),
+                    prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+                    ..Default::default()
+                });
+            }
+
+            // degenerate cycles
+            for n in [i64::MIN, -1, 0, 1] {
+                all.push(ContractTerms {
+                    contract_type,
+                    initial_exchange_date: Some(0),
+                    notional_principal: Some(1_000),
+                    maturity_date: Some(u64::MAX),
+                    cycle_anchor_date_of_interest_payment: Some(1),
+                    cycle_of_interest_payment: Some(cycle(n, Period::P_Y)),
+                    cycle_anchor_date_of_principal_redemption: Some(1),
+                    cycle_of_principal_redemption: Some(cycle(n, Period::P_W)),
+                    ..Default::default()
+                });
+            }
+        }
+
+        all
+    }
+
+    #[test]
+    fn test_pathological_terms_never_panic() {
+        let timestamps = [0, 1, 40 * math::SECONDS_PER_DAY, u64::MAX - 1, u64::MAX];
+
+        for terms in pathological_terms() {
+            for event in EventType::ALL {
+                for timestamp in timestamps {
+                    let mut state = ContractState::new(&terms);
+                    let _ = TransitionEngine::next_event(&state, &terms);
+                    let last = Some((event, timestamp));
+                    let _ = TransitionEngine::next_occurrence(event, &state, &terms, last);
+
+                    // IED first, so events also run against an outstanding notional
+                    for event in [EventType::IED, event, EventType::CE, event] {
+                        let _ = TransitionEngine::process_event(event, timestamp, &mut state, &terms);
+                        let _ = TransitionEngine::next_event(&state, &terms);
+                    }
+
+                    let (mut rolled_state, mut rolled_terms) = (state.clone(), terms.clone());
+                    let _ = TransitionEngine::roll_over(
+                        &mut rolled_state,
+                        &mut rolled_terms,
+                        u64::MAX,
+                        Some(Rate::MAX),
+                    );
+                    let _ = TransitionEngine::write_off(&mut state, timestamp);
+                }
+            }
+        }
+    }
+
+    /// The payoff, notional and accrued interest after each event of [`GOLDEN_EVENTS`]
+    type Trace = Vec<(Option<SignedUnits>, Units, SignedUnits)>;
+
+    /// Every event each type handles or ignores, including an MD off its date. An active
+    /// contract rejects a second IED, so an AD stands in for the one it used to ignore.
+    const GOLDEN_EVENTS: [(EventType, u64); 12] = [
+        (EventType::IED, 0),
+        (EventType::AD, 10),
+        (EventType::AD, 30),
+        (EventType::IP, 91),
+        (EventType::FP, 120),
+        (EventType::RR, 150),
+        (EventType::PP, 180),
+        (EventType::PR, 182),
+        (EventType::IPCI, 200),
+        (EventType::IP, 273),
+        (EventType::MD, 300),
+        (EventType::MD, 365),
+    ];
+
+    fn golden_trace(contract_type: ContractType) -> Trace {
+        let day = math::SECONDS_PER_DAY;
+        let anchor = Some(91 * day);
+        let terms = ContractTerms {
+            contract_type,
+            initial_exchange_date: Some(0),
+            maturity_date: Some(365 * day),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: anchor,
+            cycle_of_interest_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            next_principal_redemption_payment: (contract_type != ContractType::ANN)
+                .then_some(100_000),
+            prepayment_effect: Some(PrepaymentEffect::PPEF_A),
+            ..Default::default()
+        };
+        let mut state = ContractState::new(&terms);
+
+        GOLDEN_EVENTS
+            .into_iter()
+            .map(|(event, days)| {
+                let payoff = TransitionEngine::process_event(event, days * day, &mut state, &terms)
+                    .unwrap();
+                (payoff, state.notional_principal, state.accrued_interest)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn golden_cashflows_are_unchanged() {
+        #[rustfmt::skip]
+        let golden: [(ContractType, Trace); 4] = [
+            (ContractType::PAM, vec![
+                (Some(1_000_000), 1_000_000, 0), (None, 1_000_000, 1_365),
+                (None, 1_000_000, 4_100), (Some(12_455), 1_000_000, 0),
+                (None, 1_000_000, 3_970), (None, 1_000_000, 8_075),
+                (Some(100_000), 1_000_000, 12_180), (None, 1_000_000, 12_450),
+                (None, 1_000_000, 14_915), (Some(24_915), 1_000_000, 0),
+                (None, 1_000_000, 3_695), (Some(1_012_595), 0, 0),
+            ]),
+            (ContractType::LAM, vec![
+                (Some(1_000_000), 1_000_000, 0), (None, 1_000_000, 1_365),
+                (None, 1_000_000, 4_100), (Some(12_455), 1_000_000, 0),
+                (None, 1_000_000, 3_970), (None, 1_000_000, 8_075),
+                (None, 1_000_000, 12_180), (Some(100_000), 900_000, 12_450),
+                (None, 900_000, 14_668), (Some(23_668), 900_000, 0),
+                (None, 900_000, 3_325), (Some(911_335), 0, 0),
+            ]),
+            (ContractType::NAM, vec![
+                (Some(1_000_000), 1_000_000, 0), (None, 1_000_000, 1_365),
+                (None, 1_000_000, 4_100), (None, 1_000_000, 12_455),
+                (None, 1_000_000, 16_425), (None, 1_000_000, 20_530),
+                (None, 1_000_000, 24_635), (None, 1_000_000, 24_905),
+                (Some(27_370), 1_027_370, 0), (None, 1_027_370, 10_273),
+                (None, 1_027_370, 14_069), (Some(1_050_582), 0, 0),
+            ]),
+            (ContractType::ANN, vec![
+                (Some(1_000_000), 1_000_000, 0), (None, 1_000_000, 1_365),
+                (None, 1_000_000, 4_100), (Some(257_856), 754_599, 0),
+                (None, 754_599, 2_995), (None, 754_599, 6_092),
+                (None, 754_599, 9_189), (None, 754_599, 9_392),
+                (None, 754_599, 11_252), (Some(257_856), 515_540, 0),
+                (None, 515_540, 1_904), (Some(522_032), 0, 0),
+            ]),
+        ];
+
+        for (contract_type, trace) in golden {
+            assert_eq!(golden_trace(contract_type), trace, "{contract_type:?}");
+        }
+    }
+}
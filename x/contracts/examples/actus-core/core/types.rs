@@ -0,0 +1,2353 @@
+// src/type.rs
+
+#![allow(non_camel_case_types)]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, io};
+
+use super::{Error, Result};
+use crate::math;
+
+/// Amounts of terms, state and payoffs, in [`AMOUNT_DECIMALS`](math::AMOUNT_DECIMALS)
+/// fixed point. Payoffs narrow to the `u64` of the token when they are settled.
+pub type Units = u128;
+
+/// Amounts that may be owed either way, such as interest accrued at a negative rate.
+/// A negative payoff flows against the usual direction of its event.
+pub type SignedUnits = i128;
+
+/// Interest rates are expressed in basis points (500 = 5%) and may be negative
+pub type Rate = i64;
+
+////////////////////////////////////////////////////////////////////////////////
+// 1. Core "Timestamp" Type
+////////////////////////////////////////////////////////////////////////////////
+
+/// If you prefer a simple alias for Unix timestamp:
+pub type Timestamp = u64;
+
+////////////////////////////////////////////////////////////////////////////////
+// 2. EVENT TYPES
+////////////////////////////////////////////////////////////////////////////////
+
+/// All ACTUS event types, matching "BusinessEvents.hs"
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Ord, PartialOrd, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum EventType {
+    IED  = 0,   // Initial Exchange
+    FP   = 1,   // Fee Payment
+    PR   = 2,   // Principal Redemption
+    PD   = 3,   // Principal Drawing
+    PY   = 4,   // Penalty Payment
+    PP   = 5,   // Principal Prepayment
+    IP   = 6,   // Interest Payment
+    IPFX = 7,   // Interest Payment Fixed Leg
+    IPFL = 8,   // Interest Payment Floating Leg
+    IPCI = 9,   // Interest Capitalization
+    CE   = 10,  // Credit Event
+    RRF  = 11,  // Rate Reset Fixing with Known Rate
+    RR   = 12,  // Rate Reset Fixing with Unknown Rate
+    PRF  = 13,  // Principal Payment Amount Fixing
+    DV   = 14,  // Dividend Payment
+    PRD  = 15,  // Purchase
+    MR   = 16,  // Margin Call
+    TD   = 17,  // Termination
+    SC   = 18,  // Scaling Index Fixing
+    IPCB = 19,  // Interest Calculation Base Fixing
+    MD   = 20,  // Maturity
+    XD   = 21,  // Exercise
+    STD  = 22,  // Settlement
+    PI   = 23,  // Principal Increase
+    AD   = 24,  // Monitoring
+    WO   = 25,  // Write-off (not an ACTUS event, only recorded by `write_off`)
+    AMD  = 26,  // Amendment applied (not an ACTUS event, recorded when one is accepted)
+    AMX  = 27,  // Amendment proposal rejected or expired (not an ACTUS event)
+}
+
+impl EventType {
+    /// All event types, indexed by their `u8` discriminant
+    pub const ALL: [EventType; 28] = [
+        EventType::IED, EventType::FP, EventType::PR, EventType::PD, EventType::PY,
+        EventType::PP, EventType::IP, EventType::IPFX, EventType::IPFL, EventType::IPCI,
+        EventType::CE, EventType::RRF, EventType::RR, EventType::PRF, EventType::DV,
+        EventType::PRD, EventType::MR, EventType::TD, EventType::SC, EventType::IPCB,
+        EventType::MD, EventType::XD, EventType::STD, EventType::PI, EventType::AD,
+        EventType::WO, EventType::AMD, EventType::AMX,
+    ];
+
+    /// Whether the payoff of this event flows from the creditor to the debtor
+    /// (disbursements and purchases)
+    pub fn paid_by_creditor(self) -> bool {
+        matches!(self, EventType::IED | EventType::PD | EventType::PI | EventType::PRD)
+    }
+
+    /// Whether the event is only recorded by its own entrypoint, never processed
+    pub fn is_recorded_only(self) -> bool {
+        matches!(self, EventType::WO | EventType::AMD | EventType::AMX)
+    }
+
+    /// Whether the event is a scheduled payment a skipped date drops
+    pub fn is_skippable(self) -> bool {
+        matches!(self, EventType::FP | EventType::IP | EventType::PR)
+    }
+}
+
+impl TryFrom<u8> for EventType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or_else(|| Error::ValidationError(format!("Unknown event type {value}")))
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// 3. CONTRACT STATE
+////////////////////////////////////////////////////////////////////////////////
+
+/// This mirrors "ContractState.hs".
+/// We've replaced `LocalTime` with `Timestamp` (u64).
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct ContractState {
+    // tmd  :: Maybe LocalTime
+    #[cfg_attr(feature = "serde", serde(rename = "maturityDate"))]
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub time_of_maturity: Option<Timestamp>,
+
+    // nt   :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub notional_principal: Units,
+
+    // ipnr :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub nominal_interest_rate: Rate,
+
+    // ipac :: a, negative when the holder owes interest
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub accrued_interest: SignedUnits,
+
+    // ipac1 :: Maybe a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub accrued_interest_first_leg: Option<Units>,
+
+    // ipac2 :: Maybe a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub accrued_interest_second_leg: Option<Units>,
+
+    // ipla :: Maybe a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub last_interest_period: Option<u64>,
+
+    // feac :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub fee_accrued: Units,
+
+    // nsc :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub notional_scaling_multiplier: u64,
+
+    // isc :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub interest_scaling_multiplier: u64,
+
+    // prf :: PRF
+    pub contract_performance: ContractPerformance,
+
+    // sd   :: LocalTime
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub status_date: Timestamp,
+
+    // prnxt :: a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub next_principal_redemption_payment: Units,
+
+    // ipcb :: a
+    #[cfg_attr(feature = "serde", serde(rename = "interestCalculationBaseAmount"))]
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub interest_calculation_base: Units,
+
+    // xd :: Maybe LocalTime
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub exercise_date: Option<Timestamp>,
+
+    // xa :: Maybe a
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub exercise_amount: Option<Units>,
+
+    // Payments the debtor missed, settled with the delinquency charge by their next one
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub overdue_amount: Units,
+
+    // Due date of the earliest missed payment still overdue
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub overdue_since: Option<Timestamp>,
+
+    // Set once the penalty of the overdue payments is paid, so settling them does not
+    // charge it again
+    pub penalty_assessed: bool,
+
+    // Reference rate last observed at a rate reset, which penalties on the interest
+    // differential compare the nominal rate with
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub reference_rate: Option<Rate>,
+
+    // Rate fixed at a reset in the middle of an interest period, which under `CP_B`
+    // applies from the next one
+    #[cfg_attr(feature = "serde", serde(with = "super::json::option_string"))]
+    pub reset_rate: Option<Rate>,
+
+    // Where the contract is in its life, which decides the events it accepts
+    pub phase: ContractPhase,
+
+    // What the creditor side is owed beyond the payoffs settled so far, the fraction of
+    // the currency's smallest unit they truncated, negative when it owes
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub payoff_residual: SignedUnits,
+
+    // Interest paid to the creditor side to date, including that paid in advance at the
+    // IED, net of what a negative rate paid the debtor
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub total_interest_paid: SignedUnits,
+
+    // Principal repaid by redemptions, prepayments, repayments and the MD to date
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub total_principal_repaid: Units,
+
+    // Fees paid by FP events and the MD to date
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub total_fees_paid: Units,
+
+    // Late-payment penalties and delinquency charges paid to date
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub total_penalties_paid: Units,
+
+    // The interest, fees and penalties of the overdue amount, the rest of which is
+    // principal. They count towards the totals once it is settled.
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub overdue_interest: Units,
+
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub overdue_fees: Units,
+
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub overdue_penalties: Units,
+}
+
+impl ContractState {
+    /// Builds the pre-IED state for `terms`; notional and rate are set when IED is processed.
+    /// Cash has no IED, and holds its notional from the status date.
+    pub fn new(terms: &ContractTerms) -> Self {
+        let cash = terms.contract_type == ContractType::CSH;
+
+        Self {
+            time_of_maturity: terms.maturity_date,
+            notional_principal: if cash { terms.notional_principal.unwrap_or(0) } else { 0 },
+            nominal_interest_rate: 0,
+            accrued_interest: terms.accrued_interest.unwrap_or(0),
+            accrued_interest_first_leg: None,
+            accrued_interest_second_leg: None,
+            last_interest_period: None,
+            fee_accrued: terms.fee_accrued.unwrap_or(0),
+            notional_scaling_multiplier: terms.notional_scaling_multiplier.unwrap_or(SCALING_FACTOR_ONE),
+            interest_scaling_multiplier: terms.interest_scaling_multiplier.unwrap_or(SCALING_FACTOR_ONE),
+            contract_performance: terms.contract_performance.unwrap_or(ContractPerformance::PF),
+            status_date: terms.status_date,
+            next_principal_redemption_payment: terms.next_principal_redemption_payment.unwrap_or(0),
+            interest_calculation_base: 0,
+            exercise_date: terms.exercise_date,
+            exercise_amount: None,
+            overdue_amount: 0,
+            overdue_since: None,
+            penalty_assessed: false,
+            reference_rate: None,
+            reset_rate: None,
+            phase: if cash { ContractPhase::Active } else { ContractPhase::PreInitialExchange },
+            payoff_residual: 0,
+            total_interest_paid: 0,
+            total_principal_repaid: 0,
+            total_fees_paid: 0,
+            total_penalties_paid: 0,
+            overdue_interest: 0,
+            overdue_fees: 0,
+            overdue_penalties: 0,
+        }
+    }
+}
+
+/// A processed event and the payoff it produced, as kept in the event history
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventRecord {
+    pub event_type: EventType,
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub timestamp: Timestamp,
+    pub payoff: Option<SignedUnits>,
+    /// The late-payment penalty included in the payoff, if any
+    pub penalty: Option<SignedUnits>,
+    /// The principal an MD settles beyond the last regular redemption, if any
+    pub residual: Option<SignedUnits>,
+    /// Block timestamp at which the event was processed
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub processed_at: Timestamp,
+}
+
+/// A payment the debtor left unpaid under a shortfall [`SettlementPolicy`], in the base
+/// unit of the settlement currency
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Obligation {
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub amount: Units,
+    /// The event whose payment fell short
+    pub event: EventType,
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub due_date: Timestamp,
+}
+
+/// A change to the terms of a live contract the parties agree to, applied by
+/// [`TransitionEngine::amend`](super::TransitionEngine::amend)
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Amendment {
+    /// The rate interest accrues at from the amendment on
+    pub rate: Option<Rate>,
+    pub maturity: Option<Timestamp>,
+    /// Scheduled payment dates to skip, whose amounts the next payment settles
+    pub skipped_dates: Vec<Timestamp>,
+}
+
+/// The side of a contract a payoff flows from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PayoffDirection {
+    DebtorToCreditor,
+    CreditorToDebtor,
+}
+
+impl PayoffDirection {
+    /// Disbursements flow from the creditor side, everything else towards it, and
+    /// negative payoffs such as interest at a negative rate the other way
+    pub fn of(event: EventType, payoff: SignedUnits) -> Self {
+        if event.paid_by_creditor() != (payoff < 0) {
+            Self::CreditorToDebtor
+        } else {
+            Self::DebtorToCreditor
+        }
+    }
+}
+
+/// What processing an event did: the event, its payoff as an amount and the side paying
+/// it, and the notional and accrued interest it left
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessEventResult {
+    pub event: EventType,
+    #[cfg_attr(feature = "serde", serde(with = "super::json::string"))]
+    pub timestamp: Timestamp,
+    /// `None` for events without a payoff, such as an AD
+    pub payoff: Option<(Units, PayoffDirection)>,
+    pub notional_after: Units,
+    pub accrued_after: SignedUnits,
+}
+
+impl ProcessEventResult {
+    /// The result of `event` at `timestamp` paying `payoff` and leaving `state`
+    pub fn new(
+        event: EventType,
+        timestamp: Timestamp,
+        payoff: Option<SignedUnits>,
+        state: &ContractState,
+    ) -> Self {
+        Self {
+            event,
+            timestamp,
+            payoff: payoff.map(|p| (p.unsigned_abs(), PayoffDirection::of(event, p))),
+            notional_after: state.notional_principal,
+            accrued_after: state.accrued_interest,
+        }
+    }
+
+    /// The payoff signed as [`process_event`](super::TransitionEngine::process_event)
+    /// returns it, negative when it flows against the usual direction of the event
+    pub fn signed_payoff(&self) -> Option<SignedUnits> {
+        self.payoff.map(|(amount, direction)| {
+            // both wrap, so the magnitude of `SignedUnits::MIN` maps back to it
+            let amount = amount as SignedUnits;
+            if direction == PayoffDirection::of(self.event, 1) {
+                amount
+            } else {
+                amount.wrapping_neg()
+            }
+        })
+    }
+}
+
+/// Scaling multipliers are expressed in basis points, so 1.0 is 10_000
+pub const SCALING_FACTOR_ONE: u64 = 10_000;
+
+/// Contract performance states, from `PRF_PF, PRF_DL, PRF_DQ, PRF_DF`.
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ContractPerformance {
+    PF = 0, // Performant
+    DL = 1, // Delayed
+    DQ = 2, // Delinquent
+    DF = 3, // Default
+}
+
+/// Where a contract is in its life. The IED makes it active, and the MD, a prepayment
+/// leaving nothing outstanding or a write-off end it. A default is a performance state rather
+/// than a phase: a defaulted contract stays active until it is written off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ContractPhase {
+    PreInitialExchange = 0,
+    Active = 1,
+    Matured = 2,
+    Terminated = 3,
+    WrittenOff = 4,
+}
+
+impl ContractPhase {
+    /// Whether `event` can be processed in the phase. Nothing is paid before the IED, or
+    /// the PRD of a stock or commodity, and neither happens more than once. A matured or
+    /// terminated contract still takes AD events for monitoring, which accrue nothing; a
+    /// written-off one takes none.
+    pub fn allows(self, event: EventType) -> bool {
+        match self {
+            ContractPhase::PreInitialExchange => !matches!(
+                event,
+                EventType::IP
+                    | EventType::IPCI
+                    | EventType::PR
+                    | EventType::PI
+                    | EventType::MD
+                    | EventType::PP
+                    | EventType::PY
+                    | EventType::FP
+                    | EventType::DV
+                    | EventType::TD
+            ),
+            ContractPhase::Active => !matches!(event, EventType::IED | EventType::PRD),
+            ContractPhase::Matured | ContractPhase::Terminated => event == EventType::AD,
+            ContractPhase::WrittenOff => false,
+        }
+    }
+
+    /// Whether the contract has matured, been terminated or been written off
+    pub fn is_over(self) -> bool {
+        matches!(
+            self,
+            ContractPhase::Matured | ContractPhase::Terminated | ContractPhase::WrittenOff
+        )
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// 4. CONTRACT TERMS
+////////////////////////////////////////////////////////////////////////////////
+
+/// Fixed-size contract identifier. An id of up to 32 bytes is kept right-padded with
+/// zeros and displays as its text; a longer one is replaced by its SHA-256 hash and
+/// displays as `0x` followed by 64 hex digits, which `from` parses back.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, BorshSerialize, BorshDeserialize,
+)]
+pub struct ContractId(pub [u8; ContractId::LEN]);
+
+impl ContractId {
+    pub const LEN: usize = 32;
+
+    /// The text of a padded id, unset for hashed ones
+    pub fn text(&self) -> Option<&str> {
+        let end = self.0.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1);
+        std::str::from_utf8(&self.0[..end])
+            .ok()
+            .filter(|text| !text.chars().any(char::is_control))
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        from_hex(hex).map(Self)
+    }
+}
+
+/// The `N` bytes `0x` and `2 * N` hex digits spell
+fn from_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    let digits = hex.strip_prefix("0x").filter(|digits| digits.len() == 2 * N)?;
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(digits.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+impl From<&str> for ContractId {
+    fn from(id: &str) -> Self {
+        if let Some(hashed) = Self::from_hex(id) {
+            return hashed;
+        }
+
+        let mut padded = [0; Self::LEN];
+        match id.len() {
+            len if len <= Self::LEN => padded[..len].copy_from_slice(id.as_bytes()),
+            _ => padded = Sha256::digest(id.as_bytes()).into(),
+        }
+        Self(padded)
+    }
+}
+
+impl fmt::Display for ContractId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.text() {
+            Some(text) => f.write_str(text),
+            None => {
+                f.write_str("0x")?;
+                self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+            },
+        }
+    }
+}
+
+impl fmt::Debug for ContractId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ContractId({self})")
+    }
+}
+
+/// Address of another contract on chain, the bytes of a wasmlanche `Address`, for terms
+/// that refer to one. Displays as `0x` followed by 66 hex digits, which it parses from.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct ContractAddress(pub [u8; ContractAddress::LEN]);
+
+impl ContractAddress {
+    pub const LEN: usize = 33;
+}
+
+impl std::str::FromStr for ContractAddress {
+    type Err = Error;
+
+    fn from_str(address: &str) -> Result<Self> {
+        from_hex(address)
+            .map(Self)
+            .ok_or_else(|| Error::ValidationError(format!("Invalid contract address {address}")))
+    }
+}
+
+impl fmt::Display for ContractAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("0x")?;
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl fmt::Debug for ContractAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ContractAddress({self})")
+    }
+}
+
+/// Current layout of serialized [`ContractTerms`], see [`ContractTerms::to_versioned_bytes`]
+pub const TERMS_VERSION: u8 = 21;
+
+/// The version each term appended after the label was added in. Terms of an earlier
+/// version decode with those unset, each taking a single zero byte.
+const APPENDED_TERMS: [u8; 33] = [
+    3, 3, 4, 5, 6, 6, 6, 6, 7, 8, 9, 10, 11, 12, 13, 13, 13, 14, 14, 14, 15, 16, 16, 17, 17, 17,
+    17, 18, 18, 19, 20, 20, 21,
+];
+
+/// Leads versioned terms. Terms serialized before versioning start with the `u32`
+/// length of their string contract id, which is never `u32::MAX`.
+pub const TERMS_VERSION_MARKER: [u8; 4] = [0xff; 4];
+
+/// Largest serialized terms [`ContractTerms::from_versioned_bytes`] accepts, in bytes
+pub const MAX_TERMS_LEN: usize = 4096;
+
+/// Longest string term, in bytes: market object codes, currencies and the label. The
+/// length prefix is checked before anything is allocated for the string.
+pub const MAX_STRING_TERM_LEN: usize = 128;
+
+/// A string term whose length prefix exceeds [`MAX_STRING_TERM_LEN`]
+#[derive(Debug)]
+struct TermTooLong {
+    field: &'static str,
+}
+
+impl fmt::Display for TermTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` exceeds {MAX_STRING_TERM_LEN} bytes", self.field)
+    }
+}
+
+impl std::error::Error for TermTooLong {}
+
+/// `deserialize_with` readers of the string terms, each failing with its field's name
+mod bounded {
+    use super::{TermTooLong, MAX_STRING_TERM_LEN};
+    use borsh::{io, BorshDeserialize};
+
+    fn read<R: io::Read>(reader: &mut R, field: &'static str) -> io::Result<Option<String>> {
+        if !bool::deserialize_reader(reader)? {
+            return Ok(None);
+        }
+
+        let len = u32::deserialize_reader(reader)? as usize;
+        if len > MAX_STRING_TERM_LEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, TermTooLong { field }));
+        }
+
+        let mut bytes = vec![0; len];
+        reader.read_exact(&mut bytes)?;
+        String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    macro_rules! string_terms {
+        ($($field:ident),* $(,)?) => {
+            $(
+                pub fn $field<R: io::Read>(reader: &mut R) -> io::Result<Option<String>> {
+                    read(reader, stringify!($field))
+                }
+            )*
+        };
+    }
+
+    string_terms!(
+        settlement_currency,
+        market_object_code,
+        currency,
+        currency2,
+        market_object_code_of_scaling_index,
+        market_object_code_of_rate_reset,
+        contract_label,
+        market_object_code_of_underlying,
+        market_object_code_of_dividends,
+    );
+}
+
+/// Matches "ContractTerms.hs" but uses simple `Timestamp` for date/time fields.
+/// Amounts are [`Units`], or [`SignedUnits`] where they may be owed either way; other
+/// numeric fields use `u64`. Rates are signed [`Rate`]s in basis points.
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ContractTerms {
+    // General
+    pub contract_id: ContractId,
+    pub contract_type: ContractType,
+    pub contract_role: ContractRole,
+    /// A currency code like [`currency`](Self::currency). The token the payoffs settle in
+    /// is not a term but the address `init` is given.
+    #[borsh(deserialize_with = "bounded::settlement_currency")]
+    pub settlement_currency: Option<String>,
+
+    // Calendar
+    pub initial_exchange_date: Option<Timestamp>,
+    pub day_count_convention: Option<DayCountConvention>,
+    pub schedule_config: ScheduleConfig,
+
+    // Identification
+    pub status_date: Timestamp,
+    #[borsh(deserialize_with = "bounded::market_object_code")]
+    pub market_object_code: Option<String>,
+
+    // Performance / credit
+    pub contract_performance: Option<ContractPerformance>,
+    // If you want creditEventTypeCovered, add it here:
+    // pub credit_event_type_covered: Option<CreditEventTypeCovered>,
+
+    // Fees
+    pub cycle_of_fee: Option<Cycle>,
+    pub cycle_anchor_date_of_fee: Option<Timestamp>,
+    pub fee_accrued: Option<Units>,
+    pub fee_basis: Option<FeeBasis>,
+    pub fee_rate: Option<u64>,
+
+    // Interest
+    pub cycle_anchor_date_of_interest_payment: Option<Timestamp>,
+    pub cycle_of_interest_payment: Option<Cycle>,
+    pub accrued_interest: Option<SignedUnits>,
+    pub capitalization_end_date: Option<Timestamp>,
+    pub cycle_anchor_date_of_interest_calculation_base: Option<Timestamp>,
+    pub cycle_of_interest_calculation_base: Option<Cycle>,
+    pub interest_calculation_base: Option<IPCB>,
+    pub interest_calculation_base_amount: Option<Units>,
+    pub nominal_interest_rate: Option<Rate>,
+    pub nominal_interest_rate2: Option<Rate>,
+    pub interest_scaling_multiplier: Option<u64>,
+
+    // Dates
+    pub maturity_date: Option<Timestamp>,
+    pub amortization_date: Option<Timestamp>,
+    pub exercise_date: Option<Timestamp>,
+
+    // Notional
+    pub notional_principal: Option<Units>,
+    pub premium_discount_at_ied: Option<Units>,
+    pub cycle_anchor_date_of_principal_redemption: Option<Timestamp>,
+    pub cycle_of_principal_redemption: Option<Cycle>,
+    pub next_principal_redemption_payment: Option<Units>,
+    pub purchase_date: Option<Timestamp>,
+    pub price_at_purchase_date: Option<Units>,
+    pub termination_date: Option<Timestamp>,
+    pub price_at_termination_date: Option<Units>,
+    pub quantity: Option<u64>,
+    #[borsh(deserialize_with = "bounded::currency")]
+    pub currency: Option<String>,
+    #[borsh(deserialize_with = "bounded::currency2")]
+    pub currency2: Option<String>,
+
+    // Scaling
+    pub scaling_effect: Option<ScalingEffect>,
+    pub scaling_index_at_status_date: Option<u64>,
+    pub cycle_anchor_date_of_scaling_index: Option<Timestamp>,
+    pub cycle_of_scaling_index: Option<Cycle>,
+    pub scaling_index_at_contract_deal_date: Option<u64>,
+    #[borsh(deserialize_with = "bounded::market_object_code_of_scaling_index")]
+    pub market_object_code_of_scaling_index: Option<String>,
+    pub notional_scaling_multiplier: Option<u64>,
+
+    // Rate Reset Fields
+    pub cycle_anchor_date_of_rate_reset: Option<Timestamp>,
+    pub cycle_of_rate_reset: Option<Cycle>,
+    // Added to the observed rate at each reset
+    pub rate_spread: Option<Rate>,
+    #[borsh(deserialize_with = "bounded::market_object_code_of_rate_reset")]
+    pub market_object_code_of_rate_reset: Option<String>,
+    // pub next_reset_rate: Option<Rate>,
+
+    // Penalty
+    pub penalty_rate: Option<u64>,
+    pub penalty_type: Option<PenaltyType>,
+    pub prepayment_effect: Option<PrepaymentEffect>,
+
+    // Human-readable name of the contract, appended last so unversioned terms decode
+    // by appending an unset label
+    #[borsh(deserialize_with = "bounded::contract_label")]
+    pub contract_label: Option<String>,
+
+    // Delinquency, see `APPENDED_TERMS`
+    // Time past its due date a missed payment is delayed rather than delinquent
+    pub grace_period: Option<u64>,
+    // Charged on overdue payments settled after the grace period, from their due date
+    pub delinquency_rate: Option<u64>,
+
+    // Rate reset, see `APPENDED_TERMS`
+    pub cycle_point_of_rate_reset: Option<CyclePoint>,
+    // Time before an RR that its rate is observed at
+    pub fixing_period: Option<u64>,
+
+    // Option exercise, see `APPENDED_TERMS`
+    pub option_exercise_type: Option<OptionExerciseType>,
+    // First date an American option can be exercised on, the IED if unset
+    pub option_exercise_start_date: Option<Timestamp>,
+    // Expiry: the exercise date of a European option and the last of the others
+    pub option_exercise_end_date: Option<Timestamp>,
+    // The dates a Bermudan option can be exercised on
+    pub option_exercise_dates: Option<Vec<Timestamp>>,
+
+    // Credit facility, see `APPENDED_TERMS`
+    // Most the notional can be drawn up to
+    pub credit_limit: Option<Units>,
+
+    // Interest timing, see `APPENDED_TERMS`
+    pub interest_timing: Option<InterestTiming>,
+
+    // Staged disbursement, see `APPENDED_TERMS`
+    // (date, amount) tranches of the notional, the first on the IED
+    pub disbursements: Option<Vec<(Timestamp, Units)>>,
+
+    // Payment holidays, see `APPENDED_TERMS`
+    // Dates whose scheduled FP, IP and PR are skipped, their amounts paid by the next
+    pub skipped_dates: Option<Vec<Timestamp>>,
+
+    // Time granularity, see `APPENDED_TERMS`
+    // The unit the dates and durations of the terms count in, seconds if unset
+    pub time_granularity: Option<TimeGranularity>,
+
+    // Settlement policy, see `APPENDED_TERMS`
+    // What a payment the debtor cannot cover does, reverting if unset
+    pub settlement_policy: Option<SettlementPolicy>,
+
+    // Option payoff, see `APPENDED_TERMS`
+    pub option_type: Option<OptionType>,
+    // The price of a unit of the underlying the option is struck at
+    pub option_strike1: Option<Units>,
+    // The risk factor quoting the price of a unit of the underlying
+    #[borsh(deserialize_with = "bounded::market_object_code_of_underlying")]
+    pub market_object_code_of_underlying: Option<String>,
+
+    // Guarantee, see `APPENDED_TERMS`
+    // The contract whose default a guarantee covers
+    pub covered_contract: Option<ContractAddress>,
+    // Share of the exposure covered in basis points, all of it if unset
+    pub coverage_of_credit_enhancement: Option<u64>,
+    // Whether the interest accrued is covered along with the notional, only that if unset
+    pub guaranteed_exposure: Option<GuaranteedExposure>,
+
+    // Call money, see `APPENDED_TERMS`
+    // Time after a call that the called contract matures
+    pub x_day_notice: Option<u64>,
+
+    // Rate limits, see `APPENDED_TERMS`
+    // Highest rate over the life of the contract, the strike of a cap
+    pub life_cap: Option<Rate>,
+    // Lowest rate over the life of the contract, the strike of a floor
+    pub life_floor: Option<Rate>,
+
+    // Dividends, see `APPENDED_TERMS`
+    pub cycle_anchor_date_of_dividend_payment: Option<Timestamp>,
+    pub cycle_of_dividend_payment: Option<Cycle>,
+    // Paid on each share at a DV, unless observed
+    pub next_dividend_payment_amount: Option<Units>,
+    // The risk factor quoting the dividend paid on each share
+    #[borsh(deserialize_with = "bounded::market_object_code_of_dividends")]
+    pub market_object_code_of_dividends: Option<String>,
+
+    // Swap legs, see `APPENDED_TERMS`
+    // The contract whose cashflows the holder of a swap receives
+    pub first_leg: Option<ContractAddress>,
+    // The contract whose cashflows the holder of a swap pays
+    pub second_leg: Option<ContractAddress>,
+
+    // Reset multiplier, see `APPENDED_TERMS`
+    // Multiplies the rate observed at a reset, in basis points, one if unset
+    pub rate_multiplier: Option<u64>,
+
+    // Period limits, see `APPENDED_TERMS`
+    // Most the rate may rise at a single reset
+    pub period_cap: Option<Rate>,
+    // Most the rate may fall at a single reset
+    pub period_floor: Option<Rate>,
+
+    // Market data, see `APPENDED_TERMS`
+    // The account publishing the observations the contract reads on-chain
+    pub market_object_provider: Option<ContractAddress>,
+}
+
+impl ContractTerms {
+    /// Names of all the terms, in declaration order, so errors naming one can be decoded
+    pub const FIELDS: [&'static str; 89] = [
+        "contract_id", "contract_type", "contract_role", "settlement_currency",
+        "initial_exchange_date", "day_count_convention", "schedule_config", "status_date",
+        "market_object_code", "contract_performance", "cycle_of_fee",
+        "cycle_anchor_date_of_fee", "fee_accrued", "fee_basis", "fee_rate",
+        "cycle_anchor_date_of_interest_payment", "cycle_of_interest_payment",
+        "accrued_interest", "capitalization_end_date",
+        "cycle_anchor_date_of_interest_calculation_base", "cycle_of_interest_calculation_base",
+        "interest_calculation_base", "interest_calculation_base_amount",
+        "nominal_interest_rate", "nominal_interest_rate2", "interest_scaling_multiplier",
+        "maturity_date", "amortization_date", "exercise_date", "notional_principal",
+        "premium_discount_at_ied", "cycle_anchor_date_of_principal_redemption",
+        "cycle_of_principal_redemption", "next_principal_redemption_payment", "purchase_date",
+        "price_at_purchase_date", "termination_date", "price_at_termination_date", "quantity",
+        "currency", "currency2", "scaling_effect", "scaling_index_at_status_date",
+        "cycle_anchor_date_of_scaling_index", "cycle_of_scaling_index",
+        "scaling_index_at_contract_deal_date", "market_object_code_of_scaling_index",
+        "notional_scaling_multiplier", "cycle_anchor_date_of_rate_reset", "cycle_of_rate_reset",
+        "rate_spread", "market_object_code_of_rate_reset", "penalty_rate", "penalty_type",
+        "prepayment_effect", "contract_label", "grace_period", "delinquency_rate",
+        "cycle_point_of_rate_reset", "fixing_period", "option_exercise_type",
+        "option_exercise_start_date", "option_exercise_end_date", "option_exercise_dates",
+        "credit_limit", "interest_timing", "disbursements", "skipped_dates", "time_granularity",
+        "settlement_policy", "option_type", "option_strike1",
+        "market_object_code_of_underlying", "covered_contract",
+        "coverage_of_credit_enhancement", "guaranteed_exposure", "x_day_notice", "life_cap",
+        "life_floor", "cycle_anchor_date_of_dividend_payment", "cycle_of_dividend_payment",
+        "next_dividend_payment_amount", "market_object_code_of_dividends", "first_leg",
+        "second_leg", "rate_multiplier", "period_cap", "period_floor", "market_object_provider",
+    ];
+
+    /// Names of the terms the supported contract types require: a stock or commodity only
+    /// its purchase date, cash only its notional, a swap of two contracts none, and the
+    /// others all but the purchase date, though a UMP goes without a maturity date and a
+    /// CLM is only given one once called
+    pub const REQUIRED: [&'static str; 4] =
+        ["initial_exchange_date", "notional_principal", "maturity_date", "purchase_date"];
+
+    /// Fails with [`Error::MissingTerm`] naming the first required term that is not set
+    pub fn check_required(&self) -> Result<()> {
+        let traded = self.contract_type.is_traded();
+        let swap = self.contract_type == ContractType::SWAPS;
+        // neither cash nor a swap of two other contracts is exchanged or matures
+        let unscheduled = swap || self.contract_type == ContractType::CSH;
+        let set = [
+            self.initial_exchange_date.is_some() || traded || unscheduled,
+            self.notional_principal.is_some() || traded || swap,
+            self.maturity_date.is_some()
+                || matches!(self.contract_type, ContractType::UMP | ContractType::CLM)
+                || traded
+                || unscheduled,
+            self.purchase_date.is_some() || !traded,
+        ];
+
+        match Self::REQUIRED.into_iter().zip(set).find(|(_, set)| !set) {
+            Some((field, _)) => Err(Error::MissingTerm { field }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails unless the [`disbursements`](Self::disbursements), if any, start on the IED,
+    /// follow each other, sum to the notional and all fall before maturity and the first
+    /// PR
+    pub fn check_disbursements(&self) -> Result<()> {
+        let Some(tranches) = self.disbursements.as_deref() else {
+            return Ok(());
+        };
+
+        let invalid = |reason: &str| Err(Error::ValidationError(format!("Disbursements {reason}")));
+        let Some(((first, _), (last, _))) = tranches.first().zip(tranches.last()) else {
+            return invalid("must not be empty");
+        };
+        if Some(*first) != self.initial_exchange_date {
+            return invalid("must start on the IED");
+        }
+        if tranches.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return invalid("must be in chronological order");
+        }
+        if tranches.iter().any(|(_, amount)| *amount == 0) {
+            return invalid("must not be zero");
+        }
+
+        let total = tranches
+            .iter()
+            .try_fold(0 as Units, |total, (_, amount)| total.checked_add(*amount));
+        if total != self.notional_principal {
+            return invalid("must sum to the notional");
+        }
+        let first_redemption = self.cycle_anchor_date_of_principal_redemption;
+        if [self.maturity_date, first_redemption].into_iter().flatten().any(|end| *last >= end) {
+            return invalid("must all fall before maturity and the first PR");
+        }
+
+        Ok(())
+    }
+
+    /// The event the contract opens with and its date: the PRD of a stock or commodity,
+    /// which is bought rather than lent, and the IED of the others
+    pub fn opening(&self) -> (EventType, Option<Timestamp>) {
+        match self.contract_type.is_traded() {
+            true => (EventType::PRD, self.purchase_date),
+            false => (EventType::IED, self.initial_exchange_date),
+        }
+    }
+
+    /// The event the contract ends with, if it is scheduled, and its date: the TD of a
+    /// stock or commodity, which is sold rather than repaid, and the MD of the others
+    pub fn closing(&self) -> (EventType, Option<Timestamp>) {
+        match self.contract_type.is_traded() {
+            true => (EventType::TD, self.termination_date),
+            false => (EventType::MD, self.maturity_date),
+        }
+    }
+
+    /// The date the schedule runs up to: the [`closing`](Self::closing) date, or
+    /// [`OPEN_ENDED_HORIZON`] after the [`opening`](Self::opening) for a UMP, an uncalled
+    /// CLM, cash or a stock or commodity held indefinitely
+    pub fn schedule_end(&self) -> Timestamp {
+        self.closing().1.unwrap_or_else(|| {
+            let horizon = self.granularity().from_seconds(OPEN_ENDED_HORIZON);
+            self.opening().1.unwrap_or_default().saturating_add(horizon)
+        })
+    }
+
+    /// The unit the dates of the terms count in
+    pub fn granularity(&self) -> TimeGranularity {
+        self.time_granularity.unwrap_or_default()
+    }
+
+    /// What a payment the debtor cannot cover does
+    pub fn settlement(&self) -> SettlementPolicy {
+        self.settlement_policy.unwrap_or_default()
+    }
+
+    /// Fails unless every date term of a contract counted in days is at most
+    /// [`TimeGranularity::MAX_DAYS`], as one in seconds was likely given
+    pub fn check_dates(&self) -> Result<()> {
+        if self.granularity() != TimeGranularity::Days {
+            return Ok(());
+        }
+
+        let single = [
+            Some(self.status_date),
+            self.initial_exchange_date,
+            self.maturity_date,
+            self.amortization_date,
+            self.exercise_date,
+            self.purchase_date,
+            self.termination_date,
+            self.capitalization_end_date,
+            self.cycle_anchor_date_of_fee,
+            self.cycle_anchor_date_of_interest_payment,
+            self.cycle_anchor_date_of_interest_calculation_base,
+            self.cycle_anchor_date_of_principal_redemption,
+            self.cycle_anchor_date_of_scaling_index,
+            self.cycle_anchor_date_of_rate_reset,
+            self.cycle_anchor_date_of_dividend_payment,
+            self.option_exercise_start_date,
+            self.option_exercise_end_date,
+        ];
+        let listed = [&self.option_exercise_dates, &self.skipped_dates]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .copied();
+        let tranches = self.disbursements.iter().flatten().map(|(date, _)| *date);
+
+        let max = TimeGranularity::MAX_DAYS;
+        match single.into_iter().flatten().chain(listed).chain(tranches).find(|date| *date > max) {
+            Some(date) => Err(Error::ValidationError(format!(
+                "Date {date} exceeds {max} days since the epoch, is it in seconds?"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// The notional exchanged on the IED: the first disbursement when it is staged
+    pub fn initial_disbursement(&self) -> Option<Units> {
+        match self.disbursements.as_deref() {
+            Some([(_, amount), ..]) => Some(*amount),
+            _ => self.notional_principal,
+        }
+    }
+
+    /// Whether `event` is scheduled at `timestamp` but skipped by a payment holiday
+    pub fn skips(&self, event: EventType, timestamp: Timestamp) -> bool {
+        event.is_skippable()
+            && self.skipped_dates.as_deref().unwrap_or_default().contains(&timestamp)
+    }
+
+    /// The tranche disbursed after the IED at `timestamp`, if any
+    pub fn disbursement_at(&self, timestamp: Timestamp) -> Option<Units> {
+        let tranches = self.disbursements.as_deref().unwrap_or_default();
+        tranches.iter().skip(1).find(|(date, _)| *date == timestamp).map(|(_, amount)| *amount)
+    }
+
+    /// The terms behind [`TERMS_VERSION_MARKER`] and [`TERMS_VERSION`], as `init` expects
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = [&TERMS_VERSION_MARKER[..], &[TERMS_VERSION]].concat();
+        self.serialize(&mut bytes).expect("writing to a Vec never fails");
+        bytes
+    }
+
+    /// Decodes terms of the current version, of an earlier one with the terms appended
+    /// since unset, of version 1 with `u64` amounts, or unversioned ones serialized
+    /// while the contract id was a string. Those keep a hashed id's text as the label.
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() > MAX_TERMS_LEN {
+            return Err(Error::ValidationError(format!("Terms exceed {MAX_TERMS_LEN} bytes")));
+        }
+
+        let invalid = |err: io::Error| {
+            match err.get_ref().and_then(|err| err.downcast_ref::<TermTooLong>()) {
+                Some(too_long) => Error::ValidationError(too_long.to_string()),
+                None => Error::ValidationError("Failed to deserialize terms".into()),
+            }
+        };
+
+        match bytes.strip_prefix(&TERMS_VERSION_MARKER) {
+            Some([version @ 2..=TERMS_VERSION, terms @ ..]) => {
+                let unset = APPENDED_TERMS.iter().filter(|added| *added > version).count();
+                borsh::from_slice(&[terms, &vec![0; unset]].concat()).map_err(invalid)
+            },
+            Some([1, terms @ ..]) => {
+                borsh::from_slice::<v1::ContractTerms>(terms).map(Self::from).map_err(invalid)
+            },
+            Some([version, ..]) => Err(Error::ValidationError(format!(
+                "Unsupported terms version {version}"
+            ))),
+            Some([]) => Err(Error::ValidationError("Missing terms version".into())),
+            None => {
+                let mut rest = bytes;
+                let id = String::deserialize(&mut rest).map_err(invalid)?;
+                let contract_id = ContractId::from(id.as_str());
+
+                // the first versioned layout: the fixed-size id, the same fields, no label
+                let current = [&contract_id.0[..], rest, &[0]].concat();
+                let mut terms: Self = borsh::from_slice::<v1::ContractTerms>(&current)
+                    .map(Self::from)
+                    .map_err(invalid)?;
+                terms.contract_label = contract_id.text().is_none().then_some(id);
+                Ok(terms)
+            },
+        }
+    }
+}
+
+/// The layout of [`TERMS_VERSION`] 1, with `u64` amounts
+mod v1 {
+    use super::*;
+
+    #[derive(BorshDeserialize)]
+    pub(super) struct ContractTerms {
+        contract_id: ContractId,
+        contract_type: ContractType,
+        contract_role: ContractRole,
+        #[borsh(deserialize_with = "super::bounded::settlement_currency")]
+        settlement_currency: Option<String>,
+        initial_exchange_date: Option<Timestamp>,
+        day_count_convention: Option<DayCountConvention>,
+        schedule_config: ScheduleConfig,
+        status_date: Timestamp,
+        #[borsh(deserialize_with = "super::bounded::market_object_code")]
+        market_object_code: Option<String>,
+        contract_performance: Option<ContractPerformance>,
+        cycle_of_fee: Option<Cycle>,
+        cycle_anchor_date_of_fee: Option<Timestamp>,
+        fee_accrued: Option<u64>,
+        fee_basis: Option<FeeBasis>,
+        fee_rate: Option<u64>,
+        cycle_anchor_date_of_interest_payment: Option<Timestamp>,
+        cycle_of_interest_payment: Option<Cycle>,
+        accrued_interest: Option<u64>,
+        capitalization_end_date: Option<Timestamp>,
+        cycle_anchor_date_of_interest_calculation_base: Option<Timestamp>,
+        cycle_of_interest_calculation_base: Option<Cycle>,
+        interest_calculation_base: Option<IPCB>,
+        interest_calculation_base_amount: Option<u64>,
+        nominal_interest_rate: Option<u64>,
+        nominal_interest_rate2: Option<u64>,
+        interest_scaling_multiplier: Option<u64>,
+        maturity_date: Option<Timestamp>,
+        amortization_date: Option<Timestamp>,
+        exercise_date: Option<Timestamp>,
+        notional_principal: Option<u64>,
+        premium_discount_at_ied: Option<u64>,
+        cycle_anchor_date_of_principal_redemption: Option<Timestamp>,
+        cycle_of_principal_redemption: Option<Cycle>,
+        next_principal_redemption_payment: Option<u64>,
+        purchase_date: Option<Timestamp>,
+        price_at_purchase_date: Option<u64>,
+        termination_date: Option<Timestamp>,
+        price_at_termination_date: Option<u64>,
+        quantity: Option<u64>,
+        #[borsh(deserialize_with = "super::bounded::currency")]
+        currency: Option<String>,
+        #[borsh(deserialize_with = "super::bounded::currency2")]
+        currency2: Option<String>,
+        scaling_effect: Option<ScalingEffect>,
+        scaling_index_at_status_date: Option<u64>,
+        cycle_anchor_date_of_scaling_index: Option<Timestamp>,
+        cycle_of_scaling_index: Option<Cycle>,
+        scaling_index_at_contract_deal_date: Option<u64>,
+        #[borsh(deserialize_with = "super::bounded::market_object_code_of_scaling_index")]
+        market_object_code_of_scaling_index: Option<String>,
+        notional_scaling_multiplier: Option<u64>,
+        cycle_anchor_date_of_rate_reset: Option<Timestamp>,
+        cycle_of_rate_reset: Option<Cycle>,
+        rate_spread: Option<u64>,
+        #[borsh(deserialize_with = "super::bounded::market_object_code_of_rate_reset")]
+        market_object_code_of_rate_reset: Option<String>,
+        penalty_rate: Option<u64>,
+        penalty_type: Option<PenaltyType>,
+        prepayment_effect: Option<PrepaymentEffect>,
+        #[borsh(deserialize_with = "super::bounded::contract_label")]
+        contract_label: Option<String>,
+    }
+
+    impl From<ContractTerms> for super::ContractTerms {
+        fn from(terms: ContractTerms) -> Self {
+            let ContractTerms {
+                contract_id,
+                contract_type,
+                contract_role,
+                settlement_currency,
+                initial_exchange_date,
+                day_count_convention,
+                schedule_config,
+                status_date,
+                market_object_code,
+                contract_performance,
+                cycle_of_fee,
+                cycle_anchor_date_of_fee,
+                fee_accrued,
+                fee_basis,
+                fee_rate,
+                cycle_anchor_date_of_interest_payment,
+                cycle_of_interest_payment,
+                accrued_interest,
+                capitalization_end_date,
+                cycle_anchor_date_of_interest_calculation_base,
+                cycle_of_interest_calculation_base,
+                interest_calculation_base,
+                interest_calculation_base_amount,
+                nominal_interest_rate,
+                nominal_interest_rate2,
+                interest_scaling_multiplier,
+                maturity_date,
+                amortization_date,
+                exercise_date,
+                notional_principal,
+                premium_discount_at_ied,
+                cycle_anchor_date_of_principal_redemption,
+                cycle_of_principal_redemption,
+                next_principal_redemption_payment,
+                purchase_date,
+                price_at_purchase_date,
+                termination_date,
+                price_at_termination_date,
+                quantity,
+                currency,
+                currency2,
+                scaling_effect,
+                scaling_index_at_status_date,
+                cycle_anchor_date_of_scaling_index,
+                cycle_of_scaling_index,
+                scaling_index_at_contract_deal_date,
+                market_object_code_of_scaling_index,
+                notional_scaling_multiplier,
+                cycle_anchor_date_of_rate_reset,
+                cycle_of_rate_reset,
+                rate_spread,
+                market_object_code_of_rate_reset,
+                penalty_rate,
+                penalty_type,
+                prepayment_effect,
+                contract_label,
+            } = terms;
+            let amount = |amount: Option<u64>| amount.map(Units::from);
+            // rates keep their eight bytes, now read as signed
+            let rate = |rate: Option<u64>| rate.map(|rate| rate as Rate);
+
+            Self {
+                contract_id,
+                contract_type,
+                contract_role,
+                settlement_currency,
+                initial_exchange_date,
+                day_count_convention,
+                schedule_config,
+                status_date,
+                market_object_code,
+                contract_performance,
+                cycle_of_fee,
+                cycle_anchor_date_of_fee,
+                fee_accrued: amount(fee_accrued),
+                fee_basis,
+                fee_rate,
+                cycle_anchor_date_of_interest_payment,
+                cycle_of_interest_payment,
+                accrued_interest: accrued_interest.map(SignedUnits::from),
+                capitalization_end_date,
+                cycle_anchor_date_of_interest_calculation_base,
+                cycle_of_interest_calculation_base,
+                interest_calculation_base,
+                interest_calculation_base_amount: amount(interest_calculation_base_amount),
+                nominal_interest_rate: rate(nominal_interest_rate),
+                nominal_interest_rate2: rate(nominal_interest_rate2),
+                interest_scaling_multiplier,
+                maturity_date,
+                amortization_date,
+                exercise_date,
+                notional_principal: amount(notional_principal),
+                premium_discount_at_ied: amount(premium_discount_at_ied),
+                cycle_anchor_date_of_principal_redemption,
+                cycle_of_principal_redemption,
+                next_principal_redemption_payment: amount(next_principal_redemption_payment),
+                purchase_date,
+                price_at_purchase_date: amount(price_at_purchase_date),
+                termination_date,
+                price_at_termination_date: amount(price_at_termination_date),
+                quantity,
+                currency,
+                currency2,
+                scaling_effect,
+                scaling_index_at_status_date,
+                cycle_anchor_date_of_scaling_index,
+                cycle_of_scaling_index,
+                scaling_index_at_contract_deal_date,
+                market_object_code_of_scaling_index,
+                notional_scaling_multiplier,
+                cycle_anchor_date_of_rate_reset,
+                cycle_of_rate_reset,
+                rate_spread: rate(rate_spread),
+                market_object_code_of_rate_reset,
+                penalty_rate,
+                penalty_type,
+                prepayment_effect,
+                contract_label,
+                grace_period: None,
+                delinquency_rate: None,
+                cycle_point_of_rate_reset: None,
+                fixing_period: None,
+                option_exercise_type: None,
+                option_exercise_start_date: None,
+                option_exercise_end_date: None,
+                option_exercise_dates: None,
+                credit_limit: None,
+                interest_timing: None,
+                disbursements: None,
+                skipped_dates: None,
+                time_granularity: None,
+                settlement_policy: None,
+                option_type: None,
+                option_strike1: None,
+                market_object_code_of_underlying: None,
+                covered_contract: None,
+                coverage_of_credit_enhancement: None,
+                guaranteed_exposure: None,
+                x_day_notice: None,
+                life_cap: None,
+                life_floor: None,
+                cycle_anchor_date_of_dividend_payment: None,
+                cycle_of_dividend_payment: None,
+                next_dividend_payment_amount: None,
+                market_object_code_of_dividends: None,
+                first_leg: None,
+                second_leg: None,
+                rate_multiplier: None,
+                period_cap: None,
+                period_floor: None,
+                market_object_provider: None,
+            }
+        }
+    }
+}
+
+/// ContractType from `CT = PAM, LAM, NAM, ANN, STK, ...`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ContractType {
+    #[default]
+    PAM   = 0, // Principal at maturity
+    LAM   = 1, // Linear amortizer
+    NAM   = 2, // Negative amortizer
+    ANN   = 3, // Annuity
+    STK   = 4, // Stock
+    OPTNS = 5, // Option
+    FUTUR = 6, // Future
+    COM   = 7, // Commodity
+    CSH   = 8, // Cash
+    CLM   = 9, // Call Money
+    SWPPV = 10,// Plain Vanilla Swap
+    SWAPS = 11,// Swap
+    CEG   = 12,// Guarantee
+    CEC   = 13,// Collateral
+    UMP   = 14,// Undefined maturity profile
+    CAPFL = 15 // Cap and floor
+}
+
+impl ContractType {
+    /// The cargo feature compiling in the transitions of the type, if it is gated.
+    /// Stocks, commodities and cash are not.
+    pub const fn feature(self) -> Option<&'static str> {
+        match self {
+            ContractType::PAM | ContractType::UMP | ContractType::CLM => Some("pam"),
+            ContractType::LAM | ContractType::NAM => Some("lam"),
+            ContractType::ANN => Some("ann"),
+            ContractType::OPTNS
+            | ContractType::FUTUR
+            | ContractType::SWPPV
+            | ContractType::SWAPS
+            | ContractType::CAPFL => Some("derivatives"),
+            ContractType::CEG | ContractType::CEC => Some("credit-enhancements"),
+            ContractType::STK | ContractType::COM | ContractType::CSH => None,
+        }
+    }
+
+    /// Whether the contract is bought at its PRD and sold at its TD, as a stock or a
+    /// commodity, rather than lent and repaid
+    pub const fn is_traded(self) -> bool {
+        matches!(self, ContractType::STK | ContractType::COM)
+    }
+
+    /// Whether the type accrues interest at a rate an RR resets
+    pub const fn resets_rate(self) -> bool {
+        matches!(
+            self,
+            ContractType::PAM
+                | ContractType::LAM
+                | ContractType::NAM
+                | ContractType::ANN
+                | ContractType::CLM
+                | ContractType::UMP
+                | ContractType::SWPPV
+                | ContractType::CAPFL
+        )
+    }
+
+    /// Whether the transitions of the type are compiled in
+    pub const fn is_enabled(self) -> bool {
+        match self {
+            ContractType::PAM | ContractType::UMP | ContractType::CLM => cfg!(feature = "pam"),
+            ContractType::LAM | ContractType::NAM => cfg!(feature = "lam"),
+            ContractType::ANN => cfg!(feature = "ann"),
+            ContractType::OPTNS
+            | ContractType::FUTUR
+            | ContractType::SWPPV
+            | ContractType::SWAPS
+            | ContractType::CAPFL => cfg!(feature = "derivatives"),
+            ContractType::CEG | ContractType::CEC => cfg!(feature = "credit-enhancements"),
+            ContractType::STK | ContractType::COM | ContractType::CSH => true,
+        }
+    }
+}
+
+impl TryFrom<u8> for ContractType {
+    type Error = Error;
+
+    /// Fails for unknown types and for those whose feature is not enabled
+    fn try_from(value: u8) -> Result<Self> {
+        let contract_type: Self = borsh::from_slice(&[value])
+            .map_err(|_| Error::ValidationError(format!("Unknown contract type {value}")))?;
+
+        match contract_type.feature() {
+            Some(feature) if !contract_type.is_enabled() => Err(Error::ValidationError(format!(
+                "{contract_type:?} is not compiled in, it needs the `{feature}` feature"
+            ))),
+            _ => Ok(contract_type),
+        }
+    }
+}
+
+/// ContractRole from `CR_RPA, CR_RPL, CR_CLO, ...`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ContractRole {
+    #[default]
+    CR_RPA = 0,
+    CR_RPL = 1,
+    CR_CLO = 2,
+    CR_CNO = 3,
+    CR_COL = 4,
+    CR_LG  = 5,
+    CR_ST  = 6,
+    CR_BUY = 7,
+    CR_SEL = 8,
+    CR_RFL = 9,
+    CR_PFL = 10,
+    CR_RF  = 11,
+    CR_PF  = 12,
+}
+
+impl ContractRole {
+    /// The ACTUS role sign `R(CNTRL)`: +1 for the side that receives the principal back,
+    /// -1 for its counterparty
+    pub fn sign(self) -> i64 {
+        match self {
+            ContractRole::CR_RPA
+            | ContractRole::CR_CLO
+            | ContractRole::CR_COL
+            | ContractRole::CR_LG
+            | ContractRole::CR_BUY
+            | ContractRole::CR_RFL
+            | ContractRole::CR_RF => 1,
+            ContractRole::CR_RPL
+            | ContractRole::CR_CNO
+            | ContractRole::CR_ST
+            | ContractRole::CR_SEL
+            | ContractRole::CR_PFL
+            | ContractRole::CR_PF => -1,
+        }
+    }
+}
+
+impl TryFrom<u8> for ContractRole {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        borsh::from_slice(&[value])
+            .map_err(|_| Error::ValidationError(format!("Unknown contract role {value}")))
+    }
+}
+
+/// DayCountConvention from `DCC_A_AISDA, ...`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum DayCountConvention {
+    DCC_A_AISDA     = 0, // "AA"
+    DCC_A_360       = 1, // "A360"
+    DCC_A_365       = 2, // "A365"
+    DCC_E30_360ISDA = 3, // "30E360ISDA"
+    DCC_E30_360     = 4, // "30E360"
+    DCC_B_252       = 5, // "B252"
+}
+
+/// The unit the dates of a contract count from the Unix epoch in. The engine converts
+/// them to seconds for calendar arithmetic, so a contract dated at midnights pays the
+/// same in either. Durations in the terms, such as the grace period, take the same unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum TimeGranularity {
+    #[default]
+    Seconds = 0,
+    Days    = 1,
+}
+
+impl TimeGranularity {
+    /// Latest date a contract counted in days takes, in the year 29349. Later ones are
+    /// almost certainly seconds.
+    pub const MAX_DAYS: Timestamp = 10_000_000;
+
+    /// Length of a day in this unit
+    pub fn day(self) -> Timestamp {
+        match self {
+            TimeGranularity::Seconds => math::SECONDS_PER_DAY,
+            TimeGranularity::Days => 1,
+        }
+    }
+
+    /// The seconds since the epoch of `timestamp`, saturating past the end of the range
+    pub fn to_seconds(self, timestamp: Timestamp) -> Timestamp {
+        timestamp.saturating_mul(math::SECONDS_PER_DAY / self.day())
+    }
+
+    /// `seconds` since the epoch in this unit, rounded down to the day in days
+    pub fn from_seconds(self, seconds: Timestamp) -> Timestamp {
+        seconds / (math::SECONDS_PER_DAY / self.day())
+    }
+
+    /// [`math::civil_date`] of `timestamp` in this unit
+    pub fn civil_date(self, timestamp: Timestamp) -> (u64, u64, u64) {
+        math::civil_date(self.to_seconds(timestamp))
+    }
+
+    /// [`math::year_fraction`] between dates in this unit
+    pub fn year_fraction(
+        self,
+        dcc: u8,
+        start: Timestamp,
+        end: Timestamp,
+        maturity: Option<Timestamp>,
+    ) -> u64 {
+        let seconds = |timestamp| self.to_seconds(timestamp);
+        math::year_fraction(dcc, seconds(start), seconds(end), maturity.map(seconds))
+    }
+
+    /// [`math::add_months`] in this unit
+    pub fn add_months(self, timestamp: Timestamp, months: u64) -> Option<Timestamp> {
+        let per_unit = math::SECONDS_PER_DAY / self.day();
+        math::add_months(timestamp.checked_mul(per_unit)?, months).map(|date| date / per_unit)
+    }
+
+    /// [`math::months_between`] in this unit
+    pub fn months_between(self, from: Timestamp, to: Timestamp) -> u64 {
+        math::months_between(self.to_seconds(from), self.to_seconds(to))
+    }
+}
+
+/// EndOfMonthConvention from `EOMC_EOM, EOMC_SD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum EndOfMonthConvention {
+    EOMC_EOM = 0, // End of month
+    EOMC_SD  = 1  // Same day
+}
+
+/// BusinessDayConvention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum BusinessDayConvention {
+    BDC_NULL = 0,
+    BDC_SCF  = 1,
+    BDC_SCMF = 2,
+    BDC_CSF  = 3,
+    BDC_CSMF = 4,
+    BDC_SCP  = 5,
+    BDC_SCMP = 6,
+    BDC_CSP  = 7,
+    BDC_CSMP = 8,
+}
+
+/// Calendar from `CLDR_MF, CLDR_NC`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum Calendar {
+    CLDR_MF = 0, // Monday-Friday
+    CLDR_NC = 1, // No calendar
+}
+
+/// A small struct to hold schedule config
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScheduleConfig {
+    pub calendar: Option<Calendar>,
+    pub end_of_month_convention: Option<EndOfMonthConvention>,
+    pub business_day_convention: Option<BusinessDayConvention>,
+}
+
+// PRF = ContractPerformance is above.
+
+/// Additional enumerations if needed for coverageOfCreditEnhancement, creditEventTypeCovered, etc.
+/// e.g., GuaranteedExposure, FeeBasis, IPCB, SCEF, etc. 
+/// Shown below are some you might need:
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum GuaranteedExposure {
+    CEGE_NO = 0,
+    CEGE_NI = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum FeeBasis {
+    FEB_A = 0,
+    FEB_N = 1,
+}
+
+/// IPCB = InterestCalculationBase in Haskell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum IPCB {
+    IPCB_NT    = 0,
+    IPCB_NTIED = 1,
+    IPCB_NTL   = 2,
+}
+
+/// SCEF = ScalingEffect in Haskell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum ScalingEffect {
+    SE_OOO = 0,
+    SE_IOO = 1,
+    SE_ONO = 2,
+    SE_OOM = 3,
+    SE_INO = 4,
+    SE_ONM = 5,
+    SE_IOM = 6,
+    SE_INM = 7,
+}
+
+impl ScalingEffect {
+    /// Whether SC fixings scale the interest payments: interest only (`SE_IOO`), both
+    /// (`SE_INO`), or either with maximum deferral. Under `SE_OOO` the fixings only
+    /// track the index.
+    pub fn scales_interest(self) -> bool {
+        matches!(self, Self::SE_IOO | Self::SE_INO | Self::SE_IOM | Self::SE_INM)
+    }
+
+    /// Whether SC fixings scale the notional: notional only (`SE_ONO`), both (`SE_INO`),
+    /// or either with maximum deferral
+    pub fn scales_notional(self) -> bool {
+        matches!(self, Self::SE_ONO | Self::SE_INO | Self::SE_ONM | Self::SE_INM)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum PenaltyType {
+    PYTP_A = 0,
+    PYTP_N = 1,
+    PYTP_I = 2,
+    PYTP_O = 3,
+}
+
+/// RRPNT = CyclePointOfRateReset: when a rate fixed at an RR starts to apply. Under
+/// `CP_E` it applies from the reset onwards. Under `CP_B` the rate fixed at the start of
+/// an interest period applies to the whole period, and a later reset waits for the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum CyclePoint {
+    CP_B = 0,
+    CP_E = 1,
+}
+
+/// When the interest of a PAM or LAM period is paid: at its end by default, or at its
+/// start, the IED paying the first period net of the notional. Interest paid in advance
+/// is carried as negative accrued interest that the period's accrual works off, so a
+/// rate reset or redemption within the period is settled at the next IP or the MD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum InterestTiming {
+    Arrears = 0,
+    Advance = 1,
+}
+
+/// What a contract does when the debtor's balance or allowance does not cover a
+/// payment. Under either shortfall policy the event is committed and what is left unpaid
+/// is recorded as an [`Obligation`], which keeps the contract overdue until it is settled.
+/// Payments of the creditor side always revert.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum SettlementPolicy {
+    /// The event fails with [`Error::InsufficientFunds`] and stays due
+    #[default]
+    Revert          = 0,
+    /// Nothing moves and the whole payment is recorded
+    RecordShortfall = 1,
+    /// What the debtor can pay moves and the rest is recorded
+    SettlePartially = 2,
+}
+
+/// XT = OptionExerciseType: when an option can be exercised. A European option only at
+/// its expiry, an American one at any time in its exercise window and a Bermudan one on
+/// its listed dates, each within [`EXERCISE_TOLERANCE`] and never after the expiry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum OptionExerciseType {
+    XT_E = 0,
+    XT_A = 1,
+    XT_B = 2,
+}
+
+/// OPTP = OptionType: a call pays what the underlying price exceeds the strike by, a put
+/// what it falls short of it by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum OptionType {
+    C = 0,
+    P = 1,
+}
+
+/// How long after the IED the schedule of a contract without maturity is listed for, in
+/// seconds: a century. The events of the contract itself go on past it.
+pub const OPEN_ENDED_HORIZON: u64 = 100 * 365 * math::SECONDS_PER_DAY;
+
+/// How far from an exercise date an XD may be dated, in seconds. A contract counted in
+/// days takes whole days, so none.
+pub const EXERCISE_TOLERANCE: u64 = 12 * 3_600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum PrepaymentEffect {
+    PPEF_N = 0,
+    PPEF_A = 1,
+    PPEF_M = 2,
+}
+
+/// Period / Cycle structs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum Period {
+    P_D = 0,
+    P_W = 1,
+    P_M = 2,
+    P_Q = 3,
+    P_H = 4,
+    P_Y = 5,
+}
+
+/// Stub enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+#[borsh(use_discriminant = true)]
+pub enum Stub {
+    ShortStub = 0,
+    LongStub  = 1,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cycle {
+    pub n: i64,
+    pub p: Period,
+    pub stub: Stub,
+    pub include_end_day: bool,
+}
+
+impl Cycle {
+    /// Returns the date one cycle after `from`, or `None` for a non-positive cycle length
+    pub fn advance(&self, from: Timestamp, granularity: TimeGranularity) -> Option<Timestamp> {
+        self.nth(from, 1, granularity)
+    }
+
+    /// Returns the date `k` cycles after `anchor`, both counted in `granularity`. Monthly
+    /// periods are counted from the anchor rather than chained, so an end-of-month anchor
+    /// is not clamped permanently. `None` for a non-positive cycle length or a date past
+    /// the timestamp range.
+    pub fn nth(
+        &self,
+        anchor: Timestamp,
+        k: u64,
+        granularity: TimeGranularity,
+    ) -> Option<Timestamp> {
+        match self.span(granularity)? {
+            Span::Fixed(step) => anchor.checked_add(step.checked_mul(k)?),
+            Span::Months(step) => granularity.add_months(anchor, step.checked_mul(k)?),
+        }
+    }
+
+    /// Returns the first date of the cycle anchored at `anchor` that is strictly after `after`
+    pub fn next_after(
+        &self,
+        anchor: Timestamp,
+        after: Timestamp,
+        granularity: TimeGranularity,
+    ) -> Option<Timestamp> {
+        if anchor > after {
+            return Some(anchor);
+        }
+
+        // skip the whole cycles between `anchor` and `after`, as a lower bound
+        let mut k = match self.span(granularity)? {
+            Span::Fixed(step) => (after - anchor) / step,
+            Span::Months(step) => granularity.months_between(anchor, after) / step,
+        };
+
+        loop {
+            let date = self.nth(anchor, k, granularity)?;
+            if date > after {
+                return Some(date);
+            }
+            k = k.checked_add(1)?;
+        }
+    }
+
+    fn span(&self, granularity: TimeGranularity) -> Option<Span> {
+        let n = u64::try_from(self.n).ok().filter(|n| *n > 0)?;
+        let day = granularity.day();
+        let span = match self.p {
+            Period::P_D => Span::Fixed(n.checked_mul(day)?),
+            Period::P_W => Span::Fixed(n.checked_mul(7 * day)?),
+            Period::P_M => Span::Months(n),
+            Period::P_Q => Span::Months(n.checked_mul(3)?),
+            Period::P_H => Span::Months(n.checked_mul(6)?),
+            Period::P_Y => Span::Months(n.checked_mul(12)?),
+        };
+        Some(span)
+    }
+}
+
+/// Length of one cycle, either fixed or in calendar months
+enum Span {
+    Fixed(u64),
+    Months(u64),
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// 5. SHIFTED DAY
+////////////////////////////////////////////////////////////////////////////////
+
+/// Equivalent to Haskell's `ShiftedDay { paymentDay, calculationDay }`
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShiftedDay {
+    pub payment_day: Timestamp,
+    pub calculation_day: Timestamp,
+}
+
+impl ShiftedDay {
+    pub fn new(payment_day: Timestamp, calculation_day: Timestamp) -> Self {
+        Self { payment_day, calculation_day }
+    }
+    pub fn from_single(day: Timestamp) -> Self {
+        Self { payment_day: day, calculation_day: day }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG_ID: &str = "2025 senior secured term loan, tranche B";
+
+    #[test]
+    fn short_ids_keep_their_text() {
+        let id = ContractId::from("pam-2y");
+
+        assert_eq!(&id.0[..6], b"pam-2y");
+        assert_eq!(id.to_string(), "pam-2y");
+        assert_eq!(ContractId::from(id.to_string().as_str()), id);
+    }
+
+    #[test]
+    fn long_ids_are_hashed() {
+        let id = ContractId::from(LONG_ID);
+
+        assert_eq!(id.text(), None);
+        assert_eq!(id, ContractId(Sha256::digest(LONG_ID).into()));
+        assert_eq!(id.to_string().len(), 2 + 2 * ContractId::LEN);
+        assert_eq!(ContractId::from(id.to_string().as_str()), id);
+    }
+
+    #[test]
+    fn versioned_terms_round_trip() {
+        let terms = ContractTerms {
+            contract_id: ContractId::from("pam-2y"),
+            contract_label: Some("Two year bullet".into()),
+            notional_principal: Some(1_000),
+            ..Default::default()
+        };
+
+        let bytes = terms.to_versioned_bytes();
+        assert_eq!(ContractTerms::from_versioned_bytes(&bytes), Ok(terms));
+
+        let mut future = bytes;
+        future[TERMS_VERSION_MARKER.len()] = TERMS_VERSION + 1;
+        assert!(matches!(
+            ContractTerms::from_versioned_bytes(&future),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
+    fn coded_terms() -> ContractTerms {
+        ContractTerms {
+            contract_id: ContractId::from("floater"),
+            currency: Some("USD".into()),
+            market_object_code_of_rate_reset: Some("SOFR".into()),
+            notional_principal: Some(1_000),
+            ..Default::default()
+        }
+    }
+
+    fn rejection(bytes: &[u8]) -> String {
+        match ContractTerms::from_versioned_bytes(bytes) {
+            Err(Error::ValidationError(message)) => message,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn truncated_terms_are_rejected() {
+        let bytes = coded_terms().to_versioned_bytes();
+
+        for len in 0..bytes.len() {
+            rejection(&bytes[..len]);
+        }
+    }
+
+    #[test]
+    fn inflated_string_lengths_name_the_field() {
+        let bytes = coded_terms().to_versioned_bytes();
+        let code = [&4u32.to_le_bytes()[..], b"SOFR"].concat();
+        let at = bytes.windows(code.len()).position(|window| window == code).unwrap();
+
+        // a length prefix claiming far more than the input holds
+        let mut inflated = bytes.clone();
+        inflated[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            rejection(&inflated),
+            format!("`market_object_code_of_rate_reset` exceeds {MAX_STRING_TERM_LEN} bytes")
+        );
+
+        let long = ContractTerms {
+            currency: Some("X".repeat(MAX_STRING_TERM_LEN + 1)),
+            ..coded_terms()
+        };
+        assert_eq!(
+            rejection(&long.to_versioned_bytes()),
+            format!("`currency` exceeds {MAX_STRING_TERM_LEN} bytes")
+        );
+
+        let longest = ContractTerms {
+            currency: Some("X".repeat(MAX_STRING_TERM_LEN)),
+            ..coded_terms()
+        };
+        assert_eq!(
+            ContractTerms::from_versioned_bytes(&longest.to_versioned_bytes()),
+            Ok(longest)
+        );
+    }
+
+    #[test]
+    fn oversized_terms_are_rejected() {
+        let mut bytes = coded_terms().to_versioned_bytes();
+        bytes.resize(MAX_TERMS_LEN + 1, 0);
+
+        assert_eq!(rejection(&bytes), format!("Terms exceed {MAX_TERMS_LEN} bytes"));
+    }
+
+    /// The terms the version 1 fixtures were serialized from
+    fn v1_terms() -> ContractTerms {
+        ContractTerms {
+            contract_id: ContractId::from(LONG_ID),
+            notional_principal: Some(1_000),
+            accrued_interest: Some(5),
+            maturity_date: Some(365),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unversioned_terms_decode_with_a_string_id() {
+        // the layout before versioning: a string id, then the version 1 fields up to the label
+        let legacy = include_bytes!("../tests/fixtures/terms_unversioned.bin");
+
+        let decoded = ContractTerms::from_versioned_bytes(legacy).unwrap();
+        assert_eq!(decoded.contract_label.as_deref(), Some(LONG_ID));
+        assert_eq!(decoded, ContractTerms { contract_label: Some(LONG_ID.into()), ..v1_terms() });
+    }
+
+    #[test]
+    fn version_1_terms_widen_their_amounts() {
+        let v1 = include_bytes!("../tests/fixtures/terms_v1.bin");
+        assert_eq!(v1[TERMS_VERSION_MARKER.len()], 1);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v1), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_2_terms_decode_without_delinquency() {
+        let v2 = include_bytes!("../tests/fixtures/terms_v2.bin");
+        assert_eq!(v2[TERMS_VERSION_MARKER.len()], 2);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v2), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_3_terms_decode_without_rate_reset_point() {
+        let v3 = include_bytes!("../tests/fixtures/terms_v3.bin");
+        assert_eq!(v3[TERMS_VERSION_MARKER.len()], 3);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v3), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_4_terms_decode_without_fixing_period() {
+        let v4 = include_bytes!("../tests/fixtures/terms_v4.bin");
+        assert_eq!(v4[TERMS_VERSION_MARKER.len()], 4);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v4), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_5_terms_decode_without_option_exercise() {
+        let v5 = include_bytes!("../tests/fixtures/terms_v5.bin");
+        assert_eq!(v5[TERMS_VERSION_MARKER.len()], 5);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v5), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_6_terms_decode_without_credit_limit() {
+        let v6 = include_bytes!("../tests/fixtures/terms_v6.bin");
+        assert_eq!(v6[TERMS_VERSION_MARKER.len()], 6);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v6), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_7_terms_decode_without_interest_timing() {
+        let v7 = include_bytes!("../tests/fixtures/terms_v7.bin");
+        assert_eq!(v7[TERMS_VERSION_MARKER.len()], 7);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v7), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_8_terms_decode_without_disbursements() {
+        let v8 = include_bytes!("../tests/fixtures/terms_v8.bin");
+        assert_eq!(v8[TERMS_VERSION_MARKER.len()], 8);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v8), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_9_terms_decode_without_skipped_dates() {
+        let v9 = include_bytes!("../tests/fixtures/terms_v9.bin");
+        assert_eq!(v9[TERMS_VERSION_MARKER.len()], 9);
+
+        assert_eq!(ContractTerms::from_versioned_bytes(v9), Ok(v1_terms()));
+    }
+
+    #[test]
+    fn version_10_terms_decode_counted_in_seconds() {
+        let v10 = include_bytes!("../tests/fixtures/terms_v10.bin");
+        assert_eq!(v10[TERMS_VERSION_MARKER.len()], 10);
+
+        let terms = ContractTerms::from_versioned_bytes(v10).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.granularity(), TimeGranularity::Seconds);
+    }
+
+    #[test]
+    fn version_11_terms_decode_reverting_shortfalls() {
+        let v11 = include_bytes!("../tests/fixtures/terms_v11.bin");
+        assert_eq!(v11[TERMS_VERSION_MARKER.len()], 11);
+
+        let terms = ContractTerms::from_versioned_bytes(v11).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.settlement(), SettlementPolicy::Revert);
+    }
+
+    #[test]
+    fn version_12_terms_decode_without_an_option_payoff() {
+        let v12 = include_bytes!("../tests/fixtures/terms_v12.bin");
+        assert_eq!(v12[TERMS_VERSION_MARKER.len()], 12);
+
+        let terms = ContractTerms::from_versioned_bytes(v12).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!((terms.option_type, terms.option_strike1), (None, None));
+    }
+
+    #[test]
+    fn version_13_terms_decode_without_a_guarantee() {
+        let v13 = include_bytes!("../tests/fixtures/terms_v13.bin");
+        assert_eq!(v13[TERMS_VERSION_MARKER.len()], 13);
+
+        let terms = ContractTerms::from_versioned_bytes(v13).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!((terms.covered_contract, terms.guaranteed_exposure), (None, None));
+    }
+
+    #[test]
+    fn version_14_terms_decode_without_a_notice_period() {
+        let v14 = include_bytes!("../tests/fixtures/terms_v14.bin");
+        assert_eq!(v14[TERMS_VERSION_MARKER.len()], 14);
+
+        let terms = ContractTerms::from_versioned_bytes(v14).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.x_day_notice, None);
+    }
+
+    #[test]
+    fn version_15_terms_decode_without_rate_limits() {
+        let v15 = include_bytes!("../tests/fixtures/terms_v15.bin");
+        assert_eq!(v15[TERMS_VERSION_MARKER.len()], 15);
+
+        let terms = ContractTerms::from_versioned_bytes(v15).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!((terms.life_cap, terms.life_floor), (None, None));
+    }
+
+    #[test]
+    fn version_16_terms_decode_without_dividends() {
+        let v16 = include_bytes!("../tests/fixtures/terms_v16.bin");
+        assert_eq!(v16[TERMS_VERSION_MARKER.len()], 16);
+
+        let terms = ContractTerms::from_versioned_bytes(v16).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.cycle_of_dividend_payment, None);
+        assert_eq!(terms.market_object_code_of_dividends, None);
+    }
+
+    #[test]
+    fn version_17_terms_decode_without_swap_legs() {
+        let v17 = include_bytes!("../tests/fixtures/terms_v17.bin");
+        assert_eq!(v17[TERMS_VERSION_MARKER.len()], 17);
+
+        let terms = ContractTerms::from_versioned_bytes(v17).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!((terms.first_leg, terms.second_leg), (None, None));
+    }
+
+    #[test]
+    fn version_18_terms_decode_without_a_rate_multiplier() {
+        let v18 = include_bytes!("../tests/fixtures/terms_v18.bin");
+        assert_eq!(v18[TERMS_VERSION_MARKER.len()], 18);
+
+        let terms = ContractTerms::from_versioned_bytes(v18).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.rate_multiplier, None);
+    }
+
+    #[test]
+    fn version_19_terms_decode_without_period_limits() {
+        let v19 = include_bytes!("../tests/fixtures/terms_v19.bin");
+        assert_eq!(v19[TERMS_VERSION_MARKER.len()], 19);
+
+        let terms = ContractTerms::from_versioned_bytes(v19).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!((terms.period_cap, terms.period_floor), (None, None));
+    }
+
+    #[test]
+    fn version_20_terms_decode_without_a_market_object_provider() {
+        let v20 = include_bytes!("../tests/fixtures/terms_v20.bin");
+        assert_eq!(v20[TERMS_VERSION_MARKER.len()], 20);
+
+        let terms = ContractTerms::from_versioned_bytes(v20).unwrap();
+        assert_eq!(terms, v1_terms());
+        assert_eq!(terms.market_object_provider, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fields_name_every_term() {
+        let json = serde_json::to_value(ContractTerms::default()).unwrap();
+        let mut names: Vec<_> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        let mut fields = ContractTerms::FIELDS.to_vec();
+        names.sort();
+        fields.sort();
+        assert_eq!(names, fields);
+    }
+
+    #[test]
+    fn stocks_only_require_their_purchase_date() {
+        let stock = ContractTerms { contract_type: ContractType::STK, ..Default::default() };
+        assert_eq!(stock.check_required(), Err(Error::MissingTerm { field: "purchase_date" }));
+
+        let bought = ContractTerms { purchase_date: Some(1), ..stock };
+        assert_eq!(bought.check_required(), Ok(()));
+        assert_eq!(bought.opening(), (EventType::PRD, Some(1)));
+        assert_eq!(bought.closing(), (EventType::TD, None));
+    }
+
+    #[test]
+    fn cash_holds_its_notional_from_the_status_date() {
+        let cash = ContractTerms { contract_type: ContractType::CSH, ..Default::default() };
+        assert_eq!(cash.check_required(), Err(Error::MissingTerm { field: "notional_principal" }));
+
+        let held = ContractTerms { notional_principal: Some(500), status_date: 10, ..cash };
+        assert_eq!(held.check_required(), Ok(()));
+        let state = ContractState::new(&held);
+        assert_eq!((state.notional_principal, state.phase), (500, ContractPhase::Active));
+        assert!(!state.phase.allows(EventType::IED));
+
+        // a commodity is bought like a stock
+        let commodity = ContractTerms { contract_type: ContractType::COM, ..Default::default() };
+        assert_eq!(
+            commodity.check_required(),
+            Err(Error::MissingTerm { field: "purchase_date" })
+        );
+        assert_eq!(commodity.opening(), (EventType::PRD, None));
+    }
+
+    #[test]
+    fn contract_addresses_parse_from_their_display() {
+        let address = ContractAddress([7; ContractAddress::LEN]);
+
+        assert_eq!(address.to_string().len(), 2 + 2 * ContractAddress::LEN);
+        assert_eq!(address.to_string().parse(), Ok(address));
+        assert!("0x07".parse::<ContractAddress>().is_err());
+    }
+
+    #[test]
+    fn amounts_past_u64_round_trip() {
+        let terms = ContractTerms {
+            notional_principal: Some(Units::from(u64::MAX) + 1),
+            premium_discount_at_ied: Some(Units::MAX),
+            ..v1_terms()
+        };
+
+        assert_eq!(ContractTerms::from_versioned_bytes(&terms.to_versioned_bytes()), Ok(terms));
+    }
+
+    #[test]
+    fn types_parse_only_when_compiled_in() {
+        for value in 0..=ContractType::CAPFL as u8 {
+            let contract_type: ContractType = borsh::from_slice(&[value]).unwrap();
+
+            match ContractType::try_from(value) {
+                Ok(parsed) => {
+                    assert_eq!(parsed, contract_type);
+                    assert!(contract_type.is_enabled());
+                }
+                Err(error) => {
+                    let feature = contract_type.feature().unwrap();
+                    assert!(!contract_type.is_enabled());
+                    assert!(error.to_string().contains(&format!("`{feature}` feature")));
+                }
+            }
+        }
+
+        assert!(ContractType::try_from(ContractType::CAPFL as u8 + 1).is_err());
+    }
+
+    #[test]
+    fn results_give_the_side_paying_and_keep_the_sign() {
+        let state = ContractState::new(&ContractTerms::default());
+        let direction = |event, payoff| {
+            ProcessEventResult::new(event, 0, Some(payoff), &state).payoff.map(|(_, d)| d)
+        };
+
+        // a negative IP, at a negative rate, is paid by the creditor like an IED
+        assert_eq!(direction(EventType::IED, 100), Some(PayoffDirection::CreditorToDebtor));
+        assert_eq!(direction(EventType::IP, 100), Some(PayoffDirection::DebtorToCreditor));
+        assert_eq!(direction(EventType::IP, -100), Some(PayoffDirection::CreditorToDebtor));
+        assert_eq!(direction(EventType::PI, -100), Some(PayoffDirection::DebtorToCreditor));
+
+        for event in [EventType::IED, EventType::IP] {
+            for payoff in [0, 7, -7, SignedUnits::MAX, SignedUnits::MIN] {
+                let result = ProcessEventResult::new(event, 0, Some(payoff), &state);
+                assert_eq!(result.signed_payoff(), Some(payoff), "{event:?} paying {payoff}");
+            }
+        }
+        let none = ProcessEventResult::new(EventType::AD, 0, None, &state);
+        assert_eq!((none.payoff, none.signed_payoff()), (None, None));
+    }
+
+    #[test]
+    fn days_count_the_midnights_of_their_seconds() {
+        let (days, seconds) = (TimeGranularity::Days, TimeGranularity::Seconds);
+        let leap_day = math::timestamp_from_civil(2024, 2, 29);
+        let day = leap_day / math::SECONDS_PER_DAY;
+
+        assert_eq!(days.to_seconds(day), leap_day);
+        assert_eq!(days.from_seconds(leap_day + 3_600), day);
+        assert_eq!(days.civil_date(day), (2024, 2, 29));
+        let next_year = seconds.add_months(leap_day, 12).unwrap();
+        assert_eq!(days.add_months(day, 12), Some(next_year / math::SECONDS_PER_DAY));
+        assert_eq!(days.months_between(day, day + 31), 1);
+        for dcc in 0..=5 {
+            let year_later = leap_day + 365 * math::SECONDS_PER_DAY;
+            let year = math::year_fraction(dcc, leap_day, year_later, None);
+            assert_eq!(days.year_fraction(dcc, day, day + 365, None), year, "dcc {dcc}");
+        }
+
+        let cycle = |p| Cycle { n: 1, p, stub: Stub::ShortStub, include_end_day: false };
+        for p in [Period::P_D, Period::P_W, Period::P_M, Period::P_Y] {
+            let next = cycle(p).next_after(leap_day, leap_day, seconds).unwrap();
+            let next_day = next / math::SECONDS_PER_DAY;
+            assert_eq!(cycle(p).next_after(day, day, days), Some(next_day), "{p:?}");
+        }
+        assert_eq!(days.add_months(u64::MAX / 2, 1), None);
+    }
+
+    #[test]
+    fn days_contracts_reject_dates_in_seconds() {
+        let max = TimeGranularity::MAX_DAYS;
+        let terms = ContractTerms {
+            time_granularity: Some(TimeGranularity::Days),
+            status_date: 20_000,
+            initial_exchange_date: Some(20_000),
+            maturity_date: Some(max),
+            ..Default::default()
+        };
+        assert_eq!(terms.check_dates(), Ok(()));
+
+        let in_seconds = [
+            ContractTerms { maturity_date: Some(max + 1), ..terms.clone() },
+            ContractTerms { skipped_dates: Some(vec![20_001, 1_750_000_000]), ..terms.clone() },
+            ContractTerms { disbursements: Some(vec![(20_000, 1), (max + 1, 1)]), ..terms.clone() },
+        ];
+        for terms in in_seconds {
+            assert!(matches!(terms.check_dates(), Err(Error::ValidationError(_))), "{terms:?}");
+            // the same dates are fine in seconds
+            let seconds = ContractTerms { time_granularity: None, ..terms };
+            assert_eq!(seconds.check_dates(), Ok(()));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! CSV export of a projected cashflow schedule.
+//!
+//! One row per event, dated with its ISO 8601 calendar date. Amounts are written in
+//! whole currency units with all [`AMOUNT_DECIMALS`] decimals, so the same events
+//! always produce the same bytes.
+
+use crate::{
+    core::{SignedUnits, Units},
+    lifecycle::CashflowEvent,
+    math::{self, AMOUNT_DECIMALS},
+};
+use std::io::{self, Write};
+
+pub const HEADER: [&str; 7] = [
+    "contract_id",
+    "event_type",
+    "date",
+    "payoff",
+    "currency",
+    "notional_principal",
+    "accrued_interest",
+];
+
+/// Writes the header and one row per event. Unset payoffs and currencies are left
+/// empty.
+pub fn export_csv<W: Write>(events: &[CashflowEvent], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{}", HEADER.join(","))?;
+
+    for event in events {
+        let (year, month, day) = math::civil_date(event.time);
+
+        writeln!(
+            writer,
+            "{},{:?},{year:04}-{month:02}-{day:02},{},{},{},{}",
+            escape(&event.contract_id.to_string()),
+            event.event_type,
+            event.payoff.map(format_signed).unwrap_or_default(),
+            escape(event.currency.as_deref().unwrap_or_default()),
+            format_amount(event.state.notional_principal),
+            format_signed(event.state.accrued_interest),
+        )?;
+    }
+
+    writer.flush()
+}
+
+/// `amount` in whole units, e.g. `1.500000000` for 1_500_000_000 base units
+pub fn format_amount(amount: Units) -> String {
+    let scale = Units::pow(10, AMOUNT_DECIMALS.into());
+    format!(
+        "{}.{:0width$}",
+        amount / scale,
+        amount % scale,
+        width = AMOUNT_DECIMALS.into()
+    )
+}
+
+/// Like [`format_amount`], with a leading `-` when negative
+pub fn format_signed(amount: SignedUnits) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{sign}{}", format_amount(amount.unsigned_abs()))
+}
+
+/// Quotes fields holding a separator, quote or line break, doubling inner quotes
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractId, ContractTerms, ContractType, Cycle, DayCountConvention, Period, Stub},
+        lifecycle,
+    };
+
+    fn pam_events() -> Vec<CashflowEvent> {
+        let ied = math::timestamp_from_civil(2024, 1, 15);
+        let terms = ContractTerms {
+            contract_id: ContractId::from("pam, \"senior\""),
+            contract_type: ContractType::PAM,
+            currency: Some("USD".to_string()),
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 12),
+            notional_principal: Some(1_000_000_000_000),
+            nominal_interest_rate: Some(450),
+            day_count_convention: Some(DayCountConvention::DCC_A_360),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 6),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 6,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+
+        lifecycle::run(&terms, &()).unwrap()
+    }
+
+    /// Splits a CSV document into records, unquoting fields
+    fn parse(csv: &str) -> Vec<Vec<String>> {
+        let mut records = vec![];
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = csv.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => record.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                _ => field.push(c),
+            }
+        }
+
+        records
+    }
+
+    fn parse_amount(field: &str) -> SignedUnits {
+        let (sign, field) = field
+            .strip_prefix('-')
+            .map_or((1, field), |field| (-1, field));
+        let (whole, fraction) = field.split_once('.').unwrap();
+        assert_eq!(fraction.len(), usize::from(AMOUNT_DECIMALS));
+        sign * (whole.parse::<SignedUnits>().unwrap()
+            * SignedUnits::pow(10, AMOUNT_DECIMALS.into())
+            + fraction.parse::<SignedUnits>().unwrap())
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        let events = pam_events();
+        let mut bytes = vec![];
+        export_csv(&events, &mut bytes).unwrap();
+
+        let records = parse(std::str::from_utf8(&bytes).unwrap());
+        assert_eq!(records[0], HEADER);
+        assert_eq!(records.len(), events.len() + 1);
+
+        for (record, event) in records[1..].iter().zip(&events) {
+            let (year, month, day) = math::civil_date(event.time);
+            let payoff = Some(&record[3]).filter(|field| !field.is_empty());
+
+            assert_eq!(record[0], event.contract_id.to_string());
+            assert_eq!(record[1], format!("{:?}", event.event_type));
+            assert_eq!(record[2], format!("{year:04}-{month:02}-{day:02}"));
+            assert_eq!(payoff.map(|field| parse_amount(field)), event.payoff);
+            assert_eq!(record[4], "USD");
+            assert_eq!(
+                parse_amount(&record[5]),
+                event.state.notional_principal as SignedUnits
+            );
+            assert_eq!(parse_amount(&record[6]), event.state.accrued_interest);
+        }
+    }
+
+    #[test]
+    fn rows_are_formatted_deterministically() {
+        let mut bytes = vec![];
+        export_csv(&pam_events()[..1], &mut bytes).unwrap();
+
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            "contract_id,event_type,date,payoff,currency,notional_principal,accrued_interest\n\
+             \"pam, \"\"senior\"\"\",IED,2024-01-15,1000.000000000,USD,1000.000000000,0.000000000\n"
+        );
+    }
+
+    #[test]
+    fn amounts_keep_every_decimal() {
+        assert_eq!(format_amount(0), "0.000000000");
+        assert_eq!(format_amount(1), "0.000000001");
+        assert_eq!(format_amount(1_500_000_000), "1.500000000");
+    }
+}
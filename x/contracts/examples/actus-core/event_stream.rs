@@ -0,0 +1,234 @@
+//! Processed events in the ACTUS event-stream layout, so runs of this crate diff
+//! directly against the output of the reference implementation.
+//!
+//! As there, payoffs are signed from the point of view of the contract's role,
+//! amounts are decimal numbers of whole currency units, rates fractions rather than
+//! basis points and dates ISO 8601 local date-times.
+
+use crate::{
+    core::{
+        ContractPerformance, ContractState, ContractTerms, EventRecord, EventType, SignedUnits,
+        Timestamp,
+    },
+    lifecycle::CashflowEvent,
+    math::{self, AMOUNT_DECIMALS, BASIS_POINT_FACTOR, SECONDS_PER_DAY},
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamEvent {
+    pub contract_id: String,
+    pub event_type: EventType,
+    pub event_date: String,
+    pub payoff: f64,
+    pub currency: Option<String>,
+    /// The state right after the event; the on-chain history does not keep it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub states: Option<StreamStates>,
+}
+
+/// The state variables, with amounts signed by the contract role
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamStates {
+    pub status_date: String,
+    pub maturity_date: Option<String>,
+    pub notional_principal: f64,
+    pub nominal_interest_rate: f64,
+    pub accrued_interest: f64,
+    pub fee_accrued: f64,
+    pub notional_scaling_multiplier: f64,
+    pub interest_scaling_multiplier: f64,
+    pub next_principal_redemption_payment: f64,
+    pub contract_performance: ContractPerformance,
+}
+
+/// Events of an off-chain run of `terms`, with their states
+pub fn from_cashflows(terms: &ContractTerms, events: &[CashflowEvent]) -> Vec<StreamEvent> {
+    let sign = terms.contract_role.sign();
+
+    events
+        .iter()
+        .map(|event| StreamEvent {
+            contract_id: event.contract_id.to_string(),
+            event_type: event.event_type,
+            event_date: date_time(event.time),
+            payoff: payoff(sign, event.event_type, event.payoff),
+            currency: event.currency.clone(),
+            states: Some(states(sign, &event.state)),
+        })
+        .collect()
+}
+
+/// Events of the contract's on-chain history, which records payoffs only
+pub fn from_history(terms: &ContractTerms, records: &[EventRecord]) -> Vec<StreamEvent> {
+    let sign = terms.contract_role.sign();
+
+    records
+        .iter()
+        .map(|record| StreamEvent {
+            contract_id: terms.contract_id.to_string(),
+            event_type: record.event_type,
+            event_date: date_time(record.timestamp),
+            payoff: payoff(sign, record.event_type, record.payoff),
+            currency: terms.currency.clone(),
+            states: None,
+        })
+        .collect()
+}
+
+pub fn to_json(events: &[StreamEvent]) -> String {
+    serde_json::to_string_pretty(events).expect("stream events always serialize")
+}
+
+/// Disbursements flow away from the creditor, every other payoff towards it
+fn payoff(sign: i64, event_type: EventType, payoff: Option<SignedUnits>) -> f64 {
+    let direction = if event_type.paid_by_creditor() {
+        -sign
+    } else {
+        sign
+    };
+    direction as f64 * amount(payoff.unwrap_or(0) as f64)
+}
+
+fn states(sign: i64, state: &ContractState) -> StreamStates {
+    let signed = |units: f64| sign as f64 * amount(units);
+
+    StreamStates {
+        status_date: date_time(state.status_date),
+        maturity_date: state.time_of_maturity.map(date_time),
+        notional_principal: signed(state.notional_principal as f64),
+        nominal_interest_rate: fraction(state.nominal_interest_rate as f64),
+        accrued_interest: signed(state.accrued_interest as f64),
+        fee_accrued: signed(state.fee_accrued as f64),
+        notional_scaling_multiplier: fraction(state.notional_scaling_multiplier as f64),
+        interest_scaling_multiplier: fraction(state.interest_scaling_multiplier as f64),
+        next_principal_redemption_payment: signed(state.next_principal_redemption_payment as f64),
+        contract_performance: state.contract_performance,
+    }
+}
+
+fn amount(units: f64) -> f64 {
+    units / 10f64.powi(AMOUNT_DECIMALS.into())
+}
+
+fn fraction(basis_points: f64) -> f64 {
+    basis_points / BASIS_POINT_FACTOR as f64
+}
+
+fn date_time(time: Timestamp) -> String {
+    let (year, month, day) = math::civil_date(time);
+    let seconds = time % SECONDS_PER_DAY;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractId, ContractRole, ContractType, Cycle, DayCountConvention, Period, Stub},
+        lifecycle,
+    };
+
+    const GOLDEN: &str = include_str!("tests/fixtures/pam_event_stream.json");
+
+    fn pam_terms() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2025, 1, 1);
+
+        ContractTerms {
+            contract_id: ContractId::from("pam-2y"),
+            contract_type: ContractType::PAM,
+            contract_role: ContractRole::CR_RPA,
+            currency: Some("USD".to_string()),
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 24),
+            notional_principal: Some(1_000_000_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 6),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 6,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pam_lifecycle_matches_golden_stream() {
+        let terms = pam_terms();
+        let events = lifecycle::run(&terms, &()).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&from_cashflows(&terms, &events))).unwrap();
+        let golden: serde_json::Value = serde_json::from_str(GOLDEN).unwrap();
+        assert_eq!(json, golden);
+    }
+
+    #[test]
+    fn payoffs_are_signed_by_the_role() {
+        let lender = pam_terms();
+        let borrower = ContractTerms {
+            contract_role: ContractRole::CR_RPL,
+            ..pam_terms()
+        };
+        let events = lifecycle::run(&lender, &()).unwrap();
+
+        for (lent, borrowed) in from_cashflows(&lender, &events)
+            .into_iter()
+            .zip(from_cashflows(&borrower, &events))
+        {
+            assert_eq!(lent.payoff, -borrowed.payoff);
+        }
+
+        let stream = from_cashflows(&lender, &events);
+        assert_eq!(stream[0].payoff, -1000.0);
+        assert_eq!(stream[1].payoff, 24.79);
+    }
+
+    #[test]
+    fn history_matches_cashflows_without_states() {
+        let terms = pam_terms();
+        let events = lifecycle::run(&terms, &()).unwrap();
+        let records: Vec<_> = events
+            .iter()
+            .map(|event| EventRecord {
+                event_type: event.event_type,
+                timestamp: event.time,
+                payoff: event.payoff,
+                penalty: None,
+                residual: None,
+                processed_at: event.time + 60,
+            })
+            .collect();
+
+        for (recorded, projected) in from_history(&terms, &records)
+            .into_iter()
+            .zip(from_cashflows(&terms, &events))
+        {
+            assert_eq!(recorded.states, None);
+            assert_eq!(
+                recorded,
+                StreamEvent {
+                    states: None,
+                    ..projected
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn dates_keep_the_time_of_day() {
+        assert_eq!(date_time(0), "1970-01-01T00:00:00");
+        assert_eq!(date_time(1_735_689_600 + 3_723), "2025-01-01T01:02:03");
+    }
+}
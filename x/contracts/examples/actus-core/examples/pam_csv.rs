@@ -0,0 +1,44 @@
+// x/contracts/examples/actus-core/examples/pam_csv.rs
+
+//! Projects a two year PAM with semi-annual coupons and writes its schedule as CSV
+//! to stdout:
+//!
+//! ```sh
+//! cargo run -p actus-core --features csv --example pam_csv
+//! ```
+
+use actus_core::{
+    core::{ContractId, ContractTerms, ContractType, Cycle, DayCountConvention, Period, Stub},
+    csv, lifecycle, math,
+};
+use std::io;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let ied = math::timestamp_from_civil(2025, 1, 1);
+
+    let terms = ContractTerms {
+        contract_id: ContractId::from("pam-2y"),
+        contract_type: ContractType::PAM,
+        currency: Some("USD".to_string()),
+        status_date: ied,
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 24),
+        // 1_000 units of a currency with 9 decimals, at 5%
+        notional_principal: Some(1_000_000_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 6),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 6,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        ..Default::default()
+    };
+
+    let events = lifecycle::run(&terms, &())?;
+    csv::export_csv(&events, io::stdout().lock())?;
+
+    Ok(())
+}
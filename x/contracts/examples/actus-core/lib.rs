@@ -0,0 +1,31 @@
+//! ACTUS contract terms, state and transitions, without any dependency on the
+//! wasmlanche host, so pricing services compute the same payoffs natively that the
+//! `actus` contract settles on-chain.
+//!
+//! `core` holds the contract terms, state and transition logic, `math` the
+//! day-count and financial helpers, and `lifecycle` runs a whole contract in memory
+//! against the market data in `risk_factors`, and `scenario` over many simulated paths.
+//! `valuation` prices the remaining cashflows at a date, and `accrual` caches the year
+//! fractions of the scheduled periods. `templates` sets up the terms of common
+//! instruments such as bonds, mortgages and deposits, and `amortization` tabulates how
+//! a loan's payments split into interest and principal. `metrics` measures the weighted
+//! average life and duration of a run's cashflows, and `swap` nets the cashflows of the
+//! two contracts a swap is composed of.
+//! The `csv` feature adds a spreadsheet export of the projected events, and `json`
+//! the JSON form of the state that monitoring APIs serve and the ACTUS event stream.
+
+pub mod accrual;
+pub mod amortization;
+pub mod core;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(feature = "json")]
+pub mod event_stream;
+pub mod lifecycle;
+pub mod math;
+pub mod metrics;
+pub mod risk_factors;
+pub mod scenario;
+pub mod swap;
+pub mod templates;
+pub mod valuation;
@@ -0,0 +1,1210 @@
+//! Runs the whole lifecycle of a contract in memory.
+//!
+//! [`run`] starts from the state at the status date and processes every event the
+//! engine schedules until none is left, the same transitions the contract applies
+//! one call at a time:
+//!
+//! ```ignore
+//! let events = lifecycle::run(&terms, &())?;
+//! let total: SignedUnits = events.iter().filter_map(|event| event.payoff).sum();
+//! ```
+
+use crate::{
+    core::{
+        ContractId, ContractState, ContractTerms, ContractType, Error, EventRecord, EventType, Rate,
+        Result, ScalingEffect, SignedUnits, Timestamp, TransitionEngine, Units, SCALING_FACTOR_ONE,
+        unadjusted_schedule,
+    },
+    risk_factors::RiskFactors,
+};
+use std::fmt;
+
+/// How far from its fixing date a reset observation may be stamped, in seconds. A
+/// contract counted in days takes whole days, so none.
+pub const FIXING_TOLERANCE: u64 = 12 * 3_600;
+
+/// A processed event with its payoff and the state right after it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CashflowEvent {
+    pub contract_id: ContractId,
+    /// Currency of the terms the payoff is denominated in
+    pub currency: Option<String>,
+    pub event_type: EventType,
+    pub time: Timestamp,
+    pub payoff: Option<SignedUnits>,
+    pub state: ContractState,
+}
+
+/// Why a run stopped, with the event being processed when the engine rejected it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunError {
+    /// Unset when the terms were rejected before the first event
+    pub event: Option<(EventType, Timestamp)>,
+    pub error: Error,
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.event {
+            Some((event_type, time)) => write!(f, "{event_type:?} at {time}: {}", self.error),
+            None => self.error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+impl From<Error> for RunError {
+    fn from(error: Error) -> Self {
+        Self { event: None, error }
+    }
+}
+
+/// The events of `terms` in processing order, at the unadjusted dates of
+/// [`unadjusted_schedule`]
+pub fn schedule(terms: &ContractTerms) -> Result<Vec<(EventType, Timestamp)>> {
+    unadjusted_schedule(terms)
+}
+
+/// The RR dates of `terms` with the fixing date each one observes its rate at, the
+/// [`fixing_period`](ContractTerms::fixing_period) before it
+pub fn fixing_dates(terms: &ContractTerms) -> Result<Vec<(Timestamp, Timestamp)>> {
+    let fixing_period = terms.fixing_period.unwrap_or(0);
+
+    schedule(terms)?
+        .into_iter()
+        .filter(|(event_type, _)| *event_type == EventType::RR)
+        .map(|(_, time)| {
+            time.checked_sub(fixing_period)
+                .map(|fixing| (time, fixing))
+                .ok_or(Error::ValidationError(
+                    "Fixing date before the epoch".into(),
+                ))
+        })
+        .collect()
+}
+
+/// Processes every scheduled event of `terms` from the IED to maturity, stopping early
+/// once the principal is repaid. Rate resets and scaling index fixings read their
+/// market object code from `risk_factors`, and fail naming the code when it has no
+/// observation at their date.
+pub fn run<R>(
+    terms: &ContractTerms,
+    risk_factors: &R,
+) -> std::result::Result<Vec<CashflowEvent>, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    run_until(terms, risk_factors, Timestamp::MAX)
+}
+
+/// Like [`run`], stopping before the first event scheduled after `until`
+pub fn run_until<R>(
+    terms: &ContractTerms,
+    risk_factors: &R,
+    until: Timestamp,
+) -> std::result::Result<Vec<CashflowEvent>, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    project(terms, &ContractState::new(terms), risk_factors, until)
+}
+
+/// Continues from `state` with the scheduled events after its status date, up to
+/// `until`. A state before the IED, or the PRD of a stock or commodity, also gets it at
+/// its status date. A stock without a fixed dividend pays the one `risk_factors` observes for its
+/// [`market_object_code_of_dividends`](ContractTerms::market_object_code_of_dividends)
+/// at each DV.
+pub fn project<R>(
+    terms: &ContractTerms,
+    state: &ContractState,
+    risk_factors: &R,
+    until: Timestamp,
+) -> std::result::Result<Vec<CashflowEvent>, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    let mut state = state.clone();
+    let status_date = state.status_date;
+    let exchanged = state.notional_principal > 0;
+    let (opening, _) = terms.opening();
+    let mut events: Vec<CashflowEvent> = Vec::new();
+
+    let pending = schedule(terms)?.into_iter().filter(|&(event_type, time)| {
+        let initial_exchange = event_type == opening && !exchanged;
+        time > status_date || (time == status_date && initial_exchange)
+    });
+
+    for (event_type, time) in pending {
+        // nothing outstanding once the principal is repaid, but an emptied UMP stays open
+        let repaid = event_type != opening
+            && state.notional_principal == 0
+            && terms.contract_type != ContractType::UMP;
+        if time > until || repaid {
+            break;
+        }
+
+        let failed = |error| RunError {
+            event: Some((event_type, time)),
+            error,
+        };
+
+        // an interest period starts with the IED and each interest payment
+        let starts_period = events.last().is_some_and(|last| {
+            last.time == time
+                && matches!(
+                    last.event_type,
+                    EventType::IED | EventType::IP | EventType::IPCI
+                )
+        });
+
+        let dividend = match event_type {
+            EventType::DV => observed_dividend(terms, time, risk_factors).map_err(failed)?,
+            _ => None,
+        };
+        let payoff = TransitionEngine::process_event(
+            event_type,
+            time,
+            &mut state,
+            dividend.as_ref().unwrap_or(terms),
+        )
+        .map_err(failed)?;
+        observe(
+            event_type,
+            time,
+            starts_period,
+            &mut state,
+            terms,
+            risk_factors,
+        )
+        .map_err(failed)?;
+
+        events.push(CashflowEvent {
+            contract_id: terms.contract_id,
+            currency: terms.currency.clone(),
+            event_type,
+            time,
+            payoff,
+            state: state.clone(),
+        });
+    }
+
+    Ok(events)
+}
+
+/// `terms` paying the dividend `risk_factors` observes at `time`, unset when the terms
+/// fix the dividend or observe none
+fn observed_dividend<R>(
+    terms: &ContractTerms,
+    time: Timestamp,
+    risk_factors: &R,
+) -> Result<Option<ContractTerms>>
+where
+    R: RiskFactors + ?Sized,
+{
+    let code = match terms.market_object_code_of_dividends.as_deref() {
+        Some(code) if terms.next_dividend_payment_amount.is_none() => code,
+        _ => return Ok(None),
+    };
+    let dividend = risk_factors
+        .observe(code, time)
+        .ok_or_else(|| Error::ValidationError(format!("no observation of risk factor `{code}`")))?;
+
+    Ok(Some(ContractTerms {
+        next_dividend_payment_amount: Some(Units::from(dividend)),
+        ..terms.clone()
+    }))
+}
+
+/// Exercises the option of `terms` at `time`, at the price `risk_factors` observes for
+/// its [`market_object_code_of_underlying`](ContractTerms::market_object_code_of_underlying)
+pub fn exercise<R>(
+    terms: &ContractTerms,
+    state: &mut ContractState,
+    time: Timestamp,
+    risk_factors: &R,
+) -> Result<()>
+where
+    R: RiskFactors + ?Sized,
+{
+    let code = terms
+        .market_object_code_of_underlying
+        .as_deref()
+        .ok_or(Error::MissingTerm { field: "market_object_code_of_underlying" })?;
+    let price = risk_factors
+        .observe(code, time)
+        .ok_or_else(|| Error::ValidationError(format!("no observation of risk factor `{code}`")))?;
+
+    TransitionEngine::exercise(time, Units::from(price), state, terms)
+}
+
+/// Applies a recorded event to `state` as the contract processed it, settling its payoff
+/// in a currency of `decimals`. A rejected or expired amendment proposal leaves it as it
+/// is. Fails if the payoff differs from the recorded one, for an applied amendment, whose
+/// terms are not recorded, for the drawdowns and repayments of a credit facility and
+/// the deposits and withdrawals of a UMP, whose amount is only recorded in the currency's
+/// precision, and for exercises and observed rate resets and dividends, whose market
+/// data is not recorded.
+pub fn replay(
+    terms: &ContractTerms,
+    state: &mut ContractState,
+    record: &EventRecord,
+    decimals: u8,
+) -> Result<()> {
+    let (event_type, timestamp) = (record.event_type, record.timestamp);
+    match event_type {
+        EventType::WO => return TransitionEngine::write_off(state, timestamp).map(|_| ()),
+        EventType::AMX => return Ok(()),
+        EventType::AMD => {
+            return Err(Error::StateError(
+                "Applied amendments cannot be replayed".into(),
+            ))
+        }
+        _ => {}
+    }
+    let changes_notional = terms.credit_limit.is_some() || terms.contract_type == ContractType::UMP;
+    if changes_notional && matches!(event_type, EventType::PI | EventType::PP) {
+        return Err(Error::StateError(
+            "Drawdowns, repayments, deposits and withdrawals cannot be replayed".into(),
+        ));
+    }
+    if matches!(terms.contract_type, ContractType::OPTNS | ContractType::CEG)
+        && event_type == EventType::XD
+    {
+        return Err(Error::StateError(
+            "Exercises cannot be replayed".into(),
+        ));
+    }
+    let observed = match event_type {
+        EventType::RR => terms.market_object_code_of_rate_reset.is_some(),
+        EventType::DV => {
+            terms.next_dividend_payment_amount.is_none()
+                && terms.market_object_code_of_dividends.is_some()
+        }
+        _ => false,
+    };
+    if observed {
+        return Err(Error::StateError(
+            "Observed rate resets and dividends cannot be replayed".into(),
+        ));
+    }
+
+    let payoff = TransitionEngine::process_event(event_type, timestamp, state, terms)?
+        .map(|payoff| TransitionEngine::settle_payoff(event_type, payoff, state, decimals))
+        .transpose()?;
+    if payoff != record.payoff {
+        return Err(Error::StateError(format!(
+            "Replayed {event_type:?} at {timestamp} pays {payoff:?}, recorded {:?}",
+            record.payoff
+        )));
+    }
+
+    Ok(())
+}
+
+/// Applies the market data a processed RR or SC event observes: the reset rate, as
+/// [`TransitionEngine::reset_rate`] derives it, or the scaling multipliers relative to
+/// the index at the status date. With a fixing period the reset reads the rate observed
+/// at its fixing date, within [`FIXING_TOLERANCE`].
+fn observe<R>(
+    event_type: EventType,
+    time: Timestamp,
+    starts_period: bool,
+    state: &mut ContractState,
+    terms: &ContractTerms,
+    risk_factors: &R,
+) -> Result<()>
+where
+    R: RiskFactors + ?Sized,
+{
+    let code = match event_type {
+        EventType::RR => terms.market_object_code_of_rate_reset.as_deref(),
+        EventType::SC => terms.market_object_code_of_scaling_index.as_deref(),
+        _ => None,
+    };
+    let Some(code) = code else {
+        return Ok(());
+    };
+
+    let missing = || Error::ValidationError(format!("no observation of risk factor `{code}`"));
+    let value = match terms.fixing_period.filter(|_| event_type == EventType::RR) {
+        Some(fixing_period) => {
+            let fixing = time
+                .checked_sub(fixing_period)
+                .ok_or(Error::ValidationError(
+                    "Fixing date before the epoch".into(),
+                ))?;
+            // the latest observation up to the tolerance past the fixing date, as long as
+            // it is no more than the tolerance before it
+            let tolerance = terms.granularity().from_seconds(FIXING_TOLERANCE);
+            risk_factors
+                .observation(code, fixing.saturating_add(tolerance))
+                .filter(|(observed_at, _)| observed_at.abs_diff(fixing) <= tolerance)
+                .map(|(_, value)| value)
+                .ok_or_else(|| {
+                    Error::ValidationError(format!(
+                        "no observation of risk factor `{code}` at the fixing date {fixing}"
+                    ))
+                })?
+        }
+        None => risk_factors.observe(code, time).ok_or_else(missing)?,
+    };
+
+    if event_type == EventType::RR {
+        let reference =
+            Rate::try_from(value).map_err(|_| Error::MathError("Reset rate overflow".into()))?;
+        return TransitionEngine::reset_rate(reference, starts_period, state, terms).map(|_| ());
+    }
+
+    let base = terms
+        .scaling_index_at_status_date
+        .filter(|index| *index > 0)
+        .ok_or(Error::ValidationError(
+            "scaling_index_at_status_date must be positive".into(),
+        ))?;
+    let multiplier =
+        u64::try_from(u128::from(value) * u128::from(SCALING_FACTOR_ONE) / u128::from(base))
+            .map_err(|_| Error::MathError("Scaling multiplier overflow".into()))?;
+
+    let effect = terms.scaling_effect.unwrap_or(ScalingEffect::SE_OOO);
+    if effect.scales_interest() {
+        state.interest_scaling_multiplier = multiplier;
+    }
+    if effect.scales_notional() {
+        state.notional_scaling_multiplier = multiplier;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{
+            ContractPhase, Cycle, CyclePoint, DayCountConvention, FeeBasis, InterestTiming,
+            OptionExerciseType, OptionType, Period, Stub, TimeGranularity,
+        },
+        math,
+        risk_factors::RiskFactorSeries,
+    };
+
+    fn quarterly_pam() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: Some(math::add_months(ied, 12).unwrap()),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: Some(math::add_months(ied, 3).unwrap()),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn runs_every_scheduled_event() {
+        let terms = quarterly_pam();
+        let events = run(&terms, &()).unwrap();
+
+        let schedule: Vec<_> = events.iter().map(|event| event.event_type).collect();
+        assert_eq!(
+            schedule,
+            [
+                EventType::IED,
+                EventType::IP,
+                EventType::IP,
+                EventType::IP,
+                EventType::MD
+            ]
+        );
+
+        let last = events.last().unwrap();
+        assert_eq!(Some(last.time), terms.maturity_date);
+        assert_eq!(last.state.notional_principal, 0);
+        assert_eq!(TransitionEngine::next_event(&last.state, &terms), None);
+    }
+
+    #[test]
+    fn pays_the_same_counted_in_days_as_in_seconds() {
+        let ied = math::timestamp_from_civil(2024, 1, 31);
+        let seconds = ContractTerms {
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 13),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360ISDA),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+            cycle_anchor_date_of_fee: Some(ied + 14 * math::SECONDS_PER_DAY),
+            cycle_of_fee: Some(Cycle {
+                n: 2,
+                p: Period::P_W,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            fee_basis: Some(FeeBasis::FEB_N),
+            fee_rate: Some(25),
+            ..quarterly_pam()
+        };
+        let day = |date: Timestamp| date / math::SECONDS_PER_DAY;
+        let days = ContractTerms {
+            time_granularity: Some(TimeGranularity::Days),
+            status_date: day(ied),
+            initial_exchange_date: seconds.initial_exchange_date.map(day),
+            maturity_date: seconds.maturity_date.map(day),
+            cycle_anchor_date_of_interest_payment: seconds
+                .cycle_anchor_date_of_interest_payment
+                .map(day),
+            cycle_anchor_date_of_fee: seconds.cycle_anchor_date_of_fee.map(day),
+            ..seconds.clone()
+        };
+
+        let in_seconds = run(&seconds, &()).unwrap();
+        let in_days = run(&days, &()).unwrap();
+        assert_eq!(in_seconds.len(), in_days.len());
+        assert!(in_seconds.len() > 30);
+        for (seconds, days) in in_seconds.iter().zip(&in_days) {
+            assert_eq!(
+                (seconds.event_type, day(seconds.time), seconds.payoff),
+                (days.event_type, days.time, days.payoff)
+            );
+        }
+
+        // dates in seconds read as days are rejected rather than run in the far future
+        let mistaken = ContractTerms {
+            time_granularity: Some(TimeGranularity::Days),
+            ..seconds
+        };
+        assert!(matches!(
+            run(&mistaken, &()),
+            Err(RunError {
+                event: None,
+                error: Error::ValidationError(_)
+            })
+        ));
+    }
+
+    #[test]
+    fn matches_processing_events_one_by_one() {
+        let terms = quarterly_pam();
+        let mut state = ContractState::new(&terms);
+
+        for event in run(&terms, &()).unwrap() {
+            let payoff =
+                TransitionEngine::process_event(event.event_type, event.time, &mut state, &terms)
+                    .unwrap();
+            assert_eq!(payoff, event.payoff);
+            assert_eq!(state, event.state);
+        }
+    }
+
+    #[test]
+    fn replaying_records_returns_each_state() {
+        let terms = quarterly_pam();
+        let mut state = ContractState::new(&terms);
+
+        for event in run(&terms, &()).unwrap() {
+            let mut record = EventRecord {
+                event_type: event.event_type,
+                timestamp: event.time,
+                payoff: event.payoff,
+                penalty: None,
+                residual: None,
+                processed_at: event.time,
+            };
+            let before = state.clone();
+            replay(&terms, &mut state, &record, math::AMOUNT_DECIMALS).unwrap();
+            assert_eq!(state, event.state);
+
+            // a history that disagrees with the terms is not silently replayed
+            record.payoff = Some(record.payoff.unwrap_or_default() + 1);
+            let mut state = before;
+            assert!(matches!(
+                replay(&terms, &mut state, &record, math::AMOUNT_DECIMALS),
+                Err(Error::StateError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn observed_resets_are_not_replayed() {
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(0, 600)]);
+        let terms = resetting_pam();
+        let reset = run(&terms, &risk_factors)
+            .unwrap()
+            .into_iter()
+            .find(|event| event.event_type == EventType::RR)
+            .unwrap();
+        let record = EventRecord {
+            event_type: reset.event_type,
+            timestamp: reset.time,
+            payoff: reset.payoff,
+            penalty: None,
+            residual: None,
+            processed_at: reset.time,
+        };
+
+        // the rate the reset read is not recorded, so replaying it would keep the old one
+        let mut state = ContractState::new(&terms);
+        assert!(matches!(
+            replay(&terms, &mut state, &record, math::AMOUNT_DECIMALS),
+            Err(Error::StateError(_))
+        ));
+    }
+
+    #[test]
+    fn missing_terms_fail_before_any_event() {
+        let terms = ContractTerms {
+            maturity_date: None,
+            ..quarterly_pam()
+        };
+
+        assert_eq!(
+            run(&terms, &()),
+            Err(RunError {
+                event: None,
+                error: Error::MissingTerm {
+                    field: "maturity_date"
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn zero_notional_stops_after_the_initial_exchange() {
+        let terms = ContractTerms {
+            notional_principal: Some(0),
+            ..quarterly_pam()
+        };
+
+        let events = run(&terms, &()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, EventType::IED);
+    }
+
+    #[test]
+    fn stops_at_the_horizon() {
+        let terms = quarterly_pam();
+        let all = run(&terms, &()).unwrap();
+        let until = all[2].time;
+
+        assert_eq!(run_until(&terms, &(), until).unwrap(), all[..3]);
+        assert_eq!(run_until(&terms, &(), until - 1).unwrap(), all[..2]);
+    }
+
+    fn resetting_pam() -> ContractTerms {
+        let terms = quarterly_pam();
+
+        ContractTerms {
+            cycle_anchor_date_of_rate_reset: terms.cycle_anchor_date_of_interest_payment,
+            cycle_of_rate_reset: terms.cycle_of_interest_payment.clone(),
+            market_object_code_of_rate_reset: Some("EURIBOR3M".to_string()),
+            ..terms
+        }
+    }
+
+    /// Interest and maturity payoffs of [`quarterly_pam`] at a fixed rate, by date
+    fn fixed_rate_payments(rate: Rate) -> Vec<(Timestamp, Option<SignedUnits>)> {
+        let terms = ContractTerms {
+            nominal_interest_rate: Some(rate),
+            ..quarterly_pam()
+        };
+
+        run(&terms, &())
+            .unwrap()
+            .into_iter()
+            .filter(|event| matches!(event.event_type, EventType::IP | EventType::MD))
+            .map(|event| (event.time, event.payoff))
+            .collect()
+    }
+
+    #[test]
+    fn rate_resets_change_the_following_payments() {
+        let terms = resetting_pam();
+        let [first, second, third] = [3, 6, 9]
+            .map(|months| math::add_months(terms.initial_exchange_date.unwrap(), months).unwrap());
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(first, 600), (second, 700)]);
+        let events = run(&terms, &risk_factors).unwrap();
+
+        let schedule: Vec<_> = events
+            .iter()
+            .map(|event| (event.event_type, event.time))
+            .collect();
+        assert_eq!(
+            schedule[1..7],
+            [
+                (EventType::IP, first),
+                (EventType::RR, first),
+                (EventType::IP, second),
+                (EventType::RR, second),
+                (EventType::IP, third),
+                (EventType::RR, third),
+            ]
+        );
+
+        let rates: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_type == EventType::RR)
+            .map(|event| event.state.nominal_interest_rate)
+            .collect();
+        assert_eq!(rates, [600, 700, 700]);
+
+        // each period pays what a fixed rate at its reset would have paid
+        let payments: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event.event_type, EventType::IP | EventType::MD))
+            .map(|event| (event.time, event.payoff))
+            .collect();
+        let at = |rate, period: usize| fixed_rate_payments(rate)[period];
+
+        assert_eq!(payments, [at(500, 0), at(600, 1), at(700, 2), at(700, 3)]);
+        assert!(payments[0].1 < payments[1].1 && payments[1].1 < payments[2].1);
+    }
+
+    #[test]
+    fn caps_pay_what_the_observed_rate_passes_the_strike_by() {
+        let terms = ContractTerms {
+            contract_type: ContractType::CAPFL,
+            life_cap: Some(550),
+            ..resetting_pam()
+        };
+        let [first, second] = [3, 6]
+            .map(|months| math::add_months(terms.initial_exchange_date.unwrap(), months).unwrap());
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(first, 600), (second, 700)]);
+        let payments: Vec<_> = run(&terms, &risk_factors)
+            .unwrap()
+            .into_iter()
+            .filter(|event| matches!(event.event_type, EventType::IP | EventType::MD))
+            .map(|event| (event.time, event.payoff))
+            .collect();
+
+        // each period pays what a loan at the excess over the cap would pay in interest,
+        // and the notional is never repaid
+        let at = |rate, period: usize| fixed_rate_payments(rate)[period];
+        let (maturity, last) = at(150, 3);
+        assert_eq!(
+            payments,
+            [
+                (first, Some(0)),
+                at(50, 1),
+                at(150, 2),
+                (maturity, last.map(|payoff| payoff - 1_000_000)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stocks_pay_the_observed_dividends_unless_fixed() {
+        let purchase = math::timestamp_from_civil(2024, 1, 1);
+        let month = |months| math::add_months(purchase, months).unwrap();
+        let terms = ContractTerms {
+            contract_type: ContractType::STK,
+            status_date: purchase,
+            purchase_date: Some(purchase),
+            price_at_purchase_date: Some(40),
+            quantity: Some(1_000),
+            cycle_anchor_date_of_dividend_payment: Some(month(3)),
+            cycle_of_dividend_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            market_object_code_of_dividends: Some("DIVS".into()),
+            termination_date: Some(month(11)),
+            price_at_termination_date: Some(45),
+            ..Default::default()
+        };
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("DIVS", vec![(month(3), 1), (month(6), 2), (month(9), 3)]);
+        let payments = |terms: &ContractTerms| -> Vec<_> {
+            run(terms, &risk_factors)
+                .unwrap()
+                .into_iter()
+                .map(|event| (event.event_type, event.payoff))
+                .collect()
+        };
+
+        assert_eq!(
+            payments(&terms),
+            [
+                (EventType::PRD, Some(40_000)),
+                (EventType::DV, Some(1_000)),
+                (EventType::DV, Some(2_000)),
+                (EventType::DV, Some(3_000)),
+                (EventType::TD, Some(45_000)),
+            ]
+        );
+
+        // a fixed dividend is paid without looking at the market
+        let fixed = ContractTerms { next_dividend_payment_amount: Some(5), ..terms.clone() };
+        assert!(payments(&fixed)
+            .iter()
+            .filter(|(event_type, _)| *event_type == EventType::DV)
+            .all(|(_, payoff)| *payoff == Some(5_000)));
+
+        // a dividend date the market has no dividend for
+        let error = run(&terms, &RiskFactorSeries::new()).unwrap_err();
+        assert_eq!(error.event, Some((EventType::DV, month(3))));
+    }
+
+    #[test]
+    fn beginning_of_period_resets_wait_for_the_next_period() {
+        // resets a month into each quarter, between the interest payments
+        let ied = quarterly_pam().initial_exchange_date.unwrap();
+        let resets = |cycle_point| ContractTerms {
+            cycle_anchor_date_of_rate_reset: math::add_months(ied, 1),
+            cycle_point_of_rate_reset: Some(cycle_point),
+            ..resetting_pam()
+        };
+        let [first, second] = [1, 4].map(|months| math::add_months(ied, months).unwrap());
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(first, 600), (second, 700)]);
+        let payments = |terms: &ContractTerms| -> Vec<_> {
+            run(terms, &risk_factors)
+                .unwrap()
+                .into_iter()
+                .filter(|event| matches!(event.event_type, EventType::IP | EventType::MD))
+                .map(|event| (event.time, event.payoff))
+                .collect()
+        };
+        let at = |rate, period: usize| fixed_rate_payments(rate)[period];
+
+        // each period pays the rate fixed by the time it started, but for truncating the
+        // interest accrued up to the reset separately
+        let beginning = payments(&resets(CyclePoint::CP_B));
+        let expected = [at(500, 0), at(600, 1), at(700, 2), at(700, 3)];
+        for ((time, payoff), (expected_time, expected)) in beginning.iter().zip(expected) {
+            assert_eq!(*time, expected_time);
+            assert!(
+                payoff.unwrap().abs_diff(expected.unwrap()) <= 10,
+                "{payoff:?}"
+            );
+        }
+
+        // while a reset at the end applies from the reset onwards
+        let end = payments(&resets(CyclePoint::CP_E));
+        assert!(end[0].1 > beginning[0].1 && end[1].1 > beginning[1].1);
+        assert_eq!(end[2..], beginning[2..]);
+
+        // resets at the start of each period apply to it either way
+        let aligned = ContractTerms {
+            cycle_point_of_rate_reset: Some(CyclePoint::CP_B),
+            ..resetting_pam()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(0, 600)]);
+        assert_eq!(
+            run(&aligned, &risk_factors).unwrap(),
+            run(&resetting_pam(), &risk_factors).unwrap()
+        );
+    }
+
+    #[test]
+    fn resets_add_the_spread() {
+        let terms = ContractTerms {
+            rate_spread: Some(25),
+            ..resetting_pam()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(0, 300)]);
+
+        let events = run(&terms, &risk_factors).unwrap();
+        let reset = events
+            .iter()
+            .find(|event| event.event_type == EventType::RR)
+            .unwrap();
+        assert_eq!(reset.state.nominal_interest_rate, 325);
+        assert_eq!(reset.state.reference_rate, Some(300));
+    }
+
+    #[test]
+    fn resets_multiply_the_observed_rate_before_adding_the_spread() {
+        let terms = ContractTerms {
+            rate_multiplier: Some(12_500),
+            rate_spread: Some(-20),
+            ..resetting_pam()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(0, 400)]);
+
+        let events = run(&terms, &risk_factors).unwrap();
+        let rates: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_type == EventType::RR)
+            .map(|event| (event.state.nominal_interest_rate, event.state.reference_rate))
+            .collect();
+        assert_eq!(rates, [(480, Some(400)); 3]);
+
+        // the reset rate is truncated toward zero
+        let mut state = ContractState::new(&terms);
+        assert_eq!(TransitionEngine::reset_rate(333, true, &mut state, &terms), Ok(396));
+        assert_eq!(TransitionEngine::reset_rate(-333, true, &mut state, &terms), Ok(-436));
+    }
+
+    #[test]
+    fn resets_are_clamped_by_the_period_and_life_limits() {
+        let terms = ContractTerms {
+            period_cap: Some(100),
+            period_floor: Some(150),
+            life_cap: Some(575),
+            life_floor: Some(450),
+            ..resetting_pam()
+        };
+        let [first, second, third] = [3, 6, 9]
+            .map(|months| math::add_months(terms.initial_exchange_date.unwrap(), months).unwrap());
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(first, 900), (second, 300), (third, 1_000)]);
+
+        let events = run(&terms, &risk_factors).unwrap();
+        let rates: Vec<_> = events
+            .iter()
+            .filter(|event| event.event_type == EventType::RR)
+            .map(|event| (event.state.nominal_interest_rate, event.state.reference_rate))
+            .collect();
+        // up from 500 by at most 100 and then to the life cap, down from there by at most
+        // 150 and then to the life floor, then up by at most 100 again
+        assert_eq!(rates, [(575, Some(900)), (450, Some(300)), (550, Some(1_000))]);
+
+        // a cap resets its underlying rate past its strikes
+        let capfl = ContractTerms {
+            contract_type: ContractType::CAPFL,
+            period_cap: None,
+            period_floor: None,
+            ..terms
+        };
+        let mut state = ContractState::new(&capfl);
+        assert_eq!(TransitionEngine::reset_rate(900, true, &mut state, &capfl), Ok(900));
+    }
+
+    #[test]
+    fn missing_series_names_the_code() {
+        let terms = resetting_pam();
+        let error = run(&terms, &RiskFactorSeries::new()).unwrap_err();
+
+        assert_eq!(
+            error.event,
+            Some((
+                EventType::RR,
+                terms.cycle_anchor_date_of_rate_reset.unwrap()
+            ))
+        );
+        assert_eq!(
+            error.error,
+            Error::ValidationError("no observation of risk factor `EURIBOR3M`".into())
+        );
+    }
+
+    #[test]
+    fn fixing_period_observes_the_rate_before_the_reset() {
+        let terms = ContractTerms {
+            fixing_period: Some(2 * math::SECONDS_PER_DAY),
+            ..resetting_pam()
+        };
+        let fixings = fixing_dates(&terms).unwrap();
+        let (first, fixing) = fixings[0];
+        assert_eq!(fixings.len(), 3);
+        assert_eq!(first - fixing, 2 * math::SECONDS_PER_DAY);
+
+        // stamped at the reset itself, the observation is too late for the fixing date
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(first, 600)]);
+        let error = run(&terms, &risk_factors).unwrap_err();
+        assert_eq!(error.event, Some((EventType::RR, first)));
+        assert_eq!(
+            error.error,
+            Error::ValidationError(format!(
+                "no observation of risk factor `EURIBOR3M` at the fixing date {fixing}"
+            ))
+        );
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert(
+            "EURIBOR3M",
+            fixings.iter().map(|&(_, fixing)| (fixing, 600)).collect(),
+        );
+        let events = run(&terms, &risk_factors).unwrap();
+        let reset = events
+            .iter()
+            .find(|event| event.event_type == EventType::RR)
+            .unwrap();
+        assert_eq!(
+            (reset.time, reset.state.nominal_interest_rate),
+            (first, 600)
+        );
+    }
+
+    #[test]
+    fn scaling_fixings_set_the_multipliers() {
+        let terms = ContractTerms {
+            scaling_effect: Some(ScalingEffect::SE_ONO),
+            scaling_index_at_status_date: Some(200),
+            cycle_anchor_date_of_scaling_index: Some(
+                math::add_months(quarterly_pam().status_date, 6).unwrap(),
+            ),
+            market_object_code_of_scaling_index: Some("CPI".to_string()),
+            ..quarterly_pam()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("CPI", vec![(0, 210)]);
+
+        let events = run(&terms, &risk_factors).unwrap();
+        let fixing = events
+            .iter()
+            .find(|event| event.event_type == EventType::SC)
+            .unwrap();
+        assert_eq!(fixing.state.notional_scaling_multiplier, 10_500);
+        assert_eq!(fixing.state.interest_scaling_multiplier, SCALING_FACTOR_ONE);
+    }
+
+    #[test]
+    fn fee_bases_on_an_amortizing_schedule() {
+        // a quarter of the notional is repaid every quarter, right after each FP
+        let quarterly = quarterly_pam().cycle_of_interest_payment;
+        let lam = |fee_basis, fee_rate| ContractTerms {
+            contract_type: ContractType::LAM,
+            cycle_anchor_date_of_principal_redemption: quarterly_pam()
+                .cycle_anchor_date_of_interest_payment,
+            cycle_of_principal_redemption: quarterly.clone(),
+            next_principal_redemption_payment: Some(250_000),
+            cycle_anchor_date_of_fee: quarterly_pam().cycle_anchor_date_of_interest_payment,
+            cycle_of_fee: quarterly.clone(),
+            fee_basis: Some(fee_basis),
+            fee_rate: Some(fee_rate),
+            ..quarterly_pam()
+        };
+        let fees = |terms: &ContractTerms| -> Vec<_> {
+            run(terms, &())
+                .unwrap()
+                .into_iter()
+                .filter(|event| event.event_type == EventType::FP)
+                .map(|event| event.payoff.unwrap())
+                .collect()
+        };
+
+        // an absolute fee of 500 per payment
+        assert_eq!(fees(&lam(FeeBasis::FEB_A, 500)), [500, 500, 500]);
+
+        // 1% a year on the notional outstanding over each quarter
+        let fees = fees(&lam(FeeBasis::FEB_N, 100));
+        assert_eq!(fees.len(), 3);
+        assert!(fees[0] > fees[1] && fees[1] > fees[2], "{fees:?}");
+        assert_eq!(fees[0], 1_000_000 * 100 * 2_493 / 100_000_000);
+    }
+
+    #[test]
+    fn interest_in_advance_pays_the_same_a_period_earlier() {
+        let arrears = run(&quarterly_pam(), &()).unwrap();
+        let advance = run(
+            &ContractTerms {
+                interest_timing: Some(InterestTiming::Advance),
+                ..quarterly_pam()
+            },
+            &(),
+        )
+        .unwrap();
+        let payoffs = |events: &[CashflowEvent]| -> Vec<_> {
+            events.iter().map(|event| event.payoff.unwrap()).collect()
+        };
+        let (arrears, advance) = (payoffs(&arrears), payoffs(&advance));
+        let notional = 1_000_000;
+
+        // in arrears each IP and the MD pay the quarter before, in advance the IED and
+        // each IP that after
+        let interest_in_arrears = [arrears[1], arrears[2], arrears[3], arrears[4] - notional];
+        let interest_in_advance = [notional - advance[0], advance[1], advance[2], advance[3]];
+        assert_eq!(interest_in_advance, interest_in_arrears);
+        assert_eq!(advance[4], notional);
+        assert_eq!(
+            interest_in_advance.iter().sum::<SignedUnits>(),
+            interest_in_arrears.iter().sum::<SignedUnits>()
+        );
+    }
+
+    #[test]
+    fn staged_disbursements_accrue_from_their_tranche() {
+        let ied = quarterly_pam().status_date;
+        let [second, third] =
+            [3, 6].map(|months| math::add_months(ied, months).unwrap() + 14 * 86_400);
+        let terms = ContractTerms {
+            disbursements: Some(vec![(ied, 400_000), (second, 300_000), (third, 300_000)]),
+            ..quarterly_pam()
+        };
+        let events = run(&terms, &()).unwrap();
+
+        let disbursed: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event.event_type, EventType::IED | EventType::PI))
+            .map(|event| (event.time, event.payoff, event.state.notional_principal))
+            .collect();
+        assert_eq!(
+            disbursed,
+            [
+                (ied, Some(400_000), 400_000),
+                (second, Some(300_000), 700_000),
+                (third, Some(300_000), 1_000_000)
+            ]
+        );
+
+        // the first IP falls before the second tranche
+        let first_tranche_only = ContractTerms {
+            notional_principal: Some(400_000),
+            ..quarterly_pam()
+        };
+        let first_ip = |events: &[CashflowEvent]| {
+            events
+                .iter()
+                .find(|event| event.event_type == EventType::IP)
+                .and_then(|event| event.payoff)
+        };
+        assert_eq!(
+            first_ip(&events),
+            first_ip(&run(&first_tranche_only, &()).unwrap())
+        );
+        assert_eq!(first_ip(&events), Some(400_000 * 500 * 2_493 / 100_000_000));
+        assert_eq!(events.last().unwrap().state.notional_principal, 0);
+    }
+
+    #[test]
+    fn disbursements_must_sum_to_the_notional_before_any_redemption() {
+        let ied = quarterly_pam().status_date;
+        let staged = |tranches: Vec<(Timestamp, Units)>| ContractTerms {
+            disbursements: Some(tranches),
+            cycle_anchor_date_of_principal_redemption: Some(ied + 200 * 86_400),
+            ..quarterly_pam()
+        };
+        let invalid = |reason: &str| {
+            Err(RunError {
+                event: None,
+                error: Error::ValidationError(format!("Disbursements {reason}")),
+            })
+        };
+
+        let short = staged(vec![(ied, 400_000), (ied + 86_400, 500_000)]);
+        assert_eq!(run(&short, &()), invalid("must sum to the notional"));
+        let late = staged(vec![(ied, 400_000), (ied + 300 * 86_400, 600_000)]);
+        assert_eq!(
+            run(&late, &()),
+            invalid("must all fall before maturity and the first PR")
+        );
+        let after_ied = staged(vec![(ied + 86_400, 1_000_000)]);
+        assert_eq!(run(&after_ied, &()), invalid("must start on the IED"));
+    }
+
+    #[test]
+    fn scaling_effects_scale_their_payoffs() {
+        // the index rises 5% at a fixing six months in, before the last IP and the MD
+        let scaled = |effect| ContractTerms {
+            scaling_effect: Some(effect),
+            scaling_index_at_status_date: Some(200),
+            cycle_anchor_date_of_scaling_index: Some(
+                math::add_months(quarterly_pam().status_date, 6).unwrap(),
+            ),
+            market_object_code_of_scaling_index: Some("CPI".to_string()),
+            ..quarterly_pam()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("CPI", vec![(0, 210)]);
+        let payoffs = |terms: &ContractTerms| -> Vec<_> {
+            run(terms, &risk_factors)
+                .unwrap()
+                .into_iter()
+                .filter(|event| event.event_type != EventType::SC)
+                .map(|event| event.payoff.unwrap())
+                .collect()
+        };
+
+        // only tracking the index pays as much as an unscaled contract
+        let unscaled = payoffs(&quarterly_pam());
+        assert_eq!(payoffs(&scaled(ScalingEffect::SE_OOO)), unscaled);
+
+        let up = |amount: SignedUnits| amount * 10_500 / 10_000;
+        let (last_ip, md) = (unscaled[3], unscaled[4]);
+        let notional = 1_000_000;
+        let interest = md - notional;
+
+        for (effect, interest_scaled, notional_scaled) in [
+            (ScalingEffect::SE_IOO, true, false),
+            (ScalingEffect::SE_ONO, false, true),
+            (ScalingEffect::SE_INO, true, true),
+        ] {
+            let pick = |amount, scaled| if scaled { up(amount) } else { amount };
+
+            let expected = [
+                unscaled[0],
+                unscaled[1],
+                unscaled[2],
+                pick(last_ip, interest_scaled),
+                pick(notional, notional_scaled) + pick(interest, interest_scaled),
+            ];
+            assert_eq!(payoffs(&scaled(effect)), expected, "{effect:?}");
+        }
+    }
+
+    #[test]
+    fn options_exercise_at_the_observed_underlying_price() {
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+        let expiry = math::add_months(ied, 6).unwrap();
+        let call = ContractTerms {
+            contract_type: ContractType::OPTNS,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: Some(expiry),
+            notional_principal: Some(100),
+            premium_discount_at_ied: Some(300),
+            option_type: Some(OptionType::C),
+            option_strike1: Some(50),
+            option_exercise_type: Some(OptionExerciseType::XT_A),
+            option_exercise_end_date: Some(expiry),
+            market_object_code_of_underlying: Some("ACME".into()),
+            ..Default::default()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("ACME", vec![(ied, 45), (math::add_months(ied, 3).unwrap(), 58)]);
+
+        // the holder pays the premium, then the option expires worthless unexercised
+        let events = run(&call, &risk_factors).unwrap();
+        let payoffs: Vec<_> = events.iter().map(|event| (event.event_type, event.payoff)).collect();
+        assert_eq!(payoffs, [(EventType::IED, Some(300)), (EventType::MD, Some(0))]);
+
+        // exercised at 58, the call pays 8 on each of its 100 units
+        let exercised = math::add_months(ied, 4).unwrap();
+        let mut state = events[0].state.clone();
+        exercise(&call, &mut state, exercised, &risk_factors).unwrap();
+        assert_eq!((state.exercise_date, state.exercise_amount), (Some(exercised), Some(800)));
+        assert_eq!(
+            exercise(&call, &mut state.clone(), exercised, &risk_factors),
+            Err(Error::TransitionError("The contract was already exercised".into()))
+        );
+
+        let settled = TransitionEngine::process_event(EventType::STD, exercised, &mut state, &call);
+        assert_eq!(settled, Ok(Some(800)));
+        assert_eq!(state.phase, ContractPhase::Terminated);
+
+        // a put struck at 50 is out of the money at 58
+        let put = ContractTerms { option_type: Some(OptionType::P), ..call };
+        let mut state = events[0].state.clone();
+        exercise(&put, &mut state, exercised, &risk_factors).unwrap();
+        assert_eq!(state.exercise_amount, Some(0));
+
+        // before the first observation there is no price to exercise at
+        let mut state = events[0].state.clone();
+        assert!(exercise(&put, &mut state, ied - 1, &risk_factors).is_err());
+    }
+}
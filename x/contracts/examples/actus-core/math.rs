@@ -1,10 +1,13 @@
-use token::Units;
+use crate::core::Units;
 
 /// Constants for time calculations
-const SECONDS_PER_DAY: u64 = 86400;
-const DAYS_PER_YEAR: u64 = 365;
+pub const SECONDS_PER_DAY: u64 = 86400;
 const MONTHS_PER_YEAR: u64 = 12;
-const BASIS_POINT_FACTOR: u64 = 10000;
+/// Fixed-point scale shared by rates, year fractions and scaling factors
+pub const BASIS_POINT_FACTOR: u64 = 10000;
+/// Decimals of the amounts in terms and state. Payoffs are converted to the
+/// decimals of the settlement token when settled.
+pub const AMOUNT_DECIMALS: u8 = 9;
 
 // ============= Year Fraction Calculations =============
 
@@ -14,7 +17,7 @@ pub fn year_fraction(
     start_time: u64,
     end_time: u64,
     maturity_time: Option<u64>
-) -> Units {
+) -> u64 {
     match dcc {
         0 => year_fraction_actual_actual_isda(start_time, end_time),
         1 => year_fraction_actual_360(start_time, end_time),
@@ -26,7 +29,7 @@ pub fn year_fraction(
     }
 }
 
-fn year_fraction_actual_actual_isda(start_time: u64, end_time: u64) -> Units {
+fn year_fraction_actual_actual_isda(start_time: u64, end_time: u64) -> u64 {
     if start_time >= end_time {
         return 0;
     }
@@ -37,20 +40,20 @@ fn year_fraction_actual_actual_isda(start_time: u64, end_time: u64) -> Units {
     if start_year == end_year {
         let year_length = if is_leap_year(start_year) { 366 } else { 365 };
         let days_diff = days_between(start_time, end_time);
-        return (days_diff * BASIS_POINT_FACTOR / year_length) as Units;
+        return days_diff * BASIS_POINT_FACTOR / year_length;
     }
 
     // Handle multi-year periods
     let mut total_fraction = 0;
     
-    // First partial year
+    // First partial year, computed directly: the next year's start falls in
+    // another year, so recursing on it would never terminate
     let next_year_start = start_of_year(start_year + 1);
-    total_fraction += year_fraction_actual_actual_isda(start_time, next_year_start);
+    let year_length = if is_leap_year(start_year) { 366 } else { 365 };
+    total_fraction += days_between(start_time, next_year_start) * BASIS_POINT_FACTOR / year_length;
     
     // Full years in between
-    for year in (start_year + 1)..end_year {
-        total_fraction += BASIS_POINT_FACTOR as Units;
-    }
+    total_fraction += (end_year - start_year - 1) * BASIS_POINT_FACTOR;
     
     // Final partial year
     total_fraction += year_fraction_actual_actual_isda(
@@ -61,25 +64,25 @@ fn year_fraction_actual_actual_isda(start_time: u64, end_time: u64) -> Units {
     total_fraction
 }
 
-fn year_fraction_actual_360(start_time: u64, end_time: u64) -> Units {
+fn year_fraction_actual_360(start_time: u64, end_time: u64) -> u64 {
     if start_time >= end_time {
         return 0;
     }
     
     let days = days_between(start_time, end_time);
-    (days * BASIS_POINT_FACTOR / 360) as Units
+    days * BASIS_POINT_FACTOR / 360
 }
 
-fn year_fraction_actual_365(start_time: u64, end_time: u64) -> Units {
+fn year_fraction_actual_365(start_time: u64, end_time: u64) -> u64 {
     if start_time >= end_time {
         return 0;
     }
     
     let days = days_between(start_time, end_time);
-    (days * BASIS_POINT_FACTOR / 365) as Units
+    days * BASIS_POINT_FACTOR / 365
 }
 
-fn year_fraction_thirty_e_360(start_time: u64, end_time: u64) -> Units {
+fn year_fraction_thirty_e_360(start_time: u64, end_time: u64) -> u64 {
     if start_time >= end_time {
         return 0;
     }
@@ -90,18 +93,14 @@ fn year_fraction_thirty_e_360(start_time: u64, end_time: u64) -> Units {
     let d1 = if start_d == 31 { 30 } else { start_d };
     let d2 = if end_d == 31 { 30 } else { end_d };
     
-    let days = 360 * (end_y - start_y) + 
-               30 * (end_m - start_m) + 
-               (d2 - d1);
-               
-    (days * BASIS_POINT_FACTOR / 360) as Units
+    thirty_360_fraction((start_y, start_m, d1), (end_y, end_m, d2))
 }
 
 fn year_fraction_thirty_e_360_isda(
     start_time: u64,
     end_time: u64,
     maturity: Option<u64>
-) -> Units {
+) -> u64 {
     if start_time >= end_time {
         return 0;
     }
@@ -111,29 +110,33 @@ fn year_fraction_thirty_e_360_isda(
     
     let d1 = if is_last_day_of_month(start_time) { 30 } else { start_d };
     let d2 = if is_last_day_of_month(end_time) && 
-                (maturity.is_none() || end_time != maturity.unwrap() || end_m != 2)
+                (maturity != Some(end_time) || end_m != 2)
              { 30 } else { end_d };
     
-    let days = 360 * (end_y - start_y) + 
-               30 * (end_m - start_m) + 
-               (d2 - d1);
-               
-    (days * BASIS_POINT_FACTOR / 360) as Units
+    thirty_360_fraction((start_y, start_m, d1), (end_y, end_m, d2))
 }
 
-fn year_fraction_bus_252(start_time: u64, end_time: u64) -> Units {
+/// 30/360 fraction between two already-adjusted dates. Later months can carry
+/// smaller month or day numbers, so the difference is taken signed.
+fn thirty_360_fraction(start: (u64, u64, u64), end: (u64, u64, u64)) -> u64 {
+    let diff = |a: u64, b: u64| b as i128 - a as i128;
+    let days = 360 * diff(start.0, end.0) + 30 * diff(start.1, end.1) + diff(start.2, end.2);
+    (days.max(0) as u128 * BASIS_POINT_FACTOR as u128 / 360) as u64
+}
+
+fn year_fraction_bus_252(start_time: u64, end_time: u64) -> u64 {
     if start_time >= end_time {
         return 0;
     }
     
     let business_days = count_business_days(start_time, end_time);
-    (business_days * BASIS_POINT_FACTOR / 252) as Units
+    business_days * BASIS_POINT_FACTOR / 252
 }
 
 // ============= Annuity Calculations =============
 
 /// Calculates the annuity amount given an interest rate and time factors
-pub fn annuity(rate: Units, time_factors: &[Units]) -> Units {
+pub fn annuity(rate: u64, time_factors: &[u64]) -> u64 {
     let numerator = product(&time_factors.iter()
         .map(|&t| rate * t / BASIS_POINT_FACTOR + BASIS_POINT_FACTOR)
         .collect::<Vec<_>>());
@@ -142,27 +145,132 @@ pub fn annuity(rate: Units, time_factors: &[Units]) -> Units {
         .map(|&t| rate * t / BASIS_POINT_FACTOR + BASIS_POINT_FACTOR)
         .collect::<Vec<_>>()));
         
-    if denominator == 0 {
-        0
+    (numerator * BASIS_POINT_FACTOR)
+        .checked_div(denominator)
+        .unwrap_or(0)
+}
+
+/// Fixed-point scale of the discount factors in [`annuity_payment`]; basis points
+/// would leave the installment of a long mortgage off by whole units
+const ANNUITY_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Returns the constant installment, rounded to the nearest unit, that repays `notional`
+/// over periods of `year_fractions` (in basis points) at `rate` (in basis points).
+/// `None` when there are no periods or on overflow.
+pub fn annuity_payment(notional: Units, rate: u64, year_fractions: &[u64]) -> Option<Units> {
+    let factor = BASIS_POINT_FACTOR as u128 * BASIS_POINT_FACTOR as u128;
+    let mut discount = ANNUITY_SCALE;
+    let mut discount_sum: u128 = 0;
+
+    // the installment is the notional over the sum of each period's discount factor
+    for &fraction in year_fractions {
+        let period_rate = (rate as u128)
+            .checked_mul(fraction as u128)?
+            .checked_mul(ANNUITY_SCALE)?
+            / factor;
+        discount = discount.checked_mul(ANNUITY_SCALE)? / ANNUITY_SCALE.checked_add(period_rate)?;
+        discount_sum = discount_sum.checked_add(discount)?;
+    }
+
+    if discount_sum == 0 {
+        return None;
+    }
+
+    let payment = notional
+        .checked_mul(ANNUITY_SCALE)?
+        .checked_add(discount_sum / 2)?
+        / discount_sum;
+
+    Units::try_from(payment).ok()
+}
+
+/// Converts `amount` from `from` to `to` decimals, truncating the digits `to` cannot
+/// represent. `None` on overflow.
+pub fn rescale_decimals(amount: Units, from: u8, to: u8) -> Option<Units> {
+    if to < from {
+        // a divisor beyond the range truncates everything
+        Some(Units::checked_pow(10, (from - to) as u32).map_or(0, |divisor| amount / divisor))
     } else {
-        numerator * BASIS_POINT_FACTOR / denominator
+        amount.checked_mul(Units::checked_pow(10, (to - from) as u32)?)
     }
 }
 
+// ============= Calendar Arithmetic =============
+
+/// Converts a timestamp to its proleptic Gregorian (year, month, day) date
+pub fn civil_date(timestamp: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's `civil_from_days`, restricted to dates after the epoch
+    let z = timestamp / SECONDS_PER_DAY + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a (year, month, day) date to the timestamp of its midnight
+pub fn timestamp_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146_097 + doe - 719_468) * SECONDS_PER_DAY
+}
+
+/// Number of days in `month` of `year`, accounting for leap years
+pub fn days_in_civil_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        _ => days_in_month(month),
+    }
+}
+
+/// Adds calendar months, clamping to the end of shorter months and keeping the time of day.
+/// Returns `None` past the end of the timestamp range.
+pub fn add_months(timestamp: u64, months: u64) -> Option<u64> {
+    let (year, month, day) = civil_date(timestamp);
+    let months_since_zero = (year * MONTHS_PER_YEAR + (month - 1)).checked_add(months)?;
+    let (year, month) = (months_since_zero / MONTHS_PER_YEAR, months_since_zero % MONTHS_PER_YEAR + 1);
+    let day = day.min(days_in_civil_month(year, month));
+
+    // `timestamp_from_civil`, with the steps that can overflow checked
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era.checked_mul(146_097)?
+        .checked_add(doe)?
+        .checked_sub(719_468)?
+        .checked_mul(SECONDS_PER_DAY)?
+        .checked_add(timestamp % SECONDS_PER_DAY)
+}
+
+/// Number of calendar month boundaries between `from` and `to`, zero if `to` is earlier
+pub fn months_between(from: u64, to: u64) -> u64 {
+    let (from_year, from_month, _) = civil_date(from);
+    let (to_year, to_month, _) = civil_date(to);
+    (to_year * MONTHS_PER_YEAR + to_month).saturating_sub(from_year * MONTHS_PER_YEAR + from_month)
+}
+
 // ============= Helper Functions =============
 
 /// Get year from timestamp
 fn get_year(timestamp: u64) -> u64 {
-    timestamp / (SECONDS_PER_DAY * DAYS_PER_YEAR)
+    civil_date(timestamp).0
 }
 
 /// Get year, month, day from timestamp
 fn get_ymd(timestamp: u64) -> (u64, u64, u64) {
-    let days = timestamp / SECONDS_PER_DAY;
-    let year = days / DAYS_PER_YEAR;
-    let month = (days % (DAYS_PER_YEAR)) / 30; // Approximate
-    let day = days % 30 + 1;
-    (year, month, day)
+    civil_date(timestamp)
 }
 
 /// Calculate days between timestamps
@@ -172,28 +280,18 @@ fn days_between(start: u64, end: u64) -> u64 {
 
 /// Check if year is leap year
 fn is_leap_year(year: u64) -> bool {
-    if year % 4 != 0 {
-        false
-    } else if year % 100 != 0 {
-        true
-    } else if year % 400 != 0 {
-        false
-    } else {
-        true
-    }
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
 }
 
 /// Get timestamp for start of year
 fn start_of_year(year: u64) -> u64 {
-    year * DAYS_PER_YEAR * SECONDS_PER_DAY
+    timestamp_from_civil(year, 1, 1)
 }
 
 /// Check if timestamp is last day of month
 fn is_last_day_of_month(timestamp: u64) -> bool {
-    let (_, _, day) = get_ymd(timestamp);
-    let tomorrow = timestamp + SECONDS_PER_DAY;
-    let (_, tomorrow_m, _) = get_ymd(tomorrow);
-    day == days_in_month(get_ymd(timestamp).1)
+    let (year, month, day) = get_ymd(timestamp);
+    day == days_in_civil_month(year, month)
 }
 
 /// Get days in month (simplified)
@@ -218,7 +316,7 @@ fn days_in_month(month: u64) -> u64 {
 /// Count business days between timestamps
 fn count_business_days(start: u64, end: u64) -> u64 {
     let mut total_days = days_between(start, end);
-    let mut weeks = total_days / 7;
+    let weeks = total_days / 7;
     let remaining_days = total_days % 7;
     
     // Subtract weekends
@@ -239,12 +337,12 @@ fn count_business_days(start: u64, end: u64) -> u64 {
 }
 
 /// Product of a vector of numbers
-fn product(values: &[Units]) -> Units {
+fn product(values: &[u64]) -> u64 {
     values.iter().fold(1, |acc, &x| acc * x / BASIS_POINT_FACTOR)
 }
 
 /// Running product (tails) of a vector
-fn running_product(values: &[Units]) -> Vec<Units> {
+fn running_product(values: &[u64]) -> Vec<u64> {
     let mut result = Vec::with_capacity(values.len());
     let mut product = 1;
     for &value in values.iter().rev() {
@@ -256,23 +354,21 @@ fn running_product(values: &[Units]) -> Vec<Units> {
 }
 
 /// Sum of a vector of numbers
-fn sum(values: &[Units]) -> Units {
-    values.iter().fold(0, |acc, &x| acc + x)
+fn sum(values: &[u64]) -> u64 {
+    values.iter().sum()
 }
 
-#[cfg(test)]
-
 // ============= Option Math =============
 
 /// Black-Scholes option pricing - returns value in basis points
 pub fn option_price(
-    spot: Units,           // Current price of underlying in basis points
-    strike: Units,         // Strike price in basis points
-    time: Units,           // Time to maturity in year fractions (basis points)
-    rate: Units,           // Risk-free rate in basis points
-    volatility: Units,     // Volatility in basis points
+    spot: u64,           // Current price of underlying in basis points
+    strike: u64,         // Strike price in basis points
+    time: u64,           // Time to maturity in year fractions (basis points)
+    rate: u64,           // Risk-free rate in basis points
+    volatility: u64,     // Volatility in basis points
     is_call: bool,         // true for call, false for put
-) -> Units {
+) -> u64 {
     let s = spot as f64 / BASIS_POINT_FACTOR as f64;
     let k = strike as f64 / BASIS_POINT_FACTOR as f64;
     let t = time as f64 / BASIS_POINT_FACTOR as f64;
@@ -288,7 +384,7 @@ pub fn option_price(
         k * f64::exp(-r * t) * normal_cdf(-d2) - s * normal_cdf(-d1)
     };
 
-    (price * BASIS_POINT_FACTOR as f64) as Units
+    (price * BASIS_POINT_FACTOR as f64) as u64
 }
 
 /// Normal cumulative distribution function
@@ -319,48 +415,48 @@ fn erf(x: f64) -> f64 {
 
 /// Calculate scaling factor based on index value changes
 pub fn calculate_scaling_factor(
-    initial_index: Units,
-    current_index: Units,
-    reference_index: Units,
-) -> Units {
+    initial_index: u64,
+    current_index: u64,
+    _reference_index: u64,
+) -> u64 {
     if initial_index == 0 {
-        return BASIS_POINT_FACTOR as Units;
+        return BASIS_POINT_FACTOR;
     }
     
     let numerator = (current_index as u128) * (BASIS_POINT_FACTOR as u128);
     let denominator = initial_index as u128;
-    (numerator / denominator) as Units
+    (numerator / denominator) as u64
 }
 
 /// Apply scaling to an amount
 pub fn apply_scaling(
     amount: Units,
-    scaling_factor: Units,
+    scaling_factor: u64,
 ) -> Units {
-    ((amount as u128) * (scaling_factor as u128) / (BASIS_POINT_FACTOR as u128)) as Units
+    amount.saturating_mul(scaling_factor as Units) / BASIS_POINT_FACTOR as Units
 }
 
 // ============= Interest Rate Calculations =============
 
 /// Calculate effective interest rate from nominal rate
 pub fn effective_rate(
-    nominal_rate: Units,    // In basis points
+    nominal_rate: u64,    // In basis points
     compounds_per_year: u64,
-) -> Units {
+) -> u64 {
     let r = nominal_rate as f64 / BASIS_POINT_FACTOR as f64;
     let n = compounds_per_year as f64;
     
     let effective = (1.0 + r/n).powf(n) - 1.0;
-    (effective * BASIS_POINT_FACTOR as f64) as Units
+    (effective * BASIS_POINT_FACTOR as f64) as u64
 }
 
 /// Calculate forward rate from spot rates
 pub fn forward_rate(
-    rate1: Units,  // Rate for period 1 in basis points
-    rate2: Units,  // Rate for period 2 in basis points
-    time1: Units,  // Length of period 1 in year fractions (basis points)
-    time2: Units,  // Length of period 2 in year fractions (basis points)
-) -> Units {
+    rate1: u64,  // Rate for period 1 in basis points
+    rate2: u64,  // Rate for period 2 in basis points
+    time1: u64,  // Length of period 1 in year fractions (basis points)
+    time2: u64,  // Length of period 2 in year fractions (basis points)
+) -> u64 {
     if time1 >= time2 {
         return 0;
     }
@@ -371,15 +467,15 @@ pub fn forward_rate(
     let t2 = time2 as f64 / BASIS_POINT_FACTOR as f64;
 
     let forward = ((1.0 + r2).powf(t2) / (1.0 + r1).powf(t1)).powf(1.0/(t2-t1)) - 1.0;
-    (forward * BASIS_POINT_FACTOR as f64) as Units
+    (forward * BASIS_POINT_FACTOR as f64) as u64
 }
 
 // ============= Present Value Calculations =============
 
 /// Calculate present value of future cash flows
 pub fn present_value(
-    future_values: &[(Units, Units)],  // (amount, time) pairs, time in year fractions
-    discount_rate: Units,              // In basis points
+    future_values: &[(Units, u64)],  // (amount, time) pairs, time in year fractions
+    discount_rate: u64,                // In basis points
 ) -> Units {
     let mut total_pv = 0u128;
     let r = discount_rate as f64 / BASIS_POINT_FACTOR as f64;
@@ -391,15 +487,15 @@ pub fn present_value(
         total_pv += pv;
     }
 
-    (total_pv * (BASIS_POINT_FACTOR as u128) / (BASIS_POINT_FACTOR as u128)) as Units
+    total_pv
 }
 
 /// Calculate internal rate of return (IRR)
 pub fn calculate_irr(
-    cash_flows: &[(Units, Units)],  // (amount, time) pairs, time in year fractions
-    initial_guess: Units,           // Initial rate guess in basis points
+    cash_flows: &[(i64, u64)],    // (signed amount, time) pairs, time in year fractions
+    initial_guess: u64,           // Initial rate guess in basis points
     max_iterations: u32,
-) -> Option<Units> {
+) -> Option<u64> {
     let mut rate = initial_guess as f64 / BASIS_POINT_FACTOR as f64;
     let tolerance = 0.0001;
 
@@ -420,7 +516,7 @@ pub fn calculate_irr(
 
         let new_rate = rate - f / df;
         if (new_rate - rate).abs() < tolerance {
-            return Some((new_rate * BASIS_POINT_FACTOR as f64) as Units);
+            return Some((new_rate * BASIS_POINT_FACTOR as f64) as u64);
         }
         rate = new_rate;
     }
@@ -432,12 +528,12 @@ pub fn calculate_irr(
 
 /// Linear interpolation
 pub fn interpolate(
-    x: Units,
-    x0: Units,
-    x1: Units,
-    y0: Units,
-    y1: Units,
-) -> Units {
+    x: u64,
+    x0: u64,
+    x1: u64,
+    y0: u64,
+    y1: u64,
+) -> u64 {
     if x0 == x1 {
         return y0;
     }
@@ -447,19 +543,19 @@ pub fn interpolate(
     let x_diff = (x as i128) - (x0 as i128);
     
     let interpolated = y0 as i128 + (x_diff * dy) / dx;
-    interpolated as Units
+    interpolated as u64
 }
 
 /// Safe exponentiation for basis points
 pub fn pow(
-    base: Units,        // In basis points
-    exponent: Units,    // In basis points
-) -> Units {
+    base: u64,        // In basis points
+    exponent: u64,    // In basis points
+) -> u64 {
     let b = base as f64 / BASIS_POINT_FACTOR as f64;
     let e = exponent as f64 / BASIS_POINT_FACTOR as f64;
     
     let result = b.powf(e);
-    (result * BASIS_POINT_FACTOR as f64) as Units
+    (result * BASIS_POINT_FACTOR as f64) as u64
 }
 
 #[cfg(test)]
@@ -477,11 +573,56 @@ mod tests {
         let call_price = option_price(spot, strike, time, rate, vol, true);
         let put_price = option_price(spot, strike, time, rate, vol, false);
         
-        // Put-call parity check (approximately)
-        let parity_diff = (call_price as i64 - put_price as i64).abs();
+        // Put-call parity: C - P = S - K * e^(-rT), here 1 - e^(-0.05) ~ 488bp
+        let parity_diff = (call_price as i64 - put_price as i64 - 488).abs();
         assert!(parity_diff < 100);  // Within 1 cent
     }
 
+    #[test]
+    fn test_rescale_decimals() {
+        assert_eq!(rescale_decimals(1_234_567_891, 9, 9), Some(1_234_567_891));
+        assert_eq!(rescale_decimals(1_234_567_891, 9, 6), Some(1_234_567));
+        assert_eq!(rescale_decimals(1_234_567_891, 9, 18), Some(1_234_567_891_000_000_000));
+        assert_eq!(rescale_decimals(999, 9, 6), Some(0));
+        assert_eq!(rescale_decimals(1, 0, 39), None);
+        assert_eq!(rescale_decimals(Units::MAX, 40, 0), Some(0));
+        assert_eq!(rescale_decimals(Units::MAX, 9, 10), None);
+
+        // 18.5 tokens at 18 decimals, past the 2^64 a u64 amount could hold
+        let past_u64 = rescale_decimals(18_500_000_000, 9, 18).unwrap();
+        assert_eq!(past_u64, 18_500_000_000_000_000_000);
+        assert!(past_u64 > u64::MAX as Units);
+    }
+
+    #[test]
+    fn test_annuity_payment() {
+        // 120 periods of 0.0834 years at 4.8%, a period rate r = 0.0040032:
+        // 300_000 * r / (1 - (1 + r)^-120) ~ 3153.28
+        assert_eq!(annuity_payment(300_000, 480, &[834; 120]), Some(3153));
+
+        // without interest the notional is split evenly
+        assert_eq!(annuity_payment(1_200, 0, &[833; 12]), Some(100));
+        assert_eq!(annuity_payment(1_200, 500, &[]), None);
+    }
+
+    #[test]
+    fn test_civil_date_round_trip() {
+        let leap_day = 1_709_164_800; // 2024-02-29
+        assert_eq!(civil_date(leap_day), (2024, 2, 29));
+        assert_eq!(timestamp_from_civil(2024, 2, 29), leap_day);
+        assert_eq!(civil_date(0), (1970, 1, 1));
+    }
+
+    #[test]
+    fn test_add_months_clamps_to_month_end() {
+        let end_of_jan = timestamp_from_civil(2023, 1, 31) + 3600;
+
+        assert_eq!(add_months(end_of_jan, 1), Some(timestamp_from_civil(2023, 2, 28) + 3600));
+        assert_eq!(add_months(end_of_jan, 13), Some(timestamp_from_civil(2024, 2, 29) + 3600));
+        assert_eq!(add_months(end_of_jan, 12), Some(timestamp_from_civil(2024, 1, 31) + 3600));
+        assert_eq!(add_months(u64::MAX, 1), None);
+    }
+
     #[test]
     fn test_scaling_factor() {
         let initial = 10000;    // 1.00
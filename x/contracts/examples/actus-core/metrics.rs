@@ -0,0 +1,414 @@
+//! Weighted average life, duration and yield of a contract's cashflows.
+//!
+//! Each reads the events of a whole run, as [`lifecycle::run`] returns them, and measures
+//! time from the first of them, the IED. Times are year fractions of a day-count
+//! convention in [`BASIS_POINT_FACTOR`] fixed point, floored to a basis point of a year
+//! (under an hour) like the accruals of the engine, and lives and durations are rounded
+//! to the nearest basis point, so 52_500 is 5.25 years. Yields are whole basis points:
+//!
+//! ```ignore
+//! let events = lifecycle::run(&terms, &())?;
+//! let wal = metrics::weighted_average_life(&events, DayCountConvention::DCC_A_365);
+//! let duration = metrics::duration(&events, 500, DayCountConvention::DCC_A_365);
+//! let ytm = metrics::yield_to_maturity(&events, 985_000, DayCountConvention::DCC_A_365)?;
+//! ```
+//!
+//! [`lifecycle::run`]: crate::lifecycle::run
+
+use crate::{
+    core::{DayCountConvention, Error, EventType, Rate, Result, SignedUnits, Timestamp, Units},
+    lifecycle::CashflowEvent,
+    math::{self, BASIS_POINT_FACTOR},
+    valuation::Price,
+};
+
+/// The durations of a contract's cashflows, in years of basis points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Duration {
+    /// The mean time to the cashflows weighted by their present values
+    pub macaulay: u64,
+    /// The relative fall in the present value per unit rise of an annually compounded
+    /// discount rate, `macaulay / (1 + rate)`
+    pub modified: u64,
+}
+
+/// The year fraction from the first of `events` to `event`
+fn years_to(events: &[CashflowEvent], event: &CashflowEvent, day_count: DayCountConvention) -> u64 {
+    let start: Timestamp = events.first().map_or(event.time, |first| first.time);
+    math::year_fraction(day_count as u8, start, event.time, None)
+}
+
+/// The payoffs after the first of `events` but for an IED, with their year fractions
+fn cashflows(
+    events: &[CashflowEvent],
+    day_count: DayCountConvention,
+) -> impl Iterator<Item = (SignedUnits, u64)> + '_ {
+    events.iter().skip(1).filter_map(move |event| {
+        let payoff = event
+            .payoff
+            .filter(|_| event.event_type != EventType::IED)?;
+        Some((payoff, years_to(events, event, day_count)))
+    })
+}
+
+/// `weighted / total` rounded to the nearest whole number
+fn rounded_ratio(weighted: u128, total: u128) -> Option<u64> {
+    let ratio = weighted.checked_add(total / 2)? / total;
+    u64::try_from(ratio).ok()
+}
+
+/// The mean time to the principal repayments weighted by their amounts, the fall in the
+/// notional at each event. Disbursements do not reduce the life. Unset when nothing is
+/// repaid or the weighted sum overflows.
+pub fn weighted_average_life(
+    events: &[CashflowEvent],
+    day_count: DayCountConvention,
+) -> Option<u64> {
+    let mut weighted: u128 = 0;
+    let mut repaid: Units = 0;
+
+    for pair in events.windows(2) {
+        let [before, event] = pair else {
+            continue;
+        };
+        let principal = before
+            .state
+            .notional_principal
+            .saturating_sub(event.state.notional_principal);
+        let years = u128::from(years_to(events, event, day_count));
+
+        weighted = weighted.checked_add(principal.checked_mul(years)?)?;
+        repaid = repaid.checked_add(principal)?;
+    }
+
+    if repaid == 0 {
+        return None;
+    }
+    rounded_ratio(weighted, repaid)
+}
+
+/// The Macaulay and modified durations of the payoffs after the first of `events`,
+/// discounted at an annually compounded `discount_rate` in basis points the way
+/// [`valuation`](crate::valuation) prices them. A payoff flowing against its event, like
+/// interest at a negative rate, weighs against the others. Unset when the payoffs are not
+/// worth more than nothing or the weighted sum overflows.
+pub fn duration(
+    events: &[CashflowEvent],
+    discount_rate: u64,
+    day_count: DayCountConvention,
+) -> Option<Duration> {
+    let mut weighted: i128 = 0;
+    let mut value: SignedUnits = 0;
+
+    for (payoff, years) in cashflows(events, day_count) {
+        let present = math::present_value(&[(payoff.unsigned_abs(), years)], discount_rate);
+        let present = SignedUnits::try_from(present).ok()?;
+        let present = if payoff < 0 { -present } else { present };
+
+        weighted = weighted.checked_add(present.checked_mul(i128::from(years))?)?;
+        value = value.checked_add(present)?;
+    }
+
+    if value <= 0 || weighted < 0 {
+        return None;
+    }
+    let macaulay = rounded_ratio(weighted.unsigned_abs(), value.unsigned_abs())?;
+    let modified = rounded_ratio(
+        u128::from(macaulay) * u128::from(BASIS_POINT_FACTOR),
+        u128::from(BASIS_POINT_FACTOR) + u128::from(discount_rate),
+    )?;
+
+    Some(Duration { macaulay, modified })
+}
+
+/// How [`YieldSearch::solve`] looks for a yield
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YieldSearch {
+    /// The lowest and highest yields tried, in basis points, above -100%
+    pub bracket: (Rate, Rate),
+    /// How far the price at the yield found may be from the given one
+    pub tolerance: Price,
+    /// Prices evaluated before giving up, besides those of the bracket
+    pub max_iterations: u32,
+}
+
+/// From -50% to 1000% a year, within 0.0001 of the price
+impl Default for YieldSearch {
+    fn default() -> Self {
+        Self {
+            bracket: (-5_000, 100_000),
+            tolerance: 1,
+            max_iterations: 64,
+        }
+    }
+}
+
+impl YieldSearch {
+    /// The annually compounded yield in basis points at which the payoffs after the
+    /// first of `events` are worth `price` per 100 of the notional it exchanged, quoted
+    /// like the [`valuation`](crate::valuation) prices. Takes Newton steps on the price,
+    /// rounded to whole basis points, while they stay inside the bracket, and bisects it
+    /// otherwise.
+    ///
+    /// # Errors
+    /// [`Error::MathError`] when no yield in the bracket gives the price, as for
+    /// cashflows that are all paid out, or none is found within the iterations.
+    pub fn solve(
+        &self,
+        events: &[CashflowEvent],
+        price: Price,
+        day_count: DayCountConvention,
+    ) -> Result<Rate> {
+        let math_error = |message: String| Error::MathError(message);
+        let (mut low, mut high) = self.bracket;
+        let bps = BASIS_POINT_FACTOR as f64;
+        if low <= -(BASIS_POINT_FACTOR as Rate) || high <= low {
+            return Err(math_error(format!(
+                "The yield bracket {:?} must be above -100%",
+                self.bracket
+            )));
+        }
+
+        let notional = events
+            .first()
+            .map(|first| first.state.notional_principal)
+            .filter(|notional| *notional > 0)
+            .ok_or_else(|| math_error("No notional to price against".into()))?;
+        let flows: Vec<(f64, f64)> = cashflows(events, day_count)
+            .map(|(payoff, years)| (payoff as f64, years as f64 / bps))
+            .collect();
+
+        // the price per 100 of the notional at `rate`, less the one to match
+        let gap = |rate: Rate| {
+            let growth = 1.0 + rate as f64 / bps;
+            let value: f64 = flows
+                .iter()
+                .map(|(payoff, years)| payoff * growth.powf(-years))
+                .sum();
+            value / notional as f64 * 100.0 * bps - price as f64
+        };
+        let tolerance = self.tolerance as f64;
+
+        let mut low_gap = gap(low);
+        let high_gap = gap(high);
+        if low_gap.abs() <= tolerance {
+            return Ok(low);
+        }
+        if high_gap.abs() <= tolerance {
+            return Ok(high);
+        }
+        if (low_gap > 0.0) == (high_gap > 0.0) {
+            return Err(math_error(format!(
+                "No yield from {low} to {high} basis points prices the cashflows at {price}"
+            )));
+        }
+
+        // from a zero yield: the price of positive cashflows falls convexly in the yield,
+        // so Newton steps from below a positive yield approach it without overshooting
+        let mut rate = 0.clamp(low + 1, high - 1);
+        for _ in 0..self.max_iterations {
+            let at = gap(rate);
+            if at.abs() <= tolerance {
+                return Ok(rate);
+            }
+            // the price crosses the target between `low` and `high`
+            if (at > 0.0) == (low_gap > 0.0) {
+                (low, low_gap) = (rate, at);
+            } else {
+                high = rate;
+            }
+            if high - low <= 1 {
+                return Ok(if low_gap.abs() <= gap(high).abs() {
+                    low
+                } else {
+                    high
+                });
+            }
+
+            let slope = gap(rate + 1) - at;
+            let newton = (rate as f64 - at / slope).round();
+            rate = if (low as f64) < newton && newton < high as f64 {
+                newton as Rate
+            } else {
+                low + (high - low) / 2
+            };
+        }
+
+        Err(math_error(format!(
+            "The yield did not converge in {} iterations",
+            self.max_iterations
+        )))
+    }
+}
+
+/// [`YieldSearch::solve`] with the [default](YieldSearch::default) search
+pub fn yield_to_maturity(
+    events: &[CashflowEvent],
+    price: Price,
+    day_count: DayCountConvention,
+) -> Result<Rate> {
+    YieldSearch::default().solve(events, price, day_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractTerms, ContractType, Cycle, Period, Stub},
+        lifecycle, templates,
+    };
+
+    #[test]
+    fn zero_coupon_bond_lives_and_lasts_until_maturity() {
+        let start = math::timestamp_from_civil(2025, 1, 1);
+        let maturity = math::add_months(start, 30).unwrap();
+        let terms = templates::term_deposit(1_000_000, 400, start, maturity, "USD").unwrap();
+        let events = lifecycle::run(&terms, &()).unwrap();
+
+        for day_count in [
+            DayCountConvention::DCC_A_365,
+            DayCountConvention::DCC_E30_360,
+        ] {
+            let to_maturity = math::year_fraction(day_count as u8, start, maturity, None);
+            assert_eq!(weighted_average_life(&events, day_count), Some(to_maturity));
+
+            let duration = duration(&events, 500, day_count).unwrap();
+            assert_eq!(duration.macaulay, to_maturity);
+            assert_eq!(duration.modified, (to_maturity * 10_000 + 5_250) / 10_500);
+        }
+    }
+
+    /// The 300_000 mortgage at 4.8% over ten years the contract tests settle
+    fn golden_mortgage() -> Vec<CashflowEvent> {
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+        let terms = ContractTerms {
+            contract_type: ContractType::ANN,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 120),
+            notional_principal: Some(300_000),
+            nominal_interest_rate: Some(480),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        };
+        lifecycle::run(&terms, &()).unwrap()
+    }
+
+    #[test]
+    fn annuity_matches_the_spreadsheet() {
+        let events = golden_mortgage();
+        let day_count = DayCountConvention::DCC_E30_360;
+
+        // a spreadsheet amortizing 300_000 over 120 months at 0.4% a month, with the
+        // k-th installment of 3152.72 k/12 years in: WAL = SUMPRODUCT(t, principal) /
+        // 300_000 = 5.43932 years, and discounted at 5% the installments weigh to a
+        // Macaulay duration of 4.63671 years, 4.41592 modified. The engine floors its
+        // year fractions to basis points and truncates interest to the unit, so it
+        // comes out a few basis points shorter.
+        let wal = weighted_average_life(&events, day_count).unwrap();
+        assert!(wal.abs_diff(54_393) <= 5, "WAL {wal}");
+
+        let duration = duration(&events, 500, day_count).unwrap();
+        assert!(duration.macaulay.abs_diff(46_367) <= 5, "{duration:?}");
+        assert!(duration.modified.abs_diff(44_159) <= 5, "{duration:?}");
+        assert!(duration.macaulay < wal);
+    }
+
+    /// 1000 at 5% with annual coupons for two years, on Actual/365 over years of 365 days
+    fn two_coupon_bond() -> Vec<CashflowEvent> {
+        let issue = math::timestamp_from_civil(2025, 1, 1);
+        let maturity = math::timestamp_from_civil(2027, 1, 1);
+        let terms = ContractTerms {
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2026, 1, 1)),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_Y,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..templates::term_deposit(1_000_000_000_000, 500, issue, maturity, "USD").unwrap()
+        };
+        lifecycle::run(&terms, &()).unwrap()
+    }
+
+    #[test]
+    fn par_bond_yields_its_coupon() {
+        let events = two_coupon_bond();
+        assert_eq!(
+            yield_to_maturity(&events, 1_000_000, DayCountConvention::DCC_A_365),
+            Ok(500)
+        );
+    }
+
+    #[test]
+    fn discount_bond_yields_above_its_coupon() {
+        let events = two_coupon_bond();
+        let day_count = DayCountConvention::DCC_A_365;
+
+        // 50 / 1.0609 + 1050 / 1.0609^2 = 980.04 per 1000, the nearest to 980
+        assert_eq!(yield_to_maturity(&events, 980_000, day_count), Ok(609));
+        assert!(yield_to_maturity(&events, 999_000, day_count).unwrap() > 500);
+        assert!(yield_to_maturity(&events, 1_020_000, day_count).unwrap() < 500);
+    }
+
+    #[test]
+    fn solver_converges_for_a_long_annuity() {
+        let start = math::timestamp_from_civil(2025, 1, 1);
+        let terms = templates::annuity_mortgage(300_000_000_000, 480, start, 360, "USD").unwrap();
+        let events = lifecycle::run(&terms, &()).unwrap();
+        let day_count = DayCountConvention::DCC_A_360;
+
+        let search = YieldSearch {
+            max_iterations: 8,
+            ..Default::default()
+        };
+        // monthly compounding at 4.8% on Actual/360 is about 5.0% a year
+        let ytm = search.solve(&events, 1_000_000, day_count).unwrap();
+        assert!(ytm.abs_diff(500) <= 10, "yield {ytm}");
+
+        // a cap too low to narrow the bracket gives up
+        let capped = YieldSearch {
+            max_iterations: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            capped.solve(&events, 1_000_000, day_count),
+            Err(Error::MathError(_))
+        ));
+    }
+
+    #[test]
+    fn no_yield_prices_what_is_paid_out() {
+        let events: Vec<_> = two_coupon_bond()
+            .into_iter()
+            .map(|event| CashflowEvent {
+                payoff: event.payoff.map(|payoff| -payoff),
+                ..event
+            })
+            .collect();
+
+        assert!(matches!(
+            yield_to_maturity(&events, 1_000_000, DayCountConvention::DCC_A_365),
+            Err(Error::MathError(_))
+        ));
+    }
+
+    #[test]
+    fn nothing_paid_has_no_life_or_duration() {
+        let events = &golden_mortgage()[..1];
+        assert_eq!(
+            weighted_average_life(events, DayCountConvention::DCC_A_365),
+            None
+        );
+        assert_eq!(duration(events, 500, DayCountConvention::DCC_A_365), None);
+        assert_eq!(duration(&[], 500, DayCountConvention::DCC_A_365), None);
+    }
+}
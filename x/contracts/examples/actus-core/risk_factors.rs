@@ -0,0 +1,137 @@
+//! Market data observed during an off-chain run.
+//!
+//! [`lifecycle::run`](crate::lifecycle::run) looks up the market object code of the
+//! terms at each rate reset and scaling index fixing. Values are in the unit the
+//! observing term expects: basis points for rates, the scaling index itself for SC.
+
+use crate::core::Timestamp;
+use std::collections::BTreeMap;
+
+/// Market data keyed by market object code
+pub trait RiskFactors {
+    /// The value of the `market_object_code` series in effect at `time`
+    fn observe(&self, market_object_code: &str, time: Timestamp) -> Option<u64>;
+
+    /// Like [`observe`](Self::observe), with the time the value was observed at.
+    /// Sources that do not keep it report the value as observed at `time`.
+    fn observation(&self, market_object_code: &str, time: Timestamp) -> Option<(Timestamp, u64)> {
+        self.observe(market_object_code, time)
+            .map(|value| (time, value))
+    }
+}
+
+/// No market data, for contracts that observe none
+impl RiskFactors for () {
+    fn observe(&self, _market_object_code: &str, _time: Timestamp) -> Option<u64> {
+        None
+    }
+}
+
+/// Observed values of each market object code in time order. A series holds each
+/// value until its next observation, and has none before its first one.
+///
+/// The JSON form maps each code to its `[timestamp, value]` observations:
+///
+/// ```json
+/// { "EURIBOR3M": [[1735689600, 300], [1743465600, 325]] }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "BTreeMap<String, Vec<(Timestamp, u64)>>")
+)]
+pub struct RiskFactorSeries {
+    series: BTreeMap<String, Vec<(Timestamp, u64)>>,
+}
+
+impl RiskFactorSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the series of `market_object_code`. Observations at the same time keep
+    /// their order, so the last of them is the one in effect.
+    pub fn insert(
+        &mut self,
+        market_object_code: impl Into<String>,
+        observations: Vec<(Timestamp, u64)>,
+    ) {
+        let mut observations = observations;
+        observations.sort_by_key(|(time, _)| *time);
+        self.series.insert(market_object_code.into(), observations);
+    }
+
+    pub fn get(&self, market_object_code: &str) -> Option<&[(Timestamp, u64)]> {
+        self.series.get(market_object_code).map(Vec::as_slice)
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+impl From<BTreeMap<String, Vec<(Timestamp, u64)>>> for RiskFactorSeries {
+    fn from(series: BTreeMap<String, Vec<(Timestamp, u64)>>) -> Self {
+        let mut factors = Self::new();
+        for (market_object_code, observations) in series {
+            factors.insert(market_object_code, observations);
+        }
+        factors
+    }
+}
+
+impl RiskFactors for RiskFactorSeries {
+    fn observe(&self, market_object_code: &str, time: Timestamp) -> Option<u64> {
+        self.observation(market_object_code, time)
+            .map(|(_, value)| value)
+    }
+
+    fn observation(&self, market_object_code: &str, time: Timestamp) -> Option<(Timestamp, u64)> {
+        let series = self.get(market_object_code)?;
+        let observed = series.partition_point(|(at, _)| *at <= time);
+        observed.checked_sub(1).map(|last| series[last])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn series_hold_the_previous_observation() {
+        let mut factors = RiskFactorSeries::new();
+        factors.insert("EURIBOR3M", vec![(200, 350), (100, 300)]);
+
+        assert_eq!(factors.observe("EURIBOR3M", 99), None);
+        assert_eq!(factors.observe("EURIBOR3M", 100), Some(300));
+        assert_eq!(factors.observe("EURIBOR3M", 199), Some(300));
+        assert_eq!(factors.observe("EURIBOR3M", 1_000), Some(350));
+        assert_eq!(factors.observe("ESTR", 1_000), None);
+
+        assert_eq!(factors.observation("EURIBOR3M", 199), Some((100, 300)));
+        assert_eq!(().observation("EURIBOR3M", 199), None);
+    }
+
+    #[test]
+    fn later_observations_at_the_same_time_win() {
+        let mut factors = RiskFactorSeries::new();
+        factors.insert("EURIBOR3M", vec![(100, 300), (100, 310)]);
+
+        assert_eq!(factors.observe("EURIBOR3M", 100), Some(310));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_series_are_sorted_on_load() {
+        let factors =
+            RiskFactorSeries::from_json(r#"{ "EURIBOR3M": [[200, 350], [100, 300]] }"#).unwrap();
+
+        assert_eq!(
+            factors.get("EURIBOR3M"),
+            Some(&[(100, 300), (200, 350)][..])
+        );
+        assert!(RiskFactorSeries::from_json(r#"{ "EURIBOR3M": [[100]] }"#).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+//! Monte Carlo runs of the same terms over simulated market data.
+//!
+//! A [`PathGenerator`] draws the risk factors of one path from a seed. Each path's
+//! seed is derived from the run seed and the path index alone, so results do not
+//! depend on how paths are spread over threads with the `parallel` feature.
+
+use crate::{
+    core::{ContractTerms, Timestamp, Units},
+    lifecycle::{self, CashflowEvent, RunError},
+    math::SECONDS_PER_DAY,
+    risk_factors::RiskFactorSeries,
+};
+use std::fmt;
+
+/// Draws the market data of one path
+pub trait PathGenerator: Sync {
+    fn generate(&self, rng: &mut SplitMix64) -> RiskFactorSeries;
+}
+
+/// Discount factors for valuing the cashflows of a path
+pub trait DiscountCurve: Sync {
+    fn discount_factor(&self, time: Timestamp) -> f64;
+}
+
+/// A constant annually compounded rate in basis points, discounting to the valuation
+/// date on actual/365. Cashflows before the valuation date are not discounted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlatCurve {
+    pub valuation_date: Timestamp,
+    pub rate: u64,
+}
+
+impl DiscountCurve for FlatCurve {
+    fn discount_factor(&self, time: Timestamp) -> f64 {
+        let years =
+            time.saturating_sub(self.valuation_date) as f64 / (365.0 * SECONDS_PER_DAY as f64);
+        (1.0 + self.rate as f64 / 10_000.0).powf(-years)
+    }
+}
+
+/// A rate moving up or down by `step` basis points with equal odds at each date,
+/// never below zero, observed under `market_object_code`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomWalk {
+    pub market_object_code: String,
+    pub start: u64,
+    pub step: u64,
+    pub dates: Vec<Timestamp>,
+}
+
+impl PathGenerator for RandomWalk {
+    fn generate(&self, rng: &mut SplitMix64) -> RiskFactorSeries {
+        let mut rate = self.start;
+        let observations = self
+            .dates
+            .iter()
+            .map(|date| {
+                rate = if rng.next_u64() & 1 == 0 {
+                    rate.saturating_add(self.step)
+                } else {
+                    rate.saturating_sub(self.step)
+                };
+                (*date, rate)
+            })
+            .collect();
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert(self.market_object_code.clone(), observations);
+        risk_factors
+    }
+}
+
+/// The SplitMix64 generator: small, fast and reproducible on every platform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Totals of one path, from the point of view of the contract's role
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathResult {
+    /// Payoffs received, in base units
+    pub inflows: Units,
+    /// Payoffs paid out, in base units
+    pub outflows: Units,
+    /// Net payoffs discounted on the curve, in base units
+    pub npv: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarlo {
+    /// Results in path order
+    pub paths: Vec<PathResult>,
+    pub mean_npv: f64,
+    /// NPVs of all paths in ascending order
+    sorted_npvs: Vec<f64>,
+}
+
+impl MonteCarlo {
+    /// The NPV below which `percentile` percent of the paths fall, by nearest rank
+    pub fn npv_percentile(&self, percentile: f64) -> f64 {
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.sorted_npvs.len() as f64).ceil();
+        self.sorted_npvs[(rank as usize).clamp(1, self.sorted_npvs.len()) - 1]
+    }
+}
+
+/// The first path, in path order, whose run failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathError {
+    pub path: usize,
+    pub error: RunError,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "path {}: {}", self.path, self.error)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Runs `terms` over `n_paths` paths of `path_generator`, valuing each on `curve`.
+/// The same seed always gives the same result.
+///
+/// # Panics
+/// Panics if `n_paths` is zero.
+pub fn monte_carlo<G, C>(
+    terms: &ContractTerms,
+    path_generator: &G,
+    curve: &C,
+    n_paths: usize,
+    seed: u64,
+) -> Result<MonteCarlo, PathError>
+where
+    G: PathGenerator + ?Sized,
+    C: DiscountCurve + ?Sized,
+{
+    assert!(n_paths > 0, "monte carlo needs at least one path");
+
+    let run_path = |path: usize| {
+        let mut rng = SplitMix64::new(path_seed(seed, path));
+        let risk_factors = path_generator.generate(&mut rng);
+        lifecycle::run(terms, &risk_factors)
+            .map(|events| totals(terms, &events, curve))
+            .map_err(|error| PathError { path, error })
+    };
+
+    // every path runs, so the error reported does not depend on scheduling
+    #[cfg(feature = "parallel")]
+    let results: Vec<_> = {
+        use rayon::prelude::*;
+        (0..n_paths).into_par_iter().map(run_path).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let results: Vec<_> = (0..n_paths).map(run_path).collect();
+
+    let paths = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+    let mut sorted_npvs: Vec<f64> = paths.iter().map(|path| path.npv).collect();
+    sorted_npvs.sort_by(f64::total_cmp);
+    let mean_npv = sorted_npvs.iter().sum::<f64>() / n_paths as f64;
+
+    Ok(MonteCarlo {
+        paths,
+        mean_npv,
+        sorted_npvs,
+    })
+}
+
+/// Seed of path `path`, independent of every other path
+fn path_seed(seed: u64, path: usize) -> u64 {
+    SplitMix64::new(seed ^ (path as u64).wrapping_mul(0xd605_bbb5_8c8a_bbc3)).next_u64()
+}
+
+fn totals<C: DiscountCurve + ?Sized>(
+    terms: &ContractTerms,
+    events: &[CashflowEvent],
+    curve: &C,
+) -> PathResult {
+    let sign = terms.contract_role.sign();
+    let (mut inflows, mut outflows, mut npv): (Units, Units, f64) = (0, 0, 0.0);
+
+    for event in events {
+        let Some(payoff) = event.payoff else { continue };
+        // a negative payoff flows against its event
+        let received = (event.event_type.paid_by_creditor() == (sign < 0)) != (payoff < 0);
+        let amount = payoff.unsigned_abs();
+
+        if received {
+            inflows = inflows.saturating_add(amount);
+            npv += amount as f64 * curve.discount_factor(event.time);
+        } else {
+            outflows = outflows.saturating_add(amount);
+            npv -= amount as f64 * curve.discount_factor(event.time);
+        }
+    }
+
+    PathResult {
+        inflows,
+        outflows,
+        npv,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractType, Cycle, DayCountConvention, EventType, Period, SignedUnits, Stub},
+        math,
+    };
+
+    fn floating_pam() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2025, 1, 1);
+        let quarterly = Cycle {
+            n: 3,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        };
+
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 24),
+            notional_principal: Some(1_000_000_000_000),
+            nominal_interest_rate: Some(300),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 3),
+            cycle_of_interest_payment: Some(quarterly.clone()),
+            cycle_anchor_date_of_rate_reset: math::add_months(ied, 3),
+            cycle_of_rate_reset: Some(quarterly),
+            market_object_code_of_rate_reset: Some("EURIBOR3M".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn walk(terms: &ContractTerms) -> RandomWalk {
+        let dates = lifecycle::schedule(terms)
+            .unwrap()
+            .into_iter()
+            .filter(|(event, _)| *event == EventType::RR)
+            .map(|(_, date)| date)
+            .collect();
+
+        RandomWalk {
+            market_object_code: "EURIBOR3M".to_string(),
+            start: 300,
+            step: 25,
+            dates,
+        }
+    }
+
+    fn curve(terms: &ContractTerms) -> FlatCurve {
+        FlatCurve {
+            valuation_date: terms.status_date,
+            rate: 300,
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_result() {
+        let terms = floating_pam();
+        let (walk, curve) = (walk(&terms), curve(&terms));
+
+        let first = monte_carlo(&terms, &walk, &curve, 200, 42).unwrap();
+        let second = monte_carlo(&terms, &walk, &curve, 200, 42).unwrap();
+        assert_eq!(first, second);
+
+        let other = monte_carlo(&terms, &walk, &curve, 200, 43).unwrap();
+        assert_ne!(first.paths, other.paths);
+    }
+
+    #[test]
+    fn paths_do_not_depend_on_the_path_count() {
+        let terms = floating_pam();
+        let (walk, curve) = (walk(&terms), curve(&terms));
+
+        let few = monte_carlo(&terms, &walk, &curve, 10, 7).unwrap();
+        let many = monte_carlo(&terms, &walk, &curve, 100, 7).unwrap();
+        assert_eq!(few.paths, many.paths[..10]);
+    }
+
+    #[test]
+    fn statistics_summarize_the_paths() {
+        let terms = floating_pam();
+        let result = monte_carlo(&terms, &walk(&terms), &curve(&terms), 101, 1).unwrap();
+
+        let npvs: Vec<f64> = result.paths.iter().map(|path| path.npv).collect();
+        let min = npvs.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = npvs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        assert_eq!(result.npv_percentile(0.0), min);
+        assert_eq!(result.npv_percentile(100.0), max);
+        assert!(result.npv_percentile(5.0) <= result.npv_percentile(50.0));
+        assert!(result.npv_percentile(50.0) <= result.npv_percentile(95.0));
+        assert!(min <= result.mean_npv && result.mean_npv <= max);
+
+        // the lender pays the notional once and gets it back with the interest
+        for path in &result.paths {
+            assert_eq!(path.outflows, 1_000_000_000_000);
+            assert!(path.inflows > path.outflows);
+        }
+    }
+
+    #[test]
+    fn constant_paths_match_a_single_run() {
+        struct Constant;
+
+        impl PathGenerator for Constant {
+            fn generate(&self, _rng: &mut SplitMix64) -> RiskFactorSeries {
+                let mut risk_factors = RiskFactorSeries::new();
+                risk_factors.insert("EURIBOR3M", vec![(0, 300)]);
+                risk_factors
+            }
+        }
+
+        let terms = floating_pam();
+        let curve = FlatCurve {
+            valuation_date: terms.status_date,
+            rate: 0,
+        };
+        let result = monte_carlo(&terms, &Constant, &curve, 5, 0).unwrap();
+
+        let events = lifecycle::run(&terms, &Constant.generate(&mut SplitMix64::new(0))).unwrap();
+        let interest: SignedUnits = events
+            .iter()
+            .filter(|event| event.event_type != EventType::IED)
+            .filter_map(|event| event.payoff)
+            .sum::<SignedUnits>()
+            - 1_000_000_000_000;
+
+        for path in &result.paths {
+            assert_eq!(path.npv, interest as f64);
+        }
+        assert_eq!(result.npv_percentile(50.0), interest as f64);
+    }
+
+    #[test]
+    fn failing_paths_are_reported_in_order() {
+        let terms = floating_pam();
+        let empty = RandomWalk {
+            dates: vec![],
+            ..walk(&terms)
+        };
+
+        let error = monte_carlo(&terms, &empty, &curve(&terms), 8, 3).unwrap_err();
+        assert_eq!(error.path, 0);
+        assert_eq!(
+            error.error.event.map(|(event, _)| event),
+            Some(EventType::RR)
+        );
+    }
+
+    #[test]
+    fn splitmix_is_reproducible() {
+        let mut rng = SplitMix64::new(1234567);
+        let draws: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+
+        assert_eq!(
+            draws,
+            [
+                6457827717110365317,
+                3203168211198807973,
+                9817491932198370423
+            ]
+        );
+        assert!((0..1_000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+    }
+}
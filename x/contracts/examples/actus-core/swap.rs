@@ -0,0 +1,178 @@
+//! Swaps composed of two contracts, a leg the holder receives and a leg it pays.
+//!
+//! Each leg runs as a contract of its own, and the swap pays what they pay their
+//! creditors netted at each date: the first leg's cashflows less the second's, so a
+//! fixed-for-floating swap is a fixed PAM against a resetting one.
+//!
+//! ```ignore
+//! let cashflows = swap::run(&fixed, &floating, &risk_factors)?;
+//! let total: SignedUnits = cashflows.iter().map(NetCashflow::net).sum();
+//! ```
+
+use crate::{
+    core::{ContractTerms, Error, EventType, Result, SignedUnits, Timestamp},
+    lifecycle::{self, CashflowEvent, RunError},
+    risk_factors::RiskFactors,
+};
+
+/// What the legs of a swap pay their creditors at one date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetCashflow {
+    pub time: Timestamp,
+    /// Paid by the first leg, which the holder receives
+    pub received: SignedUnits,
+    /// Paid by the second leg, which the holder pays
+    pub paid: SignedUnits,
+}
+
+impl NetCashflow {
+    /// What the holder of the swap nets, negative when it pays
+    pub fn net(&self) -> SignedUnits {
+        self.received - self.paid
+    }
+}
+
+/// The payoff of `event` as paid to the creditor side, negative when the creditor pays
+/// it, like a disbursement or a purchase
+pub fn to_creditor(event: EventType, payoff: SignedUnits) -> SignedUnits {
+    if event.paid_by_creditor() {
+        -payoff
+    } else {
+        payoff
+    }
+}
+
+/// Nets the events of the two legs by date, earliest first, from their payoffs as
+/// [`TransitionEngine::process_event`](crate::core::TransitionEngine::process_event)
+/// returns them, projected or recorded. A date only one leg pays on nets against zero,
+/// and events without a payoff are left out.
+pub fn net(
+    first: &[(EventType, Timestamp, Option<SignedUnits>)],
+    second: &[(EventType, Timestamp, Option<SignedUnits>)],
+) -> Result<Vec<NetCashflow>> {
+    let mut cashflows: Vec<NetCashflow> = Vec::new();
+    let first = first.iter().map(|event| (true, event));
+    let second = second.iter().map(|event| (false, event));
+
+    for (receives, &(event_type, time, payoff)) in first.chain(second) {
+        let Some(payoff) = payoff else {
+            continue;
+        };
+
+        let position = cashflows.partition_point(|cashflow| cashflow.time < time);
+        if cashflows.get(position).is_none_or(|cashflow| cashflow.time != time) {
+            cashflows.insert(position, NetCashflow { time, received: 0, paid: 0 });
+        }
+
+        let cashflow = &mut cashflows[position];
+        let leg = if receives { &mut cashflow.received } else { &mut cashflow.paid };
+        *leg = leg
+            .checked_add(to_creditor(event_type, payoff))
+            .ok_or(Error::MathError("Swap payoff overflow".into()))?;
+    }
+
+    Ok(cashflows)
+}
+
+/// Runs both legs from their status dates as [`lifecycle::run`] does and nets their
+/// cashflows. The legs must pay in the same currency.
+pub fn run<R>(
+    first: &ContractTerms,
+    second: &ContractTerms,
+    risk_factors: &R,
+) -> std::result::Result<Vec<NetCashflow>, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    if first.currency != second.currency {
+        return Err(Error::ValidationError(
+            "The legs of a swap must pay in the same currency".into(),
+        )
+        .into());
+    }
+
+    let payoffs = |events: Vec<CashflowEvent>| -> Vec<_> {
+        events
+            .into_iter()
+            .map(|event| (event.event_type, event.time, event.payoff))
+            .collect()
+    };
+    let first = payoffs(lifecycle::run(first, risk_factors)?);
+    let second = payoffs(lifecycle::run(second, risk_factors)?);
+
+    Ok(net(&first, &second)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{ContractType, Cycle, DayCountConvention, Period, Stub},
+        math,
+        risk_factors::RiskFactorSeries,
+    };
+
+    /// A year of quarterly interest on 1,000,000 at 5%
+    fn fixed_leg() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: math::add_months(ied, 12),
+            notional_principal: Some(1_000_000),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_E30_360),
+            cycle_anchor_date_of_interest_payment: math::add_months(ied, 3),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 3,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn swaps_net_the_interest_of_their_legs() {
+        // the same notional at 4%, reset to 6% after the first quarter
+        let ied = math::timestamp_from_civil(2024, 1, 1);
+        let reset = math::add_months(ied, 3).unwrap();
+        let floating = ContractTerms {
+            nominal_interest_rate: Some(400),
+            cycle_anchor_date_of_rate_reset: Some(reset),
+            cycle_of_rate_reset: fixed_leg().cycle_of_interest_payment,
+            market_object_code_of_rate_reset: Some("EURIBOR3M".into()),
+            ..fixed_leg()
+        };
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("EURIBOR3M", vec![(reset, 600)]);
+
+        let cashflows = run(&fixed_leg(), &floating, &risk_factors).unwrap();
+        let nets: Vec<_> = cashflows.iter().map(NetCashflow::net).collect();
+
+        // the notionals exchanged at the IED and the MD cancel out, leaving a quarter of
+        // 1% received, then paid once the floating rate is above the fixed one
+        assert_eq!(nets, [0, 2_500, -2_500, -2_500, -2_500]);
+        assert_eq!(cashflows[1], NetCashflow { time: reset, received: 12_500, paid: 10_000 });
+    }
+
+    #[test]
+    fn dates_only_one_leg_pays_on_net_against_nothing() {
+        let first = [(EventType::IP, 10, Some(30)), (EventType::AD, 15, None)];
+        let second = [(EventType::IED, 5, Some(100)), (EventType::IP, 10, Some(20))];
+
+        assert_eq!(
+            net(&first, &second),
+            Ok(vec![
+                NetCashflow { time: 5, received: 0, paid: -100 },
+                NetCashflow { time: 10, received: 30, paid: 20 },
+            ])
+        );
+
+        let mismatched = ContractTerms { currency: Some("USD".into()), ..fixed_leg() };
+        assert!(run(&fixed_leg(), &mismatched, &()).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Terms of common instruments, so a bond or a mortgage is set up from its notional,
+//! rate, dates and currency rather than the ACTUS terms behind it.
+//!
+//! Every template accrues on Actual/360 and records a Monday to Friday calendar with
+//! payments rolled to the following business day; the engine schedules on the
+//! unadjusted dates. The terms are held by the lender, with an unset contract id, and
+//! are checked the way `init` checks them before they are returned:
+//!
+//! ```ignore
+//! let terms = ContractTerms {
+//!     contract_id: "us-mortgage-1".into(),
+//!     ..templates::annuity_mortgage(300_000, 480, start, 360, "USD")?
+//! };
+//! ```
+
+use crate::{
+    core::{
+        BusinessDayConvention, Calendar, ContractTerms, ContractType, Cycle, DayCountConvention,
+        EndOfMonthConvention, Error, Period, Rate, Result, ScheduleConfig, Stub, Timestamp, Units,
+        MAX_STRING_TERM_LEN,
+    },
+    math,
+};
+
+/// Months between the coupons of a bullet bond
+pub const BOND_COUPON_MONTHS: i64 = 6;
+
+fn schedule_config() -> ScheduleConfig {
+    ScheduleConfig {
+        calendar: Some(Calendar::CLDR_MF),
+        end_of_month_convention: Some(EndOfMonthConvention::EOMC_SD),
+        business_day_convention: Some(BusinessDayConvention::BDC_SCF),
+    }
+}
+
+fn every(n: i64) -> Option<Cycle> {
+    Some(Cycle {
+        n,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    })
+}
+
+/// The terms every template shares: a fixed-rate contract of `contract_type` from
+/// `start` to `maturity`
+fn fixed_rate(
+    contract_type: ContractType,
+    notional: Units,
+    rate: Rate,
+    start: Timestamp,
+    maturity: Timestamp,
+    currency: &str,
+) -> Result<ContractTerms> {
+    if notional == 0 {
+        return Err(Error::ValidationError(
+            "The notional must be non-zero".into(),
+        ));
+    }
+    if maturity <= start {
+        return Err(Error::ValidationError(
+            "Maturity must follow the start date".into(),
+        ));
+    }
+    if currency.len() > MAX_STRING_TERM_LEN {
+        return Err(Error::ValidationError(format!(
+            "`currency` exceeds {MAX_STRING_TERM_LEN} bytes"
+        )));
+    }
+
+    Ok(ContractTerms {
+        contract_type,
+        currency: Some(currency.into()),
+        status_date: start,
+        initial_exchange_date: Some(start),
+        maturity_date: Some(maturity),
+        notional_principal: Some(notional),
+        nominal_interest_rate: Some(rate),
+        day_count_convention: Some(DayCountConvention::DCC_A_360),
+        schedule_config: schedule_config(),
+        ..Default::default()
+    })
+}
+
+fn checked(terms: ContractTerms) -> Result<ContractTerms> {
+    terms.check_required()?;
+    terms.check_disbursements()?;
+    terms.check_dates()?;
+    Ok(terms)
+}
+
+/// A PAM issued at `issue_date` paying a coupon at `rate` basis points every
+/// [`BOND_COUPON_MONTHS`] from then, and the notional with the last coupon at
+/// `maturity`
+pub fn bullet_bond(
+    notional: Units,
+    rate: Rate,
+    issue_date: Timestamp,
+    maturity: Timestamp,
+    currency: &str,
+) -> Result<ContractTerms> {
+    let terms = fixed_rate(
+        ContractType::PAM,
+        notional,
+        rate,
+        issue_date,
+        maturity,
+        currency,
+    )?;
+    checked(ContractTerms {
+        cycle_anchor_date_of_interest_payment: math::add_months(
+            issue_date,
+            BOND_COUPON_MONTHS as u64,
+        ),
+        cycle_of_interest_payment: every(BOND_COUPON_MONTHS),
+        ..terms
+    })
+}
+
+/// An ANN repaid in `months` equal monthly installments of interest and principal,
+/// the first a month after `start`. The rate must not be negative, as the installment
+/// has no closed form below zero; running it needs the `ann` feature.
+pub fn annuity_mortgage(
+    notional: Units,
+    rate: Rate,
+    start: Timestamp,
+    months: u64,
+    currency: &str,
+) -> Result<ContractTerms> {
+    if rate < 0 {
+        return Err(Error::ValidationError(
+            "Annuity installments need a non-negative rate".into(),
+        ));
+    }
+    let maturity = math::add_months(start, months)
+        .ok_or_else(|| Error::ValidationError("The mortgage ends past the last date".into()))?;
+
+    let terms = fixed_rate(ContractType::ANN, notional, rate, start, maturity, currency)?;
+    checked(ContractTerms {
+        cycle_anchor_date_of_interest_payment: math::add_months(start, 1),
+        cycle_of_interest_payment: every(1),
+        ..terms
+    })
+}
+
+/// A PAM placed at `start` that returns the notional with all its interest at
+/// `maturity`, without payments in between
+pub fn term_deposit(
+    notional: Units,
+    rate: Rate,
+    start: Timestamp,
+    maturity: Timestamp,
+    currency: &str,
+) -> Result<ContractTerms> {
+    checked(fixed_rate(
+        ContractType::PAM,
+        notional,
+        rate,
+        start,
+        maturity,
+        currency,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{EventType, SignedUnits},
+        lifecycle::{self, CashflowEvent},
+    };
+
+    fn payoff(event: &CashflowEvent) -> SignedUnits {
+        event.payoff.expect("scheduled events pay")
+    }
+
+    #[test]
+    fn bullet_bond_pays_its_coupons_and_the_notional_at_maturity() {
+        let issue = math::timestamp_from_civil(2025, 1, 1);
+        let maturity = math::add_months(issue, 60).unwrap();
+        let terms = bullet_bond(1_000_000, 500, issue, maturity, "USD").unwrap();
+
+        let events = lifecycle::run(&terms, &()).unwrap();
+        let kinds: Vec<_> = events.iter().map(|event| event.event_type).collect();
+        let mut expected = vec![EventType::IED];
+        expected.extend([EventType::IP; 9]);
+        expected.push(EventType::MD);
+        assert_eq!(kinds, expected);
+
+        // each coupon accrues over the actual days of its half year, as a year fraction
+        // in basis points
+        let coupon = |days: u64| 1_000_000 * 500 * (days * 10_000 / 360) as i128 / 100_000_000;
+        for (k, event) in (1..).zip(&events[1..10]) {
+            let start = math::add_months(issue, 6 * (k - 1)).unwrap();
+            assert_eq!(event.time, math::add_months(issue, 6 * k).unwrap());
+            let days = (event.time - start) / math::SECONDS_PER_DAY;
+            assert_eq!(payoff(event), coupon(days), "coupon {k}");
+        }
+
+        let last = events.last().unwrap();
+        assert_eq!(last.time, maturity);
+        assert_eq!(payoff(last), 1_000_000 + coupon(184));
+        assert_eq!(last.state.notional_principal, 0);
+        assert_eq!(terms.currency.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn annuity_mortgage_repays_in_equal_installments() {
+        let start = math::timestamp_from_civil(2025, 1, 1);
+        let terms = annuity_mortgage(300_000, 480, start, 360, "USD").unwrap();
+        assert_eq!(terms.maturity_date, math::add_months(start, 360));
+
+        let events = lifecycle::run(&terms, &()).unwrap();
+        let (exchange, installments) = events.split_first().unwrap();
+        assert_eq!(exchange.event_type, EventType::IED);
+        assert_eq!(installments.len(), 360);
+
+        let installment = exchange.state.next_principal_redemption_payment as SignedUnits;
+        let (last, regular) = installments.split_last().unwrap();
+        for (month, event) in (1..).zip(regular) {
+            assert_eq!(event.event_type, EventType::IP);
+            assert_eq!(event.time, math::add_months(start, month).unwrap());
+            assert_eq!(payoff(event), installment, "month {month}");
+        }
+
+        assert_eq!(last.event_type, EventType::MD);
+        assert_eq!(last.state.notional_principal, 0);
+        // the last installment settles what the rounding of the others left
+        assert!(payoff(last).abs_diff(installment) < 1_000);
+    }
+
+    #[test]
+    fn term_deposit_pays_everything_at_maturity() {
+        let start = math::timestamp_from_civil(2025, 1, 1);
+        let maturity = math::add_months(start, 3).unwrap();
+        let terms = term_deposit(1_000_000, 400, start, maturity, "EUR").unwrap();
+
+        let events = lifecycle::run(&terms, &()).unwrap();
+        let payoffs: Vec<_> = events
+            .iter()
+            .map(|event| (event.event_type, event.time, payoff(event)))
+            .collect();
+
+        // 90 days of 4% on Actual/360 are a quarter of its yearly interest
+        assert_eq!(
+            payoffs,
+            [
+                (EventType::IED, start, 1_000_000),
+                (EventType::MD, maturity, 1_010_000),
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_reject_what_init_would() {
+        let start = math::timestamp_from_civil(2025, 1, 1);
+        let invalid =
+            |result: Result<ContractTerms>| matches!(result, Err(Error::ValidationError(_)));
+
+        assert!(invalid(bullet_bond(1_000, 500, start, start, "USD")));
+        assert!(invalid(term_deposit(0, 400, start, start + 1, "USD")));
+        assert!(invalid(term_deposit(
+            1_000,
+            400,
+            start,
+            start + 1,
+            &"X".repeat(129)
+        )));
+        assert!(invalid(annuity_mortgage(1_000, -10, start, 12, "USD")));
+        assert!(invalid(annuity_mortgage(1_000, 480, start, 0, "USD")));
+        assert!(invalid(annuity_mortgage(
+            1_000,
+            480,
+            start,
+            u64::MAX,
+            "USD"
+        )));
+    }
+}
@@ -0,0 +1,108 @@
+// x/contracts/examples/actus-core/tests/actus_sim.rs
+
+//! Drives the `actus-sim` binary against the terms fixtures.
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn actus_sim(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_actus-sim"))
+        .args(args)
+        .output()
+        .expect("failed to run actus-sim")
+}
+
+fn stdout_rows(output: &Output) -> Vec<String> {
+    assert!(
+        output.status.success(),
+        "actus-sim failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|row| row.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn prints_the_event_table() {
+    let terms = fixture("pam.json");
+    let output = actus_sim(&["--terms", terms.to_str().unwrap(), "--until", "2030-01-01"]);
+
+    assert_eq!(
+        stdout_rows(&output),
+        [
+            "IED 2025-01-01 1000.000000000 1000.000000000 0.000000000",
+            "IP 2025-07-01 24.790000000 1000.000000000 0.000000000",
+            "IP 2026-01-01 25.205000000 1000.000000000 0.000000000",
+            "IP 2026-07-01 24.790000000 1000.000000000 0.000000000",
+            "MD 2027-01-01 1025.205000000 0.000000000 0.000000000",
+        ]
+    );
+}
+
+#[test]
+fn stops_at_the_horizon_and_writes_csv() {
+    let terms = fixture("pam.json");
+    let csv = env::temp_dir().join(format!("actus-sim-{}.csv", std::process::id()));
+    let output = actus_sim(&[
+        "--terms",
+        terms.to_str().unwrap(),
+        "--until",
+        "2026-01-01",
+        "--csv",
+        csv.to_str().unwrap(),
+    ]);
+
+    let rows = stdout_rows(&output);
+    assert_eq!(rows.len(), 3);
+    assert!(rows[2].starts_with("IP 2026-01-01"));
+
+    let written = fs::read_to_string(&csv).unwrap();
+    fs::remove_file(&csv).unwrap();
+    assert_eq!(written.lines().count(), rows.len() + 1);
+    assert!(written
+        .lines()
+        .nth(3)
+        .unwrap()
+        .starts_with("pam-2y,IP,2026-01-01,"));
+}
+
+#[test]
+fn errors_name_the_missing_term() {
+    let terms = env::temp_dir().join(format!("actus-sim-terms-{}.json", std::process::id()));
+    let mut value: serde_json::Value =
+        serde_json::from_slice(&fs::read(fixture("pam.json")).unwrap()).unwrap();
+    value.as_object_mut().unwrap().remove("maturity_date");
+    fs::write(&terms, value.to_string()).unwrap();
+
+    let output = actus_sim(&["--terms", terms.to_str().unwrap(), "--until", "2030-01-01"]);
+    fs::remove_file(&terms).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap().trim(),
+        "error: missing term: term `maturity_date` is not set"
+    );
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    let output = actus_sim(&["--terms", "terms.json", "--until", "2030-02-30"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: `2030-02-30` is not a YYYY-MM-DD date"));
+}
@@ -0,0 +1,247 @@
+// x/contracts/examples/actus-core/tests/snapshots.rs
+
+//! Payoffs and post-states of every event type over a matrix of canned terms, states and
+//! timestamps, compared against the snapshots under `tests/fixtures/snapshots/`. Each
+//! snapshot holds one line per canned state and one per event processed on it, with the
+//! payoff and the state fields the event changed, or the error it failed with.
+//!
+//! A change to the transitions that is meant to move the numbers regenerates them with
+//! `UPDATE_SNAPSHOTS=1 cargo test -p actus-core --test snapshots`.
+
+use actus_core::{
+    core::{
+        ContractState, ContractTerms, ContractType, Cycle, DayCountConvention, EventType, FeeBasis,
+        PenaltyType, Period, Stub, Timestamp, TransitionEngine, IPCB,
+    },
+    math::{self, SECONDS_PER_DAY},
+};
+use serde_json::{Map, Value};
+use std::{env, fs, path::PathBuf};
+
+/// Set to regenerate the snapshots instead of comparing against them
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+/// Scheduled events processed from the IED to reach the mid-life state
+const MID_LIFE_EVENTS: usize = 3;
+/// Offset of the unscheduled timestamp from the status date of a canned state
+const OFF_CYCLE: Timestamp = 45 * SECONDS_PER_DAY;
+
+fn cycle(n: i64, p: Period) -> Option<Cycle> {
+    Some(Cycle {
+        n,
+        p,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    })
+}
+
+fn two_year_pam() -> ContractTerms {
+    let ied = math::timestamp_from_civil(2025, 1, 1);
+    ContractTerms {
+        contract_id: "pam-2y".into(),
+        contract_type: ContractType::PAM,
+        status_date: ied,
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(math::timestamp_from_civil(2027, 1, 1)),
+        notional_principal: Some(1_000_000_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2025, 7, 1)),
+        cycle_of_interest_payment: cycle(6, Period::P_M),
+        ..Default::default()
+    }
+}
+
+fn pam_matrix() -> Vec<(&'static str, ContractTerms)> {
+    let quarterly = math::timestamp_from_civil(2025, 4, 1);
+    vec![
+        ("semiannual", two_year_pam()),
+        (
+            "fees_and_penalties",
+            ContractTerms {
+                day_count_convention: Some(DayCountConvention::DCC_A_360),
+                cycle_anchor_date_of_interest_payment: Some(quarterly),
+                cycle_of_interest_payment: cycle(3, Period::P_M),
+                cycle_anchor_date_of_fee: Some(quarterly),
+                cycle_of_fee: cycle(3, Period::P_M),
+                fee_basis: Some(FeeBasis::FEB_N),
+                fee_rate: Some(100),
+                penalty_type: Some(PenaltyType::PYTP_A),
+                penalty_rate: Some(5_000_000),
+                grace_period: Some(5 * SECONDS_PER_DAY),
+                delinquency_rate: Some(1_000),
+                ..two_year_pam()
+            },
+        ),
+    ]
+}
+
+fn lam_matrix() -> Vec<(&'static str, ContractTerms)> {
+    let first = math::timestamp_from_civil(2025, 2, 1);
+    let monthly = ContractTerms {
+        contract_id: "lam-2y".into(),
+        contract_type: ContractType::LAM,
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_principal_redemption: Some(first),
+        cycle_of_principal_redemption: cycle(1, Period::P_M),
+        next_principal_redemption_payment: Some(40_000_000_000),
+        cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2025, 4, 1)),
+        cycle_of_interest_payment: cycle(3, Period::P_M),
+        ..two_year_pam()
+    };
+    vec![
+        ("monthly", monthly.clone()),
+        (
+            "lagged_base",
+            ContractTerms {
+                interest_calculation_base: Some(IPCB::IPCB_NTL),
+                cycle_anchor_date_of_interest_calculation_base: Some(math::timestamp_from_civil(
+                    2025, 7, 1,
+                )),
+                cycle_of_interest_calculation_base: cycle(6, Period::P_M),
+                ..monthly
+            },
+        ),
+    ]
+}
+
+/// The canned states of `terms`: before and right after the IED, a few scheduled
+/// events in, and with the first payment after the IED missed
+fn canned_states(terms: &ContractTerms) -> Vec<(&'static str, ContractState)> {
+    let ied = terms.initial_exchange_date.unwrap();
+    let new = ContractState::new(terms);
+    let mut after_ied = new.clone();
+    TransitionEngine::process_event(EventType::IED, ied, &mut after_ied, terms).unwrap();
+
+    let mut mid_life = after_ied.clone();
+    for _ in 0..MID_LIFE_EVENTS {
+        let (event, time) = TransitionEngine::next_event(&mid_life, terms).unwrap();
+        TransitionEngine::process_event(event, time, &mut mid_life, terms).unwrap();
+    }
+
+    let mut overdue = after_ied.clone();
+    let (event, time) = TransitionEngine::next_event(&overdue, terms).unwrap();
+    TransitionEngine::miss_payment(event, time, &mut overdue, terms).unwrap();
+
+    vec![
+        ("new", new),
+        ("after_ied", after_ied),
+        ("mid_life", mid_life),
+        ("overdue", overdue),
+    ]
+}
+
+fn state_json(state: &ContractState) -> Map<String, Value> {
+    match serde_json::from_str(&state.to_json()).unwrap() {
+        Value::Object(fields) => fields,
+        other => panic!("a state serializes as an object, not {other}"),
+    }
+}
+
+/// The outcome of `event` at `time` on `state`: its payoff and the fields it changed
+fn outcome(
+    event: EventType,
+    time: Timestamp,
+    state: &ContractState,
+    terms: &ContractTerms,
+) -> Value {
+    let mut after = state.clone();
+    match TransitionEngine::process_event(event, time, &mut after, terms) {
+        Ok(payoff) => {
+            let before = state_json(state);
+            let changed: Map<_, _> = state_json(&after)
+                .into_iter()
+                .filter(|(field, value)| before.get(field) != Some(value))
+                .collect();
+            serde_json::json!({
+                "payoff": payoff.map(|payoff| payoff.to_string()),
+                "state": changed,
+            })
+        }
+        Err(error) => serde_json::json!({ "error": error.to_string() }),
+    }
+}
+
+/// Every case of `matrix`, in order, keyed by terms, state, event and timestamp
+fn run_matrix(matrix: &[(&str, ContractTerms)]) -> Vec<(String, Value)> {
+    let mut cases = Vec::new();
+    for (terms_name, terms) in matrix {
+        for (state_name, state) in canned_states(terms) {
+            let scheduled = TransitionEngine::next_event(&state, terms).map(|(_, time)| time);
+            let times = scheduled
+                .into_iter()
+                .chain([state.status_date + OFF_CYCLE])
+                .collect::<Vec<_>>();
+
+            let prefix = format!("{terms_name}/{state_name}");
+            cases.push((prefix.clone(), Value::Object(state_json(&state))));
+            for event in EventType::ALL {
+                for &time in &times {
+                    let key = format!("{prefix}/{event:?}@{time}");
+                    cases.push((key, outcome(event, time, &state, terms)));
+                }
+            }
+        }
+    }
+    cases
+}
+
+/// One case to a line, so a change shows as the lines of the cases it moved
+fn render(cases: &[(String, Value)]) -> String {
+    let lines: Vec<_> = cases
+        .iter()
+        .map(|(key, value)| format!("  {}: {value}", Value::from(key.as_str())))
+        .collect();
+    format!("{{\n{}\n}}\n", lines.join(",\n"))
+}
+
+fn check_snapshot(name: &str, matrix: &[(&str, ContractTerms)]) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/snapshots")
+        .join(format!("{name}.json"));
+    let cases = run_matrix(matrix);
+
+    if env::var_os(UPDATE_VAR).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, render(&cases)).unwrap();
+        return;
+    }
+
+    let snapshot = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("{}: {err}, create it with {UPDATE_VAR}=1", path.display()));
+    let mut expected: Map<String, Value> = serde_json::from_str(&snapshot).unwrap();
+
+    let mut diff = Vec::new();
+    for (key, actual) in &cases {
+        match expected.remove(key) {
+            Some(expected) if expected == *actual => {}
+            Some(expected) => diff.push(format!(
+                "~ {key}\n    expected {expected}\n      actual {actual}"
+            )),
+            None => diff.push(format!("+ {key}: {actual}")),
+        }
+    }
+    diff.extend(
+        expected
+            .iter()
+            .map(|(key, expected)| format!("- {key}: {expected}")),
+    );
+
+    assert!(
+        diff.is_empty(),
+        "{} of the {name} cases differ from {}:\n{}\nrerun with {UPDATE_VAR}=1 if the change \
+         is intended",
+        diff.len(),
+        path.display(),
+        diff.join("\n"),
+    );
+}
+
+#[test]
+fn pam_transitions_match_the_snapshot() {
+    check_snapshot("pam", &pam_matrix());
+}
+
+#[test]
+fn lam_transitions_match_the_snapshot() {
+    check_snapshot("lam", &lam_matrix());
+}
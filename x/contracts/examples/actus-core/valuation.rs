@@ -0,0 +1,321 @@
+//! Prices of a contract's remaining cashflows at a valuation date.
+//!
+//! Prices are per 100 of the notional outstanding at the valuation date, in
+//! [`BASIS_POINT_FACTOR`](math::BASIS_POINT_FACTOR) fixed point, so 1_012_500 is
+//! 101.25. The cashflows are those [`lifecycle::project`] schedules after the
+//! valuation date, discounted at an annually compounded rate over the year fractions
+//! of the terms' day-count convention:
+//!
+//! ```ignore
+//! let dirty = valuation::dirty_price(&terms, &state, now, 500)?;
+//! let clean = valuation::clean_price(&terms, &state, now, 500)?;
+//! let accrued = dirty - clean;
+//! ```
+//!
+//! Cash, stocks and commodities are valued at what they hold instead, by
+//! [`market_value`].
+
+use crate::{
+    core::{
+        ContractState, ContractTerms, ContractType, DayCountConvention, Error, EventType,
+        SignedUnits, Timestamp, TransitionEngine, Units,
+    },
+    lifecycle::{self, RunError},
+    math::{self, BASIS_POINT_FACTOR},
+    risk_factors::RiskFactors,
+};
+
+/// A price per 100 of notional, in basis points of a unit
+pub type Price = u64;
+
+/// The present value at `as_of` of every payoff scheduled after it, per 100 of the
+/// notional outstanding then. Events up to `as_of` are processed from `state` and
+/// their payoffs treated as settled; the initial exchange is not a cashflow of the
+/// holder and is never discounted. `discount_rate` is in basis points.
+pub fn dirty_price(
+    terms: &ContractTerms,
+    state: &ContractState,
+    as_of: Timestamp,
+    discount_rate: u64,
+) -> Result<Price, RunError> {
+    let (outstanding, notional) = outstanding(terms, state, as_of)?;
+    let dcc = terms
+        .day_count_convention
+        .unwrap_or(DayCountConvention::DCC_A_365) as u8;
+    let granularity = terms.granularity();
+
+    // payoffs flowing against their event, like interest at a negative rate, are
+    // discounted apart and netted off
+    let (received, paid): (Vec<_>, Vec<_>) =
+        lifecycle::project(terms, &outstanding, &(), Timestamp::MAX)?
+            .into_iter()
+            .filter(|event| event.event_type != EventType::IED)
+            .filter_map(|event| {
+                let time = granularity.year_fraction(dcc, as_of, event.time, terms.maturity_date);
+                event.payoff.map(|payoff| (payoff, time))
+            })
+            .partition(|(payoff, _)| *payoff >= 0);
+    let value = |cashflows: Vec<(SignedUnits, u64)>| {
+        let cashflows: Vec<(Units, u64)> = cashflows
+            .into_iter()
+            .map(|(payoff, time)| (payoff.unsigned_abs(), time))
+            .collect();
+        math::present_value(&cashflows, discount_rate)
+    };
+
+    per_hundred(value(received).saturating_sub(value(paid)), notional)
+}
+
+/// [`dirty_price`] less the interest accrued from the last payment to `as_of`, so it
+/// equals the dirty price on a payment date
+pub fn clean_price(
+    terms: &ContractTerms,
+    state: &ContractState,
+    as_of: Timestamp,
+    discount_rate: u64,
+) -> Result<Price, RunError> {
+    let dirty = dirty_price(terms, state, as_of, discount_rate)?;
+    let (mut outstanding, notional) = outstanding(terms, state, as_of)?;
+
+    if as_of > outstanding.status_date {
+        TransitionEngine::process_event(EventType::AD, as_of, &mut outstanding, terms).map_err(
+            |error| RunError {
+                event: Some((EventType::AD, as_of)),
+                error,
+            },
+        )?;
+    }
+
+    let accrued = per_hundred(outstanding.accrued_interest.unsigned_abs(), notional)?;
+    Ok(if outstanding.accrued_interest < 0 {
+        dirty.saturating_add(accrued)
+    } else {
+        dirty.saturating_sub(accrued)
+    })
+}
+
+/// What the position is worth at `as_of`: the balance of cash, or the quantity of a
+/// stock or commodity held times the price `risk_factors` observes for its
+/// [`market_object_code`](ContractTerms::market_object_code). Events up to `as_of` are
+/// processed from `state` first, so nothing is held before the PRD or after the TD.
+pub fn market_value<R>(
+    terms: &ContractTerms,
+    state: &ContractState,
+    as_of: Timestamp,
+    risk_factors: &R,
+) -> Result<Units, RunError>
+where
+    R: RiskFactors + ?Sized,
+{
+    if as_of < state.status_date {
+        return Err(Error::EventBeforeStatusDate {
+            event_time: as_of,
+            status_date: state.status_date,
+        }
+        .into());
+    }
+
+    let held = lifecycle::project(terms, state, risk_factors, as_of)?
+        .pop()
+        .map_or(state.notional_principal, |event| event.state.notional_principal);
+
+    match terms.contract_type {
+        ContractType::CSH => Ok(held),
+        contract_type if contract_type.is_traded() => {
+            let code = terms
+                .market_object_code
+                .as_deref()
+                .ok_or(Error::MissingTerm { field: "market_object_code" })?;
+            let price = risk_factors.observe(code, as_of).ok_or_else(|| {
+                Error::ValidationError(format!("no observation of risk factor `{code}`"))
+            })?;
+
+            let value = held.checked_mul(Units::from(price));
+            value.ok_or_else(|| Error::MathError("Market value overflow".into()).into())
+        }
+        _ => Err(Error::ValidationError(
+            "Only cash, stocks and commodities have a market value".into(),
+        )
+        .into()),
+    }
+}
+
+/// The state after the events up to `as_of`, with the notional it leaves outstanding
+fn outstanding(
+    terms: &ContractTerms,
+    state: &ContractState,
+    as_of: Timestamp,
+) -> Result<(ContractState, Units), RunError> {
+    if as_of < state.status_date {
+        return Err(Error::EventBeforeStatusDate {
+            event_time: as_of,
+            status_date: state.status_date,
+        }
+        .into());
+    }
+
+    let outstanding = lifecycle::project(terms, state, &(), as_of)?
+        .pop()
+        .map_or_else(|| state.clone(), |event| event.state);
+
+    match outstanding.notional_principal {
+        0 => Err(Error::AlreadyTerminated.into()),
+        notional => Ok((outstanding, notional)),
+    }
+}
+
+/// `amount` per 100 of `notional`, rounded to the nearest basis point
+fn per_hundred(amount: Units, notional: Units) -> Result<Price, RunError> {
+    let scale = 100 * BASIS_POINT_FACTOR as Units;
+
+    amount
+        .checked_mul(scale)
+        .map(|scaled| (scaled + notional / 2) / notional)
+        .and_then(|price| Price::try_from(price).ok())
+        .ok_or_else(|| Error::MathError("Price overflow".into()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::{Cycle, Period, Stub},
+        risk_factors::RiskFactorSeries,
+    };
+
+    const NOTIONAL: Units = 1_000 * Units::pow(10, math::AMOUNT_DECIMALS as u32);
+
+    /// 1000 at 5% with annual coupons on 2026-01-01 and 2027-01-01, the second paid
+    /// with the principal at maturity
+    fn two_coupon_bond() -> ContractTerms {
+        let ied = math::timestamp_from_civil(2025, 1, 1);
+
+        ContractTerms {
+            contract_type: ContractType::PAM,
+            status_date: ied,
+            initial_exchange_date: Some(ied),
+            maturity_date: Some(math::timestamp_from_civil(2027, 1, 1)),
+            notional_principal: Some(NOTIONAL),
+            nominal_interest_rate: Some(500),
+            day_count_convention: Some(DayCountConvention::DCC_A_365),
+            cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2026, 1, 1)),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_Y,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn prices(as_of: Timestamp) -> (Price, Price) {
+        let terms = two_coupon_bond();
+        let state = ContractState::new(&terms);
+
+        (
+            dirty_price(&terms, &state, as_of, 500).unwrap(),
+            clean_price(&terms, &state, as_of, 500).unwrap(),
+        )
+    }
+
+    #[test]
+    fn prices_at_par_when_the_discount_rate_is_the_coupon() {
+        // 50 / 1.05 + 1050 / 1.05^2 = 1000
+        let ied = math::timestamp_from_civil(2025, 1, 1);
+        assert_eq!(prices(ied), (1_000_000, 1_000_000));
+    }
+
+    #[test]
+    fn clean_price_excludes_the_accrued_coupon() {
+        // 182 days in: the coupon is 183 days away (0.5013 years) and maturity 548
+        // (1.5013), so dirty = 50 / 1.05^0.5013 + 1050 / 1.05^1.5013 = 1024.630 per
+        // 1000, and the accrued 1000 * 5% * 0.4986 = 24.93 leaves a clean 999.700
+        let (dirty, clean) = prices(math::timestamp_from_civil(2025, 7, 2));
+
+        assert_eq!(dirty, 1_024_630);
+        assert_eq!(clean, 999_700);
+    }
+
+    #[test]
+    fn clean_equals_dirty_on_a_coupon_date() {
+        // the first coupon is settled, leaving 1050 a year away
+        let (dirty, clean) = prices(math::timestamp_from_civil(2026, 1, 1));
+
+        assert_eq!(dirty, 1_000_000);
+        assert_eq!(clean, dirty);
+    }
+
+    #[test]
+    fn continues_from_a_recorded_state() {
+        let terms = two_coupon_bond();
+        let as_of = math::timestamp_from_civil(2025, 7, 2);
+        let recorded = lifecycle::run_until(&terms, &(), as_of)
+            .unwrap()
+            .pop()
+            .unwrap()
+            .state;
+
+        assert_eq!(
+            dirty_price(&terms, &recorded, as_of, 500),
+            Ok(prices(as_of).0)
+        );
+    }
+
+    #[test]
+    fn rejects_dates_it_cannot_price() {
+        let terms = two_coupon_bond();
+        let state = ContractState::new(&terms);
+        let maturity = terms.maturity_date.unwrap();
+
+        assert_eq!(
+            dirty_price(&terms, &state, maturity, 500)
+                .unwrap_err()
+                .error,
+            Error::AlreadyTerminated
+        );
+
+        let matured = lifecycle::run(&terms, &()).unwrap().pop().unwrap().state;
+        assert!(matches!(
+            clean_price(&terms, &matured, state.status_date, 500)
+                .unwrap_err()
+                .error,
+            Error::EventBeforeStatusDate { .. }
+        ));
+    }
+
+    #[test]
+    fn commodities_are_worth_the_observed_price_while_held() {
+        let purchase = math::timestamp_from_civil(2025, 1, 1);
+        let sale = math::timestamp_from_civil(2025, 7, 1);
+        let terms = ContractTerms {
+            contract_type: ContractType::COM,
+            status_date: purchase,
+            purchase_date: Some(purchase),
+            price_at_purchase_date: Some(70),
+            termination_date: Some(sale),
+            price_at_termination_date: Some(80),
+            quantity: Some(500),
+            market_object_code: Some("BRENT".into()),
+            ..Default::default()
+        };
+        let state = ContractState::new(&terms);
+
+        let mut risk_factors = RiskFactorSeries::new();
+        risk_factors.insert("BRENT", vec![(purchase, 70), (purchase + 1, 75)]);
+        assert_eq!(market_value(&terms, &state, purchase + 1, &risk_factors), Ok(37_500));
+        assert_eq!(market_value(&terms, &state, sale, &risk_factors), Ok(0));
+
+        // cash is worth its balance, whatever the market
+        let cash = ContractTerms {
+            contract_type: ContractType::CSH,
+            notional_principal: Some(1_000),
+            ..terms
+        };
+        let state = ContractState::new(&cash);
+        assert_eq!(market_value(&cash, &state, sale, &RiskFactorSeries::new()), Ok(1_000));
+
+        let bond = two_coupon_bond();
+        assert!(market_value(&bond, &ContractState::new(&bond), sale, &risk_factors).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! A hook for ACTUS contracts to notify of their events, keeping every call it gets so
+//! tests can read them back. It can be set to fail, to exercise the failure policy the
+//! hook was registered with.
+
+use actus_core::core::{ContractId, EventType, SignedUnits, Timestamp};
+use wasmlanche::{public, state_schema, Address, Context};
+
+/// A call of [`on_actus_event`]: the contract calling it and what it was notified of
+pub type Call = (
+    Address,
+    ContractId,
+    EventType,
+    Timestamp,
+    Option<SignedUnits>,
+);
+
+/// Path of the wasm the build script compiled the hook to, for tests of host builds to
+/// deploy it
+///
+/// # Panics
+/// In builds the script does not compile the wasm for, such as fuzzing ones
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub const fn contract_path() -> &'static str {
+    match option_env!("CONTRACT_PATH") {
+        Some(path) => path,
+        None => panic!("the build script compiled no wasm for this build of the hook"),
+    }
+}
+
+state_schema! {
+    /// The calls received, in order
+    Calls => Vec<Call>,
+    /// Whether calls panic rather than being kept
+    Failing => bool,
+}
+
+/// Called by an ACTUS contract once it recorded an event
+#[public]
+pub fn on_actus_event(
+    context: &mut Context,
+    contract_id: ContractId,
+    event_type: EventType,
+    timestamp: Timestamp,
+    payoff: Option<SignedUnits>,
+) {
+    assert!(!failing(context), "the hook is failing");
+
+    let mut calls = calls(context);
+    calls.push((context.actor(), contract_id, event_type, timestamp, payoff));
+    context
+        .store_by_key(Calls, calls)
+        .expect("failed to store the call");
+}
+
+/// Makes the calls that follow panic, or be kept again
+#[public]
+pub fn set_failing(context: &mut Context, failing: bool) {
+    context
+        .store_by_key(Failing, failing)
+        .expect("failed to store the failure mode");
+}
+
+#[public]
+pub fn calls(context: &mut Context) -> Vec<Call> {
+    context
+        .get(Calls)
+        .expect("failed to load the calls")
+        .unwrap_or_default()
+}
+
+#[public]
+pub fn failing(context: &mut Context) -> bool {
+    context
+        .get(Failing)
+        .expect("failed to load the failure mode")
+        .unwrap_or_default()
+}
@@ -0,0 +1,6 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+fn main() {
+    wasmlanche::build::build_wasm();
+}
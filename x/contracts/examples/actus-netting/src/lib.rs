@@ -0,0 +1,232 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use actus::core::Timestamp;
+use wasmlanche::{public, state_schema, Address, Context, Gas};
+
+/// Upper bound on the events processed for a single position at one date
+pub const MAX_EVENTS_PER_DATE: usize = 8;
+
+/// Leaves room for the token calls each position makes while processing an event
+const MAX_GAS: Gas = 100_000_000;
+const ZERO: u64 = 0;
+
+state_schema! {
+    /// The first counterparty; net amounts are signed from its perspective
+    PartyA => Address,
+    /// The second counterparty
+    PartyB => Address,
+    /// Settlement currency shared by every registered position
+    Currency => Address,
+    /// ACTUS contracts between the two parties settled by this contract
+    Positions => Vec<Address>,
+}
+
+/// Initializes the netting agreement between two parties settling in `currency`
+#[public]
+pub fn init(context: &mut Context, party_a: Address, party_b: Address, currency: Address) {
+    assert!(
+        context
+            .get(Currency)
+            .expect("failed to get currency")
+            .is_none(),
+        "netting already initialized"
+    );
+    assert_ne!(party_a, party_b, "parties must differ");
+
+    context
+        .store(((PartyA, party_a), (PartyB, party_b), (Currency, currency)))
+        .expect("failed to store parties");
+}
+
+/// Registers an ACTUS contract between the two parties.
+/// The contract must settle in the agreed currency and have delegated settlement to this contract.
+#[public]
+pub fn register_position(context: &mut Context, position: Address) {
+    let (party_a, party_b) = parties(context);
+    let actor = context.actor();
+    assert!(
+        actor == party_a || actor == party_b,
+        "caller is required to be a party"
+    );
+
+    let mut positions = positions(context);
+    assert!(
+        !positions.contains(&position),
+        "position already registered"
+    );
+
+    let (creditor, debtor) = internal::position_parties(context, position);
+    assert!(
+        (creditor, debtor) == (party_a, party_b) || (creditor, debtor) == (party_b, party_a),
+        "position is not between the netting parties"
+    );
+
+    let currency = internal::currency(context);
+    let position_currency: Address = internal::query(context, position, "currency");
+    assert_eq!(
+        position_currency, currency,
+        "position settles in another currency"
+    );
+
+    let agent: Option<Address> = internal::query(context, position, "settlement_agent");
+    assert_eq!(
+        agent,
+        Some(context.contract_address()),
+        "position has not delegated settlement to this contract"
+    );
+
+    positions.push(position);
+
+    context
+        .store_by_key(Positions, positions)
+        .expect("failed to store positions");
+}
+
+/// Returns the registered positions
+#[public]
+pub fn positions(context: &mut Context) -> Vec<Address> {
+    context
+        .get(Positions)
+        .expect("failed to get positions")
+        .unwrap_or_default()
+}
+
+/// Returns the `(party_a, party_b)` counterparties
+#[public]
+pub fn parties(context: &mut Context) -> (Address, Address) {
+    let party_a = context
+        .get(PartyA)
+        .expect("failed to get party")
+        .expect("netting not initialized");
+    let party_b = context
+        .get(PartyB)
+        .expect("failed to get party")
+        .expect("netting not initialized");
+
+    (party_a, party_b)
+}
+
+/// Processes every event due at `date` on the registered positions and settles
+/// the net payoff with a single transfer. Returns the net amount received by party A
+/// (negative when party A pays). Any failing event reverts the whole settlement.
+#[public]
+pub fn settle(context: &mut Context, date: Timestamp) -> i128 {
+    let (party_a, party_b) = parties(context);
+    let mut net: i128 = 0;
+
+    for position in positions(context) {
+        let (creditor, _) = internal::position_parties(context, position);
+
+        for (event, payoff) in internal::process_due_events(context, position, date) {
+            // signed from the creditor's perspective, then from party A's
+            let to_creditor = if event.paid_by_creditor() {
+                -payoff
+            } else {
+                payoff
+            };
+
+            net += if creditor == party_a {
+                to_creditor
+            } else {
+                -to_creditor
+            };
+        }
+    }
+
+    let (payer, payee) = if net >= 0 {
+        (party_b, party_a)
+    } else {
+        (party_a, party_b)
+    };
+
+    let amount = token::Units::try_from(net.unsigned_abs()).expect("net amount overflow");
+
+    if amount > 0 {
+        internal::transfer(context, payer, payee, amount);
+    }
+
+    net
+}
+
+#[cfg(not(feature = "bindings"))]
+mod internal {
+    use super::*;
+    use actus::core::{Error as ActusError, EventType, SignedUnits};
+    use wasmlanche::{borsh::BorshDeserialize, ExternalCallArgs};
+
+    pub fn currency(context: &mut Context) -> Address {
+        context
+            .get(Currency)
+            .expect("failed to get currency")
+            .expect("netting not initialized")
+    }
+
+    /// Calls a read-only entrypoint without arguments, panicking on any failure
+    pub fn query<T: BorshDeserialize>(
+        context: &mut Context,
+        position: Address,
+        function: &str,
+    ) -> T {
+        context
+            .call_contract::<Result<T, ActusError>>(position, function, &[], MAX_GAS, ZERO)
+            .expect("failed to query position")
+            .expect("position query failed")
+    }
+
+    pub fn position_parties(context: &mut Context, position: Address) -> (Address, Address) {
+        query(context, position, "parties")
+    }
+
+    /// Processes the position's events scheduled at `date`, returning their payoffs
+    pub fn process_due_events(
+        context: &mut Context,
+        position: Address,
+        date: Timestamp,
+    ) -> Vec<(EventType, SignedUnits)> {
+        let mut payoffs = Vec::new();
+
+        for _ in 0..MAX_EVENTS_PER_DATE {
+            let next: Option<(EventType, Timestamp)> = query(context, position, "get_next_event");
+
+            let event = match next {
+                Some((event, time)) if time == date => event,
+                Some((_, time)) => {
+                    assert!(time > date, "position has unsettled events before date");
+                    break;
+                }
+                None => break,
+            };
+
+            let args =
+                wasmlanche::borsh::to_vec(&(event as u8, date)).expect("failed to serialize event");
+
+            let payoff = context
+                .call_contract::<Result<Option<SignedUnits>, ActusError>>(
+                    position,
+                    "process_event",
+                    &args,
+                    MAX_GAS,
+                    ZERO,
+                )
+                .expect("failed to process event")
+                .expect("event processing failed");
+
+            if let Some(payoff) = payoff {
+                payoffs.push((event, payoff));
+            }
+        }
+
+        payoffs
+    }
+
+    pub fn transfer(context: &mut Context, payer: Address, payee: Address, amount: token::Units) {
+        let args = ExternalCallArgs {
+            contract_address: currency(context),
+            max_units: MAX_GAS,
+            value: ZERO,
+        };
+
+        token::transfer_from(context.to_extern(args), payer, payee, amount);
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use actus::{
+    core::{
+        ContractId, ContractRole, ContractTerms, ContractType, DayCountConvention,
+        Error as ActusError, Units,
+    },
+    math::{AMOUNT_DECIMALS, SECONDS_PER_DAY},
+};
+use wasmlanche::{
+    simulator::{CallTrace, CreateContractResult, Error as SimError, SimpleState, Simulator},
+    Address,
+};
+
+const CONTRACT_PATH: &str = env!("CONTRACT_PATH");
+const MAX_GAS: u64 = 1_000_000_000;
+const YEAR: u64 = 365 * SECONDS_PER_DAY;
+
+/// Deploys a PAM where the actor lends `notional` to `debtor`.
+/// Both parties hand settlement over to `netting`.
+fn deploy_position(
+    simulator: &mut Simulator,
+    creditor: Address,
+    debtor: Address,
+    currency: &CreateContractResult,
+    notional: Units,
+    netting: Address,
+) -> Result<Address, SimError> {
+    simulator.set_actor(creditor);
+    let position = simulator.create_contract(actus::contract_path())?.address;
+
+    let terms = ContractTerms {
+        contract_id: ContractId::from(format!("pam-{notional}").as_str()),
+        contract_type: ContractType::PAM,
+        contract_role: ContractRole::CR_RPA,
+        status_date: 0,
+        initial_exchange_date: Some(0),
+        maturity_date: Some(2 * YEAR),
+        notional_principal: Some(notional),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(YEAR),
+        ..Default::default()
+    };
+
+    simulator
+        .call_contract::<Result<(), ActusError>, _>(
+            position,
+            "init",
+            (
+                terms.contract_type as u8,
+                terms.contract_role as u8,
+                currency.address,
+                debtor,
+                currency.id.clone(),
+                terms.to_versioned_bytes(),
+            ),
+            MAX_GAS,
+        )?
+        .expect("init failed");
+
+    for party in [creditor, debtor] {
+        simulator.set_actor(party);
+        simulator
+            .call_contract::<Result<(), ActusError>, _>(
+                position,
+                "delegate_settlement",
+                netting,
+                MAX_GAS,
+            )?
+            .expect("delegate_settlement failed");
+    }
+
+    Ok(position)
+}
+
+/// Settles the events due at `date`, printing the call tree when the settlement fails
+fn settle(simulator: &Simulator, netting: Address, date: u64) -> Result<i128, SimError> {
+    let result = simulator.call_contract(netting, "settle", date, MAX_GAS);
+
+    if result.is_err() {
+        if let Some(trace) = simulator.take_trace() {
+            eprintln!("settle({date}) failed:\n{trace}");
+        }
+    }
+
+    result
+}
+
+fn balance_of(sim: &Simulator, token: Address, account: Address) -> Result<token::Units, SimError> {
+    sim.call_contract(token, "balance_of", account, MAX_GAS)
+}
+
+#[test]
+fn offsetting_positions_settle_the_residual() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+    simulator.set_tracing(true);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+
+    simulator.set_actor(alice);
+    let token = simulator.create_contract(token::contract_path())?;
+    simulator.call_contract::<(), _>(
+        token.address,
+        "init",
+        ("TestToken".to_string(), "TT".to_string(), AMOUNT_DECIMALS),
+        MAX_GAS,
+    )?;
+
+    let netting = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(netting, "init", (alice, bob, token.address), MAX_GAS)?;
+
+    for party in [alice, bob] {
+        simulator.set_actor(alice);
+        simulator.call_contract::<(), _>(token.address, "mint", (party, 10_000_000u64), MAX_GAS)?;
+        simulator.set_actor(party);
+        simulator.call_contract::<(), _>(
+            token.address,
+            "approve",
+            (netting, 10_000_000u64),
+            MAX_GAS,
+        )?;
+    }
+
+    let lent = deploy_position(&mut simulator, alice, bob, &token, 1_000_000, netting)?;
+    let borrowed = deploy_position(&mut simulator, bob, alice, &token, 990_000, netting)?;
+
+    simulator.set_actor(alice);
+    for position in [lent, borrowed] {
+        simulator.call_contract::<(), _>(netting, "register_position", position, MAX_GAS)?;
+    }
+
+    let alice_before = balance_of(&simulator, token.address, alice)?;
+    let bob_before = balance_of(&simulator, token.address, bob)?;
+
+    // alice disburses 1_000_000 and receives 990_000
+    let net: i128 = settle(&simulator, netting, 0)?;
+    assert_eq!(net, -10_000);
+    assert_eq!(
+        balance_of(&simulator, token.address, alice)?,
+        alice_before - 10_000
+    );
+    assert_eq!(
+        balance_of(&simulator, token.address, bob)?,
+        bob_before + 10_000
+    );
+
+    // alice receives 50_000 of interest and pays 49_500
+    let net: i128 = settle(&simulator, netting, YEAR)?;
+    assert_eq!(net, 500);
+    assert_eq!(
+        balance_of(&simulator, token.address, alice)?,
+        alice_before - 9_500
+    );
+    assert_eq!(
+        balance_of(&simulator, token.address, bob)?,
+        bob_before + 9_500
+    );
+
+    Ok(())
+}
+
+#[test]
+fn positions_must_delegate_settlement() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+    simulator.set_tracing(true);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+
+    simulator.set_actor(alice);
+    let token = simulator.create_contract(token::contract_path())?;
+    simulator.call_contract::<(), _>(
+        token.address,
+        "init",
+        ("TestToken".to_string(), "TT".to_string(), AMOUNT_DECIMALS),
+        MAX_GAS,
+    )?;
+
+    let netting = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(netting, "init", (alice, bob, token.address), MAX_GAS)?;
+
+    let other_netting = simulator.create_contract(CONTRACT_PATH)?.address;
+    let position = deploy_position(&mut simulator, alice, bob, &token, 1_000_000, other_netting)?;
+
+    simulator.set_actor(alice);
+    let result = simulator.call_contract::<(), _>(netting, "register_position", position, MAX_GAS);
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+/// Each position's IED mints its receipt, so one settlement covering both positions
+/// traces a subtree per position under the netting contract, then the single transfer
+#[test]
+fn settlement_traces_a_subtree_per_position() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+    simulator.set_tracing(true);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+
+    simulator.set_actor(alice);
+    let token = simulator.create_contract(token::contract_path())?;
+    simulator.call_contract::<(), _>(
+        token.address,
+        "init",
+        ("TestToken".to_string(), "TT".to_string(), AMOUNT_DECIMALS),
+        MAX_GAS,
+    )?;
+
+    let netting = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(netting, "init", (alice, bob, token.address), MAX_GAS)?;
+
+    for party in [alice, bob] {
+        simulator.set_actor(alice);
+        simulator.call_contract::<(), _>(token.address, "mint", (party, 10_000_000u64), MAX_GAS)?;
+        simulator.set_actor(party);
+        simulator.call_contract::<(), _>(
+            token.address,
+            "approve",
+            (netting, 10_000_000u64),
+            MAX_GAS,
+        )?;
+    }
+
+    let lent = deploy_position(&mut simulator, alice, bob, &token, 1_000_000, netting)?;
+    let borrowed = deploy_position(&mut simulator, bob, alice, &token, 990_000, netting)?;
+
+    simulator.set_actor(alice);
+    for position in [lent, borrowed] {
+        simulator.call_contract::<(), _>(netting, "register_position", position, MAX_GAS)?;
+    }
+
+    // without tracing nothing is recorded
+    simulator.set_tracing(false);
+    simulator.call_contract::<Vec<Address>, _>(netting, "positions", (), MAX_GAS)?;
+    assert!(simulator.take_trace().is_none());
+
+    simulator.set_tracing(true);
+    settle(&simulator, netting, 0)?;
+    let trace: CallTrace = simulator.take_trace().expect("tracing is enabled");
+
+    assert!(trace.caller == alice && trace.callee == netting);
+    assert_eq!(trace.function, "settle");
+    assert_eq!(trace.params_len, 8);
+    assert!(trace.error.is_none());
+    assert_eq!(trace.fuel_used, simulator.last_call_units());
+
+    // every call the netting contract makes is to a position, then to the currency
+    assert!(trace.calls.iter().all(|call| call.caller == netting));
+    let callees: Vec<_> = trace.calls.iter().map(|call| call.callee).collect();
+    let split = callees.iter().position(|callee| *callee != lent).unwrap();
+    assert!(split > 0);
+    assert!(callees[split..callees.len() - 1]
+        .iter()
+        .all(|callee| *callee == borrowed));
+
+    let transfer = trace.calls.last().unwrap();
+    assert!(transfer.callee == token.address);
+    assert_eq!(transfer.function, "transfer_from");
+    assert!(transfer.calls.is_empty());
+
+    // the only nested calls are the receipts minted by each position's IED
+    for position in [lent, borrowed] {
+        let nested: Vec<&CallTrace> = trace
+            .calls
+            .iter()
+            .filter(|call| call.callee == position)
+            .flat_map(|call| &call.calls)
+            .collect();
+
+        assert_eq!(nested.len(), 1);
+        assert!(nested[0].caller == position);
+        assert_eq!(nested[0].function, "mint");
+        assert!(nested[0].error.is_none() && nested[0].calls.is_empty());
+    }
+
+    let children: u64 = trace.calls.iter().map(|call| call.fuel_used).sum();
+    assert!(children < trace.fuel_used);
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use actus::core::{ContractState, Error as ActusError, EventType, SignedUnits, Timestamp, Units};
+use wasmlanche::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    public, state_schema, Address, Context, Gas,
+};
+
+/// Maximum number of positions aggregated by a single call
+pub const MAX_PAGE_SIZE: u32 = 16;
+
+const MAX_GAS: Gas = 10_000_000;
+const ZERO: u64 = 0;
+
+state_schema! {
+    /// The account allowed to add and remove positions
+    Owner => Address,
+    /// Addresses of the ACTUS contracts held in the portfolio
+    Positions => Vec<Address>,
+}
+
+/// Exposure summed over one page of positions, the totals saturating at the bounds of
+/// their types
+#[derive(Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "wasmlanche::borsh")]
+pub struct Aggregate {
+    pub total_notional: Units,
+    pub total_accrued_interest: SignedUnits,
+    /// Earliest upcoming event time across the page
+    pub next_event_time: Option<Timestamp>,
+    /// Positions whose queries failed and were skipped
+    pub failed: Vec<Address>,
+    /// Offset of the next page, if any positions remain
+    pub next_offset: Option<u32>,
+}
+
+/// Initializes the portfolio with the actor as its owner
+#[public]
+pub fn init(context: &mut Context) {
+    let actor = context.actor();
+
+    context
+        .store_by_key(Owner, actor)
+        .expect("failed to store owner");
+}
+
+/// Adds an ACTUS contract to the portfolio
+#[public]
+pub fn add_position(context: &mut Context, position: Address) {
+    internal::check_owner(context);
+
+    let mut positions = positions(context);
+    assert!(!positions.contains(&position), "position already held");
+    positions.push(position);
+
+    context
+        .store_by_key(Positions, positions)
+        .expect("failed to store positions");
+}
+
+/// Removes an ACTUS contract from the portfolio
+#[public]
+pub fn remove_position(context: &mut Context, position: Address) {
+    internal::check_owner(context);
+
+    let mut positions = positions(context);
+    positions.retain(|held| *held != position);
+
+    context
+        .store_by_key(Positions, positions)
+        .expect("failed to store positions");
+}
+
+/// Returns the positions held in the portfolio
+#[public]
+pub fn positions(context: &mut Context) -> Vec<Address> {
+    context
+        .get(Positions)
+        .expect("failed to get positions")
+        .unwrap_or_default()
+}
+
+/// Sums notional and accrued interest over at most `limit` positions starting at `offset`,
+/// capped at [`MAX_PAGE_SIZE`]. Positions that cannot be queried are reported in `failed`.
+#[public]
+pub fn aggregate(context: &mut Context, offset: u32, limit: u32) -> Aggregate {
+    let positions = positions(context);
+
+    let start = (offset as usize).min(positions.len());
+    let end = start
+        .saturating_add(limit.min(MAX_PAGE_SIZE) as usize)
+        .min(positions.len());
+
+    let mut aggregate = Aggregate {
+        next_offset: (end < positions.len()).then_some(end as u32),
+        ..Default::default()
+    };
+
+    for &position in &positions[start..end] {
+        let Some((state, next_event)) = internal::summarize(context, position) else {
+            aggregate.failed.push(position);
+            continue;
+        };
+
+        aggregate.total_notional = aggregate
+            .total_notional
+            .saturating_add(state.notional_principal);
+        aggregate.total_accrued_interest = aggregate
+            .total_accrued_interest
+            .saturating_add(state.accrued_interest);
+
+        if let Some((_, time)) = next_event {
+            aggregate.next_event_time = Some(
+                aggregate
+                    .next_event_time
+                    .map_or(time, |earliest| earliest.min(time)),
+            );
+        }
+    }
+
+    aggregate
+}
+
+#[cfg(not(feature = "bindings"))]
+mod internal {
+    use super::*;
+
+    pub fn check_owner(context: &mut Context) {
+        let owner = context
+            .get(Owner)
+            .expect("failed to get owner")
+            .expect("owner not initialized");
+
+        assert_eq!(owner, context.actor(), "caller is required to be owner");
+    }
+
+    /// Queries a position's state and next event, returning `None` if either call fails
+    pub fn summarize(
+        context: &mut Context,
+        position: Address,
+    ) -> Option<(ContractState, Option<(EventType, Timestamp)>)> {
+        let state = context
+            .call_contract::<Result<ContractState, ActusError>>(
+                position,
+                "get_state",
+                &[],
+                MAX_GAS,
+                ZERO,
+            )
+            .ok()?
+            .ok()?;
+
+        let next_event = context
+            .call_contract::<Result<Option<(EventType, Timestamp)>, ActusError>>(
+                position,
+                "get_next_event",
+                &[],
+                MAX_GAS,
+                ZERO,
+            )
+            .ok()?
+            .ok()?;
+
+        Some((state, next_event))
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use actus::{
+    core::{
+        ContractId, ContractRole, ContractState, ContractTerms, ContractType, Cycle,
+        DayCountConvention, Error as ActusError, EventType, Period, Stub, Timestamp, Units,
+    },
+    math::{AMOUNT_DECIMALS, SECONDS_PER_DAY},
+};
+use actus_portfolio::Aggregate;
+use wasmlanche::{
+    simulator::{Error as SimError, SimpleState, Simulator},
+    Address,
+};
+
+const CONTRACT_PATH: &str = env!("CONTRACT_PATH");
+const MAX_GAS: u64 = 1_000_000_000;
+const YEAR: u64 = 365 * SECONDS_PER_DAY;
+
+/// Deploys a funded ACTUS position between `creditor` and `debtor` and runs its initial exchange
+fn deploy_position(
+    simulator: &mut Simulator,
+    creditor: Address,
+    debtor: Address,
+    terms: &ContractTerms,
+) -> Result<Address, SimError> {
+    simulator.set_actor(creditor);
+
+    let position = simulator.create_contract(actus::contract_path())?.address;
+    let token = simulator.create_contract(token::contract_path())?;
+
+    simulator.call_contract::<(), _>(
+        token.address,
+        "init",
+        ("TestToken".to_string(), "TT".to_string(), AMOUNT_DECIMALS),
+        MAX_GAS,
+    )?;
+
+    for party in [creditor, debtor] {
+        simulator.call_contract::<(), _>(token.address, "mint", (party, 10_000_000u64), MAX_GAS)?;
+        simulator.set_actor(party);
+        simulator.call_contract::<(), _>(
+            token.address,
+            "approve",
+            (position, 10_000_000u64),
+            MAX_GAS,
+        )?;
+        simulator.set_actor(creditor);
+    }
+
+    simulator
+        .call_contract::<Result<(), ActusError>, _>(
+            position,
+            "init",
+            (
+                terms.contract_type as u8,
+                terms.contract_role as u8,
+                token.address,
+                debtor,
+                token.id,
+                terms.to_versioned_bytes(),
+            ),
+            MAX_GAS,
+        )?
+        .expect("init failed");
+
+    let ied = terms.initial_exchange_date.unwrap();
+    simulator
+        .call_contract::<Result<Option<Units>, ActusError>, _>(
+            position,
+            "process_event",
+            (EventType::IED as u8, ied),
+            MAX_GAS,
+        )?
+        .expect("initial exchange failed");
+
+    Ok(position)
+}
+
+fn terms(contract_type: ContractType, notional: Units) -> ContractTerms {
+    ContractTerms {
+        contract_id: ContractId::from(format!("{contract_type:?}").as_str()),
+        contract_type,
+        contract_role: ContractRole::CR_RPA,
+        status_date: 0,
+        initial_exchange_date: Some(0),
+        maturity_date: Some(2 * YEAR),
+        notional_principal: Some(notional),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(YEAR),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn aggregates_exposure_across_positions() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+    let missing = Address::new([9; 33]);
+
+    let pam = deploy_position(
+        &mut simulator,
+        alice,
+        bob,
+        &terms(ContractType::PAM, 1_000_000),
+    )?;
+
+    let lam_terms = ContractTerms {
+        cycle_anchor_date_of_principal_redemption: Some(30 * SECONDS_PER_DAY),
+        cycle_of_principal_redemption: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        ..terms(ContractType::LAM, 400_000)
+    };
+    let lam = deploy_position(&mut simulator, alice, bob, &lam_terms)?;
+
+    simulator
+        .call_contract::<Result<Option<Units>, ActusError>, _>(
+            pam,
+            "process_event",
+            (EventType::AD as u8, 73 * SECONDS_PER_DAY),
+            MAX_GAS,
+        )?
+        .expect("accrual failed");
+
+    simulator.set_actor(alice);
+    let portfolio = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(portfolio, "init", (), MAX_GAS)?;
+
+    for position in [pam, missing, lam] {
+        simulator.call_contract::<(), _>(portfolio, "add_position", position, MAX_GAS)?;
+    }
+
+    let mut expected = Aggregate {
+        failed: vec![missing],
+        ..Default::default()
+    };
+
+    for position in [pam, lam] {
+        let state = simulator
+            .call_contract::<Result<ContractState, ActusError>, _>(
+                position,
+                "get_state",
+                (),
+                MAX_GAS,
+            )?
+            .expect("get_state failed");
+        expected.total_notional += state.notional_principal;
+        expected.total_accrued_interest += state.accrued_interest;
+
+        let next = simulator
+            .call_contract::<Result<Option<(EventType, Timestamp)>, ActusError>, _>(
+                position,
+                "get_next_event",
+                (),
+                MAX_GAS,
+            )?
+            .expect("get_next_event failed")
+            .map(|(_, time)| time);
+        expected.next_event_time = match (expected.next_event_time, next) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    assert_eq!(expected.total_notional, 1_400_000);
+    assert!(expected.total_accrued_interest > 0);
+
+    let aggregate: Aggregate =
+        simulator.call_contract(portfolio, "aggregate", (0u32, 10u32), MAX_GAS)?;
+    assert_eq!(aggregate, expected);
+
+    Ok(())
+}
+
+#[test]
+fn aggregate_is_paginated() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+
+    let pam = deploy_position(
+        &mut simulator,
+        alice,
+        bob,
+        &terms(ContractType::PAM, 1_000_000),
+    )?;
+    let other = deploy_position(
+        &mut simulator,
+        alice,
+        bob,
+        &terms(ContractType::PAM, 250_000),
+    )?;
+
+    simulator.set_actor(alice);
+    let portfolio = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(portfolio, "init", (), MAX_GAS)?;
+
+    for position in [pam, other] {
+        simulator.call_contract::<(), _>(portfolio, "add_position", position, MAX_GAS)?;
+    }
+
+    let first: Aggregate =
+        simulator.call_contract(portfolio, "aggregate", (0u32, 1u32), MAX_GAS)?;
+    assert_eq!(first.total_notional, 1_000_000);
+    assert_eq!(first.next_offset, Some(1));
+
+    let second: Aggregate =
+        simulator.call_contract(portfolio, "aggregate", (1u32, 1u32), MAX_GAS)?;
+    assert_eq!(second.total_notional, 250_000);
+    assert_eq!(second.next_offset, None);
+
+    Ok(())
+}
+
+#[test]
+fn only_owner_can_add_positions() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let alice = Address::new([1; 33]);
+    let bob = Address::new([2; 33]);
+
+    simulator.set_actor(alice);
+    let portfolio = simulator.create_contract(CONTRACT_PATH)?.address;
+    simulator.call_contract::<(), _>(portfolio, "init", (), MAX_GAS)?;
+
+    simulator.set_actor(bob);
+    let result = simulator.call_contract::<(), _>(portfolio, "add_position", bob, MAX_GAS);
+    assert!(result.is_err());
+
+    Ok(())
+}
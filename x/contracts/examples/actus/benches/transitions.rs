@@ -0,0 +1,168 @@
+// x/contracts/examples/actus/benches/transitions.rs
+
+//! Native benchmarks of the transition engine. `core` does not touch the host, so
+//! these run against the plain library build rather than the wasm contract.
+
+use actus::{
+    core::{
+        ContractState, ContractTerms, ContractType, Cycle, DayCountConvention, EventType, Period,
+        Stub, Timestamp, TransitionEngine, Units,
+    },
+    math,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::hint::black_box;
+
+criterion_group!(
+    benches,
+    pam_interest_payment,
+    lam_lifecycle,
+    schedule_generation,
+    borsh_round_trip
+);
+criterion_main!(benches);
+
+fn monthly() -> Cycle {
+    Cycle {
+        n: 1,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    }
+}
+
+/// A contract with monthly interest payments, and monthly redemptions for a LAM
+fn terms(contract_type: ContractType, years: u64) -> ContractTerms {
+    let ied = math::timestamp_from_civil(2024, 1, 15);
+    let first_payment = math::timestamp_from_civil(2024, 2, 15);
+    let notional = 360_000_000;
+
+    let mut terms = ContractTerms {
+        contract_type,
+        status_date: ied,
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, years * 12),
+        notional_principal: Some(notional),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(first_payment),
+        cycle_of_interest_payment: Some(monthly()),
+        ..Default::default()
+    };
+
+    if contract_type == ContractType::LAM {
+        // `next_event` returns one event per date, so interest is paid at month end
+        terms.cycle_anchor_date_of_interest_payment = Some(math::timestamp_from_civil(2024, 1, 31));
+        terms.cycle_anchor_date_of_principal_redemption = Some(first_payment);
+        terms.cycle_of_principal_redemption = Some(monthly());
+        terms.next_principal_redemption_payment = Some(notional / Units::from(years * 12));
+    }
+
+    terms
+}
+
+/// Every event of the contract in order, as returned by [`TransitionEngine::next_event`]
+fn schedule(terms: &ContractTerms) -> Vec<(EventType, Timestamp)> {
+    let mut state = ContractState::new(terms);
+    let mut events = Vec::new();
+
+    while let Some((event, time)) = TransitionEngine::next_event(&state, terms) {
+        events.push((event, time));
+        TransitionEngine::process_event(event, time, &mut state, terms)
+            .expect("failed to process scheduled event");
+    }
+
+    events
+}
+
+fn pam_interest_payment(c: &mut Criterion) {
+    let terms = terms(ContractType::PAM, 30);
+    let ied = terms.initial_exchange_date.unwrap();
+    let payment = terms.cycle_anchor_date_of_interest_payment.unwrap();
+
+    let mut funded = ContractState::new(&terms);
+    TransitionEngine::process_event(EventType::IED, ied, &mut funded, &terms)
+        .expect("failed to process IED");
+
+    c.bench_function("pam_interest_payment", |b| {
+        b.iter_batched(
+            || funded.clone(),
+            |mut state| {
+                TransitionEngine::process_event(
+                    black_box(EventType::IP),
+                    black_box(payment),
+                    &mut state,
+                    &terms,
+                )
+                .expect("failed to process IP");
+                state
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// 15 years of monthly IP and PR events, 361 with the IED and MD
+fn lam_lifecycle(c: &mut Criterion) {
+    let terms = terms(ContractType::LAM, 15);
+    let events = schedule(&terms);
+    assert_eq!(events.len(), 361);
+
+    c.bench_function("lam_lifecycle", |b| {
+        b.iter(|| {
+            let mut state = ContractState::new(&terms);
+
+            for &(event, time) in black_box(&events) {
+                TransitionEngine::process_event(event, time, &mut state, &terms)
+                    .expect("failed to process event");
+            }
+
+            state
+        })
+    });
+}
+
+fn schedule_generation(c: &mut Criterion) {
+    let terms = terms(ContractType::PAM, 30);
+    let ied = terms.initial_exchange_date.unwrap();
+    let maturity = terms.maturity_date.unwrap();
+    let anchor = terms.cycle_anchor_date_of_interest_payment.unwrap();
+    let (cycle, granularity) = (monthly(), terms.granularity());
+
+    c.bench_function("schedule_generation", |b| {
+        b.iter(|| {
+            std::iter::successors(
+                cycle.next_after(anchor, black_box(ied), granularity),
+                |date| cycle.next_after(anchor, *date, granularity),
+            )
+            .take_while(|date| *date < maturity)
+            .collect::<Vec<_>>()
+        })
+    });
+}
+
+fn borsh_round_trip(c: &mut Criterion) {
+    let terms = terms(ContractType::LAM, 30);
+    let mut state = ContractState::new(&terms);
+    TransitionEngine::process_event(
+        EventType::IED,
+        terms.initial_exchange_date.unwrap(),
+        &mut state,
+        &terms,
+    )
+    .expect("failed to process IED");
+
+    c.bench_function("borsh_round_trip_terms", |b| {
+        b.iter(|| {
+            let bytes = borsh::to_vec(black_box(&terms)).expect("failed to serialize terms");
+            borsh::from_slice::<ContractTerms>(&bytes).expect("failed to deserialize terms")
+        })
+    });
+
+    c.bench_function("borsh_round_trip_state", |b| {
+        b.iter(|| {
+            let bytes = borsh::to_vec(black_box(&state)).expect("failed to serialize state");
+            borsh::from_slice::<ContractState>(&bytes).expect("failed to deserialize state")
+        })
+    });
+}
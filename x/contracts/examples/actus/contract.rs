@@ -1,16 +1,24 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use wasmlanche::{
-    public, state_schema, Address, Context, ContractId, 
-    ExternalCallArgs, ExternalCallContext, Gas, Units,
-};
+use wasmlanche::{public, state_schema, Address, Context, ContractId, ExternalCallArgs, Gas};
 
+use crate::accrual::{self, AccrualFactor};
 use crate::core::{
-    ContractState, ContractTerms, EventType,
-    TransitionEngine, Result, Error,
+    event_schedule, Amendment, ColdState, ContractAddress, ContractPhase, ContractRole as Role,
+    ContractState, ContractTerms, ContractType as Type, Error, EventRecord, EventType, HotState,
+    InterestTiming, Obligation, PayoffDirection, ProcessEventResult, Rate, Result,
+    SettlementPolicy, ShiftedDay, SignedUnits, TermGroup, TermGroups, Timestamp,
+    TransitionEngine, Units,
 };
+use crate::{lifecycle, math, swap};
 
 const MAX_GAS: Gas = 10_000_000;
 const ZERO: u64 = 0;
+/// Scheduled periods whose year fractions are cached at init
+const MAX_CACHED_PERIODS: usize = 128;
+/// Recorded events a single [`get_state_at`] call replays
+pub const MAX_REPLAYED_EVENTS: u32 = 64;
+
+/// Shares of a position are expressed as parts of this denominator
+pub const SHARE_DENOMINATOR: u64 = 1_000_000;
 
 // Define core state storage schema WITHOUT schedules
 state_schema! {
@@ -19,9 +27,53 @@ state_schema! {
     ContractRole => u8,    // Role in the contract
     Currency => Address,   // Settlement currency token address
 
+    // Parties
+    Creditor => Address,   // Party holding the asset side of the contract
+    Debtor => Address,     // Party holding the liability side of the contract
+    Holders => Vec<(Address, u64)>, // Cap table of the creditor position
+
+    // Position receipt
+    Receipt => Address,    // Token contract representing the creditor position
+    ReceiptIssued => bool, // Whether the receipt has been minted at IED
+
+    // Delegated settlement
+    SettlementAgent => Address,             // Contract processing events and settling payoffs
+    SettlementConsent(Address) => Address,  // Agent each party has agreed to
+
+    // Roll-over
+    RollOverConsent(Address) => (u64, Option<Rate>), // (maturity, rate) each party has agreed to
+
     // Contract State
-    State => Vec<u8>,      // Serialized ContractState
-    Terms => Vec<u8>,      // Serialized ContractTerms
+    State => Vec<u8>,      // Serialized HotState, rewritten by every event
+    StateCold => Vec<u8>,  // Serialized ColdState, rewritten only when it changes
+    Terms(u8) => Vec<u8>,  // Serialized group of ContractTerms by TermGroup
+
+    // Event history
+    HistoryLength => u32,        // Number of recorded events
+    HistoryEntry(u32) => Vec<u8>, // Serialized EventRecord by index
+
+    // Settlement precision
+    CurrencyDecimals => u8, // Decimals of the settlement currency, queried once at init
+
+    // Accrual
+    AccrualCache(u64) => AccrualFactor, // Year fraction of the scheduled period ending then
+
+    // Replay checkpoints
+    Snapshot(u32) => Vec<u8>,     // Serialized ContractState after that many recorded events
+    Snapshots => Vec<(u32, bool)>, // (history length, whether the terms changed) of each
+
+    // Amendments
+    Creator => Address, // Party that called init, who may amend the terms before the IED
+    AmendmentProposal => (Address, Amendment, u64), // (proposer, amendment, expiry) pending
+
+    // Event hook
+    Hook => (Address, bool), // Contract notified of each recorded event, whether it failing reverts
+
+    // Shortfalls
+    Obligations => Vec<Obligation>, // Payments the debtor left unpaid, by due date
+
+    // Market data
+    Observation(u8, u64) => u64, // Value the market object provider published for (event, time)
 
     // (Removed schedules and maturity date fields)
     // MaturityDate => u64,
@@ -40,136 +92,1813 @@ fn call_args_from_address(address: Address) -> ExternalCallArgs {
 }
 
 /// Initializes the ACTUS contract (no schedule generation)
+/// The actor takes `contract_role` and `counterparty` takes the opposite side.
+/// `receipt_token` is deployed as the position token minted to the creditor side at IED.
+/// Amounts in the terms carry [`math::AMOUNT_DECIMALS`] decimals; payoffs are reported and
+/// settled in the base unit of `currency`, truncated to its decimals.
 #[public]
 pub fn init(
     context: &mut Context,
     contract_type: u8,
     contract_role: u8,
     currency: Address,
-    terms_bytes: Vec<u8>,  // Serialized ContractTerms
+    counterparty: Address,
+    receipt_token: ContractId,
+    terms_bytes: Vec<u8>,  // ContractTerms::to_versioned_bytes
 ) -> Result<()> {
+    if context
+        .get(Terms(TermGroup::Principal as u8))
+        .map_err(|_| Error::StorageError("Failed to load terms".into()))?
+        .is_some()
+    {
+        return Err(Error::ValidationError("Contract already initialized".into()));
+    }
+
     // 1. Deserialize and validate terms
-    let contract_terms: ContractTerms = ContractTerms::try_from_slice(&terms_bytes)
-        .map_err(|_| Error::ValidationError("Failed to deserialize terms".into()))?;
-
-    // 2. Initialize empty or partially set up state
-    //    If you have a custom "new" function, adapt accordingly.
-    //    Otherwise, do something like:
-    let mut initial_state = ContractState::new(
-        context.timestamp(),
-        &contract_terms,
-    )?;
+    let contract_terms = ContractTerms::from_versioned_bytes(&terms_bytes)?;
+
+    if Type::try_from(contract_type)? != contract_terms.contract_type
+        || Role::try_from(contract_role)? != contract_terms.contract_role
+    {
+        return Err(Error::ValidationError(
+            "Contract type or role does not match terms".into(),
+        ));
+    }
 
-    // If you have removed "ContractState::new", you could do:
-    // let mut initial_state = ContractState::default();
-    // initial_state.status_date = context.timestamp();
-    // ... do any needed field setup ...
+    contract_terms.check_required()?;
+    contract_terms.check_disbursements()?;
+    contract_terms.check_dates()?;
 
-    // 3. Store configuration and state
+    // 2. Initialize the pre-IED state
+    let initial_state = ContractState::new(&contract_terms);
+
+    // 3. Assign the creditor and debtor sides from the actor's role
+    let actor = context.actor();
+    let (creditor, debtor) = match contract_terms.contract_role {
+        Role::CR_RPL => (counterparty, actor),
+        _ => (actor, counterparty),
+    };
+
+    // 4. Deploy the position receipt, owned by this contract
+    let name = contract_terms
+        .contract_label
+        .clone()
+        .unwrap_or_else(|| contract_terms.contract_id.to_string());
+    let receipt = context.deploy(receipt_token, context.contract_address().as_ref());
+    token::init(
+        context.to_extern(call_args_from_address(receipt)),
+        format!("{name} position"),
+        String::from("ACTUS-POS"),
+        6, // a whole receipt is SHARE_DENOMINATOR
+    );
+    let decimals = token::decimals(context.to_extern(call_args_from_address(currency)));
+
+    // 5. Store configuration and state
     //    (removed schedules => no references to generate_schedules)
     context.store((
         (ContractType, contract_type),
         (ContractRole, contract_role),
         (Currency, currency),
-        (Terms, terms_bytes),
-        (State, initial_state.try_to_vec()?),
-        // We no longer store MaturityDate or Schedules
+        (Creditor, creditor),
+        (Debtor, debtor),
+        (Holders, vec![(creditor, SHARE_DENOMINATOR)]),
+        (Receipt, receipt),
+        (ReceiptIssued, false),
+        (CurrencyDecimals, decimals),
     ))
     .map_err(|_| Error::StorageError("Failed to set state".into()))?;
+    context.store_by_key(Creator, actor)
+        .map_err(|_| Error::StorageError("Failed to set state".into()))?;
 
-    Ok(())
+    store_accrual_factors(context, &contract_terms)?;
+    store_terms(context, contract_terms)?;
+    store_state(context, &initial_state)
+}
+
+/// Replaces the terms before the IED, validated as [`init`] validates them. The type
+/// and role cannot change, and the state is rebuilt from the new terms with any events
+/// recorded so far replayed under them. Only callable by the party that called `init`.
+#[public]
+pub fn amend_terms(context: &mut Context, terms_bytes: Vec<u8>) -> Result<()> {
+    let creator = context.get(Creator)
+        .map_err(|_| Error::StorageError("Failed to load creator".into()))?;
+    if creator != Some(context.actor()) {
+        return Err(Error::NotAuthorized);
+    }
+
+    let current = load_state(context)?;
+    if current.phase != ContractPhase::PreInitialExchange {
+        return Err(Error::StateError("Terms can only be amended before the IED".into()));
+    }
+
+    let terms = ContractTerms::from_versioned_bytes(&terms_bytes)?;
+    let stored = load_term_groups(context, &[TermGroup::Principal])?;
+    if (terms.contract_type, terms.contract_role) != (stored.contract_type, stored.contract_role)
+    {
+        return Err(Error::ValidationError(
+            "An amendment cannot change the contract type or role".into(),
+        ));
+    }
+    terms.check_required()?;
+    terms.check_disbursements()?;
+    terms.check_dates()?;
+
+    let decimals = currency_decimals(context)?;
+    let mut state = ContractState::new(&terms);
+    for index in 0..history_length(context)? {
+        let record = load_event(context, index)?;
+        lifecycle::replay(&terms, &mut state, &record, decimals)?;
+    }
+
+    store_accrual_factors(context, &terms)?;
+    store_terms(context, terms)?;
+    store_state(context, &state)?;
+    store_snapshot(context, &state, true)
 }
 
-/// Process an ACTUS event
+/// Process an ACTUS event.
+/// Callable by either party or a holder of the creditor position, or only by the
+/// settlement agent once settlement is delegated. Only the debtor may prepay.
+/// Returns the payoff signed as [`ProcessEventResult::signed_payoff`] is; see
+/// [`process_event_v2`] for the side paying it and the state it leaves.
 #[public]
 pub fn process_event(
     context: &mut Context,
     event_type: u8,
     timestamp: u64,
-) -> Result<Option<Units>> {
-    // 1. Load the current state bytes
-    let state_bytes = context.get(State)
-        .map_err(|_| Error::StorageError("Failed to load state".into()))?
-        .ok_or_else(|| Error::StateError("State not initialized".into()))?;
+) -> Result<Option<SignedUnits>> {
+    process(context, event_type, timestamp).map(|result| result.signed_payoff())
+}
 
-    let terms_bytes = context.get(Terms)
-        .map_err(|_| Error::StorageError("Failed to load terms".into()))?
-        .ok_or_else(|| Error::StateError("Terms not initialized".into()))?;
+/// Process an ACTUS event as [`process_event`] does, returning the payoff as an amount
+/// and the side paying it, with the notional and accrued interest the event leaves
+#[public]
+pub fn process_event_v2(
+    context: &mut Context,
+    event_type: u8,
+    timestamp: u64,
+) -> Result<ProcessEventResult> {
+    process(context, event_type, timestamp)
+}
 
-    // 2. Deserialize
-    let mut state: ContractState = ContractState::try_from_slice(&state_bytes)
-        .map_err(|_| Error::StateError("Failed to deserialize state".into()))?;
-    
-    let terms: ContractTerms = ContractTerms::try_from_slice(&terms_bytes)
-        .map_err(|_| Error::StateError("Failed to deserialize terms".into()))?;
+/// Runs `event` as [`process_event_v2`] would, checking the actor the same way, and
+/// returns its result without storing the state, recording the event or moving any
+/// tokens. A PI or PP with an `amount` is run as [`draw_down`] or [`repay`] would run it,
+/// or [`deposit`] or [`withdraw`] for a UMP. An `observation` stands in for the one the
+/// market object provider publishes for an RR or DV, which need not be published yet;
+/// events that read none reject it.
+#[public]
+pub fn simulate_event(
+    context: &mut Context,
+    event_type: u8,
+    timestamp: u64,
+    amount: Option<Units>,
+    observation: Option<u64>,
+) -> Result<ProcessEventResult> {
+    let event = EventType::try_from(event_type)?;
+    if amount.is_some() && observation.is_some() {
+        return Err(Error::ValidationError(format!("{event:?} reads no market observation")));
+    }
+
+    let (state, payoff) = match amount {
+        Some(amount) => {
+            let (state, amount) = change_notional_of(context, event, timestamp, amount)?;
+            (state, Some(amount))
+        }
+        None => {
+            let processed = evaluate(context, event, timestamp, observation)?;
+            (processed.state, processed.payoff)
+        }
+    };
+
+    Ok(ProcessEventResult::new(event, timestamp, payoff, &state))
+}
+
+/// An event run against a copy of the state, which processing then commits
+struct Evaluated {
+    state: ContractState,
+    payoff: Option<SignedUnits>,
+    penalty: Option<SignedUnits>,
+    residual: Option<SignedUnits>,
+    agent: Option<Address>,
+    terms: ContractTerms,
+    /// Whether the event read an observation, which the history does not record
+    observed: bool,
+}
+
+fn process(context: &mut Context, event_type: u8, timestamp: u64) -> Result<ProcessEventResult> {
+    let event = EventType::try_from(event_type)?;
+    let Evaluated { mut state, payoff, penalty, residual, agent, terms, observed } =
+        evaluate(context, event, timestamp, None)?;
+    let policy = terms.settlement();
+
+    // 3. If the event triggers a payment, do a token transfer
+    //    (left to the settlement agent when one is set). A payment that cannot be made
+    //    fails before anything is written, so the event stays due, unless the policy
+    //    has what the debtor left unpaid recorded as an obligation.
+    let mut unpaid = 0;
+    if let Some(amount) = payoff {
+        if agent.is_none() {
+            unpaid = process_payment(context, event, amount, policy)?;
+        }
+
+        if event == terms.opening().0 {
+            issue_receipt(context)?;
+        }
+
+        if event == terms.closing().0 || event == EventType::STD {
+            redeem_receipt(context)?;
+        }
+    }
+
+    // 4. Keep the contract overdue while obligations are outstanding, a change the
+    //    history does not replay
+    let mut marked = false;
+    if policy != SettlementPolicy::Revert {
+        let mut obligations = load_obligations(context)?;
+        let earliest = obligations.first().map(|first| first.due_date);
+        if unpaid > 0 {
+            obligations.push(Obligation { amount: unpaid, event, due_date: timestamp });
+            store_obligations(context, obligations)?;
+        }
+
+        if let Some(due_date) = earliest.or((unpaid > 0).then_some(timestamp)) {
+            let before = (state.overdue_since, state.contract_performance);
+            TransitionEngine::record_shortfall(due_date, timestamp, &mut state, &terms);
+            marked = before != (state.overdue_since, state.contract_performance);
+        }
+    }
+
+    // 5. Store the updated state, which replaying an observed event could not rebuild
+    store_state(context, &state)?;
+    record_event(context, event, timestamp, payoff, penalty, residual)?;
+    if marked || observed {
+        store_snapshot(context, &state, false)?;
+    }
+
+    Ok(ProcessEventResult::new(event, timestamp, payoff, &state))
+}
+
+/// Authorizes `event` and runs it against the stored state, reading storage only. A
+/// `simulated` observation stands in for the published one, see [`observed`].
+fn evaluate(
+    context: &mut Context,
+    event: EventType,
+    timestamp: u64,
+    simulated: Option<u64>,
+) -> Result<Evaluated> {
+    let agent = load_settlement_agent(context)?;
+
+    if let Some(agent) = agent {
+        if context.actor() != agent {
+            return Err(Error::NotAuthorized);
+        }
+
+        let creditor = load_creditor(context)?;
+        if current_holders(context)? != [(creditor, SHARE_DENOMINATOR)] {
+            return Err(Error::StateError(
+                "Delegated settlement requires the creditor to hold the whole position".into(),
+            ));
+        }
+    } else {
+        authorize_event(context, event)?;
+    }
+
+    // 1. Load the current state and the terms the event reads
+    let mut state = load_state(context)?;
+    let mut terms = load_term_groups(context, TermGroup::read_by(event))?;
+    // interest paid in advance runs to the next IP, which the schedule dates, and a NAM
+    // only pays installments on the redemption cycle of the schedule
+    let dated = match event {
+        EventType::IP => terms.interest_timing == Some(InterestTiming::Advance),
+        EventType::PR => terms.contract_type == Type::NAM,
+        _ => false,
+    };
+    if dated {
+        let groups = [TermGroup::Principal, TermGroup::Rate, TermGroup::Schedule];
+        terms = load_term_groups(context, &groups)?;
+    }
+    let observation = observed(context, event, timestamp, &terms, simulated)?;
+    // a stock that does not fix its dividend pays the one observed
+    let dividend = observation.filter(|_| event == EventType::DV).map(|dividend| ContractTerms {
+        next_dividend_payment_amount: Some(Units::from(dividend)),
+        ..terms.clone()
+    });
+    let starts_period = match (event, observation) {
+        (EventType::RR, Some(_)) => starts_interest_period(context, timestamp)?,
+        _ => false,
+    };
+    let year_fraction = cached_year_fraction(context, event, timestamp, &state, &terms)?;
+    let penalty = TransitionEngine::penalty_due(event, timestamp, &state, &terms)?;
+    let residual =
+        TransitionEngine::residual_principal(event, timestamp, &state, &terms, year_fraction)?;
 
-    // 3. Process the event
-    let result = TransitionEngine::process_event(
-        event_type.into(),
+    // 2. Process the event, settling the payoff in the currency's precision, with the
+    //    residual earlier payoffs truncated, and converting the penalty and residual
+    //    principal it includes
+    let result = TransitionEngine::process_event_with_factor(
+        event,
         timestamp,
         &mut state,
-        &terms
+        dividend.as_ref().unwrap_or(&terms),
+        year_fraction,
     )?;
+    // an RR resets the rate to the reference rate observed, as the lifecycle does
+    if let (EventType::RR, Some(reference)) = (event, observation) {
+        let reference = Rate::try_from(reference)
+            .map_err(|_| Error::MathError("Reset rate overflow".into()))?;
+        TransitionEngine::reset_rate(reference, starts_period, &mut state, &terms)?;
+    }
+    let decimals = currency_decimals(context)?;
+    let result = result
+        .map(|amount| TransitionEngine::settle_payoff(event, amount, &mut state, decimals))
+        .transpose()?;
+    let included = |context: &mut Context, amount: Units| match result {
+        Some(_) if amount > 0 => SignedUnits::try_from(amount)
+            .map_err(|_| Error::MathError("Payoff overflow".into()))
+            .and_then(|amount| to_currency(context, amount))
+            .map(Some),
+        _ => Ok(None),
+    };
+    let penalty = included(context, penalty)?;
+    let residual = included(context, residual)?;
+
+    Ok(Evaluated {
+        state,
+        payoff: result,
+        penalty,
+        residual,
+        agent,
+        terms,
+        observed: observation.is_some(),
+    })
+}
+
+/// The observation `event` reads at `timestamp` under `terms`: the reference rate of an
+/// RR with a `market_object_code_of_rate_reset` and the dividend of a DV a stock does not
+/// fix, as the market object provider published it or as `simulated` stands in for it.
+/// An RR of a type whose rate does not reset is not applicable, and events reading none
+/// reject a `simulated` one.
+fn observed(
+    context: &mut Context,
+    event: EventType,
+    timestamp: u64,
+    terms: &ContractTerms,
+    simulated: Option<u64>,
+) -> Result<Option<u64>> {
+    let code = match event {
+        EventType::RR if !terms.contract_type.resets_rate() => {
+            return Err(Error::EventNotApplicable { event, contract_type: terms.contract_type });
+        }
+        EventType::RR => terms.market_object_code_of_rate_reset.as_deref(),
+        EventType::DV if terms.next_dividend_payment_amount.is_none() => {
+            terms.market_object_code_of_dividends.as_deref()
+        }
+        _ => None,
+    };
 
-    // 4. If the event triggers a payment, do a token transfer
-    if let Some(amount) = result {
-        process_payment(context, amount, &terms)?;
+    match (code, simulated) {
+        (None, Some(_)) => {
+            Err(Error::ValidationError(format!("{event:?} reads no market observation")))
+        }
+        (None, None) => Ok(None),
+        (Some(_), Some(value)) => Ok(Some(value)),
+        (Some(code), None) => load_observation(context, event, timestamp, code).map(Some),
     }
+}
 
-    // 5. Store the updated state
-    context.store((
-        (State, state.try_to_vec()?),
-    ))
-    .map_err(|_| Error::StorageError("Failed to update state".into()))?;
+/// Whether an interest period starts at `timestamp`, as it does with the IED and each
+/// interest payment recorded then
+fn starts_interest_period(context: &mut Context, timestamp: u64) -> Result<bool> {
+    let Some(last) = history_length(context)?.checked_sub(1) else {
+        return Ok(false);
+    };
+    let last = load_event(context, last)?;
+
+    Ok(last.timestamp == timestamp
+        && matches!(last.event_type, EventType::IED | EventType::IP | EventType::IPCI))
+}
+
+/// Writes off a defaulted contract, recording the loss in the event history
+/// and closing the contract. Only callable by the creditor.
+#[public]
+pub fn write_off(context: &mut Context, timestamp: u64) -> Result<Units> {
+    if context.actor() != load_creditor(context)? {
+        return Err(Error::NotAuthorized);
+    }
+
+    let mut state = load_state(context)?;
+    let loss = TransitionEngine::write_off(&mut state, timestamp)?;
+    let loss = SignedUnits::try_from(loss)
+        .map_err(|_| Error::MathError("Write-off amount overflow".into()))
+        .and_then(|loss| to_currency(context, loss))?;
+
+    if receipt_issued(context)? {
+        redeem_receipt(context)?;
+    }
+
+    store_state(context, &state)?;
+    record_event(context, EventType::WO, timestamp, Some(loss), None, None)?;
+
+    Ok(loss.unsigned_abs())
+}
+
+/// Draws `amount` on a credit facility, which the holders of the creditor position pay
+/// to the debtor, returning it in the currency's precision. Only callable by the debtor.
+#[public]
+pub fn draw_down(context: &mut Context, timestamp: u64, amount: Units) -> Result<SignedUnits> {
+    change_notional(context, EventType::PI, timestamp, amount)
+}
+
+/// Repays `amount` of the balance drawn on a credit facility to the holders of the
+/// creditor position, returning it in the currency's precision. Only callable by the
+/// debtor.
+#[public]
+pub fn repay(context: &mut Context, timestamp: u64, amount: Units) -> Result<SignedUnits> {
+    change_notional(context, EventType::PP, timestamp, amount)
+}
+
+/// Deposits `amount` in a UMP, which the depositor, the creditor, pays to the debtor,
+/// returning it in the currency's precision. Only callable by the creditor.
+#[public]
+pub fn deposit(context: &mut Context, timestamp: u64, amount: Units) -> Result<SignedUnits> {
+    change_notional(context, EventType::PI, timestamp, amount)
+}
+
+/// Withdraws `amount` of the balance of a UMP, which the debtor pays to the creditor,
+/// returning it in the currency's precision. Only callable by the creditor.
+#[public]
+pub fn withdraw(context: &mut Context, timestamp: u64, amount: Units) -> Result<SignedUnits> {
+    change_notional(context, EventType::PP, timestamp, amount)
+}
+
+/// Draws on a credit facility or deposits in a UMP with a PI, or repays or withdraws
+/// with a PP, settling and recording the amount like a processed event
+fn change_notional(
+    context: &mut Context,
+    event: EventType,
+    timestamp: u64,
+    amount: Units,
+) -> Result<SignedUnits> {
+    let (state, amount) = change_notional_of(context, event, timestamp, amount)?;
+    if load_settlement_agent(context)?.is_none() {
+        process_payment(context, event, amount, SettlementPolicy::Revert)?;
+    }
+
+    store_state(context, &state)?;
+    record_event(context, event, timestamp, Some(amount), None, None)?;
+    // the amount is only recorded in the currency's precision, so it is not replayed
+    store_snapshot(context, &state, false)?;
+
+    Ok(amount)
+}
+
+/// The state a drawdown, repayment, deposit or withdrawal of `amount` leaves and the
+/// amount settled, reading storage only
+fn change_notional_of(
+    context: &mut Context,
+    event: EventType,
+    timestamp: u64,
+    amount: Units,
+) -> Result<(ContractState, SignedUnits)> {
+    let terms = load_term_groups(context, TermGroup::read_by(event))?;
+    let deposit = terms.contract_type == Type::UMP;
+    // the borrower draws on and repays a facility, the depositor pays in and withdraws
+    let initiator = if deposit { load_creditor(context)? } else { load_debtor(context)? };
+    if context.actor() != initiator {
+        return Err(Error::NotAuthorized);
+    }
+
+    let change = match (event, deposit) {
+        (EventType::PI, false) => TransitionEngine::draw_down,
+        (EventType::PP, false) => TransitionEngine::repay,
+        (EventType::PI, true) => TransitionEngine::deposit,
+        (EventType::PP, true) => TransitionEngine::withdraw,
+        _ => {
+            return Err(Error::ValidationError(
+                "Only drawdowns, repayments, deposits and withdrawals take an amount".into(),
+            ))
+        }
+    };
+    let mut state = load_state(context)?;
+    change(&mut state, &terms, timestamp, amount)?;
+
+    let decimals = currency_decimals(context)?;
+    let amount = SignedUnits::try_from(amount)
+        .map_err(|_| Error::MathError("Payoff overflow".into()))
+        .and_then(|amount| TransitionEngine::settle_payoff(event, amount, &mut state, decimals))?;
+
+    Ok((state, amount))
+}
+
+/// Publishes `value` as the observation `event_type` reads at `timestamp`: the reference
+/// rate an RR resets to, as fixed at its fixing date, in basis points, the dividend a DV
+/// pays on each share, or the price of a unit of the underlying an option is exercised at
+/// by an XD. A published observation cannot be changed. Only callable by the
+/// `market_object_provider` of the terms.
+#[public]
+pub fn observe(context: &mut Context, event_type: u8, timestamp: u64, value: u64) -> Result<()> {
+    let event = EventType::try_from(event_type)?;
+    let terms = load_term_groups(context, &[TermGroup::Principal])?;
+    let provider = terms
+        .market_object_provider
+        .ok_or(Error::MissingTerm { field: "market_object_provider" })?;
+    if context.actor() != Address::new(provider.0) {
+        return Err(Error::NotAuthorized);
+    }
 
-    Ok(result)
+    if !matches!(event, EventType::RR | EventType::DV | EventType::XD) {
+        return Err(Error::ValidationError(
+            "Only rate resets, dividends and exercises read observations".into(),
+        ));
+    }
+    let key = Observation(event_type, timestamp);
+    let published = context.get(key)
+        .map_err(|_| Error::StorageError("Failed to load observation".into()))?;
+    if published.is_some() {
+        return Err(Error::StateError("The observation is already published".into()));
+    }
+
+    context.store_by_key(key, value)
+        .map_err(|_| Error::StorageError("Failed to store observation".into()))
+}
+
+/// Exercises an option at `timestamp` at the price of a unit of its underlying the market
+/// object provider published for the XD then, returning what the STD settles in the
+/// currency's precision. Fails until the price is published. Only callable by a holder of
+/// the option.
+#[public]
+pub fn exercise(context: &mut Context, timestamp: u64) -> Result<SignedUnits> {
+    authorize_event(context, EventType::XD)?;
+
+    let mut state = load_state(context)?;
+    let terms = load_term_groups(context, TermGroup::read_by(EventType::XD))?;
+    let code = terms
+        .market_object_code_of_underlying
+        .as_deref()
+        .ok_or(Error::MissingTerm { field: "market_object_code_of_underlying" })?;
+    let price = load_observation(context, EventType::XD, timestamp, code)?;
+    TransitionEngine::exercise(timestamp, Units::from(price), &mut state, &terms)?;
+
+    store_state(context, &state)?;
+    record_event(context, EventType::XD, timestamp, None, None, None)?;
+    // the price is not recorded, so the exercise is not replayed
+    store_snapshot(context, &state, false)?;
+
+    let amount = SignedUnits::try_from(state.exercise_amount.unwrap_or(0))
+        .map_err(|_| Error::MathError("Payoff overflow".into()))?;
+    to_currency(context, amount)
+}
+
+/// Exercises a guarantee at `timestamp` once the contract it covers, the ACTUS contract
+/// at its `covered_contract` address, has registered a credit event, returning what the
+/// STD settles in the currency's precision. Only callable by a holder of the guarantee.
+#[public]
+pub fn exercise_guarantee(context: &mut Context, timestamp: u64) -> Result<SignedUnits> {
+    authorize_event(context, EventType::XD)?;
+
+    let mut state = load_state(context)?;
+    let terms = load_term_groups(context, TermGroup::read_by(EventType::XD))?;
+    let covered = terms
+        .covered_contract
+        .ok_or(Error::ValidationError("Guarantees need a covered contract".into()))?;
+    let covered = context
+        .call_contract::<Result<ContractState>>(
+            Address::new(covered.0),
+            "get_state",
+            &[],
+            MAX_GAS,
+            ZERO,
+        )
+        .map_err(|_| Error::StateError("Failed to read the covered contract".into()))??;
+    TransitionEngine::exercise_guarantee(timestamp, &covered, &mut state, &terms)?;
+
+    store_state(context, &state)?;
+    record_event(context, EventType::XD, timestamp, None, None, None)?;
+    // the covered state is not recorded, so the exercise is not replayed
+    store_snapshot(context, &state, false)?;
+
+    let amount = SignedUnits::try_from(state.exercise_amount.unwrap_or(0))
+        .map_err(|_| Error::MathError("Payoff overflow".into()))?;
+    to_currency(context, amount)
+}
+
+/// Processes the events due by `timestamp` of both legs of a swap, the ACTUS contracts at
+/// its `first_leg` and `second_leg` addresses, and settles what they pay netted: the
+/// first leg's payoffs less the second's, paid by the debtor when positive. Each leg
+/// delegates its settlement to the swap, so the legs move no tokens themselves. Returns
+/// the net in the currency's precision. Callable by either party or a holder of the swap.
+#[public]
+pub fn settle_swap(context: &mut Context, timestamp: u64) -> Result<SignedUnits> {
+    authorize_event(context, EventType::STD)?;
+
+    let mut state = load_state(context)?;
+    let terms = load_term_groups(context, &[TermGroup::Principal, TermGroup::Optionality])?;
+    if terms.contract_type != Type::SWAPS {
+        return Err(Error::ValidationError("Only swaps settle legs".into()));
+    }
+    let (Some(first), Some(second)) = (terms.first_leg, terms.second_leg) else {
+        return Err(Error::ValidationError("Swaps need both legs".into()));
+    };
+    TransitionEngine::settle_swap(&mut state, timestamp)?;
+
+    let first = process_leg(context, first, timestamp)?;
+    let second = process_leg(context, second, timestamp)?;
+    if first.is_empty() && second.is_empty() {
+        return Err(Error::ValidationError(format!(
+            "Neither leg has an event due by {timestamp}"
+        )));
+    }
+    let net = swap::net(&first, &second)?
+        .iter()
+        .try_fold(0, |net: SignedUnits, cashflow| net.checked_add(cashflow.net()))
+        .ok_or(Error::MathError("Swap payoff overflow".into()))?;
+    if net != 0 {
+        process_payment(context, EventType::STD, net, SettlementPolicy::Revert)?;
+    }
+
+    store_state(context, &state)?;
+    record_event(context, EventType::STD, timestamp, Some(net), None, None)?;
+    // the payoffs of the legs are not recorded, so the netting is not replayed
+    store_snapshot(context, &state, false)?;
+    Ok(net)
+}
+
+/// Records that the debtor missed the payment `event_type` was due to make at
+/// `timestamp`, returning the amount now overdue from it. The overdue amount is
+/// settled, with any delinquency charge, by the debtor's next payment. Only callable
+/// by the creditor once the due date has passed.
+#[public]
+pub fn miss_payment(context: &mut Context, event_type: u8, timestamp: u64) -> Result<Units> {
+    let event = EventType::try_from(event_type)?;
+    if context.actor() != load_creditor(context)? {
+        return Err(Error::NotAuthorized);
+    }
+    let terms = load_term_groups(context, TermGroup::read_by(event))?;
+    if terms.granularity().from_seconds(context.timestamp()) <= timestamp {
+        return Err(Error::StateError("The payment is not yet overdue".into()));
+    }
+
+    let mut state = load_state(context)?;
+    let missed = TransitionEngine::miss_payment(event, timestamp, &mut state, &terms)?;
+
+    store_state(context, &state)?;
+    store_snapshot(context, &state, false)?;
+    Ok(missed)
+}
+
+/// The observation of `code` the market object provider published for `event` at
+/// `timestamp`
+fn load_observation(
+    context: &mut Context,
+    event: EventType,
+    timestamp: u64,
+    code: &str,
+) -> Result<u64> {
+    context.get(Observation(event as u8, timestamp))
+        .map_err(|_| Error::StorageError("Failed to load observation".into()))?
+        .ok_or_else(|| Error::ValidationError(format!("no observation of risk factor `{code}`")))
+}
+
+/// Retrieve the payments the debtor left unpaid under a shortfall settlement policy,
+/// earliest first
+#[public]
+pub fn get_outstanding_obligations(context: &mut Context) -> Result<Vec<Obligation>> {
+    load_obligations(context)
+}
+
+/// Settles the obligation at `index` of [`get_outstanding_obligations`], moving it from
+/// the debtor to the holders of the creditor position with the delinquency charge it
+/// bears at the block time, and returns the amount paid. Once none is left and no
+/// missed payment is overdue, the contract performs again. Callable by whoever may
+/// process the event that fell short.
+#[public]
+pub fn settle_obligation(context: &mut Context, index: u32) -> Result<Units> {
+    let mut obligations = load_obligations(context)?;
+    if index as usize >= obligations.len() {
+        return Err(Error::ValidationError(format!("No outstanding obligation at {index}")));
+    }
+    let obligation = obligations.remove(index as usize);
+    authorize_event(context, obligation.event)?;
+
+    let terms = load_term_groups(context, &[TermGroup::Principal, TermGroup::Rate])?;
+    let now = terms.granularity().from_seconds(context.timestamp());
+    let charge =
+        TransitionEngine::late_charge(obligation.amount, obligation.due_date, &terms, now)?;
+    let amount = obligation
+        .amount
+        .checked_add(charge)
+        .and_then(|amount| SignedUnits::try_from(amount).ok())
+        .ok_or_else(|| Error::MathError("Obligation overflow".into()))?;
+    process_payment(context, obligation.event, amount, SettlementPolicy::Revert)?;
+
+    let mut state = load_state(context)?;
+    let unpaid_since = obligations.first().map(|first| first.due_date);
+    TransitionEngine::settle_shortfall(unpaid_since, now, &mut state, &terms);
+
+    store_obligations(context, obligations)?;
+    store_state(context, &state)?;
+    store_snapshot(context, &state, false)?;
+    Ok(amount.unsigned_abs())
 }
 
 /// Retrieve the current contract state
 #[public]
 pub fn get_state(context: &mut Context) -> Result<ContractState> {
-    let state_bytes = context.get(State)
-        .map_err(|_| Error::StorageError("Failed to load state".into()))?
-        .ok_or_else(|| Error::StateError("State not initialized".into()))?;
+    load_state(context)
+}
 
-    ContractState::try_from_slice(&state_bytes)
-        .map_err(|_| Error::StateError("Failed to deserialize state".into()))
+/// Retrieve where the contract is in its life, as [`get_state`] reports it, reading only
+/// the record of the state it is kept in. Fails with a [`Error::StateError`] before `init`,
+/// like [`get_state`].
+#[public]
+pub fn get_phase(context: &mut Context) -> Result<ContractPhase> {
+    Ok(load_cold_state(context)?.phase)
 }
 
-/// (Optional) Payment logic if your Terms define 'party'/'counterparty'
-fn process_payment(
+/// Retrieve the contract terms, including any roll-over
+#[public]
+pub fn get_terms(context: &mut Context) -> Result<ContractTerms> {
+    load_terms(context)
+}
+
+/// Records the actor's consent to extend maturity to `new_maturity`, optionally
+/// resetting the rate. The roll-over is applied once both parties consent to the
+/// same terms, and `true` is returned. Fails once matured or in default.
+#[public]
+pub fn roll_over(context: &mut Context, new_maturity: u64, new_rate: Option<Rate>) -> Result<bool> {
+    let actor = context.actor();
+    let creditor = load_creditor(context)?;
+    let debtor = load_debtor(context)?;
+
+    if actor != creditor && actor != debtor {
+        return Err(Error::NotAuthorized);
+    }
+
+    // validate against copies so a first consent fails early
+    let mut state = load_state(context)?;
+    let mut terms = load_terms(context)?;
+    TransitionEngine::roll_over(&mut state, &mut terms, new_maturity, new_rate)?;
+
+    let other = if actor == creditor { debtor } else { creditor };
+    let other_consent = context.get(RollOverConsent(other))
+        .map_err(|_| Error::StorageError("Failed to load roll-over consent".into()))?;
+
+    if other_consent != Some((new_maturity, new_rate)) {
+        context.store_by_key(RollOverConsent(actor), (new_maturity, new_rate))
+            .map_err(|_| Error::StorageError("Failed to store roll-over consent".into()))?;
+        return Ok(false);
+    }
+
+    // stale consents are never cleared: they name the maturity now in force,
+    // and any further roll-over has to move past it
+
+    store_terms(context, terms)?;
+    store_state(context, &state)?;
+    store_snapshot(context, &state, true)?;
+
+    Ok(true)
+}
+
+/// Calls a CLM at the block time, returning the maturity it falls due at, the
+/// `x_day_notice` later. Its principal and interest are then paid at that MD. Only
+/// callable by the creditor or the debtor, and only once.
+#[public]
+pub fn call(context: &mut Context) -> Result<Timestamp> {
+    let actor = context.actor();
+    if actor != load_creditor(context)? && actor != load_debtor(context)? {
+        return Err(Error::NotAuthorized);
+    }
+
+    let mut state = load_state(context)?;
+    let mut terms = load_terms(context)?;
+    let now = terms.granularity().from_seconds(context.timestamp());
+    let maturity = TransitionEngine::call(&mut state, &mut terms, now)?;
+
+    store_terms(context, terms)?;
+    store_state(context, &state)?;
+    store_snapshot(context, &state, true)?;
+
+    Ok(maturity)
+}
+
+/// Proposes `amendment` of the rate, maturity or payment dates of a live contract to the
+/// other party, who may accept it until `expires_at`. It is checked against the current
+/// state and terms, and a pending proposal has to be accepted, rejected or expire first.
+/// Only callable by the creditor or the debtor.
+#[public]
+pub fn propose_amendment(
     context: &mut Context,
-    amount: Units,
-    terms: &ContractTerms,
+    amendment: Amendment,
+    expires_at: Timestamp,
 ) -> Result<()> {
-    // 1. Load the token currency
-    let currency = context.get(Currency)
-        .map_err(|_| Error::StorageError("Failed to load currency".into()))?
-        .ok_or_else(|| Error::StateError("Currency not set".into()))?;
+    let actor = context.actor();
+    if actor != load_creditor(context)? && actor != load_debtor(context)? {
+        return Err(Error::NotAuthorized);
+    }
+    if amendment_pending(context)? {
+        return Err(Error::StateError("An amendment is already proposed".into()));
+    }
 
-    let args = call_args_from_address(currency);
+    let now = context.timestamp();
+    if expires_at <= now {
+        return Err(Error::ValidationError("The proposal must expire after it is made".into()));
+    }
+
+    // validate against copies so an amendment that cannot apply fails early
+    let mut state = load_state(context)?;
+    let mut terms = load_terms(context)?;
+    let now = terms.granularity().from_seconds(now);
+    TransitionEngine::amend(&mut state, &mut terms, &amendment, now)?;
 
-    // 2. Transfer from terms.party -> terms.counterparty
-    //    If you removed 'party'/'counterparty' from ContractTerms, adapt accordingly.
-    token::transfer_from(
-        context.to_extern(args),
-        terms.party,
-        terms.counterparty,
-        amount,
-    ).map_err(|_| Error::PaymentError("Transfer failed".into()))
+    context.store_by_key(AmendmentProposal, (actor, amendment, expires_at))
+        .map_err(|_| Error::StorageError("Failed to store the amendment proposal".into()))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Accepts the pending amendment, applying it at the block time after accruing interest
+/// under the old terms up to it, and returns `true`. A proposal past its expiry is closed
+/// instead, recorded as an AMX, and `false` is returned. Only callable by the party the
+/// amendment was proposed to.
+#[public]
+pub fn accept_amendment(context: &mut Context) -> Result<bool> {
+    let (proposer, amendment, expires_at) = context.get(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to load the amendment proposal".into()))?
+        .ok_or_else(|| Error::StateError("No amendment is proposed".into()))?;
 
-    #[test]
-    fn test_init() {
-        // TODO: Test contract initialization
+    let actor = context.actor();
+    let (creditor, debtor) = (load_creditor(context)?, load_debtor(context)?);
+    let counterparty = if proposer == creditor { debtor } else { creditor };
+    if actor != counterparty {
+        return Err(Error::NotAuthorized);
     }
 
-    #[test]
-    fn test_process_event() {
-        // TODO: Test event processing
+    let now = context.timestamp();
+    if now > expires_at {
+        close_amendment(context, expires_at)?;
+        return Ok(false);
+    }
+
+    let mut state = load_state(context)?;
+    let mut terms = load_terms(context)?;
+    let now = terms.granularity().from_seconds(now);
+    TransitionEngine::amend(&mut state, &mut terms, &amendment, now)?;
+
+    context.delete(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to clear the amendment proposal".into()))?;
+    store_accrual_factors(context, &terms)?;
+    store_terms(context, terms)?;
+    store_state(context, &state)?;
+    record_event(context, EventType::AMD, now, None, None, None)?;
+    store_snapshot(context, &state, true)?;
+
+    Ok(true)
+}
+
+/// Rejects the pending amendment, or withdraws it when called by its proposer, recording
+/// it as an AMX. Only callable by the creditor or the debtor.
+#[public]
+pub fn reject_amendment(context: &mut Context) -> Result<()> {
+    let actor = context.actor();
+    if actor != load_creditor(context)? && actor != load_debtor(context)? {
+        return Err(Error::NotAuthorized);
+    }
+
+    let (_, _, expires_at) = context.get(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to load the amendment proposal".into()))?
+        .ok_or_else(|| Error::StateError("No amendment is proposed".into()))?;
+
+    close_amendment(context, expires_at)
+}
+
+/// Returns the pending amendment with its proposer and expiry, if any has not expired
+#[public]
+pub fn amendment_proposal(
+    context: &mut Context,
+) -> Result<Option<(Address, Amendment, Timestamp)>> {
+    let proposal = context.get(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to load the amendment proposal".into()))?;
+    let now = context.timestamp();
+
+    Ok(proposal.filter(|(_, _, expires_at)| now <= *expires_at))
+}
+
+/// Returns up to `limit` recorded events starting at index `offset`
+#[public]
+pub fn get_event_history(context: &mut Context, offset: u32, limit: u32) -> Result<Vec<EventRecord>> {
+    let length = history_length(context)?;
+    let end = offset.saturating_add(limit).min(length);
+
+    (offset..end).map(|index| load_event(context, index)).collect()
+}
+
+/// Reconstructs the state after the event at `event_index` in the history, in force
+/// until the next one, by replaying the recorded events from the initial state or the
+/// latest state stored for a missed payment, facility drawdown or repayment, observed
+/// rate reset or dividend, or roll-over before it. At most [`MAX_REPLAYED_EVENTS`] are replayed per
+/// call; a longer replay continues `from` a `(index, state)` an earlier call returned,
+/// which is trusted as given. States before a roll-over cannot be replayed under the new
+/// terms.
+#[public]
+pub fn get_state_at(
+    context: &mut Context,
+    event_index: u32,
+    from: Option<(u32, ContractState)>,
+) -> Result<ContractState> {
+    if event_index >= history_length(context)? {
+        return Err(Error::ValidationError("No recorded event at that index".into()));
+    }
+    // the number of events the state is after
+    let end = event_index + 1;
+    let snapshots = load_snapshots(context)?;
+    let latest = snapshots.iter().rev().map(|&(length, _)| length).find(|&length| length <= end);
+
+    let (start, mut state) = match (from, latest) {
+        (Some((index, _)), _) if index > event_index => {
+            return Err(Error::ValidationError("Cannot replay back from a later state".into()));
+        }
+        (Some((index, state)), latest) if latest.is_none_or(|length| length <= index) => {
+            (index + 1, state)
+        }
+        (_, Some(length)) => (length, load_snapshot(context, length)?),
+        (_, None) => (0, ContractState::new(&load_terms(context)?)),
+    };
+    if start == end {
+        return Ok(state);
+    }
+
+    if snapshots.iter().any(|&(length, terms_changed)| terms_changed && length > end) {
+        return Err(Error::StateError("The terms have changed since that event".into()));
+    }
+    if end - start > MAX_REPLAYED_EVENTS {
+        return Err(Error::ValidationError(format!(
+            "Replaying {} events exceeds the limit of {MAX_REPLAYED_EVENTS}, continue from \
+             index {}",
+            end - start,
+            start + MAX_REPLAYED_EVENTS - 1
+        )));
+    }
+
+    let terms = load_terms(context)?;
+    let decimals = currency_decimals(context)?;
+    for index in start..end {
+        let record = load_event(context, index)?;
+        lifecycle::replay(&terms, &mut state, &record, decimals)?;
+    }
+
+    Ok(state)
+}
+
+/// Returns the next scheduled event and its time, if any remains
+#[public]
+pub fn get_next_event(context: &mut Context) -> Result<Option<(EventType, Timestamp)>> {
+    let state = load_state(context)?;
+    let terms = load_terms(context)?;
+
+    Ok(TransitionEngine::next_event(&state, &terms))
+}
+
+/// Returns every event the terms schedule, each on the days the schedule config moves it
+/// to, as [`event_schedule`] derives them off-chain. Events are processed at their
+/// unadjusted dates regardless.
+#[public]
+pub fn get_schedule(context: &mut Context) -> Result<Vec<(EventType, ShiftedDay)>> {
+    let terms = load_terms(context)?;
+    event_schedule(&terms)
+}
+
+/// Whether `event_type` has a scheduled occurrence at or before `as_of` that is not yet
+/// processed, with the time it is next due, if ever
+#[public]
+pub fn is_event_due(
+    context: &mut Context,
+    event_type: u8,
+    as_of: Timestamp,
+) -> Result<(bool, Option<Timestamp>)> {
+    let event = EventType::try_from(event_type)?;
+    let state = load_state(context)?;
+    let terms = load_terms(context)?;
+    let last = history_length(context)?
+        .checked_sub(1)
+        .map(|index| load_event(context, index))
+        .transpose()?
+        .map(|record| (record.event_type, record.timestamp));
+
+    let due = TransitionEngine::next_occurrence(event, &state, &terms, last);
+    Ok((due.is_some_and(|due| due <= as_of), due))
+}
+
+/// Registers `hook` to be called as `on_actus_event(contract_id, event_type, timestamp,
+/// payoff)` once each event is recorded, replacing any hook set before, or removes it.
+/// A failing hook reverts the event when `revert_on_failure` is set and is ignored
+/// otherwise. Only the party that called `init` may set it.
+#[public]
+pub fn set_hook(
+    context: &mut Context,
+    hook: Option<Address>,
+    revert_on_failure: bool,
+) -> Result<()> {
+    let creator = context.get(Creator)
+        .map_err(|_| Error::StorageError("Failed to load creator".into()))?;
+    if creator != Some(context.actor()) {
+        return Err(Error::NotAuthorized);
+    }
+
+    match hook {
+        Some(hook) => context.store_by_key(Hook, (hook, revert_on_failure)),
+        None => context.delete(Hook).map(|_| ()),
+    }
+    .map_err(|_| Error::StorageError("Failed to store the hook".into()))
+}
+
+/// Returns the hook notified of each recorded event and whether it failing reverts it
+#[public]
+pub fn hook(context: &mut Context) -> Result<Option<(Address, bool)>> {
+    load_hook(context)
+}
+
+/// Returns the `(creditor, debtor)` parties of the contract
+#[public]
+pub fn parties(context: &mut Context) -> Result<(Address, Address)> {
+    Ok((load_creditor(context)?, load_debtor(context)?))
+}
+
+/// Returns the settlement currency token
+#[public]
+pub fn currency(context: &mut Context) -> Result<Address> {
+    load_currency(context)
+}
+
+/// Returns the contract settling payoffs on behalf of the parties, if any
+#[public]
+pub fn settlement_agent(context: &mut Context) -> Result<Option<Address>> {
+    load_settlement_agent(context)
+}
+
+/// Records the actor's consent to hand settlement over to `agent`.
+/// Once both parties consent to the same agent, only it may process events and
+/// payoffs are returned to it instead of being transferred.
+#[public]
+pub fn delegate_settlement(context: &mut Context, agent: Address) -> Result<()> {
+    let actor = context.actor();
+    let creditor = load_creditor(context)?;
+    let debtor = load_debtor(context)?;
+
+    if actor != creditor && actor != debtor {
+        return Err(Error::NotAuthorized);
+    }
+
+    if load_settlement_agent(context)?.is_some() {
+        return Err(Error::StateError("Settlement already delegated".into()));
+    }
+
+    if current_holders(context)? != [(creditor, SHARE_DENOMINATOR)] {
+        return Err(Error::StateError(
+            "Delegated settlement requires the creditor to hold the whole position".into(),
+        ));
+    }
+
+    context.store_by_key(SettlementConsent(actor), agent)
+        .map_err(|_| Error::StorageError("Failed to store settlement consent".into()))?;
+
+    let other = if actor == creditor { debtor } else { creditor };
+    let other_consent = context.get(SettlementConsent(other))
+        .map_err(|_| Error::StorageError("Failed to load settlement consent".into()))?;
+
+    if other_consent == Some(agent) {
+        context.store_by_key(SettlementAgent, agent)
+            .map_err(|_| Error::StorageError("Failed to store settlement agent".into()))?;
+    }
+
+    Ok(())
+}
+
+/// Splits the creditor position among several holders.
+/// Only callable while the actor is the sole holder; `shares` must sum to [`SHARE_DENOMINATOR`].
+#[public]
+pub fn fractionalize(context: &mut Context, shares: Vec<(Address, u64)>) -> Result<()> {
+    let actor = context.actor();
+
+    if current_holders(context)? != [(actor, SHARE_DENOMINATOR)] {
+        return Err(Error::NotAuthorized);
+    }
+
+    let mut total: u64 = 0;
+    for (i, (holder, share)) in shares.iter().enumerate() {
+        if *share == 0 {
+            return Err(Error::ValidationError("Shares must be non-zero".into()));
+        }
+        if shares[..i].iter().any(|(other, _)| other == holder) {
+            return Err(Error::ValidationError("Duplicate holder in shares".into()));
+        }
+        total = total
+            .checked_add(*share)
+            .ok_or(Error::MathError("Share total overflow".into()))?;
+    }
+
+    if total != SHARE_DENOMINATOR {
+        return Err(Error::ValidationError(format!(
+            "Shares must sum to {SHARE_DENOMINATOR}"
+        )));
+    }
+
+    if receipt_issued(context)? {
+        let receipt = load_receipt(context)?;
+        let args = call_args_from_address(receipt);
+        token::burn(context.to_extern(args), actor, SHARE_DENOMINATOR);
+        for &(holder, share) in &shares {
+            token::mint(context.to_extern(call_args_from_address(receipt)), holder, share);
+        }
+    }
+
+    store_holders(context, shares)
+}
+
+/// Returns the cap table of the creditor position as `(holder, share)` pairs.
+/// Once the receipt is issued, shares are the registered holders' receipt balances.
+#[public]
+pub fn holders(context: &mut Context) -> Result<Vec<(Address, u64)>> {
+    current_holders(context)
+}
+
+/// Returns the address of the position receipt token
+#[public]
+pub fn receipt(context: &mut Context) -> Result<Address> {
+    load_receipt(context)
+}
+
+/// Registers `holder` as a receipt holder so payoffs are routed to them.
+/// Needed after receipt tokens are received through a plain token transfer: payments
+/// and the redemption fail while receipt tokens are held by unregistered holders.
+/// Anyone can register a holder, so one that does not register holds up no payment.
+#[public]
+pub fn register_holder(context: &mut Context, holder: Address) -> Result<()> {
+    if !receipt_issued(context)? {
+        return Err(Error::ValidationError("Receipt not issued yet".into()));
+    }
+
+    let mut holders = current_holders(context)?;
+    if holders.iter().any(|(registered, _)| *registered == holder) {
+        return Ok(());
+    }
+
+    let receipt = load_receipt(context)?;
+    let balance = token::balance_of(context.to_extern(call_args_from_address(receipt)), holder);
+    if balance == 0 {
+        return Err(Error::ValidationError("Holder holds no receipt".into()));
+    }
+
+    holders.push((holder, balance));
+    store_holders(context, holders)
+}
+
+/// Transfers `share` of the actor's position to `recipient`
+#[public]
+pub fn transfer_share(context: &mut Context, recipient: Address, share: u64) -> Result<()> {
+    let actor = context.actor();
+
+    if share == 0 || recipient == actor {
+        return Err(Error::ValidationError("Invalid share transfer".into()));
+    }
+
+    let mut holders = current_holders(context)?;
+
+    let from = holders
+        .iter()
+        .position(|(holder, _)| *holder == actor)
+        .ok_or(Error::ValidationError("Actor holds no share".into()))?;
+
+    holders[from].1 = holders[from]
+        .1
+        .checked_sub(share)
+        .ok_or(Error::ValidationError("Insufficient share".into()))?;
+
+    match holders.iter_mut().find(|(holder, _)| *holder == recipient) {
+        Some((_, existing)) => *existing += share,
+        None => holders.push((recipient, share)),
+    }
+
+    holders.retain(|(_, share)| *share > 0);
+
+    if receipt_issued(context)? {
+        let receipt = load_receipt(context)?;
+        token::burn(context.to_extern(call_args_from_address(receipt)), actor, share);
+        token::mint(context.to_extern(call_args_from_address(receipt)), recipient, share);
+    }
+
+    store_holders(context, holders)
+}
+
+/// Fails with [`Error::NotAuthorized`] unless the actor may process `event`
+fn authorize_event(context: &mut Context, event: EventType) -> Result<()> {
+    let actor = context.actor();
+    let debtor = load_debtor(context)?;
+
+    // prepaying is the borrower's option
+    if event == EventType::PP {
+        return if actor == debtor { Ok(()) } else { Err(Error::NotAuthorized) };
+    }
+
+    // exercising is the holder's
+    if event == EventType::XD {
+        let holders = current_holders(context)?;
+        return if holders.iter().any(|(holder, _)| *holder == actor) {
+            Ok(())
+        } else {
+            Err(Error::NotAuthorized)
+        };
+    }
+
+    if actor == debtor || actor == load_creditor(context)? {
+        return Ok(());
+    }
+
+    if current_holders(context)?.iter().any(|(holder, _)| *holder == actor) {
+        Ok(())
+    } else {
+        Err(Error::NotAuthorized)
+    }
+}
+
+/// Moves `amount` between the debtor and the holders of the creditor position,
+/// splitting it pro-rata across the cap table. Returns what a debtor short of the
+/// payment left unpaid under a shortfall `policy`; any other payer short of its part
+/// fails the payment.
+fn process_payment(
+    context: &mut Context,
+    event: EventType,
+    amount: SignedUnits,
+    policy: SettlementPolicy,
+) -> Result<Units> {
+    // 1. Load the token currency and parties
+    let currency = load_currency(context)?;
+    let debtor = load_debtor(context)?;
+
+    let holders = payees(context)?;
+
+    // 2. Settle what the policy has a debtor short of the payment pay
+    let creditor_pays = PayoffDirection::of(event, amount) == PayoffDirection::CreditorToDebtor;
+    let due = amount.unsigned_abs();
+    let paid = if creditor_pays || policy == SettlementPolicy::Revert {
+        due
+    } else {
+        let available = funds_of(context, currency, debtor);
+        match policy {
+            SettlementPolicy::SettlePartially => due.min(available),
+            _ if available < due => 0,
+            _ => due,
+        }
+    };
+
+    // 3. Narrow every portion to the token's range before moving any of them
+    let portions = split_pro_rata(paid, &holders)
+        .into_iter()
+        .map(|(holder, portion)| Ok((holder, to_token_units(portion)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // 4. Pair each portion with its direction
+    let transfers: Vec<_> = portions
+        .into_iter()
+        .filter(|&(_, portion)| portion != 0)
+        .map(|(holder, portion)| {
+            if creditor_pays {
+                (holder, debtor, portion)
+            } else {
+                (debtor, holder, portion)
+            }
+        })
+        .collect();
+
+    // 5. Check every payer covers its whole part before moving any of it
+    let mut owed: Vec<(Address, Units)> = Vec::new();
+    for &(sender, _, portion) in &transfers {
+        match owed.iter_mut().find(|(payer, _)| *payer == sender) {
+            Some((_, total)) => *total += Units::from(portion),
+            None => owed.push((sender, Units::from(portion))),
+        }
+    }
+    for (sender, required) in owed {
+        let available = funds_of(context, currency, sender);
+        if available < required {
+            return Err(Error::InsufficientFunds { required, available });
+        }
+    }
+
+    // 6. Move each portion
+    for (sender, recipient, portion) in transfers {
+        let args = call_args_from_address(currency);
+        token::transfer_from(context.to_extern(args), sender, recipient, portion);
+    }
+
+    Ok(due - paid)
+}
+
+/// What this contract can move from `owner`: its balance, capped by its allowance
+fn funds_of(context: &mut Context, currency: Address, owner: Address) -> Units {
+    let spender = context.contract_address();
+    let args = call_args_from_address(currency);
+    let balance = token::balance_of(context.to_extern(args), owner);
+    let args = call_args_from_address(currency);
+    let allowance = token::allowance(context.to_extern(args), owner, spender);
+
+    Units::from(balance.min(allowance))
+}
+
+/// Decimals of the settlement currency, read at `init`
+fn currency_decimals(context: &mut Context) -> Result<u8> {
+    context.get(CurrencyDecimals)
+        .map_err(|_| Error::StorageError("Failed to load currency decimals".into()))?
+        .ok_or_else(|| Error::StateError("Currency decimals not set".into()))
+}
+
+/// Converts an amount of the terms to the base unit of the settlement currency,
+/// keeping its sign
+fn to_currency(context: &mut Context, amount: SignedUnits) -> Result<SignedUnits> {
+    let decimals = currency_decimals(context)?;
+
+    let magnitude = math::rescale_decimals(amount.unsigned_abs(), math::AMOUNT_DECIMALS, decimals)
+        .and_then(|magnitude| SignedUnits::try_from(magnitude).ok())
+        .ok_or_else(|| Error::MathError("Payoff exceeds the currency's range".into()))?;
+
+    Ok(if amount < 0 { -magnitude } else { magnitude })
+}
+
+/// Narrows an amount in the currency's base unit to the token's own amounts
+fn to_token_units(amount: Units) -> Result<token::Units> {
+    token::Units::try_from(amount)
+        .map_err(|_| Error::MathError("Payoff exceeds the token's range".into()))
+}
+
+/// Splits `amount` by `shares`, giving the rounding residual to the largest holder
+fn split_pro_rata(amount: Units, shares: &[(Address, u64)]) -> Vec<(Address, Units)> {
+    let total: Units = shares.iter().map(|&(_, share)| Units::from(share)).sum();
+
+    let mut portions: Vec<_> = shares
+        .iter()
+        .map(|&(holder, share)| {
+            // split the quotient and remainder so no amount overflows the product
+            let share = Units::from(share);
+            let portion = amount.checked_div(total).unwrap_or_default() * share
+                + (amount.checked_rem(total).unwrap_or_default() * share)
+                    .checked_div(total)
+                    .unwrap_or_default();
+            (holder, portion)
+        })
+        .collect();
+
+    let distributed: Units = portions.iter().map(|(_, portion)| portion).sum();
+
+    // ties go to the first listed holder
+    let largest = shares
+        .iter()
+        .enumerate()
+        .max_by_key(|&(i, &(_, share))| (share, std::cmp::Reverse(i)))
+        .map(|(i, _)| i);
+
+    if let Some(i) = largest {
+        portions[i].1 += amount - distributed;
+    }
+
+    portions
+}
+
+/// Processes the events of the ACTUS contract at `leg` due by `timestamp` as the agent it
+/// delegated settlement to, returning their payoffs in the currency's precision
+fn process_leg(
+    context: &mut Context,
+    leg: ContractAddress,
+    timestamp: Timestamp,
+) -> Result<Vec<(EventType, Timestamp, Option<SignedUnits>)>> {
+    let leg = Address::new(leg.0);
+    let currency = context
+        .call_contract::<Result<Address>>(leg, "currency", &[], MAX_GAS, ZERO)
+        .map_err(|_| Error::StateError("Failed to read a leg of the swap".into()))??;
+    if currency != load_currency(context)? {
+        return Err(Error::ValidationError(
+            "The legs of a swap must pay in the same currency".into(),
+        ));
+    }
+
+    let mut payoffs = Vec::new();
+    loop {
+        let next = context
+            .call_contract::<Result<Option<(EventType, Timestamp)>>>(
+                leg,
+                "get_next_event",
+                &[],
+                MAX_GAS,
+                ZERO,
+            )
+            .map_err(|_| Error::StateError("Failed to read a leg of the swap".into()))??;
+        let Some((event, time)) = next.filter(|(_, time)| *time <= timestamp) else {
+            return Ok(payoffs);
+        };
+
+        let args = borsh::to_vec(&(event as u8, time))
+            .map_err(|_| Error::StateError("Failed to serialize the leg call".into()))?;
+        let payoff = context
+            .call_contract::<Result<Option<SignedUnits>>>(
+                leg,
+                "process_event",
+                &args,
+                MAX_GAS,
+                ZERO,
+            )
+            .map_err(|_| Error::StateError("Failed to process a leg of the swap".into()))??;
+        payoffs.push((event, time, payoff));
+    }
+}
+
+/// Mints the receipt to the creditor side following the cap table
+fn issue_receipt(context: &mut Context) -> Result<()> {
+    if receipt_issued(context)? {
+        return Ok(());
+    }
+
+    let receipt = load_receipt(context)?;
+    for (holder, share) in load_holders(context)? {
+        token::mint(context.to_extern(call_args_from_address(receipt)), holder, share);
+    }
+
+    context.store_by_key(ReceiptIssued, true)
+        .map_err(|_| Error::StorageError("Failed to store receipt status".into()))
+}
+
+/// Burns the outstanding receipt once the position is redeemed
+fn redeem_receipt(context: &mut Context) -> Result<()> {
+    let receipt = load_receipt(context)?;
+    for (holder, balance) in payees(context)? {
+        token::burn(context.to_extern(call_args_from_address(receipt)), holder, balance);
+    }
+
+    store_holders(context, Vec::new())
+}
+
+/// Returns the cap table, refreshed from receipt balances once the receipt is issued
+fn current_holders(context: &mut Context) -> Result<Vec<(Address, u64)>> {
+    let holders = load_holders(context)?;
+
+    if !receipt_issued(context)? {
+        return Ok(holders);
+    }
+
+    let receipt = load_receipt(context)?;
+    let holders = holders
+        .into_iter()
+        .map(|(holder, _)| {
+            let args = call_args_from_address(receipt);
+            (holder, token::balance_of(context.to_extern(args), holder))
+        })
+        .filter(|(_, balance)| *balance > 0)
+        .collect();
+
+    Ok(holders)
+}
+
+/// Returns the cap table payoffs are split by, failing unless its holders hold every
+/// receipt token so no part of a payment goes to the wrong holder or is left unpaid
+fn payees(context: &mut Context) -> Result<Vec<(Address, u64)>> {
+    let holders = current_holders(context)?;
+
+    if receipt_issued(context)? {
+        let receipt = load_receipt(context)?;
+        let supply = token::total_supply(context.to_extern(call_args_from_address(receipt)));
+        let registered: u64 = holders.iter().map(|&(_, balance)| balance).sum();
+        if registered != supply {
+            return Err(Error::StateError(
+                "Receipt tokens are held by unregistered holders".into(),
+            ));
+        }
+    }
+
+    if holders.is_empty() {
+        return Err(Error::StateError("The position has no holders".into()));
+    }
+
+    Ok(holders)
+}
+
+fn load_currency(context: &mut Context) -> Result<Address> {
+    context.get(Currency)
+        .map_err(|_| Error::StorageError("Failed to load currency".into()))?
+        .ok_or_else(|| Error::StateError("Currency not set".into()))
+}
+
+fn load_creditor(context: &mut Context) -> Result<Address> {
+    context.get(Creditor)
+        .map_err(|_| Error::StorageError("Failed to load creditor".into()))?
+        .ok_or_else(|| Error::StateError("Creditor not set".into()))
+}
+
+fn load_debtor(context: &mut Context) -> Result<Address> {
+    context.get(Debtor)
+        .map_err(|_| Error::StorageError("Failed to load debtor".into()))?
+        .ok_or_else(|| Error::StateError("Debtor not set".into()))
+}
+
+fn load_settlement_agent(context: &mut Context) -> Result<Option<Address>> {
+    context.get(SettlementAgent)
+        .map_err(|_| Error::StorageError("Failed to load settlement agent".into()))
+}
+
+fn receipt_issued(context: &mut Context) -> Result<bool> {
+    context.get(ReceiptIssued)
+        .map_err(|_| Error::StorageError("Failed to load receipt status".into()))
+        .map(|issued| issued.unwrap_or_default())
+}
+
+fn load_receipt(context: &mut Context) -> Result<Address> {
+    context.get(Receipt)
+        .map_err(|_| Error::StorageError("Failed to load receipt".into()))?
+        .ok_or_else(|| Error::StateError("Receipt not initialized".into()))
+}
+
+fn load_holders(context: &mut Context) -> Result<Vec<(Address, u64)>> {
+    context.get(Holders)
+        .map_err(|_| Error::StorageError("Failed to load holders".into()))?
+        .ok_or_else(|| Error::StateError("Holders not initialized".into()))
+}
+
+fn store_holders(context: &mut Context, holders: Vec<(Address, u64)>) -> Result<()> {
+    context.store_by_key(Holders, holders)
+        .map_err(|_| Error::StorageError("Failed to store holders".into()))
+}
+
+fn load_obligations(context: &mut Context) -> Result<Vec<Obligation>> {
+    context.get(Obligations)
+        .map_err(|_| Error::StorageError("Failed to load obligations".into()))
+        .map(|obligations| obligations.unwrap_or_default())
+}
+
+fn store_obligations(context: &mut Context, obligations: Vec<Obligation>) -> Result<()> {
+    context.store_by_key(Obligations, obligations)
+        .map_err(|_| Error::StorageError("Failed to store obligations".into()))
+}
+
+fn history_length(context: &mut Context) -> Result<u32> {
+    context.get(HistoryLength)
+        .map_err(|_| Error::StorageError("Failed to load event history".into()))
+        .map(|length| length.unwrap_or_default())
+}
+
+fn load_event(context: &mut Context, index: u32) -> Result<EventRecord> {
+    let bytes = context.get(HistoryEntry(index))
+        .map_err(|_| Error::StorageError("Failed to load event history".into()))?
+        .ok_or_else(|| Error::StateError("Missing event history entry".into()))?;
+
+    borsh::from_slice(&bytes)
+        .map_err(|_| Error::StateError("Failed to deserialize event record".into()))
+}
+
+/// Appends a processed event to the history, emits it as a log and notifies the hook
+fn record_event(
+    context: &mut Context,
+    event_type: EventType,
+    timestamp: Timestamp,
+    payoff: Option<SignedUnits>,
+    penalty: Option<SignedUnits>,
+    residual: Option<SignedUnits>,
+) -> Result<()> {
+    let index = history_length(context)?;
+    let record = EventRecord {
+        event_type,
+        timestamp,
+        payoff,
+        penalty,
+        residual,
+        processed_at: context.timestamp(),
+    };
+    let bytes = borsh::to_vec(&record)
+        .map_err(|_| Error::StateError("Failed to serialize event record".into()))?;
+
+    context.emit_log(&bytes);
+    context.store(((HistoryEntry(index), bytes), (HistoryLength, index + 1)))
+        .map_err(|_| Error::StorageError("Failed to store event history".into()))?;
+
+    notify_hook(context, &record)
+}
+
+fn load_hook(context: &mut Context) -> Result<Option<(Address, bool)>> {
+    context.get(Hook)
+        .map_err(|_| Error::StorageError("Failed to load the hook".into()))
+}
+
+/// Calls the hook, if any, with `record`. Payoffs have moved by then and an error would
+/// not undo them, so a hook whose failure reverts the event panics to abort the call.
+fn notify_hook(context: &mut Context, record: &EventRecord) -> Result<()> {
+    let Some((hook, revert_on_failure)) = load_hook(context)? else {
+        return Ok(());
+    };
+
+    let contract_id = load_term_groups(context, &[TermGroup::Principal])?.contract_id;
+    let args = borsh::to_vec(&(contract_id, record.event_type, record.timestamp, record.payoff))
+        .map_err(|_| Error::StateError("Failed to serialize the hook call".into()))?;
+    let result = context.call_contract::<()>(hook, "on_actus_event", &args, MAX_GAS, ZERO);
+
+    if let (Err(error), true) = (result, revert_on_failure) {
+        panic!("The hook failed on {:?}: {error:?}", record.event_type);
+    }
+
+    Ok(())
+}
+
+fn load_snapshots(context: &mut Context) -> Result<Vec<(u32, bool)>> {
+    context.get(Snapshots)
+        .map_err(|_| Error::StorageError("Failed to load snapshots".into()))
+        .map(|snapshots| snapshots.unwrap_or_default())
+}
+
+fn load_snapshot(context: &mut Context, length: u32) -> Result<ContractState> {
+    let bytes = context.get(Snapshot(length))
+        .map_err(|_| Error::StorageError("Failed to load snapshots".into()))?
+        .ok_or_else(|| Error::StateError("Missing snapshot".into()))?;
+
+    borsh::from_slice(&bytes)
+        .map_err(|_| Error::StateError("Failed to deserialize state".into()))
+}
+
+/// Keeps `state` as the replay starting point after the events recorded so far, for a
+/// change the history does not replay. A later change after as many events replaces it.
+fn store_snapshot(context: &mut Context, state: &ContractState, terms_changed: bool) -> Result<()> {
+    let length = history_length(context)?;
+    let mut snapshots = load_snapshots(context)?;
+    match snapshots.last_mut() {
+        Some((last, changed)) if *last == length => *changed |= terms_changed,
+        _ => snapshots.push((length, terms_changed)),
+    }
+
+    let bytes = borsh::to_vec(state)
+        .map_err(|_| Error::StateError("Failed to serialize state".into()))?;
+    context.store(((Snapshot(length), bytes), (Snapshots, snapshots)))
+        .map_err(|_| Error::StorageError("Failed to store snapshots".into()))
+}
+
+/// Whether an unexpired amendment is pending, closing an expired one
+fn amendment_pending(context: &mut Context) -> Result<bool> {
+    let proposal = context.get(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to load the amendment proposal".into()))?;
+
+    match proposal {
+        Some((_, _, expires_at)) if context.timestamp() > expires_at => {
+            close_amendment(context, expires_at)?;
+            Ok(false)
+        }
+        proposal => Ok(proposal.is_some()),
+    }
+}
+
+/// Clears the pending amendment without applying it, recording an AMX at its expiry.
+/// It expired if the record was processed after that, else it was rejected.
+fn close_amendment(context: &mut Context, expires_at: Timestamp) -> Result<()> {
+    context.delete(AmendmentProposal)
+        .map_err(|_| Error::StorageError("Failed to clear the amendment proposal".into()))?;
+    let granularity = load_term_groups(context, &[TermGroup::Rate])?.granularity();
+    record_event(context, EventType::AMX, granularity.from_seconds(expires_at), None, None, None)
+}
+
+fn load_terms(context: &mut Context) -> Result<ContractTerms> {
+    load_term_groups(context, &TermGroup::ALL)
+}
+
+/// Loads only `groups` of the terms, leaving the others at their defaults
+fn load_term_groups(context: &mut Context, groups: &[TermGroup]) -> Result<ContractTerms> {
+    let mut terms = TermGroups::default();
+
+    for &group in groups {
+        let bytes = context.get(Terms(group as u8))
+            .map_err(|_| Error::StorageError("Failed to load terms".into()))?
+            .ok_or_else(|| Error::StateError("Terms not initialized".into()))?;
+
+        terms.read(group, &bytes)
+            .map_err(|_| Error::StateError("Failed to deserialize terms".into()))?;
+    }
+
+    Ok(terms.into())
+}
+
+/// Stores every group of the terms under its own key
+fn store_terms(context: &mut Context, terms: ContractTerms) -> Result<()> {
+    let terms = TermGroups::from(terms);
+    let [principal, rate, schedule, optionality] = TermGroup::ALL.map(|group| {
+        terms.to_bytes(group)
+            .map_err(|_| Error::StateError("Failed to serialize terms".into()))
+    });
+
+    context.store((
+        (Terms(TermGroup::Principal as u8), principal?),
+        (Terms(TermGroup::Rate as u8), rate?),
+        (Terms(TermGroup::Schedule as u8), schedule?),
+        (Terms(TermGroup::Optionality as u8), optionality?),
+    ))
+    .map_err(|_| Error::StorageError("Failed to store terms".into()))
+}
+
+/// Caches the year fractions of the first scheduled periods of `terms`. Entries of
+/// other terms left in the cache fail their check.
+fn store_accrual_factors(context: &mut Context, terms: &ContractTerms) -> Result<()> {
+    for factor in accrual::scheduled_factors(terms, MAX_CACHED_PERIODS)? {
+        context.store_by_key(AccrualCache(factor.to), factor)
+            .map_err(|_| Error::StorageError("Failed to store accrual factors".into()))?;
+    }
+
+    Ok(())
+}
+
+/// The cached year fraction of the period a scheduled event closes, unset for
+/// prepayments and analysis dates, which fall at arbitrary times, and for entries that
+/// fail their check, so the engine computes it live
+fn cached_year_fraction(
+    context: &mut Context,
+    event: EventType,
+    timestamp: Timestamp,
+    state: &ContractState,
+    terms: &ContractTerms,
+) -> Result<Option<u64>> {
+    if matches!(event, EventType::PP | EventType::AD) || timestamp <= state.status_date {
+        return Ok(None);
+    }
+
+    let factor = context.get(AccrualCache(timestamp))
+        .map_err(|_| Error::StorageError("Failed to load accrual factors".into()))?;
+
+    Ok(factor.and_then(|factor| factor.verified(terms, state.status_date, timestamp)))
+}
+
+/// Merges the hot and cold records of the state
+fn load_state(context: &mut Context) -> Result<ContractState> {
+    let hot = context.get(State)
+        .map_err(|_| Error::StorageError("Failed to load state".into()))?
+        .ok_or_else(|| Error::StateError("State not initialized".into()))?;
+    let hot: HotState = borsh::from_slice(&hot)
+        .map_err(|_| Error::StateError("Failed to deserialize state".into()))?;
+
+    Ok((hot, load_cold_state(context)?).into())
+}
+
+/// Reads the cold record of the state alone
+fn load_cold_state(context: &mut Context) -> Result<ColdState> {
+    let cold = context.get(StateCold)
+        .map_err(|_| Error::StorageError("Failed to load state".into()))?
+        .ok_or_else(|| Error::StateError("State not initialized".into()))?;
+
+    borsh::from_slice(&cold).map_err(|_| Error::StateError("Failed to deserialize state".into()))
+}
+
+/// Rewrites the hot record, and the cold one only if it differs from the stored one,
+/// which `load_state` has already read into the cache
+fn store_state(context: &mut Context, state: &ContractState) -> Result<()> {
+    let (hot, cold): (HotState, ColdState) = state.clone().into();
+    let hot = borsh::to_vec(&hot)
+        .map_err(|_| Error::StateError("Failed to serialize state".into()))?;
+    let cold = borsh::to_vec(&cold)
+        .map_err(|_| Error::StateError("Failed to serialize state".into()))?;
+
+    context.store_by_key(State, hot)
+        .map_err(|_| Error::StorageError("Failed to update state".into()))?;
+
+    let stored = context.get(StateCold)
+        .map_err(|_| Error::StorageError("Failed to load state".into()))?;
+    if stored.as_ref() != Some(&cold) {
+        context.store_by_key(StateCold, cold)
+            .map_err(|_| Error::StorageError("Failed to update state".into()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_pro_rata_gives_residual_to_largest_holder() {
+        let [alice, bob, carol] = [1, 2, 3].map(|i| Address::new([i; 33]));
+        let shares = [(alice, 333_333), (bob, 333_334), (carol, 333_333)];
+
+        let portions = split_pro_rata(100, &shares);
+
+        assert_eq!(portions, vec![(alice, 33), (bob, 34), (carol, 33)]);
+    }
+
+    #[test]
+    fn split_pro_rata_ties_go_to_first_holder() {
+        let [alice, bob] = [1, 2].map(|i| Address::new([i; 33]));
+        let shares = [(alice, 500_000), (bob, 500_000)];
+
+        let portions = split_pro_rata(101, &shares);
+
+        assert_eq!(portions, vec![(alice, 51), (bob, 50)]);
+    }
+
+    #[test]
+    fn split_pro_rata_does_not_overflow_wide_amounts() {
+        let [alice, bob] = [1, 2].map(|i| Address::new([i; 33]));
+        let shares = [(alice, 250_000), (bob, 750_000)];
+
+        let portions = split_pro_rata(Units::MAX, &shares);
+
+        assert_eq!(portions[0].1, Units::MAX / 4);
+        assert_eq!(portions[0].1 + portions[1].1, Units::MAX);
+        assert!(to_token_units(portions[0].1).is_err());
+        assert_eq!(to_token_units(u64::MAX.into()), Ok(u64::MAX));
     }
 }
@@ -0,0 +1,79 @@
+#![no_main]
+
+//! Feeds arbitrary `(event, timestamp, notional)` triples into `process_event` on a
+//! contract of each supported type right after its IED. Events must never panic, and a
+//! failed event must leave the stored state byte for byte as it was.
+
+use actus::{
+    core::{
+        ContractState, ContractTerms, ContractType, Cycle, DayCountConvention, EventType, Period,
+        PrepaymentEffect, Stub, TransitionEngine, Units,
+    },
+    math,
+};
+use libfuzzer_sys::fuzz_target;
+
+fn cycle(n: i64, p: Period) -> Cycle {
+    Cycle {
+        n,
+        p,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    }
+}
+
+/// Ten years of monthly interest and quarterly redemptions
+fn terms(contract_type: ContractType, notional: Units) -> ContractTerms {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+
+    ContractTerms {
+        contract_type,
+        status_date: ied,
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 120),
+        notional_principal: Some(notional),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(cycle(1, Period::P_M)),
+        cycle_anchor_date_of_principal_redemption: math::add_months(ied, 3),
+        cycle_of_principal_redemption: Some(cycle(1, Period::P_Q)),
+        next_principal_redemption_payment: (contract_type == ContractType::LAM)
+            .then_some(notional / 40),
+        prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+        ..Default::default()
+    }
+}
+
+fuzz_target!(|input: (u8, u64, Units)| {
+    let (event, timestamp, notional) = input;
+    let Ok(event) = EventType::try_from(event) else {
+        return;
+    };
+
+    for contract_type in [
+        ContractType::PAM,
+        ContractType::LAM,
+        ContractType::NAM,
+        ContractType::ANN,
+    ] {
+        let terms = terms(contract_type, notional);
+        let mut state = ContractState::new(&terms);
+
+        // the installment of a huge ANN notional overflows
+        let ied = terms.initial_exchange_date.unwrap();
+        if TransitionEngine::process_event(EventType::IED, ied, &mut state, &terms).is_err() {
+            continue;
+        }
+
+        let before = borsh::to_vec(&state).unwrap();
+        match TransitionEngine::process_event(event, timestamp, &mut state, &terms) {
+            Ok(_) => assert_eq!(state.status_date, timestamp),
+            Err(_) => assert_eq!(
+                borsh::to_vec(&state).unwrap(),
+                before,
+                "{event:?} failed but changed the state"
+            ),
+        }
+    }
+});
@@ -0,0 +1,58 @@
+#![no_main]
+
+//! Feeds arbitrary bytes through the `terms_bytes` path of `init`: the Borsh decode, the
+//! type and role checks and `check_required`. Terms that pass are then driven through
+//! their schedule, which must never panic nor leave the state changed by a failed event.
+
+use actus::core::{
+    ContractRole, ContractState, ContractTerms, ContractType, TransitionEngine,
+    TERMS_VERSION_MARKER,
+};
+use libfuzzer_sys::fuzz_target;
+
+/// Bounds the events replayed per input; schedules can be arbitrarily long
+const MAX_EVENTS: usize = 64;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(terms) = ContractTerms::from_versioned_bytes(data) else {
+        return;
+    };
+
+    // the encoding is canonical, so current terms re-encode to the exact input;
+    // unversioned ones are upgraded instead
+    if data.starts_with(&TERMS_VERSION_MARKER) {
+        assert_eq!(terms.to_versioned_bytes(), data);
+    }
+
+    // `init` compares the type and role against their discriminants
+    assert_eq!(
+        ContractType::try_from(terms.contract_type as u8),
+        Ok(terms.contract_type)
+    );
+    assert_eq!(
+        ContractRole::try_from(terms.contract_role as u8),
+        Ok(terms.contract_role)
+    );
+
+    if terms.check_required().is_err() {
+        return;
+    }
+
+    let mut state = ContractState::new(&terms);
+
+    for _ in 0..MAX_EVENTS {
+        let Some((event, time)) = TransitionEngine::next_event(&state, &terms) else {
+            break;
+        };
+
+        let before = borsh::to_vec(&state).unwrap();
+        if TransitionEngine::process_event(event, time, &mut state, &terms).is_err() {
+            assert_eq!(
+                borsh::to_vec(&state).unwrap(),
+                before,
+                "{event:?} failed but changed the state"
+            );
+            break;
+        }
+    }
+});
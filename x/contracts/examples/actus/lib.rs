@@ -0,0 +1,30 @@
+//! ACTUS financial contracts on top of wasmlanche.
+//!
+//! The terms, state and transitions live in `actus-core` and are re-exported as
+//! `core`, `math`, `lifecycle`, `accrual` and `swap`; `contract` holds the public
+//! entrypoints that persist them and settle the payoffs.
+//! With the `test` feature, `testutils` deploys contracts in the simulator, from the wasm
+//! [`contract_path`] locates.
+
+pub use actus_core::{accrual, core, lifecycle, math, swap};
+#[cfg(feature = "test")]
+pub mod testutils;
+
+#[cfg_attr(feature = "bindings", allow(dead_code, unused_imports))]
+mod contract;
+
+pub use contract::*;
+
+/// Path of the wasm the build script compiled the contract to, with the contract types
+/// of this build, for tests of host builds to deploy it
+///
+/// # Panics
+/// In builds the script does not compile the wasm for, such as fuzzing ones
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub const fn contract_path() -> &'static str {
+    match option_env!("CONTRACT_PATH") {
+        Some(path) => path,
+        None => panic!("the build script compiled no wasm for this build of the contract"),
+    }
+}
@@ -0,0 +1,961 @@
+// x/contracts/examples/actus/tests/encoding.rs
+
+//! Borsh layouts of the types the contract stores and returns. Generated values of
+//! each type round-trip exactly, and a sample with a distinct value in every field is
+//! pinned to a fixture under `tests/fixtures/layouts/`, so reordering a field or
+//! changing its type fails with the first byte that moved.
+
+use actus::{
+    accrual::AccrualFactor,
+    core::{
+        Amendment, BusinessDayConvention, Calendar, ColdState, ContractAddress, ContractId,
+        ContractPerformance, ContractPhase, ContractRole, ContractState, ContractTerms,
+        ContractType, Cycle, CyclePoint, DayCountConvention, EndOfMonthConvention, EventRecord,
+        EventType, FeeBasis, GuaranteedExposure, HotState, InterestTiming, Obligation,
+        OptionExerciseType, OptionType, PayoffDirection, PenaltyType, Period, PrepaymentEffect,
+        ProcessEventResult, ScalingEffect, ScheduleConfig, SettlementPolicy, ShiftedDay, Stub,
+        TermGroup, TermGroups, TimeGranularity, IPCB, MAX_TERMS_LEN,
+    },
+    math::SECONDS_PER_DAY,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use proptest::{collection::vec, option, prelude::*, sample::select};
+use std::{fmt::Debug, fs};
+
+const LAYOUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/layouts");
+/// Bytes per line of a layout fixture
+const LINE_BYTES: usize = 32;
+/// Entries of the longest generated vectors: as many timestamps as the largest
+/// accepted terms hold
+const MAX_ENTRIES: usize = MAX_TERMS_LEN / 8;
+
+/// Every value of a fieldless enum, found by decoding each byte
+fn variants<T: BorshDeserialize>() -> Vec<T> {
+    (0..=u8::MAX)
+        .filter_map(|byte| borsh::from_slice(&[byte]).ok())
+        .collect()
+}
+
+fn variant<T: BorshDeserialize + Clone + Debug + 'static>() -> impl Strategy<Value = T> {
+    select(variants::<T>())
+}
+
+fn maybe<T: Arbitrary>() -> impl Strategy<Value = Option<T>> {
+    option::of(any::<T>())
+}
+
+/// Empty, short or as long as a string term may be
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![Just(String::new()), "\\PC{1,32}", "[ -~]{128}"]
+}
+
+/// Empty, short or [`MAX_ENTRIES`] long
+fn entries<S>(entry: S) -> impl Strategy<Value = Vec<S::Value>>
+where
+    S: Strategy + Clone,
+    S::Value: Clone,
+{
+    prop_oneof![
+        Just(Vec::new()),
+        vec(entry.clone(), 1..8),
+        vec(entry, MAX_ENTRIES)
+    ]
+}
+
+fn contract_address() -> impl Strategy<Value = ContractAddress> {
+    vec(any::<u8>(), ContractAddress::LEN)
+        .prop_map(|bytes| ContractAddress(bytes.try_into().unwrap()))
+}
+
+fn cycle() -> impl Strategy<Value = Cycle> {
+    (any::<i64>(), variant(), variant(), any::<bool>()).prop_map(|(n, p, stub, include_end_day)| {
+        Cycle {
+            n,
+            p,
+            stub,
+            include_end_day,
+        }
+    })
+}
+
+fn schedule_config() -> impl Strategy<Value = ScheduleConfig> {
+    (
+        option::of(variant()),
+        option::of(variant()),
+        option::of(variant()),
+    )
+        .prop_map(
+            |(calendar, end_of_month_convention, business_day_convention)| ScheduleConfig {
+                calendar,
+                end_of_month_convention,
+                business_day_convention,
+            },
+        )
+}
+
+/// Terms with every field drawn independently, valid or not
+fn contract_terms() -> impl Strategy<Value = ContractTerms> {
+    let general = (
+        any::<[u8; ContractId::LEN]>(),
+        variant(),
+        variant(),
+        option::of(text()),
+        maybe(),
+        option::of(variant()),
+        schedule_config(),
+        any::<u64>(),
+        option::of(text()),
+        option::of(variant()),
+    );
+    let fees_and_dates = (
+        option::of(cycle()),
+        maybe(),
+        maybe(),
+        option::of(variant()),
+        maybe(),
+        maybe(),
+        maybe(),
+        maybe(),
+    );
+    let interest = (
+        maybe(),
+        option::of(cycle()),
+        maybe(),
+        maybe(),
+        maybe(),
+        option::of(cycle()),
+        option::of(variant()),
+        maybe(),
+        maybe(),
+        maybe(),
+        maybe(),
+    );
+    let notional = (
+        maybe(),
+        maybe(),
+        maybe(),
+        option::of(cycle()),
+        maybe(),
+        maybe(),
+        maybe(),
+        maybe(),
+        maybe(),
+        maybe(),
+        option::of(text()),
+        option::of(text()),
+    );
+    let scaling_and_reset = (
+        option::of(variant()),
+        maybe(),
+        maybe(),
+        option::of(cycle()),
+        maybe(),
+        option::of(text()),
+        maybe(),
+        maybe(),
+        option::of(cycle()),
+        maybe(),
+        option::of(text()),
+    );
+    let appended = (
+        maybe(),
+        option::of(variant()),
+        option::of(variant()),
+        option::of(text()),
+        maybe(),
+        maybe(),
+        option::of(variant()),
+        maybe(),
+        option::of(variant()),
+        maybe(),
+        maybe(),
+        option::of(entries(any::<u64>())),
+    );
+    let facility = (
+        maybe(),
+        option::of(variant()),
+        option::of(entries(any::<(u64, u128)>())),
+        option::of(entries(any::<u64>())),
+        option::of(variant()),
+        option::of(variant()),
+        option::of(variant()),
+        maybe(),
+        option::of(text()),
+        option::of(contract_address()),
+        maybe(),
+        option::of(variant()),
+    );
+
+    (
+        general,
+        fees_and_dates,
+        interest,
+        notional,
+        scaling_and_reset,
+        appended,
+        facility,
+        (
+            maybe(),
+            maybe(),
+            maybe(),
+            maybe(),
+            option::of(cycle()),
+            maybe(),
+            option::of(text()),
+            option::of(contract_address()),
+            option::of(contract_address()),
+            maybe(),
+            maybe(),
+            maybe(),
+        ),
+        option::of(contract_address()),
+    )
+        .prop_map(
+            |(
+                (
+                    contract_id,
+                    contract_type,
+                    contract_role,
+                    settlement_currency,
+                    initial_exchange_date,
+                    day_count_convention,
+                    schedule_config,
+                    status_date,
+                    market_object_code,
+                    contract_performance,
+                ),
+                (
+                    cycle_of_fee,
+                    cycle_anchor_date_of_fee,
+                    fee_accrued,
+                    fee_basis,
+                    fee_rate,
+                    maturity_date,
+                    amortization_date,
+                    exercise_date,
+                ),
+                (
+                    cycle_anchor_date_of_interest_payment,
+                    cycle_of_interest_payment,
+                    accrued_interest,
+                    capitalization_end_date,
+                    cycle_anchor_date_of_interest_calculation_base,
+                    cycle_of_interest_calculation_base,
+                    interest_calculation_base,
+                    interest_calculation_base_amount,
+                    nominal_interest_rate,
+                    nominal_interest_rate2,
+                    interest_scaling_multiplier,
+                ),
+                (
+                    notional_principal,
+                    premium_discount_at_ied,
+                    cycle_anchor_date_of_principal_redemption,
+                    cycle_of_principal_redemption,
+                    next_principal_redemption_payment,
+                    purchase_date,
+                    price_at_purchase_date,
+                    termination_date,
+                    price_at_termination_date,
+                    quantity,
+                    currency,
+                    currency2,
+                ),
+                (
+                    scaling_effect,
+                    scaling_index_at_status_date,
+                    cycle_anchor_date_of_scaling_index,
+                    cycle_of_scaling_index,
+                    scaling_index_at_contract_deal_date,
+                    market_object_code_of_scaling_index,
+                    notional_scaling_multiplier,
+                    cycle_anchor_date_of_rate_reset,
+                    cycle_of_rate_reset,
+                    rate_spread,
+                    market_object_code_of_rate_reset,
+                ),
+                (
+                    penalty_rate,
+                    penalty_type,
+                    prepayment_effect,
+                    contract_label,
+                    grace_period,
+                    delinquency_rate,
+                    cycle_point_of_rate_reset,
+                    fixing_period,
+                    option_exercise_type,
+                    option_exercise_start_date,
+                    option_exercise_end_date,
+                    option_exercise_dates,
+                ),
+                (
+                    credit_limit,
+                    interest_timing,
+                    disbursements,
+                    skipped_dates,
+                    time_granularity,
+                    settlement_policy,
+                    option_type,
+                    option_strike1,
+                    market_object_code_of_underlying,
+                    covered_contract,
+                    coverage_of_credit_enhancement,
+                    guaranteed_exposure,
+                ),
+                (
+                    x_day_notice,
+                    life_cap,
+                    life_floor,
+                    cycle_anchor_date_of_dividend_payment,
+                    cycle_of_dividend_payment,
+                    next_dividend_payment_amount,
+                    market_object_code_of_dividends,
+                    first_leg,
+                    second_leg,
+                    rate_multiplier,
+                    period_cap,
+                    period_floor,
+                ),
+                market_object_provider,
+            )| ContractTerms {
+                contract_id: ContractId(contract_id),
+                contract_type,
+                contract_role,
+                settlement_currency,
+                initial_exchange_date,
+                day_count_convention,
+                schedule_config,
+                status_date,
+                market_object_code,
+                contract_performance,
+                cycle_of_fee,
+                cycle_anchor_date_of_fee,
+                fee_accrued,
+                fee_basis,
+                fee_rate,
+                cycle_anchor_date_of_interest_payment,
+                cycle_of_interest_payment,
+                accrued_interest,
+                capitalization_end_date,
+                cycle_anchor_date_of_interest_calculation_base,
+                cycle_of_interest_calculation_base,
+                interest_calculation_base,
+                interest_calculation_base_amount,
+                nominal_interest_rate,
+                nominal_interest_rate2,
+                interest_scaling_multiplier,
+                maturity_date,
+                amortization_date,
+                exercise_date,
+                notional_principal,
+                premium_discount_at_ied,
+                cycle_anchor_date_of_principal_redemption,
+                cycle_of_principal_redemption,
+                next_principal_redemption_payment,
+                purchase_date,
+                price_at_purchase_date,
+                termination_date,
+                price_at_termination_date,
+                quantity,
+                currency,
+                currency2,
+                scaling_effect,
+                scaling_index_at_status_date,
+                cycle_anchor_date_of_scaling_index,
+                cycle_of_scaling_index,
+                scaling_index_at_contract_deal_date,
+                market_object_code_of_scaling_index,
+                notional_scaling_multiplier,
+                cycle_anchor_date_of_rate_reset,
+                cycle_of_rate_reset,
+                rate_spread,
+                market_object_code_of_rate_reset,
+                penalty_rate,
+                penalty_type,
+                prepayment_effect,
+                contract_label,
+                grace_period,
+                delinquency_rate,
+                cycle_point_of_rate_reset,
+                fixing_period,
+                option_exercise_type,
+                option_exercise_start_date,
+                option_exercise_end_date,
+                option_exercise_dates,
+                credit_limit,
+                interest_timing,
+                disbursements,
+                skipped_dates,
+                time_granularity,
+                settlement_policy,
+                option_type,
+                option_strike1,
+                market_object_code_of_underlying,
+                covered_contract,
+                coverage_of_credit_enhancement,
+                guaranteed_exposure,
+                x_day_notice,
+                life_cap,
+                life_floor,
+                cycle_anchor_date_of_dividend_payment,
+                cycle_of_dividend_payment,
+                next_dividend_payment_amount,
+                market_object_code_of_dividends,
+                first_leg,
+                second_leg,
+                rate_multiplier,
+                period_cap,
+                period_floor,
+                market_object_provider,
+            },
+        )
+}
+
+/// A state with every field drawn independently, reachable or not
+fn contract_state() -> impl Strategy<Value = ContractState> {
+    let amounts = (
+        any::<u128>(),
+        any::<i64>(),
+        any::<i128>(),
+        maybe(),
+        maybe(),
+        maybe(),
+        any::<u128>(),
+        any::<u64>(),
+        any::<u64>(),
+        variant(),
+    );
+    let schedule = (
+        maybe(),
+        any::<u64>(),
+        any::<u128>(),
+        any::<u128>(),
+        maybe(),
+        maybe(),
+        any::<u128>(),
+        maybe(),
+        any::<bool>(),
+        maybe(),
+        maybe(),
+        variant(),
+    );
+    let totals = (
+        any::<i128>(),
+        any::<i128>(),
+        any::<u128>(),
+        any::<u128>(),
+        any::<u128>(),
+        any::<u128>(),
+        any::<u128>(),
+        any::<u128>(),
+    );
+
+    (amounts, schedule, totals).prop_map(
+        |(
+            (
+                notional_principal,
+                nominal_interest_rate,
+                accrued_interest,
+                accrued_interest_first_leg,
+                accrued_interest_second_leg,
+                last_interest_period,
+                fee_accrued,
+                notional_scaling_multiplier,
+                interest_scaling_multiplier,
+                contract_performance,
+            ),
+            (
+                time_of_maturity,
+                status_date,
+                next_principal_redemption_payment,
+                interest_calculation_base,
+                exercise_date,
+                exercise_amount,
+                overdue_amount,
+                overdue_since,
+                penalty_assessed,
+                reference_rate,
+                reset_rate,
+                phase,
+            ),
+            (
+                payoff_residual,
+                total_interest_paid,
+                total_principal_repaid,
+                total_fees_paid,
+                total_penalties_paid,
+                overdue_interest,
+                overdue_fees,
+                overdue_penalties,
+            ),
+        )| ContractState {
+            time_of_maturity,
+            notional_principal,
+            nominal_interest_rate,
+            accrued_interest,
+            accrued_interest_first_leg,
+            accrued_interest_second_leg,
+            last_interest_period,
+            fee_accrued,
+            notional_scaling_multiplier,
+            interest_scaling_multiplier,
+            contract_performance,
+            status_date,
+            next_principal_redemption_payment,
+            interest_calculation_base,
+            exercise_date,
+            exercise_amount,
+            overdue_amount,
+            overdue_since,
+            penalty_assessed,
+            reference_rate,
+            reset_rate,
+            phase,
+            payoff_residual,
+            total_interest_paid,
+            total_principal_repaid,
+            total_fees_paid,
+            total_penalties_paid,
+            overdue_interest,
+            overdue_fees,
+            overdue_penalties,
+        },
+    )
+}
+
+fn process_event_result() -> impl Strategy<Value = ProcessEventResult> {
+    (
+        variant(),
+        any::<u64>(),
+        option::of((any::<u128>(), variant())),
+        any::<u128>(),
+        any::<i128>(),
+    )
+        .prop_map(
+            |(event, timestamp, payoff, notional_after, accrued_after)| ProcessEventResult {
+                event,
+                timestamp,
+                payoff,
+                notional_after,
+                accrued_after,
+            },
+        )
+}
+
+fn event_record() -> impl Strategy<Value = EventRecord> {
+    (
+        variant(),
+        any::<u64>(),
+        maybe(),
+        maybe(),
+        maybe(),
+        any::<u64>(),
+    )
+        .prop_map(
+            |(event_type, timestamp, payoff, penalty, residual, processed_at)| EventRecord {
+                event_type,
+                timestamp,
+                payoff,
+                penalty,
+                residual,
+                processed_at,
+            },
+        )
+}
+
+fn amendment() -> impl Strategy<Value = Amendment> {
+    (maybe(), maybe(), entries(any::<u64>())).prop_map(|(rate, maturity, skipped_dates)| {
+        Amendment {
+            rate,
+            maturity,
+            skipped_dates,
+        }
+    })
+}
+
+/// Decoding the encoding of `value` gives it back, and encoding that the same bytes
+fn round_trip<T>(value: &T) -> Result<(), TestCaseError>
+where
+    T: BorshSerialize + BorshDeserialize + PartialEq + Debug,
+{
+    let bytes = borsh::to_vec(value).expect("writing to a Vec never fails");
+    let decoded = borsh::from_slice::<T>(&bytes)
+        .map_err(|err| TestCaseError::fail(format!("{value:?} does not decode: {err}")))?;
+    prop_assert_eq!(&decoded, value);
+    prop_assert_eq!(borsh::to_vec(&decoded).unwrap(), bytes);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn terms_round_trip(terms in contract_terms()) {
+        round_trip(&terms)?;
+
+        let bytes = terms.to_versioned_bytes();
+        match ContractTerms::from_versioned_bytes(&bytes) {
+            Ok(decoded) => prop_assert_eq!(&decoded, &terms),
+            Err(err) => prop_assert!(bytes.len() > MAX_TERMS_LEN, "{err}"),
+        }
+
+        let groups = TermGroups::from(terms.clone());
+        round_trip(&groups.principal)?;
+        round_trip(&groups.rate)?;
+        round_trip(&groups.schedule)?;
+        round_trip(&groups.optionality)?;
+
+        let mut read = TermGroups::default();
+        for group in TermGroup::ALL {
+            read.read(group, &groups.to_bytes(group).unwrap()).unwrap();
+        }
+        prop_assert_eq!(ContractTerms::from(read), terms);
+    }
+
+    #[test]
+    fn states_round_trip(state in contract_state()) {
+        round_trip(&state)?;
+
+        let (hot, cold) = <(HotState, ColdState)>::from(state.clone());
+        round_trip(&hot)?;
+        round_trip(&cold)?;
+        prop_assert_eq!(ContractState::from((hot, cold)), state);
+    }
+
+    #[test]
+    fn results_and_records_round_trip(
+        result in process_event_result(),
+        record in event_record(),
+        amendment in amendment(),
+    ) {
+        round_trip(&result)?;
+        round_trip(&record)?;
+        round_trip(&amendment)?;
+    }
+
+    #[test]
+    fn schedule_types_round_trip(
+        cycle in cycle(),
+        config in schedule_config(),
+        payment_day in any::<u64>(),
+        calculation_day in any::<u64>(),
+    ) {
+        round_trip(&cycle)?;
+        round_trip(&config)?;
+        round_trip(&ShiftedDay {
+            payment_day,
+            calculation_day,
+        })?;
+    }
+}
+
+/// `bytes` in hex, [`LINE_BYTES`] to a line
+fn hex_lines(bytes: &[u8]) -> String {
+    bytes
+        .chunks(LINE_BYTES)
+        .map(|line| {
+            line.iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+/// Fails on the first line of `actual` that differs from the fixture `name`
+fn assert_fixture(name: &str, actual: &str) {
+    let path = format!("{LAYOUT_DIR}/{name}");
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| panic!("{path}: {err}"));
+
+    let mismatch = expected
+        .lines()
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .zip(actual.lines().map(Some).chain(std::iter::repeat(None)))
+        .take_while(|lines| *lines != (None, None))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual);
+
+    if let Some((line, (expected, actual_line))) = mismatch {
+        panic!(
+            "{name} differs from byte {}, on line {}:\n  expected {}\n    actual {}\nthe \
+             whole encoding is now\n{actual}",
+            line * LINE_BYTES,
+            line + 1,
+            expected.unwrap_or("<end>"),
+            actual_line.unwrap_or("<end>"),
+        );
+    }
+}
+
+fn assert_layout<T: BorshSerialize + BorshDeserialize + PartialEq + Debug>(name: &str, value: &T) {
+    let bytes = borsh::to_vec(value).unwrap();
+    assert_eq!(&borsh::from_slice::<T>(&bytes).unwrap(), value);
+    assert_fixture(&format!("{name}.hex"), &hex_lines(&bytes));
+}
+
+/// `name::VARIANT = byte` for each variant of a `T`, checking it encodes to that byte
+fn variant_lines<T: BorshSerialize + BorshDeserialize + Debug>(name: &str) -> String {
+    (0..=u8::MAX)
+        .filter_map(|byte| Some((byte, borsh::from_slice::<T>(&[byte]).ok()?)))
+        .map(|(byte, variant)| {
+            assert_eq!(
+                borsh::to_vec(&variant).unwrap(),
+                [byte],
+                "{name}::{variant:?}"
+            );
+            format!("{name}::{variant:?} = {byte}\n")
+        })
+        .collect()
+}
+
+#[test]
+fn enums_keep_their_discriminants() {
+    let lines = [
+        variant_lines::<EventType>("EventType"),
+        variant_lines::<ContractPerformance>("ContractPerformance"),
+        variant_lines::<ContractPhase>("ContractPhase"),
+        variant_lines::<ContractType>("ContractType"),
+        variant_lines::<ContractRole>("ContractRole"),
+        variant_lines::<DayCountConvention>("DayCountConvention"),
+        variant_lines::<TimeGranularity>("TimeGranularity"),
+        variant_lines::<EndOfMonthConvention>("EndOfMonthConvention"),
+        variant_lines::<BusinessDayConvention>("BusinessDayConvention"),
+        variant_lines::<Calendar>("Calendar"),
+        variant_lines::<GuaranteedExposure>("GuaranteedExposure"),
+        variant_lines::<FeeBasis>("FeeBasis"),
+        variant_lines::<IPCB>("IPCB"),
+        variant_lines::<ScalingEffect>("ScalingEffect"),
+        variant_lines::<PenaltyType>("PenaltyType"),
+        variant_lines::<CyclePoint>("CyclePoint"),
+        variant_lines::<InterestTiming>("InterestTiming"),
+        variant_lines::<SettlementPolicy>("SettlementPolicy"),
+        variant_lines::<OptionExerciseType>("OptionExerciseType"),
+        variant_lines::<OptionType>("OptionType"),
+        variant_lines::<PrepaymentEffect>("PrepaymentEffect"),
+        variant_lines::<Period>("Period"),
+        variant_lines::<Stub>("Stub"),
+        variant_lines::<PayoffDirection>("PayoffDirection"),
+    ];
+
+    assert_fixture("enums.txt", &lines.concat());
+}
+
+fn sample_cycle(n: i64, p: Period) -> Option<Cycle> {
+    Some(Cycle {
+        n,
+        p,
+        stub: Stub::LongStub,
+        include_end_day: true,
+    })
+}
+
+/// Terms with every field set, each to a value no other field of its type has
+fn sample_terms() -> ContractTerms {
+    ContractTerms {
+        contract_id: "layout".into(),
+        contract_type: ContractType::LAM,
+        contract_role: ContractRole::CR_RPL,
+        settlement_currency: Some("USD".into()),
+        initial_exchange_date: Some(1),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        schedule_config: ScheduleConfig {
+            calendar: Some(Calendar::CLDR_NC),
+            end_of_month_convention: Some(EndOfMonthConvention::EOMC_SD),
+            business_day_convention: Some(BusinessDayConvention::BDC_SCMF),
+        },
+        status_date: 2,
+        market_object_code: Some("MOC".into()),
+        contract_performance: Some(ContractPerformance::DL),
+        cycle_of_fee: sample_cycle(1, Period::P_D),
+        cycle_anchor_date_of_fee: Some(3),
+        fee_accrued: Some(4),
+        fee_basis: Some(FeeBasis::FEB_N),
+        fee_rate: Some(5),
+        cycle_anchor_date_of_interest_payment: Some(6),
+        cycle_of_interest_payment: sample_cycle(2, Period::P_W),
+        accrued_interest: Some(-7),
+        capitalization_end_date: Some(8),
+        cycle_anchor_date_of_interest_calculation_base: Some(9),
+        cycle_of_interest_calculation_base: sample_cycle(3, Period::P_M),
+        interest_calculation_base: Some(IPCB::IPCB_NTL),
+        interest_calculation_base_amount: Some(10),
+        nominal_interest_rate: Some(11),
+        nominal_interest_rate2: Some(-12),
+        interest_scaling_multiplier: Some(13),
+        maturity_date: Some(14),
+        amortization_date: Some(15),
+        exercise_date: Some(16),
+        notional_principal: Some(17),
+        premium_discount_at_ied: Some(18),
+        cycle_anchor_date_of_principal_redemption: Some(19),
+        cycle_of_principal_redemption: sample_cycle(4, Period::P_Q),
+        next_principal_redemption_payment: Some(20),
+        purchase_date: Some(21),
+        price_at_purchase_date: Some(22),
+        termination_date: Some(23),
+        price_at_termination_date: Some(24),
+        quantity: Some(25),
+        currency: Some("EUR".into()),
+        currency2: Some("CHF".into()),
+        scaling_effect: Some(ScalingEffect::SE_INM),
+        scaling_index_at_status_date: Some(26),
+        cycle_anchor_date_of_scaling_index: Some(27),
+        cycle_of_scaling_index: sample_cycle(5, Period::P_H),
+        scaling_index_at_contract_deal_date: Some(28),
+        market_object_code_of_scaling_index: Some("CPI".into()),
+        notional_scaling_multiplier: Some(29),
+        cycle_anchor_date_of_rate_reset: Some(30),
+        cycle_of_rate_reset: sample_cycle(6, Period::P_Y),
+        rate_spread: Some(31),
+        market_object_code_of_rate_reset: Some("SOFR".into()),
+        penalty_rate: Some(32),
+        penalty_type: Some(PenaltyType::PYTP_I),
+        prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+        contract_label: Some("label".into()),
+        grace_period: Some(33),
+        delinquency_rate: Some(34),
+        cycle_point_of_rate_reset: Some(CyclePoint::CP_E),
+        fixing_period: Some(35),
+        option_exercise_type: Some(OptionExerciseType::XT_B),
+        option_exercise_start_date: Some(36),
+        option_exercise_end_date: Some(37),
+        option_exercise_dates: Some(vec![38, 39]),
+        credit_limit: Some(40),
+        interest_timing: Some(InterestTiming::Advance),
+        disbursements: Some(vec![(41, 42), (43, 44)]),
+        skipped_dates: Some(vec![45, 46]),
+        time_granularity: Some(TimeGranularity::Seconds),
+        settlement_policy: Some(SettlementPolicy::SettlePartially),
+        option_type: Some(OptionType::P),
+        option_strike1: Some(47),
+        market_object_code_of_underlying: Some("ACME".into()),
+        covered_contract: Some(ContractAddress([48; ContractAddress::LEN])),
+        coverage_of_credit_enhancement: Some(49),
+        guaranteed_exposure: Some(GuaranteedExposure::CEGE_NI),
+        x_day_notice: Some(50),
+        life_cap: Some(51),
+        life_floor: Some(-52),
+        cycle_anchor_date_of_dividend_payment: Some(53),
+        cycle_of_dividend_payment: sample_cycle(7, Period::P_W),
+        next_dividend_payment_amount: Some(54),
+        market_object_code_of_dividends: Some("DIVS".into()),
+        first_leg: Some(ContractAddress([55; ContractAddress::LEN])),
+        second_leg: Some(ContractAddress([56; ContractAddress::LEN])),
+        rate_multiplier: Some(57),
+        period_cap: Some(58),
+        period_floor: Some(59),
+        market_object_provider: Some(ContractAddress([60; ContractAddress::LEN])),
+    }
+}
+
+/// A state with every field set, each to a value no other field of its type has
+fn sample_state() -> ContractState {
+    ContractState {
+        time_of_maturity: Some(1),
+        notional_principal: 2,
+        nominal_interest_rate: 3,
+        accrued_interest: -4,
+        accrued_interest_first_leg: Some(5),
+        accrued_interest_second_leg: Some(6),
+        last_interest_period: Some(7),
+        fee_accrued: 8,
+        notional_scaling_multiplier: 9,
+        interest_scaling_multiplier: 10,
+        contract_performance: ContractPerformance::DQ,
+        status_date: 11,
+        next_principal_redemption_payment: 12,
+        interest_calculation_base: 13,
+        exercise_date: Some(14),
+        exercise_amount: Some(15),
+        overdue_amount: 16,
+        overdue_since: Some(17),
+        penalty_assessed: true,
+        reference_rate: Some(18),
+        reset_rate: Some(-19),
+        phase: ContractPhase::Active,
+        payoff_residual: -20,
+        total_interest_paid: 21,
+        total_principal_repaid: 22,
+        total_fees_paid: 23,
+        total_penalties_paid: 24,
+        overdue_interest: 25,
+        overdue_fees: 26,
+        overdue_penalties: 27,
+    }
+}
+
+#[test]
+fn terms_keep_their_layout() {
+    let terms = sample_terms();
+    assert_layout("contract_terms", &terms);
+    assert_fixture(
+        "contract_terms_versioned.hex",
+        &hex_lines(&terms.to_versioned_bytes()),
+    );
+
+    let groups = TermGroups::from(terms.clone());
+    assert_layout("principal_terms", &groups.principal);
+    assert_layout("rate_terms", &groups.rate);
+    assert_layout("schedule_terms", &groups.schedule);
+    assert_layout("optionality_terms", &groups.optionality);
+
+    let year = AccrualFactor::new(&terms, 0, 365 * SECONDS_PER_DAY).unwrap();
+    assert_layout("accrual_factor", &year);
+}
+
+#[test]
+fn state_keeps_its_layout() {
+    let state = sample_state();
+    assert_layout("contract_state", &state);
+
+    let (hot, cold) = <(HotState, ColdState)>::from(state);
+    assert_layout("hot_state", &hot);
+    assert_layout("cold_state", &cold);
+}
+
+#[test]
+fn results_and_records_keep_their_layout() {
+    assert_layout(
+        "process_event_result",
+        &ProcessEventResult {
+            event: EventType::IP,
+            timestamp: 1,
+            payoff: Some((2, PayoffDirection::CreditorToDebtor)),
+            notional_after: 3,
+            accrued_after: -4,
+        },
+    );
+    assert_layout(
+        "event_record",
+        &EventRecord {
+            event_type: EventType::PY,
+            timestamp: 1,
+            payoff: Some(2),
+            penalty: Some(-3),
+            residual: Some(4),
+            processed_at: 5,
+        },
+    );
+    assert_layout(
+        "amendment",
+        &Amendment {
+            rate: Some(1),
+            maturity: Some(2),
+            skipped_dates: vec![3, 4],
+        },
+    );
+    assert_layout(
+        "obligation",
+        &Obligation {
+            amount: 1,
+            event: EventType::IP,
+            due_date: 2,
+        },
+    );
+    assert_layout(
+        "shifted_day",
+        &ShiftedDay {
+            payment_day: 1,
+            calculation_day: 2,
+        },
+    );
+}
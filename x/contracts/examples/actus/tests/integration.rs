@@ -1,142 +1,3202 @@
 // x/contracts/examples/actus/tests/integration.rs
 
-use std::{path::PathBuf, process::Command};
+use actus::{
+    core::{
+        event_schedule, Amendment, BusinessDayConvention, Calendar, ColdState, ContractAddress,
+        ContractPerformance, ContractPhase, ContractState, ContractTerms, ContractType, Cycle,
+        DayCountConvention, EndOfMonthConvention, Error as ActusError, EventRecord, EventType,
+        HotState, IPCB, Obligation, OptionExerciseType, OptionType, PayoffDirection, PenaltyType,
+        Period, PrepaymentEffect, ProcessEventResult, ScheduleConfig, SettlementPolicy,
+        ShiftedDay, SignedUnits, Stub, TimeGranularity, Timestamp, TransitionEngine, Units,
+    },
+    lifecycle,
+    math::{self, SECONDS_PER_DAY},
+    MAX_REPLAYED_EVENTS,
+    testutils::{
+        actors, addresses, pam_terms, token_path, Actor, Actors, Scenario, ACTOR_SEED,
+        CONTRACT_PATH, DEFAULT_FUNDING, MAX_GAS,
+    },
+    SHARE_DENOMINATOR,
+};
+use actus_core::risk_factors::RiskFactorSeries;
+use token::TransferLog;
 use wasmlanche::{
+    borsh,
     simulator::{Error as SimError, SimpleState, Simulator},
     Address,
 };
-use token::Units;
-
-/// If your contract references these from your “mod types” or “type.rs”
-use crate::core::{ContractState, ContractTerms, ContractType, EventType};
-// Or adjust to your actual code location, e.g. "use actus::{ContractState, ...};"
 
-/// If you have environment variables for the compiled WASM:
-const CONTRACT_PATH: &str = env!("CONTRACT_PATH");  // or define a static path if you prefer
+/// Raw keys of the hot and cold state records: a unit key is stored as its position in
+/// the contract's `state_schema!` followed by the big-endian maximum chunk count, 4
+const STATE_KEY: [u8; 3] = [11, 0, 4];
+const STATE_COLD_KEY: [u8; 3] = [12, 0, 4];
 
 #[test]
 fn test_pam_integration() -> Result<(), SimError> {
-    // 1. Create an in-memory chain state and simulator
     let mut state = SimpleState::new();
-    let simulator = Simulator::new(&mut state);
+    let mut scenario = Scenario::builder().build(&mut state)?;
 
-    // 2. Deploy your ACTUS contract
-    //    Make sure CONTRACT_PATH points to the compiled WASM artifact
-    let deployed_contract = simulator.create_contract(CONTRACT_PATH)?;
-    let contract_address = deployed_contract.address;
+    assert_eq!(scenario.process(EventType::IED, 1000)?, Some(500_000));
 
-    // 3. Optionally deploy or reference a token contract for settlement, if needed
-    //    Here, we create a simple test token
-    let token_contract = simulator.create_contract("PATH_TO_TOKEN_WASM")?; 
-    let token_address = token_contract.address;
+    let ip_result = scenario.process(EventType::IP, 1100)?;
+    println!("IP result: {:?}", ip_result);
 
-    // 4. Initialize the token (this depends on your token’s “init” signature)
-    simulator.call_contract::<(), _>(
-        token_address,
-        "init",
-        ("TestToken", "TT"),
-        10_000_000
-    )?;
+    let pr_result = scenario.process(EventType::PR, 1200)?;
+    println!("PR result: {:?}", pr_result);
 
-    // 5. Optionally mint tokens to a user for testing
-    let alice = Address::new([1; 33]);
-    simulator.set_actor(alice);
-    simulator.call_contract::<(), _>(
-        token_address,
-        "mint",
-        (alice, 1_000_000u64),
-        10_000_000
-    )?;
+    assert_eq!(scenario.process(EventType::MD, 1300)?, Some(500_000));
 
-    // 6. Build or Borsh-serialize some minimal `ContractTerms`
-    let terms_bytes = create_pam_terms(token_address); // see below
+    let final_state = scenario.state()?;
+    assert_eq!(final_state.notional_principal, 0);
+    assert_eq!(final_state.accrued_interest, 0);
 
-    // 7. Initialize the ACTUS contract
-    //    “init” matches your contract’s `init(context, contract_type, contract_role, currency, terms)`
-    simulator.call_contract::<(), _>(
-        contract_address,
-        "init",
-        (
-            ContractType::PAM as u8, // or your numeric code for PAM
-            0u8,                     // contract_role if needed
-            token_address,           // currency
-            terms_bytes,             // Borsh-serialized ContractTerms
-        ),
-        10_000_000
-    )?;
+    Ok(())
+}
 
-    // 8. Helper function to process events
-    fn process_event(
-        sim: &Simulator<SimpleState>,
-        contract_addr: Address,
-        evt_type: EventType,
-        timestamp: u64,
-    ) -> Result<Option<Units>, SimError> {
-        // This calls “process_event(u8, u64)” with the event type + timestamp
-        sim.call_contract::<Option<Units>, _>(
-            contract_addr,
-            "process_event",
-            (evt_type as u8, timestamp),
-            10_000_000
-        )
+#[test]
+fn off_chain_run_matches_on_chain_processing() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+
+    let projected = lifecycle::run(&scenario.terms, &()).expect("lifecycle run failed");
+    assert!(!projected.is_empty());
+
+    for event in projected {
+        assert_eq!(
+            scenario.call::<Option<(EventType, u64)>, _>("get_next_event", ())?,
+            Ok(Some((event.event_type, event.time)))
+        );
+        assert_eq!(scenario.process(event.event_type, event.time)?, event.payoff);
+        assert_eq!(scenario.state()?, event.state);
     }
 
-    // 9. Now we can trigger events (IED at t=1000, IP at t=1100, etc.)
-    //    This is an example—adapt to your actual logic
-    let ied_result = process_event(&simulator, contract_address, EventType::IED, 1000)?;
-    println!("IED result: {:?}", ied_result);
+    assert_eq!(scenario.call::<Option<(EventType, u64)>, _>("get_next_event", ())?, Ok(None));
 
-    let ip_result = process_event(&simulator, contract_address, EventType::IP, 1100)?;
-    println!("IP result: {:?}", ip_result);
+    Ok(())
+}
 
-    let pr_result = process_event(&simulator, contract_address, EventType::PR, 1200)?;
-    println!("PR result: {:?}", pr_result);
+#[test]
+fn schedule_view_matches_the_off_chain_schedule() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 5, 31);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(math::timestamp_from_civil(2024, 12, 31)),
+        status_date: ied,
+        cycle_anchor_date_of_interest_payment: Some(ied),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        schedule_config: ScheduleConfig {
+            calendar: Some(Calendar::CLDR_MF),
+            end_of_month_convention: Some(EndOfMonthConvention::EOMC_EOM),
+            business_day_convention: Some(BusinessDayConvention::BDC_CSMF),
+        },
+        ..pam_terms()
+    };
+    let mut state = SimpleState::new();
+    let scenario = Scenario::builder().terms(terms.clone()).build(&mut state)?;
 
-    let md_result = process_event(&simulator, contract_address, EventType::MD, 1300)?;
-    println!("MD result: {:?}", md_result);
+    let schedule = scenario
+        .call::<Vec<(EventType, ShiftedDay)>, _>("get_schedule", ())?
+        .expect("get_schedule failed");
+    assert_eq!(Ok(schedule.clone()), event_schedule(&terms));
 
-    // 10. Query final state to check principal=0, interest=0, etc.
-    let final_state: ContractState = simulator.call_contract(
-        contract_address,
-        "get_state",
-        (),
-        10_000_000
-    )?;
-    println!("Final contract state: {:?}", final_state);
+    // Sunday 2024-06-30 is paid on the Friday before and calculated on the day
+    let june = ShiftedDay::new(math::timestamp_from_civil(2024, 6, 28), ied + 30 * SECONDS_PER_DAY);
+    assert_eq!(schedule[1], (EventType::IP, june));
+    let unadjusted: Vec<_> = schedule
+        .iter()
+        .map(|(event, day)| (*event, day.calculation_day))
+        .collect();
+    assert_eq!(Ok(unadjusted), lifecycle::schedule(&terms));
 
-    // 11. Asserts
-    assert_eq!(final_state.notional_principal, 0);
-    assert_eq!(final_state.accrued_interest, 0);
+    Ok(())
+}
+
+#[test]
+fn get_terms_reassembles_every_group() -> Result<(), SimError> {
+    let terms = ContractTerms {
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(1100),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_D,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+        market_object_code_of_rate_reset: Some("USD-SOFR-3M".to_string()),
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let scenario = Scenario::builder().terms(terms.clone()).build(&mut state)?;
+
+    assert_eq!(scenario.call::<ContractTerms, _>("get_terms", ())?, Ok(terms));
 
     Ok(())
 }
 
-/// Example function to create minimal “PAM” terms and Borsh-serialize them
-fn create_pam_terms(settlement_currency: Address) -> Vec<u8> {
-    use borsh::BorshSerialize;
+// an IP reads the principal and rate terms only, so cycles and options it never looks
+// at do not add to its cost
+#[test]
+fn interest_payment_skips_unread_terms() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let quarterly = Cycle {
+        n: 3,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    };
+    let plain = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(90 * day),
+        cycle_of_interest_payment: Some(quarterly.clone()),
+        status_date: 0,
+        ..pam_terms()
+    };
+    let scheduled = ContractTerms {
+        cycle_anchor_date_of_fee: Some(90 * day),
+        cycle_of_fee: Some(quarterly.clone()),
+        cycle_anchor_date_of_rate_reset: Some(90 * day),
+        cycle_of_rate_reset: Some(quarterly),
+        prepayment_effect: Some(PrepaymentEffect::PPEF_N),
+        termination_date: Some(300 * day),
+        price_at_termination_date: Some(1),
+        ..plain.clone()
+    };
+
+    let mut units = Vec::new();
+    for terms in [plain, scheduled] {
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+        scenario.process(EventType::IED, 0)?;
+        scenario.process(EventType::IP, 90 * day)?;
+        units.push(scenario.simulator.last_call_units());
+    }
+
+    // units vary by a few thousand between runs, well below a storage read
+    assert!(units[0].abs_diff(units[1]) < 5_000, "{units:?}");
+
+    Ok(())
+}
 
+#[test]
+fn settlement_transfers_reconcile_with_payoffs() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
     let terms = ContractTerms {
-        // Fill in the fields your “init” logic or “transitions” code expects
-        contract_id: "pam-contract".to_string(),
-        contract_type: ContractType::PAM,
-        contract_role: ContractRole::CR_RPA, 
-        settlement_currency: Some(settlement_currency.as_ref().to_vec()),
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
 
-        // e.g. a simple scenario
-        initial_exchange_date: Some(1000),
-        notional_principal: Some(500_000),
-        nominal_interest_rate: Some(50_000), // 5% in basis points
-        maturity_date: Some(1300),
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let parties = [scenario.creditor, scenario.debtor];
+    let token = scenario.token;
 
-        // fill other fields as needed or default them
-        status_date: 1000, // or context.timestamp at init
-        schedule_config: ScheduleConfig {
-            calendar: None,
-            end_of_month_convention: None,
-            business_day_convention: None,
-        },
+    let mut payoffs = 0;
+    let mut transfers = Vec::new();
+
+    // quarterly interest, with the accrual of the last quarter paid at maturity
+    for (event, days) in [
+        (EventType::IED, 0),
+        (EventType::IP, 90),
+        (EventType::IP, 180),
+        (EventType::IP, 270),
+        (EventType::MD, 365),
+    ] {
+        let time = days * day;
+        payoffs += scenario.process(event, time)?.unwrap_or_default();
+
+        // the receipt token logs its own mints and burns, so only the currency is kept
+        transfers.extend(
+            scenario
+                .simulator
+                .take_logs()
+                .into_iter()
+                .filter(|log| log.contract == token)
+                .map(|log| borsh::from_slice::<TransferLog>(&log.data).expect("invalid log")),
+        );
+    }
+
+    let is_party = |address: Option<Address>| address.is_some_and(|a| parties.contains(&a));
+    assert!(transfers.iter().all(|log| is_party(log.from) && is_party(log.to)));
+    assert_eq!(transfers.len(), 5);
+    assert_eq!(
+        transfers.iter().map(|log| SignedUnits::from(log.amount)).sum::<SignedUnits>(),
+        payoffs
+    );
+    scenario.assert_conservation(&parties)?;
+
+    Ok(())
+}
+
+#[test]
+fn unfunded_payment_leaves_the_event_due() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+    let mallory = actors().mallory.address;
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    scenario.process(EventType::IED, 0)?;
+
+    // bob's whole balance leaves before the first coupon
+    let drained = scenario.token_balance(bob)?;
+    send_tokens(&mut scenario, bob, mallory, drained)?;
+
+    let before = scenario.state()?;
+    let due = scenario.is_event_due(EventType::IP, 90 * day)?;
+    let mut funded = scenario.fork();
+    send_tokens(&mut funded, mallory, bob, drained)?;
+    let interest = funded.process(EventType::IP, 90 * day)?.expect("coupon");
+
+    assert_eq!(
+        scenario.try_process(EventType::IP, 90 * day)?,
+        Err(ActusError::InsufficientFunds {
+            required: interest.unsigned_abs(),
+            available: 0,
+        })
+    );
+
+    // nothing moved or was recorded, so the coupon is still owed in full
+    assert_eq!(scenario.state()?, before);
+    assert_eq!(scenario.is_event_due(EventType::IP, 90 * day)?, due);
+    assert_eq!(
+        history(&scenario)?
+            .iter()
+            .map(|record| record.event_type)
+            .collect::<Vec<_>>(),
+        [EventType::IED]
+    );
+    assert_eq!(scenario.token_balance(bob)?, 0);
+
+    // once funded again the same coupon settles
+    send_tokens(&mut scenario, mallory, bob, drained)?;
+    assert_eq!(scenario.process(EventType::IP, 90 * day)?, Some(interest));
+    assert_eq!(last_record(&scenario)?.event_type, EventType::IP);
+    scenario.assert_conservation(&[alice, bob, mallory])?;
+
+    Ok(())
+}
+
+/// A PAM whose first coupon, 6_232 on day 91, bob cannot pay, with five days of grace
+/// and a 10% delinquency rate
+fn shortfall_scenario(
+    state: &mut SimpleState,
+    policy: SettlementPolicy,
+) -> Result<Scenario<'_>, SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        grace_period: Some(5 * day),
+        delinquency_rate: Some(1_000),
+        settlement_policy: Some(policy),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut scenario = Scenario::builder().terms(terms).build(state)?;
+    scenario.process(EventType::IED, 0)?;
+    Ok(scenario)
+}
+
+/// Moves `amount` of the settlement currency from `from` to `to`, leaving the creditor
+/// as the actor
+fn send_tokens(
+    scenario: &mut Scenario<'_>,
+    from: Address,
+    to: Address,
+    amount: u64,
+) -> Result<(), SimError> {
+    scenario.simulator.set_actor(from);
+    let sent = scenario
+        .simulator
+        .call_contract::<(), _>(scenario.token, "transfer", (to, amount), MAX_GAS);
+    scenario.simulator.set_actor(scenario.creditor);
+    sent
+}
+
+fn obligations(scenario: &Scenario) -> Result<Vec<Obligation>, SimError> {
+    scenario
+        .call("get_outstanding_obligations", ())
+        .map(|obligations| obligations.expect("get_outstanding_obligations failed"))
+}
+
+#[test]
+fn unpaid_coupon_is_owed_until_settled() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let mallory = actors().mallory.address;
+    let mut state = SimpleState::new();
+    let mut scenario = shortfall_scenario(&mut state, SettlementPolicy::RecordShortfall)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    let drained = scenario.token_balance(bob)?;
+    send_tokens(&mut scenario, bob, mallory, drained)?;
+
+    // the coupon is committed with nothing moved, and owed from its date
+    let creditor_before = scenario.token_balance(alice)?;
+    assert_eq!(scenario.process(EventType::IP, 91 * day)?, Some(6_232));
+    assert_eq!(scenario.token_balance(alice)?, creditor_before);
+    assert_eq!(last_record(&scenario)?.event_type, EventType::IP);
+    assert_eq!(
+        obligations(&scenario)?,
+        [Obligation { amount: 6_232, event: EventType::IP, due_date: 91 * day }]
+    );
+    let state = scenario.state()?;
+    assert_eq!((state.accrued_interest, state.overdue_amount), (0, 0));
+    assert_eq!(state.overdue_since, Some(91 * day));
+    assert_eq!(state.contract_performance, ContractPerformance::DL);
+
+    // delinquent once the grace period lapses
+    scenario.process(EventType::AD, 97 * day)?;
+    assert_eq!(scenario.state()?.contract_performance, ContractPerformance::DQ);
+
+    // settled ten days late with 17 of delinquency once bob is funded again
+    send_tokens(&mut scenario, mallory, bob, drained)?;
+    scenario.simulator.set_timestamp(101 * day);
+    let result = scenario.call::<Units, _>("settle_obligation", 1u32)?;
+    assert!(matches!(result, Err(ActusError::ValidationError(_))));
+    scenario.simulator.set_actor(mallory);
+    let result = scenario.call::<Units, _>("settle_obligation", 0u32)?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    scenario.simulator.set_actor(bob);
+    let result = scenario.call::<Units, _>("settle_obligation", 0u32)?;
+    assert_eq!(result, Ok(6_232 + 17));
+    assert_eq!(scenario.token_balance(alice)?, creditor_before + 6_232 + 17);
+    assert!(obligations(&scenario)?.is_empty());
+    let state = scenario.state()?;
+    assert_eq!(state.overdue_since, None);
+    assert_eq!(state.contract_performance, ContractPerformance::PF);
+    scenario.assert_conservation(&[alice, bob, mallory])?;
+
+    Ok(())
+}
+
+#[test]
+fn partial_settlement_owes_only_the_rest() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let mallory = actors().mallory.address;
+    let mut state = SimpleState::new();
+    let mut scenario = shortfall_scenario(&mut state, SettlementPolicy::SettlePartially)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    // bob keeps 2_000 of the coupon
+    let drained = scenario.token_balance(bob)? - 2_000;
+    send_tokens(&mut scenario, bob, mallory, drained)?;
+
+    let creditor_before = scenario.token_balance(alice)?;
+    assert_eq!(scenario.process(EventType::IP, 91 * day)?, Some(6_232));
+    assert_eq!(scenario.token_balance(alice)?, creditor_before + 2_000);
+    assert_eq!(scenario.token_balance(bob)?, 0);
+    assert_eq!(
+        obligations(&scenario)?,
+        [Obligation { amount: 4_232, event: EventType::IP, due_date: 91 * day }]
+    );
+
+    // a later coupon bob can pay in full leaves the contract overdue
+    send_tokens(&mut scenario, mallory, bob, drained)?;
+    assert!(scenario.process(EventType::IP, 182 * day)?.is_some());
+    assert_eq!(scenario.state()?.contract_performance, ContractPerformance::DQ);
+    assert_eq!(obligations(&scenario)?.len(), 1);
+
+    // settled with the second coupon, 91 days late, with 105 of delinquency
+    let result = scenario.call::<Units, _>("settle_obligation", 0u32)?;
+    assert_eq!(result, Ok(4_232 + 105));
+    assert!(obligations(&scenario)?.is_empty());
+    assert_eq!(scenario.state()?.contract_performance, ContractPerformance::PF);
+    scenario.assert_conservation(&[alice, bob, mallory])?;
+
+    Ok(())
+}
+
+#[test]
+fn results_name_the_side_paying_and_the_state_left() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms.clone()).build(&mut state)?;
+
+    let exchange = scenario.try_process_v2(EventType::IED, 0)?.unwrap();
+    assert_eq!(
+        exchange,
+        ProcessEventResult {
+            event: EventType::IED,
+            timestamp: 0,
+            payoff: Some((1_000_000, PayoffDirection::CreditorToDebtor)),
+            notional_after: 1_000_000,
+            accrued_after: 0,
+        }
+    );
+
+    let accrual = scenario.try_process_v2(EventType::AD, 180 * day)?.unwrap();
+    let state = scenario.state()?;
+    assert_eq!(accrual.payoff, None);
+    assert_eq!(accrual.notional_after, state.notional_principal);
+    assert_eq!(accrual.accrued_after, state.accrued_interest);
+    assert!(accrual.accrued_after > 0);
+
+    // the old entrypoint keeps the payoff signed by event
+    let mut expected = ContractState::new(&terms);
+    for (event, time) in [(EventType::IED, 0), (EventType::AD, 180 * day)] {
+        TransitionEngine::process_event(event, time, &mut expected, &terms).unwrap();
+    }
+    let repaid = TransitionEngine::process_event(EventType::MD, 365 * day, &mut expected, &terms);
+
+    scenario.simulator.set_timestamp(365 * day);
+    let maturity: Option<SignedUnits> =
+        scenario.try_call("process_event", (EventType::MD as u8, 365 * day))?;
+    assert_eq!(Ok(maturity), repaid);
+    assert!(maturity.is_some_and(|payoff| payoff > 1_000_000));
+    let state = scenario.state()?;
+    assert_eq!((state.notional_principal, state.accrued_interest), (0, 0));
+
+    Ok(())
+}
+
+#[test]
+fn simulated_events_match_processing_them() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    scenario.process(EventType::IED, 0)?;
+
+    let simulate = |scenario: &Scenario, event: EventType, amount, observation| {
+        scenario.call::<ProcessEventResult, _>(
+            "simulate_event",
+            (event as u8, 180 * day, amount, observation),
+        )
+    };
+    let history = |scenario: &Scenario| {
+        scenario
+            .call::<Vec<EventRecord>, _>("get_event_history", (0u32, 10u32))
+            .map(|history| history.expect("get_event_history failed"))
+    };
+
+    scenario.simulator.set_timestamp(180 * day);
+    let before = (scenario.state()?, history(&scenario)?);
+    let balances = (scenario.token_balance(alice)?, scenario.token_balance(bob)?);
+
+    let simulated = simulate(&scenario, EventType::IP, None::<Units>, None::<u64>)?;
+    assert!(simulated.as_ref().is_ok_and(|result| result.payoff.is_some()));
+    assert_eq!((scenario.state()?, history(&scenario)?), before);
+    assert_eq!((scenario.token_balance(alice)?, scenario.token_balance(bob)?), balances);
+
+    // errors are those of processing, checked as the same actor
+    scenario.simulator.set_actor(actors().mallory.address);
+    assert_eq!(
+        simulate(&scenario, EventType::IP, None, None)?,
+        Err(ActusError::NotAuthorized)
+    );
+    scenario.simulator.set_actor(alice);
+    assert_eq!(
+        simulate(&scenario, EventType::IP, None, Some(500))?,
+        Err(ActusError::ValidationError("IP reads no market observation".into()))
+    );
+    scenario.simulator.set_actor(bob);
+    assert!(matches!(
+        simulate(&scenario, EventType::IP, Some(1_000), None)?,
+        Err(ActusError::ValidationError(_))
+    ));
+
+    scenario.simulator.set_actor(alice);
+    let processed = scenario.try_process_v2(EventType::IP, 180 * day)?;
+    assert_eq!(borsh::to_vec(&simulated).unwrap(), borsh::to_vec(&processed).unwrap());
+    assert_ne!(scenario.state()?, before.0);
+
+    Ok(())
+}
+
+#[test]
+fn terms_are_amended_before_the_initial_exchange() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(30 * day),
+        maturity_date: Some(395 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: Some(120 * day),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 3,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: 0,
+        ..pam_terms()
+    };
+    let amended = ContractTerms {
+        nominal_interest_rate: Some(800),
+        ..terms.clone()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    let amend = |scenario: &Scenario, terms: &ContractTerms| {
+        scenario.call::<(), _>("amend_terms", terms.to_versioned_bytes())
+    };
+
+    scenario.simulator.set_actor(bob);
+    assert_eq!(amend(&scenario, &amended)?, Err(ActusError::NotAuthorized));
+    scenario.simulator.set_actor(alice);
+    let retyped = ContractTerms {
+        contract_type: ContractType::LAM,
+        ..amended.clone()
+    };
+    assert!(matches!(amend(&scenario, &retyped)?, Err(ActusError::ValidationError(_))));
+
+    amend(&scenario, &amended)?.expect("amend_terms failed");
+    assert_eq!(scenario.state()?, ContractState::new(&amended));
+    let stored = scenario.call::<ContractTerms, _>("get_terms", ())?;
+    assert_eq!(stored.map(|terms| terms.nominal_interest_rate), Ok(Some(800)));
+
+    // the payoffs of the lifecycle are those of the amended rate, not the original one
+    let cashflows = |terms: &ContractTerms| -> Vec<_> {
+        let events = lifecycle::run(terms, &()).expect("the terms run");
+        events.iter().map(|event| (event.event_type, event.time, event.payoff)).collect()
+    };
+    let log = scenario.drive(395 * day)?;
+    let processed: Vec<_> =
+        log.iter().map(|event| (event.event, event.time, event.payoff)).collect();
+    assert_eq!(processed, cashflows(&amended));
+    assert_ne!(processed, cashflows(&scenario.terms));
+
+    let result = amend(&scenario, &amended)?;
+    assert!(matches!(result, Err(ActusError::StateError(_))));
+
+    Ok(())
+}
+
+fn propose(
+    scenario: &mut Scenario,
+    proposer: Address,
+    amendment: &Amendment,
+    expires_at: u64,
+) -> Result<Result<(), ActusError>, SimError> {
+    scenario.simulator.set_actor(proposer);
+    scenario.call("propose_amendment", (amendment.clone(), expires_at))
+}
+
+fn accept(scenario: &mut Scenario, party: Address) -> Result<Result<bool, ActusError>, SimError> {
+    scenario.simulator.set_actor(party);
+    scenario.call("accept_amendment", ())
+}
+
+type Proposal = Option<(Address, Amendment, u64)>;
+
+fn proposal(scenario: &Scenario) -> Result<Result<Proposal, ActusError>, SimError> {
+    scenario.call("amendment_proposal", ())
+}
+
+fn history(scenario: &Scenario) -> Result<Vec<EventRecord>, SimError> {
+    scenario
+        .call("get_event_history", (0u32, 100u32))
+        .map(|history| history.expect("get_event_history failed"))
+}
+
+fn last_record(scenario: &Scenario) -> Result<EventRecord, SimError> {
+    Ok(history(scenario)?.pop().expect("no history"))
+}
+
+#[test]
+fn accepted_rate_cut_splits_the_accrual() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+    let cut = Amendment {
+        rate: Some(300),
+        ..Default::default()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms.clone()).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    // only live contracts are amended
+    scenario.simulator.set_timestamp(0);
+    let early = propose(&mut scenario, bob, &cut, 60 * day)?;
+    assert!(matches!(early, Err(ActusError::StateError(_))));
+    scenario.process(EventType::IED, 0)?;
+
+    scenario.simulator.set_timestamp(30 * day);
+    propose(&mut scenario, bob, &cut, 60 * day)?.expect("propose_amendment failed");
+    let pending = propose(&mut scenario, alice, &cut, 60 * day)?;
+    assert!(matches!(pending, Err(ActusError::StateError(_))));
+    assert_eq!(accept(&mut scenario, bob)?, Err(ActusError::NotAuthorized));
+    assert_eq!(proposal(&scenario)?, Ok(Some((bob, cut.clone(), 60 * day))));
+
+    // accepted mid-coupon: the interest to then accrues at the old rate
+    scenario.simulator.set_timestamp(45 * day);
+    assert_eq!(accept(&mut scenario, alice)?, Ok(true));
+    let record = last_record(&scenario)?;
+    assert_eq!((record.event_type, record.timestamp), (EventType::AMD, 45 * day));
+    let stored = scenario.call::<ContractTerms, _>("get_terms", ())?;
+    assert_eq!(stored.map(|terms| terms.nominal_interest_rate), Ok(Some(300)));
+
+    let (mut expected, mut amended) = (ContractState::new(&terms), terms.clone());
+    TransitionEngine::process_event(EventType::IED, 0, &mut expected, &amended).unwrap();
+    let mut unamended = expected.clone();
+    TransitionEngine::amend(&mut expected, &mut amended, &cut, 45 * day).unwrap();
+    let interest =
+        TransitionEngine::process_event(EventType::IP, 90 * day, &mut expected, &amended);
+    let full = TransitionEngine::process_event(EventType::IP, 90 * day, &mut unamended, &terms);
+
+    let payoff = scenario.process(EventType::IP, 90 * day)?;
+    assert_eq!(Ok(payoff), interest);
+    assert!(payoff < full.unwrap());
+    assert_eq!(scenario.state()?, expected);
+
+    // the amendment is replayed from the state it left
+    let last = u32::try_from(history(&scenario)?.len() - 1).unwrap();
+    assert_eq!(state_at(&scenario, last, None)?, Ok(expected));
+
+    Ok(())
+}
+
+#[test]
+fn expired_amendment_changes_nothing() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+    let extension = Amendment {
+        maturity: Some(730 * day),
         ..Default::default()
     };
 
-    terms.try_to_vec().unwrap()
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    scenario.process(EventType::IED, 0)?;
+    let stored_terms = |scenario: &Scenario| {
+        scenario
+            .call::<ContractTerms, _>("get_terms", ())
+            .map(|terms| terms.expect("get_terms failed"))
+    };
+    let before = (scenario.state()?, stored_terms(&scenario)?);
+
+    scenario.simulator.set_timestamp(30 * day);
+    propose(&mut scenario, alice, &extension, 40 * day)?.expect("propose_amendment failed");
+
+    // accepted too late, it is closed at its expiry instead
+    scenario.simulator.set_timestamp(50 * day);
+    assert_eq!(proposal(&scenario)?, Ok(None));
+    assert_eq!(accept(&mut scenario, bob)?, Ok(false));
+    assert_eq!((scenario.state()?, stored_terms(&scenario)?), before);
+
+    let record = last_record(&scenario)?;
+    assert_eq!((record.event_type, record.timestamp), (EventType::AMX, 40 * day));
+    assert!(record.processed_at > record.timestamp);
+    assert!(matches!(accept(&mut scenario, bob)?, Err(ActusError::StateError(_))));
+
+    // a rejection is recorded before the expiry
+    propose(&mut scenario, alice, &extension, 60 * day)?.expect("propose_amendment failed");
+    scenario.simulator.set_actor(bob);
+    scenario.call::<(), _>("reject_amendment", ())?.expect("reject_amendment failed");
+    let record = last_record(&scenario)?;
+    assert_eq!(record.event_type, EventType::AMX);
+    assert!(record.processed_at <= record.timestamp);
+    assert_eq!((scenario.state()?, stored_terms(&scenario)?), before);
+
+    Ok(())
+}
+
+/// Deploys the mock hook and registers it as the creator of the scenario's contract
+fn register_hook(scenario: &mut Scenario, revert_on_failure: bool) -> Result<Address, SimError> {
+    let hook = scenario.simulator.create_contract(actus_hook::contract_path())?.address;
+    scenario.simulator.set_actor(scenario.creditor);
+    scenario
+        .call::<(), _>("set_hook", (Some(hook), revert_on_failure))?
+        .expect("set_hook failed");
+    Ok(hook)
+}
+
+fn hook_calls(scenario: &Scenario, hook: Address) -> Result<Vec<actus_hook::Call>, SimError> {
+    scenario.simulator.call_contract(hook, "calls", (), MAX_GAS)
+}
+
+#[test]
+fn hooks_are_notified_of_recorded_events() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+
+    scenario.simulator.set_actor(scenario.debtor);
+    let result = scenario.call::<(), _>("set_hook", (Some(scenario.token), false))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    let hook = register_hook(&mut scenario, false)?;
+    assert_eq!(scenario.call("hook", ())?, Ok(Some((hook, false))));
+
+    scenario.process(EventType::IED, 1000)?;
+    scenario.process(EventType::IP, 1100)?;
+    scenario.process(EventType::MD, 1300)?;
+
+    let history = history(&scenario)?;
+    assert_eq!(history.len(), 3);
+    let id = scenario.terms.contract_id;
+    let notified: Vec<_> = history
+        .iter()
+        .map(|record| (scenario.contract, id, record.event_type, record.timestamp, record.payoff))
+        .collect();
+    assert_eq!(hook_calls(&scenario, hook)?, notified);
+
+    Ok(())
+}
+
+#[test]
+fn failing_hooks_are_ignored_or_revert_the_event() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let hook = register_hook(&mut scenario, false)?;
+    scenario.simulator.call_contract::<(), _>(hook, "set_failing", true, MAX_GAS)?;
+
+    // the event stands when the hook's failure is ignored
+    scenario.process(EventType::IED, 1000)?;
+    assert_eq!(scenario.state()?.phase, ContractPhase::Active);
+    assert_eq!(hook_calls(&scenario, hook)?, []);
+
+    scenario.simulator.set_actor(scenario.creditor);
+    scenario
+        .call::<(), _>("set_hook", (Some(hook), true))?
+        .expect("set_hook failed");
+
+    // else the call aborts, and the chain discards what it wrote, which the simulator
+    // keeps
+    scenario.simulator.set_timestamp(1100);
+    let result =
+        scenario.call::<ProcessEventResult, _>("process_event_v2", (EventType::IP as u8, 1100u64));
+    assert!(matches!(result, Err(SimError::CallContract(_))), "{result:?}");
+    assert_eq!(hook_calls(&scenario, hook)?, []);
+
+    scenario.simulator.call_contract::<(), _>(hook, "set_failing", false, MAX_GAS)?;
+    scenario.process(EventType::MD, 1300)?;
+    let calls = hook_calls(&scenario, hook)?;
+    assert_eq!(calls.iter().map(|call| call.2).collect::<Vec<_>>(), [EventType::MD]);
+
+    Ok(())
+}
+
+#[test]
+fn events_without_a_hook_cost_no_call() -> Result<(), SimError> {
+    let mut plain_state = SimpleState::new();
+    let mut plain = Scenario::builder().build(&mut plain_state)?;
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+
+    // a removed hook leaves nothing to call
+    register_hook(&mut scenario, true)?;
+    scenario
+        .call::<(), _>("set_hook", (None::<Address>, false))?
+        .expect("set_hook failed");
+    assert_eq!(scenario.call("hook", ())?, Ok(None::<(Address, bool)>));
+
+    // the contracts only differ by their addresses, which move the fuel by a few units
+    let units = |scenario: &Scenario| scenario.simulator.last_call_units();
+    plain.process(EventType::IED, 1000)?;
+    scenario.process(EventType::IED, 1000)?;
+    assert!(units(&scenario).abs_diff(units(&plain)) * 1_000 < units(&plain));
+
+    register_hook(&mut scenario, true)?;
+    plain.process(EventType::IP, 1100)?;
+    scenario.process(EventType::IP, 1100)?;
+    assert!(units(&scenario) > units(&plain) * 101 / 100);
+
+    Ok(())
+}
+
+/// The fixture mints to the debtor mid-life, standing in for a settlement bug that
+/// creates tokens
+#[test]
+#[should_panic(expected = "tokens were created or destroyed")]
+fn conservation_catches_created_tokens() {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state).unwrap();
+    let parties = [scenario.creditor, scenario.debtor];
+    scenario.process(EventType::IED, 1000).unwrap();
+    scenario.assert_conservation(&parties).unwrap();
+
+    scenario.simulator.set_actor(scenario.token_owner);
+    scenario
+        .simulator
+        .call_contract::<(), _>(scenario.token, "mint", (scenario.debtor, 1u64), MAX_GAS)
+        .unwrap();
+    scenario.simulator.set_actor(scenario.creditor);
+    scenario.process(EventType::MD, 1300).unwrap();
+
+    scenario.assert_conservation(&parties).unwrap();
+}
+
+#[test]
+fn test_lam_integration() -> Result<(), SimError> {
+    let cycle = |n, p| Cycle {
+        n,
+        p,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    };
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let maturity = math::timestamp_from_civil(2025, 5, 1);
+
+    // 30E/360 makes every month a twelfth of a year, so interest only changes with notional
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(maturity),
+        notional_principal: Some(500_000),
+        nominal_interest_rate: Some(600),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: Some(math::timestamp_from_civil(2024, 2, 1)),
+        cycle_of_interest_payment: Some(cycle(1, Period::P_M)),
+        cycle_anchor_date_of_principal_redemption: Some(math::timestamp_from_civil(2024, 4, 1)),
+        cycle_of_principal_redemption: Some(cycle(1, Period::P_Q)),
+        next_principal_redemption_payment: Some(100_000),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::LAM)
+        .build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    let alice_before = scenario.token_balance(alice)?;
+    let bob_before = scenario.token_balance(bob)?;
+
+    let log = scenario.drive(maturity)?;
+    let (exchange, log) = log.split_first().expect("the IED is scheduled");
+    assert_eq!((exchange.event, exchange.time), (EventType::IED, ied));
+    assert_eq!(exchange.payoff, Some(500_000));
+
+    // a twelfth of a year is 833 basis points, on a notional stepping down by 100_000
+    // after the IP of every quarter
+    let expected_interest = [500_000, 400_000, 300_000, 200_000, 100_000]
+        .into_iter()
+        .flat_map(|notional| [notional * 600 * 833 / 100_000_000; 3]);
+    let mut log = log.iter();
+
+    for (month, expected) in (1..).zip(expected_interest) {
+        let date = math::add_months(ied, month).unwrap();
+
+        let interest = log.next().expect("an IP every month");
+        assert_eq!((interest.event, interest.time), (EventType::IP, date), "month {month}");
+        assert_eq!(interest.payoff, Some(expected), "IP after {month} months");
+
+        if month % 3 == 0 {
+            let redemption = log.next().expect("a PR every quarter");
+            assert_eq!((redemption.event, redemption.time), (EventType::PR, date));
+            assert_eq!(redemption.payoff, Some(100_000), "PR after {month} months");
+        }
+    }
+    assert_eq!(log.next(), None);
+
+    // nothing is scheduled once the principal is repaid, but the MD still closes the
+    // contract. Fully amortized, it pays no residual principal and no interest accrued
+    // since the last PR.
+    assert_eq!(scenario.next_event()?, None);
+    assert_eq!(scenario.process(EventType::MD, maturity)?, Some(0));
+
+    let final_state = scenario.state()?;
+    assert_eq!(final_state.notional_principal, 0);
+    assert_eq!(final_state.accrued_interest, 0);
+    assert_eq!(final_state.status_date, maturity);
+
+    // the principal went out and came back, so each balance moved by the interest alone
+    let interest = 3 * (2_499 + 1_999 + 1_499 + 999 + 499);
+    assert_eq!(scenario.token_balance(alice)?, alice_before + interest);
+    assert_eq!(scenario.token_balance(bob)?, bob_before - interest);
+    scenario.assert_conservation(&[alice, bob])?;
+
+    Ok(())
+}
+
+#[test]
+fn lam_accrues_on_its_interest_calculation_base() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(730 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(1_000),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        next_principal_redemption_payment: Some(500_000),
+        interest_calculation_base: Some(IPCB::IPCB_NTIED),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::LAM)
+        .build(&mut state)?;
+
+    assert_eq!(scenario.process(EventType::IED, 0)?, Some(1_000_000));
+    assert_eq!(scenario.process(EventType::IP, 365 * day)?, Some(100_000));
+    assert_eq!(scenario.process(EventType::PR, 365 * day)?, Some(500_000));
+
+    // the base stays at the notional exchanged at the IED, so the second year accrues
+    // as much interest as the first
+    let state = scenario.state()?;
+    assert_eq!((state.notional_principal, state.interest_calculation_base), (500_000, 1_000_000));
+    assert_eq!(scenario.process(EventType::MD, 730 * day)?, Some(500_000 + 100_000));
+
+    Ok(())
+}
+
+#[test]
+fn nam_installments_short_of_the_interest_grow_the_notional() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 12),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(1_200),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_principal_redemption: math::add_months(ied, 1),
+        cycle_of_principal_redemption: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        next_principal_redemption_payment: Some(5_000),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::NAM)
+        .build(&mut state)?;
+
+    let log = scenario.drive(math::add_months(ied, 6).unwrap())?;
+    let (exchange, installments) = log.split_first().expect("the IED is scheduled");
+    assert_eq!(exchange.state.notional_principal, 1_000_000);
+    assert_eq!(installments.len(), 6);
+
+    // each installment of 5_000 pays part of a month's interest at 12%, the rest of
+    // which is added to the notional
+    let mut notional = 1_000_000;
+    for (month, installment) in (1..).zip(installments) {
+        let date = math::add_months(ied, month).unwrap();
+        assert_eq!((installment.event, installment.time), (EventType::PR, date));
+        assert_eq!(installment.payoff, Some(5_000), "PR after {month} months");
+
+        let interest = notional * 1_200 * 833 / 100_000_000;
+        assert!(interest > 5_000);
+        notional += interest - 5_000;
+        assert_eq!(installment.state.notional_principal, notional, "PR after {month} months");
+    }
+
+    let totals = scenario.state()?;
+    assert_eq!((totals.total_interest_paid, totals.total_principal_repaid), (30_000, 0));
+
+    Ok(())
+}
+
+/// A PAM of 500_000 at 5% over nine months, paying interest and resetting its rate to the
+/// `SOFR` the oracle publishes every quarter, by no more than 1.5% at once and to no more
+/// than 7%
+fn resetting_pam() -> ContractTerms {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let quarterly = Cycle {
+        n: 3,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    };
+    ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 9),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 3),
+        cycle_of_interest_payment: Some(quarterly.clone()),
+        cycle_anchor_date_of_rate_reset: math::add_months(ied, 3),
+        cycle_of_rate_reset: Some(quarterly),
+        market_object_code_of_rate_reset: Some("SOFR".into()),
+        market_object_provider: Some(actors().oracle.contract_address()),
+        period_cap: Some(150),
+        life_cap: Some(700),
+        status_date: ied,
+        ..pam_terms()
+    }
+}
+
+#[test]
+fn rate_resets_read_the_rate_the_provider_publishes() -> Result<(), SimError> {
+    let terms = resetting_pam();
+    let ied = terms.initial_exchange_date.unwrap();
+    let [first, second, maturity] = [3, 6, 9].map(|months| math::add_months(ied, months).unwrap());
+
+    let Actors { oracle, mallory, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    scenario.process(EventType::IED, ied)?;
+    assert_eq!(scenario.process(EventType::IP, first)?, Some(6_250));
+
+    // a reset waits for the oracle, which alone publishes the rate, once
+    assert_eq!(
+        scenario.try_process(EventType::RR, first)?,
+        Err(ActusError::ValidationError("no observation of risk factor `SOFR`".into()))
+    );
+    scenario.simulator.set_actor(mallory.address);
+    let result = scenario.call::<(), _>("observe", (EventType::RR as u8, first, 100u64))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+    scenario.simulator.set_actor(scenario.creditor);
+    scenario.publish(EventType::RR, first, 900)?;
+    scenario.simulator.set_actor(oracle.address);
+    let result = scenario.call::<(), _>("observe", (EventType::RR as u8, first, 100u64))?;
+    assert!(matches!(result, Err(ActusError::StateError(_))));
+    scenario.simulator.set_actor(scenario.creditor);
+
+    // 9% is clamped to 1.5% over the 5% before, then to the 7% life cap
+    scenario.process(EventType::RR, first)?;
+    let reset = scenario.state()?;
+    assert_eq!((reset.nominal_interest_rate, reset.reference_rate), (650, Some(900)));
+    assert_eq!(scenario.process(EventType::IP, second)?, Some(8_125));
+    scenario.publish(EventType::RR, second, 900)?;
+    scenario.process(EventType::RR, second)?;
+    assert_eq!(scenario.state()?.nominal_interest_rate, 700);
+    assert_eq!(scenario.process(EventType::MD, maturity)?, Some(508_750));
+
+    // the states after the resets are stored, since replaying them would not read the rate
+    let reset = history(&scenario)?
+        .iter()
+        .rposition(|record| record.event_type == EventType::RR)
+        .unwrap();
+    let at_reset = state_at(&scenario, reset as u32, None)?.expect("get_state_at failed");
+    assert_eq!(at_reset.nominal_interest_rate, 700);
+
+    Ok(())
+}
+
+#[test]
+fn only_interest_bearing_contracts_reset_their_rate() -> Result<(), SimError> {
+    let terms = ContractTerms {
+        contract_type: ContractType::STK,
+        initial_exchange_date: None,
+        maturity_date: None,
+        notional_principal: None,
+        nominal_interest_rate: None,
+        purchase_date: Some(1000),
+        price_at_purchase_date: Some(40),
+        quantity: Some(1_000),
+        market_object_code_of_rate_reset: Some("SOFR".into()),
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    scenario.process(EventType::PRD, 1000)?;
+
+    assert_eq!(
+        scenario.try_process(EventType::RR, 1100)?,
+        Err(ActusError::EventNotApplicable {
+            event: EventType::RR,
+            contract_type: ContractType::STK,
+        })
+    );
+    // nor do events read observations a simulation makes up for them
+    let result = scenario.call::<ProcessEventResult, _>(
+        "simulate_event",
+        (EventType::PRD as u8, 1000u64, None::<Units>, Some(900u64)),
+    )?;
+    assert!(matches!(result, Err(ActusError::ValidationError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn swaps_pay_their_legs_net() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 3),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(300),
+        nominal_interest_rate2: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let Actors { alice, bob, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::SWPPV)
+        .build(&mut state)?;
+    let creditor = scenario.token_balance(alice.address)?;
+    let debtor = scenario.token_balance(bob.address)?;
+
+    // the notional is never exchanged
+    let log = scenario.drive(math::add_months(ied, 3).unwrap())?;
+    let (start, settlements) = log.split_first().expect("the IED is scheduled");
+    assert_eq!((start.event, start.payoff), (EventType::IED, Some(0)));
+    assert_eq!(start.state.notional_principal, 1_000_000);
+
+    // the floating leg of 4_165 a month outweighs the fixed one of 2_499, so the
+    // creditor pays the difference
+    let events: Vec<_> =
+        settlements.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::IP, Some(-1_666)),
+            (EventType::IP, Some(-1_666)),
+            (EventType::MD, Some(-1_666)),
+        ]
+    );
+
+    assert_eq!(scenario.token_balance(alice.address)?, creditor - 4_998);
+    assert_eq!(scenario.token_balance(bob.address)?, debtor + 4_998);
+    assert_eq!(scenario.phase()?, ContractPhase::Matured);
+
+    Ok(())
+}
+
+#[test]
+fn swaps_float_their_leg_at_the_published_rate() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let monthly = Cycle {
+        n: 1,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    };
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 3),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(300),
+        nominal_interest_rate2: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(monthly.clone()),
+        cycle_anchor_date_of_rate_reset: math::add_months(ied, 1),
+        cycle_of_rate_reset: Some(monthly),
+        market_object_code_of_rate_reset: Some("SOFR".into()),
+        market_object_provider: Some(actors().oracle.contract_address()),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::SWPPV)
+        .build(&mut state)?;
+    for months in [1, 2] {
+        scenario.publish(EventType::RR, math::add_months(ied, months).unwrap(), 200)?;
+    }
+
+    // the floating leg outweighs the fixed one until it resets to 2%, when the debtor
+    // pays the 833 the fixed leg of 2_499 a month exceeds it by
+    let log = scenario.drive(math::add_months(ied, 3).unwrap())?;
+    let events: Vec<_> = log.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::IED, Some(0)),
+            (EventType::IP, Some(-1_666)),
+            (EventType::RR, None),
+            (EventType::IP, Some(833)),
+            (EventType::RR, None),
+            (EventType::MD, Some(833)),
+        ]
+    );
+    let floating: Vec<_> = log
+        .iter()
+        .filter(|settled| settled.event == EventType::RR)
+        .map(|reset| reset.state.nominal_interest_rate)
+        .collect();
+    assert_eq!(floating, [200, 200]);
+
+    // the off-chain lifecycle observing the same rate pays the same
+    let mut risk_factors = RiskFactorSeries::new();
+    risk_factors.insert("SOFR", vec![(0, 200)]);
+    let projected: Vec<_> = lifecycle::run(&scenario.terms, &risk_factors)
+        .expect("lifecycle run failed")
+        .iter()
+        .map(|event| (event.event_type, event.payoff))
+        .collect();
+    assert_eq!(projected, events);
+
+    Ok(())
+}
+
+#[test]
+fn options_are_exercised_by_their_holder_and_settled() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let expiry = math::add_months(ied, 6).unwrap();
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(expiry),
+        notional_principal: Some(100),
+        premium_discount_at_ied: Some(300),
+        option_type: Some(OptionType::C),
+        option_strike1: Some(50),
+        option_exercise_type: Some(OptionExerciseType::XT_A),
+        option_exercise_end_date: Some(expiry),
+        market_object_code_of_underlying: Some("ACME".into()),
+        market_object_provider: Some(actors().oracle.contract_address()),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let Actors { alice, bob, mallory, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::OPTNS)
+        .build(&mut state)?;
+    let holder = scenario.token_balance(alice.address)?;
+
+    // the holder pays the premium
+    assert_eq!(scenario.process(EventType::IED, ied)?, Some(300));
+    assert_eq!(scenario.token_balance(alice.address)?, holder - 300);
+
+    // only the holder exercises, and only through `exercise`
+    let exercised = math::add_months(ied, 4).unwrap();
+    scenario.simulator.set_timestamp(exercised);
+    scenario.simulator.set_actor(bob.address);
+    let result = scenario.call::<SignedUnits, _>("exercise", exercised)?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+    scenario.simulator.set_actor(alice.address);
+    assert!(scenario.try_process(EventType::XD, exercised)?.is_err());
+
+    // at the price the oracle publishes, not one the holder or anyone else quotes
+    let result = scenario.call::<SignedUnits, _>("exercise", exercised)?;
+    assert_eq!(
+        result,
+        Err(ActusError::ValidationError("no observation of risk factor `ACME`".into()))
+    );
+    for quoting in [alice, mallory] {
+        scenario.simulator.set_actor(quoting.address);
+        let result = scenario.call::<(), _>("observe", (EventType::XD as u8, exercised, 1_000u64))?;
+        assert_eq!(result, Err(ActusError::NotAuthorized), "{quoting}");
+    }
+    scenario.simulator.set_actor(alice.address);
+    scenario.publish(EventType::XD, exercised, 58)?;
+
+    // at 58 the call pays 8 on each of its 100 units, which the STD settles
+    let result = scenario.call::<SignedUnits, _>("exercise", exercised)?;
+    assert_eq!(result, Ok(800));
+    assert_eq!(scenario.process(EventType::STD, exercised)?, Some(800));
+    assert_eq!(scenario.token_balance(alice.address)?, holder + 500);
+    assert_eq!(scenario.phase()?, ContractPhase::Terminated);
+    assert_eq!(scenario.next_event()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn caps_pay_the_holder_above_the_strike() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 3),
+        notional_principal: Some(1_000_000),
+        premium_discount_at_ied: Some(1_000),
+        nominal_interest_rate: Some(700),
+        life_cap: Some(400),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let Actors { alice, bob, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::CAPFL)
+        .build(&mut state)?;
+    let creditor = scenario.token_balance(alice.address)?;
+    let debtor = scenario.token_balance(bob.address)?;
+
+    // the holder buys the cap, then the writer pays 300 over the cap each month
+    let log = scenario.drive(math::add_months(ied, 3).unwrap())?;
+    let events: Vec<_> = log.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::IED, Some(1_000)),
+            (EventType::IP, Some(2_499)),
+            (EventType::IP, Some(2_499)),
+            (EventType::MD, Some(2_499)),
+        ]
+    );
+
+    assert_eq!(scenario.token_balance(alice.address)?, creditor + 6_497);
+    assert_eq!(scenario.token_balance(bob.address)?, debtor - 6_497);
+    assert_eq!(scenario.phase()?, ContractPhase::Matured);
+
+    Ok(())
+}
+
+#[test]
+fn caps_pay_once_the_published_rate_rises_over_the_strike() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let monthly = Cycle {
+        n: 1,
+        p: Period::P_M,
+        stub: Stub::ShortStub,
+        include_end_day: false,
+    };
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 3),
+        notional_principal: Some(1_000_000),
+        premium_discount_at_ied: Some(1_000),
+        nominal_interest_rate: Some(300),
+        life_cap: Some(400),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(monthly.clone()),
+        cycle_anchor_date_of_rate_reset: math::add_months(ied, 1),
+        cycle_of_rate_reset: Some(monthly),
+        market_object_code_of_rate_reset: Some("SOFR".into()),
+        market_object_provider: Some(actors().oracle.contract_address()),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::CAPFL)
+        .build(&mut state)?;
+    for months in [1, 2] {
+        scenario.publish(EventType::RR, math::add_months(ied, months).unwrap(), 700)?;
+    }
+
+    // the cap pays nothing while the rate is under its 4% strike, then the writer pays
+    // the 3% the published 7% exceeds it by, which the strike does not clamp
+    let log = scenario.drive(math::add_months(ied, 3).unwrap())?;
+    let events: Vec<_> = log.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::IED, Some(1_000)),
+            (EventType::IP, Some(0)),
+            (EventType::RR, None),
+            (EventType::IP, Some(2_499)),
+            (EventType::RR, None),
+            (EventType::MD, Some(2_499)),
+        ]
+    );
+    let reset = log.iter().find(|settled| settled.event == EventType::RR).unwrap();
+    assert_eq!(reset.state.nominal_interest_rate, 700);
+    assert_eq!(scenario.phase()?, ContractPhase::Matured);
+
+    Ok(())
+}
+
+#[test]
+fn stocks_pay_dividends_until_sold() -> Result<(), SimError> {
+    let purchase = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        contract_type: ContractType::STK,
+        initial_exchange_date: None,
+        maturity_date: None,
+        notional_principal: None,
+        nominal_interest_rate: None,
+        purchase_date: Some(purchase),
+        price_at_purchase_date: Some(40),
+        quantity: Some(1_000),
+        next_dividend_payment_amount: Some(2),
+        cycle_anchor_date_of_dividend_payment: math::add_months(purchase, 3),
+        cycle_of_dividend_payment: Some(Cycle {
+            n: 3,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        termination_date: math::add_months(purchase, 7),
+        price_at_termination_date: Some(45),
+        status_date: purchase,
+        ..pam_terms()
+    };
+
+    let Actors { alice, bob, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let holder = scenario.token_balance(alice.address)?;
+    let issuer = scenario.token_balance(bob.address)?;
+
+    // the holder buys the shares, earns two dividends on them and sells them back
+    let log = scenario.drive(math::add_months(purchase, 12).unwrap())?;
+    let events: Vec<_> = log.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::PRD, Some(40_000)),
+            (EventType::DV, Some(2_000)),
+            (EventType::DV, Some(2_000)),
+            (EventType::TD, Some(45_000)),
+        ]
+    );
+
+    assert_eq!(scenario.token_balance(alice.address)?, holder + 9_000);
+    assert_eq!(scenario.token_balance(bob.address)?, issuer - 9_000);
+    assert_eq!(scenario.phase()?, ContractPhase::Terminated);
+
+    Ok(())
+}
+
+#[test]
+fn stocks_pay_the_dividends_the_provider_publishes() -> Result<(), SimError> {
+    let purchase = math::timestamp_from_civil(2024, 1, 1);
+    let [first, second] = [3, 6].map(|months| math::add_months(purchase, months).unwrap());
+    let terms = ContractTerms {
+        contract_type: ContractType::STK,
+        initial_exchange_date: None,
+        maturity_date: None,
+        notional_principal: None,
+        nominal_interest_rate: None,
+        purchase_date: Some(purchase),
+        price_at_purchase_date: Some(40),
+        quantity: Some(1_000),
+        market_object_code_of_dividends: Some("DIVS".into()),
+        market_object_provider: Some(actors().oracle.contract_address()),
+        cycle_anchor_date_of_dividend_payment: Some(first),
+        cycle_of_dividend_payment: Some(Cycle {
+            n: 3,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        termination_date: math::add_months(purchase, 7),
+        price_at_termination_date: Some(45),
+        status_date: purchase,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    assert_eq!(scenario.process(EventType::PRD, purchase)?, Some(40_000));
+
+    // a dividend waits for the oracle, though a simulation may assume one
+    assert_eq!(
+        scenario.try_process(EventType::DV, first)?,
+        Err(ActusError::ValidationError("no observation of risk factor `DIVS`".into()))
+    );
+    let simulated = scenario.call::<ProcessEventResult, _>(
+        "simulate_event",
+        (EventType::DV as u8, first, None::<Units>, Some(5u64)),
+    )?;
+    assert_eq!(simulated.map(|result| result.signed_payoff()), Ok(Some(5_000)));
+
+    scenario.publish(EventType::DV, first, 2)?;
+    scenario.publish(EventType::DV, second, 3)?;
+    let log = scenario.drive(math::add_months(purchase, 12).unwrap())?;
+    let events: Vec<_> = log.iter().map(|settled| (settled.event, settled.payoff)).collect();
+    assert_eq!(
+        events,
+        [
+            (EventType::DV, Some(2_000)),
+            (EventType::DV, Some(3_000)),
+            (EventType::TD, Some(45_000)),
+        ]
+    );
+    assert_eq!(scenario.phase()?, ContractPhase::Terminated);
+
+    Ok(())
+}
+
+#[test]
+fn swaps_settle_the_net_of_their_legs() -> Result<(), SimError> {
+    let Actors { alice, bob, .. } = actors();
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let maturity = math::add_months(ied, 12).unwrap();
+    let fixed = ContractTerms {
+        contract_id: "fixed".into(),
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(maturity),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        status_date: ied,
+        ..pam_terms()
+    };
+    let mut state = SimpleState::new();
+    let first = Scenario::builder().terms(fixed.clone()).build(&mut state)?;
+
+    // alice receives 5% on 500_000 from bob and pays him 3% on the same notional
+    let mut scenario = first;
+    let simulator = &mut scenario.simulator;
+    let swap = simulator.create_contract(CONTRACT_PATH)?.address;
+    let second = simulator.create_contract(CONTRACT_PATH)?.address;
+    let leg = |address: Address| Some(ContractAddress(address.as_ref().try_into().unwrap()));
+    let swap_terms = ContractTerms {
+        contract_id: "swap".into(),
+        contract_type: ContractType::SWAPS,
+        initial_exchange_date: None,
+        maturity_date: None,
+        status_date: ied,
+        first_leg: leg(scenario.contract),
+        second_leg: leg(second),
+        ..pam_terms()
+    };
+    let floating = ContractTerms {
+        contract_id: "floating".into(),
+        nominal_interest_rate: Some(300),
+        ..fixed
+    };
+    for (contract, terms) in [(swap, &swap_terms), (second, &floating)] {
+        for party in [alice.address, bob.address] {
+            simulator.set_actor(party);
+            simulator.call_contract::<(), _>(
+                scenario.token,
+                "approve",
+                (contract, DEFAULT_FUNDING),
+                MAX_GAS,
+            )?;
+        }
+        let receipt = simulator.create_contract(token_path())?.id;
+        simulator.set_actor(alice.address);
+        simulator
+            .call_contract::<Result<(), ActusError>, _>(
+                contract,
+                "init",
+                (
+                    terms.contract_type as u8,
+                    terms.contract_role as u8,
+                    scenario.token,
+                    bob.address,
+                    receipt,
+                    terms.to_versioned_bytes(),
+                ),
+                MAX_GAS,
+            )?
+            .expect("init failed");
+    }
+
+    // the legs settle through the swap alone
+    for leg in [scenario.contract, second] {
+        for party in [alice.address, bob.address] {
+            simulator.set_actor(party);
+            simulator
+                .call_contract::<Result<(), ActusError>, _>(
+                    leg,
+                    "delegate_settlement",
+                    swap,
+                    MAX_GAS,
+                )?
+                .expect("delegation failed");
+        }
+    }
+    simulator.set_actor(alice.address);
+    let holder = scenario.token_balance(alice.address)?;
+    let counterparty = scenario.token_balance(bob.address)?;
+    scenario.contract = swap;
+    scenario.terms = swap_terms;
+
+    // the notionals exchanged cancel out, then the interest nets to 2% at maturity
+    assert_eq!(scenario.call::<SignedUnits, _>("settle_swap", ied)?, Ok(0));
+    assert_eq!(scenario.call::<SignedUnits, _>("settle_swap", maturity)?, Ok(10_000));
+    assert_eq!(scenario.token_balance(alice.address)?, holder + 10_000);
+    assert_eq!(scenario.token_balance(bob.address)?, counterparty - 10_000);
+
+    let result = scenario.call::<SignedUnits, _>("settle_swap", maturity)?;
+    assert!(matches!(result, Err(ActusError::ValidationError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn guarantees_pay_out_once_the_covered_contract_defaults() -> Result<(), SimError> {
+    let Actors { alice, bob, .. } = actors();
+    let mut state = SimpleState::new();
+    let mut covered = Scenario::builder().build(&mut state)?;
+    covered.process(EventType::IED, 1000)?;
+
+    // bob's 500_000 loan is guaranteed up to 400_000, which alice buys for 2_000
+    let covered_address = covered.contract;
+    let terms = ContractTerms {
+        contract_id: "guarantee".into(),
+        contract_type: ContractType::CEG,
+        notional_principal: Some(400_000),
+        premium_discount_at_ied: Some(2_000),
+        covered_contract: Some(ContractAddress(covered_address.as_ref().try_into().unwrap())),
+        ..pam_terms()
+    };
+    let simulator = &mut covered.simulator;
+    let guarantee = simulator.create_contract(CONTRACT_PATH)?.address;
+    let receipt = simulator.create_contract(token_path())?.id;
+    for party in [alice.address, bob.address] {
+        simulator.set_actor(party);
+        simulator.call_contract::<(), _>(
+            covered.token,
+            "approve",
+            (guarantee, DEFAULT_FUNDING),
+            MAX_GAS,
+        )?;
+    }
+    simulator.set_actor(alice.address);
+    simulator
+        .call_contract::<Result<(), ActusError>, _>(
+            guarantee,
+            "init",
+            (
+                terms.contract_type as u8,
+                terms.contract_role as u8,
+                covered.token,
+                bob.address,
+                receipt,
+                terms.to_versioned_bytes(),
+            ),
+            MAX_GAS,
+        )?
+        .expect("init failed");
+    let mut scenario = Scenario { contract: guarantee, terms, ..covered };
+    let holder = scenario.token_balance(alice.address)?;
+
+    assert_eq!(scenario.process(EventType::IED, 1000)?, Some(2_000));
+
+    // nothing is covered while the loan performs, and only the holder exercises
+    scenario.simulator.set_timestamp(1100);
+    let result = scenario.call::<SignedUnits, _>("exercise_guarantee", 1100u64)?;
+    assert!(matches!(result, Err(ActusError::ValidationError(_))));
+    scenario.simulator.set_actor(bob.address);
+    let result = scenario.call::<SignedUnits, _>("exercise_guarantee", 1100u64)?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+    scenario.simulator.set_actor(alice.address);
+
+    // the loan defaults, and the guarantor pays what it covers of the notional
+    scenario
+        .simulator
+        .call_contract::<Result<ProcessEventResult, ActusError>, _>(
+            covered_address,
+            "process_event_v2",
+            (EventType::CE as u8, 1100u64),
+            MAX_GAS,
+        )?
+        .expect("credit event failed");
+    let result = scenario.call::<SignedUnits, _>("exercise_guarantee", 1100u64)?;
+    assert_eq!(result, Ok(400_000));
+    assert_eq!(scenario.process(EventType::STD, 1100)?, Some(400_000));
+    assert_eq!(scenario.token_balance(alice.address)?, holder + 398_000);
+    assert_eq!(scenario.phase()?, ContractPhase::Terminated);
+
+    Ok(())
+}
+
+#[test]
+fn only_scheduled_events_are_due() -> Result<(), SimError> {
+    let terms = pam_terms();
+    let (ied, maturity) = (terms.initial_exchange_date.unwrap(), terms.maturity_date.unwrap());
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+
+    // nothing but the IED can happen before the principal is exchanged
+    assert_eq!(scenario.is_event_due(EventType::IED, ied - 1)?, (false, Some(ied)));
+    assert_eq!(scenario.is_event_due(EventType::IED, ied)?, (true, Some(ied)));
+    assert_eq!(scenario.is_event_due(EventType::MD, maturity)?, (false, None));
+
+    scenario.process(EventType::IED, ied)?;
+    assert_eq!(scenario.is_event_due(EventType::IED, maturity)?, (false, None));
+    assert_eq!(scenario.is_event_due(EventType::MD, maturity - 1)?, (false, Some(maturity)));
+    assert_eq!(scenario.is_event_due(EventType::MD, maturity)?, (true, Some(maturity)));
+
+    // unscheduled events are never due, however late it is
+    for event in [EventType::PP, EventType::CE, EventType::WO] {
+        assert_eq!(scenario.is_event_due(event, maturity)?, (false, None), "{event:?}");
+    }
+
+    scenario.process(EventType::MD, maturity)?;
+    for event in [EventType::IP, EventType::MD] {
+        assert_eq!(scenario.is_event_due(event, u64::MAX)?, (false, None), "{event:?}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn residual_principal_is_recorded_at_maturity() -> Result<(), SimError> {
+    // three redemptions of 125_000 leave 125_003 of the notional for the MD
+    let terms = ContractTerms {
+        notional_principal: Some(500_003),
+        nominal_interest_rate: Some(0),
+        next_principal_redemption_payment: Some(125_000),
+        ..pam_terms()
+    };
+    let (ied, maturity) = (terms.initial_exchange_date.unwrap(), terms.maturity_date.unwrap());
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::LAM)
+        .build(&mut state)?;
+
+    scenario.process(EventType::IED, ied)?;
+    for date in [ied + 1, ied + 2, ied + 3] {
+        assert_eq!(scenario.process(EventType::PR, date)?, Some(125_000));
+    }
+    assert_eq!(scenario.process(EventType::MD, maturity)?, Some(125_003));
+    assert_eq!(scenario.state()?.notional_principal, 0);
+
+    let history = scenario
+        .call::<Vec<EventRecord>, _>("get_event_history", (0u32, 10u32))?
+        .expect("get_event_history failed");
+    let md = history.last().unwrap();
+    assert_eq!((md.event_type, md.payoff, md.residual), (EventType::MD, Some(125_003), Some(3)));
+
+    Ok(())
+}
+
+#[test]
+fn credit_facility_is_drawn_and_repaid() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(0),
+        credit_limit: Some(500_000),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    let bob_before = scenario.token_balance(bob)?;
+    scenario.process(EventType::IED, 0)?;
+
+    // only the debtor draws
+    scenario.simulator.set_actor(alice);
+    let result = scenario.call::<SignedUnits, _>("draw_down", (0u64, 300_000 as Units))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    scenario.simulator.set_actor(bob);
+    let drawn = scenario.call::<SignedUnits, _>("draw_down", (0u64, 300_000 as Units))?;
+    assert_eq!(drawn, Ok(300_000));
+    assert_eq!(scenario.token_balance(bob)?, bob_before + 300_000);
+
+    let result = scenario.call::<SignedUnits, _>("draw_down", (0u64, 200_001 as Units))?;
+    assert_eq!(
+        result,
+        Err(ActusError::ValidationError("Drawdown exceeds the credit limit of 500000".into()))
+    );
+
+    // a fifth of a year at 5% on the 300_000 drawn
+    scenario.simulator.set_timestamp(73 * day);
+    let repaid = scenario.call::<SignedUnits, _>("repay", (73 * day, 100_000 as Units))?;
+    assert_eq!(repaid, Ok(100_000));
+    assert_eq!(scenario.token_balance(bob)?, bob_before + 200_000);
+
+    let state = scenario.state()?;
+    assert_eq!((state.notional_principal, state.accrued_interest), (200_000, 3_000));
+    scenario.assert_conservation(&[alice, bob])?;
+
+    Ok(())
+}
+
+#[test]
+fn deposits_are_paid_in_and_withdrawn_by_the_depositor() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        contract_type: ContractType::UMP,
+        initial_exchange_date: Some(0),
+        maturity_date: None,
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    let alice_before = scenario.token_balance(alice)?;
+    assert_eq!(scenario.process(EventType::IED, 0)?, Some(500_000));
+
+    // only the depositor pays in
+    scenario.simulator.set_actor(bob);
+    let result = scenario.call::<SignedUnits, _>("deposit", (0u64, 100_000 as Units))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    scenario.simulator.set_actor(alice);
+    let deposited = scenario.call::<SignedUnits, _>("deposit", (0u64, 100_000 as Units))?;
+    assert_eq!(deposited, Ok(100_000));
+    assert_eq!(scenario.token_balance(alice)?, alice_before - 600_000);
+
+    // a fifth of a year at 5% on the 600_000 deposited
+    scenario.simulator.set_timestamp(73 * day);
+    let withdrawn = scenario.call::<SignedUnits, _>("withdraw", (73 * day, 450_000 as Units))?;
+    assert_eq!(withdrawn, Ok(450_000));
+    let state = scenario.state()?;
+    assert_eq!((state.notional_principal, state.accrued_interest), (150_000, 6_000));
+
+    // the deposit stays open once emptied
+    let withdrawn = scenario.call::<SignedUnits, _>("withdraw", (73 * day, 150_000 as Units))?;
+    assert_eq!(withdrawn, Ok(150_000));
+    assert_eq!(scenario.token_balance(alice)?, alice_before);
+    assert_eq!(scenario.phase()?, ContractPhase::Active);
+    scenario.assert_conservation(&[alice, bob])?;
+
+    Ok(())
+}
+
+#[test]
+fn called_money_matures_the_notice_after_the_call() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        contract_type: ContractType::CLM,
+        initial_exchange_date: Some(0),
+        maturity_date: None,
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        x_day_notice: Some(30 * day),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    assert_eq!(scenario.process(EventType::IED, 0)?, Some(500_000));
+
+    // the lender calls the money 43 days in, due 30 days later
+    scenario.simulator.set_timestamp(43 * day);
+    scenario.simulator.set_actor(alice);
+    assert_eq!(scenario.call::<u64, _>("call", ())?, Ok(73 * day));
+    assert_eq!(scenario.next_event()?, Some((EventType::MD, 73 * day)));
+
+    scenario.simulator.set_actor(bob);
+    let again = scenario.call::<u64, _>("call", ())?;
+    assert!(matches!(again, Err(ActusError::ValidationError(_))));
+
+    // a fifth of a year at 5% on the 500_000 lent
+    scenario.simulator.set_timestamp(73 * day);
+    assert_eq!(scenario.process(EventType::MD, 73 * day)?, Some(505_000));
+    assert_eq!(scenario.phase()?, ContractPhase::Matured);
+    scenario.assert_conservation(&[alice, bob])?;
+
+    Ok(())
+}
+
+/// The installment and its split for every period of a 300_000 mortgage at 4.8% over
+/// ten years, computed in floating point with the contract's conventions: the 30E/360
+/// year fraction of a month is kept to four decimals, interest is truncated to the unit
+/// and the final installment repays whatever principal is left
+fn golden_mortgage_cashflows() -> (Units, Vec<(Units, Units)>) {
+    let periods = 120;
+    let rate = 0.048 * 0.0833;
+    let mut balance = 300_000f64;
+
+    let installment = balance * rate / (1.0 - (1.0 + rate).powi(-periods));
+    let installment = installment.round();
+
+    let cashflows = (1..=periods)
+        .map(|period| {
+            let interest = (balance * rate).floor();
+            let principal = if period == periods {
+                balance
+            } else {
+                installment - interest
+            };
+            balance -= principal;
+            (interest as Units, principal as Units)
+        })
+        .collect();
+
+    (installment as Units, cashflows)
+}
+
+#[test]
+fn annuity_mortgage_matches_golden_cashflows() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 120),
+        notional_principal: Some(300_000),
+        nominal_interest_rate: Some(480),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::ANN)
+        .build(&mut state)?;
+    let alice_before = scenario.token_balance(scenario.creditor)?;
+
+    let log = scenario.drive(math::add_months(ied, 120).unwrap())?;
+    let (exchange, installments) = log.split_first().expect("the IED is scheduled");
+    assert_eq!(exchange.event, EventType::IED);
+
+    let (installment, golden) = golden_mortgage_cashflows();
+    assert_eq!(exchange.state.next_principal_redemption_payment, installment);
+    assert_eq!(installments.len(), golden.len());
+
+    let mut outstanding = 300_000;
+    let mut received = 0;
+
+    for ((month, (interest, principal)), processed) in (1..).zip(golden).zip(installments) {
+        let event = if month == 120 { EventType::MD } else { EventType::IP };
+        let date = math::add_months(ied, month).unwrap();
+        assert_eq!((processed.event, processed.time), (event, date), "period {month}");
+
+        let payoff = processed.payoff.expect("installment");
+        let payoff = Units::try_from(payoff).expect("installments are paid by the debtor");
+
+        let repaid = outstanding - processed.state.notional_principal;
+        let paid_interest = payoff - repaid;
+        assert!(
+            paid_interest.abs_diff(interest) <= 1,
+            "period {month}: interest {paid_interest}, expected {interest}"
+        );
+        assert!(
+            repaid.abs_diff(principal) <= 1,
+            "period {month}: principal {repaid}, expected {principal}"
+        );
+
+        outstanding = processed.state.notional_principal;
+        received += payoff;
+    }
+
+    assert_eq!(outstanding, 0);
+    assert_eq!(
+        Units::from(scenario.token_balance(scenario.creditor)?),
+        Units::from(alice_before) - 300_000 + received
+    );
+
+    let totals = scenario.state()?;
+    let interest = Units::try_from(totals.total_interest_paid).expect("a positive rate");
+    assert_eq!(interest + totals.total_principal_repaid, received);
+    assert_eq!(totals.total_principal_repaid, 300_000);
+    assert_eq!((totals.total_fees_paid, totals.total_penalties_paid), (0, 0));
+
+    Ok(())
+}
+
+#[test]
+fn annuity_refixes_over_its_remaining_periods() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let month = |k| math::add_months(ied, k).unwrap();
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: Some(month(12)),
+        notional_principal: Some(1_200),
+        nominal_interest_rate: Some(0),
+        day_count_convention: Some(DayCountConvention::DCC_E30_360),
+        cycle_anchor_date_of_interest_payment: Some(month(1)),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder()
+        .terms(terms)
+        .contract_type(ContractType::ANN)
+        .build(&mut state)?;
+
+    scenario.process(EventType::IED, ied)?;
+    for k in 1..=6 {
+        assert_eq!(scenario.process(EventType::IP, month(k))?, Some(100));
+    }
+
+    // the six monthly periods left still repay 100 each at a zero rate
+    assert_eq!(scenario.process(EventType::PRF, month(6))?, None);
+    let state = scenario.state()?;
+    assert_eq!((state.notional_principal, state.next_principal_redemption_payment), (600, 100));
+
+    Ok(())
+}
+
+#[test]
+fn fractionalized_interest_is_split_pro_rata() -> Result<(), SimError> {
+    let carol = actors().carol.address;
+
+    let year = 365 * SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(2 * year),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let alice = scenario.creditor;
+
+    scenario.process(EventType::IED, 0)?;
+
+    let shares = vec![(alice, 600_000), (carol, 400_000)];
+    scenario
+        .call::<(), _>("fractionalize", (shares.clone(),))?
+        .expect("fractionalize failed");
+
+    let holders = scenario
+        .call::<Vec<(Address, u64)>, _>("holders", ())?
+        .expect("holders failed");
+    assert_eq!(holders, shares);
+
+    let alice_before = scenario.token_balance(alice)?;
+    let carol_before = scenario.token_balance(carol)?;
+
+    let payoff = scenario
+        .process(EventType::IP, year)?
+        .expect("interest payment");
+    assert_eq!(payoff, 50_000);
+
+    let alice_received = scenario.token_balance(alice)? - alice_before;
+    let carol_received = scenario.token_balance(carol)? - carol_before;
+
+    let shares = SignedUnits::from(SHARE_DENOMINATOR);
+    assert_eq!(SignedUnits::from(alice_received), payoff * 600_000 / shares);
+    assert_eq!(SignedUnits::from(carol_received), payoff * 400_000 / shares);
+    scenario.assert_conservation(&[alice, scenario.debtor, carol])?;
+
+    Ok(())
+}
+
+#[test]
+fn only_sole_holder_can_fractionalize() -> Result<(), SimError> {
+    let carol = actors().carol.address;
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.simulator.set_actor(bob);
+    let result = scenario.call::<(), _>("fractionalize", (vec![(bob, SHARE_DENOMINATOR)],))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    // errors reach callers as a (code, message) pair
+    scenario.simulator.set_actor(alice);
+    let result = scenario.simulator.call_contract::<Result<(), (u16, String)>, _>(
+        scenario.contract,
+        "fractionalize",
+        (vec![(alice, 600_000u64), (carol, 300_000u64)],),
+        MAX_GAS,
+    )?;
+    assert_eq!(result, Err((1, format!("Shares must sum to {SHARE_DENOMINATOR}"))));
+
+    scenario
+        .call::<(), _>("transfer_share", (carol, 250_000u64))?
+        .expect("transfer_share failed");
+
+    let holders = scenario
+        .call::<Vec<(Address, u64)>, _>("holders", ())?
+        .expect("holders failed");
+    assert_eq!(holders, vec![(alice, 750_000), (carol, 250_000)]);
+
+    Ok(())
+}
+
+#[test]
+fn receipt_holder_receives_redemption() -> Result<(), SimError> {
+    let carol = actors().carol.address;
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let alice = scenario.creditor;
+
+    let receipt = scenario
+        .call::<Address, _>("receipt", ())?
+        .expect("receipt failed");
+    let receipt_balance =
+        |scenario: &Scenario, account: Address| scenario.simulator.balance_of(receipt, account);
+
+    scenario.process(EventType::IED, 1000)?;
+    assert_eq!(receipt_balance(&scenario, alice)?, SHARE_DENOMINATOR);
+
+    // alice sells the position to carol mid-life
+    scenario
+        .simulator
+        .call_contract::<(), _>(receipt, "transfer", (carol, SHARE_DENOMINATOR), MAX_GAS)?;
+
+    scenario.simulator.set_actor(carol);
+    scenario
+        .call::<(), _>("register_holder", (carol,))?
+        .expect("register_holder failed");
+
+    let alice_before = scenario.token_balance(alice)?;
+    let carol_before = scenario.token_balance(carol)?;
+
+    let redemption = scenario
+        .process(EventType::MD, 1300)?
+        .expect("maturity redemption");
+
+    assert_eq!(scenario.token_balance(alice)?, alice_before);
+    assert_eq!(SignedUnits::from(scenario.token_balance(carol)? - carol_before), redemption);
+    scenario.assert_conservation(&[alice, scenario.debtor, carol])?;
+
+    // the receipt is burned once the position is redeemed
+    let supply: token::Units = scenario
+        .simulator
+        .call_contract(receipt, "total_supply", (), MAX_GAS)?;
+    assert_eq!(supply, 0);
+
+    Ok(())
+}
+
+#[test]
+fn receipts_moved_without_registering_hold_up_payments() -> Result<(), SimError> {
+    let carol = actors().carol.address;
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    let receipt = scenario
+        .call::<Address, _>("receipt", ())?
+        .expect("receipt failed");
+
+    scenario.process(EventType::IED, 1000)?;
+
+    // alice sells part of the position to carol, who does not register
+    scenario
+        .simulator
+        .call_contract::<(), _>(receipt, "transfer", (carol, 400_000u64), MAX_GAS)?;
+
+    // carol's part is neither paid to alice nor left with bob
+    let result = scenario.try_process(EventType::MD, 1300)?;
+    assert_eq!(
+        result,
+        Err(ActusError::StateError("Receipt tokens are held by unregistered holders".into()))
+    );
+
+    // anyone can register her
+    scenario.simulator.set_actor(bob);
+    scenario
+        .call::<(), _>("register_holder", (carol,))?
+        .expect("register_holder failed");
+
+    let alice_before = scenario.token_balance(alice)?;
+    let carol_before = scenario.token_balance(carol)?;
+
+    let redemption = scenario
+        .process(EventType::MD, 1300)?
+        .expect("maturity redemption");
+
+    let shares = SignedUnits::from(SHARE_DENOMINATOR);
+    let alice_received = SignedUnits::from(scenario.token_balance(alice)? - alice_before);
+    let carol_received = SignedUnits::from(scenario.token_balance(carol)? - carol_before);
+    // the rounding residual goes to the largest holder
+    assert_eq!(carol_received, redemption * 400_000 / shares);
+    assert_eq!(alice_received, redemption - carol_received);
+    scenario.assert_conservation(&[alice, bob, carol])?;
+
+    // every receipt token is burned, carol's too
+    let supply: token::Units = scenario
+        .simulator
+        .call_contract(receipt, "total_supply", (), MAX_GAS)?;
+    assert_eq!(supply, 0);
+    assert_eq!(scenario.simulator.balance_of(receipt, carol)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn defaulted_contract_is_written_off() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 1000)?;
+
+    // a performant contract cannot be written off
+    scenario.simulator.set_timestamp(1100);
+    let result = scenario.call::<Units, _>("write_off", 1100u64)?;
+    assert!(result.is_err());
+
+    scenario.process(EventType::CE, 1100)?;
+
+    // only the creditor writes off
+    scenario.simulator.set_timestamp(1200);
+    scenario.simulator.set_actor(bob);
+    let result = scenario.call::<Units, _>("write_off", 1200u64)?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    scenario.simulator.set_actor(alice);
+    let loss = scenario
+        .call::<Units, _>("write_off", 1200u64)?
+        .expect("write_off failed");
+    assert_eq!(loss, 500_000);
+
+    let final_state = scenario.state()?;
+    assert_eq!(final_state.notional_principal, 0);
+    assert_eq!(final_state.accrued_interest, 0);
+    assert_eq!(final_state.contract_performance, ContractPerformance::DF);
+    assert_eq!(final_state.phase, ContractPhase::WrittenOff);
+
+    let history = scenario
+        .call::<Vec<EventRecord>, _>("get_event_history", (0u32, 10u32))?
+        .expect("get_event_history failed");
+    assert_eq!(
+        history.last(),
+        Some(&EventRecord {
+            event_type: EventType::WO,
+            timestamp: 1200,
+            payoff: Some(500_000),
+            penalty: None,
+            residual: None,
+            processed_at: 1200,
+        })
+    );
+
+    // no further events once written off
+    let result = scenario.try_process(EventType::MD, 1300)?;
+    assert_eq!(
+        result,
+        Err(ActusError::EventNotAllowed {
+            event: EventType::MD,
+            phase: ContractPhase::WrittenOff
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rolled_over_contract_keeps_accruing() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(90 * day),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 0)?;
+
+    let roll_over = |scenario: &mut Scenario, party, maturity: u64, rate: Option<u64>| {
+        scenario.simulator.set_actor(party);
+        scenario
+            .call::<bool, _>("roll_over", (maturity, rate))
+            .map(|result| result.expect("roll_over failed"))
+    };
+
+    // the roll-over only applies once both parties agree
+    assert!(!roll_over(&mut scenario, alice, 180 * day, None)?);
+    assert!(roll_over(&mut scenario, bob, 180 * day, None)?);
+
+    assert!(!roll_over(&mut scenario, bob, 270 * day, Some(600))?);
+    assert!(roll_over(&mut scenario, alice, 270 * day, Some(600))?);
+
+    let next_event = scenario
+        .call::<Option<(EventType, u64)>, _>("get_next_event", ())?
+        .expect("get_next_event failed");
+    assert_eq!(next_event, Some((EventType::MD, 270 * day)));
+
+    // interest keeps accruing past the original maturity
+    scenario.process(EventType::AD, 120 * day)?;
+    let accrued = scenario.state()?.accrued_interest;
+    assert!(accrued > 0);
+
+    let redemption = scenario
+        .process(EventType::MD, 270 * day)?
+        .expect("maturity redemption");
+    assert!(redemption > 1_000_000 + accrued);
+
+    // a matured contract cannot roll over
+    scenario.simulator.set_actor(alice);
+    let result = scenario.call::<bool, _>("roll_over", (360 * day, None::<u64>))?;
+    assert_eq!(result, Err(ActusError::AlreadyTerminated));
+
+    Ok(())
+}
+
+#[test]
+fn history_records_block_time() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+
+    // the event is due at 1000 but only processed once the block time reaches 1042
+    scenario.simulator.set_timestamp(1042);
+    scenario
+        .call::<Option<SignedUnits>, _>("process_event", (EventType::IED as u8, 1000u64))?
+        .expect("event processing failed");
+
+    let history = scenario
+        .call::<Vec<EventRecord>, _>("get_event_history", (0u32, 10u32))?
+        .expect("get_event_history failed");
+    assert_eq!(
+        history,
+        vec![EventRecord {
+            event_type: EventType::IED,
+            timestamp: 1000,
+            payoff: Some(500_000),
+            penalty: None,
+            residual: None,
+            processed_at: 1042,
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stored_state_is_the_unversioned_records() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let scenario = Scenario::builder().build(&mut state)?;
+
+    scenario
+        .call::<Option<SignedUnits>, _>("process_event", (EventType::IED as u8, 1000u64))?
+        .expect("event processing failed");
+    let live = scenario.call::<ContractState, _>("get_state", ())?.expect("get_state failed");
+
+    let stored = scenario.simulator.state();
+    let record = |key: &[u8]| -> Vec<u8> {
+        let value = stored.get_raw(scenario.contract, key).expect("record not stored");
+        // the value is the borsh encoding of the stored byte vector
+        borsh::from_slice(&value).expect("record is not a byte vector")
+    };
+
+    let (hot, cold): (HotState, ColdState) = live.into();
+    assert_eq!(record(&STATE_KEY), borsh::to_vec(&hot).unwrap());
+    assert_eq!(record(&STATE_COLD_KEY), borsh::to_vec(&cold).unwrap());
+
+    // no version byte: the hot record starts with the status date
+    assert_eq!(record(&STATE_KEY)[..8], 1000u64.to_le_bytes());
+
+    let keys: Vec<&[u8]> = stored.keys(scenario.contract).collect();
+    assert!(keys.contains(&&STATE_KEY[..]) && keys.contains(&&STATE_COLD_KEY[..]));
+    assert!(stored.keys(scenario.token).all(|key| key != STATE_KEY));
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_branches_evolve_independently() -> Result<(), SimError> {
+    let terms = ContractTerms {
+        prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 1000)?;
+    let after_ied = scenario.simulator.snapshot();
+
+    // the borrower prepays at t=1150
+    scenario.simulator.set_actor(bob);
+    assert_eq!(scenario.process(EventType::PP, 1150)?, Some(500_000));
+    let prepaid_state = scenario.state()?;
+    let prepaid_balance = scenario.token_balance(alice)?;
+    assert_eq!(prepaid_state.notional_principal, 0);
+
+    // ... or doesn't, and pays interest instead
+    scenario.simulator.revert_to(after_ied);
+    assert_eq!(scenario.now(), 1000);
+
+    scenario.process(EventType::IP, 1150)?;
+    let interest_state = scenario.state()?;
+    assert_eq!(interest_state.notional_principal, 500_000);
+    assert!(scenario.token_balance(alice)? < prepaid_balance);
+
+    // the prepayment branch can be replayed from the same snapshot
+    scenario.simulator.revert_to(after_ied);
+    scenario.simulator.set_actor(bob);
+    scenario.process(EventType::PP, 1150)?;
+    assert_eq!(scenario.state()?, prepaid_state);
+    assert_eq!(scenario.token_balance(alice)?, prepaid_balance);
+
+    Ok(())
+}
+
+#[test]
+fn phase_follows_the_contract_through_its_life() -> Result<(), SimError> {
+    let terms = ContractTerms {
+        prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+    let phase = |scenario: &Scenario| -> Result<ContractPhase, SimError> {
+        let phase = scenario.phase()?;
+        assert_eq!(phase, scenario.state()?.phase);
+        Ok(phase)
+    };
+    let rejects = |scenario: &mut Scenario, event, time, phase| -> Result<(), SimError> {
+        assert_eq!(
+            scenario.try_process(event, time)?,
+            Err(ActusError::EventNotAllowed { event, phase })
+        );
+        assert_eq!(scenario.next_event()?, None);
+        Ok(())
+    };
+
+    assert_eq!(phase(&scenario)?, ContractPhase::PreInitialExchange);
+    scenario.process(EventType::IED, 1000)?;
+    assert_eq!(phase(&scenario)?, ContractPhase::Active);
+    let running = scenario.simulator.snapshot();
+
+    // runs to maturity
+    scenario.process(EventType::MD, 1300)?;
+    assert_eq!(phase(&scenario)?, ContractPhase::Matured);
+    rejects(&mut scenario, EventType::IP, 1400, ContractPhase::Matured)?;
+
+    // is prepaid in full
+    scenario.simulator.revert_to(running);
+    assert_eq!(phase(&scenario)?, ContractPhase::Active);
+    scenario.simulator.set_actor(bob);
+    scenario.process(EventType::PP, 1150)?;
+    assert_eq!(phase(&scenario)?, ContractPhase::Terminated);
+    rejects(&mut scenario, EventType::MD, 1300, ContractPhase::Terminated)?;
+
+    // or defaults and is written off
+    scenario.simulator.revert_to(running);
+    scenario.simulator.set_actor(alice);
+    scenario.process(EventType::CE, 1100)?;
+    assert_eq!(phase(&scenario)?, ContractPhase::Active);
+    scenario.simulator.set_timestamp(1200);
+    scenario
+        .call::<Units, _>("write_off", 1200u64)?
+        .expect("write_off failed");
+    assert_eq!(phase(&scenario)?, ContractPhase::WrittenOff);
+    rejects(&mut scenario, EventType::MD, 1300, ContractPhase::WrittenOff)?;
+
+    Ok(())
+}
+
+#[test]
+fn tiny_gas_limit_runs_out_of_fuel() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let scenario = Scenario::builder().build(&mut state)?;
+
+    scenario.state()?;
+    let units = scenario.simulator.last_call_units();
+    assert!(units > 0 && units < MAX_GAS);
+
+    let result = scenario.simulator.call_contract::<Result<ContractState, ActusError>, _>(
+        scenario.contract,
+        "get_state",
+        (),
+        1,
+    );
+    assert!(matches!(result, Err(SimError::OutOfFuel)));
+    assert_eq!(scenario.simulator.last_call_units(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn processed_events_are_logged() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().build(&mut state)?;
+    let contract = scenario.contract;
+
+    scenario.process(EventType::IED, 1000)?;
+    scenario.simulator.take_logs();
+
+    let payoff = scenario.process(EventType::IP, 1100)?;
+
+    let logs: Vec<EventRecord> = scenario
+        .simulator
+        .take_logs()
+        .into_iter()
+        .filter(|log| log.contract == contract)
+        .map(|log| borsh::from_slice(&log.data).expect("failed to decode log"))
+        .collect();
+    assert_eq!(
+        logs,
+        vec![EventRecord {
+            event_type: EventType::IP,
+            timestamp: 1100,
+            payoff,
+            penalty: None,
+            residual: None,
+            processed_at: 1100,
+        }]
+    );
+
+    // rejected events are not logged
+    let stale = (EventType::IP as u8, 1000u64);
+    assert!(scenario.call::<Option<SignedUnits>, _>("process_event", stale)?.is_err());
+    assert!(scenario.simulator.take_logs().is_empty());
+
+    // nor are the events of calls that fail
+    let result = scenario.simulator.call_contract::<Result<Option<SignedUnits>, ActusError>, _>(
+        contract,
+        "process_event",
+        (EventType::MD as u8, 1300u64),
+        1,
+    );
+    assert!(matches!(result, Err(SimError::OutOfFuel)));
+    assert!(scenario.simulator.take_logs().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn payoffs_are_truncated_to_the_currency_decimals() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    // digits below a micro-unit, which a 6-decimal token cannot carry
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        notional_principal: Some(5_000_001_234),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    // the payoff of the IP after 100 days, in the decimals of the terms
+    let mut expected = ContractState::new(&terms);
+    TransitionEngine::process_event(EventType::IED, 0, &mut expected, &terms).unwrap();
+    let interest = TransitionEngine::process_event(EventType::IP, 100 * day, &mut expected, &terms)
+        .unwrap()
+        .expect("interest payment");
+    assert_ne!(interest % 1_000, 0);
+
+    let interest_payoff = |decimals: u8, funding: token::Units| -> Result<SignedUnits, SimError> {
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder()
+            .terms(terms.clone())
+            .decimals(decimals)
+            .funding(funding)
+            .build(&mut state)?;
+        let creditor = scenario.creditor;
+
+        scenario.process(EventType::IED, 0)?;
+        let before = scenario.token_balance(creditor)?;
+        let payoff = scenario.process(EventType::IP, 100 * day)?.expect("interest payment");
+
+        // what is reported is what settles
+        assert_eq!(SignedUnits::from(scenario.token_balance(creditor)? - before), payoff);
+        Ok(payoff)
+    };
+
+    let six = interest_payoff(6, 10_000_000)?;
+    let eighteen = interest_payoff(18, 8_000_000_000_000_000_000)?;
+
+    // the 234 units the IED did not disburse are carried into the interest
+    assert_eq!(six, (interest - 234) / 1_000);
+    assert_eq!(eighteen, interest * 1_000_000_000);
+    assert_ne!(eighteen, six * 1_000_000_000_000);
+
+    Ok(())
+}
+
+#[test]
+fn payoffs_past_the_token_range_are_rejected() -> Result<(), SimError> {
+    // 100_000 tokens are 10^23 base units of an 18-decimal token, past u64::MAX
+    let terms = ContractTerms {
+        notional_principal: Some(100_000 * Units::pow(10, math::AMOUNT_DECIMALS.into())),
+        ..pam_terms()
+    };
+    let notional = terms.notional_principal.unwrap();
+    let settled = math::rescale_decimals(notional, math::AMOUNT_DECIMALS, 18).unwrap();
+    assert!(settled > Units::from(u64::MAX));
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).decimals(18).build(&mut state)?;
+
+    let result = scenario.try_process(EventType::IED, 1000)?;
+    assert_eq!(
+        result,
+        Err(ActusError::MathError("Payoff exceeds the token's range".into()))
+    );
+    assert_eq!(scenario.state()?.notional_principal, 0);
+
+    Ok(())
+}
+
+#[test]
+fn negative_interest_is_paid_by_the_creditor() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    // a deposit at -0.5%
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        nominal_interest_rate: Some(-50),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (creditor, debtor) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 0)?;
+    let creditor_before = scenario.token_balance(creditor)?;
+    let debtor_before = scenario.token_balance(debtor)?;
+
+    let payoff = scenario.process(EventType::IP, 90 * day)?.expect("interest payment");
+    assert!(payoff < 0);
+
+    let paid = SignedUnits::from(creditor_before - scenario.token_balance(creditor)?);
+    assert_eq!(paid, -payoff);
+    assert_eq!(SignedUnits::from(scenario.token_balance(debtor)? - debtor_before), paid);
+
+    Ok(())
+}
+
+#[test]
+fn missed_payment_is_settled_with_the_delinquency_charge() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    // five days of grace, then a penalty of 100 and 10% a year on what is overdue
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        grace_period: Some(5 * day),
+        delinquency_rate: Some(1_000),
+        penalty_type: Some(PenaltyType::PYTP_A),
+        penalty_rate: Some(100),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 0)?;
+
+    // not overdue before its due date, and only the creditor records it
+    scenario.simulator.set_timestamp(91 * day);
+    let result = scenario.call::<Units, _>("miss_payment", (EventType::IP as u8, 91 * day))?;
+    assert!(result.is_err());
+
+    scenario.simulator.set_timestamp(92 * day);
+    scenario.simulator.set_actor(bob);
+    let result = scenario.call::<Units, _>("miss_payment", (EventType::IP as u8, 91 * day))?;
+    assert_eq!(result, Err(ActusError::NotAuthorized));
+
+    scenario.simulator.set_actor(alice);
+    let missed = scenario
+        .call::<Units, _>("miss_payment", (EventType::IP as u8, 91 * day))?
+        .expect("miss_payment failed");
+    assert_eq!(missed, 6_232);
+    assert_eq!(scenario.state()?.contract_performance, ContractPerformance::DL);
+
+    // paid ten days late: 682 accrued since, the 6_232 overdue, 17 of delinquency and
+    // the penalty, recorded alongside the payoff
+    let debtor_before = scenario.token_balance(bob)?;
+    let payoff = scenario.process(EventType::IP, 101 * day)?;
+    assert_eq!(payoff, Some(682 + 6_232 + 17 + 100));
+    assert_eq!(debtor_before - scenario.token_balance(bob)?, 682 + 6_232 + 17 + 100);
+
+    let history = scenario
+        .call::<Vec<EventRecord>, _>("get_event_history", (0u32, 10u32))?
+        .expect("get_event_history failed");
+    let record = history.last().expect("no history");
+    assert_eq!((record.payoff, record.penalty), (payoff, Some(100)));
+
+    let state = scenario.state()?;
+    assert_eq!((state.overdue_amount, state.overdue_since), (0, None));
+    assert_eq!(state.contract_performance, ContractPerformance::PF);
+
+    Ok(())
+}
+
+fn state_at(
+    scenario: &Scenario,
+    event_index: u32,
+    from: Option<(u32, ContractState)>,
+) -> Result<Result<ContractState, ActusError>, SimError> {
+    scenario.call("get_state_at", (event_index, from))
+}
+
+#[test]
+fn replayed_history_reaches_the_live_state() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2024, 1, 1);
+    let terms = ContractTerms {
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 72),
+        notional_principal: Some(1_000_000),
+        nominal_interest_rate: Some(500),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(Cycle {
+            n: 1,
+            p: Period::P_M,
+            stub: Stub::ShortStub,
+            include_end_day: false,
+        }),
+        status_date: ied,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let log = scenario.drive(math::add_months(ied, 72).unwrap())?;
+    let last = u32::try_from(log.len() - 1).unwrap();
+    assert!(last >= MAX_REPLAYED_EVENTS);
+
+    assert!(matches!(state_at(&scenario, last + 1, None)?, Err(ActusError::ValidationError(_))));
+    assert!(matches!(state_at(&scenario, last, None)?, Err(ActusError::ValidationError(_))));
+
+    // each call replays up to the limit and the next continues from the state it returned
+    let mut from = None;
+    let mut index = 0;
+    while index < last {
+        index = (index + MAX_REPLAYED_EVENTS - 1).min(last);
+        let state = state_at(&scenario, index, from)?.expect("get_state_at failed");
+        assert_eq!(state, log[index as usize].state, "state after event {index}");
+        from = Some((index, state));
+    }
+    assert_eq!(from.map(|(_, state)| state), Some(scenario.state()?));
+
+    let before = state_at(&scenario, 3, None)?;
+    assert_eq!(before, Ok(log[3].state.clone()));
+    let result = state_at(&scenario, 3, Some((4, log[4].state.clone())))?;
+    assert!(matches!(result, Err(ActusError::ValidationError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn replayed_history_keeps_changes_outside_it() -> Result<(), SimError> {
+    let day = SECONDS_PER_DAY;
+    let terms = ContractTerms {
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * day),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        grace_period: Some(5 * day),
+        delinquency_rate: Some(1_000),
+        status_date: 0,
+        ..pam_terms()
+    };
+
+    let mut state = SimpleState::new();
+    let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+    let (alice, bob) = (scenario.creditor, scenario.debtor);
+
+    scenario.process(EventType::IED, 0)?;
+    let exchanged = scenario.state()?;
+
+    // the missed payment is not an event of the history, but the state after the IED
+    // stays in force with it until the next one
+    scenario.simulator.set_timestamp(92 * day);
+    scenario
+        .call::<Units, _>("miss_payment", (EventType::IP as u8, 91 * day))?
+        .expect("miss_payment failed");
+    assert_eq!(state_at(&scenario, 0, None)?, Ok(scenario.state()?));
+    assert_ne!(scenario.state()?, exchanged);
+
+    scenario.process(EventType::IP, 101 * day)?;
+    scenario.process(EventType::AD, 120 * day)?;
+    assert_eq!(state_at(&scenario, 2, None)?, Ok(scenario.state()?));
+
+    for party in [alice, bob] {
+        scenario.simulator.set_actor(party);
+        scenario
+            .call::<bool, _>("roll_over", (500 * day, Some(600u64)))?
+            .expect("roll_over failed");
+    }
+    scenario.process(EventType::AD, 400 * day)?;
+    assert_eq!(state_at(&scenario, 3, None)?, Ok(scenario.state()?));
+
+    // events before the roll-over were processed under the terms it replaced
+    assert!(matches!(state_at(&scenario, 1, None)?, Err(ActusError::StateError(_))));
+
+    Ok(())
+}
+
+/// Deploys a contract and initializes it with `terms` by hand, as the scenario builder
+/// expects init to succeed
+fn init_by_hand(terms: &ContractTerms) -> Result<Result<(), ActusError>, SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let Actors { alice, bob, .. } = actors();
+
+    simulator.set_actor(alice.address);
+    let contract_address = simulator.create_contract(CONTRACT_PATH)?.address;
+    let token = simulator.create_contract(token_path())?;
+
+    simulator.call_contract::<Result<(), ActusError>, _>(
+        contract_address,
+        "init",
+        (
+            terms.contract_type as u8,
+            terms.contract_role as u8,
+            token.address,
+            bob.address,
+            token.id,
+            terms.to_versioned_bytes(),
+        ),
+        MAX_GAS,
+    )
+}
+
+#[test]
+fn init_requires_terms() -> Result<(), SimError> {
+    let terms = ContractTerms {
+        maturity_date: None,
+        ..pam_terms()
+    };
+
+    let result = init_by_hand(&terms)?;
+    assert_eq!(result, Err(ActusError::MissingTerm { field: "maturity_date" }));
+
+    Ok(())
+}
+
+#[test]
+fn days_contracts_pay_what_seconds_ones_do() -> Result<(), SimError> {
+    let ied = math::timestamp_from_civil(2025, 1, 31);
+    let monthly = Cycle { n: 1, p: Period::P_M, stub: Stub::ShortStub, include_end_day: false };
+    let seconds = ContractTerms {
+        status_date: ied,
+        initial_exchange_date: Some(ied),
+        maturity_date: math::add_months(ied, 12),
+        day_count_convention: Some(DayCountConvention::DCC_A_AISDA),
+        cycle_anchor_date_of_interest_payment: math::add_months(ied, 1),
+        cycle_of_interest_payment: Some(monthly),
+        ..pam_terms()
+    };
+    let day = |date: Timestamp| date / SECONDS_PER_DAY;
+    let days = ContractTerms {
+        time_granularity: Some(TimeGranularity::Days),
+        status_date: day(ied),
+        initial_exchange_date: Some(day(ied)),
+        maturity_date: seconds.maturity_date.map(day),
+        cycle_anchor_date_of_interest_payment: seconds
+            .cycle_anchor_date_of_interest_payment
+            .map(day),
+        ..seconds.clone()
+    };
+
+    let run = |terms: &ContractTerms| -> Result<Vec<_>, SimError> {
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().terms(terms.clone()).build(&mut state)?;
+        let driven = scenario.drive(Timestamp::MAX)?;
+        Ok(driven.into_iter().map(|event| (event.event, event.time, event.payoff)).collect())
+    };
+    let in_seconds = run(&seconds)?;
+    let in_days = run(&days)?;
+
+    assert_eq!(in_seconds.len(), 14);
+    assert_eq!(
+        in_days,
+        in_seconds
+            .iter()
+            .map(|&(event, time, payoff)| (event, day(time), payoff))
+            .collect::<Vec<_>>()
+    );
+
+    // dates in seconds are not taken for days
+    let mistaken = ContractTerms { time_granularity: Some(TimeGranularity::Days), ..seconds };
+    assert!(matches!(init_by_hand(&mistaken)?, Err(ActusError::ValidationError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn generated_addresses_do_not_collide() {
+    let generated: Vec<Address> = addresses(ACTOR_SEED).take(500).collect();
+    let distinct: std::collections::HashSet<_> = generated.iter().collect();
+    assert_eq!(distinct.len(), generated.len());
+
+    // the same on every run, and another seed gives others
+    assert_eq!(addresses(ACTOR_SEED).take(500).collect::<Vec<_>>(), generated);
+    assert!(addresses(ACTOR_SEED + 1).take(500).all(|address| !distinct.contains(&address)));
+
+    let actors = actors();
+    assert_eq!(generated[..2], [actors.alice.address, actors.bob.address]);
+    assert!(actors.mallory.to_string().starts_with("mallory (0x"));
+}
+
+/// Calls made by the wrong actor. Each access-controlled entrypoint gets a case here
+/// next to its happy path, asserting the typed error and that the state is untouched.
+mod adversarial {
+    use super::*;
+    use actus::core::Timestamp;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use std::fmt::Debug;
+
+    /// Calls `function` as `actor`, asserting it fails with the code of `expected` and
+    /// leaves the contract state as it was
+    fn assert_rejected<T, A>(
+        scenario: &mut Scenario,
+        actor: Actor,
+        function: &str,
+        args: A,
+        expected: ActusError,
+    ) -> Result<(), SimError>
+    where
+        T: BorshDeserialize + Debug,
+        A: BorshSerialize,
+    {
+        let before = scenario.state()?;
+
+        scenario.simulator.set_actor(actor.address);
+        match scenario.try_call::<T, _>(function, args) {
+            Err(SimError::Contract { code, message }) => {
+                assert_eq!(code, expected.code(), "{function} as {actor}: {message}")
+            }
+            other => panic!("{function} as {actor} was not rejected by the contract: {other:?}"),
+        }
+        assert_eq!(scenario.state()?, before, "{function} as {actor} changed the state");
+
+        Ok(())
+    }
+
+    #[test]
+    fn stranger_cannot_process_events() -> Result<(), SimError> {
+        let mallory = actors().mallory;
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+
+        scenario.simulator.set_timestamp(1000);
+        assert_rejected::<Option<SignedUnits>, _>(
+            &mut scenario,
+            mallory,
+            "process_event",
+            (EventType::IED as u8, 1000u64),
+            ActusError::NotAuthorized,
+        )
+    }
+
+    #[test]
+    fn only_borrower_can_prepay() -> Result<(), SimError> {
+        let terms = ContractTerms {
+            prepayment_effect: Some(PrepaymentEffect::PPEF_M),
+            ..pam_terms()
+        };
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().terms(terms).build(&mut state)?;
+        let Actors { alice, bob, mallory, .. } = actors();
+
+        scenario.process(EventType::IED, 1000)?;
+
+        scenario.simulator.set_timestamp(1100);
+        for actor in [alice, mallory] {
+            assert_rejected::<Option<SignedUnits>, _>(
+                &mut scenario,
+                actor,
+                "process_event",
+                (EventType::PP as u8, 1100u64),
+                ActusError::NotAuthorized,
+            )?;
+        }
+
+        scenario.simulator.set_actor(bob.address);
+        assert_eq!(scenario.process(EventType::PP, 1100)?, Some(500_000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn counterparty_cannot_change_terms_alone() -> Result<(), SimError> {
+        let Actors { bob, mallory, .. } = actors();
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+
+        scenario.process(EventType::IED, 1000)?;
+
+        let roll_over = (2_000u64, Some(900u64));
+        assert_rejected::<bool, _>(
+            &mut scenario,
+            mallory,
+            "roll_over",
+            roll_over,
+            ActusError::NotAuthorized,
+        )?;
+
+        // the debtor's proposal is only recorded as consent
+        let before = scenario.state()?;
+        scenario.simulator.set_actor(bob.address);
+        let applied = scenario
+            .call::<bool, _>("roll_over", roll_over)?
+            .expect("roll_over failed");
+
+        assert!(!applied);
+        assert_eq!(scenario.state()?, before);
+
+        let next = scenario
+            .call::<Option<(EventType, Timestamp)>, _>("get_next_event", ())?
+            .expect("get_next_event failed");
+        assert_eq!(next, Some((EventType::MD, 1300)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_creditor_can_write_off() -> Result<(), SimError> {
+        let Actors { bob, mallory, .. } = actors();
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+
+        scenario.process(EventType::IED, 1000)?;
+        scenario.process(EventType::CE, 1100)?;
+
+        for actor in [bob, mallory] {
+            assert_rejected::<Units, _>(
+                &mut scenario,
+                actor,
+                "write_off",
+                1100u64,
+                ActusError::NotAuthorized,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stranger_cannot_delegate_settlement() -> Result<(), SimError> {
+        let mallory = actors().mallory;
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+
+        assert_rejected::<(), _>(
+            &mut scenario,
+            mallory,
+            "delegate_settlement",
+            mallory.address,
+            ActusError::NotAuthorized,
+        )
+    }
+
+    #[test]
+    fn only_token_owner_can_mint() -> Result<(), SimError> {
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+        let token = scenario.token;
+        let Actors {
+            alice,
+            bob,
+            mallory,
+            token_owner,
+            ..
+        } = actors();
+
+        let mint = |scenario: &mut Scenario, actor: Actor| {
+            scenario.simulator.set_actor(actor.address);
+            scenario
+                .simulator
+                .call_contract::<(), _>(token, "mint", (actor.address, 1_000u64), MAX_GAS)
+        };
+
+        let before = scenario.token_balance(alice.address)?;
+        for actor in [alice, bob, mallory] {
+            assert!(mint(&mut scenario, actor).is_err(), "{actor} minted");
+        }
+        assert_eq!(scenario.token_balance(alice.address)?, before);
+
+        mint(&mut scenario, token_owner)?;
+        assert_eq!(scenario.token_balance(token_owner.address)?, 1_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stranger_cannot_take_a_share() -> Result<(), SimError> {
+        let mut state = SimpleState::new();
+        let mut scenario = Scenario::builder().build(&mut state)?;
+        let Actors { alice, mallory, .. } = actors();
+
+        assert_rejected::<(), _>(
+            &mut scenario,
+            mallory,
+            "fractionalize",
+            (vec![(mallory.address, SHARE_DENOMINATOR)],),
+            ActusError::NotAuthorized,
+        )?;
+
+        assert_rejected::<(), _>(
+            &mut scenario,
+            mallory,
+            "transfer_share",
+            (alice.address, SHARE_DENOMINATOR),
+            ActusError::ValidationError("Actor holds no share".into()),
+        )
+    }
 }
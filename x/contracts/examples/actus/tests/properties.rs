@@ -0,0 +1,105 @@
+// x/contracts/examples/actus/tests/properties.rs
+
+//! Invariants of the transition engine over generated terms and event sequences.
+//! Failing cases shrink towards the smallest terms and shortest sequence that still fail.
+
+mod strategies;
+
+use actus::core::{ContractState, EventType, SignedUnits, Timestamp, TransitionEngine};
+use proptest::prelude::*;
+use strategies::{scenario, Scenario};
+
+/// A processed event with its payoff and the state right after it
+struct Step {
+    event: EventType,
+    time: Timestamp,
+    payoff: Option<SignedUnits>,
+    state: ContractState,
+}
+
+/// Processes the scenario's events from the initial state, failing on the first error.
+/// Stops once the principal is repaid, as a LAM can be fully redeemed before maturity.
+fn replay(scenario: &Scenario) -> Result<Vec<Step>, TestCaseError> {
+    let terms = &scenario.terms;
+    let mut state = ContractState::new(terms);
+    let mut steps = Vec::new();
+
+    for &(event, time) in &scenario.events {
+        if !steps.is_empty() && state.notional_principal == 0 {
+            break;
+        }
+
+        let payoff = TransitionEngine::process_event(event, time, &mut state, terms)
+            .map_err(|err| TestCaseError::fail(format!("{event:?} at {time} failed: {err}")))?;
+
+        steps.push(Step {
+            event,
+            time,
+            payoff,
+            state: state.clone(),
+        });
+    }
+
+    Ok(steps)
+}
+
+proptest! {
+    // notional is unsigned, so going negative would surface as an error or an underflow
+    // panic; it also never exceeds the amount exchanged at IED
+    #[test]
+    fn notional_stays_within_initial_exchange(scenario in scenario()) {
+        let notional = scenario.terms.notional_principal.unwrap();
+
+        for step in replay(&scenario)? {
+            prop_assert!(
+                step.state.notional_principal <= notional,
+                "{:?} at {} left notional {} above {notional}",
+                step.event,
+                step.time,
+                step.state.notional_principal
+            );
+        }
+    }
+
+    #[test]
+    fn interest_payment_clears_accrued_interest(scenario in scenario()) {
+        for step in replay(&scenario)?.iter().filter(|step| step.event == EventType::IP) {
+            prop_assert_eq!(step.state.accrued_interest, 0, "IP at {}", step.time);
+        }
+    }
+
+    #[test]
+    fn status_date_never_decreases(scenario in scenario()) {
+        let mut status_date = scenario.terms.status_date;
+
+        for step in replay(&scenario)? {
+            prop_assert!(step.state.status_date >= status_date, "{:?} at {}", step.event, step.time);
+            prop_assert_eq!(step.state.status_date, step.time);
+            status_date = step.state.status_date;
+        }
+    }
+
+    // interest is settled by the IP at maturity, so only principal is left for MD
+    #[test]
+    fn redemptions_repay_initial_notional(scenario in scenario()) {
+        let repaid: SignedUnits = replay(&scenario)?
+            .iter()
+            .filter(|step| matches!(step.event, EventType::PR | EventType::MD))
+            .filter_map(|step| step.payoff)
+            .sum();
+
+        prop_assert_eq!(repaid, scenario.terms.notional_principal.unwrap() as SignedUnits);
+    }
+
+    #[test]
+    fn replay_is_deterministic(scenario in scenario()) {
+        let first = replay(&scenario)?;
+        let second = replay(&scenario)?;
+
+        prop_assert_eq!(first.len(), second.len());
+        for (first, second) in first.iter().zip(&second) {
+            prop_assert_eq!(first.payoff, second.payoff);
+            prop_assert_eq!(&first.state, &second.state);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// x/contracts/examples/actus/tests/reference.rs
+
+//! Replays ACTUS reference test vectors through the transition engine.
+//!
+//! Each fixture under `tests/fixtures/<contract type>/` holds the contract terms, in the
+//! serde form of [`ContractTerms`], and the expected event sequence. Every event is
+//! processed in order and its payoff and post-event state compared with the expectation,
+//! allowing a per-fixture absolute `tolerance` in the smallest currency unit. Year
+//! fractions are kept to four decimals, so an interest payoff can fall short of the exact
+//! value by up to notional × rate × 0.0001.
+
+use actus::core::{
+    ContractState, ContractTerms, EventType, Rate, SignedUnits, Timestamp, TransitionEngine, Units,
+};
+use serde::Deserialize;
+use std::{fmt::Write, fs, path::Path};
+
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+#[derive(Deserialize)]
+struct Fixture {
+    identifier: String,
+    #[serde(default)]
+    tolerance: Units,
+    terms: ContractTerms,
+    events: Vec<ExpectedEvent>,
+}
+
+/// An expected event; state variables that are not set are not checked
+#[derive(Deserialize)]
+struct ExpectedEvent {
+    event_type: EventType,
+    time: Timestamp,
+    #[serde(default)]
+    payoff: Option<SignedUnits>,
+    notional_principal: Option<Units>,
+    nominal_interest_rate: Option<Rate>,
+    accrued_interest: Option<SignedUnits>,
+}
+
+fn within(expected: SignedUnits, actual: SignedUnits, tolerance: Units) -> bool {
+    expected.abs_diff(actual) <= tolerance
+}
+
+/// Replays the fixture, returning a description of each divergence
+fn replay(fixture: &Fixture) -> Vec<String> {
+    let mut state = ContractState::new(&fixture.terms);
+    let mut divergences = Vec::new();
+
+    for (index, expected) in fixture.events.iter().enumerate() {
+        let event = format!("event {index} ({:?} at {})", expected.event_type, expected.time);
+
+        let payoff = match TransitionEngine::process_event(
+            expected.event_type,
+            expected.time,
+            &mut state,
+            &fixture.terms,
+        ) {
+            Ok(payoff) => payoff,
+            Err(err) => {
+                // later events depend on this one, so stop here
+                divergences.push(format!("{event}: failed with {err}"));
+                break;
+            }
+        };
+
+        let payoff_matches = match (expected.payoff, payoff) {
+            (Some(expected), Some(actual)) => within(expected, actual, fixture.tolerance),
+            (expected, actual) => expected == actual,
+        };
+        if !payoff_matches {
+            divergences.push(format!(
+                "{event}: payoff expected {:?}, got {payoff:?}",
+                expected.payoff
+            ));
+        }
+
+        let variables = [
+            (
+                "notional_principal",
+                expected.notional_principal.map(|notional| notional as SignedUnits),
+                state.notional_principal as SignedUnits,
+            ),
+            (
+                "nominal_interest_rate",
+                expected.nominal_interest_rate.map(SignedUnits::from),
+                state.nominal_interest_rate.into(),
+            ),
+            ("accrued_interest", expected.accrued_interest, state.accrued_interest),
+        ];
+
+        for (name, expected, actual) in variables {
+            if let Some(expected) = expected.filter(|&e| !within(e, actual, fixture.tolerance)) {
+                divergences.push(format!("{event}: {name} expected {expected}, got {actual}"));
+            }
+        }
+    }
+
+    divergences
+}
+
+fn load_fixtures(dir: &Path) -> Vec<(String, Fixture)> {
+    let mut paths = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", dir.display()))
+        .map(|entry| entry.expect("failed to read fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let bytes = fs::read(&path).expect("failed to read fixture");
+            let fixture = serde_json::from_slice(&bytes)
+                .unwrap_or_else(|err| panic!("invalid fixture {}: {err}", path.display()));
+            (path.display().to_string(), fixture)
+        })
+        .collect()
+}
+
+fn check_fixtures(contract_type: &str) {
+    let fixtures = load_fixtures(&Path::new(FIXTURE_DIR).join(contract_type));
+    assert!(!fixtures.is_empty(), "no {contract_type} fixtures found");
+
+    let mut report = String::new();
+
+    for (path, fixture) in &fixtures {
+        for divergence in replay(fixture) {
+            writeln!(report, "{} ({path}): {divergence}", fixture.identifier).unwrap();
+        }
+    }
+
+    assert!(report.is_empty(), "reference vectors diverged:\n{report}");
+}
+
+#[test]
+fn pam_reference_vectors() {
+    check_fixtures("pam");
+}
+
+#[test]
+fn divergences_name_the_event() {
+    let path = Path::new(FIXTURE_DIR).join("pam/pam03.json");
+    let mut fixture: Fixture = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    fixture.events[2].payoff = Some(7_400);
+
+    assert_eq!(
+        replay(&fixture),
+        vec![format!(
+            "event 2 (IP at {}): payoff expected Some(7400), got Some(7500)",
+            fixture.events[2].time
+        )]
+    );
+}
@@ -0,0 +1,225 @@
+// x/contracts/examples/actus/tests/strategies/mod.rs
+
+//! Proptest generators for contract terms, cycles and event sequences.
+//!
+//! [`scenario`] draws valid PAM or LAM terms together with the events a holder
+//! would process on them: the IED, the scheduled interest and principal dates, a few
+//! unscheduled IP and AD events in between, and an IP followed by the MD at maturity.
+//! Events are in chronological order, so every one of them is accepted by the engine.
+
+use actus::{
+    core::{
+        ContractTerms, ContractType, Cycle, DayCountConvention, EventType, Period, Rate, Stub,
+        TimeGranularity, Timestamp, Units,
+    },
+    math::{self, SECONDS_PER_DAY},
+};
+use proptest::{option, prelude::*, sample::select};
+
+/// Longest contract generated; keeps the schedules short enough to replay quickly
+const MAX_TERM_MONTHS: u64 = 120;
+const MAX_NOTIONAL: Units = 1_000_000_000_000;
+/// 20% in basis points
+const MAX_RATE: Rate = 2_000;
+const MAX_EXTRA_EVENTS: usize = 8;
+
+/// Valid terms and an event sequence to process on them
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub terms: ContractTerms,
+    pub events: Vec<(EventType, Timestamp)>,
+}
+
+pub fn period() -> impl Strategy<Value = Period> {
+    select(vec![
+        Period::P_D,
+        Period::P_W,
+        Period::P_M,
+        Period::P_Q,
+        Period::P_H,
+        Period::P_Y,
+    ])
+}
+
+/// A positive cycle of at most a few years
+pub fn cycle() -> impl Strategy<Value = Cycle> {
+    (period(), any::<bool>(), any::<bool>()).prop_flat_map(|(p, long_stub, include_end_day)| {
+        let max_n: i64 = match p {
+            Period::P_D => 90,
+            Period::P_W => 12,
+            Period::P_M => 12,
+            Period::P_Q => 4,
+            Period::P_H | Period::P_Y => 2,
+        };
+        let stub = if long_stub {
+            Stub::LongStub
+        } else {
+            Stub::ShortStub
+        };
+
+        (1..=max_n).prop_map(move |n| Cycle {
+            n,
+            p,
+            stub,
+            include_end_day,
+        })
+    })
+}
+
+pub fn day_count_convention() -> impl Strategy<Value = DayCountConvention> {
+    select(vec![
+        DayCountConvention::DCC_A_AISDA,
+        DayCountConvention::DCC_A_360,
+        DayCountConvention::DCC_A_365,
+        DayCountConvention::DCC_E30_360ISDA,
+        DayCountConvention::DCC_E30_360,
+        DayCountConvention::DCC_B_252,
+    ])
+}
+
+/// A midnight timestamp between 2000 and 2049
+pub fn date() -> impl Strategy<Value = Timestamp> {
+    (2000u64..2050, 1u64..=12, 1u64..=31).prop_map(|(year, month, day)| {
+        math::timestamp_from_civil(year, month, day.min(math::days_in_civil_month(year, month)))
+    })
+}
+
+/// Terms with everything the engine requires for `contract_type`. Cycles are anchored
+/// at most a year after the IED; LAM terms always carry a principal redemption cycle.
+pub fn terms(contract_type: ContractType) -> impl Strategy<Value = ContractTerms> {
+    let amortizing = contract_type == ContractType::LAM;
+
+    (
+        date(),
+        1..=MAX_TERM_MONTHS,
+        1..=MAX_NOTIONAL,
+        0..=MAX_RATE,
+        option::of(day_count_convention()),
+        option::of((1..=365u64, cycle())),
+        (1..=365u64, cycle(), 1..=10_000u64),
+    )
+        .prop_map(
+            move |(ied, months, notional, rate, dcc, ip, (pr_days, pr_cycle, pr_share))| {
+                let maturity = math::add_months(ied, months).expect("maturity out of range");
+                let anchor = |days: u64| ied + days * SECONDS_PER_DAY;
+
+                let mut terms = ContractTerms {
+                    contract_type,
+                    status_date: ied,
+                    initial_exchange_date: Some(ied),
+                    maturity_date: Some(maturity),
+                    notional_principal: Some(notional),
+                    nominal_interest_rate: Some(rate),
+                    day_count_convention: dcc,
+                    cycle_anchor_date_of_interest_payment: ip
+                        .as_ref()
+                        .map(|(days, _)| anchor(*days)),
+                    cycle_of_interest_payment: ip.map(|(_, cycle)| cycle),
+                    ..Default::default()
+                };
+
+                if amortizing {
+                    // each redemption repays a share, in basis points, of the notional
+                    let payment =
+                        notional * Units::from(pr_share) / Units::from(math::BASIS_POINT_FACTOR);
+                    terms.cycle_anchor_date_of_principal_redemption = Some(anchor(pr_days));
+                    terms.cycle_of_principal_redemption = Some(pr_cycle);
+                    terms.next_principal_redemption_payment = Some(payment.max(1));
+                }
+
+                terms
+            },
+        )
+}
+
+/// Dates of the cycle anchored at `anchor` that fall strictly between `start` and `end`
+pub fn cycle_dates(
+    anchor: Timestamp,
+    cycle: &Cycle,
+    start: Timestamp,
+    end: Timestamp,
+) -> Vec<Timestamp> {
+    std::iter::successors(
+        cycle.next_after(anchor, start, TimeGranularity::Seconds),
+        |date| cycle.next_after(anchor, *date, TimeGranularity::Seconds),
+    )
+    .take_while(|date| *date < end)
+    .collect()
+}
+
+/// The IED, scheduled IP and PR dates and MD of `terms`, with an IP at maturity so the
+/// MD repays principal only. At equal times IP precedes PR, as in the ACTUS sequence.
+pub fn schedule(terms: &ContractTerms) -> Vec<(EventType, Timestamp)> {
+    let ied = terms
+        .initial_exchange_date
+        .expect("generated terms have an IED");
+    let maturity = terms
+        .maturity_date
+        .expect("generated terms have a maturity");
+
+    let mut events = vec![(EventType::IED, ied)];
+
+    let cycles = [
+        (
+            EventType::IP,
+            terms.cycle_anchor_date_of_interest_payment,
+            terms.cycle_of_interest_payment.as_ref(),
+        ),
+        (
+            EventType::PR,
+            terms
+                .cycle_anchor_date_of_principal_redemption
+                .filter(|_| terms.contract_type == ContractType::LAM),
+            terms.cycle_of_principal_redemption.as_ref(),
+        ),
+    ];
+
+    for (event, anchor, cycle) in cycles {
+        if let (Some(anchor), Some(cycle)) = (anchor, cycle) {
+            events.extend(
+                cycle_dates(anchor, cycle, ied, maturity)
+                    .into_iter()
+                    .map(|date| (event, date)),
+            );
+        }
+    }
+
+    events.extend([(EventType::IP, maturity), (EventType::MD, maturity)]);
+    sort_events(&mut events);
+    events
+}
+
+/// Orders events by time, keeping the ACTUS order of events at the same time
+pub fn sort_events(events: &mut [(EventType, Timestamp)]) {
+    let rank = |event: EventType| match event {
+        EventType::IED => 0,
+        EventType::AD => 1,
+        EventType::IP => 2,
+        EventType::PR => 3,
+        _ => 4,
+    };
+    events.sort_by_key(|&(event, time)| (time, rank(event)));
+}
+
+/// Terms of either supported type with their schedule, plus unscheduled IP and AD
+/// events strictly between the IED and maturity
+pub fn scenario() -> impl Strategy<Value = Scenario> {
+    prop_oneof![terms(ContractType::PAM), terms(ContractType::LAM)].prop_flat_map(|terms| {
+        let ied = terms.initial_exchange_date.unwrap();
+        let maturity = terms.maturity_date.unwrap();
+        let extra = (
+            select(vec![EventType::IP, EventType::AD]),
+            ied + 1..maturity,
+        );
+
+        proptest::collection::vec(extra, 0..=MAX_EXTRA_EVENTS).prop_map(move |extra| {
+            let mut events = schedule(&terms);
+            events.extend(extra);
+            sort_events(&mut events);
+            Scenario {
+                terms: terms.clone(),
+                events,
+            }
+        })
+    })
+}
@@ -0,0 +1,306 @@
+// x/contracts/examples/actus/tests/stress.rs
+
+//! Hundreds of positions sharing one simulator state, each checked against its own
+//! off-chain run, and positions run on threads of their own. The timings and fuel are printed with
+//! `cargo test -p actus --test stress -- --nocapture`.
+
+use actus::{
+    core::{
+        ContractState, ContractTerms, Cycle, DayCountConvention, Error as ActusError, EventType,
+        Period, Rate, SignedUnits, Stub, TransitionEngine, Units,
+    },
+    math::{AMOUNT_DECIMALS, SECONDS_PER_DAY},
+    testutils::{
+        addresses, pam_terms, token_path, Scenario, CONTRACT_PATH, DEFAULT_FUNDING, MAX_GAS,
+    },
+};
+use std::{
+    collections::HashSet,
+    thread,
+    time::{Duration, Instant},
+};
+use wasmlanche::{
+    simulator::{Error as SimError, SimpleState, Simulator},
+    Address,
+};
+
+const POSITIONS: u64 = 500;
+/// Share of the interest payments averaged to compare the fuel of the first and last
+/// deployed positions
+const SAMPLE: usize = 100;
+/// Positions run at once, each on its own thread and simulator state
+const THREADS: u64 = 8;
+
+/// A deployed position and what its off-chain run expects of it
+struct Position {
+    contract: Address,
+    creditor: Address,
+    debtor: Address,
+    terms: ContractTerms,
+    expected: ContractState,
+    interest: SignedUnits,
+    ip_time: u64,
+}
+
+/// A fixed sequence of well-mixed salts, so a failing position can be rerun alone
+fn salt(index: u64) -> u64 {
+    let mut z = (index + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z ^ (z >> 31)
+}
+
+/// A year-long PAM whose notional, rate, identifier and payment date all derive from
+/// `salt`, so two positions reading each other's storage disagree on every field
+fn salted_terms(index: u64, salt: u64) -> ContractTerms {
+    ContractTerms {
+        contract_id: format!("stress-{index}-{salt:016x}").as_str().into(),
+        initial_exchange_date: Some(0),
+        maturity_date: Some(365 * SECONDS_PER_DAY),
+        notional_principal: Some(Units::from(100_000 + salt % 900_000)),
+        nominal_interest_rate: Some(100 + (salt >> 20) as Rate % 900),
+        day_count_convention: Some(DayCountConvention::DCC_A_365),
+        status_date: 0,
+        ..pam_terms()
+    }
+}
+
+fn get_state(simulator: &Simulator, contract: Address) -> Result<ContractState, SimError> {
+    simulator
+        .call_contract::<Result<ContractState, ActusError>, _>(contract, "get_state", (), MAX_GAS)
+        .map(|state| state.expect("get_state failed"))
+}
+
+#[test]
+fn positions_sharing_a_state_keep_their_own_storage() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+    let mut accounts = addresses(0x0578_5E55);
+    let token_owner = accounts.next().unwrap();
+
+    simulator.set_actor(token_owner);
+    let token = simulator.create_contract(token_path())?;
+    simulator.call_contract::<(), _>(
+        token.address,
+        "init",
+        ("TestToken".to_string(), "TT".to_string(), AMOUNT_DECIMALS),
+        MAX_GAS,
+    )?;
+
+    // the positions are instances of one contract, so its wasm is only compiled once
+    let template = simulator.create_contract(CONTRACT_PATH)?;
+    let started = Instant::now();
+    let mut positions = Vec::new();
+
+    for index in 0..POSITIONS {
+        let salt = salt(index);
+        let terms = salted_terms(index, salt);
+        let (creditor, debtor) = (accounts.next().unwrap(), accounts.next().unwrap());
+
+        simulator.set_actor(token_owner);
+        for party in [creditor, debtor] {
+            simulator.call_contract::<(), _>(
+                token.address,
+                "mint",
+                (party, DEFAULT_FUNDING),
+                MAX_GAS,
+            )?;
+        }
+
+        let contract = simulator
+            .deploy_contract(&template.id, &salt.to_be_bytes())?
+            .address;
+        for party in [creditor, debtor] {
+            simulator.set_actor(party);
+            simulator.call_contract::<(), _>(
+                token.address,
+                "approve",
+                (contract, DEFAULT_FUNDING),
+                MAX_GAS,
+            )?;
+        }
+
+        simulator.set_actor(creditor);
+        simulator
+            .call_contract::<Result<(), ActusError>, _>(
+                contract,
+                "init",
+                (
+                    terms.contract_type as u8,
+                    terms.contract_role as u8,
+                    token.address,
+                    debtor,
+                    token.id.clone(),
+                    terms.to_versioned_bytes(),
+                ),
+                MAX_GAS,
+            )?
+            .unwrap_or_else(|error| panic!("init of position {index} failed: {error}"));
+
+        simulator.set_timestamp(0);
+        simulator
+            .call_contract::<Result<Option<SignedUnits>, ActusError>, _>(
+                contract,
+                "process_event",
+                (EventType::IED as u8, 0u64),
+                MAX_GAS,
+            )?
+            .unwrap_or_else(|error| panic!("IED of position {index} failed: {error}"));
+
+        let ip_time = (30 + (salt >> 40) % 300) * SECONDS_PER_DAY;
+        let mut expected = ContractState::new(&terms);
+        TransitionEngine::process_event(EventType::IED, 0, &mut expected, &terms).unwrap();
+        let interest =
+            TransitionEngine::process_event(EventType::IP, ip_time, &mut expected, &terms)
+                .unwrap()
+                .expect("an IP pays the accrued interest");
+
+        positions.push(Position {
+            contract,
+            creditor,
+            debtor,
+            terms,
+            expected,
+            interest,
+            ip_time,
+        });
+    }
+    let deployed = started.elapsed();
+
+    // every position is live before any pays, so a shared key would be overwritten by
+    // a later deployment
+    let started = Instant::now();
+    let mut fuel = Vec::new();
+
+    for (index, position) in positions.iter().enumerate() {
+        simulator.set_actor(position.creditor);
+        simulator.set_timestamp(position.ip_time);
+        let payoff = simulator
+            .call_contract::<Result<Option<SignedUnits>, ActusError>, _>(
+                position.contract,
+                "process_event",
+                (EventType::IP as u8, position.ip_time),
+                MAX_GAS,
+            )?
+            .unwrap_or_else(|error| panic!("IP of position {index} failed: {error}"));
+        fuel.push(simulator.last_call_units());
+
+        assert_eq!(payoff, Some(position.interest), "IP of position {index}");
+    }
+    let paid = started.elapsed();
+
+    let contracts: HashSet<_> = positions.iter().map(|position| position.contract).collect();
+    assert_eq!(contracts.len(), positions.len());
+
+    let funding = Units::from(DEFAULT_FUNDING);
+    let balance = |account| {
+        simulator
+            .balance_of(token.address, account)
+            .map(Units::from)
+    };
+
+    for (index, position) in positions.iter().enumerate() {
+        let state = get_state(&simulator, position.contract)?;
+        assert_eq!(state, position.expected, "state of position {index}");
+
+        let notional = position.terms.notional_principal.unwrap();
+        let interest = Units::try_from(position.interest).unwrap();
+        assert_eq!(
+            balance(position.creditor)?,
+            funding - notional + interest,
+            "creditor of position {index}"
+        );
+        assert_eq!(
+            balance(position.debtor)?,
+            funding + notional - interest,
+            "debtor of position {index}"
+        );
+    }
+
+    let mean = |fuel: &[u64]| fuel.iter().sum::<u64>() / fuel.len() as u64;
+    let (first, last) = (mean(&fuel[..SAMPLE]), mean(&fuel[fuel.len() - SAMPLE..]));
+    let per_position = |elapsed: Duration| elapsed / POSITIONS as u32;
+    println!(
+        "{POSITIONS} positions: deployed in {deployed:?} ({:?} each), paid in {paid:?} \
+         ({:?} each), {} fuel in total, {first} per IP of the first {SAMPLE} and {last} \
+         of the last",
+        per_position(deployed),
+        per_position(paid),
+        fuel.iter().sum::<u64>(),
+    );
+
+    // fuel only depends on the position, so a state holding more contracts must not make
+    // an IP of the last one dearer
+    assert!(
+        last.abs_diff(first) * 100 < first,
+        "IPs of the last positions use {last} fuel, {first} for the first ones"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn deploying_needs_a_created_contract() -> Result<(), SimError> {
+    let mut state = SimpleState::new();
+    let simulator = Simulator::new(&mut state);
+
+    let template = simulator.create_contract(CONTRACT_PATH)?;
+    let first = simulator.deploy_contract(&template.id, b"salt")?;
+    assert_eq!(first.id, template.id);
+    assert_ne!(first.address, template.address);
+    assert_ne!(
+        simulator.deploy_contract(&template.id, b"other")?.address,
+        first.address
+    );
+
+    assert!(matches!(
+        simulator.deploy_contract(&[7; 32], b"salt"),
+        Err(SimError::CreateContract(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_runs_match_their_serial_ones() -> Result<(), SimError> {
+    let mut states: Vec<_> = (0..THREADS).map(|_| SimpleState::new()).collect();
+    let mut forks = Vec::new();
+    let mut serial = Vec::new();
+
+    for (index, state) in (0..).zip(&mut states) {
+        let salt = salt(index);
+        let terms = ContractTerms {
+            cycle_anchor_date_of_interest_payment: Some((20 + salt % 60) * SECONDS_PER_DAY),
+            cycle_of_interest_payment: Some(Cycle {
+                n: 1,
+                p: Period::P_M,
+                stub: Stub::ShortStub,
+                include_end_day: false,
+            }),
+            ..salted_terms(index, salt)
+        };
+        let mut scenario = Scenario::builder().terms(terms).build(state)?;
+
+        // the fork is taken before the serial run moves the original on
+        forks.push(scenario.fork());
+        let horizon = scenario.terms.maturity_date.unwrap();
+        serial.push(scenario.drive(horizon)?);
+    }
+
+    let runs: Vec<_> = forks
+        .into_iter()
+        .map(|mut scenario| {
+            thread::spawn(move || {
+                let horizon = scenario.terms.maturity_date.unwrap();
+                scenario.drive(horizon)
+            })
+        })
+        .collect();
+
+    for (index, (run, serial)) in runs.into_iter().zip(&serial).enumerate() {
+        let run = run.join().expect("the run panicked")?;
+        assert!(serial.len() > 2, "position {index} pays interest");
+        assert_eq!(&run, serial, "run of position {index}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,545 @@
+//! Deploys a settlement token and an ACTUS contract in the simulator for tests.
+//!
+//! [`Scenario::builder`] funds both parties, has them approve the contract to move
+//! their tokens and initializes the contract with the given terms, so a test only
+//! describes the events it processes:
+//!
+//! ```ignore
+//! let mut state = SimpleState::new();
+//! let mut scenario = Scenario::builder().build(&mut state)?;
+//! scenario.process(EventType::IED, 1000)?;
+//! assert_eq!(scenario.state()?.notional_principal, 500_000);
+//! ```
+
+use crate::{
+    core::{
+        ContractAddress, ContractId, ContractPhase, ContractRole, ContractState, ContractTerms,
+        ContractType, Error, EventType, ProcessEventResult, SignedUnits, Timestamp,
+        TransitionEngine,
+    },
+    math::{AMOUNT_DECIMALS, SECONDS_PER_DAY},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::fmt;
+use wasmlanche::{
+    simulator::{Error as SimError, SimpleState, Simulator},
+    Address,
+};
+
+pub const CONTRACT_PATH: &str = crate::contract_path();
+pub const MAX_GAS: u64 = 1_000_000_000;
+/// Tokens minted to each party, in base units of the settlement token, all of which
+/// the contract is approved to move
+pub const DEFAULT_FUNDING: token::Units = 10_000_000;
+
+/// Seed of the addresses [`actors`] hands out
+pub const ACTOR_SEED: u64 = 0x00AC_7005;
+
+/// A test account, shown by its name and the start of its address in assertion failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Actor {
+    pub name: &'static str,
+    pub address: Address,
+}
+
+impl Actor {
+    /// The address as the terms name an account, such as a `market_object_provider`
+    pub fn contract_address(&self) -> ContractAddress {
+        ContractAddress(self.address.as_ref().try_into().expect("addresses have one length"))
+    }
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, ..] = self.address.as_ref() else {
+            unreachable!("addresses are longer than four bytes")
+        };
+        write!(f, "{} (0x{a:02x}{b:02x}{c:02x}{d:02x})", self.name)
+    }
+}
+
+/// The named accounts of a test, each of its own address
+#[derive(Debug, Clone, Copy)]
+pub struct Actors {
+    pub alice: Actor,
+    pub bob: Actor,
+    pub carol: Actor,
+    pub oracle: Actor,
+    pub keeper: Actor,
+    pub mallory: Actor,
+    pub token_owner: Actor,
+}
+
+impl Actors {
+    /// The actors named after the first addresses generated from `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        let mut addresses = addresses(seed);
+        let mut next = |name| Actor {
+            name,
+            address: addresses.next().expect("addresses never run out"),
+        };
+
+        Self {
+            alice: next("alice"),
+            bob: next("bob"),
+            carol: next("carol"),
+            oracle: next("oracle"),
+            keeper: next("keeper"),
+            mallory: next("mallory"),
+            token_owner: next("token owner"),
+        }
+    }
+}
+
+/// The named accounts generated from [`ACTOR_SEED`]; the creditor of a built scenario
+/// is alice and the debtor bob
+pub fn actors() -> Actors {
+    Actors::with_seed(ACTOR_SEED)
+}
+
+/// Addresses generated from `seed`, the same on every run. Their words are successive
+/// outputs of a SplitMix64 stream, which never repeats one within 2^64 outputs, so no
+/// two addresses collide.
+pub fn addresses(seed: u64) -> impl Iterator<Item = Address> {
+    const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+    const WORDS: u64 = Address::LEN.div_ceil(8) as u64;
+    let mix = |mut z: u64| {
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+
+    (0u64..).map(move |index| {
+        let mut bytes = [0; Address::LEN];
+        for (word, chunk) in (0u64..).zip(bytes.chunks_mut(8)) {
+            let position = index.wrapping_mul(WORDS).wrapping_add(word);
+            let value = mix(seed.wrapping_add(position.wrapping_mul(GAMMA))).to_le_bytes();
+            chunk.copy_from_slice(&value[..chunk.len()]);
+        }
+        Address::new(bytes)
+    })
+}
+
+/// The wasm of the settlement token, compiled by the build script of the token example
+pub fn token_path() -> &'static str {
+    token::contract_path()
+}
+
+/// A PAM position of 500_000 at 5% from t=1000 to t=1300
+pub fn pam_terms() -> ContractTerms {
+    ContractTerms {
+        contract_id: ContractId::from("pam-contract"),
+        contract_type: ContractType::PAM,
+        contract_role: ContractRole::CR_RPA,
+        initial_exchange_date: Some(1000),
+        notional_principal: Some(500_000),
+        nominal_interest_rate: Some(500),
+        maturity_date: Some(1300),
+        status_date: 1000,
+        ..Default::default()
+    }
+}
+
+pub struct ScenarioBuilder {
+    terms: ContractTerms,
+    token_owner: Address,
+    creditor: Address,
+    debtor: Address,
+    funding: token::Units,
+    decimals: u8,
+}
+
+impl Default for ScenarioBuilder {
+    fn default() -> Self {
+        Self {
+            terms: pam_terms(),
+            token_owner: actors().token_owner.address,
+            creditor: actors().alice.address,
+            debtor: actors().bob.address,
+            funding: DEFAULT_FUNDING,
+            decimals: AMOUNT_DECIMALS,
+        }
+    }
+}
+
+impl ScenarioBuilder {
+    /// Replaces the terms, [`pam_terms`] by default
+    pub fn terms(mut self, terms: ContractTerms) -> Self {
+        self.terms = terms;
+        self
+    }
+
+    /// Overrides the contract type of the terms
+    pub fn contract_type(mut self, contract_type: ContractType) -> Self {
+        self.terms.contract_type = contract_type;
+        self
+    }
+
+    pub fn parties(mut self, creditor: Address, debtor: Address) -> Self {
+        self.creditor = creditor;
+        self.debtor = debtor;
+        self
+    }
+
+    /// Deploys the settlement token and mints the parties' funding
+    pub fn token_owner(mut self, token_owner: Address) -> Self {
+        self.token_owner = token_owner;
+        self
+    }
+
+    /// Tokens minted to each party, [`DEFAULT_FUNDING`] by default
+    pub fn funding(mut self, funding: token::Units) -> Self {
+        self.funding = funding;
+        self
+    }
+
+    /// Decimals of the settlement token, by default those of the terms so payoffs
+    /// settle unconverted
+    pub fn decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Deploys the token and the contract, leaving the creditor as the actor.
+    /// Panics if the contract rejects the terms.
+    pub fn build(self, state: &mut SimpleState) -> Result<Scenario<'_>, SimError> {
+        let Self {
+            terms,
+            token_owner,
+            creditor,
+            debtor,
+            funding,
+            decimals,
+        } = self;
+
+        let mut simulator = Simulator::new(state);
+        simulator.set_actor(token_owner);
+        let token = simulator.create_contract(token_path())?;
+
+        simulator.call_contract::<(), _>(
+            token.address,
+            "init",
+            ("TestToken".to_string(), "TT".to_string(), decimals),
+            MAX_GAS,
+        )?;
+
+        for party in [creditor, debtor] {
+            simulator.call_contract::<(), _>(token.address, "mint", (party, funding), MAX_GAS)?;
+        }
+
+        simulator.set_actor(creditor);
+        let contract = simulator.create_contract(CONTRACT_PATH)?.address;
+
+        for party in [creditor, debtor] {
+            simulator.set_actor(party);
+            simulator.call_contract::<(), _>(
+                token.address,
+                "approve",
+                (contract, funding),
+                MAX_GAS,
+            )?;
+        }
+
+        simulator.set_actor(creditor);
+        simulator
+            .call_contract::<Result<(), Error>, _>(
+                contract,
+                "init",
+                (
+                    terms.contract_type as u8,
+                    terms.contract_role as u8,
+                    token.address,
+                    debtor,
+                    token.id,
+                    terms.to_versioned_bytes(),
+                ),
+                MAX_GAS,
+            )?
+            .expect("init failed");
+
+        Ok(Scenario {
+            simulator,
+            contract,
+            token: token.address,
+            token_owner,
+            creditor,
+            debtor,
+            terms,
+            minted: 2 * funding,
+        })
+    }
+}
+
+/// A deployed contract and its settlement token
+pub struct Scenario<'a> {
+    pub simulator: Simulator<'a>,
+    pub contract: Address,
+    pub token: Address,
+    pub token_owner: Address,
+    pub creditor: Address,
+    pub debtor: Address,
+    pub terms: ContractTerms,
+    /// Tokens minted to the parties by [`ScenarioBuilder::build`]
+    pub minted: token::Units,
+}
+
+/// An event processed by [`Scenario::drive`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrivenEvent {
+    pub event: EventType,
+    pub time: Timestamp,
+    pub payoff: Option<SignedUnits>,
+    /// The contract state once the event is processed
+    pub state: ContractState,
+}
+
+impl Scenario<'_> {
+    pub fn builder() -> ScenarioBuilder {
+        ScenarioBuilder::default()
+    }
+
+    /// A copy of the scenario on a copy of its state, which runs apart from this one and
+    /// can be moved to another thread
+    pub fn fork(&self) -> Scenario<'static> {
+        Scenario {
+            simulator: self.simulator.fork(),
+            terms: self.terms.clone(),
+            ..*self
+        }
+    }
+
+    /// Calls `function` on the contract as the current actor
+    pub fn call<T, A>(&self, function: &str, args: A) -> Result<Result<T, Error>, SimError>
+    where
+        T: BorshDeserialize,
+        A: BorshSerialize,
+    {
+        self.simulator
+            .call_contract(self.contract, function, args, MAX_GAS)
+    }
+
+    /// Calls `function` on the contract as the current actor, surfacing the contract's
+    /// error as [`SimError::Contract`] with its stable code
+    pub fn try_call<T, A>(&self, function: &str, args: A) -> Result<T, SimError>
+    where
+        T: BorshDeserialize,
+        A: BorshSerialize,
+    {
+        self.simulator
+            .try_call_contract(self.contract, function, args, MAX_GAS)
+    }
+
+    /// Moves the block time to `time` and processes `event` at it.
+    /// Panics if the contract rejects the event.
+    pub fn process(
+        &mut self,
+        event: EventType,
+        time: Timestamp,
+    ) -> Result<Option<SignedUnits>, SimError> {
+        Ok(self
+            .try_process(event, time)?
+            .expect("event processing failed"))
+    }
+
+    /// Like [`Scenario::process`], returning the contract's error instead
+    pub fn try_process(
+        &mut self,
+        event: EventType,
+        time: Timestamp,
+    ) -> Result<Result<Option<SignedUnits>, Error>, SimError> {
+        let result = self.try_process_v2(event, time)?.map(|result| {
+            assert_eq!((result.event, result.timestamp), (event, time));
+            result.signed_payoff()
+        });
+
+        Ok(result)
+    }
+
+    /// Moves the block time to `time` and processes `event` at it with
+    /// `process_event_v2`, returning the contract's error if it rejects the event
+    pub fn try_process_v2(
+        &mut self,
+        event: EventType,
+        time: Timestamp,
+    ) -> Result<Result<ProcessEventResult, Error>, SimError> {
+        self.simulator.set_timestamp(time);
+        let result = self.call("process_event_v2", (event as u8, time))?;
+        println!(
+            "{event:?} consumed {} units",
+            self.simulator.last_call_units()
+        );
+
+        Ok(result)
+    }
+
+    /// Publishes `value` as the observation `event` reads at `time`, as the
+    /// `market_object_provider` of the terms, leaving the actor as it was.
+    /// Panics if the terms name no provider or the contract rejects the observation.
+    pub fn publish(
+        &mut self,
+        event: EventType,
+        time: Timestamp,
+        value: u64,
+    ) -> Result<(), SimError> {
+        let provider = self
+            .terms
+            .market_object_provider
+            .expect("the terms name no market object provider");
+        let actor = self.simulator.get_actor();
+        self.simulator.set_actor(Address::new(provider.0));
+        let published = self.call::<(), _>("observe", (event as u8, time, value));
+        self.simulator.set_actor(actor);
+
+        published.map(|published| published.expect("observe failed"))
+    }
+
+    /// Processes the events the contract schedules, in order and each at its own block
+    /// time, until it reports none or the next one falls after `horizon`.
+    /// Panics with the event and its position in the run if the contract rejects one,
+    /// does not report it due before processing it or still does after, or schedules the
+    /// same event again once it is processed.
+    pub fn drive(&mut self, horizon: Timestamp) -> Result<Vec<DrivenEvent>, SimError> {
+        let mut log: Vec<DrivenEvent> = Vec::new();
+        // the rest of the events of the date just processed, last first
+        let mut due = Vec::new();
+
+        loop {
+            let next = match due.pop() {
+                Some(next) => Some(next),
+                None => self.next_event()?,
+            };
+            let Some((event, time)) = next.filter(|(_, time)| *time <= horizon) else {
+                break;
+            };
+
+            if let Some(last) = log.last() {
+                assert!(
+                    (last.event, last.time) != (event, time),
+                    "event {} of the run: {event:?} at {time} is scheduled again",
+                    log.len()
+                );
+            }
+
+            assert_eq!(
+                self.is_event_due(event, time)?,
+                (true, Some(time)),
+                "event {} of the run: {event:?} at {time} is not reported due",
+                log.len()
+            );
+
+            let payoff = self.try_process(event, time)?.unwrap_or_else(|error| {
+                panic!(
+                    "event {} of the run: {event:?} at {time} failed: {error}",
+                    log.len()
+                )
+            });
+
+            let (still_due, next) = self.is_event_due(event, time)?;
+            assert!(
+                !still_due && next.is_none_or(|next| next > time),
+                "event {} of the run: {event:?} at {time} is still due at {next:?}",
+                log.len()
+            );
+
+            log.push(DrivenEvent {
+                event,
+                time,
+                payoff,
+                state: self.state()?,
+            });
+
+            if due.is_empty() {
+                due = self.due_after(event, time);
+                due.reverse();
+            }
+        }
+
+        Ok(log)
+    }
+
+    /// The cyclic events that fall on `time` after `event` in the order of
+    /// [`TransitionEngine::cycles`]. The contract only schedules events after its status
+    /// date, so it does not report them once `event` is processed.
+    fn due_after(&self, event: EventType, time: Timestamp) -> Vec<(EventType, Timestamp)> {
+        if self.terms.closing().1.is_some_and(|closing| time >= closing) {
+            return Vec::new();
+        }
+
+        let cycles = TransitionEngine::cycles(&self.terms);
+        // a tranche comes before the cyclic events of its date
+        let first = match cycles.iter().position(|(cyclic, ..)| *cyclic == event) {
+            Some(position) => position + 1,
+            None if event == EventType::PI => 0,
+            None => cycles.len(),
+        };
+
+        cycles[first..]
+            .iter()
+            .filter(|(_, anchor, cycle)| match (anchor, cycle) {
+                (Some(anchor), Some(cycle)) => {
+                    time.checked_sub(1).and_then(|after| {
+                        cycle.next_after(*anchor, after, self.terms.granularity())
+                    }) == Some(time)
+                }
+                (Some(anchor), None) => *anchor == time,
+                _ => false,
+            })
+            .filter(|(cyclic, ..)| !self.terms.skips(*cyclic, time))
+            .map(|(cyclic, ..)| (*cyclic, time))
+            .collect()
+    }
+
+    /// Whether `event` has an occurrence due by `as_of`, and when it is next due
+    pub fn is_event_due(
+        &self,
+        event: EventType,
+        as_of: Timestamp,
+    ) -> Result<(bool, Option<Timestamp>), SimError> {
+        self.call("is_event_due", (event as u8, as_of))
+            .map(|due| due.expect("is_event_due failed"))
+    }
+
+    pub fn next_event(&self) -> Result<Option<(EventType, Timestamp)>, SimError> {
+        self.call("get_next_event", ())
+            .map(|next| next.expect("get_next_event failed"))
+    }
+
+    pub fn state(&self) -> Result<ContractState, SimError> {
+        self.call("get_state", ())
+            .map(|state| state.expect("get_state failed"))
+    }
+
+    pub fn phase(&self) -> Result<ContractPhase, SimError> {
+        self.call("get_phase", ())
+            .map(|phase| phase.expect("get_phase failed"))
+    }
+
+    pub fn token_balance(&self, account: Address) -> Result<token::Units, SimError> {
+        self.simulator.balance_of(self.token, account)
+    }
+
+    /// Asserts that `accounts` and the contract together hold every token minted to
+    /// the parties, so settlement moved tokens between them without creating or
+    /// destroying any. `accounts` must include each party once.
+    pub fn assert_conservation(&self, accounts: &[Address]) -> Result<(), SimError> {
+        let mut total = self.token_balance(self.contract)?;
+        for account in accounts {
+            total += self.token_balance(*account)?;
+        }
+
+        assert_eq!(
+            total, self.minted,
+            "tokens were created or destroyed: {accounts:?} and the contract hold {total}"
+        );
+        Ok(())
+    }
+
+    pub fn now(&self) -> Timestamp {
+        self.simulator.get_timestamp()
+    }
+
+    /// Moves the block time forward by whole days
+    pub fn advance(&mut self, days: u64) {
+        self.simulator.advance_time(days * SECONDS_PER_DAY);
+    }
+}
@@ -45,6 +45,7 @@ pub fn init(
         liquidity_context,
         String::from("liquidity token"),
         String::from("LT"),
+        9,
     );
 
     context
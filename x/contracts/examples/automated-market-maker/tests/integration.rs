@@ -322,10 +322,10 @@ fn init_amm(simulator: &mut Simulator) -> (Address, Address, Address, Address) {
 
     // initialize tokens
     simulator
-        .call_contract::<(), _>(token_x, "init", ("CoinX", "CX"), MAX_GAS)
+        .call_contract::<(), _>(token_x, "init", ("CoinX", "CX", 9u8), MAX_GAS)
         .unwrap();
     simulator
-        .call_contract::<(), _>(token_y, "init", ("YCoin", "YC"), MAX_GAS)
+        .call_contract::<(), _>(token_y, "init", ("YCoin", "YC", 9u8), MAX_GAS)
         .unwrap();
     let amm_contract = simulator.create_contract(CONTRACT_PATH).unwrap().address;
 
@@ -1,10 +1,37 @@
 // Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
 // See the file LICENSE for licensing terms.
 
-use wasmlanche::{public, state_schema, Address, Context};
+use wasmlanche::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    public, state_schema, Address, Context,
+};
 
 pub type Units = u64;
 
+/// Path of the wasm the build script compiled the token to, for tests of host builds to
+/// deploy it without knowing where the build directory is
+///
+/// # Panics
+/// In builds the script does not compile the wasm for, such as fuzzing ones
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub const fn contract_path() -> &'static str {
+    match option_env!("CONTRACT_PATH") {
+        Some(path) => path,
+        None => panic!("the build script compiled no wasm for this build of the token"),
+    }
+}
+
+/// Emitted as a log on every balance change. Mints have no sender and burns no
+/// recipient.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[borsh(crate = "wasmlanche::borsh")]
+pub struct TransferLog {
+    pub from: Option<Address>,
+    pub to: Option<Address>,
+    pub amount: Units,
+}
+
 state_schema! {
     /// The total supply of the token. Key prefix 0x0.
     TotalSupply => Units,
@@ -18,11 +45,16 @@ state_schema! {
     Allowance(Address, Address) => Units,
     // Original owner of the token
     Owner => Address,
+    /// The number of decimals of the token's base unit. Key prefix 0x6.
+    Decimals => u8,
+    /// The owner proposed by the current owner, until they accept. Key prefix 0x7.
+    PendingOwner => Address,
 }
 
-/// Initializes the contract with a name, symbol, and total supply.
+/// Initializes the contract with a name, symbol, and the number of decimals
+/// amounts are expressed in.
 #[public]
-pub fn init(context: &mut Context, name: String, symbol: String) {
+pub fn init(context: &mut Context, name: String, symbol: String, decimals: u8) {
     let actor = context.actor();
 
     context
@@ -30,7 +62,7 @@ pub fn init(context: &mut Context, name: String, symbol: String) {
         .expect("failed to store owner");
 
     context
-        .store(((Name, name), (Symbol, symbol)))
+        .store(((Name, name), (Symbol, symbol), (Decimals, decimals)))
         .expect("failed to store owner");
 }
 
@@ -59,6 +91,8 @@ pub fn mint(context: &mut Context, recipient: Address, amount: Units) {
             (TotalSupply, (total_supply + amount)),
         ))
         .expect("failed to store balance");
+
+    internal::emit_transfer(context, None, Some(recipient), amount);
 }
 
 /// Burn the token from the recipient.
@@ -68,21 +102,26 @@ pub fn burn(context: &mut Context, recipient: Address, value: Units) -> Units {
 
     internal::check_owner(context, actor);
 
-    let total = balance_of(context, recipient);
+    internal::burn(context, recipient, value)
+}
+
+/// Burns tokens of the caller, returning the remaining balance.
+#[public]
+pub fn burn_own(context: &mut Context, amount: Units) -> Units {
+    let actor = context.actor();
 
-    assert!(value <= total, "address doesn't have enough tokens to burn");
+    internal::burn(context, actor, amount)
+}
 
-    let new_amount = total - value;
-    let new_total_supply = total_supply(context) - value;
+/// Burns tokens of the owner, returning the owner's remaining balance.
+/// The caller must have an allowance to spend the owner's tokens.
+#[public]
+pub fn burn_from(context: &mut Context, owner: Address, amount: Units) -> Units {
+    let actor = context.actor();
 
-    context
-        .store((
-            (Balance(recipient), new_amount),
-            (TotalSupply, new_total_supply),
-        ))
-        .expect("failed to burn recipient tokens");
+    internal::spend_allowance(context, owner, actor, amount);
 
-    new_amount
+    internal::burn(context, owner, amount)
 }
 
 /// Gets the balance of the recipient.
@@ -129,25 +168,49 @@ pub fn transfer_from(context: &mut Context, sender: Address, recipient: Address,
 
     let actor = context.actor();
 
-    let total_allowance = allowance(context, sender, actor);
-    assert!(total_allowance >= amount, "insufficient allowance");
-
-    context
-        .store_by_key(Allowance(sender, actor), total_allowance - amount)
-        .expect("failed to store allowance");
+    internal::spend_allowance(context, sender, actor, amount);
 
     internal::transfer(context, sender, recipient, amount);
 }
 
+/// Proposes a new owner of the token. Ownership only changes once they accept.
 #[public]
 pub fn transfer_ownership(context: &mut Context, new_owner: Address) {
     internal::check_owner(context, context.actor());
 
     context
-        .store_by_key(Owner, new_owner)
+        .store_by_key(PendingOwner, new_owner)
+        .expect("failed to store pending owner");
+}
+
+/// Accepts the ownership proposed to the caller.
+#[public]
+pub fn accept_ownership(context: &mut Context) {
+    let actor = context.actor();
+
+    let pending_owner = context
+        .get(PendingOwner)
+        .expect("failed to get pending owner");
+    assert_eq!(
+        pending_owner,
+        Some(actor),
+        "caller is not the pending owner"
+    );
+
+    context
+        .delete(PendingOwner)
+        .expect("failed to delete pending owner");
+    context
+        .store_by_key(Owner, actor)
         .expect("failed to store owner");
 }
 
+/// Returns the owner of the token.
+#[public]
+pub fn owner(context: &mut Context) -> Address {
+    internal::get_owner(context)
+}
+
 #[public]
 // grab the symbol of the token
 pub fn symbol(context: &mut Context) -> String {
@@ -166,6 +229,15 @@ pub fn name(context: &mut Context) -> String {
         .expect("name not initialized")
 }
 
+#[public]
+// grab the number of decimals of the token
+pub fn decimals(context: &mut Context) -> u8 {
+    context
+        .get(Decimals)
+        .expect("failed to get decimals")
+        .expect("decimals not initialized")
+}
+
 #[cfg(not(feature = "bindings"))]
 mod internal {
     use super::*;
@@ -184,6 +256,38 @@ mod internal {
         assert_eq!(get_owner(context), actor, "caller is required to be owner")
     }
 
+    // Decrements the allowance of the spender over the owner's tokens
+    // If the allowance is insufficient, the contract will panic
+    pub fn spend_allowance(context: &mut Context, owner: Address, spender: Address, amount: Units) {
+        let total_allowance = allowance(context, owner, spender);
+        assert!(total_allowance >= amount, "insufficient allowance");
+
+        context
+            .store_by_key(Allowance(owner, spender), total_allowance - amount)
+            .expect("failed to store allowance");
+    }
+
+    // Burns tokens of the account, returning its remaining balance
+    pub fn burn(context: &mut Context, account: Address, value: Units) -> Units {
+        let total = balance_of(context, account);
+
+        assert!(value <= total, "address doesn't have enough tokens to burn");
+
+        let new_amount = total - value;
+        let new_total_supply = total_supply(context) - value;
+
+        context
+            .store((
+                (Balance(account), new_amount),
+                (TotalSupply, new_total_supply),
+            ))
+            .expect("failed to burn tokens");
+
+        emit_transfer(context, Some(account), None, value);
+
+        new_amount
+    }
+
     pub fn transfer(context: &mut Context, sender: Address, recipient: Address, amount: Units) {
         // ensure the sender has adequate balance
         let sender_balance = balance_of(context, sender);
@@ -198,6 +302,18 @@ mod internal {
                 (Balance(recipient), (recipient_balance + amount)),
             ))
             .expect("failed to update balances");
+
+        emit_transfer(context, Some(sender), Some(recipient), amount);
+    }
+
+    pub fn emit_transfer(
+        context: &mut Context,
+        from: Option<Address>,
+        to: Option<Address>,
+        amount: Units,
+    ) {
+        let log = TransferLog { from, to, amount };
+        context.emit_log(&wasmlanche::borsh::to_vec(&log).expect("failed to serialize log"));
     }
 }
 
@@ -213,7 +329,7 @@ mod tests {
         let token_name = "Test Token".to_string();
         let token_symbol = "TST".to_string();
 
-        init(&mut context, token_name.clone(), token_symbol.clone());
+        init(&mut context, token_name.clone(), token_symbol.clone(), 6);
 
         let init_name = name(&mut context);
         assert_eq!(init_name, token_name);
@@ -221,6 +337,8 @@ mod tests {
         let init_symbol = symbol(&mut context);
         assert_eq!(init_symbol, token_symbol);
 
+        assert_eq!(decimals(&mut context), 6);
+
         let total_supply = total_supply(&mut context);
         assert_eq!(total_supply, 0);
     }
@@ -253,18 +371,68 @@ mod tests {
     #[test]
     fn transfer_ownership_of_token() {
         let mut context = init_test_token();
+        let old_owner = context.actor();
 
         let new_owner = Address::new([2; 33]);
         transfer_ownership(&mut context, new_owner);
+        assert_eq!(owner(&mut context), old_owner);
 
-        let mint_amount = 100;
         context.set_actor(new_owner);
+        accept_ownership(&mut context);
+        assert_eq!(owner(&mut context), new_owner);
+
+        let mint_amount = 100;
         mint(&mut context, new_owner, mint_amount);
 
         let total_supply = total_supply(&mut context);
         assert_eq!(total_supply, mint_amount);
     }
 
+    #[test]
+    #[should_panic = "caller is required to be owner"]
+    fn proposed_owner_cannot_mint_before_accepting() {
+        let mut context = init_test_token();
+
+        let new_owner = Address::new([2; 33]);
+        transfer_ownership(&mut context, new_owner);
+
+        context.set_actor(new_owner);
+        mint(&mut context, new_owner, 100);
+    }
+
+    #[test]
+    #[should_panic = "caller is not the pending owner"]
+    fn only_pending_owner_can_accept() {
+        let mut context = init_test_token();
+
+        transfer_ownership(&mut context, Address::new([2; 33]));
+
+        context.set_actor(Address::new([3; 33]));
+        accept_ownership(&mut context);
+    }
+
+    #[test]
+    #[should_panic = "caller is required to be owner"]
+    fn transfer_ownership_not_owner() {
+        let mut context = init_test_token();
+        let actor = Address::new([2; 33]);
+
+        context.set_actor(actor);
+        transfer_ownership(&mut context, actor);
+    }
+
+    #[test]
+    #[should_panic = "caller is not the pending owner"]
+    fn ownership_is_accepted_once() {
+        let mut context = init_test_token();
+        let new_owner = Address::new([2; 33]);
+        transfer_ownership(&mut context, new_owner);
+
+        context.set_actor(new_owner);
+        accept_ownership(&mut context);
+        accept_ownership(&mut context);
+    }
+
     #[test]
     fn burn_tokens() {
         let mut context = init_test_token();
@@ -280,9 +448,87 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
+    #[should_panic = "caller is required to be owner"]
+    fn burn_not_owner() {
+        let mut context = init_test_token();
+        let holder = Address::new([2; 33]);
+        mint(&mut context, holder, 100);
+
+        context.set_actor(Address::new([3; 33]));
+        burn(&mut context, holder, 10);
+    }
+
+    #[test]
+    fn burn_own_tokens() {
+        let mut context = init_test_token();
+        let holder = Address::new([2; 33]);
+        mint(&mut context, holder, 100);
+
+        context.set_actor(holder);
+        assert_eq!(burn_own(&mut context, 30), 70);
+
+        assert_eq!(balance_of(&mut context, holder), 70);
+        assert_eq!(total_supply(&mut context), 70);
+    }
+
+    #[test]
+    #[should_panic = "address doesn't have enough tokens to burn"]
+    fn burn_insufficient_balance() {
+        let mut context = init_test_token();
+        let holder = Address::new([2; 33]);
+        mint(&mut context, holder, 100);
+
+        context.set_actor(holder);
+        burn_own(&mut context, 101);
+    }
+
+    #[test]
+    fn burn_from_spends_allowance() {
+        let mut context = init_test_token();
+        let holder = Address::new([2; 33]);
+        let spender = Address::new([3; 33]);
+        mint(&mut context, holder, 100);
+
+        context.set_actor(holder);
+        super::approve(&mut context, spender, 50);
+
+        context.set_actor(spender);
+        assert_eq!(burn_from(&mut context, holder, 20), 80);
+
+        assert_eq!(allowance(&mut context, holder, spender), 30);
+        assert_eq!(total_supply(&mut context), 80);
+    }
+
+    #[test]
+    #[should_panic = "insufficient allowance"]
+    fn burn_from_insufficient_allowance() {
+        let mut context = init_test_token();
+        let holder = Address::new([2; 33]);
+        let spender = Address::new([3; 33]);
+        mint(&mut context, holder, 100);
+
+        context.set_actor(holder);
+        super::approve(&mut context, spender, 50);
+
+        context.set_actor(spender);
+        burn_from(&mut context, holder, 60);
+    }
+
+    #[test]
     fn approve() {
-        // TODO
+        let mut context = init_test_token();
+        let owner = context.actor();
+        let spender = Address::new([2; 33]);
+
+        assert_eq!(allowance(&mut context, owner, spender), 0);
+
+        super::approve(&mut context, spender, 100);
+        assert_eq!(allowance(&mut context, owner, spender), 100);
+
+        // a new approval replaces the previous one
+        super::approve(&mut context, spender, 40);
+        assert_eq!(allowance(&mut context, owner, spender), 40);
+        assert_eq!(allowance(&mut context, spender, owner), 0);
     }
 
     #[test]
@@ -306,27 +552,68 @@ mod tests {
     }
 
     #[test]
-    #[ignore]
+    #[should_panic = "sender has insufficient balance"]
     fn transfer_insufficient_balance() {
-        // TODO
+        let mut context = init_test_token();
+
+        let sender = context.actor();
+        mint(&mut context, sender, 100);
+
+        transfer(&mut context, Address::new([2; 33]), 101);
     }
 
     #[test]
-    #[ignore]
     fn transfer_from() {
-        // TODO
+        let mut context = init_test_token();
+
+        let owner = context.actor();
+        let spender = Address::new([2; 33]);
+        let recipient = Address::new([3; 33]);
+        mint(&mut context, owner, 100);
+        super::approve(&mut context, spender, 50);
+
+        context.set_actor(spender);
+        super::transfer_from(&mut context, owner, recipient, 30);
+
+        assert_eq!(balance_of(&mut context, owner), 70);
+        assert_eq!(balance_of(&mut context, recipient), 30);
+        assert_eq!(balance_of(&mut context, spender), 0);
+        assert_eq!(allowance(&mut context, owner, spender), 20);
     }
 
     #[test]
-    #[ignore]
+    #[should_panic = "insufficient allowance"]
     fn transfer_from_insufficient_allowance() {
-        // TODO
+        let mut context = init_test_token();
+
+        let owner = context.actor();
+        let spender = Address::new([2; 33]);
+        mint(&mut context, owner, 100);
+        super::approve(&mut context, spender, 50);
+
+        context.set_actor(spender);
+        super::transfer_from(&mut context, owner, Address::new([3; 33]), 30);
+        super::transfer_from(&mut context, owner, Address::new([3; 33]), 30);
+    }
+
+    #[test]
+    #[should_panic = "sender has insufficient balance"]
+    fn transfer_from_insufficient_balance() {
+        let mut context = init_test_token();
+
+        let owner = context.actor();
+        let spender = Address::new([2; 33]);
+        mint(&mut context, owner, 10);
+        super::approve(&mut context, spender, 50);
+
+        context.set_actor(spender);
+        super::transfer_from(&mut context, owner, Address::new([3; 33]), 30);
     }
 
     fn init_test_token() -> Context {
         let address = Address::default();
-        let mut context = Context::new(address);
-        init(&mut context, "TEST".to_string(), "TST".to_string());
+        let mut context = Context::with_actor(address);
+        init(&mut context, "TEST".to_string(), "TST".to_string(), 9);
         context
     }
 }
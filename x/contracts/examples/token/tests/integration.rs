@@ -1,7 +1,9 @@
 // Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
 // See the file LICENSE for licensing terms.
 
+use token::TransferLog;
 use wasmlanche::{
+    borsh,
     simulator::{Error, SimpleState, Simulator},
     Address,
 };
@@ -30,7 +32,7 @@ fn init_token() {
     let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
 
     simulator
-        .call_contract::<(), _>(contract_address, "init", ("Test", "TST"), MAX_UNITS)
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
         .unwrap();
 
     let supply = simulator
@@ -47,6 +49,11 @@ fn init_token() {
         .call_contract::<String, _>(contract_address, "name", (), MAX_UNITS)
         .unwrap();
     assert_eq!(name, "Test");
+
+    let decimals = simulator
+        .call_contract::<u8, _>(contract_address, "decimals", (), MAX_UNITS)
+        .unwrap();
+    assert_eq!(decimals, 6);
 }
 
 #[test]
@@ -60,7 +67,7 @@ fn mint() {
     let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
 
     simulator
-        .call_contract::<(), _>(contract_address, "init", ("Test", "TST"), MAX_UNITS)
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
         .unwrap();
 
     simulator
@@ -95,7 +102,7 @@ fn burn() {
     let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
 
     simulator
-        .call_contract::<(), _>(contract_address, "init", ("Test", "TST"), MAX_UNITS)
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
         .unwrap();
 
     simulator
@@ -121,3 +128,203 @@ fn burn() {
         .unwrap();
     assert_eq!(balance, alice_initial_balance - alice_burn_amount);
 }
+
+#[test]
+// the sequence the ACTUS contract uses to escrow the notional at IED: the creditor
+// approves the contract, which then moves the funds to the debtor on its own account
+fn transfer_from_spends_allowance() {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let owner = Address::new([1; 33]);
+    let creditor = Address::new([2; 33]);
+    let debtor = Address::new([3; 33]);
+    let escrow = Address::new([4; 33]);
+    let notional = 500;
+
+    simulator.set_actor(owner);
+    let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
+
+    simulator
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
+        .unwrap();
+    simulator
+        .call_contract::<(), _>(contract_address, "mint", (creditor, 1000u64), MAX_UNITS)
+        .unwrap();
+
+    simulator.set_actor(creditor);
+    simulator
+        .call_contract::<(), _>(contract_address, "approve", (escrow, 800u64), MAX_UNITS)
+        .unwrap();
+
+    let allowance = |simulator: &Simulator| {
+        simulator
+            .call_contract::<Units, _>(contract_address, "allowance", (creditor, escrow), MAX_UNITS)
+            .unwrap()
+    };
+    let balance = |simulator: &Simulator, account: Address| {
+        simulator
+            .call_contract::<Units, _>(contract_address, "balance_of", (account,), MAX_UNITS)
+            .unwrap()
+    };
+    assert_eq!(allowance(&simulator), 800);
+
+    simulator.set_actor(escrow);
+    simulator
+        .call_contract::<(), _>(
+            contract_address,
+            "transfer_from",
+            (creditor, debtor, notional),
+            MAX_UNITS,
+        )
+        .unwrap();
+
+    assert_eq!(balance(&simulator, creditor), 1000 - notional);
+    assert_eq!(balance(&simulator, debtor), notional);
+    assert_eq!(allowance(&simulator), 800 - notional);
+
+    // the remaining allowance does not cover a second escrow
+    let result = simulator.call_contract::<(), _>(
+        contract_address,
+        "transfer_from",
+        (creditor, debtor, notional),
+        MAX_UNITS,
+    );
+    assert!(result.is_err());
+    assert_eq!(balance(&simulator, creditor), 1000 - notional);
+    assert_eq!(allowance(&simulator), 800 - notional);
+}
+
+#[test]
+fn mint_burn_round_trip_restores_supply() {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let owner = Address::new([1; 33]);
+    let alice = Address::new([2; 33]);
+    let bob = Address::new([3; 33]);
+    let spender = Address::new([4; 33]);
+
+    simulator.set_actor(owner);
+    let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
+
+    simulator
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
+        .unwrap();
+
+    for account in [alice, bob] {
+        simulator
+            .call_contract::<(), _>(contract_address, "mint", (account, 1000u64), MAX_UNITS)
+            .unwrap();
+    }
+
+    let total_supply = |simulator: &Simulator| {
+        simulator
+            .call_contract::<Units, _>(contract_address, "total_supply", (), MAX_UNITS)
+            .unwrap()
+    };
+    assert_eq!(total_supply(&simulator), 2000);
+
+    // alice burns her own tokens
+    simulator.set_actor(alice);
+    let remaining = simulator
+        .call_contract::<Units, _>(contract_address, "burn_own", (1000u64,), MAX_UNITS)
+        .unwrap();
+    assert_eq!(remaining, 0);
+    assert_eq!(total_supply(&simulator), 1000);
+
+    // bob's are burned by a spender he approved
+    simulator.set_actor(bob);
+    simulator
+        .call_contract::<(), _>(contract_address, "approve", (spender, 1000u64), MAX_UNITS)
+        .unwrap();
+
+    simulator.set_actor(spender);
+    let result = simulator.call_contract::<Units, _>(
+        contract_address,
+        "burn_from",
+        (bob, 1001u64),
+        MAX_UNITS,
+    );
+    assert!(result.is_err());
+    assert_eq!(total_supply(&simulator), 1000);
+
+    let remaining = simulator
+        .call_contract::<Units, _>(contract_address, "burn_from", (bob, 1000u64), MAX_UNITS)
+        .unwrap();
+    assert_eq!(remaining, 0);
+    assert_eq!(total_supply(&simulator), 0);
+}
+
+#[test]
+fn balance_changes_are_logged() {
+    let mut state = SimpleState::new();
+    let mut simulator = Simulator::new(&mut state);
+
+    let owner = Address::new([1; 33]);
+    let alice = Address::new([2; 33]);
+    let bob = Address::new([3; 33]);
+
+    simulator.set_actor(owner);
+    let contract_address = simulator.create_contract(CONTRACT_PATH).unwrap().address;
+
+    // the logs of the last call only
+    let transfer_logs = |simulator: &Simulator| -> Vec<TransferLog> {
+        simulator
+            .take_logs()
+            .into_iter()
+            .map(|log| {
+                assert_eq!(log.contract, contract_address);
+                borsh::from_slice(&log.data).expect("failed to decode log")
+            })
+            .collect()
+    };
+
+    simulator
+        .call_contract::<(), _>(contract_address, "init", ("Test", "TST", 6u8), MAX_UNITS)
+        .unwrap();
+    assert_eq!(transfer_logs(&simulator), vec![]);
+
+    simulator
+        .call_contract::<(), _>(contract_address, "mint", (alice, 1000u64), MAX_UNITS)
+        .unwrap();
+    assert_eq!(
+        transfer_logs(&simulator),
+        vec![TransferLog {
+            from: None,
+            to: Some(alice),
+            amount: 1000,
+        }]
+    );
+
+    simulator.set_actor(alice);
+    simulator
+        .call_contract::<(), _>(contract_address, "transfer", (bob, 300u64), MAX_UNITS)
+        .unwrap();
+    assert_eq!(
+        transfer_logs(&simulator),
+        vec![TransferLog {
+            from: Some(alice),
+            to: Some(bob),
+            amount: 300,
+        }]
+    );
+
+    simulator
+        .call_contract::<Units, _>(contract_address, "burn_own", (200u64,), MAX_UNITS)
+        .unwrap();
+    assert_eq!(
+        transfer_logs(&simulator),
+        vec![TransferLog {
+            from: Some(alice),
+            to: None,
+            amount: 200,
+        }]
+    );
+
+    // a failed transfer logs nothing
+    let result =
+        simulator.call_contract::<(), _>(contract_address, "transfer", (bob, 1000u64), MAX_UNITS);
+    assert!(result.is_err());
+    assert_eq!(transfer_logs(&simulator), vec![]);
+}
@@ -42,6 +42,13 @@ type CallInfo struct {
     Timestamp uint64
     ActionID ids.ID
     Value uint64
+    // Logs emitted by this call and its successful nested calls
+    Logs []Log
+    // logBytes counts the data logged so far, including by the calling contracts
+    logBytes int
+    // Trace records the calls made by this call, and by those in turn, in Calls
+    Trace bool
+    Calls []CallTrace
     inst *ContractInstance
 }
 
@@ -37,6 +37,20 @@ type callContractInput struct {
 	Value        uint64
 }
 
+// CallTrace records a contract-to-contract call and the calls it made in turn
+type CallTrace struct {
+	Caller       codec.Address
+	Callee       codec.Address
+	FunctionName string
+	ParamsLen    uint32
+	// nil when the call succeeded
+	Error     *ContractCallErrorCode
+	ResultLen uint32
+	// a call that fails once started consumes its whole fuel limit
+	FuelUsed uint64
+	Calls    []CallTrace
+}
+
 type deployContractInput struct {
 	ContractID          ContractID
 	AccountCreationData []byte
@@ -57,6 +71,23 @@ func ExtractContractCallErrorCode(err error) (ContractCallErrorCode, bool) {
 	return 0, false
 }
 
+// traceCall records a nested call made by [c] when tracing is enabled
+func (c *CallInfo) traceCall(input callContractInput, calls []CallTrace, result []byte, fuelUsed uint64, code *ContractCallErrorCode) {
+	if !c.Trace {
+		return
+	}
+	c.Calls = append(c.Calls, CallTrace{
+		Caller:       c.Contract,
+		Callee:       input.Contract,
+		FunctionName: input.FunctionName,
+		ParamsLen:    uint32(len(input.Params)),
+		Error:        code,
+		ResultLen:    uint32(len(result)),
+		FuelUsed:     fuelUsed,
+		Calls:        calls,
+	})
+}
+
 func NewContractModule(r *WasmRuntime) *ImportModule {
 	return &ImportModule{
 		Name: "contract",
@@ -65,6 +96,8 @@ func NewContractModule(r *WasmRuntime) *ImportModule {
 				newInfo := *callInfo
 
 				if err := callInfo.ConsumeFuel(input.Fuel); err != nil {
+					code := OutOfFuel
+					callInfo.traceCall(input, nil, nil, 0, &code)
 					return Err[RawBytes, ContractCallErrorCode](OutOfFuel), nil //nolint:nilerr
 				}
 
@@ -74,19 +107,30 @@ func NewContractModule(r *WasmRuntime) *ImportModule {
 				newInfo.Params = input.Params
 				newInfo.Fuel = input.Fuel
 				newInfo.Value = input.Value
+				newInfo.Logs = nil
+				newInfo.Calls = nil
 
 				result, err := r.CallContract(
 					context.Background(),
 					&newInfo)
 				if err != nil {
-					if code, ok := ExtractContractCallErrorCode(err); ok {
+					code, ok := ExtractContractCallErrorCode(err)
+					if !ok {
+						code = ExecutionFailure
+					}
+					callInfo.traceCall(input, newInfo.Calls, nil, input.Fuel, &code)
+					if ok {
 						return Err[RawBytes, ContractCallErrorCode](code), nil
 					}
 					return Err[RawBytes, ContractCallErrorCode](ExecutionFailure), err
 				}
 
 				// return any remaining fuel to the calling contract
-				callInfo.AddFuel(newInfo.RemainingFuel())
+				remaining := newInfo.RemainingFuel()
+				callInfo.AddFuel(remaining)
+				callInfo.Logs = append(callInfo.Logs, newInfo.Logs...)
+				callInfo.logBytes = newInfo.logBytes
+				callInfo.traceCall(input, newInfo.Calls, result, input.Fuel-remaining, nil)
 
 				return Ok[RawBytes, ContractCallErrorCode](result), nil
 			})},
@@ -0,0 +1,40 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+package runtime
+
+import (
+	"errors"
+	"slices"
+
+	"github.com/ava-labs/hypersdk/codec"
+)
+
+const (
+	emitCost     = 1000
+	emitByteCost = 10
+
+	// maxLogBytes bounds the data logged by a call and its nested calls
+	maxLogBytes = 64 * 1024
+)
+
+var errLogLimitExceeded = errors.New("log limit exceeded")
+
+// Log is a payload emitted by a contract during a call
+type Log struct {
+	Contract codec.Address
+	Data     []byte
+}
+
+func emitLog(callInfo *CallInfo, input RawBytes) error {
+	if callInfo.logBytes+len(input) > maxLogBytes {
+		return errLogLimitExceeded
+	}
+	if err := callInfo.ConsumeFuel(uint64(len(input)) * emitByteCost); err != nil {
+		return err
+	}
+	callInfo.logBytes += len(input)
+	// needs to clone because this points into the current store's linear memory
+	callInfo.Logs = append(callInfo.Logs, Log{Contract: callInfo.Contract, Data: slices.Clone(input)})
+	return nil
+}
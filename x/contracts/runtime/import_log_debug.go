@@ -16,6 +16,7 @@ func NewLogModule() *ImportModule {
 	return &ImportModule{
 		Name: "log",
 		HostFunctions: map[string]HostFunction{
+			"emit": {FuelCost: emitCost, Function: FunctionNoOutput[RawBytes](emitLog)},
 			"write": {FuelCost: logCost, Function: FunctionNoOutput[RawBytes](func(_ *CallInfo, input RawBytes) error {
 				_, err := fmt.Fprintf(os.Stderr, "%s\n", input)
 				return err
@@ -11,6 +11,7 @@ func NewLogModule() *ImportModule {
 	return &ImportModule{
 		Name: "log",
 		HostFunctions: map[string]HostFunction{
+			"emit":  {FuelCost: emitCost, Function: FunctionNoOutput[RawBytes](emitLog)},
 			"write": {FuelCost: logCost, Function: FunctionNoOutput[RawBytes](func(*CallInfo, RawBytes) error { return nil })},
 		},
 	}
@@ -329,9 +329,9 @@ func BenchmarkAmmSwaps(b *testing.B) {
 	amountMint := uint64((1 << 28))
 
 	// initialize the tokens
-	_, err = tokenX.Call("init", "tokenX", "TKX")
+	_, err = tokenX.Call("init", "tokenX", "TKX", uint8(9))
 	require.NoError(err)
-	_, err = tokenY.Call("init", "tokenY", "TKY")
+	_, err = tokenY.Call("init", "tokenY", "TKY", uint8(9))
 	require.NoError(err)
 
 	swaper := codec.CreateAddress(0, ids.GenerateTestID())
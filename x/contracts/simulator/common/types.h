@@ -6,6 +6,7 @@
  *
  */
 
+#include <stdbool.h>
 #include <stdint.h>
 #include <stddef.h>
 
@@ -42,6 +43,8 @@ typedef struct {
     Bytes params;
     // max allowed gas during execution
     uint64_t max_gas;
+    // record the tree of contract-to-contract calls
+    bool trace;
 } SimulatorCallContext;
 
 // Response from calling a contract
@@ -49,6 +52,10 @@ typedef struct {
     char* error;
     Bytes result;
     uint64_t fuel;
+    // borsh serialized logs emitted during the call
+    Bytes logs;
+    // borsh serialized call tree, empty unless tracing was requested
+    Bytes trace;
 } CallContractResponse;
 
 // Response from creating a contract
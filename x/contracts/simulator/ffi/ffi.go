@@ -19,6 +19,7 @@ import (
 
 	"github.com/ava-labs/avalanchego/ids"
 	"github.com/ava-labs/avalanchego/utils/logging"
+	"github.com/bytecodealliance/wasmtime-go/v25"
 
 	"github.com/ava-labs/hypersdk/codec"
 	"github.com/ava-labs/hypersdk/state"
@@ -29,6 +30,8 @@ import (
 
 var (
 	ErrInvalidParam = errors.New("invalid parameter")
+	// must match ERR_OUT_OF_FUEL on the rust side
+	ErrOutOfFuel    = errors.New("out of fuel")
 	SimLogger       = logging.NewLogger("Simulator")
 	SimContext      = context.TODO()
 )
@@ -36,7 +39,7 @@ var (
 //export CallContract
 func CallContract(db *C.Mutable, ctx *C.SimulatorCallContext) C.CallContractResponse {
 	if db == nil || ctx == nil {
-		return newCallContractResponse(nil, 0, ErrInvalidParam)
+		return newCallContractResponse(nil, 0, nil, nil, ErrInvalidParam)
 	}
 
 	// build the db
@@ -48,12 +51,66 @@ func CallContract(db *C.Mutable, ctx *C.SimulatorCallContext) C.CallContractResp
 
 	rt := runtime.NewRuntime(config, SimLogger)
 	result, err := rt.CallContract(SimContext, callInfo)
+
+	// the trace is returned even when the call fails
+	trace, traceErr := serializeTrace(callInfo, result, err)
+	if traceErr != nil {
+		return newCallContractResponse(nil, 0, nil, nil, fmt.Errorf("error serializing trace: %w", traceErr))
+	}
+
+	if isOutOfFuel(err) {
+		return newCallContractResponse(nil, 0, nil, trace, ErrOutOfFuel)
+	}
+	if err != nil {
+		return newCallContractResponse(nil, 0, nil, trace, fmt.Errorf("error during runtime execution: %w", err))
+	}
+
+	logs, err := runtime.Serialize(callInfo.Logs)
 	if err != nil {
-		return newCallContractResponse(nil, 0, fmt.Errorf("error during runtime execution: %w", err))
+		return newCallContractResponse(nil, 0, nil, nil, fmt.Errorf("error serializing logs: %w", err))
 	}
 
 	fuel := callInfo.RemainingFuel()
-	return newCallContractResponse(result, fuel, nil)
+	return newCallContractResponse(result, fuel, logs, trace, nil)
+}
+
+// serializeTrace returns the top-level call as the root of the recorded call tree,
+// or nil when tracing was not requested
+func serializeTrace(callInfo *runtime.CallInfo, result []byte, err error) ([]byte, error) {
+	if !callInfo.Trace {
+		return nil, nil
+	}
+
+	root := runtime.CallTrace{
+		Caller:       callInfo.Actor,
+		Callee:       callInfo.Contract,
+		FunctionName: callInfo.FunctionName,
+		ParamsLen:    uint32(len(callInfo.Params)),
+		ResultLen:    uint32(len(result)),
+		FuelUsed:     callInfo.Fuel,
+		Calls:        callInfo.Calls,
+	}
+
+	if err != nil {
+		code, ok := runtime.ExtractContractCallErrorCode(err)
+		if !ok {
+			code = runtime.ExecutionFailure
+		}
+		root.Error = &code
+	} else {
+		root.FuelUsed -= callInfo.RemainingFuel()
+	}
+
+	return runtime.Serialize(root)
+}
+
+func isOutOfFuel(err error) bool {
+	var trap *wasmtime.Trap
+	if !errors.As(err, &trap) {
+		return false
+	}
+	code := trap.Code()
+	return code != nil && *code == wasmtime.OutOfFuel
 }
 
 func createRuntimeCallInfo(db state.Mutable, ctx *C.SimulatorCallContext) *runtime.CallInfo {
@@ -71,6 +128,7 @@ func createRuntimeCallInfo(db state.Mutable, ctx *C.SimulatorCallContext) *runti
 		Fuel:         uint64(ctx.max_gas),
 		Height:       uint64(ctx.height),
 		Timestamp:    uint64(ctx.timestamp),
+		Trace:        bool(ctx.trace),
 	}
 }
 
@@ -122,6 +180,45 @@ func CreateContract(db *C.Mutable, path *C.char) C.CreateContractResponse {
 	}
 }
 
+// DeployContract creates an account running [contractID], whose bytes
+// CreateContract already stored, the way a contract deploys another.
+// Accounts created this way share the contract bytes and are the same for the
+// same [accountCreationData].
+//
+//export DeployContract
+func DeployContract(db *C.Mutable, contractID C.ContractId, accountCreationData C.Bytes) C.CreateContractResponse {
+	state := simState.NewSimulatorState(unsafe.Pointer(db))
+	contractManager := simState.NewContractStateManager(state)
+
+	id := runtime.ContractID(C.GoBytes(unsafe.Pointer(contractID.data), C.int(contractID.length))) //nolint:all
+	data := C.GoBytes(unsafe.Pointer(accountCreationData.data), C.int(accountCreationData.length)) //nolint:all
+
+	if _, err := contractManager.GetContractBytes(context.TODO(), id); err != nil {
+		errmsg := "unknown contract: " + err.Error()
+		return C.CreateContractResponse{
+			error: C.CString(errmsg),
+		}
+	}
+
+	account, err := contractManager.NewAccountWithContract(context.TODO(), id, data)
+	if err != nil {
+		errmsg := "contract deployment failed: " + err.Error()
+		return C.CreateContractResponse{
+			error: C.CString(errmsg),
+		}
+	}
+	return C.CreateContractResponse{
+		error: nil,
+		contract_id: C.ContractId{
+			data:   (*C.uint8_t)(C.CBytes(id)), //nolint:all
+			length: (C.size_t)(len(id)),
+		},
+		contract_address: C.Address{
+			*(*[33]C.uchar)(C.CBytes(account[:])), //nolint:all
+		},
+	}
+}
+
 // generateRandomID creates a unique ID.
 // Note: ids.GenerateID() is not used because the IDs are not unique and will
 // collide.
@@ -139,7 +236,7 @@ func generateRandomID() (ids.ID, error) {
 	return id, nil
 }
 
-func newCallContractResponse(result []byte, fuel uint64, err error) C.CallContractResponse {
+func newCallContractResponse(result []byte, fuel uint64, logs []byte, trace []byte, err error) C.CallContractResponse {
 	var errPtr *C.char
 	if err == nil {
 		errPtr = nil
@@ -154,6 +251,14 @@ func newCallContractResponse(result []byte, fuel uint64, err error) C.CallContra
 			length: C.size_t(len(result)),
 		},
 		fuel: C.uint64_t(fuel),
+		logs: C.Bytes{
+			data:   (*C.uint8_t)(C.CBytes(logs)),
+			length: C.size_t(len(logs)),
+		},
+		trace: C.Bytes{
+			data:   (*C.uint8_t)(C.CBytes(trace)),
+			length: C.size_t(len(trace)),
+		},
 	}
 }
 
@@ -6,9 +6,13 @@ use std::ffi::CString;
 pub mod bindings;
 pub mod state;
 
+/// Error returned when a call exhausts its gas limit
+pub const ERR_OUT_OF_FUEL: &str = "out of fuel";
+
 mod ffi {
     use super::bindings::{
-        Address, CallContractResponse, CreateContractResponse, SimulatorCallContext,
+        Address, Bytes, CallContractResponse, ContractId, CreateContractResponse,
+        SimulatorCallContext,
     };
     use libc::c_char;
 
@@ -17,6 +21,13 @@ mod ffi {
         #[link_name = "CreateContract"]
         pub fn create_contract(db: usize, path: *const c_char) -> CreateContractResponse;
 
+        #[link_name = "DeployContract"]
+        pub fn deploy_contract(
+            db: usize,
+            contract_id: ContractId,
+            account_creation_data: Bytes,
+        ) -> CreateContractResponse;
+
         #[link_name = "CallContract"]
         pub fn call_contract(db: usize, ctx: *const SimulatorCallContext) -> CallContractResponse;
 
@@ -38,6 +49,17 @@ pub fn create_contract(
     unsafe { ffi::create_contract(state_addr, contract_path.as_ptr()) }
 }
 
+/// Creates an account running the contract `contract_id` identifies, as a contract
+/// deploying it would
+pub fn deploy_contract(
+    state: &state::Mutable<'_>,
+    contract_id: &[u8],
+    account_creation_data: &[u8],
+) -> bindings::CreateContractResponse {
+    let state_addr = state as *const _ as usize;
+    unsafe { ffi::deploy_contract(state_addr, contract_id.into(), account_creation_data.into()) }
+}
+
 pub fn call_contract(
     state: &state::Mutable<'_>,
     context: &bindings::SimulatorCallContext,
@@ -1,7 +1,7 @@
 // Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
 // See the file LICENSE for licensing terms.
 
-use crate::bindings::{Bytes, BytesWithError};
+use crate::bindings::{Address, Bytes, BytesWithError};
 use std::{collections::HashMap, ffi::CString, ops::Deref};
 
 // define constant error messages
@@ -10,17 +10,26 @@ pub const ERR_NOT_FOUND: &str = "not found";
 
 type BoxedSlice = Box<[u8]>;
 
+// contract state spaces are laid out as in the Go ContractStateManager:
+// "contract" + [account prefix] + [address] + [account state prefix] + [key]
+const CONTRACT_MANAGER_PREFIX: &[u8] = b"contract";
+const ACCOUNT_PREFIX: u8 = 0x1;
+const ACCOUNT_STATE_PREFIX: u8 = 0x1;
+
 /// A simple key-value store representing the state of the simulated VM.
-#[repr(transparent)]
-#[derive(Debug)]
+/// A clone shares nothing with the original, checkpoints included.
+#[derive(Debug, Clone)]
 pub struct SimpleState {
     state: HashMap<BoxedSlice, BoxedSlice>,
+    /// Previous values of modified keys, oldest first, kept once a checkpoint is taken
+    journal: Option<Vec<(BoxedSlice, Option<BoxedSlice>)>>,
 }
 
 impl SimpleState {
     pub fn new() -> SimpleState {
         SimpleState {
             state: HashMap::new(),
+            journal: None,
         }
     }
     pub fn get_value(&self, key: &[u8]) -> Option<&[u8]> {
@@ -28,13 +37,68 @@ impl SimpleState {
     }
 
     pub fn insert(&mut self, key: BoxedSlice, value: BoxedSlice) {
-        self.state.insert(key, value);
+        let previous = self.state.insert(key.clone(), value);
+        self.record(key, previous);
     }
 
     pub fn remove(&mut self, key: &[u8]) {
-        self.state.remove(key);
+        if let Some((key, previous)) = self.state.remove_entry(key) {
+            self.record(key, Some(previous));
+        }
+    }
+
+    /// Starts recording changes and returns a checkpoint that [`SimpleState::revert`]
+    /// can restore. Taking a checkpoint does not copy the state.
+    pub fn checkpoint(&mut self) -> usize {
+        self.journal.get_or_insert_with(Vec::new).len()
+    }
+
+    /// Undoes every change made since `checkpoint` was taken.
+    /// Checkpoints taken after `checkpoint` become invalid.
+    pub fn revert(&mut self, checkpoint: usize) {
+        let Some(journal) = self.journal.as_mut() else {
+            return;
+        };
+
+        for (key, previous) in journal.drain(checkpoint.min(journal.len())..).rev() {
+            match previous {
+                Some(value) => self.state.insert(key, value),
+                None => self.state.remove(&key),
+            };
+        }
+    }
+
+    /// Returns the bytes `contract` stored under `key`, exactly as the contract wrote them.
+    pub fn get_raw(&self, contract: impl Into<Address>, key: &[u8]) -> Option<Vec<u8>> {
+        let key = [account_state_prefix(contract.into()), key.to_vec()].concat();
+        self.get_value(&key).map(<[u8]>::to_vec)
+    }
+
+    /// Iterates over the keys `contract` has stored, in no particular order.
+    pub fn keys(&self, contract: impl Into<Address>) -> impl Iterator<Item = &[u8]> {
+        let prefix = account_state_prefix(contract.into());
+        self.state
+            .keys()
+            .filter_map(move |key| key.strip_prefix(prefix.as_slice()))
     }
+
+    fn record(&mut self, key: BoxedSlice, previous: Option<BoxedSlice>) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.push((key, previous));
+        }
+    }
+}
+
+fn account_state_prefix(contract: Address) -> Vec<u8> {
+    [
+        CONTRACT_MANAGER_PREFIX,
+        &[ACCOUNT_PREFIX],
+        &contract.address,
+        &[ACCOUNT_STATE_PREFIX],
+    ]
+    .concat()
 }
+
 impl Default for SimpleState {
     fn default() -> Self {
         Self::new()
@@ -89,3 +153,65 @@ pub type GetStateCallback =
     extern "C" fn(simObjectPtr: &mut SimpleState, key: Bytes) -> BytesWithError;
 pub type InsertStateCallback = extern "C" fn(objectPtr: &mut SimpleState, key: Bytes, value: Bytes);
 pub type RemoveStateCallback = extern "C" fn(objectPtr: &mut SimpleState, key: Bytes);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(bytes: &[u8]) -> BoxedSlice {
+        bytes.into()
+    }
+
+    #[test]
+    fn revert_undoes_changes_since_checkpoint() {
+        let mut state = SimpleState::new();
+        state.insert(boxed(b"kept"), boxed(b"1"));
+        state.insert(boxed(b"changed"), boxed(b"1"));
+        state.insert(boxed(b"removed"), boxed(b"1"));
+
+        let checkpoint = state.checkpoint();
+        state.insert(boxed(b"changed"), boxed(b"2"));
+        state.insert(boxed(b"changed"), boxed(b"3"));
+        state.remove(b"removed");
+        state.insert(boxed(b"added"), boxed(b"1"));
+
+        state.revert(checkpoint);
+
+        assert_eq!(state.get_value(b"kept"), Some(&b"1"[..]));
+        assert_eq!(state.get_value(b"changed"), Some(&b"1"[..]));
+        assert_eq!(state.get_value(b"removed"), Some(&b"1"[..]));
+        assert_eq!(state.get_value(b"added"), None);
+    }
+
+    #[test]
+    fn raw_reads_are_scoped_to_the_contract() {
+        let alice = Address { address: [1; 33] };
+        let bob = Address { address: [2; 33] };
+        let stored =
+            |contract, key: &[u8]| boxed(&[account_state_prefix(contract), key.into()].concat());
+
+        let mut state = SimpleState::new();
+        state.insert(stored(alice, b"key"), boxed(b"1"));
+        state.insert(stored(bob, b"other"), boxed(b"2"));
+
+        assert_eq!(state.get_raw(alice, b"key"), Some(b"1".to_vec()));
+        assert_eq!(state.get_raw(bob, b"key"), None);
+        assert_eq!(state.keys(alice).collect::<Vec<_>>(), [&b"key"[..]]);
+    }
+
+    #[test]
+    fn nested_checkpoints_revert_independently() {
+        let mut state = SimpleState::new();
+
+        let outer = state.checkpoint();
+        state.insert(boxed(b"key"), boxed(b"1"));
+        let inner = state.checkpoint();
+        state.insert(boxed(b"key"), boxed(b"2"));
+
+        state.revert(inner);
+        assert_eq!(state.get_value(b"key"), Some(&b"1"[..]));
+
+        state.revert(outer);
+        assert_eq!(state.get_value(b"key"), None);
+    }
+}
@@ -1,7 +1,10 @@
 // Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
 // See the file LICENSE for licensing terms.
 
-use std::{path::Path, process::Command};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 pub const BUILD_DIR_NAME: &str = "build";
 const WASM_TARGET: &str = "wasm32-unknown-unknown";
@@ -15,7 +18,10 @@ pub fn build_wasm() {
     let target = std::env::var("TARGET").unwrap();
     let profile = std::env::var("PROFILE").unwrap();
 
-    if target != WASM_TARGET {
+    // fuzz builds pass sanitizer flags that cannot target wasm, and link the crate natively
+    let fuzzing = std::env::var_os("CARGO_CFG_FUZZING").is_some();
+
+    if target != WASM_TARGET && !fuzzing {
         let package_name = std::env::var("CARGO_PKG_NAME").unwrap();
         let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
 
@@ -25,21 +31,18 @@ pub fn build_wasm() {
             "test"
         };
 
-        let features = std::env::vars()
-            .filter_map(|(key, value)| {
-                if key.starts_with("CARGO_FEATURE_") && value == "1" {
-                    let feature = key.trim_start_matches("CARGO_FEATURE_").to_lowercase();
-
-                    match feature.as_str() {
-                        "bindings" | "test" => None,
-                        _ => Some(feature),
-                    }
-                } else {
-                    None
-                }
-            })
+        // `CARGO_CFG_FEATURE` keeps the hyphens that `CARGO_FEATURE_*` names lose
+        let features = std::env::var("CARGO_CFG_FEATURE")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|feature| !matches!(*feature, "" | "bindings" | "test"))
+            .map(String::from)
             .collect::<Vec<_>>();
 
+        ensure_wasm_target();
+
+        // the nested build keeps its own target directory, so it only recompiles what
+        // changed since the last run of this script
         let target_dir = format!("{manifest_dir}/{BUILD_DIR_NAME}");
         let mut command = Command::new("cargo");
         command
@@ -115,3 +118,28 @@ pub fn build_wasm() {
         );
     }
 }
+
+/// Panics with the command installing the wasm target if the toolchain building this
+/// crate lacks it, instead of the `can't find crate for core` the nested build fails with
+fn ensure_wasm_target() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let sysroot = Command::new(&rustc)
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+
+    // without a sysroot, the nested build reports what is wrong with the toolchain
+    let Some(sysroot) = sysroot else {
+        return;
+    };
+
+    assert!(
+        sysroot.join("lib/rustlib").join(WASM_TARGET).is_dir(),
+        "the `{WASM_TARGET}` target is not installed for {rustc} ({}); contracts are \
+         compiled to it before the tests deploy them. Install it with \
+         `rustup target add {WASM_TARGET}`, in CI as a step before the tests.",
+        sysroot.display()
+    );
+}
@@ -230,6 +230,15 @@ impl Context {
         borsh::from_slice(&bytes).expect("failed to deserialize the result")
     }
 
+    /// Emits `data` as a log of the current call.
+    /// The host discards the logs of calls that fail.
+    /// Logging is charged per byte, and traps once a call and its nested calls have
+    /// logged more than 64 KiB.
+    #[inline]
+    pub fn emit_log(&self, data: &[u8]) {
+        self.host_accessor.emit_log(data);
+    }
+
     /// Attempts to call a function `name` with `args` on the given contract. This method
     /// is used to call functions on external contracts.
     /// # Errors
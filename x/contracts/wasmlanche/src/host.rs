@@ -189,6 +189,11 @@ mod test_wrappers {
 
             host_ptr
         }
+
+        #[allow(clippy::unused_self)]
+        pub fn emit_log(&self, _data: &[u8]) {
+            // logs are only collected by the host
+        }
     }
 
     impl Default for MockState {
@@ -350,5 +355,16 @@ mod external_wrappers {
 
             unsafe { send_value(args.as_ptr(), args.len()) }
         }
+
+        #[inline]
+        pub fn emit_log(&self, data: &[u8]) {
+            #[link(wasm_import_module = "log")]
+            extern "C" {
+                #[link_name = "emit"]
+                fn emit_log(ptr: *const u8, len: usize);
+            }
+
+            unsafe { emit_log(data.as_ptr(), data.len()) }
+        }
     }
 }
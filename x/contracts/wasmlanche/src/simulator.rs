@@ -1,11 +1,15 @@
 // Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
 // See the file LICENSE for licensing terms.
 
-use crate::{borsh, Address};
-use core::{marker::PhantomData, ops::Deref};
+use crate::{
+    borsh::{self, BorshDeserialize},
+    Address,
+};
+use core::{cell::Cell, marker::PhantomData, ops::Deref};
 use simulator::{
-    bindings::{Address as BindingAddress, Bytes, SimulatorCallContext},
+    bindings::{Address as BindingAddress, Bytes, CreateContractResponse, SimulatorCallContext},
     state::{self, Mutable},
+    ERR_OUT_OF_FUEL,
 };
 use std::{
     error::Error as StdError,
@@ -16,6 +20,9 @@ use std::{
 
 pub use state::SimpleState;
 
+/// Gas for the read-only calls the simulator makes on a test's behalf
+const QUERY_GAS: u64 = 100_000_000;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Error across the FFI boundary: {0}")]
@@ -28,6 +35,10 @@ pub enum Error {
     CreateContract(String),
     #[error("Error during contract execution")]
     CallContract(String),
+    #[error("Contract execution ran out of fuel")]
+    OutOfFuel,
+    #[error("Contract returned error {code}: {message}")]
+    Contract { code: u16, message: String },
 }
 
 pub struct ExternalCallError(crate::ExternalCallError);
@@ -105,6 +116,7 @@ impl<'a> From<&'a CallContext<'_, '_>> for BorrowedCallContext<'a> {
                     length: ctx.params.len(),
                 },
                 max_gas: ctx.gas,
+                trace: ctx.simulator.tracing,
             },
             PhantomData,
         )
@@ -119,11 +131,102 @@ impl Deref for BorrowedCallContext<'_> {
     }
 }
 
+/// A payload emitted by a contract during a call
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[derive(Clone, PartialEq, Eq, borsh::BorshDeserialize)]
+#[borsh(crate = "crate::borsh")]
+pub struct Log {
+    pub contract: Address,
+    pub data: Vec<u8>,
+}
+
+/// A contract call and the calls it made in turn, recorded while the [`Simulator`] is tracing
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[derive(PartialEq, Eq, borsh::BorshDeserialize)]
+#[borsh(crate = "crate::borsh")]
+pub struct CallTrace {
+    /// The actor of a top-level call, otherwise the calling contract
+    pub caller: Address,
+    pub callee: Address,
+    pub function: String,
+    /// Length of the serialized arguments
+    pub params_len: u32,
+    /// `None` when the call succeeded
+    pub error: Option<crate::ExternalCallError>,
+    /// Length of the serialized result
+    pub result_len: u32,
+    /// Includes the fuel of the nested calls. A failed call reports its whole gas limit.
+    pub fuel_used: u64,
+    pub calls: Vec<CallTrace>,
+}
+
+impl CallTrace {
+    fn fmt_depth(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}0x", "", indent = 2 * depth)?;
+        // the first byte is the same for every contract
+        for byte in &self.callee.as_ref()[1..5] {
+            write!(f, "{byte:02x}")?;
+        }
+        write!(f, ".{}({} bytes) ", self.function, self.params_len)?;
+
+        match &self.error {
+            None => write!(f, "-> {} bytes", self.result_len)?,
+            Some(error) => write!(f, "-> {error}")?,
+        }
+        writeln!(f, ", {} fuel", self.fuel_used)?;
+
+        self.calls
+            .iter()
+            .try_for_each(|call| call.fmt_depth(f, depth + 1))
+    }
+}
+
+/// One line per call, indented by its depth in the tree
+impl Display for CallTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_depth(f, 0)
+    }
+}
+
+/// Identifies a point the [`Simulator`] can be reverted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SnapshotId(usize);
+
+#[derive(Clone)]
+struct Snapshot {
+    checkpoint: usize,
+    actor: Address,
+    height: u64,
+    timestamp: u64,
+}
+
+/// A state allocated by the [`Simulator`] holding it, freed once the simulator drops
+struct OwnedState(*mut SimpleState);
+
+// Safety: the pointer is the only handle to a heap-allocated `SimpleState`, which is `Send`
+unsafe impl Send for OwnedState {}
+
+impl Drop for OwnedState {
+    fn drop(&mut self) {
+        // Safety: allocated by `Simulator::with_state`, and the `Mutable` borrowing it is
+        // dropped before this is
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
+
+/// Each simulator calls contracts on its own state, so simulators of different states
+/// can run on different threads at once, each created on its thread or moved to it.
 pub struct Simulator<'a> {
     state: Mutable<'a>,
+    owned: Option<OwnedState>,
     actor: Address,
     height: u64,
     timestamp: u64,
+    snapshots: Vec<Snapshot>,
+    last_call_units: Cell<u64>,
+    logs: Cell<Vec<Log>>,
+    tracing: bool,
+    trace: Cell<Option<CallTrace>>,
 }
 
 impl<'a> Simulator<'a> {
@@ -131,18 +234,69 @@ impl<'a> Simulator<'a> {
     pub fn new(state: &'a mut SimpleState) -> Self {
         Simulator {
             state: Mutable::new(state),
+            owned: None,
             actor: Address::default(),
             height: 0,
             timestamp: 0,
+            snapshots: Vec::new(),
+            last_call_units: Cell::new(0),
+            logs: Cell::default(),
+            tracing: false,
+            trace: Cell::default(),
         }
     }
 
+    /// Returns a new Simulator instance owning `state`, which is dropped along with it.
+    /// Unlike [`Simulator::new`], it is not tied to a borrow, so it can be returned or
+    /// moved to another thread.
+    #[must_use]
+    pub fn with_state(state: SimpleState) -> Simulator<'static> {
+        let owned = Box::into_raw(Box::new(state));
+        // Safety: the allocation lives until `owned` drops, after the simulator's last use
+        let mut simulator = Simulator::new(unsafe { &mut *owned });
+        simulator.owned = Some(OwnedState(owned));
+        simulator
+    }
+
+    /// Returns a simulator of a copy of the state, with the same actor, height, timestamp,
+    /// tracing and snapshots, that calls contracts without affecting this one. It starts
+    /// without the fuel, logs or trace of a last call.
+    #[must_use]
+    pub fn fork(&self) -> Simulator<'static> {
+        let mut fork = Simulator::with_state(self.state().clone());
+        fork.actor = self.actor;
+        fork.height = self.height;
+        fork.timestamp = self.timestamp;
+        fork.snapshots.clone_from(&self.snapshots);
+        fork.tracing = self.tracing;
+        fork
+    }
+
     /// Creates a new contract from the given WASM binary path.
     /// # Errors
     /// Returns an error if the contract creation fails.
     pub fn create_contract(&self, contract_path: &str) -> Result<CreateContractResult, Error> {
-        let result = simulator::create_contract(&self.state, contract_path);
+        Self::created(simulator::create_contract(&self.state, contract_path))
+    }
+
+    /// Creates another instance of a contract created before, the way a contract deploys
+    /// one with [`Context::deploy`](crate::Context::deploy). Instances share the bytes of
+    /// the contract, and the same `account_creation_data` gives the same address.
+    /// # Errors
+    /// Returns an error if no contract has the given id.
+    pub fn deploy_contract(
+        &self,
+        contract_id: &[u8],
+        account_creation_data: &[u8],
+    ) -> Result<CreateContractResult, Error> {
+        Self::created(simulator::deploy_contract(
+            &self.state,
+            contract_id,
+            account_creation_data,
+        ))
+    }
 
+    fn created(result: CreateContractResponse) -> Result<CreateContractResult, Error> {
         if !result.error.is_null() {
             let error = {
                 let c_str = unsafe { CStr::from_ptr(result.error) };
@@ -164,8 +318,13 @@ impl<'a> Simulator<'a> {
     ///   For single values, use `(param,)`. Specify types if not explicit.
     ///   Example: `(param1 as u64, param2 as u64)`
     ///
+    /// The fuel consumed is available from [`Simulator::last_call_units`] afterwards,
+    /// the logs emitted from [`Simulator::take_logs`] and, when tracing, the call tree
+    /// from [`Simulator::take_trace`].
+    ///
     /// # Errors
     /// returns an error if the either the call or deserialization fails.
+    /// Exhausting `gas` returns [`Error::OutOfFuel`].
     ///
     /// # Panics
     /// Panics if the params fail to serialize.
@@ -179,6 +338,45 @@ impl<'a> Simulator<'a> {
     where
         T: borsh::BorshDeserialize,
         U: borsh::BorshSerialize,
+    {
+        let result = self.call_bytes(contract, method, params, gas)?;
+        Ok(borsh::from_slice(&result)?)
+    }
+
+    /// Calls a function returning `Result<T, E>` like [`Simulator::call_contract`], where
+    /// `E` is encoded as an error envelope: a borsh `(u16, String)` of a stable code and a
+    /// message, possibly followed by fields of its own.
+    ///
+    /// # Errors
+    /// Returns [`Error::Contract`] when the function returns an error envelope, and
+    /// [`Error::CallContract`] for any other error payload, besides the errors of
+    /// [`Simulator::call_contract`].
+    ///
+    /// # Panics
+    /// Panics if the params fail to serialize.
+    pub fn try_call_contract<T, U>(
+        &self,
+        contract: Address,
+        method: &str,
+        params: U,
+        gas: u64,
+    ) -> Result<T, Error>
+    where
+        T: borsh::BorshDeserialize,
+        U: borsh::BorshSerialize,
+    {
+        decode_fallible(&self.call_bytes(contract, method, params, gas)?)
+    }
+
+    fn call_bytes<U>(
+        &self,
+        contract: Address,
+        method: &str,
+        params: U,
+        gas: u64,
+    ) -> Result<Box<[u8]>, Error>
+    where
+        U: borsh::BorshSerialize,
     {
         let method = CString::new(method).expect("error converting method to CString");
         let params = borsh::to_vec(&params).expect("error serializing result");
@@ -187,6 +385,10 @@ impl<'a> Simulator<'a> {
         let context = BorrowedCallContext::from(&context);
 
         let result = simulator::call_contract(&self.state, &context);
+        self.last_call_units.set(gas.saturating_sub(result.fuel));
+        self.logs.take();
+        let trace = (!result.trace.is_empty()).then(|| borsh::from_slice(&result.trace));
+        self.trace.set(trace.transpose()?);
 
         if !result.error.is_null() {
             let error = {
@@ -194,10 +396,42 @@ impl<'a> Simulator<'a> {
                 c_str.to_str().map_err(Error::Ffi)?
             };
 
+            if error == ERR_OUT_OF_FUEL {
+                return Err(Error::OutOfFuel);
+            }
+
             return Err(Error::CallContract(error.into()));
         };
 
-        Ok(borsh::from_slice(&result.result)?)
+        if !result.logs.is_empty() {
+            self.logs.set(borsh::from_slice(&result.logs)?);
+        }
+
+        Ok(result.result.into())
+    }
+
+    /// Returns the logs emitted during the last call, in order, including those of nested calls.
+    /// A failed call emits no logs.
+    pub fn take_logs(&self) -> Vec<Log> {
+        self.logs.take()
+    }
+
+    /// Records the calls every following call makes to other contracts, until disabled.
+    pub fn set_tracing(&mut self, tracing: bool) {
+        self.tracing = tracing;
+    }
+
+    /// Returns the tree of calls made during the last call while tracing, rooted at that
+    /// call itself. A failed call keeps its trace up to the failure.
+    pub fn take_trace(&self) -> Option<CallTrace> {
+        self.trace.take()
+    }
+
+    /// Returns the fuel consumed by the last call, including any nested calls.
+    /// A failed call reports its whole gas limit.
+    #[must_use]
+    pub fn last_call_units(&self) -> u64 {
+        self.last_call_units.get()
     }
 
     /// Returns the actor address for the simulator.
@@ -233,6 +467,51 @@ impl<'a> Simulator<'a> {
         self.timestamp = timestamp;
     }
 
+    /// Advances the timestamp of the blockchain by `delta` seconds.
+    pub fn advance_time(&mut self, delta: u64) {
+        self.timestamp = self.timestamp.saturating_add(delta);
+    }
+
+    /// Captures the state along with the actor, height and timestamp.
+    /// Only changes made afterwards are recorded, so snapshots are cheap to take.
+    pub fn snapshot(&mut self) -> SnapshotId {
+        let id = SnapshotId(self.snapshots.len());
+
+        self.snapshots.push(Snapshot {
+            checkpoint: self.state.state.checkpoint(),
+            actor: self.actor,
+            height: self.height,
+            timestamp: self.timestamp,
+        });
+
+        id
+    }
+
+    /// Restores everything captured by `snapshot`. The snapshot can be reverted to again,
+    /// while snapshots taken after it are discarded.
+    ///
+    /// # Panics
+    /// Panics if `snapshot` was discarded by reverting to an earlier one.
+    pub fn revert_to(&mut self, snapshot: SnapshotId) {
+        self.snapshots.truncate(snapshot.0 + 1);
+        let snapshot = self
+            .snapshots
+            .get(snapshot.0)
+            .expect("snapshot was discarded by an earlier revert");
+
+        self.state.state.revert(snapshot.checkpoint);
+        self.actor = snapshot.actor;
+        self.height = snapshot.height;
+        self.timestamp = snapshot.timestamp;
+    }
+
+    /// Returns the state, so tests can read what contracts actually stored with
+    /// [`SimpleState::get_raw`] and [`SimpleState::keys`].
+    #[must_use]
+    pub fn state(&self) -> &SimpleState {
+        self.state.state
+    }
+
     /// Returns the balance of the given account.
     #[must_use]
     pub fn get_balance(&self, account: Address) -> u64 {
@@ -243,6 +522,30 @@ impl<'a> Simulator<'a> {
     pub fn set_balance(&mut self, account: Address, balance: u64) {
         simulator::set_balance(&self.state, account.into(), balance);
     }
+
+    /// Returns the balance `owner` holds of the token contract at `token`, as its
+    /// `balance_of` function reports it. Like any call, it replaces the fuel and logs
+    /// of the last one.
+    /// # Errors
+    /// Returns an error if the token has no such function or the call fails.
+    pub fn balance_of(&self, token: Address, owner: Address) -> Result<u64, Error> {
+        self.call_contract(token, "balance_of", owner, QUERY_GAS)
+    }
+}
+
+/// Decodes a borsh `Result<T, E>` whose `E` is an error envelope
+fn decode_fallible<T: BorshDeserialize>(bytes: &[u8]) -> Result<T, Error> {
+    let unrecognized = || Error::CallContract(format!("unrecognized result {bytes:02x?}"));
+
+    match bytes.split_first() {
+        Some((1, value)) => Ok(borsh::from_slice(value)?),
+        // the fields of structured errors may follow the envelope
+        Some((0, mut envelope)) => match <(u16, String)>::deserialize(&mut envelope) {
+            Ok((code, message)) => Err(Error::Contract { code, message }),
+            Err(_) => Err(unrecognized()),
+        },
+        _ => Err(unrecognized()),
+    }
 }
 
 pub struct CreateContractResult {
@@ -296,4 +599,118 @@ mod tests {
         let bal = simulator.get_balance(alice);
         assert_eq!(bal, expected_balance);
     }
+
+    #[test]
+    fn error_envelopes_decode_to_their_code() {
+        let ok: Result<u64, (u16, String)> = Ok(7);
+        let value: u64 = decode_fallible(&borsh::to_vec(&ok).unwrap()).unwrap();
+        assert_eq!(value, 7);
+
+        // a structured error carries fields after the envelope
+        let mut bytes = borsh::to_vec(&Err::<u64, _>((9u16, "not authorized"))).unwrap();
+        bytes.extend(42u64.to_le_bytes());
+
+        match decode_fallible::<u64>(&bytes) {
+            Err(Error::Contract { code, message }) => {
+                assert_eq!(code, 9);
+                assert_eq!(message, "not authorized");
+            }
+            other => panic!("expected a contract error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_error_payloads_fall_back_to_the_generic_error() {
+        // an `Err(bool)`, a message past the end, no result and an invalid discriminant
+        for payload in [vec![0, 1], vec![0, 9, 0, 0xff, 0xff], vec![], vec![2, 0]] {
+            let result = decode_fallible::<u64>(&payload);
+            assert!(matches!(result, Err(Error::CallContract(_))), "{payload:?}");
+        }
+    }
+
+    #[test]
+    fn advance_time() {
+        let mut state = SimpleState::new();
+        let mut simulator = Simulator::new(&mut state);
+
+        simulator.set_timestamp(1_000);
+        simulator.advance_time(500);
+        assert_eq!(simulator.get_timestamp(), 1_500);
+
+        simulator.advance_time(u64::MAX);
+        assert_eq!(simulator.get_timestamp(), u64::MAX);
+    }
+
+    #[test]
+    fn revert_to_snapshot() {
+        let mut state = SimpleState::new();
+        let mut simulator = Simulator::new(&mut state);
+        let alice = Address::new([1; 33]);
+
+        simulator.set_balance(alice, 100);
+        simulator.set_timestamp(1_000);
+        let snapshot = simulator.snapshot();
+
+        simulator.set_balance(alice, 50);
+        simulator.set_actor(alice);
+        simulator.advance_time(500);
+        let discarded = simulator.snapshot();
+        simulator.set_balance(alice, 0);
+
+        simulator.revert_to(snapshot);
+        assert_eq!(simulator.get_balance(alice), 100);
+        assert!(simulator.get_actor() == Address::default());
+        assert_eq!(simulator.get_timestamp(), 1_000);
+
+        // a snapshot can be reverted to repeatedly
+        simulator.set_balance(alice, 10);
+        simulator.revert_to(snapshot);
+        assert_eq!(simulator.get_balance(alice), 100);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            simulator.revert_to(discarded)
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn forks_do_not_share_state() {
+        let mut state = SimpleState::new();
+        let mut simulator = Simulator::new(&mut state);
+        let alice = Address::new([1; 33]);
+
+        simulator.set_balance(alice, 100);
+        simulator.set_timestamp(1_000);
+        let snapshot = simulator.snapshot();
+        simulator.set_balance(alice, 50);
+
+        let mut fork = simulator.fork();
+        assert_eq!(fork.get_balance(alice), 50);
+        assert_eq!(fork.get_timestamp(), 1_000);
+
+        fork.set_balance(alice, 0);
+        assert_eq!(simulator.get_balance(alice), 50);
+
+        // the snapshots taken before forking revert each copy on its own
+        fork.revert_to(snapshot);
+        assert_eq!(fork.get_balance(alice), 100);
+        assert_eq!(simulator.get_balance(alice), 50);
+    }
+
+    #[test]
+    fn owned_simulators_run_on_their_own_threads() {
+        let alice = Address::new([1; 33]);
+        let threads: Vec<_> = (0..4)
+            .map(|balance| {
+                let mut simulator = Simulator::with_state(SimpleState::new());
+                std::thread::spawn(move || {
+                    simulator.set_balance(alice, balance);
+                    simulator.get_balance(alice)
+                })
+            })
+            .collect();
+
+        let balances: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(balances, [0, 1, 2, 3]);
+    }
 }
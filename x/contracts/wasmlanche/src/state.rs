@@ -34,6 +34,8 @@ pub enum Error {
 enum Query<V> {
     Found(V),
     Changed(V),
+    /// Removed from storage at the flush
+    Deleted,
     NotFound,
 }
 
@@ -50,14 +52,7 @@ impl<V> Query<V> {
     fn to_option(&self) -> Option<&V> {
         match self {
             Query::Found(value) | Query::Changed(value) => Some(value),
-            Query::NotFound => None,
-        }
-    }
-
-    fn to_option_mut(&mut self) -> Option<&mut V> {
-        match self {
-            Query::Found(value) | Query::Changed(value) => Some(value),
-            Query::NotFound => None,
+            Query::Deleted | Query::NotFound => None,
         }
     }
 }
@@ -219,19 +214,14 @@ impl Cache {
 
         let cache_entry = if let Some(value) = cache.get_mut(key) {
             match value {
-                Query::Found(v) => {
+                Query::Found(_) => {
                     self.change_count += 1;
-                    self.byte_count -= size_of::<u32>() + key.len() + size_of::<u32>();
-                    *value = Query::Changed(mem::take(v));
-                    value
-                }
-                Query::Changed(v) if v.is_empty() => value,
-                Query::Changed(v) => {
-                    self.byte_count -= v.len();
-                    value
+                    self.byte_count += size_of::<u32>() + key.len() + size_of::<u32>();
                 }
-                Query::NotFound => value,
+                Query::Changed(v) => self.byte_count -= v.len(),
+                Query::Deleted | Query::NotFound => {}
             }
+            value
         } else {
             let key = CacheKey::from(key);
 
@@ -239,7 +229,7 @@ impl Cache {
                 self.change_count += 1;
                 self.byte_count += size_of::<u32>() + key.len() + size_of::<u32>();
 
-                Query::Changed(value_bytes)
+                Query::Found(value_bytes)
             } else {
                 Query::NotFound
             };
@@ -247,12 +237,15 @@ impl Cache {
             cache.entry(key).or_insert(value_bytes)
         };
 
-        match cache_entry.to_option_mut() {
-            None => Ok(None),
-            Some(val) if val.is_empty() => Ok(None),
-            Some(val) => from_slice(&mem::take(val))
-                .map_err(|_| Error::Deserialization)
-                .map(Some),
+        match cache_entry {
+            Query::Found(val) | Query::Changed(val) => {
+                let bytes = mem::take(val);
+                *cache_entry = Query::Deleted;
+                from_slice(&bytes)
+                    .map_err(|_| Error::Deserialization)
+                    .map(Some)
+            }
+            Query::Deleted | Query::NotFound => Ok(None),
         }
     }
 
@@ -276,6 +269,8 @@ impl Cache {
             .filter_map(|(key, value)| match value {
                 Query::Found(_) | Query::NotFound => None,
                 Query::Changed(value) => Some((key, value)),
+                // the host removes keys written with an empty value
+                Query::Deleted => Some((key, Vec::new())),
             })
             .for_each(|(key, value)| {
                 to_delete.extend(&key.len().to_le_bytes());
@@ -326,3 +321,66 @@ impl_to_pairs!(1, Schema);
 pub mod macro_types {
     pub use super::{MaxChunksType, PrefixType, PrefixedKey, Schema};
 }
+
+// the cache only flushes to the mocked host
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, NoUninit)]
+    #[repr(transparent)]
+    struct Counter(u32);
+
+    unsafe impl Schema for Counter {
+        type Value = u64;
+
+        fn prefix() -> u8 {
+            0
+        }
+    }
+
+    #[derive(Clone, Copy, NoUninit)]
+    #[repr(transparent)]
+    struct Flag(u32);
+
+    unsafe impl Schema for Flag {
+        type Value = ();
+
+        fn prefix() -> u8 {
+            1
+        }
+    }
+
+    #[test]
+    fn deleted_keys_read_as_missing() {
+        let mut cache = Cache::new();
+        cache.store_by_key(Counter(0), 5).unwrap();
+
+        assert_eq!(cache.delete(Counter(0)).unwrap(), Some(5));
+        assert_eq!(Counter(0).get(&mut cache).unwrap(), None);
+        assert_eq!(cache.delete(Counter(0)).unwrap(), None);
+    }
+
+    #[test]
+    fn zero_length_values_are_found() {
+        let mut cache = Cache::new();
+        cache.store_by_key(Flag(0), ()).unwrap();
+
+        assert_eq!(Flag(0).get(&mut cache).unwrap(), Some(()));
+        assert_eq!(cache.delete(Flag(0)).unwrap(), Some(()));
+        assert_eq!(Flag(0).get(&mut cache).unwrap(), None);
+    }
+
+    #[test]
+    fn deleting_a_found_key_counts_its_removal() {
+        let mut cache = Cache::new();
+        let key = CacheKey::from(to_key(Counter(0)).as_ref());
+        let value = borsh::to_vec(&5u64).unwrap();
+        cache.cache.insert(key.clone(), Query::Found(value));
+
+        assert_eq!(cache.delete(Counter(0)).unwrap(), Some(5));
+        // the removal is flushed as the key with an empty value
+        assert_eq!(cache.byte_count, size_of::<u32>() + key.len() + size_of::<u32>());
+        assert_eq!(cache.change_count, 1);
+    }
+}